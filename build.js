@@ -1,23 +1,32 @@
 const esbuild = require("esbuild");
 
-esbuild
-  .build({
-    entryPoints: ["packages/app/src/index.tsx"],
-    bundle: true,
-    format: "iife",
-    write: true,
-    jsx: "automatic",
-    jsxImportSource: "preact",
-    loader: {
-      ".png": "dataurl",
-      ".jpg": "dataurl",
-      ".jpeg": "dataurl",
-      ".gif": "dataurl",
-      ".webp": "dataurl",
-      ".ttf": "dataurl",
-    },
-    outfile: "dist/bundle.js",
-  })
+// The sample app, and the gallery of every node type that crates/gallery snapshots
+const bundles = [
+  { entry: "packages/app/src/index.tsx", outfile: "dist/bundle.js" },
+  { entry: "packages/gallery/src/index.tsx", outfile: "dist/gallery.js" },
+];
+
+Promise.all(
+  bundles.map(({ entry, outfile }) =>
+    esbuild.build({
+      entryPoints: [entry],
+      bundle: true,
+      format: "iife",
+      write: true,
+      jsx: "automatic",
+      jsxImportSource: "preact",
+      loader: {
+        ".png": "dataurl",
+        ".jpg": "dataurl",
+        ".jpeg": "dataurl",
+        ".gif": "dataurl",
+        ".webp": "dataurl",
+        ".ttf": "dataurl",
+      },
+      outfile,
+    }),
+  ),
+)
   .then(() => {
     console.log("Build succeeded!");
   })
@@ -0,0 +1,97 @@
+use juice::calibration::ColorCalibration;
+use juice::display_filter::DisplayFilter;
+use juice::dom::DomLimits;
+use juice::launcher::LauncherConfig;
+use juice::log::LogConfig;
+use juice::memory::MemoryBudget;
+use juice::mono::{MonoConfig, RefreshConfig};
+use juice::permissions::Permissions;
+use juice::sandbox::SandboxConfig;
+use juice::splash::SplashConfig;
+use juice::theme::AutoTheme;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::epd::EpdConfig;
+use crate::surface::SurfaceSource;
+
+/// Host settings read from a JSON file at `JUICE_CONFIG`, or `juice.json` in the working
+/// directory. Every field is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostConfig {
+    /// `drm`, or `it8951` for an e-paper panel on SPI
+    pub display: Option<String>,
+    /// Wiring and waveforms for the `it8951` display
+    pub epd: Option<EpdConfig>,
+    /// Frame buffers including the canvas: 2 or 3
+    pub buffers: Option<usize>,
+    /// `block` or `drop`, see `FramePolicy`
+    pub frame_policy: Option<String>,
+    /// Gamma and white point, or measured LUTs, for the panel
+    pub color: Option<ColorCalibration>,
+    /// Grayscale or 1-bit conversion and dithering for mono panels
+    pub mono: Option<MonoConfig>,
+    /// Refresh batching and full-refresh cadence, for e-paper
+    pub refresh: Option<RefreshConfig>,
+    /// Switch between light and dark themes from the ambient light sensor
+    pub auto_theme: Option<AutoTheme>,
+    /// `invert`, `high-contrast` or `deuteranopia` to start with, until JS changes it
+    pub display_filter: Option<DisplayFilter>,
+    /// Ring buffer size and file rotation for JS console output. Logs go to
+    /// `<data dir>/logs/juice.log` unless a path is given.
+    pub log: Option<LogConfig>,
+    /// Caps on the DOM tree's size, so a runaway render can't run the device out of memory
+    pub limits: Option<DomLimits>,
+    /// Bytes the image, SVG, subtree and glyph caches can hold
+    pub memory_budget: Option<MemoryBudget>,
+    /// Trim caches and fire `MemoryPressure` when the kernel reports memory pressure.
+    /// On by default where the kernel has `/proc/pressure/memory`.
+    pub memory_pressure: Option<bool>,
+    /// Measure input-to-photon latency, logging percentiles every 10 seconds and reporting
+    /// them to JS through `renderer.latencyStats()`. Off by default.
+    pub latency: Option<bool>,
+    /// A splash screen covering the app from startup until it calls `renderer.ready()`
+    pub splash: Option<SplashConfig>,
+    /// Run the app in a child process, restarted if it crashes, runs out of memory or
+    /// hangs, while this process keeps the display and input
+    pub sandbox: Option<SandboxConfig>,
+    /// Show a home grid of the apps in a folder, starting and switching between them,
+    /// instead of running the one app
+    pub launcher: Option<LauncherConfig>,
+    /// Native modules each launcher app may use, by app id, like `["fs"]`. Apps only get
+    /// those their manifest asks for too.
+    pub permissions: Option<HashMap<String, Vec<String>>>,
+    /// Native modules the single app run without a launcher may use, like `["fs"]`.
+    /// None by default.
+    pub app_permissions: Option<Vec<String>>,
+    /// Raw frame streams shown beneath the `<surface>` nodes named after them
+    pub surfaces: Option<HashMap<String, SurfaceSource>>,
+}
+
+impl HostConfig {
+    /// Load the config file, falling back to defaults if it's missing or invalid.
+    pub fn load() -> Self {
+        let path = std::env::var("JUICE_CONFIG").unwrap_or_else(|_| "juice.json".to_string());
+
+        let Ok(json) = std::fs::read_to_string(&path) else {
+            return HostConfig::default();
+        };
+
+        match serde_json::from_str(&json) {
+            Ok(config) => {
+                println!("Loaded config from {}", path);
+                config
+            }
+            Err(e) => {
+                println!("Warning: ignoring invalid config {}: {}", path, e);
+                HostConfig::default()
+            }
+        }
+    }
+
+    /// What `appPermissions` grants the single app.
+    pub fn app_permissions(&self) -> Permissions {
+        Permissions::new(self.app_permissions.clone().unwrap_or_default())
+    }
+}
@@ -1,29 +1,40 @@
 use juice::engine::JsModule;
+use juice::log::{LogLevel, LogStore};
 use rquickjs::{Object, prelude::Func};
 
-pub struct Console {}
+/// Prints JS console output and keeps it in the log store for the on-device viewer.
+pub struct Console {
+    pub logs: LogStore,
+}
 
 impl JsModule for Console {
     fn register(&self, ctx: &rquickjs::Ctx<'_>) {
         let console = Object::new(ctx.clone()).unwrap();
 
-        console
-            .set(
-                "log",
-                Func::from(|msg: String| {
-                    println!("[JS] {}", msg);
-                }),
-            )
-            .unwrap();
+        for (name, level) in [
+            ("debug", LogLevel::Debug),
+            ("log", LogLevel::Info),
+            ("info", LogLevel::Info),
+            ("warn", LogLevel::Warn),
+            ("error", LogLevel::Error),
+        ] {
+            let logs = self.logs.clone();
+
+            console
+                .set(
+                    name,
+                    Func::from(move |msg: String| {
+                        if level >= LogLevel::Warn {
+                            eprintln!("[JS] {}", msg);
+                        } else {
+                            println!("[JS] {}", msg);
+                        }
 
-        console
-            .set(
-                "error",
-                Func::from(|msg: String| {
-                    eprintln!("[JS] {}", msg);
-                }),
-            )
-            .unwrap();
+                        logs.push(level, &msg);
+                    }),
+                )
+                .unwrap();
+        }
 
         ctx.globals().set("console", console).unwrap();
     }
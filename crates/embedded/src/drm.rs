@@ -1,20 +1,51 @@
-use drm::Device;
-use drm::buffer::Buffer;
-use drm::control::{Device as ControlDevice, Mode, connector, crtc, dumbbuffer, framebuffer};
+use drm::buffer::{Buffer, DrmFourcc};
+use drm::control::atomic::AtomicModeReq;
+use drm::control::{
+    AtomicCommitFlags, ClipRect, Device as ControlDevice, Mode, ResourceHandle, connector, crtc,
+    dumbbuffer, framebuffer, plane, property,
+};
+use drm::{ClientCapability, Device};
 use embedded_graphics::pixelcolor::Rgb888;
 use embedded_graphics::prelude::*;
 use juice::canvas::Canvas;
+use juice::damage::{DamageRect, MAX_DAMAGE_RECTS};
+use juice::dom::SurfaceRect;
+use juice::mono::RefreshMode;
+use juice::video::VideoFrame;
+use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
 use std::os::unix::io::{AsFd, BorrowedFd};
 
+use crate::present::Panel;
+
+// Values of the "type" enum property on planes
+const PLANE_TYPE_OVERLAY: u64 = 0;
+const PLANE_TYPE_PRIMARY: u64 = 1;
+
+/// `struct drm_mode_rect`, the element type of the FB_DAMAGE_CLIPS blob.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct ModeRect {
+    x1: i32,
+    y1: i32,
+    x2: i32,
+    y2: i32,
+}
+
+impl From<&DamageRect> for ModeRect {
+    fn from(rect: &DamageRect) -> Self {
+        ModeRect {
+            x1: rect.x as i32,
+            y1: rect.y as i32,
+            x2: (rect.x + rect.width) as i32,
+            y2: (rect.y + rect.height) as i32,
+        }
+    }
+}
+
 pub struct DrmDisplay {
     file: File,
-    #[allow(dead_code)]
-    connector: connector::Handle,
-    #[allow(dead_code)]
     crtc: crtc::Handle,
-    #[allow(dead_code)]
-    mode: Mode,
     fb: framebuffer::Handle,
     db: dumbbuffer::DumbBuffer,
     width: u32,
@@ -22,8 +53,146 @@ pub struct DrmDisplay {
     pitch: u32,
     buffer_ptr: *mut u8,
     buffer_size: usize,
+    /// Set when the driver supports atomic modesetting and an ARGB8888 overlay plane was
+    /// found for the UI. The primary plane beneath it then carries the underlay.
+    planes: Option<Planes>,
+    underlay: Option<DumbSurface>,
+}
+
+/// The planes used when compositing in hardware: the UI on an overlay with per-pixel alpha,
+/// and the primary plane underneath for video or a plain background.
+struct Planes {
+    ui: Plane,
+    underlay: Plane,
+}
+
+struct Plane {
+    handle: plane::Handle,
+    props: HashMap<String, property::Handle>,
+}
+
+impl Plane {
+    fn set(&self, req: &mut AtomicModeReq, name: &str, value: property::Value<'_>) {
+        if let Some(&prop) = self.props.get(name) {
+            req.add_property(self.handle, prop, value);
+        }
+    }
+
+    /// Show `fb` (sized `src_w` x `src_h`) on this plane, scaled into the `dst` rect.
+    fn attach(
+        &self,
+        req: &mut AtomicModeReq,
+        crtc: crtc::Handle,
+        fb: framebuffer::Handle,
+        (src_w, src_h): (u32, u32),
+        dst: &SurfaceRect,
+    ) {
+        // SRC_* are 16.16 fixed point
+        self.set(req, "FB_ID", property::Value::Framebuffer(Some(fb)));
+        self.set(req, "CRTC_ID", property::Value::CRTC(Some(crtc)));
+        self.set(req, "SRC_X", property::Value::UnsignedRange(0));
+        self.set(req, "SRC_Y", property::Value::UnsignedRange(0));
+        self.set(
+            req,
+            "SRC_W",
+            property::Value::UnsignedRange((src_w as u64) << 16),
+        );
+        self.set(
+            req,
+            "SRC_H",
+            property::Value::UnsignedRange((src_h as u64) << 16),
+        );
+        self.set(req, "CRTC_X", property::Value::SignedRange(dst.x as i64));
+        self.set(req, "CRTC_Y", property::Value::SignedRange(dst.y as i64));
+        self.set(
+            req,
+            "CRTC_W",
+            property::Value::UnsignedRange(dst.width as u64),
+        );
+        self.set(
+            req,
+            "CRTC_H",
+            property::Value::UnsignedRange(dst.height as u64),
+        );
+    }
+}
+
+/// A mapped XRGB8888 dumb buffer with a framebuffer attached, used for the underlay.
+struct DumbSurface {
+    fb: framebuffer::Handle,
+    db: dumbbuffer::DumbBuffer,
+    width: u32,
+    height: u32,
+    pitch: u32,
+    ptr: *mut u8,
+    size: usize,
 }
 
+impl DumbSurface {
+    fn new(drm: &impl ControlDevice, width: u32, height: u32) -> Result<Self, String> {
+        let mut db = drm
+            .create_dumb_buffer((width, height), DrmFourcc::Xrgb8888, 32)
+            .map_err(|e| format!("Failed to create dumb buffer: {}", e))?;
+
+        let fb = drm
+            .add_framebuffer(&db, 24, 32)
+            .map_err(|e| format!("Failed to add framebuffer: {}", e))?;
+
+        let pitch = db.pitch();
+
+        let mut map = drm
+            .map_dumb_buffer(&mut db)
+            .map_err(|e| format!("Failed to map dumb buffer: {}", e))?;
+
+        let ptr = map.as_mut_ptr();
+        std::mem::forget(map);
+
+        Ok(DumbSurface {
+            fb,
+            db,
+            width,
+            height,
+            pitch,
+            ptr,
+            size: (pitch * height) as usize,
+        })
+    }
+
+    /// Copy XRGB8888 pixels in, row by row to respect the buffer's pitch.
+    fn write(&mut self, pixels: &[u32]) {
+        let dst = unsafe { std::slice::from_raw_parts_mut(self.ptr, self.size) };
+        let src =
+            unsafe { std::slice::from_raw_parts(pixels.as_ptr() as *const u8, pixels.len() * 4) };
+        let row_bytes = self.width as usize * 4;
+
+        for (y, row) in src
+            .chunks_exact(row_bytes)
+            .take(self.height as usize)
+            .enumerate()
+        {
+            let start = y * self.pitch as usize;
+            dst[start..start + row_bytes].copy_from_slice(row);
+        }
+    }
+
+    fn fill(&mut self, color: u32) {
+        let dst = unsafe { std::slice::from_raw_parts_mut(self.ptr as *mut u32, self.size / 4) };
+        dst.fill(color);
+    }
+
+    fn destroy(self, drm: &impl ControlDevice) {
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.size);
+        }
+        let _ = drm.destroy_framebuffer(self.fb);
+        let _ = drm.destroy_dumb_buffer(self.db);
+    }
+}
+
+// The mapped buffers are only touched through `&mut self`, so the display can be handed
+// to a presenter thread
+unsafe impl Send for DrmDisplay {}
+
 impl AsFd for DrmDisplay {
     fn as_fd(&self) -> BorrowedFd<'_> {
         self.file.as_fd()
@@ -86,9 +255,29 @@ impl DrmDisplay {
             .crtc()
             .ok_or_else(|| "No CRTC associated with encoder".to_string())?;
 
-        // Create dumb buffer (XRGB8888 = 32 bpp)
+        // Atomic modesetting lets the UI sit on its own plane with per-pixel alpha, above a
+        // separately scaled underlay. Fall back to a single XRGB8888 buffer without it.
+        let planes = if drm
+            .set_client_capability(ClientCapability::UniversalPlanes, true)
+            .is_ok()
+            && drm
+                .set_client_capability(ClientCapability::Atomic, true)
+                .is_ok()
+        {
+            find_planes(&drm, &res, crtc)
+        } else {
+            None
+        };
+
+        let format = if planes.is_some() {
+            DrmFourcc::Argb8888
+        } else {
+            DrmFourcc::Xrgb8888
+        };
+
+        // Create dumb buffer (32 bpp)
         let mut db = drm
-            .create_dumb_buffer((width, height), drm::buffer::DrmFourcc::Xrgb8888, 32)
+            .create_dumb_buffer((width, height), format, 32)
             .map_err(|e| format!("Failed to create dumb buffer: {}", e))?;
 
         let pitch = db.pitch();
@@ -99,8 +288,11 @@ impl DrmDisplay {
             width, height, pitch, buffer_size
         );
 
+        // Depth 32 selects ARGB8888, depth 24 XRGB8888
+        let depth = if planes.is_some() { 32 } else { 24 };
+
         let fb = drm
-            .add_framebuffer(&db, 24, 32)
+            .add_framebuffer(&db, depth, 32)
             .map_err(|e| format!("Failed to add framebuffer: {}", e))?;
 
         // Map the buffer
@@ -110,21 +302,46 @@ impl DrmDisplay {
 
         let buffer_ptr = map.as_mut_ptr();
 
-        // Set CRTC
-        if let Err(e) = drm.set_crtc(crtc, Some(fb), (0, 0), &[connector_handle], Some(mode)) {
-            println!("Warning: Failed to set CRTC: {}", e);
-        } else {
-            println!("Successfully set CRTC - display active");
-        }
-
         // Forget the map so it doesn't get unmapped
         std::mem::forget(map);
 
+        let mut underlay = None;
+
+        let planes = match planes {
+            Some(planes) => {
+                // The underlay starts as a black full-screen background until the host
+                // shows something on it
+                let mut background = DumbSurface::new(&drm, width, height)?;
+                background.fill(0xFF00_0000);
+
+                match atomic_modeset(&drm, &planes, connector_handle, crtc, mode, fb, &background) {
+                    Ok(()) => {
+                        println!("Atomic modeset - UI on overlay plane with alpha");
+                        underlay = Some(background);
+                        Some(planes)
+                    }
+                    Err(e) => {
+                        println!("Warning: atomic modeset failed, falling back: {}", e);
+                        background.destroy(&drm);
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
+        // Set CRTC
+        if planes.is_none() {
+            if let Err(e) = drm.set_crtc(crtc, Some(fb), (0, 0), &[connector_handle], Some(mode)) {
+                println!("Warning: Failed to set CRTC: {}", e);
+            } else {
+                println!("Successfully set CRTC - display active");
+            }
+        }
+
         Ok(DrmDisplay {
             file: drm.file,
-            connector: connector_handle,
             crtc,
-            mode,
             fb,
             db,
             width,
@@ -132,43 +349,184 @@ impl DrmDisplay {
             pitch,
             buffer_ptr,
             buffer_size,
+            planes,
+            underlay,
         })
     }
 
-    pub fn width(&self) -> u32 {
-        self.width
+    /// Whether video can be shown on a hardware plane beneath the UI. When false, hosts
+    /// should draw frames into the UI with `Renderer::set_video_frame` instead.
+    pub fn has_underlay(&self) -> bool {
+        self.planes.is_some()
     }
 
-    pub fn height(&self) -> u32 {
-        self.height
+    /// Show a frame on the underlay plane, scaled by the display hardware into `rect` —
+    /// normally the geometry of a `<surface>` node from `Renderer::surfaces()`, whose
+    /// punched hole lets it show through the UI.
+    ///
+    /// Fails if there's no underlay plane or the driver can't place it there (some
+    /// require the primary plane to cover the whole screen).
+    pub fn set_underlay(&mut self, frame: &VideoFrame, rect: &SurfaceRect) -> Result<(), String> {
+        let Some(planes) = &self.planes else {
+            return Err("No underlay plane available".to_string());
+        };
+
+        if !frame.is_complete() {
+            return Err("Underlay frame has fewer pixels than its size".to_string());
+        }
+
+        // Reuse the current buffer when the frame size hasn't changed
+        let mut surface = match self.underlay.take() {
+            Some(surface) if surface.width == frame.width && surface.height == frame.height => {
+                surface
+            }
+            old => {
+                if let Some(old) = old {
+                    old.destroy(self);
+                }
+                DumbSurface::new(self, frame.width, frame.height)?
+            }
+        };
+
+        surface.write(&frame.pixels);
+
+        let mut req = AtomicModeReq::new();
+        planes.underlay.attach(
+            &mut req,
+            self.crtc,
+            surface.fb,
+            (frame.width, frame.height),
+            rect,
+        );
+        self.underlay = Some(surface);
+
+        self.atomic_commit(AtomicCommitFlags::empty(), req)
+            .map_err(|e| format!("Failed to place underlay: {}", e))
     }
 
     fn framebuffer_mut(&mut self) -> &mut [u8] {
         unsafe { std::slice::from_raw_parts_mut(self.buffer_ptr, self.buffer_size) }
     }
 
-    /// Blit the framebuffer into the DRM display buffer.
-    /// Both are XRGB8888, so this is a row-by-row memcpy.
-    /// If a dirty rect is provided, only those rows are copied.
-    pub fn blit_from(&mut self, canvas: &Canvas) {
+    /// Blit the damaged parts of the canvas into the DRM display buffer and tell the
+    /// kernel which regions changed.
+    /// Both are XRGB8888, so this is a row-by-row memcpy of each damage rect.
+    /// DRM has no refresh modes, so a full refresh reports the whole frame as damaged,
+    /// which e-paper drivers like repaper take as a full update.
+    pub fn blit_from(&mut self, canvas: &Canvas, damage: &[DamageRect], mode: RefreshMode) {
+        let full = [DamageRect {
+            x: 0,
+            y: 0,
+            width: canvas.width,
+            height: canvas.height,
+        }];
+        let damage = match mode {
+            RefreshMode::Full => &full[..],
+            RefreshMode::Fast | RefreshMode::Partial => damage,
+        };
+
         let src = canvas.as_xrgb_bytes();
         let pitch = self.pitch as usize;
         let row_bytes = canvas.width as usize * 4;
         let dst = self.framebuffer_mut();
 
-        if pitch == row_bytes {
-            dst[..src.len()].copy_from_slice(src);
-        } else {
-            for y in 0..canvas.height as usize {
-                let src_start = y * row_bytes;
-                let dst_start = y * pitch;
-                dst[dst_start..dst_start + row_bytes]
-                    .copy_from_slice(&src[src_start..src_start + row_bytes]);
+        for rect in damage {
+            let x_start = rect.x as usize * 4;
+            let x_end = x_start + rect.width as usize * 4;
+
+            for y in rect.y as usize..(rect.y + rect.height) as usize {
+                let src_row = y * row_bytes;
+                let dst_row = y * pitch;
+                dst[dst_row + x_start..dst_row + x_end]
+                    .copy_from_slice(&src[src_row + x_start..src_row + x_end]);
+            }
+        }
+
+        self.flush_damage(damage);
+    }
+
+    /// Report changed regions to the kernel, so DSI command-mode and SPI panels only
+    /// transfer those over the bus. Atomic drivers get FB_DAMAGE_CLIPS on the UI plane;
+    /// otherwise the legacy dirty-fb ioctl is used, which continuously scanned displays
+    /// don't need and may not implement.
+    fn flush_damage(&self, damage: &[DamageRect]) {
+        if damage.is_empty() {
+            return;
+        }
+
+        if let Some(planes) = &self.planes
+            && planes.ui.props.contains_key("FB_DAMAGE_CLIPS")
+        {
+            // The blob's length sets the rect count, so pad unused slots with a repeat
+            let mut rects = [ModeRect::from(&damage[0]); MAX_DAMAGE_RECTS];
+
+            for (slot, rect) in rects.iter_mut().zip(damage) {
+                *slot = rect.into();
+            }
+
+            let blob = match self.create_property_blob(&rects) {
+                Ok(blob) => blob,
+                Err(e) => {
+                    println!("Warning: failed to create damage blob: {}", e);
+                    return;
+                }
+            };
+
+            let mut req = AtomicModeReq::new();
+            planes.ui.set(
+                &mut req,
+                "FB_ID",
+                property::Value::Framebuffer(Some(self.fb)),
+            );
+            planes.ui.set(&mut req, "FB_DAMAGE_CLIPS", blob);
+
+            if let Err(e) = self.atomic_commit(AtomicCommitFlags::empty(), req) {
+                println!("Warning: damage commit failed: {}", e);
+            }
+
+            if let property::Value::Blob(id) = blob {
+                let _ = self.destroy_property_blob(id);
             }
+        } else {
+            let clips: Vec<ClipRect> = damage
+                .iter()
+                .map(|rect| {
+                    ClipRect::new(
+                        rect.x as u16,
+                        rect.y as u16,
+                        (rect.x + rect.width) as u16,
+                        (rect.y + rect.height) as u16,
+                    )
+                })
+                .collect();
+
+            let _ = self.dirty_framebuffer(self.fb, &clips);
         }
     }
 }
 
+impl Panel for DrmDisplay {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn present(&mut self, canvas: &Canvas, damage: &[DamageRect], mode: RefreshMode) {
+        self.blit_from(canvas, damage, mode);
+    }
+
+    fn has_underlay(&self) -> bool {
+        DrmDisplay::has_underlay(self)
+    }
+
+    fn set_underlay(&mut self, frame: &VideoFrame, rect: &SurfaceRect) -> Result<(), String> {
+        DrmDisplay::set_underlay(self, frame, rect)
+    }
+}
+
 impl DrawTarget for DrmDisplay {
     type Color = Rgb888;
     type Error = core::convert::Infallible;
@@ -212,7 +570,139 @@ impl Drop for DrmDisplay {
         }
         let _ = self.destroy_framebuffer(self.fb);
         let _ = self.destroy_dumb_buffer(self.db);
+
+        if let Some(underlay) = self.underlay.take() {
+            underlay.destroy(self);
+        }
+    }
+}
+
+/// Find the primary plane and an ARGB8888-capable overlay plane that can drive `crtc`.
+fn find_planes(
+    drm: &impl ControlDevice,
+    res: &drm::control::ResourceHandles,
+    crtc: crtc::Handle,
+) -> Option<Planes> {
+    let mut primary = None;
+    let mut overlay = None;
+
+    for handle in drm.plane_handles().ok()? {
+        let Ok(info) = drm.get_plane(handle) else {
+            continue;
+        };
+
+        if !res.filter_crtcs(info.possible_crtcs()).contains(&crtc) {
+            continue;
+        }
+
+        let Some((kind, props)) = plane_props(drm, handle) else {
+            continue;
+        };
+
+        let plane = Plane { handle, props };
+
+        if kind == PLANE_TYPE_PRIMARY && primary.is_none() {
+            primary = Some(plane);
+        } else if kind == PLANE_TYPE_OVERLAY
+            && overlay.is_none()
+            && info.formats().contains(&(DrmFourcc::Argb8888 as u32))
+        {
+            overlay = Some(plane);
+        }
     }
+
+    Some(Planes {
+        ui: overlay?,
+        underlay: primary?,
+    })
+}
+
+/// Map a plane's property names to handles, returning its "type" alongside.
+fn plane_props(
+    drm: &impl ControlDevice,
+    handle: plane::Handle,
+) -> Option<(u64, HashMap<String, property::Handle>)> {
+    let props = property_handles(drm, handle)?;
+    let values = drm.get_properties(handle).ok()?;
+
+    let kind = values
+        .iter()
+        .find(|(prop, _)| Some(*prop) == props.get("type"))
+        .map(|(_, value)| *value)?;
+
+    Some((kind, props))
+}
+
+fn property_handles(
+    drm: &impl ControlDevice,
+    handle: impl ResourceHandle,
+) -> Option<HashMap<String, property::Handle>> {
+    let values = drm.get_properties(handle).ok()?;
+
+    Some(
+        values
+            .as_hashmap(drm)
+            .ok()?
+            .into_iter()
+            .map(|(name, info)| (name, info.handle()))
+            .collect(),
+    )
+}
+
+/// Enable the CRTC with the UI on the overlay plane and `background` on the primary plane.
+fn atomic_modeset(
+    drm: &impl ControlDevice,
+    planes: &Planes,
+    connector: connector::Handle,
+    crtc: crtc::Handle,
+    mode: Mode,
+    fb: framebuffer::Handle,
+    background: &DumbSurface,
+) -> Result<(), String> {
+    let connector_props =
+        property_handles(drm, connector).ok_or("Failed to get connector properties")?;
+    let crtc_props = property_handles(drm, crtc).ok_or("Failed to get CRTC properties")?;
+
+    let mode_blob = drm
+        .create_property_blob(&mode)
+        .map_err(|e| format!("Failed to create mode blob: {}", e))?;
+
+    let mut req = AtomicModeReq::new();
+
+    if let Some(&prop) = connector_props.get("CRTC_ID") {
+        req.add_property(connector, prop, property::Value::CRTC(Some(crtc)));
+    }
+
+    if let Some(&prop) = crtc_props.get("MODE_ID") {
+        req.add_property(crtc, prop, mode_blob);
+    }
+
+    if let Some(&prop) = crtc_props.get("ACTIVE") {
+        req.add_property(crtc, prop, property::Value::Boolean(true));
+    }
+
+    let (width, height) = mode.size();
+    let screen = SurfaceRect {
+        name: String::new(),
+        x: 0,
+        y: 0,
+        width: width as u32,
+        height: height as u32,
+    };
+
+    planes.underlay.attach(
+        &mut req,
+        crtc,
+        background.fb,
+        (screen.width, screen.height),
+        &screen,
+    );
+    planes
+        .ui
+        .attach(&mut req, crtc, fb, (screen.width, screen.height), &screen);
+
+    drm.atomic_commit(AtomicCommitFlags::ALLOW_MODESET, req)
+        .map_err(|e| e.to_string())
 }
 
 struct DrmDeviceInit {
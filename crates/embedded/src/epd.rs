@@ -0,0 +1,516 @@
+use juice::canvas::Canvas;
+use juice::damage::DamageRect;
+use juice::mono::{RefreshMode, luma};
+use serde::Deserialize;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::present::Panel;
+
+// spidev ioctls, _IOW('k', nr, size)
+const SPI_IOC_WR_MODE: libc::c_ulong = 0x4001_6b01;
+const SPI_IOC_WR_MAX_SPEED_HZ: libc::c_ulong = 0x4004_6b04;
+const SPI_IOC_MESSAGE_1: libc::c_ulong = 0x4020_6b00;
+
+/// spidev's default buffer size; longer transfers are split
+const SPI_MAX_TRANSFER: usize = 4096;
+
+// Each SPI transfer to the IT8951 starts with a preamble saying what follows
+const PREAMBLE_COMMAND: u16 = 0x6000;
+const PREAMBLE_WRITE: u16 = 0x0000;
+const PREAMBLE_READ: u16 = 0x1000;
+
+const CMD_SYS_RUN: u16 = 0x0001;
+const CMD_READ_REGISTER: u16 = 0x0010;
+const CMD_WRITE_REGISTER: u16 = 0x0011;
+const CMD_LOAD_IMAGE_AREA: u16 = 0x0021;
+const CMD_LOAD_IMAGE_END: u16 = 0x0022;
+const CMD_DISPLAY_AREA: u16 = 0x0034;
+const CMD_VCOM: u16 = 0x0039;
+const CMD_GET_DEVICE_INFO: u16 = 0x0302;
+
+/// Enables packed writes of pixel data
+const REG_I80CPCR: u16 = 0x0004;
+/// Address of the image buffer that loads write into, low and high words
+const REG_LISAR: u16 = 0x0208;
+/// Nonzero while the LUT engine is driving a refresh
+const REG_LUTAFSR: u16 = 0x1224;
+
+/// Load args: little-endian words, 4 bits per pixel, no rotation
+const LOAD_4BPP: u16 = 2 << 4;
+
+const WAVEFORM_INIT: u16 = 0;
+
+/// How long to wait for the controller before giving up on a transfer or refresh
+const READY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Settings for an IT8951-driven e-paper panel on SPI, as used by Waveshare's HATs.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpdConfig {
+    #[serde(default = "default_spi")]
+    pub spi: String,
+    #[serde(default = "default_speed_hz")]
+    pub speed_hz: u32,
+    /// GPIO of the controller's HRDY line, high when it can take a transfer
+    #[serde(default = "default_ready_gpio")]
+    pub ready_gpio: u32,
+    #[serde(default = "default_reset_gpio")]
+    pub reset_gpio: u32,
+    /// Panel VCOM in millivolts, printed on its flex cable, e.g. 1500 for -1.50V
+    #[serde(default = "default_vcom_mv")]
+    pub vcom_mv: u16,
+    #[serde(default)]
+    pub waveforms: Waveforms,
+}
+
+/// Waveform mode numbers for each refresh mode. These vary between panels; check the
+/// panel's waveform documentation.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Waveforms {
+    /// DU by default: black and white only, no flash
+    #[serde(default = "default_fast_waveform")]
+    pub fast: u16,
+    /// GL16 by default: 16 grays without flashing, ghosts over time
+    #[serde(default = "default_partial_waveform")]
+    pub partial: u16,
+    /// GC16 by default: 16 grays, flashes to clear ghosting
+    #[serde(default = "default_full_waveform")]
+    pub full: u16,
+}
+
+fn default_spi() -> String {
+    "/dev/spidev0.0".to_string()
+}
+
+fn default_speed_hz() -> u32 {
+    12_000_000
+}
+
+fn default_ready_gpio() -> u32 {
+    24
+}
+
+fn default_reset_gpio() -> u32 {
+    17
+}
+
+fn default_vcom_mv() -> u16 {
+    1500
+}
+
+fn default_fast_waveform() -> u16 {
+    1
+}
+
+fn default_partial_waveform() -> u16 {
+    3
+}
+
+fn default_full_waveform() -> u16 {
+    2
+}
+
+impl Default for Waveforms {
+    fn default() -> Self {
+        Self {
+            fast: default_fast_waveform(),
+            partial: default_partial_waveform(),
+            full: default_full_waveform(),
+        }
+    }
+}
+
+impl Default for EpdConfig {
+    fn default() -> Self {
+        Self {
+            spi: default_spi(),
+            speed_hz: default_speed_hz(),
+            ready_gpio: default_ready_gpio(),
+            reset_gpio: default_reset_gpio(),
+            vcom_mv: default_vcom_mv(),
+            waveforms: Waveforms::default(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct SpiIocTransfer {
+    tx_buf: u64,
+    rx_buf: u64,
+    len: u32,
+    speed_hz: u32,
+    delay_usecs: u16,
+    bits_per_word: u8,
+    cs_change: u8,
+    tx_nbits: u8,
+    rx_nbits: u8,
+    word_delay_usecs: u8,
+    pad: u8,
+}
+
+/// A GPIO line through sysfs.
+struct Gpio {
+    value: File,
+}
+
+impl Gpio {
+    fn open(pin: u32, output: bool) -> io::Result<Self> {
+        let dir = format!("/sys/class/gpio/gpio{}", pin);
+
+        if !Path::new(&dir).exists() {
+            std::fs::write("/sys/class/gpio/export", pin.to_string())?;
+        }
+
+        std::fs::write(
+            format!("{}/direction", dir),
+            if output { "out" } else { "in" },
+        )?;
+
+        let value = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(format!("{}/value", dir))?;
+
+        Ok(Gpio { value })
+    }
+
+    fn get(&mut self) -> io::Result<bool> {
+        let mut value = [0u8; 1];
+        self.value.seek(SeekFrom::Start(0))?;
+        self.value.read_exact(&mut value)?;
+        Ok(value[0] == b'1')
+    }
+
+    fn set(&mut self, high: bool) -> io::Result<()> {
+        self.value.seek(SeekFrom::Start(0))?;
+        self.value.write_all(if high { b"1" } else { b"0" })
+    }
+}
+
+/// E-paper panel driven by an IT8951 controller over SPI. Damage is loaded into the
+/// controller's image buffer at 4 bits per pixel and refreshed with the waveform for the
+/// requested mode.
+pub struct It8951 {
+    spi: File,
+    speed_hz: u32,
+    ready: Gpio,
+    width: u32,
+    height: u32,
+    image_address: u32,
+    waveforms: Waveforms,
+}
+
+impl It8951 {
+    pub fn new(config: &EpdConfig) -> Result<Self, String> {
+        println!("Opening IT8951 e-paper display on {}", config.spi);
+
+        let spi = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&config.spi)
+            .map_err(|e| format!("Failed to open {}: {}", config.spi, e))?;
+
+        let mode: u8 = 0;
+        // SAFETY: both ioctls read a value of the size encoded in their request
+        let ok = unsafe {
+            libc::ioctl(spi.as_raw_fd(), SPI_IOC_WR_MODE, &mode) >= 0
+                && libc::ioctl(spi.as_raw_fd(), SPI_IOC_WR_MAX_SPEED_HZ, &config.speed_hz) >= 0
+        };
+
+        if !ok {
+            return Err(format!(
+                "Failed to configure SPI: {}",
+                io::Error::last_os_error()
+            ));
+        }
+
+        let gpio_error = |e: io::Error| format!("Failed to open GPIO: {}", e);
+        let ready = Gpio::open(config.ready_gpio, false).map_err(gpio_error)?;
+        let mut reset = Gpio::open(config.reset_gpio, true).map_err(gpio_error)?;
+
+        let mut display = It8951 {
+            spi,
+            speed_hz: config.speed_hz,
+            ready,
+            width: 0,
+            height: 0,
+            image_address: 0,
+            waveforms: config.waveforms.clone(),
+        };
+
+        display
+            .init(&mut reset, config.vcom_mv)
+            .map_err(|e| format!("Failed to initialize IT8951: {}", e))?;
+
+        println!("E-paper mode: {}x{}", display.width, display.height);
+
+        Ok(display)
+    }
+
+    fn init(&mut self, reset: &mut Gpio, vcom_mv: u16) -> io::Result<()> {
+        reset.set(false)?;
+        thread::sleep(Duration::from_millis(100));
+        reset.set(true)?;
+        thread::sleep(Duration::from_millis(100));
+
+        self.write_command(CMD_SYS_RUN)?;
+
+        self.write_command(CMD_GET_DEVICE_INFO)?;
+        let info = self.read_data(20)?;
+        self.width = info[0] as u32;
+        self.height = info[1] as u32;
+        self.image_address = info[2] as u32 | (info[3] as u32) << 16;
+
+        self.write_register(REG_I80CPCR, 1)?;
+
+        self.write_command(CMD_VCOM)?;
+        self.write_data(&[1])?;
+        self.write_data(&[vcom_mv])?;
+
+        // Clear to white, which also resets the panel's ghosting
+        let mut white = Canvas::new(self.width, self.height);
+        white.pixels.fill(0xFFFF_FFFF);
+        let full = DamageRect {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        };
+        self.load_area(&white, full)?;
+        self.display_area(full, WAVEFORM_INIT)
+    }
+
+    fn transfer(&mut self, tx: &[u8], rx: Option<&mut [u8]>) -> io::Result<()> {
+        let transfer = SpiIocTransfer {
+            tx_buf: tx.as_ptr() as u64,
+            rx_buf: rx.map_or(0, |rx| rx.as_mut_ptr() as u64),
+            len: tx.len() as u32,
+            speed_hz: self.speed_hz,
+            bits_per_word: 8,
+            ..Default::default()
+        };
+
+        // SAFETY: the buffers outlive the ioctl, and rx, when given, is as long as tx
+        let result = unsafe { libc::ioctl(self.spi.as_raw_fd(), SPI_IOC_MESSAGE_1, &transfer) };
+
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    fn wait_ready(&mut self) -> io::Result<()> {
+        let started = Instant::now();
+
+        while !self.ready.get()? {
+            if started.elapsed() > READY_TIMEOUT {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "controller not ready",
+                ));
+            }
+
+            thread::yield_now();
+        }
+
+        Ok(())
+    }
+
+    fn write_command(&mut self, command: u16) -> io::Result<()> {
+        self.wait_ready()?;
+
+        let mut tx = PREAMBLE_COMMAND.to_be_bytes().to_vec();
+        tx.extend_from_slice(&command.to_be_bytes());
+        self.transfer(&tx, None)
+    }
+
+    fn write_data(&mut self, words: &[u16]) -> io::Result<()> {
+        let mut tx = Vec::with_capacity(words.len() * 2);
+
+        for word in words {
+            tx.extend_from_slice(&word.to_be_bytes());
+        }
+
+        self.write_bytes(&tx)
+    }
+
+    /// Write data already laid out as big-endian words, split across transfers.
+    fn write_bytes(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut tx = Vec::with_capacity(SPI_MAX_TRANSFER);
+
+        for chunk in data.chunks(SPI_MAX_TRANSFER - 2) {
+            self.wait_ready()?;
+
+            tx.clear();
+            tx.extend_from_slice(&PREAMBLE_WRITE.to_be_bytes());
+            tx.extend_from_slice(chunk);
+            self.transfer(&tx, None)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_data(&mut self, count: usize) -> io::Result<Vec<u16>> {
+        self.wait_ready()?;
+
+        // Preamble, then a dummy word while the controller fetches the data
+        let mut tx = vec![0u8; 4 + count * 2];
+        tx[..2].copy_from_slice(&PREAMBLE_READ.to_be_bytes());
+        let mut rx = vec![0u8; tx.len()];
+        self.transfer(&tx, Some(&mut rx))?;
+
+        Ok(rx[4..]
+            .chunks_exact(2)
+            .map(|word| u16::from_be_bytes([word[0], word[1]]))
+            .collect())
+    }
+
+    fn write_args(&mut self, command: u16, args: &[u16]) -> io::Result<()> {
+        self.write_command(command)?;
+
+        for &arg in args {
+            self.write_data(&[arg])?;
+        }
+
+        Ok(())
+    }
+
+    fn write_register(&mut self, register: u16, value: u16) -> io::Result<()> {
+        self.write_args(CMD_WRITE_REGISTER, &[register, value])
+    }
+
+    fn read_register(&mut self, register: u16) -> io::Result<u16> {
+        self.write_args(CMD_READ_REGISTER, &[register])?;
+        Ok(self.read_data(1)?[0])
+    }
+
+    /// Wait for the previous refresh to finish driving the panel.
+    fn wait_display_idle(&mut self) -> io::Result<()> {
+        let started = Instant::now();
+
+        while self.read_register(REG_LUTAFSR)? != 0 {
+            if started.elapsed() > READY_TIMEOUT {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "refresh stuck"));
+            }
+
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        Ok(())
+    }
+
+    /// Load a rect of the canvas into the controller's image buffer as 4-bit gray. The
+    /// rect's x should be a multiple of 4, since each word holds 4 pixels. A row whose
+    /// width isn't ends in a word padded with white.
+    fn load_area(&mut self, canvas: &Canvas, rect: DamageRect) -> io::Result<()> {
+        let address = self.image_address;
+        self.write_register(REG_LISAR + 2, (address >> 16) as u16)?;
+        self.write_register(REG_LISAR, address as u16)?;
+
+        self.write_args(
+            CMD_LOAD_IMAGE_AREA,
+            &[
+                LOAD_4BPP,
+                rect.x as u16,
+                rect.y as u16,
+                rect.width as u16,
+                rect.height as u16,
+            ],
+        )?;
+
+        let mut data = Vec::with_capacity((rect.width.div_ceil(4) * 2 * rect.height) as usize);
+
+        for y in rect.y..rect.y + rect.height {
+            let start = (y * canvas.width + rect.x) as usize;
+            let row = &canvas.pixels[start..start + rect.width as usize];
+
+            // Little-endian words: the first pixel in the lowest nibble
+            for pixels in row.chunks(4) {
+                let word = (0..4).fold(0u16, |word, i| {
+                    let gray = pixels.get(i).map_or(0xF, |&pixel| luma(pixel) >> 4);
+                    word | (gray as u16) << (i * 4)
+                });
+                data.extend_from_slice(&word.to_be_bytes());
+            }
+        }
+
+        self.write_bytes(&data)?;
+        self.write_command(CMD_LOAD_IMAGE_END)
+    }
+
+    fn display_area(&mut self, rect: DamageRect, waveform: u16) -> io::Result<()> {
+        self.write_args(
+            CMD_DISPLAY_AREA,
+            &[
+                rect.x as u16,
+                rect.y as u16,
+                rect.width as u16,
+                rect.height as u16,
+                waveform,
+            ],
+        )
+    }
+
+    fn refresh(
+        &mut self,
+        canvas: &Canvas,
+        damage: &[DamageRect],
+        mode: RefreshMode,
+    ) -> io::Result<()> {
+        let full = [DamageRect {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        }];
+        let (damage, waveform) = match mode {
+            RefreshMode::Fast => (damage, self.waveforms.fast),
+            RefreshMode::Partial => (damage, self.waveforms.partial),
+            RefreshMode::Full => (&full[..], self.waveforms.full),
+        };
+
+        self.wait_display_idle()?;
+
+        for rect in damage {
+            // Widen to whole 4-pixel words, though the last may be cut short at the edge
+            // of a panel whose width isn't a multiple of 4
+            let x = rect.x & !3;
+            let right = (rect.x + rect.width).next_multiple_of(4).min(self.width);
+            let rect = DamageRect {
+                x,
+                y: rect.y,
+                width: right - x,
+                height: rect.height,
+            };
+
+            self.load_area(canvas, rect)?;
+            self.display_area(rect, waveform)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Panel for It8951 {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn present(&mut self, canvas: &Canvas, damage: &[DamageRect], mode: RefreshMode) {
+        if let Err(e) = self.refresh(canvas, damage, mode) {
+            println!("Warning: e-paper refresh failed: {}", e);
+        }
+    }
+}
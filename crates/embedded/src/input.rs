@@ -1,5 +1,6 @@
-use evdev::{AbsoluteAxisCode, Device, EventSummary, KeyCode};
-use std::{fs::read_dir, os::unix::io::AsRawFd};
+use evdev::{AbsoluteAxisCode, Device, EventSummary, KeyCode, RelativeAxisCode};
+use juice::pointer::{Pointer, PointerTool};
+use std::{fs::read_dir, os::unix::io::AsRawFd, time::SystemTime};
 use tokio::io::unix::AsyncFd;
 
 #[derive(Clone, Copy, Debug)]
@@ -7,31 +8,61 @@ pub struct TouchState {
     pub x: i32,
     pub y: i32,
     pub pressed: bool,
+    /// Raw ABS_PRESSURE / ABS_MT_PRESSURE value, if the device reports one
+    pub pressure: Option<i32>,
+    pub tool: PointerTool,
 }
 
 #[derive(Clone, Copy, Debug)]
 pub enum TouchEvent {
-    PressIn { x: i32, y: i32 },
-    PressOut { x: i32, y: i32 },
-    Move { x: i32, y: i32 },
+    PressIn { x: i32, y: i32, pointer: Pointer },
+    PressOut { x: i32, y: i32, pointer: Pointer },
+    Move { x: i32, y: i32, pointer: Pointer },
+}
+
+impl TouchEvent {
+    pub fn position(&self) -> (f32, f32) {
+        match *self {
+            TouchEvent::PressIn { x, y, .. }
+            | TouchEvent::PressOut { x, y, .. }
+            | TouchEvent::Move { x, y, .. } => (x as f32, y as f32),
+        }
+    }
 }
 
 pub struct InputDevice {
     async_fd: AsyncFd<Device>,
     pub touch_state: TouchState,
+    /// Min and max of the device's pressure axis, for normalizing
+    pressure_range: Option<(i32, i32)>,
+    /// When the kernel timestamped the first event behind the last `next_event`
+    event_time: SystemTime,
 }
 
 impl InputDevice {
     pub fn new(device: Device) -> Self {
         set_nonblocking(&device);
 
+        let pressure_range = device.get_absinfo().ok().and_then(|mut axes| {
+            axes.find(|(code, _)| {
+                *code == AbsoluteAxisCode::ABS_MT_PRESSURE
+                    || *code == AbsoluteAxisCode::ABS_PRESSURE
+            })
+            .map(|(_, info)| (info.minimum(), info.maximum()))
+            .filter(|(min, max)| max > min)
+        });
+
         Self {
             async_fd: AsyncFd::new(device).unwrap(),
             touch_state: TouchState {
                 x: 0,
                 y: 0,
                 pressed: false,
+                pressure: None,
+                tool: PointerTool::Touch,
             },
+            pressure_range,
+            event_time: SystemTime::now(),
         }
     }
 
@@ -56,6 +87,21 @@ impl InputDevice {
             .next()
     }
 
+    /// The name the device reports, for diagnostics.
+    pub fn name(&self) -> String {
+        self.async_fd
+            .get_ref()
+            .name()
+            .unwrap_or("Unknown")
+            .to_string()
+    }
+
+    /// When the kernel timestamped the first of the evdev events behind the last
+    /// `next_event`, for measuring latency.
+    pub fn event_time(&self) -> SystemTime {
+        self.event_time
+    }
+
     pub async fn next_event(&mut self) -> TouchEvent {
         loop {
             self.async_fd.readable().await.unwrap().clear_ready();
@@ -69,9 +115,12 @@ impl InputDevice {
     fn read_touch_state(&mut self) -> Option<TouchState> {
         let mut touch_state = self.touch_state;
         let mut has_event = false;
+        let mut event_time = None;
 
         while let Ok(events) = self.async_fd.get_mut().fetch_events() {
             for event in events {
+                event_time.get_or_insert(event.timestamp());
+
                 match event.destructure() {
                     EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_X, val) => {
                         touch_state.x = val;
@@ -89,36 +138,82 @@ impl InputDevice {
                         touch_state.y = val;
                         has_event = true;
                     }
-                    EventSummary::Key(_, KeyCode::BTN_TOUCH, val)
-                    | EventSummary::Key(_, KeyCode::BTN_TOOL_FINGER, val) => {
+                    EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_PRESSURE, val)
+                    | EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_MT_PRESSURE, val) => {
+                        touch_state.pressure = Some(val);
+                        has_event = true;
+                    }
+                    EventSummary::Key(_, KeyCode::BTN_TOUCH, val) => {
                         touch_state.pressed = val != 0;
                         has_event = true;
                     }
+                    EventSummary::Key(_, KeyCode::BTN_TOOL_FINGER, val) => {
+                        touch_state.pressed = val != 0;
+                        touch_state.tool = PointerTool::Touch;
+                        has_event = true;
+                    }
+                    // Pens report their tool while hovering in range, and release it on
+                    // leaving; contact comes separately as BTN_TOUCH
+                    EventSummary::Key(_, KeyCode::BTN_TOOL_PEN, val) => {
+                        touch_state.tool = if val != 0 {
+                            PointerTool::Pen
+                        } else {
+                            PointerTool::default()
+                        };
+                    }
+                    EventSummary::Key(_, KeyCode::BTN_TOOL_RUBBER, val) => {
+                        touch_state.tool = if val != 0 {
+                            PointerTool::Eraser
+                        } else {
+                            PointerTool::default()
+                        };
+                    }
                     _ => {}
                 }
             }
         }
 
+        if let Some(event_time) = event_time {
+            self.event_time = event_time;
+        }
+
         if has_event { Some(touch_state) } else { None }
     }
 
+    fn pointer(&self, touch_state: &TouchState) -> Pointer {
+        let pressure = match (touch_state.pressure, self.pressure_range) {
+            _ if !touch_state.pressed => 0.0,
+            (Some(val), Some((min, max))) => (val - min) as f32 / (max - min) as f32,
+            _ => 0.5,
+        };
+
+        Pointer::new(touch_state.tool, pressure)
+    }
+
     fn read_touch_event(&mut self) -> Option<TouchEvent> {
         let touch_state = self.read_touch_state()?;
+        let pointer = self.pointer(&touch_state);
 
         let result = if touch_state.pressed && !self.touch_state.pressed {
             Some(TouchEvent::PressIn {
                 x: touch_state.x,
                 y: touch_state.y,
+                pointer,
             })
         } else if !touch_state.pressed && self.touch_state.pressed {
             Some(TouchEvent::PressOut {
                 x: touch_state.x,
                 y: touch_state.y,
+                pointer,
             })
-        } else if self.touch_state.x != touch_state.x || self.touch_state.y != touch_state.y {
+        } else if self.touch_state.x != touch_state.x
+            || self.touch_state.y != touch_state.y
+            || self.touch_state.pressure != touch_state.pressure
+        {
             Some(TouchEvent::Move {
                 x: touch_state.x,
                 y: touch_state.y,
+                pointer,
             })
         } else {
             None
@@ -129,6 +224,86 @@ impl InputDevice {
     }
 }
 
+/// A mouse wheel or rotary encoder reporting `REL_WHEEL`/`REL_HWHEEL`.
+pub struct WheelDevice {
+    async_fd: AsyncFd<Device>,
+    /// When the kernel timestamped the first event behind the last `next_notches`
+    event_time: SystemTime,
+}
+
+impl WheelDevice {
+    pub fn new(device: Device) -> Self {
+        set_nonblocking(&device);
+
+        Self {
+            async_fd: AsyncFd::new(device).unwrap(),
+            event_time: SystemTime::now(),
+        }
+    }
+
+    pub fn get_wheel_device() -> Option<Self> {
+        read_dir("/dev/input")
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let device = Device::open(entry.ok()?.path()).ok()?;
+
+                if has_wheel(&device) {
+                    Some(Self::new(device))
+                } else {
+                    None
+                }
+            })
+            .next()
+    }
+
+    /// The name the device reports, for diagnostics.
+    pub fn name(&self) -> String {
+        self.async_fd
+            .get_ref()
+            .name()
+            .unwrap_or("Unknown")
+            .to_string()
+    }
+
+    /// When the kernel timestamped the first of the evdev events behind the last
+    /// `next_notches`, for measuring latency.
+    pub fn event_time(&self) -> SystemTime {
+        self.event_time
+    }
+
+    /// Wait for wheel movement, returning the (horizontal, vertical) notches turned.
+    pub async fn next_notches(&mut self) -> (i32, i32) {
+        loop {
+            self.async_fd.readable().await.unwrap().clear_ready();
+
+            let mut notches = (0, 0);
+            let mut event_time = None;
+
+            while let Ok(events) = self.async_fd.get_mut().fetch_events() {
+                for event in events {
+                    event_time.get_or_insert(event.timestamp());
+
+                    match event.destructure() {
+                        EventSummary::RelativeAxis(_, RelativeAxisCode::REL_HWHEEL, val) => {
+                            notches.0 += val;
+                        }
+                        EventSummary::RelativeAxis(_, RelativeAxisCode::REL_WHEEL, val) => {
+                            notches.1 += val;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            if notches != (0, 0) {
+                self.event_time = event_time.unwrap_or_else(SystemTime::now);
+                return notches;
+            }
+        }
+    }
+}
+
 fn set_nonblocking(device: &Device) {
     unsafe {
         let flags = libc::fcntl(device.as_raw_fd(), libc::F_GETFL, 0);
@@ -145,3 +320,9 @@ fn is_touchscreen(device: &Device) -> bool {
         false
     }
 }
+
+fn has_wheel(device: &Device) -> bool {
+    device.supported_relative_axes().is_some_and(|axes| {
+        axes.contains(RelativeAxisCode::REL_WHEEL) || axes.contains(RelativeAxisCode::REL_HWHEEL)
+    })
+}
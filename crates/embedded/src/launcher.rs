@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use juice::display_filter::DisplayFilter;
+use juice::launcher::{Launcher, LauncherConfig};
+use juice::layers::LayerStack;
+use juice::memory::PressureMonitor;
+use juice::mono::RefreshMode;
+use juice::permissions::Permissions;
+
+use crate::config::HostConfig;
+use crate::input::{InputDevice, TouchEvent, WheelDevice};
+use crate::light::AmbientLightSensor;
+use crate::present::Presenter;
+
+/// Drive the display and input for a home grid of the apps in the launcher's folder,
+/// starting each app in its own renderer, with its own files under
+/// `<data dir>/apps/<id>`, when it's first opened.
+pub async fn run_launcher(
+    config: &HostConfig,
+    launcher_config: LauncherConfig,
+    mut presenter: Presenter,
+    width: u32,
+    height: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = launcher_config.dir.clone();
+    let (launcher, errors) =
+        Launcher::new(launcher_config, width, height).map_err(|e| e.message)?;
+
+    for e in errors {
+        println!("Warning: {}", e.message);
+    }
+
+    println!(
+        "Launcher: {} apps in {}",
+        launcher.apps().len(),
+        dir.display()
+    );
+
+    let grants = config.permissions.clone().unwrap_or_default();
+    let mut stack = LayerStack::new(width, height);
+
+    let mut touch_device = InputDevice::get_touchscreen_device();
+
+    if touch_device.is_none() {
+        println!("Warning: No touchscreen device found");
+    }
+
+    // Wheels scroll what's under the last touch, or the middle of the screen before one
+    let mut wheel_device = WheelDevice::get_wheel_device();
+    let mut pointer_position = None;
+
+    let mut light_sensor = AmbientLightSensor::find();
+    let mut light_interval = tokio::time::interval(Duration::from_secs(1));
+
+    let mut pressure_monitor = if config.memory_pressure.unwrap_or(true) {
+        PressureMonitor::open()
+    } else {
+        None
+    };
+    let mut pressure_interval = tokio::time::interval(Duration::from_secs(1));
+
+    let mut frame_interval = tokio::time::interval(Duration::from_millis(16));
+
+    loop {
+        tokio::select! {
+            _ = frame_interval.tick() => {}
+
+            // Every running app gets light readings and memory pressure, shown or not
+            _ = light_interval.tick(), if light_sensor.is_some() => {
+                if let Some(lux) = light_sensor.as_mut().and_then(|sensor| sensor.poll()) {
+                    for layer in stack.layers_mut() {
+                        layer.renderer.dispatch_ambient_light(lux).await;
+                    }
+                }
+            }
+
+            _ = pressure_interval.tick(), if pressure_monitor.is_some() => {
+                if let Some(level) = pressure_monitor.as_mut().and_then(|monitor| monitor.poll()) {
+                    for layer in stack.layers_mut() {
+                        layer.renderer.trim_memory(level).await;
+                    }
+                }
+            }
+
+            notches = async { wheel_device.as_mut().unwrap().next_notches().await }, if wheel_device.is_some() => {
+                let (horizontal, vertical) = notches;
+                let (x, y) = pointer_position.unwrap_or((width as f32 / 2.0, height as f32 / 2.0));
+                launcher.dispatch_wheel(&mut stack, x, y, horizontal as f32, vertical as f32).await;
+            }
+
+            event = async { touch_device.as_mut().unwrap().next_event().await }, if touch_device.is_some() => {
+                let pressed = touch_device.as_ref().is_some_and(|device| device.touch_state.pressed);
+                pointer_position = Some(event.position());
+
+                let event = match event {
+                    TouchEvent::PressIn { x, y, pointer } => Some(("PressIn", x, y, pointer)),
+                    TouchEvent::PressOut { x, y, pointer } => Some(("PressOut", x, y, pointer)),
+                    TouchEvent::Move { x, y, pointer } if pressed => Some(("PressMove", x, y, pointer)),
+                    TouchEvent::Move { .. } => None,
+                };
+
+                if let Some((name, x, y, pointer)) = event {
+                    launcher.dispatch_pointer_event(&mut stack, name, x as f32, y as f32, pointer).await;
+                }
+            }
+        }
+
+        launcher.tick(&mut stack).await;
+
+        if let Some(app) = launcher.take_launch() {
+            println!("Launcher: starting {}", app.id);
+
+            let data_dir = crate::data_dir().join("apps").join(&app.id);
+            let (permissions, denied) =
+                Permissions::for_app(&app.id, &app.manifest.permissions, &grants);
+
+            if !denied.is_empty() {
+                println!("Warning: {} isn't granted {}", app.id, denied.join(", "));
+            }
+
+            let modules: Vec<Box<dyn juice::engine::JsModule>> = vec![Box::new(launcher.clone())];
+
+            match app.load() {
+                Ok(bundle) => {
+                    match crate::start_renderer(
+                        config,
+                        width,
+                        height,
+                        &data_dir,
+                        &bundle,
+                        permissions,
+                        modules,
+                    )
+                    .await
+                    {
+                        Ok(renderer) => {
+                            // Images can come from the app's own folder
+                            renderer.set_asset_dir(Some(app.dir.clone()));
+                            launcher.open(&mut stack, &app, renderer);
+                        }
+                        Err(e) => println!("Warning: can't start {}: {}", app.id, e),
+                    }
+                }
+                Err(e) => println!("Warning: {}", e.message),
+            }
+        }
+
+        stack.tick().await;
+
+        // The app shown picks the filter and e-paper refresh
+        let active = launcher.active().unwrap_or_default();
+
+        presenter.set_filter(
+            stack
+                .layer(&active)
+                .map_or(DisplayFilter::default(), |app| app.display_filter()),
+        );
+
+        if launcher.render(&mut stack) {
+            let refresh_mode = stack
+                .layer(&active)
+                .map_or(RefreshMode::Partial, |app| app.refresh_mode());
+            presenter.submit(&stack.canvas, stack.damage(), refresh_mode, None);
+        } else if presenter.has_carried_damage() {
+            presenter.submit(&stack.canvas, &[], RefreshMode::Fast, None);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::fs::{read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+/// An ambient light sensor exposed through the IIO subsystem.
+pub struct AmbientLightSensor {
+    /// Processed lux reading, or a raw one to scale
+    input: PathBuf,
+    scale: f32,
+    offset: f32,
+    last_reported: Option<f32>,
+}
+
+impl AmbientLightSensor {
+    /// Find the first IIO device with an illuminance channel.
+    pub fn find() -> Option<Self> {
+        let devices = read_dir("/sys/bus/iio/devices").ok()?;
+
+        for device in devices.flatten() {
+            let path = device.path();
+
+            for channel in ["in_illuminance", "in_illuminance0"] {
+                if let Some(sensor) = Self::open(&path, channel) {
+                    println!("Ambient light sensor: {}", path.display());
+                    return Some(sensor);
+                }
+            }
+        }
+
+        None
+    }
+
+    fn open(device: &Path, channel: &str) -> Option<Self> {
+        let processed = device.join(format!("{}_input", channel));
+
+        if processed.exists() {
+            return Some(AmbientLightSensor {
+                input: processed,
+                scale: 1.0,
+                offset: 0.0,
+                last_reported: None,
+            });
+        }
+
+        let raw = device.join(format!("{}_raw", channel));
+
+        if !raw.exists() {
+            return None;
+        }
+
+        let attribute = |name: &str, default: f32| {
+            read_number(&device.join(format!("{}_{}", channel, name))).unwrap_or(default)
+        };
+
+        Some(AmbientLightSensor {
+            input: raw,
+            scale: attribute("scale", 1.0),
+            offset: attribute("offset", 0.0),
+            last_reported: None,
+        })
+    }
+
+    pub fn read_lux(&self) -> Option<f32> {
+        Some((read_number(&self.input)? + self.offset) * self.scale)
+    }
+
+    /// Read the sensor, returning the lux if it moved enough since the last reported value
+    /// to be worth telling JS about: 10% plus a little for noise in the dark.
+    pub fn poll(&mut self) -> Option<f32> {
+        let lux = self.read_lux()?;
+
+        if let Some(last) = self.last_reported
+            && (lux - last).abs() <= last * 0.1 + 1.0
+        {
+            return None;
+        }
+
+        self.last_reported = Some(lux);
+        Some(lux)
+    }
+}
+
+fn read_number(path: &Path) -> Option<f32> {
+    read_to_string(path).ok()?.trim().parse().ok()
+}
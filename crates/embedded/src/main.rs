@@ -1,51 +1,90 @@
+mod config;
 mod console;
 mod drm;
+mod epd;
 mod input;
+mod launcher;
+mod light;
+mod present;
+mod sandbox;
+mod surface;
 
+use juice::calibration::OutputTransform;
 use juice::canvas::{Canvas, RgbColor};
-use juice::inherited_style::{InheritedStyle, TextAlign};
+use juice::engine::JsModule;
+use juice::fs::Fs;
+use juice::inherited_style::{
+    Direction, FontStyle, FontVariations, FontWeight, InheritedStyle, LineHeight, TextAlign,
+    TextDecoration, TextOverflow,
+};
+use juice::log::LogStore;
+use juice::memory::PressureMonitor;
+use juice::mono::{MonoConverter, RefreshMode};
+use juice::permissions::Permissions;
 use juice::renderer::Renderer;
+use juice::sandbox::SandboxClient;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use crate::config::HostConfig;
 use crate::console::Console;
-use crate::input::{InputDevice, TouchEvent};
+use crate::drm::DrmDisplay;
+use crate::epd::It8951;
+use crate::input::{InputDevice, TouchEvent, WheelDevice};
+use crate::light::AmbientLightSensor;
+use crate::present::{FramePolicy, Panel, Presenter};
+use crate::surface::SurfaceFeeds;
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let fonts = HashMap::new();
+    let config = HostConfig::load();
 
-    #[cfg(feature = "hotreload")]
-    let reload_rx = juice_dev::spawn_reload_listener();
+    // Started by the display process in sandbox mode: run the app, sending frames back
+    // instead of driving the display
+    if let Some(client) = SandboxClient::connect() {
+        return sandbox::run_app(config, client.map_err(|e| e.message)?).await;
+    }
 
-    // Hardware init
-    let mut display =
-        drm::DrmDisplay::new("/dev/dri/card0").expect("Failed to initialize DRM display");
+    let (mut presenter, display_width, display_height) = open_presenter(&config);
 
-    let display_width = display.width();
-    let display_height = display.height();
+    if let Some(sandbox_config) = config.sandbox.clone() {
+        return sandbox::run_host(
+            &config,
+            &sandbox_config,
+            presenter,
+            display_width,
+            display_height,
+        )
+        .await;
+    }
 
-    println!("Display: {}x{}", display_width, display_height);
+    if let Some(launcher_config) = config.launcher.clone() {
+        return launcher::run_launcher(
+            &config,
+            launcher_config,
+            presenter,
+            display_width,
+            display_height,
+        )
+        .await;
+    }
 
-    let canvas = Canvas::new(display_width, display_height);
-    let default_font = "Roboto-Regular";
+    #[cfg(feature = "hotreload")]
+    let dev = juice_dev::DevChannel::connect();
 
-    let mut renderer = Renderer::new(
-        canvas,
-        fonts,
-        InheritedStyle {
-            color: RgbColor::from_array([255, 255, 255]),
-            font_name: default_font.to_string(),
-            font_size: 24.0,
-            text_align: TextAlign::Left,
-        },
-        vec![Box::new(Console {})],
+    let mut renderer = start_renderer(
+        &config,
+        display_width,
+        display_height,
+        &data_dir(),
+        &app_bundle(),
+        config.app_permissions(),
+        Vec::new(),
     )
-    .await;
+    .await?;
 
-    let bundle = include_str!("../../../dist/bundle.js").to_string();
-
-    renderer.engine.load(&bundle).await;
+    renderer.set_asset_dir(Some(asset_dir()));
 
     // set up touchscreen input
     let mut touch_device = InputDevice::get_touchscreen_device();
@@ -54,6 +93,54 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("Warning: No touchscreen device found");
     }
 
+    // Wheels scroll what's under the last touch. Until there's been one, rotary encoders
+    // and wheels scroll the most recently scrolled scrollable.
+    let mut wheel_device = WheelDevice::get_wheel_device();
+    let mut pointer_position = None;
+
+    // Light readings go to JS as AmbientLight events on the document, and can switch
+    // between the app's light and dark themes natively
+    let mut light_sensor = AmbientLightSensor::find();
+    let mut light_interval = tokio::time::interval(Duration::from_secs(1));
+
+    // Caches are trimmed and JS told when the kernel reports memory pressure, so a device
+    // left running for months doesn't end up killed for memory
+    let mut pressure_monitor = if config.memory_pressure.unwrap_or(true) {
+        PressureMonitor::open()
+    } else {
+        None
+    };
+    let mut pressure_interval = tokio::time::interval(Duration::from_secs(1));
+
+    // Tapping the top-right corner five times opens the diagnostics screen
+    let input_devices = [
+        touch_device
+            .as_ref()
+            .map(|device| format!("touch: {}", device.name())),
+        wheel_device
+            .as_ref()
+            .map(|device| format!("wheel: {}", device.name())),
+        light_sensor
+            .as_ref()
+            .map(|_| "ambient light sensor".to_string()),
+    ];
+    renderer.enable_diagnostics(input_devices.into_iter().flatten().collect());
+
+    // JUICE_LATENCY=1 measures input-to-photon latency, overriding the config file
+    let latency_enabled = std::env::var("JUICE_LATENCY")
+        .ok()
+        .map(|value| value == "1")
+        .or(config.latency)
+        .unwrap_or(false);
+    let mut latency_interval = tokio::time::interval(Duration::from_secs(10));
+
+    if latency_enabled {
+        presenter.set_latency(Some(renderer.track_latency()));
+    }
+
+    // Camera and decoder frames go on the plane beneath each `<surface>` named after them
+    let surface_feeds = SurfaceFeeds::start(&config.surfaces.clone().unwrap_or_default());
+
     let mut frame_interval = tokio::time::interval(Duration::from_millis(16));
 
     // Event loop
@@ -62,29 +149,287 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         tokio::select! {
             _ = frame_interval.tick() => {}
 
+            _ = light_interval.tick(), if light_sensor.is_some() => {
+                if let Some(lux) = light_sensor.as_mut().and_then(|sensor| sensor.poll()) {
+                    renderer.dispatch_ambient_light(lux).await;
+                }
+            }
+
+            _ = pressure_interval.tick(), if pressure_monitor.is_some() => {
+                if let Some(level) = pressure_monitor.as_mut().and_then(|monitor| monitor.poll()) {
+                    renderer.trim_memory(level).await;
+                }
+            }
+
+            _ = latency_interval.tick(), if latency_enabled => {
+                if let Some(stats) = renderer.latency_stats().filter(|stats| stats.samples > 0) {
+                    println!(
+                        "Latency over {} inputs, p50/p90/p99 ms: total {:.1}/{:.1}/{:.1}, dispatch {:.1}/{:.1}/{:.1}, render {:.1}/{:.1}/{:.1}, flush {:.1}/{:.1}/{:.1}",
+                        stats.samples,
+                        stats.total.p50, stats.total.p90, stats.total.p99,
+                        stats.dispatch.p50, stats.dispatch.p90, stats.dispatch.p99,
+                        stats.render.p50, stats.render.p90, stats.render.p99,
+                        stats.flush.p50, stats.flush.p90, stats.flush.p99,
+                    );
+                }
+            }
+
+            notches = async { wheel_device.as_mut().unwrap().next_notches().await }, if wheel_device.is_some() => {
+                let (horizontal, vertical) = notches;
+                renderer.record_input(wheel_device.as_ref().unwrap().event_time());
+
+                match pointer_position {
+                    Some((x, y)) => renderer.dispatch_wheel(x, y, horizontal as f32, vertical as f32).await,
+                    None => {
+                        renderer.dispatch_encoder(vertical as f32).await;
+
+                        if horizontal != 0 {
+                            let (x, y) = (display_width as f32 / 2.0, display_height as f32 / 2.0);
+                            renderer.dispatch_wheel(x, y, horizontal as f32, 0.0).await;
+                        }
+                    }
+                }
+            }
+
             event = async { touch_device.as_mut().unwrap().next_event().await }, if touch_device.is_some() => {
+                renderer.record_input(touch_device.as_ref().unwrap().event_time());
+                pointer_position = Some(event.position());
+
                 match event {
-                    TouchEvent::PressIn { x, y } => {
-                        renderer.dispatch_xy_event("PressIn", x as f32, y as f32).await;
+                    TouchEvent::PressIn { x, y, pointer } => {
+                        renderer.dispatch_pointer_event("PressIn", x as f32, y as f32, pointer).await;
+                    }
+                    TouchEvent::PressOut { x, y, pointer } => {
+                        renderer.dispatch_pointer_event("PressOut", x as f32, y as f32, pointer).await;
                     }
-                    TouchEvent::PressOut { x, y } => {
-                        renderer.dispatch_xy_event("PressOut", x as f32, y as f32).await;
+                    // Moves only count while pressed: they drive drag scrolling and gestures
+                    TouchEvent::Move { x, y, pointer } => {
+                        if touch_device.as_ref().is_some_and(|device| device.touch_state.pressed) {
+                            renderer.dispatch_pointer_event("PressMove", x as f32, y as f32, pointer).await;
+                        }
                     }
-                    _ => {}
                 }
             }
         }
 
+        surface_feeds.update(&mut renderer, &mut presenter);
+
         renderer.tick().await;
 
+        presenter.set_filter(renderer.display_filter());
+
         if renderer.render() {
-            display.blit_from(&renderer.canvas);
+            presenter.submit(
+                &renderer.canvas,
+                renderer.damage(),
+                renderer.refresh_mode(),
+                renderer.latency_tag(),
+            );
+        } else if presenter.has_carried_damage() {
+            presenter.submit(&renderer.canvas, &[], RefreshMode::Fast, None);
         }
 
         #[cfg(feature = "hotreload")]
-        if let Ok(new_bundle) = reload_rx.try_recv() {
+        if let Some(new_bundle) = dev.try_recv_bundle() {
             println!("[dev] reloading bundle...");
             renderer.reload(&new_bundle).await;
         }
+
+        #[cfg(feature = "hotreload")]
+        while let Some(request) = dev.try_recv_request() {
+            match request {
+                juice_dev::DevRequest::Animations => {
+                    dev.reply_animations(&renderer.inspect_animations())
+                }
+            }
+        }
+    }
+}
+
+/// Open the display the config or `JUICE_DISPLAY` picks, and the presenter flushing
+/// frames to it. Returns the display's width and height too.
+fn open_presenter(config: &HostConfig) -> (Presenter, u32, u32) {
+    // Hardware init. JUICE_DISPLAY=it8951 drives an e-paper panel over SPI instead of
+    // DRM, overriding the config file.
+    let display: Box<dyn Panel> = match std::env::var("JUICE_DISPLAY")
+        .ok()
+        .or(config.display.clone())
+        .as_deref()
+    {
+        Some("it8951") => Box::new(
+            It8951::new(&config.epd.clone().unwrap_or_default())
+                .expect("Failed to initialize e-paper display"),
+        ),
+        _ => {
+            let display =
+                DrmDisplay::new("/dev/dri/card0").expect("Failed to initialize DRM display");
+
+            if display.has_underlay() {
+                println!("Compositing the UI on a hardware overlay plane");
+            }
+
+            Box::new(display)
+        }
+    };
+
+    let display_width = display.width();
+    let display_height = display.height();
+
+    println!("Display: {}x{}", display_width, display_height);
+
+    // JUICE_BUFFERS=3 adds a third buffer so a frame can queue behind one being flushed,
+    // and JUICE_FRAME_POLICY=drop replaces queued frames instead of waiting on the panel.
+    // Both override the config file.
+    let buffers = std::env::var("JUICE_BUFFERS")
+        .ok()
+        .and_then(|buffers| buffers.parse().ok())
+        .or(config.buffers)
+        .unwrap_or(2);
+    let policy = std::env::var("JUICE_FRAME_POLICY")
+        .ok()
+        .or(config.frame_policy.clone())
+        .and_then(|policy| FramePolicy::parse(&policy))
+        .unwrap_or(FramePolicy::Block);
+
+    let transform = config
+        .color
+        .as_ref()
+        .and_then(|color| match OutputTransform::new(color) {
+            Ok(transform) => Some(transform),
+            Err(e) => {
+                println!("Warning: ignoring color calibration: {}", e.message);
+                None
+            }
+        });
+
+    let mono = config.mono.clone().map(|mono| {
+        println!(
+            "Converting output to {:?} with {:?} dithering",
+            mono.format, mono.dither
+        );
+        MonoConverter::new(mono)
+    });
+
+    println!("Presenting with {} buffers, {:?} policy", buffers, policy);
+    let presenter = Presenter::new(
+        display,
+        buffers,
+        policy,
+        transform,
+        mono,
+        config.refresh.clone().unwrap_or_default(),
+    );
+
+    (presenter, display_width, display_height)
+}
+
+/// Where apps keep their files and logs: `JUICE_DATA_DIR`, or `data` in the working
+/// directory.
+fn data_dir() -> PathBuf {
+    std::env::var("JUICE_DATA_DIR")
+        .unwrap_or_else(|_| "data".to_string())
+        .into()
+}
+
+/// Where images with a path for their `src` are read from: `JUICE_ASSETS`, or the folder
+/// `JUICE_BUNDLE` is in, or `assets` in the working directory for the built-in bundle.
+fn asset_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("JUICE_ASSETS") {
+        return dir.into();
+    }
+
+    match std::env::var("JUICE_BUNDLE") {
+        Ok(path) => Path::new(&path)
+            .parent()
+            .map_or_else(|| ".".into(), Path::to_path_buf),
+        Err(_) => "assets".into(),
+    }
+}
+
+/// The app's JS. JUICE_BUNDLE=gallery.js runs another bundle, like the example gallery,
+/// in place of the one built in.
+fn app_bundle() -> String {
+    match std::env::var("JUICE_BUNDLE") {
+        Ok(path) => std::fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("Can't read the bundle {}: {}", path, err)),
+        Err(_) => include_str!("../../../dist/bundle.js").to_string(),
+    }
+}
+
+/// Create the renderer for a `width` by `height` display and load `bundle` into it, with
+/// the config's logs, limits, splash screen, themes and caches, and files kept in
+/// `data_dir`. `modules` are given to JS along with the console, and the file system if
+/// `permissions` allow it.
+async fn start_renderer(
+    config: &HostConfig,
+    width: u32,
+    height: u32,
+    data_dir: &Path,
+    bundle: &str,
+    permissions: Permissions,
+    modules: Vec<Box<dyn JsModule>>,
+) -> Result<Renderer, Box<dyn std::error::Error>> {
+    let fonts = HashMap::new();
+    let canvas = Canvas::new(width, height);
+    let default_font = "Roboto-Regular";
+    std::fs::create_dir_all(data_dir)?;
+
+    let mut log_config = config.log.clone().unwrap_or_default();
+    log_config
+        .path
+        .get_or_insert_with(|| data_dir.join("logs/juice.log"));
+    let logs = LogStore::new(log_config);
+
+    let mut modules = modules;
+    modules.push(Box::new(Console { logs: logs.clone() }));
+    modules.push(permissions.guard("fs", Box::new(Fs::new(data_dir))));
+    modules.push(Box::new(permissions));
+
+    let mut renderer = Renderer::new(
+        canvas,
+        fonts,
+        InheritedStyle {
+            color: RgbColor::from_array([255, 255, 255]),
+            font_name: default_font.to_string(),
+            font_size: 24.0,
+            font_weight: FontWeight::NORMAL,
+            font_style: FontStyle::Normal,
+            font_variations: FontVariations::default(),
+            text_align: TextAlign::Left,
+            line_height: LineHeight::Normal,
+            text_overflow: TextOverflow::Wrap,
+            max_lines: 0,
+            letter_spacing: 0.0,
+            tab_size: 8.0,
+            direction: Direction::Ltr,
+            text_decoration: TextDecoration::default(),
+        },
+        modules,
+    )
+    .await;
+
+    // Tapping the top-left corner five times opens the log viewer
+    renderer.set_log_store(Some(logs));
+    renderer
+        .dom
+        .borrow_mut()
+        .set_limits(config.limits.unwrap_or_default());
+
+    // Shown until the app calls renderer.ready() and what it first draws is decoded, so
+    // the frames before that, missing icons and images, are never seen
+    if let Some(splash) = &config.splash
+        && let Err(e) = renderer.show_splash(splash)
+    {
+        println!("Warning: no splash screen: {}", e.message);
     }
+
+    renderer.engine.load(bundle).await;
+
+    // Light readings go to JS as AmbientLight events on the document, and can switch
+    // between the app's light and dark themes natively
+    renderer.set_auto_theme(config.auto_theme.clone());
+    renderer.set_display_filter(config.display_filter.unwrap_or_default());
+    renderer.set_memory_budget(config.memory_budget.unwrap_or_default());
+
+    Ok(renderer)
 }
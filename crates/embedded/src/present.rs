@@ -0,0 +1,362 @@
+use juice::calibration::OutputTransform;
+use juice::canvas::Canvas;
+use juice::damage::{DamageRect, MAX_DAMAGE_RECTS, merge_rects};
+use juice::display_filter::DisplayFilter;
+use juice::dom::SurfaceRect;
+use juice::latency::LatencyRecorder;
+use juice::mono::{MonoConverter, RefreshConfig, RefreshMode, RefreshScheduler};
+use juice::video::VideoFrame;
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Instant, SystemTime};
+
+/// A display the presenter can flush frames to.
+pub trait Panel: Send {
+    fn width(&self) -> u32;
+    fn height(&self) -> u32;
+    /// Show the damaged parts of `canvas`. Under `RefreshMode::Full`, redraw the whole
+    /// screen instead.
+    fn present(&mut self, canvas: &Canvas, damage: &[DamageRect], mode: RefreshMode);
+
+    /// Whether frames can be shown on a hardware plane beneath the UI.
+    fn has_underlay(&self) -> bool {
+        false
+    }
+
+    /// Show `frame` on the plane beneath the UI, scaled into `rect`.
+    fn set_underlay(&mut self, _frame: &VideoFrame, _rect: &SurfaceRect) -> Result<(), String> {
+        Err("This display has no underlay plane".to_string())
+    }
+}
+
+/// What to do with a new frame when every buffer is waiting on a slow panel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FramePolicy {
+    /// Wait for the panel to take a frame. Every frame is shown, but the render loop stalls.
+    Block,
+    /// Replace the oldest frame not yet being flushed, so the loop keeps handling input
+    /// and the panel always gets the newest frame next.
+    DropOldest,
+}
+
+impl FramePolicy {
+    pub fn parse(policy: &str) -> Option<Self> {
+        match policy {
+            "block" => Some(FramePolicy::Block),
+            "drop" | "drop-oldest" => Some(FramePolicy::DropOldest),
+            _ => None,
+        }
+    }
+}
+
+struct Frame {
+    canvas: Canvas,
+    damage: Vec<DamageRect>,
+    mode: RefreshMode,
+    /// Latency tags of the frames shown by this one, including any dropped for it
+    latency_tags: Vec<u64>,
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: VecDeque<Frame>,
+    flushing: bool,
+    /// Frame buffers no longer in use, kept to avoid reallocating
+    free: Vec<Canvas>,
+    shutdown: bool,
+    /// Told when tagged frames reach the panel
+    latency: Option<LatencyRecorder>,
+    /// The newest frame for the underlay plane, replacing any the panel hasn't taken yet
+    underlay: Option<(VideoFrame, SurfaceRect)>,
+}
+
+/// What the presenter thread does next.
+enum Job {
+    Flush(RefreshMode, Vec<DamageRect>, Option<LatencyRecorder>),
+    Underlay(VideoFrame, SurfaceRect),
+}
+
+/// Flushes frames to the display on its own thread, so a slow panel doesn't hold up the
+/// render loop. The canvas counts as one buffer; the rest hold copies of frames waiting to
+/// be flushed, including the one in flight. Two buffers overlap rendering with one flush,
+/// three let another frame queue up behind it.
+///
+/// The thread keeps its own copy of what the panel should show. Frames are copied into it
+/// as they arrive, so a `RefreshConfig` can batch damage across several frames for panels
+/// that refresh slowly, like e-paper, and still flush from a complete picture.
+pub struct Presenter {
+    queue: Arc<(Mutex<Queue>, Condvar)>,
+    slots: usize,
+    policy: FramePolicy,
+    /// Accessibility filter applied as frames are copied out, before color correction
+    filter: DisplayFilter,
+    /// Color correction applied as frames are copied out of the canvas
+    transform: Option<OutputTransform>,
+    /// Grayscale conversion for mono panels, applied after color correction
+    mono: Option<MonoConverter>,
+    /// Damage from frames that were dropped before reaching the panel, sent with the next
+    carried: Vec<DamageRect>,
+    carried_mode: RefreshMode,
+    carried_tags: Vec<u64>,
+    has_underlay: bool,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Presenter {
+    pub fn new(
+        mut display: Box<dyn Panel>,
+        buffers: usize,
+        policy: FramePolicy,
+        transform: Option<OutputTransform>,
+        mono: Option<MonoConverter>,
+        refresh: RefreshConfig,
+    ) -> Self {
+        let queue = Arc::new((Mutex::new(Queue::default()), Condvar::new()));
+        let worker_queue = queue.clone();
+
+        let mut shadow = Canvas::new(display.width(), display.height());
+        let mut scheduler = RefreshScheduler::new(refresh, display.width(), display.height());
+        let has_underlay = display.has_underlay();
+
+        let thread = thread::spawn(move || {
+            let (lock, cvar) = &*worker_queue;
+            // Latency tags of frames copied into the shadow but not flushed yet
+            let mut latency_tags = Vec::new();
+            // Only the first underlay failure is logged, as frames keep coming
+            let mut underlay_failed = false;
+
+            loop {
+                let job = {
+                    let mut queue = lock.lock().unwrap();
+
+                    loop {
+                        if queue.shutdown {
+                            return;
+                        }
+
+                        if !queue.pending.is_empty() {
+                            while let Some(frame) = queue.pending.pop_front() {
+                                copy_rects(&frame.canvas, &mut shadow, &frame.damage);
+                                scheduler.add_damage(&frame.damage, frame.mode);
+                                latency_tags.extend(frame.latency_tags);
+                                queue.free.push(frame.canvas);
+                            }
+
+                            cvar.notify_all();
+                        }
+
+                        if let Some((frame, rect)) = queue.underlay.take() {
+                            break Job::Underlay(frame, rect);
+                        }
+
+                        let now = Instant::now();
+
+                        if let Some((mode, damage)) = scheduler.poll(now) {
+                            queue.flushing = true;
+                            break Job::Flush(mode, damage, queue.latency.clone());
+                        }
+
+                        queue = match scheduler.next_due() {
+                            Some(due) => {
+                                cvar.wait_timeout(queue, due.saturating_duration_since(now))
+                                    .unwrap()
+                                    .0
+                            }
+                            None => cvar.wait(queue).unwrap(),
+                        };
+                    }
+                };
+
+                let (mode, damage, latency) = match job {
+                    Job::Flush(mode, damage, latency) => (mode, damage, latency),
+                    Job::Underlay(frame, rect) => {
+                        if let Err(e) = display.set_underlay(&frame, &rect)
+                            && !underlay_failed
+                        {
+                            println!("Warning: {}", e);
+                            underlay_failed = true;
+                        }
+                        continue;
+                    }
+                };
+
+                display.present(&shadow, &damage, mode);
+
+                if let Some(latency) = latency {
+                    let flushed = SystemTime::now();
+
+                    for tag in latency_tags.drain(..) {
+                        latency.flushed(tag, flushed);
+                    }
+                }
+
+                let mut queue = lock.lock().unwrap();
+                queue.flushing = false;
+                cvar.notify_all();
+            }
+        });
+
+        Presenter {
+            queue,
+            slots: buffers.max(2) - 1,
+            policy,
+            filter: DisplayFilter::None,
+            transform,
+            mono,
+            carried: Vec::new(),
+            carried_mode: RefreshMode::Fast,
+            carried_tags: Vec::new(),
+            has_underlay,
+            thread: Some(thread),
+        }
+    }
+
+    /// Whether a dropped frame's changes still need to reach the panel. Submit again even
+    /// if nothing was rendered, or the display will be left showing a stale frame.
+    pub fn has_carried_damage(&self) -> bool {
+        !self.carried.is_empty()
+    }
+
+    /// Whether the panel has a hardware plane beneath the UI for `set_underlay`.
+    pub fn has_underlay(&self) -> bool {
+        self.has_underlay
+    }
+
+    /// Show `frame` on the plane beneath the UI, scaled into `rect`, typically a
+    /// `<surface>` node's geometry from `Renderer::surfaces()`. Never waits: a frame the
+    /// panel hasn't taken yet is replaced.
+    pub fn set_underlay(&mut self, frame: VideoFrame, rect: SurfaceRect) {
+        let (lock, cvar) = &*self.queue;
+        lock.lock().unwrap().underlay = Some((frame, rect));
+        cvar.notify_all();
+    }
+
+    /// Filter frames submitted from now on, e.g. with `Renderer::display_filter`.
+    pub fn set_filter(&mut self, filter: DisplayFilter) {
+        self.filter = filter;
+    }
+
+    /// Report when frames tagged by `Renderer::latency_tag` reach the panel.
+    pub fn set_latency(&mut self, latency: Option<LatencyRecorder>) {
+        self.queue.0.lock().unwrap().latency = latency;
+    }
+
+    /// Queue a copy of the canvas to be flushed, filtered, color corrected if calibrated
+    /// and converted to gray for mono panels. Under `FramePolicy::Block` this waits while every buffer is
+    /// busy; under `DropOldest` it never waits. `latency_tag` is reported flushed once
+    /// the frame reaches the panel.
+    pub fn submit(
+        &mut self,
+        canvas: &Canvas,
+        damage: &[DamageRect],
+        mode: RefreshMode,
+        latency_tag: Option<u64>,
+    ) {
+        let mut damage: Vec<DamageRect> = self
+            .carried
+            .drain(..)
+            .chain(damage.iter().copied())
+            .collect();
+        let mut latency_tags: Vec<u64> = self.carried_tags.drain(..).chain(latency_tag).collect();
+
+        if damage.is_empty() {
+            return;
+        }
+
+        // Fast is the weakest mode, so combining with it keeps the other
+        let mut mode = std::mem::replace(&mut self.carried_mode, RefreshMode::Fast).combine(mode);
+
+        let (lock, cvar) = &*self.queue;
+        let mut queue = lock.lock().unwrap();
+
+        while queue.pending.len() + queue.flushing as usize >= self.slots {
+            match self.policy {
+                FramePolicy::Block => queue = cvar.wait(queue).unwrap(),
+                FramePolicy::DropOldest => match queue.pending.pop_front() {
+                    Some(oldest) => {
+                        damage.extend(oldest.damage);
+                        mode = mode.combine(oldest.mode);
+                        latency_tags.extend(oldest.latency_tags);
+                        queue.free.push(oldest.canvas);
+                    }
+                    // Only the frame being flushed is left, so this one can't be queued
+                    // yet. Its changes go out with the next submit.
+                    None => {
+                        merge_rects(&mut damage, MAX_DAMAGE_RECTS);
+                        self.carried = damage;
+                        self.carried_mode = mode;
+                        self.carried_tags = latency_tags;
+                        return;
+                    }
+                },
+            }
+        }
+
+        merge_rects(&mut damage, MAX_DAMAGE_RECTS);
+
+        let mut buffer = match queue.free.pop() {
+            Some(buffer) if buffer.width == canvas.width && buffer.height == canvas.height => {
+                buffer
+            }
+            _ => Canvas::new(canvas.width, canvas.height),
+        };
+
+        // Only damaged regions are flushed, so only those need copying
+        for rect in &damage {
+            for y in rect.y..rect.y + rect.height {
+                let start = (y * canvas.width + rect.x) as usize;
+                let end = start + rect.width as usize;
+                let src = &canvas.pixels[start..end];
+                let dst = &mut buffer.pixels[start..end];
+
+                match &self.transform {
+                    Some(transform) if self.filter.is_none() => transform.apply_slice(src, dst),
+                    Some(transform) => {
+                        for (dst, &src) in dst.iter_mut().zip(src) {
+                            *dst = transform.apply(self.filter.apply(src));
+                        }
+                    }
+                    None => {
+                        dst.copy_from_slice(src);
+                        self.filter.apply_slice(dst);
+                    }
+                }
+            }
+
+            if let Some(mono) = &self.mono {
+                mono.apply_xrgb(&mut buffer.pixels, canvas.width, *rect);
+            }
+        }
+
+        queue.pending.push_back(Frame {
+            canvas: buffer,
+            damage,
+            mode,
+            latency_tags,
+        });
+        cvar.notify_all();
+    }
+}
+
+fn copy_rects(src: &Canvas, dst: &mut Canvas, damage: &[DamageRect]) {
+    for rect in damage {
+        for y in rect.y..rect.y + rect.height {
+            let start = (y * src.width + rect.x) as usize;
+            let end = start + rect.width as usize;
+            dst.pixels[start..end].copy_from_slice(&src.pixels[start..end]);
+        }
+    }
+}
+
+impl Drop for Presenter {
+    fn drop(&mut self) {
+        let (lock, cvar) = &*self.queue;
+        lock.lock().unwrap().shutdown = true;
+        cvar.notify_all();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use std::time::Duration;
+
+use juice::canvas::Canvas;
+use juice::memory::PressureMonitor;
+use juice::mono::RefreshMode;
+use juice::sandbox::{SandboxClient, SandboxConfig, SandboxEvent, SandboxHost};
+
+use crate::config::HostConfig;
+use crate::input::{InputDevice, TouchEvent, WheelDevice};
+use crate::light::AmbientLightSensor;
+use crate::present::Presenter;
+
+/// Drive the display and input, running the app in a child process: this executable
+/// again, which finds the sandbox with `SandboxClient::connect` and runs `run_app`.
+pub async fn run_host(
+    config: &HostConfig,
+    sandbox: &SandboxConfig,
+    mut presenter: Presenter,
+    width: u32,
+    height: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(feature = "hotreload")]
+    let dev = juice_dev::DevChannel::connect();
+
+    let memory_limit = sandbox.memory_limit;
+
+    let command = move || {
+        let mut command = Command::new(std::env::current_exe().unwrap_or_else(|_| "juice".into()));
+        command.args(std::env::args_os().skip(1));
+
+        // SAFETY: only async-signal-safe calls between fork and exec
+        unsafe {
+            command.pre_exec(move || {
+                // An untrusted bundle can't regain privileges through setuid binaries
+                libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0);
+
+                if let Some(limit) = memory_limit {
+                    let limit = libc::rlimit {
+                        rlim_cur: limit as libc::rlim_t,
+                        rlim_max: limit as libc::rlim_t,
+                    };
+
+                    if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+
+                Ok(())
+            });
+        }
+
+        command
+    };
+
+    let mut host = SandboxHost::spawn(command, sandbox, width, height).map_err(|e| e.message)?;
+    let mut canvas = Canvas::new(width, height);
+
+    println!("Running the app sandboxed in a child process");
+
+    let mut touch_device = InputDevice::get_touchscreen_device();
+
+    if touch_device.is_none() {
+        println!("Warning: No touchscreen device found");
+    }
+
+    // Wheels scroll what's under the last touch, as in the single-app loop
+    let mut wheel_device = WheelDevice::get_wheel_device();
+    let mut pointer_position = None;
+
+    // Auto theme switching happens in the app process, from the readings sent to it
+    let mut light_sensor = AmbientLightSensor::find();
+    let mut light_interval = tokio::time::interval(Duration::from_secs(1));
+
+    let mut frame_interval = tokio::time::interval(Duration::from_millis(16));
+
+    if config.latency.unwrap_or(false) {
+        println!("Warning: latency isn't measured in sandbox mode");
+    }
+
+    loop {
+        tokio::select! {
+            _ = frame_interval.tick() => {}
+
+            _ = light_interval.tick(), if light_sensor.is_some() => {
+                if let Some(lux) = light_sensor.as_mut().and_then(|sensor| sensor.poll()) {
+                    host.send(SandboxEvent::AmbientLight { lux });
+                }
+            }
+
+            notches = async { wheel_device.as_mut().unwrap().next_notches().await }, if wheel_device.is_some() => {
+                let (horizontal, vertical) = notches;
+
+                match pointer_position {
+                    Some((x, y)) => host.send(SandboxEvent::Wheel {
+                        x,
+                        y,
+                        notches_x: horizontal as f32,
+                        notches_y: vertical as f32,
+                    }),
+                    None => {
+                        host.send(SandboxEvent::Encoder { notches: vertical as f32 });
+
+                        if horizontal != 0 {
+                            host.send(SandboxEvent::Wheel {
+                                x: width as f32 / 2.0,
+                                y: height as f32 / 2.0,
+                                notches_x: horizontal as f32,
+                                notches_y: 0.0,
+                            });
+                        }
+                    }
+                }
+            }
+
+            event = async { touch_device.as_mut().unwrap().next_event().await }, if touch_device.is_some() => {
+                let pressed = touch_device.as_ref().is_some_and(|device| device.touch_state.pressed);
+                pointer_position = Some(event.position());
+
+                let event = match event {
+                    TouchEvent::PressIn { x, y, pointer } => Some(("PressIn", x, y, pointer)),
+                    TouchEvent::PressOut { x, y, pointer } => Some(("PressOut", x, y, pointer)),
+                    TouchEvent::Move { x, y, pointer } if pressed => Some(("PressMove", x, y, pointer)),
+                    TouchEvent::Move { .. } => None,
+                };
+
+                if let Some((name, x, y, pointer)) = event {
+                    host.send(SandboxEvent::Pointer {
+                        name: name.to_string(),
+                        x: x as f32,
+                        y: y as f32,
+                        pointer,
+                    });
+                }
+            }
+        }
+
+        presenter.set_filter(host.display_filter());
+
+        if let Some(frame) = host.poll(&mut canvas) {
+            presenter.submit(&canvas, &frame.damage, frame.refresh_mode, None);
+        } else if presenter.has_carried_damage() {
+            presenter.submit(&canvas, &[], RefreshMode::Fast, None);
+        }
+
+        // Dev requests need the app's renderer, which is in the other process, so only
+        // bundles are handled
+        #[cfg(feature = "hotreload")]
+        if let Some(new_bundle) = dev.try_recv_bundle() {
+            println!("[dev] reloading bundle in the app process...");
+            host.send(SandboxEvent::Reload(new_bundle));
+        }
+    }
+}
+
+/// Run the app in the child process, drawing into frames sent to the display process
+/// and dispatching the events it forwards. Returns once the display process has gone.
+pub async fn run_app(
+    config: HostConfig,
+    mut client: SandboxClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut renderer = crate::start_renderer(
+        &config,
+        client.width,
+        client.height,
+        &crate::data_dir(),
+        &crate::app_bundle(),
+        config.app_permissions(),
+        Vec::new(),
+    )
+    .await?;
+
+    renderer.set_asset_dir(Some(crate::asset_dir()));
+
+    // The input devices are the display process's, so diagnostics can't list them
+    renderer.enable_diagnostics(Vec::new());
+
+    // The app process is the one with caches to trim
+    let mut pressure_monitor = if config.memory_pressure.unwrap_or(true) {
+        PressureMonitor::open()
+    } else {
+        None
+    };
+    let mut pressure_interval = tokio::time::interval(Duration::from_secs(1));
+
+    let mut frame_interval = tokio::time::interval(Duration::from_millis(16));
+
+    loop {
+        tokio::select! {
+            _ = frame_interval.tick() => {}
+
+            _ = pressure_interval.tick(), if pressure_monitor.is_some() => {
+                if let Some(level) = pressure_monitor.as_mut().and_then(|monitor| monitor.poll()) {
+                    renderer.trim_memory(level).await;
+                }
+            }
+        }
+
+        while let Some(event) = client.try_recv() {
+            event.dispatch(&mut renderer).await;
+        }
+
+        renderer.tick().await;
+
+        let rendered = renderer.render();
+
+        if let Err(e) = client.present(&renderer, rendered) {
+            println!("[sandbox] {}", e.message);
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use juice::renderer::Renderer;
+use juice::video::{PixelFormat, VideoFrame};
+use serde::Deserialize;
+
+use crate::present::Presenter;
+
+/// Raw frames for a `<surface>`, read from a FIFO or device that a camera or decoder
+/// process writes them to back to back.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SurfaceSource {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    /// `rgb888`, `rgba8888`, `xrgb8888` or `yuyv`
+    pub format: PixelFormat,
+}
+
+struct Feed {
+    name: String,
+    /// The newest frame not shown yet
+    latest: Arc<Mutex<Option<VideoFrame>>>,
+}
+
+/// Reads each configured source on its own thread, and shows the newest frames under the
+/// `<surface>` nodes named after them.
+pub struct SurfaceFeeds {
+    feeds: Vec<Feed>,
+}
+
+impl SurfaceFeeds {
+    pub fn start(sources: &HashMap<String, SurfaceSource>) -> Self {
+        let feeds = sources
+            .iter()
+            .map(|(name, source)| {
+                let latest = Arc::new(Mutex::new(None));
+                let reader_latest = latest.clone();
+                let source = source.clone();
+
+                thread::spawn(move || read_frames(&source, &reader_latest));
+
+                Feed {
+                    name: name.clone(),
+                    latest,
+                }
+            })
+            .collect();
+
+        SurfaceFeeds { feeds }
+    }
+
+    /// Put new frames on the underlay plane at the geometry of the surface named after
+    /// their source. Displays without an underlay plane get them drawn into the UI
+    /// instead, by any `<video>` node with that `src`.
+    pub fn update(&self, renderer: &mut Renderer, presenter: &mut Presenter) {
+        for feed in &self.feeds {
+            let Some(frame) = feed.latest.lock().unwrap().take() else {
+                continue;
+            };
+
+            if !presenter.has_underlay() {
+                renderer.set_video_frame(&feed.name, frame);
+                continue;
+            }
+
+            if let Some(rect) = renderer
+                .surfaces()
+                .iter()
+                .find(|surface| surface.name == feed.name)
+            {
+                presenter.set_underlay(frame, rect.clone());
+            }
+        }
+    }
+}
+
+/// Read whole frames until the writer goes away, then open the source again for the next.
+fn read_frames(source: &SurfaceSource, latest: &Mutex<Option<VideoFrame>>) {
+    let Some(size) = (source.width as usize)
+        .checked_mul(source.height as usize)
+        .and_then(|count| count.checked_mul(source.format.bytes_per_pixel()))
+    else {
+        println!("Warning: surface frames from {} are too big", source.path);
+        return;
+    };
+
+    let mut buffer = vec![0; size];
+    let mut warned = false;
+
+    loop {
+        match File::open(&source.path) {
+            Ok(mut file) => {
+                warned = false;
+
+                while file.read_exact(&mut buffer).is_ok() {
+                    if let Some(frame) =
+                        VideoFrame::new(source.width, source.height, source.format, &buffer)
+                    {
+                        *latest.lock().unwrap() = Some(frame);
+                    }
+                }
+            }
+            Err(e) if !warned => {
+                println!("Warning: can't open surface source {}: {}", source.path, e);
+                warned = true;
+            }
+            Err(_) => {}
+        }
+
+        thread::sleep(Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,375 @@
+// Renders each page of the example gallery (packages/gallery, built by `npm run build`
+// to dist/gallery.js) in deterministic mode, to keep snapshots of them and time them.
+// Pages show every node type, so a change to drawing anywhere shows up as a changed
+// snapshot, and a slower page as a slower mount or paint.
+//
+//     cargo run --release -p gallery -- --update target/gallery
+//     cargo run --release -p gallery -- --check target/gallery --json timings.json
+//
+// No baseline is committed, as snapshots depend on the fonts and build they were drawn
+// with. Take one with --update on the commit to compare against, then --check the change.
+//
+// Options:
+//     --bundle <path>       the built gallery (default dist/gallery.js)
+//     --update <dir>        write each page's snapshot to <dir>/<page>.png
+//     --check <dir>         compare each page with <dir>/<page>.png, exiting with 1 if any
+//                           differ, and writing what was drawn instead to <page>.actual.png.
+//                           Pages without a snapshot are reported but don't fail
+//     --tolerance <percent> share of pixels allowed to differ in a check (default 0)
+//     --time <ms>           mock time each page runs before its snapshot, so animations
+//                           are caught part way through (default 1000)
+//     --iterations <n>      mounts and paints of each page to time, reporting the median
+//                           (default 10)
+//     --size <w>x<h>        canvas size (default 800x800, the simulator's)
+//     --json <path>         also write the timings as JSON, for tracking regressions
+//
+// Stages:
+//     mount   switching to the page from another: rendering it in Preact, the native
+//             DOM calls and layout
+//     paint   drawing the whole page again
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use fontdue::{Font, FontSettings};
+use image::RgbImage;
+use juice::canvas::Canvas;
+use juice::engine::Deterministic;
+use juice::inherited_style::InheritedStyle;
+use juice::renderer::Renderer;
+use rquickjs::CatchResultExt;
+use serde::Serialize;
+
+const FONT: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../packages/app/src/fonts/Roboto-Regular.ttf"
+));
+
+/// The same seed and start time on every run, so snapshots only change with the code.
+const DETERMINISTIC: Deterministic = Deterministic {
+    seed: 42,
+    start_time: 1.7e12,
+};
+
+#[derive(Clone, Copy)]
+enum Mode {
+    /// Only time the pages
+    Time,
+    Update,
+    Check,
+}
+
+struct Options {
+    bundle: PathBuf,
+    mode: Mode,
+    snapshots: PathBuf,
+    tolerance: f64,
+    time: u64,
+    iterations: usize,
+    width: u32,
+    height: u32,
+}
+
+/// Median milliseconds of each stage for a page, and how its snapshot compared.
+#[derive(Debug, Serialize)]
+struct PageResult {
+    page: String,
+    mount: f64,
+    paint: f64,
+    /// `written`, `same`, `differs` or `missing`; absent when only timing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snapshot: Option<&'static str>,
+    /// Share of pixels differing from the snapshot, as a percentage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    differing: Option<f64>,
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let mut options = Options {
+        bundle: PathBuf::from("dist/gallery.js"),
+        mode: Mode::Time,
+        snapshots: PathBuf::new(),
+        tolerance: 0.0,
+        time: 1000,
+        iterations: 10,
+        width: 800,
+        height: 800,
+    };
+    let mut json_path = None;
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_default();
+
+        match arg.as_str() {
+            "--bundle" => options.bundle = PathBuf::from(value),
+            "--update" => {
+                options.mode = Mode::Update;
+                options.snapshots = PathBuf::from(value);
+            }
+            "--check" => {
+                options.mode = Mode::Check;
+                options.snapshots = PathBuf::from(value);
+            }
+            "--tolerance" => options.tolerance = value.parse().unwrap_or(0.0),
+            "--time" => options.time = value.parse().unwrap_or(1000),
+            "--iterations" => options.iterations = value.parse().unwrap_or(10).max(1),
+            "--size" => match value.split_once('x').map(|(w, h)| (w.parse(), h.parse())) {
+                Some((Ok(width), Ok(height))) => {
+                    options.width = width;
+                    options.height = height;
+                }
+                _ => fail(&format!("Can't read the size {}, e.g. 800x480", value)),
+            },
+            "--json" => json_path = Some(value),
+            _ => fail(&format!("Unknown option {}", arg)),
+        }
+    }
+
+    let bundle = match std::fs::read_to_string(&options.bundle) {
+        Ok(bundle) => bundle,
+        Err(err) => fail(&format!(
+            "Can't read {}: {}. Run 'npm run build' first",
+            options.bundle.display(),
+            err
+        )),
+    };
+
+    if let Mode::Update = options.mode
+        && let Err(err) = std::fs::create_dir_all(&options.snapshots)
+    {
+        fail(&format!(
+            "Can't create {}: {}",
+            options.snapshots.display(),
+            err
+        ));
+    }
+
+    let mut renderer = new_renderer(&options, &bundle).await;
+    let pages = match eval::<Vec<String>>(&renderer, "gallery.pages").await {
+        Ok(pages) if !pages.is_empty() => pages,
+        _ => fail(&format!(
+            "{} isn't a gallery bundle: it has no gallery.pages",
+            options.bundle.display()
+        )),
+    };
+
+    let mut results = Vec::new();
+
+    for (index, page) in pages.iter().enumerate() {
+        // Each page starts from a fresh engine and clock, so its snapshot doesn't depend
+        // on the pages before it
+        if index > 0 {
+            renderer = new_renderer(&options, &bundle).await;
+        }
+
+        show(&renderer, page).await;
+        renderer.advance_time(options.time).await;
+        renderer.render();
+
+        let (snapshot, differing) = match options.mode {
+            Mode::Time => (None, None),
+            Mode::Update => {
+                save(&renderer.canvas, &snapshot_path(&options, page, ""));
+                (Some("written"), None)
+            }
+            Mode::Check => check(&renderer.canvas, &options, page),
+        };
+
+        let (mount, paint) = time_page(&mut renderer, &pages, index, options.iterations).await;
+
+        results.push(PageResult {
+            page: page.clone(),
+            mount,
+            paint,
+            snapshot,
+            differing,
+        });
+    }
+
+    print_report(&results, &options);
+
+    if let Some(path) = json_path {
+        let json = serde_json::to_string_pretty(&results).unwrap();
+
+        match std::fs::write(&path, json) {
+            Ok(()) => println!("\nWrote {}", path),
+            Err(err) => eprintln!("Can't write {}: {}", path, err),
+        }
+    }
+
+    let failed = results
+        .iter()
+        .any(|result| result.snapshot == Some("differs"));
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+async fn new_renderer(options: &Options, bundle: &str) -> Renderer {
+    let font = Font::from_bytes(FONT, FontSettings::default()).unwrap();
+    let fonts = HashMap::from([("Roboto-Regular".to_string(), font)]);
+    let mut renderer = Renderer::new(
+        Canvas::new(options.width, options.height),
+        fonts,
+        InheritedStyle::new("Roboto-Regular"),
+        vec![],
+    )
+    .await;
+
+    renderer.set_deterministic(Some(DETERMINISTIC)).await;
+    renderer.engine.load(bundle).await;
+    renderer.advance_time(0).await;
+    renderer
+}
+
+async fn eval<T: for<'js> rquickjs::FromJs<'js>>(
+    renderer: &Renderer,
+    script: &str,
+) -> Result<T, String> {
+    renderer
+        .engine
+        .with_context(|ctx| {
+            ctx.eval::<T, _>(script)
+                .catch(&ctx)
+                .map_err(|err| err.to_string())
+        })
+        .await
+}
+
+/// Switch to `page` and let Preact's effects run.
+async fn show(renderer: &Renderer, page: &str) {
+    let script = format!("gallery.show({})", serde_json::to_string(page).unwrap());
+
+    match eval::<bool>(renderer, &script).await {
+        Ok(true) => {}
+        Ok(false) => fail(&format!("The gallery has no page {}", page)),
+        Err(err) => fail(&format!("Showing {} failed: {}", page, err)),
+    }
+
+    renderer.engine.tick().await;
+}
+
+/// Median mount and paint times of the page at `index`, switching away to a neighbour
+/// between mounts.
+async fn time_page(
+    renderer: &mut Renderer,
+    pages: &[String],
+    index: usize,
+    iterations: usize,
+) -> (f64, f64) {
+    let away = &pages[(index + 1) % pages.len()];
+    let mut mounts = Vec::new();
+    let mut paints = Vec::new();
+
+    for _ in 0..iterations {
+        if away != &pages[index] {
+            show(renderer, away).await;
+            renderer.render();
+        }
+
+        let start = Instant::now();
+        show(renderer, &pages[index]).await;
+        mounts.push(millis(start.elapsed()));
+
+        renderer.render();
+        let root = renderer.dom.borrow().root_node_id;
+
+        if let Some(root) = root {
+            renderer.invalidate(root);
+        }
+
+        let start = Instant::now();
+        renderer.render();
+        paints.push(millis(start.elapsed()));
+    }
+
+    (median(&mut mounts), median(&mut paints))
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    values[values.len() / 2]
+}
+
+fn snapshot_path(options: &Options, page: &str, suffix: &str) -> PathBuf {
+    options.snapshots.join(format!("{}{}.png", page, suffix))
+}
+
+fn to_image(canvas: &Canvas) -> RgbImage {
+    RgbImage::from_fn(canvas.width, canvas.height, |x, y| {
+        let [_, r, g, b] = canvas.pixels[(y * canvas.width + x) as usize].to_be_bytes();
+        image::Rgb([r, g, b])
+    })
+}
+
+fn save(canvas: &Canvas, path: &Path) {
+    if let Err(err) = to_image(canvas).save(path) {
+        fail(&format!("Can't write {}: {}", path.display(), err));
+    }
+}
+
+/// Compare what's drawn with the page's snapshot, keeping what was drawn next to it if
+/// they differ by more than the tolerance.
+fn check(canvas: &Canvas, options: &Options, page: &str) -> (Option<&'static str>, Option<f64>) {
+    let actual = to_image(canvas);
+    // From an earlier failed check
+    let _ = std::fs::remove_file(snapshot_path(options, page, ".actual"));
+
+    let expected = match image::open(snapshot_path(options, page, "")) {
+        Ok(expected) => expected.to_rgb8(),
+        Err(_) => return (Some("missing"), None),
+    };
+
+    let differing = if expected.dimensions() == actual.dimensions() {
+        let count = expected
+            .pixels()
+            .zip(actual.pixels())
+            .filter(|(expected, actual)| expected != actual)
+            .count();
+
+        count as f64 * 100.0 / (actual.width() * actual.height()) as f64
+    } else {
+        100.0
+    };
+
+    if differing > options.tolerance {
+        save(canvas, &snapshot_path(options, page, ".actual"));
+        (Some("differs"), Some(differing))
+    } else {
+        (Some("same"), Some(differing))
+    }
+}
+
+fn print_report(results: &[PageResult], options: &Options) {
+    println!(
+        "Median of {} runs on a {}x{} canvas. Times in ms.\n",
+        options.iterations, options.width, options.height
+    );
+    println!("{:<12}  {:>8}  {:>8}  snapshot", "page", "mount", "paint");
+
+    for result in results {
+        let snapshot = match (result.snapshot, result.differing) {
+            (Some("differs"), Some(differing)) => format!("differs ({:.2}% of pixels)", differing),
+            (Some(snapshot), _) => snapshot.to_string(),
+            (None, _) => String::new(),
+        };
+
+        println!(
+            "{:<12}  {:>8.2}  {:>8.2}  {}",
+            result.page, result.mount, result.paint, snapshot
+        );
+    }
+}
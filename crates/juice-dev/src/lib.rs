@@ -1,6 +1,126 @@
+use std::io::ErrorKind;
 use std::sync::mpsc;
 use std::time::Duration;
 
+use tungstenite::stream::MaybeTlsStream;
+
+/// A request from the dev server for the app's state, answered with the matching
+/// `DevChannel` reply.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DevRequest {
+    /// `{"type":"animations"}`, answered with `reply_animations`
+    Animations,
+}
+
+impl DevRequest {
+    /// Requests are small JSON objects, where bundles are JS.
+    fn parse(message: &str) -> Option<Self> {
+        let message: serde_json::Value = serde_json::from_str(message).ok()?;
+
+        match message.get("type")?.as_str()? {
+            "animations" => Some(DevRequest::Animations),
+            _ => None,
+        }
+    }
+}
+
+/// Both directions of the connection to the dev server: new bundles and requests coming
+/// in, and replies going out. Poll it each frame in your event loop.
+pub struct DevChannel {
+    bundles: mpsc::Receiver<String>,
+    requests: mpsc::Receiver<DevRequest>,
+    replies: mpsc::Sender<String>,
+}
+
+impl DevChannel {
+    /// Check for a `DEV_SERVER` environment variable and, if set, spawn a background
+    /// thread that connects to the WebSocket dev server. If it isn't set, the channel
+    /// never produces anything and replies are dropped.
+    pub fn connect() -> Self {
+        let (bundle_tx, bundles) = mpsc::channel::<String>();
+        let (request_tx, requests) = mpsc::channel::<DevRequest>();
+        let (replies, reply_rx) = mpsc::channel::<String>();
+
+        if let Ok(dev_url) = std::env::var("DEV_SERVER") {
+            std::thread::spawn(move || {
+                loop {
+                    match tungstenite::connect(&dev_url) {
+                        Ok((mut socket, _)) => {
+                            println!("[dev] connected to {}", dev_url);
+
+                            // Wake up regularly to send replies, since reads block
+                            if let MaybeTlsStream::Plain(stream) = socket.get_ref() {
+                                let _ = stream.set_read_timeout(Some(Duration::from_millis(50)));
+                            }
+
+                            loop {
+                                // A failed send shows up as a failed read below
+                                while let Ok(reply) = reply_rx.try_recv() {
+                                    let _ = socket.send(tungstenite::Message::text(reply));
+                                }
+
+                                match socket.read() {
+                                    Ok(tungstenite::Message::Text(message)) => {
+                                        match DevRequest::parse(&message) {
+                                            // Unanswered under `spawn_reload_listener`
+                                            Some(request) => {
+                                                let _ = request_tx.send(request);
+                                            }
+                                            None => {
+                                                if bundle_tx.send(message.to_string()).is_err() {
+                                                    return;
+                                                }
+                                            }
+                                        }
+                                    }
+                                    Ok(tungstenite::Message::Close(_)) => break,
+                                    Err(tungstenite::Error::Io(e))
+                                        if matches!(
+                                            e.kind(),
+                                            ErrorKind::WouldBlock | ErrorKind::TimedOut
+                                        ) => {}
+                                    Err(_) => break,
+                                    _ => {}
+                                }
+                            }
+                            println!("[dev] disconnected, reconnecting...");
+                        }
+                        Err(e) => {
+                            eprintln!("[dev] connect failed: {e}, retrying in 1s");
+                        }
+                    }
+                    std::thread::sleep(Duration::from_secs(1));
+                }
+            });
+        }
+
+        DevChannel {
+            bundles,
+            requests,
+            replies,
+        }
+    }
+
+    /// A new bundle, if one arrived since the last call. When one does, drop the old
+    /// Engine, recreate it, and boot with the new bundle.
+    pub fn try_recv_bundle(&self) -> Option<String> {
+        self.bundles.try_recv().ok()
+    }
+
+    /// A request from the dev server, if one arrived since the last call.
+    pub fn try_recv_request(&self) -> Option<DevRequest> {
+        self.requests.try_recv().ok()
+    }
+
+    /// Answer `DevRequest::Animations` with `Renderer::inspect_animations`.
+    pub fn reply_animations(&self, animations: &str) {
+        let _ = self.replies.send(format!(
+            "{{\"type\":\"animations\",\"animations\":{}}}",
+            animations
+        ));
+    }
+}
+
 /// Check for a `DEV_SERVER` environment variable and, if set, spawn a background
 /// thread that connects to the WebSocket dev server and receives new bundles.
 ///
@@ -9,36 +129,7 @@ use std::time::Duration;
 /// boot with the new bundle.
 ///
 /// If `DEV_SERVER` is not set, returns a receiver that never produces a message.
+/// Requests from the dev server are ignored; use `DevChannel` to answer them.
 pub fn spawn_reload_listener() -> mpsc::Receiver<String> {
-    let (tx, rx) = mpsc::channel::<String>();
-
-    if let Ok(dev_url) = std::env::var("DEV_SERVER") {
-        std::thread::spawn(move || {
-            loop {
-                match tungstenite::connect(&dev_url) {
-                    Ok((mut socket, _)) => {
-                        println!("[dev] connected to {}", dev_url);
-                        loop {
-                            match socket.read() {
-                                Ok(tungstenite::Message::Text(bundle)) => {
-                                    if tx.send(bundle.into()).is_err() {
-                                        return;
-                                    }
-                                }
-                                Ok(tungstenite::Message::Close(_)) | Err(_) => break,
-                                _ => {}
-                            }
-                        }
-                        println!("[dev] disconnected, reconnecting...");
-                    }
-                    Err(e) => {
-                        eprintln!("[dev] connect failed: {e}, retrying in 1s");
-                    }
-                }
-                std::thread::sleep(Duration::from_secs(1));
-            }
-        });
-    }
-
-    rx
+    DevChannel::connect().bundles
 }
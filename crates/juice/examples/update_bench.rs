@@ -0,0 +1,373 @@
+// Times each way a tree can get from JS into the renderer, end to end: building it in JS,
+// getting it across to native code, layout and paint. Trees are a list screen of rows,
+// each a box holding a label and a badge, so four nodes a row.
+//
+//     cargo run --release -p juice --example update_bench -- --nodes 100,1000,5000
+//
+// Options:
+//     --nodes <n,...>      tree sizes to try (default 100,1000,5000)
+//     --iterations <n>     runs of each, reporting the median (default 20)
+//     --changed <percent>  share of badges whose text an update changes (default 10)
+//     --json <path>        also write the report as JSON, for tracking regressions
+//
+// Paths:
+//     calls   one native call per node and prop through the `dom` global, as the
+//             reconciler does. Updates only make the calls for what changed.
+//     json    the whole tree `JSON.stringify`d and parsed natively with
+//             `Dom::try_from_json_bytes`. Updates send the whole tree again.
+//     binary  an encoded batch of updates. Not supported by this build, as
+//             `capabilities.binaryProtocol` says, so it's reported and skipped.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use fontdue::{Font, FontSettings};
+use juice::canvas::Canvas;
+use juice::capabilities::Capabilities;
+use juice::dom::{Dom, DomLimits};
+use juice::font_fallback::FontRegistry;
+use juice::inherited_style::InheritedStyle;
+use juice::renderer::Renderer;
+use serde::Serialize;
+
+const FONT: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../packages/app/src/fonts/Roboto-Regular.ttf"
+));
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 480;
+
+/// Builds trees both ways, keeping the text nodes so updates can change them.
+const SETUP_JS: &str = r##"
+globalThis.bench = {
+  badges: [],
+
+  rowStyle(i) {
+    return {
+      flexDirection: "row",
+      justifyContent: "space-between",
+      padding: 4,
+      background: i % 2 ? "#202020" : "#303030",
+    };
+  },
+
+  buildCalls(rows) {
+    const doc = dom.createElement("document");
+    dom.setStyleNumber(doc, "width", 800);
+    dom.setStyleString(doc, "flexDirection", "column");
+    this.badges = [];
+
+    for (let i = 0; i < rows; i++) {
+      const row = dom.createElement("box");
+      const style = this.rowStyle(i);
+      dom.setStyleString(row, "flexDirection", style.flexDirection);
+      dom.setStyleString(row, "justifyContent", style.justifyContent);
+      dom.setStyleNumber(row, "padding", style.padding);
+      dom.setAttributeString(row, "background", style.background);
+      dom.appendChild(row, dom.createTextNode("Item " + i));
+
+      const badge = dom.createElement("box");
+      dom.setStyleNumber(badge, "marginLeft", 8);
+      const count = dom.createTextNode(String(i));
+      dom.appendChild(badge, count);
+      dom.appendChild(row, badge);
+      dom.appendChild(doc, row);
+      this.badges.push(count);
+    }
+  },
+
+  updateCalls(changed, round) {
+    for (let i = 0; i < changed; i++) {
+      const index = (i * 7919 + round) % this.badges.length;
+      dom.setAttributeString(this.badges[index], "text", String(round * 1000 + i));
+    }
+  },
+
+  tree(rows, changed, round) {
+    const counts = [];
+
+    for (let i = 0; i < rows; i++) {
+      counts.push(String(i));
+    }
+
+    for (let i = 0; i < changed; i++) {
+      counts[(i * 7919 + round) % rows] = String(round * 1000 + i);
+    }
+
+    return {
+      tag: "document",
+      style: { width: 800, flexDirection: "column" },
+      children: counts.map((count, i) => ({
+        tag: "box",
+        style: this.rowStyle(i),
+        children: [
+          { tag: "#text", text: "Item " + i },
+          {
+            tag: "box",
+            style: { marginLeft: 8 },
+            children: [{ tag: "#text", text: count }],
+          },
+        ],
+      })),
+    };
+  },
+
+  stringify(rows, changed, round) {
+    return JSON.stringify(this.tree(rows, changed, round));
+  },
+};
+"##;
+
+#[derive(Clone, Copy)]
+struct Options {
+    iterations: usize,
+    changed_percent: usize,
+}
+
+/// Milliseconds spent in each stage of one run.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+struct Stages {
+    /// Building or changing the tree in JS, and for `calls` applying it natively too,
+    /// since the two are interleaved
+    js: f64,
+    /// Parsing what JS sent, for paths that send it in one go
+    native: f64,
+    layout: f64,
+    paint: f64,
+}
+
+impl Stages {
+    fn total(&self) -> f64 {
+        self.js + self.native + self.layout + self.paint
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Result {
+    nodes: usize,
+    path: &'static str,
+    /// `build` for a whole new tree, `update` for changing some of its text
+    change: &'static str,
+    /// Medians over the iterations
+    median: Stages,
+    median_total: f64,
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let mut sizes = vec![100, 1000, 5000];
+    let mut options = Options {
+        iterations: 20,
+        changed_percent: 10,
+    };
+    let mut json_path = None;
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_default();
+
+        match arg.as_str() {
+            "--nodes" => sizes = value.split(',').filter_map(|n| n.parse().ok()).collect(),
+            "--iterations" => options.iterations = value.parse().unwrap_or(20).max(1),
+            "--changed" => options.changed_percent = value.parse().unwrap_or(10).min(100),
+            "--json" => json_path = Some(value),
+            _ => {
+                eprintln!("Unknown option {}", arg);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut results = Vec::new();
+
+    for &nodes in &sizes {
+        let rows = (nodes / 4).max(1);
+
+        for (change, median) in bench_calls(rows, options).await {
+            results.push(result(rows * 4, "calls", change, median));
+        }
+
+        for (change, median) in bench_json(rows, options).await {
+            results.push(result(rows * 4, "json", change, median));
+        }
+    }
+
+    print_report(&results, options);
+
+    if let Some(path) = json_path {
+        let json = serde_json::to_string_pretty(&results).unwrap();
+
+        match std::fs::write(&path, json) {
+            Ok(()) => println!("\nWrote {}", path),
+            Err(err) => eprintln!("Can't write {}: {}", path, err),
+        }
+    }
+}
+
+fn result(nodes: usize, path: &'static str, change: &'static str, median: Stages) -> Result {
+    Result {
+        nodes,
+        path,
+        change,
+        median,
+        median_total: median.total(),
+    }
+}
+
+async fn new_renderer() -> Renderer {
+    let font = Font::from_bytes(FONT, FontSettings::default()).unwrap();
+    let fonts = HashMap::from([("Roboto".to_string(), font)]);
+    let mut renderer = Renderer::new(
+        Canvas::new(WIDTH, HEIGHT),
+        fonts,
+        InheritedStyle::new("Roboto"),
+        vec![],
+    )
+    .await;
+
+    renderer.reload(SETUP_JS).await;
+    renderer
+}
+
+async fn bench_calls(rows: usize, options: Options) -> [(&'static str, Stages); 2] {
+    let changed = changed(rows, options);
+    let mut builds = Vec::new();
+    let mut updates = Vec::new();
+
+    for round in 0..options.iterations {
+        let mut renderer = new_renderer().await;
+
+        builds.push(Stages {
+            js: time_async(renderer.engine.load(&format!("bench.buildCalls({})", rows))).await,
+            native: 0.0,
+            layout: time_async(renderer.engine.load("renderer.update(() => {})")).await,
+            paint: time(|| renderer.render()),
+        });
+
+        let update = format!("bench.updateCalls({}, {})", changed, round + 1);
+
+        updates.push(Stages {
+            js: time_async(renderer.engine.load(&update)).await,
+            native: 0.0,
+            layout: time_async(renderer.engine.load("renderer.update(() => {})")).await,
+            paint: time(|| renderer.render()),
+        });
+    }
+
+    [("build", median(&builds)), ("update", median(&updates))]
+}
+
+async fn bench_json(rows: usize, options: Options) -> [(&'static str, Stages); 2] {
+    let changed = changed(rows, options);
+    let font = Font::from_bytes(FONT, FontSettings::default()).unwrap();
+    let fonts = FontRegistry::new(HashMap::from([("Roboto".to_string(), font)]));
+    let mut builds = Vec::new();
+    let mut updates = Vec::new();
+
+    for round in 0..options.iterations {
+        let mut renderer = new_renderer().await;
+
+        // A build sends the first tree, an update the same tree with some text changed
+        for (changed, stages) in [(0, &mut builds), (changed, &mut updates)] {
+            let script = format!("bench.stringify({}, {}, {})", rows, changed, round + 1);
+            let start = Instant::now();
+            let json = renderer
+                .engine
+                .with_context(|ctx| ctx.eval::<String, _>(script).unwrap())
+                .await;
+            let js = millis(start.elapsed());
+
+            let start = Instant::now();
+            let mut dom = match Dom::try_from_json_bytes(
+                json.as_bytes(),
+                InheritedStyle::new("Roboto"),
+                DomLimits::default(),
+            ) {
+                Ok(dom) => dom,
+                Err(err) => panic!("Can't parse the tree: {}", err.message),
+            };
+            let native = millis(start.elapsed());
+
+            let layout = time(|| dom.compute_layout(&fonts, WIDTH as f32, HEIGHT as f32));
+
+            *renderer.dom.borrow_mut() = dom;
+            let root = renderer.dom.borrow().root_node_id.unwrap();
+            renderer.invalidate(root);
+
+            stages.push(Stages {
+                js,
+                native,
+                layout,
+                paint: time(|| renderer.render()),
+            });
+        }
+    }
+
+    [("build", median(&builds)), ("update", median(&updates))]
+}
+
+fn changed(rows: usize, options: Options) -> usize {
+    (rows * options.changed_percent / 100).max(1)
+}
+
+fn time<R>(f: impl FnOnce() -> R) -> f64 {
+    let start = Instant::now();
+    f();
+    millis(start.elapsed())
+}
+
+async fn time_async(f: impl Future) -> f64 {
+    let start = Instant::now();
+    f.await;
+    millis(start.elapsed())
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Each stage's median on its own, so one slow stage in a run doesn't skew the others.
+fn median(runs: &[Stages]) -> Stages {
+    let stage = |get: fn(&Stages) -> f64| {
+        let mut values: Vec<f64> = runs.iter().map(get).collect();
+        values.sort_by(f64::total_cmp);
+        values[values.len() / 2]
+    };
+
+    Stages {
+        js: stage(|s| s.js),
+        native: stage(|s| s.native),
+        layout: stage(|s| s.layout),
+        paint: stage(|s| s.paint),
+    }
+}
+
+fn print_report(results: &[Result], options: Options) {
+    println!(
+        "Median of {} runs on a {}x{} canvas, updates changing {}% of badges. Times in ms.\n",
+        options.iterations, WIDTH, HEIGHT, options.changed_percent
+    );
+    println!(
+        "{:>6}  {:<6}  {:<6}  {:>8}  {:>8}  {:>8}  {:>8}  {:>8}",
+        "nodes", "path", "change", "js", "native", "layout", "paint", "total"
+    );
+
+    for result in results {
+        let stages = &result.median;
+        println!(
+            "{:>6}  {:<6}  {:<6}  {:>8.2}  {:>8.2}  {:>8.2}  {:>8.2}  {:>8.2}",
+            result.nodes,
+            result.path,
+            result.change,
+            stages.js,
+            stages.native,
+            stages.layout,
+            stages.paint,
+            result.median_total
+        );
+    }
+
+    if !Capabilities::current().binary_protocol {
+        println!("\nbinary: not supported by this build (capabilities.binaryProtocol is false)");
+    }
+}
@@ -0,0 +1,9 @@
+#![no_main]
+
+use juice::dom::{Dom, DomLimits};
+use juice::inherited_style::InheritedStyle;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Dom::try_from_json_bytes(data, InheritedStyle::new("default"), DomLimits::default());
+});
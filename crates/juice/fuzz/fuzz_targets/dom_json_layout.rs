@@ -0,0 +1,17 @@
+#![no_main]
+
+use std::collections::HashMap;
+
+use juice::dom::{Dom, DomLimits};
+use juice::inherited_style::InheritedStyle;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(mut dom) =
+        Dom::try_from_json_bytes(data, InheritedStyle::new("default"), DomLimits::default())
+    {
+        dom.compute_layout(&HashMap::new(), 480.0, 320.0);
+        let _ = dom.surface_rects();
+        let _ = dom.node_at_point(240.0, 160.0);
+    }
+});
@@ -0,0 +1,76 @@
+/// How changes inside a node are announced, from the `accessibilityLive` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LiveRegion {
+    #[default]
+    Off,
+    /// Announced once whatever is being said has finished
+    Polite,
+    /// Announced straight away, interrupting anything being said
+    Assertive,
+}
+
+impl LiveRegion {
+    /// Parse `off`, `polite` or `assertive`. Anything else is `Off`.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "polite" => LiveRegion::Polite,
+            "assertive" => LiveRegion::Assertive,
+            _ => LiveRegion::Off,
+        }
+    }
+}
+
+/// What a node tells assistive technology about itself, from its `accessibility*`
+/// attributes.
+#[derive(Debug, Clone, Default)]
+pub struct A11yProps {
+    /// Spoken instead of the node's text
+    pub label: Option<String>,
+    /// What kind of control it is, like `button` or `heading`. Inputs, gauges and other
+    /// native nodes have one already.
+    pub role: Option<String>,
+    pub live: LiveRegion,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnounceReason {
+    /// The node was just focused
+    Focus,
+    /// Text inside a live region changed
+    LiveRegion,
+}
+
+/// Something for a screen reader to say, passed to the handler set with
+/// `Renderer::on_announce`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Announcement {
+    pub node_id: u64,
+    /// The node's `accessibilityLabel`, or the text inside it
+    pub label: String,
+    /// Empty if the node has no role
+    pub role: String,
+    /// The current value of an input or gauge. Never set for secure inputs.
+    pub value: Option<String>,
+    pub reason: AnnounceReason,
+    /// Whether to cut off speech in progress, for assertive live regions
+    pub interrupt: bool,
+}
+
+impl Announcement {
+    /// The parts that are set, joined for a speech engine that takes a single string,
+    /// e.g. "Temperature, text field, 21".
+    pub fn to_speech(&self) -> String {
+        [
+            Some(self.label.as_str()),
+            Some(self.role.as_str()),
+            self.value.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+pub type AnnounceHandler = Box<dyn FnMut(Announcement)>;
@@ -0,0 +1,556 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Instant;
+
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+use crate::canvas::RgbColor;
+use crate::dom_json;
+
+/// Timing functions for keyframe animations, applied between each pair of keyframes like
+/// CSS `animation-timing-function`. Apps can add named curves with `Dom::register_easing`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    CubicBezier(f32, f32, f32, f32),
+    /// Jump between values in this many equal steps, holding each until its end
+    Steps(u32),
+}
+
+impl Easing {
+    /// Parse `linear`, `ease`, `ease-in`, `ease-out`, `ease-in-out`,
+    /// `cubic-bezier(x1, y1, x2, y2)` or `steps(n)`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+
+        match value {
+            "linear" => return Some(Easing::Linear),
+            "ease" => return Some(Easing::CubicBezier(0.25, 0.1, 0.25, 1.0)),
+            "ease-in" => return Some(Easing::CubicBezier(0.42, 0.0, 1.0, 1.0)),
+            "ease-out" => return Some(Easing::CubicBezier(0.0, 0.0, 0.58, 1.0)),
+            "ease-in-out" => return Some(Easing::CubicBezier(0.42, 0.0, 0.58, 1.0)),
+            _ => {}
+        }
+
+        let (name, args) = value.strip_suffix(')')?.split_once('(')?;
+        let args: Vec<f32> = args
+            .split(',')
+            .map(|arg| arg.trim().parse().ok())
+            .collect::<Option<_>>()?;
+
+        match (name.trim(), args.as_slice()) {
+            ("cubic-bezier", &[x1, y1, x2, y2]) => Some(Easing::CubicBezier(
+                x1.clamp(0.0, 1.0),
+                y1,
+                x2.clamp(0.0, 1.0),
+                y2,
+            )),
+            ("steps", &[n]) if n >= 1.0 => Some(Easing::Steps(n as u32)),
+            _ => None,
+        }
+    }
+
+    /// A curve registered under `name`, with its x control points clamped to 0..1 like
+    /// `cubic-bezier()`. Built-in names can't be replaced.
+    pub fn custom(name: &str, x1: f32, y1: f32, x2: f32, y2: f32) -> Option<Self> {
+        if name.trim().is_empty() || Easing::parse(name).is_some() || name.contains('(') {
+            return None;
+        }
+
+        Some(Easing::CubicBezier(
+            x1.clamp(0.0, 1.0),
+            y1,
+            x2.clamp(0.0, 1.0),
+            y2,
+        ))
+    }
+
+    /// Map progress through a segment, from 0 to 1, to how far between the values it is.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        match *self {
+            Easing::Linear => t,
+            Easing::Steps(n) => (t * n as f32).floor().min(n as f32 - 1.0) / n as f32,
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                let bezier = |a: f32, b: f32, s: f32| {
+                    3.0 * a * s * (1.0 - s).powi(2) + 3.0 * b * s * s * (1.0 - s) + s.powi(3)
+                };
+
+                // Find the curve parameter for x = t by bisection, which always converges
+                // since x is monotonic for control points within 0..1
+                let (mut lo, mut hi) = (0.0, 1.0);
+
+                for _ in 0..24 {
+                    let mid = (lo + hi) / 2.0;
+
+                    if bezier(x1, x2, mid) < t {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+
+                bezier(y1, y2, (lo + hi) / 2.0)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Easing {
+    /// As CSS, which `parse` reads back.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Easing::Linear => write!(f, "linear"),
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                write!(f, "cubic-bezier({}, {}, {}, {})", x1, y1, x2, y2)
+            }
+            Easing::Steps(n) => write!(f, "steps({})", n),
+        }
+    }
+}
+
+/// What an animation currently contributes to its node. Outside of an animation every
+/// value is at rest: fully opaque, not moved or scaled and no color override.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimatedValues {
+    pub opacity: f32,
+    pub translate: (f32, f32),
+    pub scale: f32,
+    pub color: Option<RgbColor>,
+    pub background: Option<RgbColor>,
+}
+
+impl Default for AnimatedValues {
+    fn default() -> Self {
+        AnimatedValues {
+            opacity: 1.0,
+            translate: (0.0, 0.0),
+            scale: 1.0,
+            color: None,
+            background: None,
+        }
+    }
+}
+
+impl AnimatedValues {
+    /// Whether the node has to be drawn off to the side and composited, rather than
+    /// straight onto the canvas.
+    pub fn composites(&self) -> bool {
+        self.opacity < 1.0 || self.translate != (0.0, 0.0) || self.scale != 1.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnimationState {
+    Running,
+    /// Finished on this step
+    Ended,
+    /// Finished on an earlier step
+    Idle,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyframeSpec {
+    offset: Option<f32>,
+    opacity: Option<f32>,
+    translate_x: Option<f32>,
+    translate_y: Option<f32>,
+    scale: Option<f32>,
+    color: Option<String>,
+    background: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Iterations {
+    Count(f32),
+    /// `"infinite"`
+    Named(String),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnimationSpec {
+    keyframes: Vec<KeyframeSpec>,
+    /// Milliseconds per iteration
+    duration: f32,
+    #[serde(default)]
+    delay: f32,
+    iterations: Option<Iterations>,
+    easing: Option<String>,
+    direction: Option<String>,
+    fill: Option<String>,
+}
+
+/// Values of one property at the keyframes that set it, by offset.
+#[derive(Debug, Clone)]
+struct Track<T> {
+    stops: Vec<(f32, T)>,
+}
+
+impl<T> Default for Track<T> {
+    fn default() -> Self {
+        Track { stops: Vec::new() }
+    }
+}
+
+impl<T: Copy> Track<T> {
+    fn sample(&self, progress: f32, easing: Easing, lerp: impl Fn(T, T, f32) -> T) -> Option<T> {
+        let (first, last) = (self.stops.first()?, self.stops.last()?);
+
+        if progress <= first.0 {
+            return Some(first.1);
+        }
+
+        for pair in self.stops.windows(2) {
+            let ((from_offset, from), (to_offset, to)) = (pair[0], pair[1]);
+
+            if progress < to_offset {
+                let t = (progress - from_offset) / (to_offset - from_offset).max(f32::EPSILON);
+                return Some(lerp(from, to, easing.apply(t)));
+            }
+        }
+
+        Some(last.1)
+    }
+}
+
+impl<T: Copy> Track<T> {
+    /// Start and end at the property's resting value if no keyframe sets it there, like
+    /// CSS does when the `from` or `to` keyframe leaves a property out.
+    fn anchor(&mut self, rest: T) {
+        if self.stops.is_empty() {
+            return;
+        }
+
+        if self.stops[0].0 > 0.0 {
+            self.stops.insert(0, (0.0, rest));
+        }
+
+        if self.stops[self.stops.len() - 1].0 < 1.0 {
+            self.stops.push((1.0, rest));
+        }
+    }
+}
+
+/// A keyframe animation from a node's `animation` attribute, run natively from the
+/// clock so it keeps time without JS.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    /// The attribute value, so setting the same animation again doesn't restart it
+    source: String,
+    opacity: Track<f32>,
+    translate_x: Track<f32>,
+    translate_y: Track<f32>,
+    scale: Track<f32>,
+    color: Track<RgbColor>,
+    background: Track<RgbColor>,
+    /// Seconds per iteration
+    duration: f32,
+    delay: f32,
+    iterations: f32,
+    easing: Easing,
+    /// The `easing` given, when it names a registered curve that can be replaced
+    easing_name: Option<String>,
+    /// Play every other iteration backwards
+    alternate: bool,
+    /// Keep the last frame's values once finished, rather than returning to rest
+    fill_forwards: bool,
+    started: Instant,
+    ended: bool,
+    pub current: AnimatedValues,
+}
+
+impl Animation {
+    /// Parse an `animation` attribute: JSON with `keyframes`, `duration` in ms and
+    /// optionally `delay`, `iterations` (a number or `"infinite"`), `easing`,
+    /// `direction` (`normal` or `alternate`) and `fill` (`none` or `forwards`). Colors
+    /// in keyframes go through `resolve_color`, so theme variables work, and animate
+    /// from and to the node's `color` and `background` where the first or last keyframe
+    /// leaves them out. `easing` can also name one of `easings`.
+    pub fn parse(
+        source: &str,
+        now: Instant,
+        (color, background): (RgbColor, Option<RgbColor>),
+        resolve_color: impl Fn(&str) -> Option<RgbColor>,
+        easings: &HashMap<String, Easing>,
+    ) -> Option<Self> {
+        let spec: AnimationSpec = serde_json::from_str(source)
+            .map_err(|err| eprintln!("Invalid animation: {}", err))
+            .ok()?;
+        let easing_name = spec
+            .easing
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| Easing::parse(name).is_none())
+            .map(str::to_string);
+
+        let mut animation = Animation {
+            source: source.to_string(),
+            opacity: Track::default(),
+            translate_x: Track::default(),
+            translate_y: Track::default(),
+            scale: Track::default(),
+            color: Track::default(),
+            background: Track::default(),
+            duration: spec.duration.max(0.0) / 1000.0,
+            delay: spec.delay / 1000.0,
+            iterations: match spec.iterations {
+                None => 1.0,
+                Some(Iterations::Count(count)) => count.max(0.0),
+                Some(Iterations::Named(name)) if name == "infinite" => f32::INFINITY,
+                Some(Iterations::Named(_)) => 1.0,
+            },
+            easing: spec
+                .easing
+                .as_deref()
+                .and_then(|easing| {
+                    easings
+                        .get(easing.trim())
+                        .copied()
+                        .or(Easing::parse(easing))
+                })
+                .unwrap_or(Easing::Linear),
+            easing_name,
+            alternate: spec.direction.as_deref() == Some("alternate"),
+            fill_forwards: spec.fill.as_deref() == Some("forwards"),
+            started: now,
+            ended: false,
+            current: AnimatedValues::default(),
+        };
+
+        let last = spec.keyframes.len().saturating_sub(1).max(1) as f32;
+        let mut keyframes: Vec<(f32, KeyframeSpec)> = spec
+            .keyframes
+            .into_iter()
+            .enumerate()
+            .map(|(i, keyframe)| {
+                let offset = keyframe.offset.unwrap_or(i as f32 / last).clamp(0.0, 1.0);
+                (offset, keyframe)
+            })
+            .collect();
+        keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        for (offset, keyframe) in keyframes {
+            let numbers = [
+                (
+                    &mut animation.opacity,
+                    keyframe.opacity.map(|o| o.clamp(0.0, 1.0)),
+                ),
+                (&mut animation.translate_x, keyframe.translate_x),
+                (&mut animation.translate_y, keyframe.translate_y),
+                (&mut animation.scale, keyframe.scale.map(|s| s.max(0.0))),
+            ];
+
+            for (track, value) in numbers {
+                if let Some(value) = value {
+                    track.stops.push((offset, value));
+                }
+            }
+
+            let colors = [
+                (&mut animation.color, keyframe.color),
+                (&mut animation.background, keyframe.background),
+            ];
+
+            for (track, value) in colors {
+                if let Some(color) = value.as_deref().and_then(&resolve_color) {
+                    track.stops.push((offset, color));
+                }
+            }
+        }
+
+        animation.opacity.anchor(1.0);
+        animation.translate_x.anchor(0.0);
+        animation.translate_y.anchor(0.0);
+        animation.scale.anchor(1.0);
+        animation.color.anchor(color);
+
+        if let Some(background) = background {
+            animation.background.anchor(background);
+        }
+
+        Some(animation)
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The name of the registered curve the animation asked for, even if it wasn't
+    /// registered yet.
+    pub fn easing_name(&self) -> Option<&str> {
+        self.easing_name.as_deref()
+    }
+
+    /// Switch curves mid-animation, when the one it names is registered or replaced.
+    pub fn set_easing(&mut self, easing: Easing) {
+        self.easing = easing;
+    }
+
+    /// The animation's state at `now` for tooling, without stepping it: timing, progress
+    /// through the current iteration, the values it's showing and heading for, and its
+    /// keyframes.
+    pub fn inspect(&self, now: Instant) -> Value {
+        let elapsed = now.saturating_duration_since(self.started).as_secs_f32() - self.delay;
+        let iteration = if self.duration > 0.0 {
+            elapsed.max(0.0) / self.duration
+        } else {
+            f32::INFINITY
+        };
+
+        let state = if self.ended || iteration >= self.iterations {
+            "finished"
+        } else if elapsed < 0.0 {
+            "delayed"
+        } else {
+            "running"
+        };
+
+        let (progress, target) = if state == "running" {
+            // Where this iteration ends, which alternate iterations reach going backwards
+            let end = iteration.floor() + 1.0;
+            let target = self.sample_iteration(end.min(self.iterations));
+            (iteration.fract(), Some(target))
+        } else {
+            (if state == "finished" { 1.0 } else { 0.0 }, None)
+        };
+
+        let mut tracks = Map::new();
+        let numbers = [
+            ("opacity", &self.opacity),
+            ("translateX", &self.translate_x),
+            ("translateY", &self.translate_y),
+            ("scale", &self.scale),
+        ];
+
+        for (name, track) in numbers {
+            if !track.stops.is_empty() {
+                tracks.insert(name.to_string(), json!(track.stops));
+            }
+        }
+
+        let colors = [("color", &self.color), ("background", &self.background)];
+
+        for (name, track) in colors {
+            if !track.stops.is_empty() {
+                let stops: Vec<Value> = track
+                    .stops
+                    .iter()
+                    .map(|&(offset, color)| json!([offset, dom_json::color(color)]))
+                    .collect();
+                tracks.insert(name.to_string(), Value::Array(stops));
+            }
+        }
+
+        json!({
+            "state": state,
+            "elapsed": elapsed * 1000.0,
+            "duration": self.duration * 1000.0,
+            "delay": self.delay * 1000.0,
+            // Infinity isn't JSON
+            "iterations": Some(self.iterations).filter(|n| n.is_finite()),
+            "iteration": iteration.min(self.iterations).floor(),
+            "progress": progress,
+            "easing": self.easing.to_string(),
+            "easingName": self.easing_name,
+            "alternate": self.alternate,
+            "current": values_json(self.current),
+            "target": target.map(values_json),
+            "keyframes": tracks,
+        })
+    }
+
+    /// Update `current` for the time `now`, reporting whether the animation is still
+    /// running.
+    pub fn step(&mut self, now: Instant) -> AnimationState {
+        if self.ended {
+            return AnimationState::Idle;
+        }
+
+        let elapsed = now.saturating_duration_since(self.started).as_secs_f32() - self.delay;
+
+        if elapsed < 0.0 {
+            self.current = AnimatedValues::default();
+            return AnimationState::Running;
+        }
+
+        let iteration = if self.duration > 0.0 {
+            elapsed / self.duration
+        } else {
+            f32::INFINITY
+        };
+
+        if iteration >= self.iterations {
+            self.ended = true;
+            self.current = if self.fill_forwards {
+                self.sample_iteration(self.iterations)
+            } else {
+                AnimatedValues::default()
+            };
+            return AnimationState::Ended;
+        }
+
+        self.current = self.sample_iteration(iteration);
+        AnimationState::Running
+    }
+
+    /// Values a number of iterations in, e.g. 2.5 is halfway through the third.
+    fn sample_iteration(&self, iteration: f32) -> AnimatedValues {
+        // A whole number of iterations ends at the end of the last one, not the start of
+        // the next
+        let index = if iteration > 0.0 && iteration.fract() == 0.0 {
+            iteration - 1.0
+        } else {
+            iteration.floor()
+        };
+        let mut progress = (iteration - index).min(1.0);
+
+        if self.alternate && index % 2.0 == 1.0 {
+            progress = 1.0 - progress;
+        }
+
+        let number = |track: &Track<f32>, rest: f32| {
+            track
+                .sample(progress, self.easing, |a, b, t| a + (b - a) * t)
+                .unwrap_or(rest)
+        };
+        let color = |track: &Track<RgbColor>| track.sample(progress, self.easing, lerp_color);
+
+        AnimatedValues {
+            opacity: number(&self.opacity, 1.0),
+            translate: (
+                number(&self.translate_x, 0.0),
+                number(&self.translate_y, 0.0),
+            ),
+            scale: number(&self.scale, 1.0),
+            color: color(&self.color),
+            background: color(&self.background),
+        }
+    }
+}
+
+fn values_json(values: AnimatedValues) -> Value {
+    json!({
+        "opacity": values.opacity,
+        "translateX": values.translate.0,
+        "translateY": values.translate.1,
+        "scale": values.scale,
+        "color": values.color.map(dom_json::color),
+        "background": values.background.map(dom_json::color),
+    })
+}
+
+fn lerp_color(a: RgbColor, b: RgbColor, t: f32) -> RgbColor {
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+
+    RgbColor {
+        r: channel(a.r, b.r),
+        g: channel(a.g, b.g),
+        b: channel(a.b, b.b),
+        a: channel(a.a, b.a),
+    }
+}
@@ -0,0 +1,65 @@
+use std::ops::Range;
+
+use unicode_bidi::{BidiClass, BidiInfo, Level, bidi_class};
+
+use crate::inherited_style::Direction;
+
+/// Whether a line of `text` in a paragraph going `direction` is displayed in a different
+/// order than it's written: it has right-to-left characters, or it's in a right-to-left
+/// paragraph, where neutrals like punctuation move.
+pub fn needs_reordering(text: &str, direction: Direction) -> bool {
+    direction == Direction::Rtl
+        || text.chars().any(|c| {
+            matches!(
+                bidi_class(c),
+                BidiClass::R | BidiClass::AL | BidiClass::RLE | BidiClass::RLO | BidiClass::RLI
+            )
+        })
+}
+
+/// The runs of one line of `text` in display order, left to right, and whether each is
+/// read right to left.
+pub fn visual_runs(text: &str, direction: Direction) -> Vec<(Range<usize>, bool)> {
+    if !needs_reordering(text, direction) {
+        return vec![(0..text.len(), false)];
+    }
+
+    let level = match direction {
+        Direction::Ltr => Level::ltr(),
+        Direction::Rtl => Level::rtl(),
+    };
+    let info = BidiInfo::new(text, Some(level));
+    let mut runs = Vec::new();
+
+    for paragraph in &info.paragraphs {
+        let (levels, paragraph_runs) = info.visual_runs(paragraph, paragraph.range.clone());
+
+        runs.extend(
+            paragraph_runs
+                .into_iter()
+                .map(|run| (run.clone(), levels[run.start].is_rtl())),
+        );
+    }
+
+    runs
+}
+
+/// Each line of `text` with its characters in display order, for laying out left to
+/// right a character at a time. Each line is a paragraph of its own.
+///
+/// Right-to-left runs are reversed without mirroring brackets or joining letters, which
+/// needs the `shaping` feature.
+pub fn visual_lines(text: &str, direction: Direction) -> String {
+    text.split('\n')
+        .map(|line| {
+            visual_runs(line, direction)
+                .into_iter()
+                .map(|(range, rtl)| match rtl {
+                    true => line[range].chars().rev().collect(),
+                    false => line[range].to_string(),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
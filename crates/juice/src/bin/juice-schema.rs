@@ -0,0 +1,53 @@
+// Prints TypeScript declarations for the tree JSON and styles the renderer accepts, so
+// the JS side can't drift from what the Rust side takes.
+//
+//     cargo run -p juice --features lottie --bin juice-schema             # print them
+//     cargo run -p juice --features lottie --bin juice-schema -- --write  # update the file
+//     cargo run -p juice --features lottie --bin juice-schema -- --check  # fail if stale
+//
+// The file is `juice::schema::TYPESCRIPT_PATH` in the repository. `--check` suits CI,
+// after a change to the node types or style properties in `juice::capabilities`.
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use juice::schema;
+
+fn main() -> ExitCode {
+    let ts = match schema::typescript() {
+        Ok(ts) => ts,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../..")
+        .join(schema::TYPESCRIPT_PATH);
+
+    match std::env::args().nth(1).as_deref() {
+        None => print!("{}", ts),
+        Some("--write") => {
+            if let Err(err) = std::fs::write(&path, ts) {
+                eprintln!("Can't write {}: {}", path.display(), err);
+                return ExitCode::FAILURE;
+            }
+        }
+        Some("--check") => {
+            if std::fs::read_to_string(&path).ok().as_deref() != Some(ts.as_str()) {
+                eprintln!(
+                    "{} is out of date; run `cargo run -p juice --features lottie --bin juice-schema -- --write`",
+                    schema::TYPESCRIPT_PATH
+                );
+                return ExitCode::FAILURE;
+            }
+        }
+        Some(arg) => {
+            eprintln!("Unknown option {}", arg);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,167 @@
+/// Most decimal places `round` accepts
+const MAX_PLACES: usize = 20;
+
+/// Widest `pad` and `padEnd` accept, so a typo can't allocate without bound
+const MAX_PAD_WIDTH: usize = 256;
+
+/// A step applied to a bound value, like `round:1` in `{{value | round:1}}`.
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    /// Fixed number of decimal places
+    Round(usize),
+    /// Multiply, e.g. to convert units
+    Scale(f64),
+    /// Pad the start to a width with a character, e.g. `pad:3:0` for leading zeros
+    Pad(usize, char),
+    /// Pad the end to a width with a character
+    PadEnd(usize, char),
+    Upper,
+    Lower,
+    /// Shown when the value is empty
+    Default(String),
+}
+
+impl Filter {
+    fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.trim();
+        let mut args = spec.split(':').map(str::trim);
+        let name = args.next()?;
+        let arg = args.next();
+        let fill = || {
+            args.clone()
+                .next()
+                .and_then(|fill| fill.chars().next())
+                .unwrap_or(' ')
+        };
+
+        match name {
+            "round" => match arg {
+                Some(places) => places
+                    .parse()
+                    .ok()
+                    .filter(|&places| places <= MAX_PLACES)
+                    .map(Filter::Round),
+                None => Some(Filter::Round(0)),
+            },
+            "scale" => arg.and_then(|k| k.parse().ok()).map(Filter::Scale),
+            "pad" => Some(Filter::Pad(pad_width(arg?)?, fill())),
+            "padEnd" => Some(Filter::PadEnd(pad_width(arg?)?, fill())),
+            "upper" => Some(Filter::Upper),
+            "lower" => Some(Filter::Lower),
+            // Everything after the first colon, so the text can have colons in it
+            "default" => Some(Filter::Default(
+                spec.split_once(':')
+                    .map_or("", |(_, text)| text.trim())
+                    .to_string(),
+            )),
+            _ => None,
+        }
+    }
+
+    fn apply(&self, value: String) -> String {
+        let number = || value.trim().parse::<f64>().ok();
+
+        match self {
+            Filter::Round(places) => match number() {
+                Some(number) => format!("{number:.places$}"),
+                None => value,
+            },
+            Filter::Scale(factor) => match number() {
+                Some(number) => (number * factor).to_string(),
+                None => value,
+            },
+            Filter::Pad(width, fill) => {
+                let padding = width.saturating_sub(value.chars().count());
+                std::iter::repeat_n(*fill, padding)
+                    .chain(value.chars())
+                    .collect()
+            }
+            Filter::PadEnd(width, fill) => {
+                let padding = width.saturating_sub(value.chars().count());
+                value
+                    .chars()
+                    .chain(std::iter::repeat_n(*fill, padding))
+                    .collect()
+            }
+            Filter::Upper => value.to_uppercase(),
+            Filter::Lower => value.to_lowercase(),
+            Filter::Default(text) if value.is_empty() => text.clone(),
+            Filter::Default(_) => value,
+        }
+    }
+}
+
+fn pad_width(arg: &str) -> Option<usize> {
+    arg.parse().ok().filter(|&width| width <= MAX_PAD_WIDTH)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    /// Where the value goes, with the filters to apply to it in order
+    Value(Vec<Filter>),
+}
+
+/// A format for a bound value like `"{{value | round:1}} °C"`, evaluated natively so live
+/// readouts don't need JS to format them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Parse a template: text with `{{value}}` placeholders, each optionally piped
+    /// through filters. Unknown filters and ones with bad arguments are skipped with a
+    /// warning, and an unclosed `{{` is kept as text.
+    pub fn parse(template: &str) -> Self {
+        let mut parts = Vec::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find("{{") {
+            let Some(end) = rest[start..].find("}}") else {
+                break;
+            };
+
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+
+            let mut pipeline = rest[start + 2..start + end].split('|');
+            // Only the value can be shown, so whatever the expression names is it
+            pipeline.next();
+            let filters = pipeline.filter_map(|spec| {
+                let filter = Filter::parse(spec);
+
+                if filter.is_none() {
+                    eprintln!(
+                        "Skipping bad filter in format {:?}: {}",
+                        template,
+                        spec.trim()
+                    );
+                }
+
+                filter
+            });
+            parts.push(Part::Value(filters.collect()));
+            rest = &rest[start + end + 2..];
+        }
+
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+
+        Template { parts }
+    }
+
+    pub fn render(&self, value: &str) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Value(filters) => filters
+                    .iter()
+                    .fold(value.to_string(), |value, filter| filter.apply(value)),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,102 @@
+use serde::Deserialize;
+
+/// Per-panel color correction, read from the host config. Either explicit per-channel
+/// lookup tables, or a gamma exponent and white point to build them from.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColorCalibration {
+    /// Exponent applied to each channel normalised to 0–1. Below 1 brightens midtones,
+    /// above 1 darkens them.
+    #[serde(default = "default_gamma")]
+    pub gamma: f32,
+    /// Red, green and blue multipliers applied after gamma, e.g. `[1.0, 0.95, 0.85]` to
+    /// warm up a panel that runs blue.
+    #[serde(default = "default_white_point")]
+    pub white_point: [f32; 3],
+    /// Measured tables of 256 entries per channel. Overrides `gamma` and `white_point`.
+    pub lut: Option<ChannelLuts>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChannelLuts {
+    pub r: Vec<u8>,
+    pub g: Vec<u8>,
+    pub b: Vec<u8>,
+}
+
+fn default_gamma() -> f32 {
+    1.0
+}
+
+fn default_white_point() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+#[derive(Debug)]
+pub struct CalibrationError {
+    pub message: String,
+}
+
+/// Lookup tables mapping each channel of an XRGB8888 pixel to its corrected value.
+pub struct OutputTransform {
+    r: [u8; 256],
+    g: [u8; 256],
+    b: [u8; 256],
+}
+
+impl OutputTransform {
+    pub fn new(calibration: &ColorCalibration) -> Result<Self, CalibrationError> {
+        if let Some(lut) = &calibration.lut {
+            return Ok(OutputTransform {
+                r: channel_lut(&lut.r, "r")?,
+                g: channel_lut(&lut.g, "g")?,
+                b: channel_lut(&lut.b, "b")?,
+            });
+        }
+
+        let [white_r, white_g, white_b] = calibration.white_point;
+        let gamma = calibration.gamma;
+
+        Ok(OutputTransform {
+            r: gamma_lut(gamma, white_r),
+            g: gamma_lut(gamma, white_g),
+            b: gamma_lut(gamma, white_b),
+        })
+    }
+
+    #[inline(always)]
+    pub fn apply(&self, pixel: u32) -> u32 {
+        let r = self.r[((pixel >> 16) & 0xFF) as usize] as u32;
+        let g = self.g[((pixel >> 8) & 0xFF) as usize] as u32;
+        let b = self.b[(pixel & 0xFF) as usize] as u32;
+        (pixel & 0xFF00_0000) | r << 16 | g << 8 | b
+    }
+
+    /// Copy `src` into `dst`, correcting each pixel.
+    pub fn apply_slice(&self, src: &[u32], dst: &mut [u32]) {
+        for (dst, &src) in dst.iter_mut().zip(src) {
+            *dst = self.apply(src);
+        }
+    }
+}
+
+fn channel_lut(values: &[u8], channel: &str) -> Result<[u8; 256], CalibrationError> {
+    values.try_into().map_err(|_| CalibrationError {
+        message: format!(
+            "LUT channel {} has {} entries, expected 256",
+            channel,
+            values.len()
+        ),
+    })
+}
+
+fn gamma_lut(gamma: f32, white: f32) -> [u8; 256] {
+    let mut lut = [0; 256];
+
+    for (i, value) in lut.iter_mut().enumerate() {
+        let corrected = (i as f32 / 255.0).powf(gamma) * white.clamp(0.0, 1.0);
+        *value = (corrected * 255.0).round() as u8;
+    }
+
+    lut
+}
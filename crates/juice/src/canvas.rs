@@ -1,40 +1,83 @@
+use std::borrow::Cow;
+
 use embedded_graphics::{
     pixelcolor::Rgb888, pixelcolor::RgbColor as _, prelude::*, primitives::Rectangle,
 };
 use fontdue::Font;
+#[cfg(feature = "shaping")]
+use fontdue::layout::GlyphRasterConfig;
 use fontdue::layout::{
-    CoordinateSystem, HorizontalAlign, Layout as TextLayout, LayoutSettings, TextStyle,
+    CoordinateSystem, GlyphPosition, HorizontalAlign, Layout as TextLayout, LayoutSettings,
+    TextStyle,
 };
 
-use crate::inherited_style::TextAlign;
+use crate::bidi;
+use crate::color::Color;
+use crate::decoration;
+use crate::emoji;
+use crate::font_fallback;
+use crate::glyph_cache::{self, Glyph};
+use crate::inherited_style::{Direction, LineHeight, TextAlign, TextDecoration};
+#[cfg(feature = "shaping")]
+use crate::shaping;
+use crate::transform::Affine;
+
+/// How `Canvas::draw_text` sets a run of text.
+#[derive(Clone, Copy)]
+pub struct TextRun<'a> {
+    /// The font to draw in, then fallbacks for characters it doesn't have
+    pub fonts: &'a [&'a Font],
+    pub font_size: f32,
+    pub color: RgbColor,
+    pub align: TextAlign,
+    pub direction: Direction,
+    pub line_height: LineHeight,
+    pub letter_spacing: f32,
+    pub decoration: TextDecoration,
+}
 
-#[derive(Debug, Clone, Copy)]
+impl<'a> TextRun<'a> {
+    /// Left-aligned, left-to-right text at the normal line height, with no letter spacing
+    /// or decoration.
+    pub fn new(fonts: &'a [&'a Font], font_size: f32, color: RgbColor) -> Self {
+        TextRun {
+            fonts,
+            font_size,
+            color,
+            align: TextAlign::Left,
+            direction: Direction::Ltr,
+            line_height: LineHeight::Normal,
+            letter_spacing: 0.0,
+            decoration: TextDecoration::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct RgbColor {
     pub r: u8,
     pub g: u8,
     pub b: u8,
+    /// Opacity, from 0 for transparent to 255 for opaque. Not premultiplied.
+    pub a: u8,
 }
 
 impl RgbColor {
-    pub fn from_string(rgb: &str) -> Option<Self> {
-        let hex = rgb.strip_prefix('#')?;
-
-        if hex.len() != 6 {
-            return None;
-        }
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        RgbColor { r, g, b, a: 255 }
+    }
 
-        let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
-        let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
-        let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
-        Some(RgbColor { r, g, b })
+    /// Parse any CSS color `Color::parse` understands.
+    pub fn from_string(value: &str) -> Option<Self> {
+        Color::parse(value)
     }
 
     pub fn from_array(rgb: [u8; 3]) -> Self {
-        RgbColor {
-            r: rgb[0],
-            g: rgb[1],
-            b: rgb[2],
-        }
+        RgbColor::rgb(rgb[0], rgb[1], rgb[2])
+    }
+
+    pub fn is_opaque(self) -> bool {
+        self.a == 255
     }
 
     pub fn to_xrgb(self) -> u32 {
@@ -53,25 +96,83 @@ pub struct Canvas {
     pub width: u32,
     pub height: u32,
     pub pixels: Vec<u32>,
+    /// Drawing only touches pixels inside this (x0, y0, x1, y1), narrowed by `push_clip`
+    clip: (i32, i32, i32, i32),
+    clip_stack: Vec<(i32, i32, i32, i32)>,
 }
 
 impl Canvas {
     pub fn new(width: u32, height: u32) -> Self {
         let size = (width * height) as usize;
 
+        Self::from_pixels(width, height, vec![0xFF00_0000; size])
+    }
+
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<u32>) -> Self {
         Self {
             width,
             height,
-            pixels: vec![0xFF00_0000; size],
+            pixels,
+            clip: (0, 0, width as i32, height as i32),
+            clip_stack: Vec::new(),
+        }
+    }
+
+    /// Limit drawing to a rect (x, y, w, h) within the current clip, until `pop_clip`.
+    pub fn push_clip(&mut self, (x, y, w, h): (f32, f32, f32, f32)) {
+        let (x0, y0, x1, y1) = self.clip;
+
+        self.clip_stack.push(self.clip);
+        self.clip = (
+            x0.max(x.floor() as i32),
+            y0.max(y.floor() as i32),
+            x1.min((x + w).ceil() as i32),
+            y1.min((y + h).ceil() as i32),
+        );
+    }
+
+    /// Go back to the clip before the last `push_clip`.
+    pub fn pop_clip(&mut self) {
+        if let Some(clip) = self.clip_stack.pop() {
+            self.clip = clip;
         }
     }
 
+    /// Whether a rect (x, y, w, h) is entirely inside the current clip, so nothing drawn
+    /// in it is cut off.
+    pub fn clip_contains(&self, (x, y, w, h): (i32, i32, u32, u32)) -> bool {
+        let (x0, y0, x1, y1) = self.clip;
+        x >= x0 && y >= y0 && x + w as i32 <= x1 && y + h as i32 <= y1
+    }
+
+    fn in_clip(&self, x: i32, y: i32) -> bool {
+        let (x0, y0, x1, y1) = self.clip;
+        x >= x0 && x < x1 && y >= y0 && y < y1
+    }
+
     pub fn clear(&mut self, color: RgbColor) {
         self.pixels.fill(color.to_xrgb());
     }
 
+    /// Fill a rect with fully transparent black (alpha 0), so a plane underneath shows
+    /// through when the canvas is scanned out as ARGB8888.
+    pub fn punch_hole(&mut self, x: i32, y: i32, w: u32, h: u32) {
+        let (clip_x0, clip_y0, clip_x1, clip_y1) = self.clip;
+        let x0 = x.max(clip_x0) as u32;
+        let y0 = y.max(clip_y0) as u32;
+        let x1 = (x + w as i32).min(clip_x1).max(x0 as i32) as u32;
+        let y1 = (y + h as i32).min(clip_y1).max(y0 as i32) as u32;
+
+        for row in y0..y1 {
+            let start = (row * self.width + x0) as usize;
+            let end = (row * self.width + x1) as usize;
+            self.pixels[start..end].fill(0);
+        }
+    }
+
+    /// Blend a color over a pixel, `alpha` scaling the color's own alpha.
     pub fn blend_pixel(&mut self, x: i32, y: i32, color: RgbColor, alpha: u8) {
-        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+        if !self.in_clip(x, y) {
             return;
         }
         let idx = (y as u32 * self.width + x as u32) as usize;
@@ -79,7 +180,7 @@ impl Canvas {
         let bg_r = ((bg >> 16) & 0xFF) as u16;
         let bg_g = ((bg >> 8) & 0xFF) as u16;
         let bg_b = (bg & 0xFF) as u16;
-        let a = alpha as u16;
+        let a = alpha as u16 * color.a as u16 / 255;
         let inv_a = 255 - a;
         let r = ((color.r as u16 * a + bg_r * inv_a) / 255) as u8;
         let g = ((color.g as u16 * a + bg_g * inv_a) / 255) as u8;
@@ -87,6 +188,250 @@ impl Canvas {
         self.pixels[idx] = to_xrgb(r, g, b);
     }
 
+    /// Blend a color over a rect with rounded corners, optionally only where it overlaps
+    /// a circle (center x, center y, radius).
+    pub fn tint(
+        &mut self,
+        rect: (f32, f32, f32, f32),
+        radius: f32,
+        circle: Option<(f32, f32, f32)>,
+        color: RgbColor,
+        alpha: u8,
+    ) {
+        self.blend_rounded_rect(rect, radius, color, |px, py| match circle {
+            Some((ox, oy, r)) if (px - ox).hypot(py - oy) > r => 0,
+            _ => alpha,
+        });
+    }
+
+    /// Blend a color over a rect with rounded corners, with the opacity at each pixel
+    /// center given by `alpha`.
+    pub fn blend_rounded_rect(
+        &mut self,
+        (x, y, w, h): (f32, f32, f32, f32),
+        radius: f32,
+        color: RgbColor,
+        alpha: impl Fn(f32, f32) -> u8,
+    ) {
+        let x0 = x.max(0.0) as i32;
+        let y0 = y.max(0.0) as i32;
+        let x1 = (x + w).min(self.width as f32) as i32;
+        let y1 = (y + h).min(self.height as f32) as i32;
+
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let (cx, cy) = (px as f32 + 0.5, py as f32 + 0.5);
+
+                if !rounded_rect_contains((x, y, w, h), radius, cx, cy) {
+                    continue;
+                }
+
+                let alpha = alpha(cx, cy);
+
+                if alpha > 0 {
+                    self.blend_pixel(px, py, color, alpha);
+                }
+            }
+        }
+    }
+
+    /// Fill a rect with antialiased rounded corners, the radius clamped to half its
+    /// shorter side.
+    pub fn fill_rounded_rect(
+        &mut self,
+        (x, y, w, h): (f32, f32, f32, f32),
+        radius: f32,
+        color: RgbColor,
+    ) {
+        let (left, top) = (x.floor(), y.floor());
+        let (right, bottom) = ((x + w).floor(), (y + h).floor());
+        let radius = radius
+            .min((right - left) / 2.0)
+            .min((bottom - top) / 2.0)
+            .max(0.0);
+
+        let (clip_x0, clip_y0, clip_x1, clip_y1) = self.clip;
+        let x0 = (left as i32).max(clip_x0);
+        let y0 = (top as i32).max(clip_y0);
+        let x1 = (right as i32).min(clip_x1);
+        let y1 = (bottom as i32).min(clip_y1);
+
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+
+        let solid = color.to_xrgb();
+
+        for py in y0..y1 {
+            // How far into a corner this row is, and so each pixel in it: positive only
+            // beside the arcs, where coverage comes from the distance to the arc's center
+            let cy = py as f32 + 0.5;
+            let dy = (top + radius - cy).max(cy - (bottom - radius));
+
+            if dy <= 0.0 && color.is_opaque() {
+                let row = (py as u32 * self.width) as usize;
+                self.pixels[row + x0 as usize..row + x1 as usize].fill(solid);
+                continue;
+            }
+
+            for px in x0..x1 {
+                let cx = px as f32 + 0.5;
+                let dx = (left + radius - cx).max(cx - (right - radius));
+                let coverage = if dx > 0.0 && dy > 0.0 {
+                    (radius - dx.hypot(dy) + 0.5).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+
+                if coverage >= 1.0 && color.is_opaque() {
+                    self.pixels[(py as u32 * self.width + px as u32) as usize] = solid;
+                } else if coverage > 0.0 {
+                    self.blend_pixel(px, py, color, (coverage * 255.0) as u8);
+                }
+            }
+        }
+    }
+
+    /// Blend a color over the pixels of a bounding box (x0, y0, x1, y1) by the coverage
+    /// (0 to 1) of a shape at each pixel center.
+    fn blend_shape(
+        &mut self,
+        (x0, y0, x1, y1): (f32, f32, f32, f32),
+        color: RgbColor,
+        coverage: impl Fn(f32, f32) -> f32,
+    ) {
+        let x0 = x0.floor().max(0.0) as i32;
+        let y0 = y0.floor().max(0.0) as i32;
+        let x1 = x1.ceil().min(self.width as f32) as i32;
+        let y1 = y1.ceil().min(self.height as f32) as i32;
+
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let coverage = coverage(px as f32 + 0.5, py as f32 + 0.5).clamp(0.0, 1.0);
+
+                if coverage > 0.0 {
+                    self.blend_pixel(px, py, color, (coverage * 255.0) as u8);
+                }
+            }
+        }
+    }
+
+    /// Stroke an antialiased arc around (cx, cy) with square ends, centered on `radius`.
+    /// Angles are in degrees clockwise from 12 o'clock, in either order.
+    pub fn stroke_arc(
+        &mut self,
+        (cx, cy): (f32, f32),
+        radius: f32,
+        width: f32,
+        (start, end): (f32, f32),
+        color: RgbColor,
+    ) {
+        let (start, end) = (start.min(end), start.max(end));
+        let sweep = (end - start).min(360.0);
+        let outer = radius + width / 2.0;
+        let inner = (radius - width / 2.0).max(0.0);
+
+        if sweep <= 0.0 || width <= 0.0 {
+            return;
+        }
+
+        let bounds = (
+            cx - outer - 1.0,
+            cy - outer - 1.0,
+            cx + outer + 1.0,
+            cy + outer + 1.0,
+        );
+
+        self.blend_shape(bounds, color, |px, py| {
+            let (dx, dy) = (px - cx, py - cy);
+            let distance = dx.hypot(dy);
+            let radial = (outer - distance + 0.5).min(distance - inner + 0.5);
+
+            if radial <= 0.0 {
+                return 0.0;
+            }
+
+            if sweep >= 360.0 {
+                return radial;
+            }
+
+            // How far round from the start this pixel is, and its distance in pixels from
+            // the nearest end: positive inside the arc, negative beyond it
+            let angle = dx.atan2(-dy).to_degrees();
+            let offset = (angle - start).rem_euclid(360.0);
+            let inside = if offset <= sweep {
+                offset.min(sweep - offset)
+            } else {
+                -(offset - sweep).min(360.0 - offset)
+            };
+
+            radial.min(inside.to_radians() * distance + 0.5)
+        });
+    }
+
+    /// Stroke an antialiased line with square ends flush with its endpoints.
+    pub fn stroke_line(
+        &mut self,
+        (x0, y0): (f32, f32),
+        (x1, y1): (f32, f32),
+        width: f32,
+        color: RgbColor,
+    ) {
+        let half = width / 2.0;
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let length_sq = dx * dx + dy * dy;
+        let bounds = (
+            x0.min(x1) - half - 1.0,
+            y0.min(y1) - half - 1.0,
+            x0.max(x1) + half + 1.0,
+            y0.max(y1) + half + 1.0,
+        );
+
+        if length_sq == 0.0 {
+            return;
+        }
+
+        self.blend_shape(bounds, color, |px, py| {
+            // Position along the line (0 to 1) and distance either side of it
+            let along = ((px - x0) * dx + (py - y0) * dy) / length_sq;
+            let length = length_sq.sqrt();
+            let across = ((px - x0) * dy - (py - y0) * dx).abs() / length;
+            let ends = (along * length + 0.5).min((1.0 - along) * length + 0.5);
+
+            (half - across + 0.5).min(ends)
+        });
+    }
+
+    /// Fill an antialiased circle.
+    pub fn fill_circle(&mut self, (cx, cy): (f32, f32), radius: f32, color: RgbColor) {
+        let bounds = (
+            cx - radius - 1.0,
+            cy - radius - 1.0,
+            cx + radius + 1.0,
+            cy + radius + 1.0,
+        );
+
+        self.blend_shape(bounds, color, |px, py| {
+            radius - (px - cx).hypot(py - cy) + 0.5
+        });
+    }
+
+    /// Copy a rect of pixels out, clipped to the canvas, with its clipped size.
+    pub fn copy_rect(&self, x: i32, y: i32, w: u32, h: u32) -> (Vec<u32>, u32, u32) {
+        let x0 = x.clamp(0, self.width as i32) as u32;
+        let y0 = y.clamp(0, self.height as i32) as u32;
+        let x1 = (x + w as i32).clamp(0, self.width as i32) as u32;
+        let y1 = (y + h as i32).clamp(0, self.height as i32) as u32;
+        let mut pixels = Vec::with_capacity(((x1 - x0) * (y1 - y0)) as usize);
+
+        for row in y0..y1 {
+            let start = (row * self.width) as usize;
+            pixels.extend_from_slice(&self.pixels[start + x0 as usize..start + x1 as usize]);
+        }
+
+        (pixels, x1 - x0, y1 - y0)
+    }
+
     /// Returns the raw XRGB8888 pixel buffer for direct memcpy to display.
     pub fn as_xrgb_bytes(&self) -> &[u8] {
         unsafe {
@@ -95,9 +440,18 @@ impl Canvas {
     }
 
     pub fn draw_to_drawtarget(&self, drawable: &mut impl DrawTarget<Color = Rgb888>) {
+        self.draw_to_drawtarget_with(drawable, |pixel| pixel);
+    }
+
+    /// Draw every pixel to `drawable`, passing each through `filter` on the way.
+    pub fn draw_to_drawtarget_with(
+        &self,
+        drawable: &mut impl DrawTarget<Color = Rgb888>,
+        filter: impl Fn(u32) -> u32,
+    ) {
         for y in 0..self.height {
             for x in 0..self.width {
-                let px = self.pixels[(y * self.width + x) as usize];
+                let px = filter(self.pixels[(y * self.width + x) as usize]);
                 let _ = Pixel(
                     Point::new(x as i32, y as i32),
                     Rgb888::new((px >> 16) as u8, (px >> 8) as u8, px as u8),
@@ -107,19 +461,31 @@ impl Canvas {
         }
     }
 
+    /// Draw `text` set as `run` describes, wrapping at `max_width` if given and aligning
+    /// within `container_width`. Each line is a paragraph going the run's direction,
+    /// reordered for display where it mixes directions.
     pub fn draw_text(
         &mut self,
-        font: &Font,
+        run: &TextRun,
         text: &str,
-        font_size: f32,
-        color: RgbColor,
         start_x: f32,
         start_y: f32,
         max_width: Option<f32>,
-        text_align: TextAlign,
         container_width: f32,
     ) {
+        let TextRun {
+            fonts,
+            font_size,
+            color,
+            align: text_align,
+            direction,
+            line_height,
+            letter_spacing,
+            decoration,
+        } = *run;
         let mut text_layout = TextLayout::new(CoordinateSystem::PositiveYDown);
+        let normal = normal_line_height(fonts[0], font_size);
+        let line_height = line_height.resolve(font_size, normal);
 
         let horizontal_align = match text_align {
             TextAlign::Left => HorizontalAlign::Left,
@@ -134,32 +500,216 @@ impl Canvas {
             max_width
         };
 
+        // Share any extra height above and below each line, as CSS does
+        let start_y = start_y + (line_height - normal) / 2.0;
+
+        #[cfg(feature = "shaping")]
+        if shaping::can_shape(fonts[0]) {
+            let lines = match max_width {
+                Some(max_width) => wrap_lines(fonts, text, font_size, letter_spacing, max_width),
+                None => text.split('\n').map(str::to_string).collect(),
+            };
+            let ascent = fonts[0]
+                .horizontal_line_metrics(font_size)
+                .map_or(font_size, |m| m.ascent.ceil());
+
+            for (i, line) in lines.iter().enumerate() {
+                let glyphs = shaping::shape(fonts, line, font_size, direction).unwrap_or_default();
+                let width: f32 = glyphs.iter().map(|g| g.advance + letter_spacing).sum();
+                let mut x = start_x
+                    + match text_align {
+                        TextAlign::Left => 0.0,
+                        TextAlign::Center => ((container_width - width) / 2.0).floor(),
+                        TextAlign::Right => (container_width - width).floor(),
+                    };
+                let baseline = start_y + ascent + i as f32 * line_height;
+                let line_start = x;
+
+                for glyph in &glyphs {
+                    let font = font_fallback::by_hash(fonts, glyph.font_hash);
+                    let key = GlyphRasterConfig {
+                        glyph_index: glyph.glyph_index,
+                        px: font_size,
+                        font_hash: glyph.font_hash,
+                    };
+                    let raster = glyph_cache::rasterize(font, key);
+                    let metrics = raster.metrics;
+                    let px = (x + glyph.x_offset + metrics.xmin as f32).floor();
+                    let py =
+                        (baseline - glyph.y_offset - metrics.height as f32 - metrics.ymin as f32)
+                            .floor();
+
+                    self.blend_coverage(&raster, px as i32, py as i32, color);
+                    x += glyph.advance + letter_spacing;
+                }
+
+                if !glyphs.is_empty() {
+                    let line_end = x - letter_spacing;
+                    self.draw_decoration(
+                        fonts[0],
+                        font_size,
+                        decoration,
+                        (line_start, line_end),
+                        baseline,
+                        color,
+                    );
+                }
+            }
+
+            return;
+        }
+
+        // fontdue knows nothing of letter spacing or bidi, so break lines with it counted
+        // first
+        let wrapped;
+        let text = match max_width {
+            Some(max_width) if wraps_by_word(fonts, text, letter_spacing, direction) => {
+                wrapped = wrap_lines(fonts, text, font_size, letter_spacing, max_width).join("\n");
+                wrapped.as_str()
+            }
+            _ => text,
+        };
+
+        // Then put each line in display order, for fontdue to lay out left to right
+        let visual;
+        let text = if bidi::needs_reordering(text, direction) {
+            visual = bidi::visual_lines(text, direction);
+            visual.as_str()
+        } else {
+            text
+        };
+
         text_layout.reset(&LayoutSettings {
             max_width: layout_width,
             horizontal_align,
+            line_height: line_height_scale(line_height, normal),
             ..LayoutSettings::default()
         });
 
-        text_layout.append(
-            std::slice::from_ref(font),
-            &TextStyle::new(text, font_size, 0),
-        );
+        append_text(&mut text_layout, fonts, text, font_size);
+
+        // fontdue knows nothing of kerning either, so pull kerned pairs together and
+        // spread letters out line by line, keeping each aligned as fontdue placed it
+        let glyphs = text_layout.glyphs();
 
-        for glyph in text_layout.glyphs() {
-            if glyph.width == 0 || glyph.height == 0 {
+        for line in text_layout.lines().into_iter().flatten() {
+            let Some(line_glyphs) = glyphs.get(line.glyph_start..=line.glyph_end) else {
                 continue;
+            };
+
+            let (offsets, extra) = spacing_offsets(fonts, line_glyphs, letter_spacing);
+            let x = start_x
+                - match text_align {
+                    TextAlign::Left => 0.0,
+                    TextAlign::Center => extra / 2.0,
+                    TextAlign::Right => extra,
+                };
+
+            // From the pen of the first letter to past the last, leaving out spaces the
+            // line broke at
+            let mut extent: Option<(f32, f32)> = None;
+
+            for (glyph, offset) in line_glyphs.iter().zip(offsets) {
+                let font = font_fallback::by_hash(fonts, glyph.key.font_hash);
+                self.draw_glyph(font, glyph, x + offset, start_y, color);
+
+                if !decoration.is_none() && !glyph.char_data.is_whitespace() {
+                    let metrics = font.metrics_indexed(glyph.key.glyph_index, glyph.key.px);
+                    let pen = x + offset + glyph.x - metrics.bounds.xmin;
+                    let (left, _) = *extent.get_or_insert((pen, pen));
+                    extent = Some((left, pen + metrics.advance_width));
+                }
             }
 
-            let (metrics, bitmap) = font.rasterize(glyph.parent, font_size);
+            if let Some(extent) = extent {
+                let baseline = start_y + line.baseline_y;
+                self.draw_decoration(fonts[0], font_size, decoration, extent, baseline, color);
+            }
+        }
+    }
+
+    /// Draw a line of text's underline or strikethrough from `left` to `right`, placed by
+    /// the font's metrics relative to the baseline.
+    fn draw_decoration(
+        &mut self,
+        font: &Font,
+        font_size: f32,
+        decoration: TextDecoration,
+        (left, right): (f32, f32),
+        baseline: f32,
+        color: RgbColor,
+    ) {
+        let bars = [
+            decoration
+                .underline
+                .then(|| decoration::underline(font, font_size)),
+            decoration
+                .line_through
+                .then(|| decoration::strikeout(font, font_size)),
+        ];
+
+        for bar in bars.into_iter().flatten() {
+            let top = (baseline - bar.top).round() as i32;
+            let thickness = bar.thickness.round().max(1.0) as i32;
+
+            for y in top..top + thickness {
+                for x in left.round() as i32..right.round() as i32 {
+                    self.blend_pixel(x, y, color, 255);
+                }
+            }
+        }
+    }
+
+    /// Draw one glyph from a fontdue layout, offset by the layout's origin.
+    pub fn draw_glyph<U: Copy>(
+        &mut self,
+        font: &Font,
+        glyph: &GlyphPosition<U>,
+        start_x: f32,
+        start_y: f32,
+        color: RgbColor,
+    ) {
+        // Color glyphs are bitmaps, often in fonts without outlines to give them a size
+        if (glyph.width == 0 || glyph.height == 0) && !emoji::is_color(font) {
+            return;
+        }
+
+        let raster = glyph_cache::rasterize(font, glyph.key);
+        let mut x = start_x as i32 + glyph.x as i32;
+        let mut y = start_y as i32 + glyph.y as i32;
+
+        // fontdue placed the glyph by its outline, so place the bitmap from the pen and
+        // baseline instead
+        if raster.color.is_some() {
+            let bounds = font
+                .metrics_indexed(glyph.key.glyph_index, glyph.key.px)
+                .bounds;
+            let pen_x = glyph.x - bounds.xmin;
+            let baseline = glyph.y + bounds.height + bounds.ymin;
+            let metrics = raster.metrics;
+
+            x = (start_x + pen_x) as i32 + metrics.xmin;
+            y = (start_y + baseline) as i32 - metrics.height as i32 - metrics.ymin;
+        }
+
+        self.blend_coverage(&raster, x, y, color);
+    }
+
+    /// Blend a rasterized glyph with its top left corner at `(x, y)`. Color glyphs are
+    /// drawn in their own colors.
+    fn blend_coverage(&mut self, raster: &Glyph, x: i32, y: i32, color: RgbColor) {
+        let metrics = raster.metrics;
+
+        if let Some(pixels) = &raster.color {
+            self.blit_rgba(pixels, metrics.width as u32, metrics.height as u32, x, y);
+            return;
+        }
 
-            for row in 0..metrics.height {
-                for col in 0..metrics.width {
-                    let coverage = bitmap[row * metrics.width + col];
-                    if coverage > 0 {
-                        let px = start_x as i32 + glyph.x as i32 + col as i32;
-                        let py = start_y as i32 + glyph.y as i32 + row as i32;
-                        self.blend_pixel(px, py, color, coverage);
-                    }
+        for row in 0..metrics.height {
+            for col in 0..metrics.width {
+                let coverage = raster.coverage[row * metrics.width + col];
+                if coverage > 0 {
+                    self.blend_pixel(x + col as i32, y + row as i32, color, coverage);
                 }
             }
         }
@@ -167,19 +717,29 @@ impl Canvas {
 
     /// Blit non-premultiplied RGBA pixels onto the canvas with alpha blending.
     pub fn blit_rgba(&mut self, data: &[u8], src_w: u32, src_h: u32, dst_x: i32, dst_y: i32) {
-        for row in 0..src_h as i32 {
+        self.blit_rgba_region(data, src_w, (0, 0, src_w, src_h), dst_x, dst_y);
+    }
+
+    /// Blit the `region` (x, y, w, h) of a non-premultiplied RGBA image `src_w` pixels
+    /// wide, like one frame of a sprite sheet.
+    pub fn blit_rgba_region(
+        &mut self,
+        data: &[u8],
+        src_w: u32,
+        (src_x, src_y, region_w, region_h): (u32, u32, u32, u32),
+        dst_x: i32,
+        dst_y: i32,
+    ) {
+        for row in 0..region_h as i32 {
             let cy = dst_y + row;
-            if cy < 0 || cy >= self.height as i32 {
-                continue;
-            }
 
-            for col in 0..src_w as i32 {
+            for col in 0..region_w as i32 {
                 let cx = dst_x + col;
-                if cx < 0 || cx >= self.width as i32 {
+                if !self.in_clip(cx, cy) {
                     continue;
                 }
 
-                let si = ((row as u32 * src_w + col as u32) * 4) as usize;
+                let si = (((src_y + row as u32) * src_w + src_x + col as u32) * 4) as usize;
                 let a = data[si + 3];
 
                 if a == 0 {
@@ -207,6 +767,132 @@ impl Canvas {
         }
     }
 
+    /// Blit opaque XRGB8888 pixels scaled into the `dst` rect (x, y, w, h) with nearest
+    /// neighbour sampling, only touching pixels inside the `clip` rect. Draws nothing if
+    /// `src` holds fewer than `src_w` x `src_h` pixels.
+    pub fn blit_scaled_xrgb(
+        &mut self,
+        src: &[u32],
+        src_w: u32,
+        src_h: u32,
+        dst: (f32, f32, f32, f32),
+        clip: (f32, f32, f32, f32),
+    ) {
+        self.blend_scaled_xrgb(src, src_w, src_h, dst, clip, 255);
+    }
+
+    /// Like `blit_scaled_xrgb`, but blending the pixels over the canvas with a constant
+    /// opacity.
+    pub fn blend_scaled_xrgb(
+        &mut self,
+        src: &[u32],
+        src_w: u32,
+        src_h: u32,
+        dst: (f32, f32, f32, f32),
+        clip: (f32, f32, f32, f32),
+        alpha: u8,
+    ) {
+        let (dst_x, dst_y, dst_w, dst_h) = dst;
+
+        if src_w == 0 || src_h == 0 || dst_w <= 0.0 || dst_h <= 0.0 || alpha == 0 {
+            return;
+        }
+
+        // Sampling indexes up to the last row, so a short buffer would panic part way
+        if (src_w as usize)
+            .checked_mul(src_h as usize)
+            .is_none_or(|count| src.len() < count)
+        {
+            return;
+        }
+
+        let x0 = (dst_x.max(clip.0).max(0.0) as i32).max(self.clip.0);
+        let y0 = (dst_y.max(clip.1).max(0.0) as i32).max(self.clip.1);
+        let x1 =
+            ((dst_x + dst_w).min(clip.0 + clip.2).min(self.width as f32) as i32).min(self.clip.2);
+        let y1 =
+            ((dst_y + dst_h).min(clip.1 + clip.3).min(self.height as f32) as i32).min(self.clip.3);
+
+        let step_x = src_w as f32 / dst_w;
+        let step_y = src_h as f32 / dst_h;
+
+        for cy in y0..y1 {
+            let sy = (((cy as f32 - dst_y) * step_y) as u32).min(src_h - 1);
+            let src_row = (sy * src_w) as usize;
+            let dst_row = (cy as u32 * self.width) as usize;
+
+            for cx in x0..x1 {
+                let sx = (((cx as f32 - dst_x) * step_x) as u32).min(src_w - 1);
+                let pixel = src[src_row + sx as usize];
+
+                if alpha == 255 {
+                    self.pixels[dst_row + cx as usize] = pixel;
+                } else {
+                    let color = RgbColor::rgb((pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8);
+                    self.blend_pixel(cx, cy, color, alpha);
+                }
+            }
+        }
+    }
+
+    /// Blend pixels onto the canvas through `map`, from source pixel coordinates to the
+    /// canvas, with a constant opacity. Each canvas pixel takes the source pixel its
+    /// center maps back into, if any.
+    pub fn blend_affine_xrgb(
+        &mut self,
+        src: &[u32],
+        src_w: u32,
+        src_h: u32,
+        map: Affine,
+        alpha: u8,
+    ) {
+        let Some(inverse) = map.invert() else {
+            return;
+        };
+
+        if src_w == 0 || src_h == 0 || alpha == 0 {
+            return;
+        }
+
+        let (w, h) = (src_w as f32, src_h as f32);
+        let corners = [(0.0, 0.0), (w, 0.0), (0.0, h), (w, h)].map(|corner| map.apply(corner));
+        let (min_x, max_x) = corners
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(min, max), &(x, _)| {
+                (min.min(x), max.max(x))
+            });
+        let (min_y, max_y) = corners
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(min, max), &(_, y)| {
+                (min.min(y), max.max(y))
+            });
+
+        let (clip_x0, clip_y0, clip_x1, clip_y1) = self.clip;
+        let x0 = (min_x.floor() as i32).max(clip_x0);
+        let y0 = (min_y.floor() as i32).max(clip_y0);
+        let x1 = (max_x.ceil() as i32).min(clip_x1);
+        let y1 = (max_y.ceil() as i32).min(clip_y1);
+
+        for cy in y0..y1 {
+            for cx in x0..x1 {
+                let (sx, sy) = inverse.apply((cx as f32 + 0.5, cy as f32 + 0.5));
+
+                if sx < 0.0 || sy < 0.0 || sx >= w || sy >= h {
+                    continue;
+                }
+
+                let pixel = src[(sy as u32 * src_w + sx as u32) as usize];
+
+                if alpha == 255 {
+                    self.pixels[(cy as u32 * self.width + cx as u32) as usize] = pixel;
+                } else {
+                    let color = RgbColor::rgb((pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8);
+                    self.blend_pixel(cx, cy, color, alpha);
+                }
+            }
+        }
+    }
+
     /// Blit premultiplied RGBA pixels onto the canvas with alpha blending.
     pub fn blit_premultiplied_rgba(
         &mut self,
@@ -218,13 +904,10 @@ impl Canvas {
     ) {
         for row in 0..src_h as i32 {
             let cy = dst_y + row;
-            if cy < 0 || cy >= self.height as i32 {
-                continue;
-            }
 
             for col in 0..src_w as i32 {
                 let cx = dst_x + col;
-                if cx < 0 || cx >= self.width as i32 {
+                if !self.in_clip(cx, cy) {
                     continue;
                 }
 
@@ -255,6 +938,22 @@ impl Canvas {
     }
 }
 
+/// Whether the point (cx, cy) is inside a rect with rounded corners.
+pub fn rounded_rect_contains(
+    (x, y, w, h): (f32, f32, f32, f32),
+    radius: f32,
+    cx: f32,
+    cy: f32,
+) -> bool {
+    let radius = radius.min(w / 2.0).min(h / 2.0).max(0.0);
+
+    // Distance into the corner region, outside the inner rect the corners round
+    let dx = (x + radius - cx).max(cx - (x + w - radius)).max(0.0);
+    let dy = (y + radius - cy).max(cy - (y + h - radius)).max(0.0);
+
+    dx.hypot(dy) <= radius
+}
+
 impl DrawTarget for Canvas {
     type Color = Rgb888;
     type Error = core::convert::Infallible;
@@ -266,7 +965,7 @@ impl DrawTarget for Canvas {
         for Pixel(point, color) in pixels {
             let x = point.x;
             let y = point.y;
-            if x >= 0 && x < self.width as i32 && y >= 0 && y < self.height as i32 {
+            if self.in_clip(x, y) {
                 self.pixels[(y as u32 * self.width + x as u32) as usize] =
                     to_xrgb(color.r(), color.g(), color.b());
             }
@@ -276,7 +975,14 @@ impl DrawTarget for Canvas {
 
     fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
         let px = to_xrgb(color.r(), color.g(), color.b());
-        let clipped = area.intersection(&Rectangle::new(Point::zero(), self.size()));
+        let (x0, y0, x1, y1) = self.clip;
+
+        if x1 <= x0 || y1 <= y0 {
+            return Ok(());
+        }
+
+        let clip = Rectangle::with_corners(Point::new(x0, y0), Point::new(x1 - 1, y1 - 1));
+        let clipped = area.intersection(&clip);
 
         if let Some(bottom_right) = clipped.bottom_right() {
             let x0 = clipped.top_left.x as u32;
@@ -305,3 +1011,314 @@ impl OriginDimensions for Canvas {
         Size::new(self.width, self.height)
     }
 }
+
+/// Height of a line of text from the font's metrics, used for `lineHeight: "normal"`.
+pub fn normal_line_height(font: &Font, font_size: f32) -> f32 {
+    font.horizontal_line_metrics(font_size)
+        .map(|m| m.ascent - m.descent + m.line_gap)
+        .unwrap_or(font_size)
+}
+
+/// Width of `text` on one line, kerned, with `letter_spacing` after every character as
+/// CSS does. Text with line breaks is as wide as its widest line.
+pub fn text_width(fonts: &[&Font], text: &str, font_size: f32, letter_spacing: f32) -> f32 {
+    if text.contains('\n') {
+        return text
+            .split('\n')
+            .map(|line| text_width(fonts, line, font_size, letter_spacing))
+            .fold(0.0, f32::max);
+    }
+
+    #[cfg(feature = "shaping")]
+    if let Some(glyphs) = shaping::shape(fonts, text, font_size, Direction::Ltr) {
+        return glyphs.iter().map(|g| g.advance + letter_spacing).sum();
+    }
+
+    let mut width = 0.0;
+    let mut previous: Option<(char, &Font)> = None;
+
+    for c in text.chars() {
+        let font = font_fallback::pick(fonts, c);
+
+        // Pairs are only kerned within a font
+        if let Some((left, left_font)) = previous
+            && std::ptr::eq(left_font, font)
+        {
+            width += font.horizontal_kern(left, c, font_size).unwrap_or(0.0);
+        }
+
+        width += glyph_cache::advance(font, c, font_size) + letter_spacing;
+        previous = Some((c, font));
+    }
+
+    width
+}
+
+/// How far right of where fontdue put it each glyph on a line goes once kerned and
+/// letter spaced, and how much wider that makes the line.
+fn spacing_offsets<U: Copy>(
+    fonts: &[&Font],
+    glyphs: &[GlyphPosition<U>],
+    letter_spacing: f32,
+) -> (Vec<f32>, f32) {
+    let mut offsets = Vec::with_capacity(glyphs.len());
+    let mut offset = 0.0;
+    let mut previous: Option<&GlyphPosition<U>> = None;
+
+    for glyph in glyphs {
+        if let Some(left) = previous
+            && left.key.font_hash == glyph.key.font_hash
+            && !left.parent.is_control()
+            && !glyph.parent.is_control()
+        {
+            let font = font_fallback::by_hash(fonts, glyph.key.font_hash);
+            offset += font
+                .horizontal_kern_indexed(left.key.glyph_index, glyph.key.glyph_index, glyph.key.px)
+                .unwrap_or(0.0);
+        }
+
+        offsets.push(offset);
+
+        if !glyph.parent.is_control() {
+            offset += letter_spacing;
+        }
+
+        previous = Some(glyph);
+    }
+
+    (offsets, offset)
+}
+
+/// Whether `text` is broken into lines with `wrap_lines` rather than by fontdue, which
+/// knows nothing of letter spacing, shaping or lines that need reordering.
+#[cfg_attr(not(feature = "shaping"), allow(unused_variables))]
+pub fn wraps_by_word(
+    fonts: &[&Font],
+    text: &str,
+    letter_spacing: f32,
+    direction: Direction,
+) -> bool {
+    #[cfg(feature = "shaping")]
+    if shaping::can_shape(fonts[0]) {
+        return true;
+    }
+
+    letter_spacing != 0.0 || bidi::needs_reordering(text, direction)
+}
+
+/// The widest word in `text`, which is as narrow as it can wrap: lines only break at
+/// whitespace, so a word longer than its container overflows it rather than splitting.
+pub fn min_content_width(fonts: &[&Font], text: &str, font_size: f32, letter_spacing: f32) -> f32 {
+    text.split_whitespace()
+        .map(|word| text_width(fonts, word, font_size, letter_spacing))
+        .fold(0.0, f32::max)
+}
+
+/// Break `text` into lines no wider than `max_width`, at whitespace, counting letter
+/// spacing. For text fontdue can't wrap by itself. Line breaks in `text` always start a
+/// new line, and whitespace is kept as it is except where a line breaks.
+pub fn wrap_lines(
+    fonts: &[&Font],
+    text: &str,
+    font_size: f32,
+    letter_spacing: f32,
+    max_width: f32,
+) -> Vec<String> {
+    let space = text_width(fonts, " ", font_size, letter_spacing);
+    let mut lines = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        let mut width = 0.0;
+        let mut rest = paragraph;
+
+        // Each word with the whitespace before it, which is dropped if the line breaks
+        // there, or kept to indent the paragraph's first line
+        while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+            let (gap, after) = rest.split_at(start);
+            let end = after.find(char::is_whitespace).unwrap_or(after.len());
+            let (word, after) = after.split_at(end);
+            rest = after;
+
+            let gap_width = match gap {
+                "" => 0.0,
+                " " => space,
+                gap => text_width(fonts, gap, font_size, letter_spacing),
+            };
+            let word_width = text_width(fonts, word, font_size, letter_spacing);
+
+            if !line.is_empty() && width + gap_width + word_width > max_width + 1.0 {
+                lines.push(std::mem::take(&mut line));
+                width = 0.0;
+            } else {
+                line.push_str(gap);
+                width += gap_width;
+            }
+
+            line.push_str(word);
+            width += word_width;
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// `text` as it's measured and drawn: each tab widened with spaces to the next tab stop,
+/// every `tab_size` widths of a space from the start of its line, and `\r\n` line
+/// endings made `\n`. Line breaks in text kept to one line by `single_line` become
+/// spaces.
+pub fn expand_whitespace<'a>(
+    fonts: &[&Font],
+    text: &'a str,
+    font_size: f32,
+    letter_spacing: f32,
+    tab_size: f32,
+    single_line: bool,
+) -> Cow<'a, str> {
+    if !(text.contains(['\t', '\r']) || (single_line && text.contains('\n'))) {
+        return Cow::Borrowed(text);
+    }
+
+    let space = text_width(fonts, " ", font_size, letter_spacing);
+    let stop = space * tab_size;
+    let mut expanded = String::with_capacity(text.len());
+    let mut line_start = 0;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' if single_line => expanded.push(' '),
+            '\n' | '\r' => {
+                expanded.push('\n');
+                line_start = expanded.len();
+            }
+            '\t' if stop > 0.0 => {
+                let width = text_width(fonts, &expanded[line_start..], font_size, letter_spacing);
+
+                // A tab stop less than half a space away is skipped, as CSS does
+                let mut next = ((width / stop).floor() + 1.0) * stop;
+                if next - width < space / 2.0 {
+                    next += stop;
+                }
+
+                let spaces = ((next - width) / space).round() as usize;
+                expanded.extend(std::iter::repeat_n(' ', spaces));
+            }
+            // A tab size of 0 takes tabs out altogether
+            '\t' => {}
+            c => expanded.push(c),
+        }
+    }
+
+    Cow::Owned(expanded)
+}
+
+/// Lay out `text` in runs, each in the first font of the chain `fonts` that has its
+/// characters. fontdue keeps wrapping and alignment going across the runs.
+pub fn append_text(layout: &mut TextLayout, fonts: &[&Font], text: &str, font_size: f32) {
+    let mut run: Option<(usize, &Font)> = None;
+
+    for (i, c) in text.char_indices() {
+        let font = font_fallback::pick(fonts, c);
+
+        match run {
+            Some((_, current)) if std::ptr::eq(current, font) => {}
+            Some((start, current)) => {
+                let style = TextStyle::new(&text[start..i], font_size, 0);
+                layout.append(std::slice::from_ref(current), &style);
+                run = Some((i, font));
+            }
+            None => run = Some((i, font)),
+        }
+    }
+
+    if let Some((start, current)) = run {
+        let style = TextStyle::new(&text[start..], font_size, 0);
+        layout.append(std::slice::from_ref(current), &style);
+    }
+}
+
+/// `text` cut short with an ellipsis to fit in `max_width` on one line, or `None` if it
+/// already fits. Falls back to three dots for fonts without "…".
+pub fn truncate_with_ellipsis(
+    fonts: &[&Font],
+    text: &str,
+    font_size: f32,
+    letter_spacing: f32,
+    max_width: f32,
+) -> Option<String> {
+    let advance = |c: char| {
+        glyph_cache::advance(font_fallback::pick(fonts, c), c, font_size) + letter_spacing
+    };
+
+    if text.chars().map(advance).sum::<f32>() <= max_width + 1.0 {
+        return None;
+    }
+
+    let ellipsis = ellipsis(fonts);
+    let mut width = ellipsis.chars().map(advance).sum::<f32>();
+    let mut end = 0;
+
+    for (i, c) in text.char_indices() {
+        width += advance(c);
+
+        if width > max_width {
+            break;
+        }
+
+        end = i + c.len_utf8();
+    }
+
+    Some(format!("{}{}", text[..end].trim_end(), ellipsis))
+}
+
+/// "…", or three dots for fonts without it.
+fn ellipsis(fonts: &[&Font]) -> &'static str {
+    if font_fallback::pick(fonts, '…').lookup_glyph_index('…') != 0 {
+        "…"
+    } else {
+        "..."
+    }
+}
+
+/// The first `max_lines` of `lines` joined into text, for `maxLines`. With `ellipsis`,
+/// the last line kept ends in "…" if any were left out, and is truncated to make room
+/// for it, or to fit `max_width`.
+pub fn clamp_lines(
+    fonts: &[&Font],
+    mut lines: Vec<String>,
+    font_size: f32,
+    letter_spacing: f32,
+    max_width: f32,
+    max_lines: usize,
+    ellipsis: bool,
+) -> String {
+    let cut = lines.len() > max_lines;
+    lines.truncate(max_lines);
+
+    if ellipsis && let Some(last) = lines.last_mut() {
+        let line = if cut {
+            format!("{}{}", last.trim_end(), self::ellipsis(fonts))
+        } else {
+            std::mem::take(last)
+        };
+
+        *last = truncate_with_ellipsis(fonts, &line, font_size, letter_spacing, max_width)
+            .unwrap_or(line);
+    }
+
+    lines.join("\n")
+}
+
+/// fontdue's `line_height` setting for a line height in pixels, as a multiple of the
+/// font's own.
+pub fn line_height_scale(line_height: f32, normal: f32) -> f32 {
+    if normal > 0.0 {
+        line_height / normal
+    } else {
+        1.0
+    }
+}
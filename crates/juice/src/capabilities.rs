@@ -0,0 +1,167 @@
+use rquickjs::{Ctx, IntoJs, Object, Value};
+
+/// Version of the contract between the renderer and the JS framework: the `dom` and
+/// `renderer` globals, node types and style properties. Bump it when something the JS
+/// side relies on changes incompatibly, so bundles can adapt to older firmware.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Element tags with native behaviour beyond a plain box. Any other tag is laid out and
+/// drawn like `box`.
+const NODE_TYPES: &[&str] = &[
+    "document",
+    "box",
+    "skeleton",
+    "svg",
+    "img",
+    "image",
+    "animatedsprite",
+    "video",
+    "surface",
+    "input",
+    "wheelpicker",
+    "datepicker",
+    "timepicker",
+    "gauge",
+    "chart",
+    "table",
+    "markdown",
+    "scanner",
+    "bound",
+    #[cfg(feature = "lottie")]
+    "lottie",
+];
+
+/// Style properties the renderer understands, shorthands included.
+const STYLE_PROPERTIES: &[&str] = &[
+    "alignContent",
+    "alignItems",
+    "alignSelf",
+    "background",
+    "borderBottomWidth",
+    "borderColor",
+    "borderLeftWidth",
+    "borderRadius",
+    "borderRightWidth",
+    "borderTopWidth",
+    "borderWidth",
+    "boxShadow",
+    "boxSizing",
+    "color",
+    "columnGap",
+    "direction",
+    "display",
+    "flex",
+    "flexBasis",
+    "flexDirection",
+    "flexGrow",
+    "flexShrink",
+    "flexWrap",
+    "font",
+    "fontSize",
+    "fontStyle",
+    "fontVariationSettings",
+    "fontWeight",
+    "gap",
+    "gapHeight",
+    "gapWidth",
+    "gridAutoColumns",
+    "gridAutoFlow",
+    "gridAutoRows",
+    "gridColumn",
+    "gridRow",
+    "gridTemplateColumns",
+    "gridTemplateRows",
+    "height",
+    "justifyContent",
+    "justifyItems",
+    "justifySelf",
+    "letterSpacing",
+    "lineHeight",
+    "margin",
+    "marginBottom",
+    "marginLeft",
+    "marginRight",
+    "marginTop",
+    "marginX",
+    "marginY",
+    "maxHeight",
+    "maxLines",
+    "maxWidth",
+    "objectFit",
+    "opacity",
+    "overflow",
+    "overflowX",
+    "overflowY",
+    "padding",
+    "paddingBottom",
+    "paddingLeft",
+    "paddingRight",
+    "paddingTop",
+    "paddingX",
+    "paddingY",
+    "position",
+    "rowGap",
+    "tabSize",
+    "textAlign",
+    "textDecoration",
+    "textOverflow",
+    "transform",
+    "width",
+    "zIndex",
+];
+
+/// Optional modules compiled into this build.
+const FEATURES: &[&str] = &[
+    #[cfg(feature = "sqlite")]
+    "sqlite",
+    #[cfg(feature = "lottie")]
+    "lottie",
+    #[cfg(feature = "lock")]
+    "lock",
+    #[cfg(feature = "launcher")]
+    "launcher",
+    #[cfg(feature = "shaping")]
+    "shaping",
+    #[cfg(feature = "sandbox")]
+    "sandbox",
+];
+
+/// What this build of the renderer supports, exposed to JS as `renderer.capabilities` so
+/// bundles can feature-detect rather than fail on firmware that lacks something.
+#[derive(Debug, Clone, Copy)]
+pub struct Capabilities {
+    pub schema_version: u32,
+    /// The juice crate version
+    pub version: &'static str,
+    pub node_types: &'static [&'static str],
+    pub style_properties: &'static [&'static str],
+    pub features: &'static [&'static str],
+    /// Whether DOM updates can be sent in a binary encoding instead of one call each
+    pub binary_protocol: bool,
+}
+
+impl Capabilities {
+    pub fn current() -> Self {
+        Capabilities {
+            schema_version: SCHEMA_VERSION,
+            version: env!("CARGO_PKG_VERSION"),
+            node_types: NODE_TYPES,
+            style_properties: STYLE_PROPERTIES,
+            features: FEATURES,
+            binary_protocol: false,
+        }
+    }
+}
+
+impl<'js> IntoJs<'js> for Capabilities {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        let capabilities = Object::new(ctx.clone())?;
+        capabilities.set("schemaVersion", self.schema_version)?;
+        capabilities.set("version", self.version)?;
+        capabilities.set("nodeTypes", self.node_types.to_vec())?;
+        capabilities.set("styleProperties", self.style_properties.to_vec())?;
+        capabilities.set("features", self.features.to_vec())?;
+        capabilities.set("binaryProtocol", self.binary_protocol)?;
+        Ok(capabilities.into_value())
+    }
+}
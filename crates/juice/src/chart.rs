@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+
+use crate::canvas::RgbColor;
+
+/// How a `chart` node draws its values.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ChartStyle {
+    #[default]
+    Line,
+    /// A line with the space under it filled
+    Area,
+    Bar,
+}
+
+impl ChartStyle {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "area" => ChartStyle::Area,
+            "bar" => ChartStyle::Bar,
+            _ => ChartStyle::Line,
+        }
+    }
+}
+
+/// State of a `chart` node: its values, oldest first, and how to scale and draw them.
+#[derive(Debug, Clone)]
+pub struct Chart {
+    values: VecDeque<f32>,
+    /// Values to keep, dropping the oldest as new ones are appended. Also fixes the
+    /// spacing of points, so a stream fills the chart from the right and scrolls left.
+    pub capacity: Option<usize>,
+    pub style: ChartStyle,
+    /// Ends of the value axis. Each defaults to the lowest or highest value shown.
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+    /// Labelled intervals along the value axis. With 0, the default, no axis is drawn,
+    /// making a sparkline.
+    pub ticks: u32,
+    pub line_width: f32,
+    /// Color of bars and the area under the line. Defaults to the text color.
+    pub fill_color: Option<RgbColor>,
+}
+
+impl Default for Chart {
+    fn default() -> Self {
+        Chart {
+            values: VecDeque::new(),
+            capacity: None,
+            style: ChartStyle::default(),
+            min: None,
+            max: None,
+            ticks: 0,
+            line_width: 2.0,
+            fill_color: None,
+        }
+    }
+}
+
+impl Chart {
+    /// Replace every value.
+    pub fn set_values(&mut self, values: impl IntoIterator<Item = f32>) {
+        self.values.clear();
+        self.append(values);
+    }
+
+    /// Add values to the end, dropping the oldest beyond the capacity. Non-finite
+    /// values are skipped.
+    pub fn append(&mut self, values: impl IntoIterator<Item = f32>) {
+        self.values
+            .extend(values.into_iter().filter(|value| value.is_finite()));
+        self.trim();
+    }
+
+    pub fn set_capacity(&mut self, capacity: Option<usize>) {
+        self.capacity = capacity;
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        if let Some(capacity) = self.capacity {
+            let excess = self.values.len().saturating_sub(capacity);
+            self.values.drain(..excess);
+        }
+    }
+
+    pub fn values(&self) -> &VecDeque<f32> {
+        &self.values
+    }
+
+    /// How many values fit across the chart: the capacity if set, otherwise just the
+    /// values there are.
+    pub fn slots(&self) -> usize {
+        self.capacity.unwrap_or(self.values.len()).max(1)
+    }
+
+    /// The value axis range, widened around a flat line so it still has some height.
+    pub fn range(&self) -> (f32, f32) {
+        let lowest = self.values.iter().copied().fold(f32::INFINITY, f32::min);
+        let highest = self
+            .values
+            .iter()
+            .copied()
+            .fold(f32::NEG_INFINITY, f32::max);
+        let min = self
+            .min
+            .unwrap_or(if lowest.is_finite() { lowest } else { 0.0 });
+        let max = self
+            .max
+            .unwrap_or(if highest.is_finite() { highest } else { 1.0 });
+
+        if max > min {
+            (min, max)
+        } else {
+            (min - 1.0, min + 1.0)
+        }
+    }
+}
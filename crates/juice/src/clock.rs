@@ -0,0 +1,52 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// The current time for timers and animations: the system clock, or a mock clock for a
+/// deterministic engine. Clones share the same time, so a renderer hands one to its tree
+/// and engine, and other renderers keep their own.
+#[derive(Clone, Default)]
+pub struct Clock {
+    /// When the mock clock started, and how far it has been advanced since
+    mock: Rc<Cell<Option<(Instant, Duration)>>>,
+}
+
+impl Clock {
+    pub fn now(&self) -> Instant {
+        match self.mock.get() {
+            Some((start, elapsed)) => start + elapsed,
+            None => Instant::now(),
+        }
+    }
+
+    /// Switch to a mock clock that stands still until `advance_to` is called. Starts it
+    /// again from zero if it's already in use.
+    pub fn use_mock(&self) {
+        self.mock.set(Some((Instant::now(), Duration::ZERO)));
+    }
+
+    /// Go back to the system clock.
+    pub fn use_system(&self) {
+        self.mock.set(None);
+    }
+
+    pub fn is_mock(&self) -> bool {
+        self.mock.get().is_some()
+    }
+
+    /// Move the mock clock forward to `to`. It never goes back, and the system clock is
+    /// left alone.
+    pub fn advance_to(&self, to: Instant) {
+        if let Some((start, elapsed)) = self.mock.get() {
+            self.mock.set(Some((
+                start,
+                elapsed.max(to.saturating_duration_since(start)),
+            )));
+        }
+    }
+
+    /// How long the mock clock has been advanced by.
+    pub fn mock_elapsed(&self) -> Option<Duration> {
+        self.mock.get().map(|(_, elapsed)| elapsed)
+    }
+}
@@ -0,0 +1,143 @@
+use crate::canvas::Canvas;
+
+/// Most rects reported for a frame. Past this, the closest rects are merged so hosts
+/// with a fixed number of clip slots can pass them straight through.
+pub const MAX_DAMAGE_RECTS: usize = 8;
+
+/// A region of the canvas that changed in the last render, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DamageRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl DamageRect {
+    fn union(self, other: DamageRect) -> DamageRect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+
+        DamageRect {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        }
+    }
+
+    /// The pixels a rect `(x, y, w, h)` covers on a canvas `width` by `height`, or `None`
+    /// if none.
+    pub fn clamped((x, y, w, h): (f32, f32, f32, f32), width: u32, height: u32) -> Option<Self> {
+        let left = x.floor().clamp(0.0, width as f32) as u32;
+        let top = y.floor().clamp(0.0, height as f32) as u32;
+        let right = (x + w).ceil().clamp(0.0, width as f32) as u32;
+        let bottom = (y + h).ceil().clamp(0.0, height as f32) as u32;
+
+        (right > left && bottom > top).then_some(DamageRect {
+            x: left,
+            y: top,
+            width: right - left,
+            height: bottom - top,
+        })
+    }
+
+    fn area(self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+}
+
+/// Finds what changed between frames by comparing the canvas against a copy of the last
+/// one. Rows are compared as slices, which is far cheaper than pushing unchanged pixels
+/// over a slow display bus.
+#[derive(Default)]
+pub struct DamageTracker {
+    previous: Vec<u32>,
+}
+
+impl DamageTracker {
+    /// Compare the canvas to the previous frame and remember it for next time. The first
+    /// frame, or one after the canvas is resized, is damaged in full.
+    pub fn diff(&mut self, canvas: &Canvas) -> Vec<DamageRect> {
+        let width = canvas.width as usize;
+
+        if self.previous.len() != canvas.pixels.len() {
+            self.previous = canvas.pixels.clone();
+
+            return vec![DamageRect {
+                x: 0,
+                y: 0,
+                width: canvas.width,
+                height: canvas.height,
+            }];
+        }
+
+        let mut rects: Vec<DamageRect> = Vec::new();
+        // Changed rows are grouped into bands spanning their changed columns
+        let mut band: Option<DamageRect> = None;
+
+        for y in 0..canvas.height as usize {
+            let row = &canvas.pixels[y * width..(y + 1) * width];
+            let previous = &mut self.previous[y * width..(y + 1) * width];
+
+            let first = row.iter().zip(previous.iter()).position(|(a, b)| a != b);
+
+            let Some(first) = first else {
+                rects.extend(band.take());
+                continue;
+            };
+
+            let last = row
+                .iter()
+                .zip(previous.iter())
+                .rposition(|(a, b)| a != b)
+                .unwrap_or(first);
+
+            previous[first..=last].copy_from_slice(&row[first..=last]);
+
+            let changed = DamageRect {
+                x: first as u32,
+                y: y as u32,
+                width: (last - first + 1) as u32,
+                height: 1,
+            };
+
+            band = Some(match band {
+                Some(band) => band.union(changed),
+                None => changed,
+            });
+        }
+
+        rects.extend(band);
+        merge_rects(&mut rects, MAX_DAMAGE_RECTS);
+        rects
+    }
+}
+
+/// Merge neighbouring rects, cheapest first by added area, until at most `max` remain.
+/// Rects are sorted top to bottom and only adjacent pairs are considered.
+pub fn merge_rects(rects: &mut Vec<DamageRect>, max: usize) {
+    rects.sort_by_key(|rect| rect.y);
+
+    while rects.len() > max.max(1) {
+        let (index, _) = rects
+            .windows(2)
+            .enumerate()
+            .map(|(i, pair)| {
+                let merged = pair[0].union(pair[1]);
+                (
+                    i,
+                    merged
+                        .area()
+                        .saturating_sub(pair[0].area() + pair[1].area()),
+                )
+            })
+            .min_by_key(|&(_, cost)| cost)
+            .unwrap();
+
+        let next = rects.remove(index + 1);
+        rects[index] = rects[index].union(next);
+    }
+}
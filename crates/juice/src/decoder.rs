@@ -0,0 +1,578 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, mpsc};
+
+use fontdue::{Font, FontSettings};
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{Options, Tree};
+
+use crate::dom::CachedRaster;
+use crate::fs;
+use crate::memory::MemoryBudget;
+
+/// Most worker threads to decode on, leaving a core for rendering and JS
+const MAX_WORKERS: usize = 4;
+
+/// Work that's too slow to do while drawing a frame.
+pub enum DecodeJob {
+    /// Rasterize SVG markup, with `currentColor` already filled in, to premultiplied RGBA
+    Svg {
+        markup: String,
+        width: u32,
+        height: u32,
+    },
+    /// Decode an image's data URL or asset path, then scale it to straight RGBA at a size
+    Image {
+        src: String,
+        width: u32,
+        height: u32,
+    },
+    /// Scale an already decoded image
+    Resize {
+        data: Arc<[u8]>,
+        img_width: u32,
+        img_height: u32,
+        width: u32,
+        height: u32,
+    },
+}
+
+/// An asset decoded ahead of time with `renderer.preload`, so the screen that uses it
+/// doesn't stutter the first time it's shown. Each is kept until it's used once.
+pub enum Preload {
+    /// A data URL or asset path, decoded into the image cache for `img` nodes with the
+    /// same `src`
+    Image(String),
+    /// Markup, parsed for the next `svg` with the same `markup`. Markup using
+    /// `currentColor` depends on where it's shown, so can't be parsed early.
+    Svg(String),
+    /// A font's data URL, parsed for the next `addFont` with the same contents
+    Font(String),
+}
+
+impl Preload {
+    /// From the `type` and `src` JS gives. `None` for an unknown type.
+    pub fn parse(kind: &str, src: String) -> Option<Self> {
+        match kind {
+            "image" => Some(Preload::Image(src)),
+            "svg" => Some(Preload::Svg(src)),
+            "font" => Some(Preload::Font(src)),
+            _ => None,
+        }
+    }
+}
+
+/// Preloaded assets waiting to be used, keyed by `raster_key` of their source. Images go
+/// in the `ImageCache` instead.
+#[derive(Default)]
+struct Preloaded {
+    svgs: HashMap<u64, Tree>,
+    fonts: HashMap<u64, Font>,
+}
+
+/// A handle on the decoder's preloaded assets, for taking a preloaded font.
+#[derive(Clone, Default)]
+pub struct PreloadCache(Arc<Mutex<Preloaded>>);
+
+impl PreloadCache {
+    /// The font preloaded from `src`, if there is one.
+    pub fn take_font(&self, src: &str) -> Option<Font> {
+        self.0.lock().unwrap().fonts.remove(&raster_key(src))
+    }
+
+    /// Drop every preloaded asset not used yet.
+    pub fn clear(&self) {
+        *self.0.lock().unwrap() = Preloaded::default();
+    }
+
+    fn take_svg(&self, markup: &str) -> Option<Tree> {
+        self.0.lock().unwrap().svgs.remove(&raster_key(markup))
+    }
+
+    fn load(&self, asset: Preload, images: &ImageCache) {
+        match asset {
+            Preload::Image(src) => {
+                images.load(&src);
+            }
+            Preload::Svg(markup) => {
+                if let Ok(tree) = Tree::from_str(&markup, &Options::default()) {
+                    self.0
+                        .lock()
+                        .unwrap()
+                        .svgs
+                        .insert(raster_key(&*markup), tree);
+                }
+            }
+            Preload::Font(src) => match decode_font(&src) {
+                Ok(font) => {
+                    self.0.lock().unwrap().fonts.insert(raster_key(&*src), font);
+                }
+                Err(err) => println!("preload: can't load font: {}", err),
+            },
+        }
+    }
+}
+
+/// Full-size images decoded from their `src`, shared by every node showing one so each is
+/// only decoded once, up to a budget. An image bigger than the whole budget is kept on
+/// its own until another takes its place, so showing it doesn't decode it every frame.
+/// Also where asset paths are read from.
+#[derive(Clone, Default)]
+pub struct ImageCache(Arc<Mutex<Images>>);
+
+/// RGBA pixels and their width and height. Shared, so handing out a cached image doesn't
+/// copy it.
+pub type Pixels = (Arc<[u8]>, u32, u32);
+
+struct Images {
+    /// Keyed by `raster_key` of their source and the asset directory it was read from,
+    /// with when each was last used
+    decoded: HashMap<u64, (Pixels, u64)>,
+    bytes: usize,
+    /// The last image too big for the budget, with its key, not counted in `bytes`
+    oversized: Option<(u64, Pixels)>,
+    budget: usize,
+    /// Counts lookups, to order images by when they were last used
+    uses: u64,
+    asset_dir: Option<PathBuf>,
+}
+
+impl Default for Images {
+    fn default() -> Self {
+        Images {
+            decoded: HashMap::new(),
+            bytes: 0,
+            oversized: None,
+            budget: MemoryBudget::default().images,
+            uses: 0,
+            asset_dir: None,
+        }
+    }
+}
+
+impl Images {
+    /// Drop the least recently used images until they take `bytes` at most.
+    fn trim(&mut self, bytes: usize) {
+        if self.bytes <= bytes {
+            return;
+        }
+
+        let mut by_use: Vec<(u64, u64)> = self
+            .decoded
+            .iter()
+            .map(|(key, (_, used))| (*used, *key))
+            .collect();
+        by_use.sort_unstable_by_key(|(used, _)| *used);
+
+        for (_, key) in by_use {
+            if self.bytes <= bytes {
+                break;
+            }
+
+            if let Some(((data, _, _), _)) = self.decoded.remove(&key) {
+                self.bytes -= data.len();
+            }
+        }
+    }
+}
+
+impl ImageCache {
+    /// Read images whose `src` is a path rather than a data URL from `dir`, or only
+    /// take data URLs with `None`.
+    /// Images already read from the old directory are dropped, since the same paths may
+    /// name different files in the new one.
+    pub fn set_asset_dir(&self, dir: Option<PathBuf>) {
+        let mut images = self.0.lock().unwrap();
+
+        if images.asset_dir != dir {
+            images.decoded.clear();
+            images.bytes = 0;
+            images.oversized = None;
+            images.asset_dir = dir;
+        }
+    }
+
+    /// Change how many bytes of images are kept, dropping the least recently used if
+    /// they're over it.
+    pub fn set_budget(&self, bytes: usize) {
+        let mut images = self.0.lock().unwrap();
+        images.budget = bytes;
+        images.oversized = None;
+        images.trim(bytes);
+    }
+
+    /// Drop the least recently used images until they take `bytes` at most, and any
+    /// image kept for being over the budget.
+    pub fn trim(&self, bytes: usize) {
+        let mut images = self.0.lock().unwrap();
+        images.oversized = None;
+        images.trim(bytes);
+    }
+
+    /// The full-size RGBA pixels and size of the image at `src`, decoded the first time
+    /// it's asked for. `None` if it can't be read or doesn't decode.
+    pub fn load(&self, src: &str) -> Option<Pixels> {
+        let (key, asset_dir) = {
+            let mut images = self.0.lock().unwrap();
+            let key = raster_key((&images.asset_dir, src));
+            images.uses += 1;
+            let uses = images.uses;
+
+            if let Some((image, used)) = images.decoded.get_mut(&key) {
+                *used = uses;
+                return Some(image.clone());
+            }
+
+            if let Some((oversized, image)) = &images.oversized
+                && *oversized == key
+            {
+                return Some(image.clone());
+            }
+
+            (key, images.asset_dir.clone())
+        };
+
+        // Without the lock, so other workers can use the cache meanwhile
+        let (data, width, height) =
+            decode_src(asset_dir.as_deref(), src).filter(|(data, _, _)| !data.is_empty())?;
+        let image: Pixels = (data.into(), width, height);
+
+        let mut images = self.0.lock().unwrap();
+        let bytes = image.0.len();
+
+        if bytes <= images.budget {
+            // Trimming to three quarters leaves room to add images for a while before
+            // the next sort
+            if images.bytes + bytes > images.budget {
+                let keep = (images.budget * 3 / 4).min(images.budget - bytes);
+                images.trim(keep);
+            }
+
+            let uses = images.uses;
+            images.bytes += bytes;
+
+            if let Some(((old, _, _), _)) = images.decoded.insert(key, (image.clone(), uses)) {
+                images.bytes -= old.len();
+            }
+        } else {
+            images.oversized = Some((key, image.clone()));
+        }
+
+        Some(image)
+    }
+}
+
+/// A finished `DecodeJob`, for the node that asked for it.
+pub struct Decoded {
+    pub node_id: u64,
+    /// The `raster_key` the job was requested with
+    pub key: u64,
+    /// `None` if it didn't decode
+    pub raster: Option<CachedRaster>,
+    /// The full-size RGBA from an `Image` job and its size, kept for resizing later
+    pub image: Option<Pixels>,
+}
+
+/// Decodes images and rasterizes SVGs on a pool of worker threads, so a big asset
+/// doesn't hold up the frame it first appears in. Nodes show their previous raster, or
+/// nothing, until theirs is done. Preloads are decoded on the same threads.
+pub struct Decoder {
+    /// `None` when decoding inline
+    jobs: Option<mpsc::Sender<Work>>,
+    finished: mpsc::Receiver<Decoded>,
+    /// Jobs sent to the workers whose results haven't been taken from `finished` yet
+    outstanding: Cell<usize>,
+    preloaded: PreloadCache,
+    images: ImageCache,
+}
+
+enum Work {
+    Job(u64, u64, DecodeJob),
+    Preload(Preload),
+}
+
+impl Decoder {
+    pub fn new(images: ImageCache) -> Self {
+        let workers = std::thread::available_parallelism()
+            .map_or(1, |n| n.get().saturating_sub(1))
+            .clamp(1, MAX_WORKERS);
+
+        let (jobs, queue) = mpsc::channel::<Work>();
+        let queue = Arc::new(Mutex::new(queue));
+        let (finished_tx, finished) = mpsc::channel();
+        let preloaded = PreloadCache::default();
+
+        for _ in 0..workers {
+            let queue = queue.clone();
+            let finished_tx = finished_tx.clone();
+            let preloaded = preloaded.clone();
+            let images = images.clone();
+
+            std::thread::spawn(move || {
+                loop {
+                    // Hold the lock only while waiting, so workers take turns
+                    let Ok(work) = queue.lock().unwrap().recv() else {
+                        break;
+                    };
+
+                    match work {
+                        Work::Job(node_id, key, job) => {
+                            let decoded = run(&preloaded, &images, node_id, key, job);
+
+                            if finished_tx.send(decoded).is_err() {
+                                break;
+                            }
+                        }
+                        Work::Preload(asset) => preloaded.load(asset, &images),
+                    }
+                }
+            });
+        }
+
+        Decoder {
+            jobs: Some(jobs),
+            finished,
+            outstanding: Cell::new(0),
+            preloaded,
+            images,
+        }
+    }
+
+    /// A decoder that does every job as it's requested, for printing and deterministic
+    /// runs where a frame has to be complete.
+    pub fn inline(images: ImageCache) -> Self {
+        let (_, finished) = mpsc::channel();
+
+        Decoder {
+            jobs: None,
+            finished,
+            outstanding: Cell::new(0),
+            preloaded: PreloadCache::default(),
+            images,
+        }
+    }
+
+    /// Start a job for a node. An inline decoder does it straight away and returns the
+    /// result; otherwise it's picked up from `finished` later.
+    pub fn request(&self, node_id: u64, key: u64, job: DecodeJob) -> Option<Decoded> {
+        let Some(jobs) = &self.jobs else {
+            return Some(run(&self.preloaded, &self.images, node_id, key, job));
+        };
+
+        // Workers only stop once the decoder is dropped
+        if jobs.send(Work::Job(node_id, key, job)).is_ok() {
+            self.outstanding.set(self.outstanding.get() + 1);
+        }
+
+        None
+    }
+
+    /// Decode an asset ahead of the node that will use it.
+    pub fn preload(&self, asset: Preload) {
+        match &self.jobs {
+            Some(jobs) => {
+                let _ = jobs.send(Work::Preload(asset));
+            }
+            None => self.preloaded.load(asset, &self.images),
+        }
+    }
+
+    /// Shared with the workers, which fill it in as preloads finish.
+    pub fn preloaded(&self) -> PreloadCache {
+        self.preloaded.clone()
+    }
+
+    /// Jobs finished since the last call.
+    pub fn finished(&self) -> impl Iterator<Item = Decoded> + '_ {
+        self.finished.try_iter().inspect(|_| {
+            self.outstanding
+                .set(self.outstanding.get().saturating_sub(1))
+        })
+    }
+
+    /// Whether every job requested has finished and been taken. Preloads aren't counted.
+    pub fn is_idle(&self) -> bool {
+        self.outstanding.get() == 0
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Decoder::new(ImageCache::default())
+    }
+}
+
+/// Identifies what a raster is drawn from, so the same request isn't made twice and
+/// results for something since changed are dropped.
+pub fn raster_key(source: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn run(
+    preloaded: &PreloadCache,
+    images: &ImageCache,
+    node_id: u64,
+    key: u64,
+    job: DecodeJob,
+) -> Decoded {
+    let (raster, image) = match job {
+        DecodeJob::Svg {
+            markup,
+            width,
+            height,
+        } => {
+            let tree = match preloaded.take_svg(&markup) {
+                Some(tree) => Ok(tree),
+                None => Tree::from_str(&markup, &Options::default()),
+            };
+
+            match tree {
+                Ok(tree) => (rasterize_svg(&tree, width, height), None),
+                Err(err) => {
+                    println!("Error parsing SVG: {:?}", err);
+                    (None, None)
+                }
+            }
+        }
+        DecodeJob::Image { src, width, height } => match images.load(&src) {
+            Some((data, img_width, img_height)) => (
+                resize(&data, (img_width, img_height), (width, height)),
+                Some((data, img_width, img_height)),
+            ),
+            _ => (None, None),
+        },
+        DecodeJob::Resize {
+            data,
+            img_width,
+            img_height,
+            width,
+            height,
+        } => (
+            resize(&data, (img_width, img_height), (width, height)),
+            None,
+        ),
+    };
+
+    Decoded {
+        node_id,
+        key,
+        raster,
+        image,
+    }
+}
+
+fn rasterize_svg(tree: &Tree, width: u32, height: u32) -> Option<CachedRaster> {
+    let mut pixmap = Pixmap::new(width, height)?;
+    let svg_size = tree.size();
+    let sx = width as f32 / svg_size.width();
+    let sy = height as f32 / svg_size.height();
+
+    resvg::render(tree, Transform::from_scale(sx, sy), &mut pixmap.as_mut());
+
+    Some(CachedRaster {
+        data: pixmap.take(),
+        width,
+        height,
+    })
+}
+
+/// Scale straight RGBA to a size, or copy it if it's that size already.
+fn resize(
+    data: &[u8],
+    (img_width, img_height): (u32, u32),
+    (width, height): (u32, u32),
+) -> Option<CachedRaster> {
+    if (img_width, img_height) == (width, height) {
+        return Some(CachedRaster {
+            data: data.to_vec(),
+            width,
+            height,
+        });
+    }
+
+    let image = image::RgbaImage::from_raw(img_width, img_height, data.to_vec())?;
+    let resized =
+        image::imageops::resize(&image, width, height, image::imageops::FilterType::Triangle);
+
+    Some(CachedRaster {
+        data: resized.into_raw(),
+        width,
+        height,
+    })
+}
+
+/// Decode a base64 data URL (`data:image/png;base64,...`) to RGBA pixels and their
+/// size. `None` if it isn't a data URL; an empty image if it doesn't decode.
+pub fn decode_data_url(src: &str) -> Option<(Vec<u8>, u32, u32)> {
+    let base64_data = src
+        .split(',')
+        .nth(1)
+        .and_then(|s| base64::Engine::decode(&base64::engine::general_purpose::STANDARD, s).ok())?;
+
+    Some(decode_bytes(&base64_data))
+}
+
+/// Decode an image's `src`, a data URL or a path inside `asset_dir`, to RGBA pixels and
+/// their size. `None` if it can't be read; an empty image if it doesn't decode.
+pub fn decode_src(asset_dir: Option<&Path>, src: &str) -> Option<(Vec<u8>, u32, u32)> {
+    if src.starts_with("data:") {
+        return decode_data_url(src);
+    }
+
+    let Some(asset_dir) = asset_dir else {
+        println!(
+            "Error loading image {}: not a data URL, and no asset folder",
+            src
+        );
+        return None;
+    };
+
+    let bytes = fs::resolve(asset_dir, src)
+        .map_err(|err| err.message)
+        .and_then(|path| std::fs::read(path).map_err(|err| format!("{}: {}", src, err)));
+
+    match bytes {
+        Ok(bytes) => Some(decode_bytes(&bytes)),
+        Err(err) => {
+            println!("Error loading image {}", err);
+            None
+        }
+    }
+}
+
+/// PNG, JPEG, GIF or WebP to RGBA pixels and their size, or an empty image.
+fn decode_bytes(bytes: &[u8]) -> (Vec<u8>, u32, u32) {
+    match image::load_from_memory(bytes) {
+        Ok(img) => {
+            let rgba = img.to_rgba8();
+            (rgba.to_vec(), rgba.width(), rgba.height())
+        }
+        Err(err) => {
+            println!("Error loading image: {:?}", err);
+            (vec![], 0, 0)
+        }
+    }
+}
+
+/// Parse a font from a base64 data URL.
+pub fn decode_font(src: &str) -> Result<Font, String> {
+    let data = src
+        .split(',')
+        .nth(1)
+        .and_then(|s| base64::Engine::decode(&base64::engine::general_purpose::STANDARD, s).ok())
+        .ok_or("font not a valid base64 URL")?;
+
+    let font = Font::from_bytes(&*data, FontSettings::default()).map_err(str::to_string)?;
+    crate::emoji::register(&font, &data);
+    crate::decoration::register(&font, &data);
+    crate::variable_font::register(&font, &data);
+
+    #[cfg(feature = "shaping")]
+    crate::shaping::register(&font, data);
+
+    Ok(font)
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use fontdue::Font;
+use ttf_parser::Face;
+
+/// Underline and strikeout metrics of fonts with `post` and `OS/2` tables, by
+/// `Font::file_hash`, in ems. fontdue doesn't read them, so they're read from the font's
+/// file when it's loaded. Fonts can be decoded on the decoder's workers, so this is
+/// shared between threads.
+static METRICS: LazyLock<Mutex<HashMap<usize, FontMetrics>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy)]
+struct FontMetrics {
+    underline: Option<(f32, f32)>,
+    strikeout: Option<(f32, f32)>,
+}
+
+/// Where to draw a line through or under text, in pixels from the baseline: `top` is
+/// above it when positive, and the bar runs down from there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bar {
+    pub top: f32,
+    pub thickness: f32,
+}
+
+/// Keep the underline and strikeout metrics from a font's file, so decorations sit
+/// where its designer put them. Fonts loaded with `addFont` or `Renderer::add_font` are
+/// registered already; hosts passing fonts to `Renderer::new` register each with the
+/// bytes it was parsed from. Unregistered fonts get metrics guessed from their outlines.
+pub fn register(font: &Font, data: &[u8]) {
+    let Ok(face) = Face::parse(data, 0) else {
+        return;
+    };
+
+    let em = face.units_per_em() as f32;
+    let ems = |metrics: ttf_parser::LineMetrics| {
+        (metrics.position as f32 / em, metrics.thickness as f32 / em)
+    };
+
+    METRICS.lock().unwrap().insert(
+        font.file_hash(),
+        FontMetrics {
+            underline: face.underline_metrics().map(ems),
+            strikeout: face.strikeout_metrics().map(ems),
+        },
+    );
+}
+
+/// The underline of `font` at `font_size`.
+pub fn underline(font: &Font, font_size: f32) -> Bar {
+    let metrics = METRICS.lock().unwrap().get(&font.file_hash()).copied();
+
+    match metrics.and_then(|metrics| metrics.underline) {
+        Some((position, thickness)) => bar(position, thickness, font_size),
+        // Common values, a little under the baseline
+        None => bar(-0.1, 0.05, font_size),
+    }
+}
+
+/// The strikethrough line of `font` at `font_size`.
+pub fn strikeout(font: &Font, font_size: f32) -> Bar {
+    let metrics = METRICS.lock().unwrap().get(&font.file_hash()).copied();
+
+    match metrics.and_then(|metrics| metrics.strikeout) {
+        Some((position, thickness)) => bar(position, thickness, font_size),
+        None => {
+            // Through the middle of the lowercase letters
+            let thickness = (font_size * 0.05).max(1.0);
+            let x_height = font.metrics('x', font_size).height as f32;
+
+            Bar {
+                top: (x_height + thickness) / 2.0,
+                thickness,
+            }
+        }
+    }
+}
+
+fn bar(position: f32, thickness: f32, font_size: f32) -> Bar {
+    Bar {
+        top: position * font_size,
+        // At least a pixel, so small text still shows it
+        thickness: (thickness * font_size).max(1.0),
+    }
+}
@@ -0,0 +1,177 @@
+use std::time::{Duration, Instant};
+
+use crate::pointer::{Corner, CornerTaps};
+
+/// How often memory stats are sampled while the screen is shown
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Share of the screen height taken by the color bars and by the gradient
+const BARS_FRACTION: f32 = 0.15;
+const GRADIENT_FRACTION: f32 = 0.08;
+
+/// Fully saturated bars in the classic test-pattern order, then black
+pub const COLOR_BARS: [[u8; 3]; 8] = [
+    [0xff, 0xff, 0xff],
+    [0xff, 0xff, 0x00],
+    [0x00, 0xff, 0xff],
+    [0x00, 0xff, 0x00],
+    [0xff, 0x00, 0xff],
+    [0xff, 0x00, 0x00],
+    [0x00, 0x00, 0xff],
+    [0x00, 0x00, 0x00],
+];
+
+/// Where each part of the diagnostics screen goes, as (x, y, w, h) rects.
+#[derive(Debug, Clone, Copy)]
+pub struct DiagnosticsLayout {
+    pub bars: (f32, f32, f32, f32),
+    pub gradient: (f32, f32, f32, f32),
+    pub info: (f32, f32, f32, f32),
+    /// Area that traces touches, to find dead spots and check calibration
+    pub touch: (f32, f32, f32, f32),
+    pub clear: (f32, f32, f32, f32),
+    pub close: (f32, f32, f32, f32),
+    pub font_size: f32,
+    pub line_height: f32,
+}
+
+impl DiagnosticsLayout {
+    pub fn new(width: f32, height: f32) -> Self {
+        let font_size = (height / 32.0).clamp(10.0, 20.0).round();
+        let line_height = (font_size * 1.3).round();
+        let bars_height = (height * BARS_FRACTION).round();
+        let gradient_height = (height * GRADIENT_FRACTION).round();
+        let info_y = bars_height + gradient_height;
+        let info_height = line_height * 6.0;
+        let button_height = line_height * 2.0;
+        let touch_y = info_y + info_height;
+
+        DiagnosticsLayout {
+            bars: (0.0, 0.0, width, bars_height),
+            gradient: (0.0, bars_height, width, gradient_height),
+            info: (0.0, info_y, width, info_height),
+            touch: (0.0, touch_y, width, height - touch_y - button_height),
+            clear: (0.0, height - button_height, width / 2.0, button_height),
+            close: (
+                width / 2.0,
+                height - button_height,
+                width / 2.0,
+                button_height,
+            ),
+            font_size,
+            line_height,
+        }
+    }
+}
+
+fn contains((x, y, w, h): (f32, f32, f32, f32), px: f32, py: f32) -> bool {
+    px >= x && px < x + w && py >= y && py < y + h
+}
+
+/// A built-in self-test screen drawn entirely natively, so it works even when the JS app
+/// is broken or missing: color bars and a gradient to check the panel, a touch trace
+/// area, and the input devices, fonts, memory use and version in use. Opened by the
+/// host, or by tapping the top-right corner five times in quick succession.
+pub struct Diagnostics {
+    pub visible: bool,
+    /// Input devices the host found, described for people
+    pub input_devices: Vec<String>,
+    /// Strokes traced on the touch area, each a list of points
+    pub strokes: Vec<Vec<(f32, f32)>>,
+    /// Whether a press in progress is tracing the last stroke
+    tracing: bool,
+    /// Bytes allocated by the JS engine, when last sampled
+    pub js_heap: usize,
+    /// Resident memory of the whole process, where the OS reports it
+    pub resident: Option<usize>,
+    sampled: Option<Instant>,
+    corner_taps: CornerTaps,
+}
+
+impl Diagnostics {
+    pub fn new(input_devices: Vec<String>) -> Self {
+        Diagnostics {
+            visible: false,
+            input_devices,
+            strokes: Vec::new(),
+            tracing: false,
+            js_heap: 0,
+            resident: None,
+            sampled: None,
+            corner_taps: CornerTaps::new(Corner::TopRight),
+        }
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+        self.strokes.clear();
+        self.tracing = false;
+        self.sampled = None;
+    }
+
+    /// Whether memory stats are due to be sampled again.
+    pub fn needs_sample(&self, now: Instant) -> bool {
+        self.visible
+            && self
+                .sampled
+                .is_none_or(|sampled| now.duration_since(sampled) >= SAMPLE_INTERVAL)
+    }
+
+    pub fn sample(&mut self, js_heap: usize, now: Instant) {
+        self.js_heap = js_heap;
+        self.resident = resident_memory();
+        self.sampled = Some(now);
+    }
+
+    /// Handle a pointer event. While hidden, only counts corner taps towards opening;
+    /// while shown, takes every event. Returns whether the event was consumed.
+    pub fn handle_pointer(
+        &mut self,
+        event_name: &str,
+        x: f32,
+        y: f32,
+        screen_width: f32,
+        screen_height: f32,
+    ) -> bool {
+        if !self.visible {
+            if event_name == "PressIn" && self.corner_taps.press(x, y, screen_width, screen_height) {
+                self.set_visible(true);
+                return true;
+            }
+
+            return false;
+        }
+
+        let layout = DiagnosticsLayout::new(screen_width, screen_height);
+
+        match event_name {
+            "PressIn" if contains(layout.close, x, y) => self.set_visible(false),
+            "PressIn" if contains(layout.clear, x, y) => self.strokes.clear(),
+            "PressIn" if contains(layout.touch, x, y) => {
+                self.strokes.push(vec![(x, y)]);
+                self.tracing = true;
+            }
+            "PressMove" if self.tracing => {
+                let (tx, ty, tw, th) = layout.touch;
+                let point = (x.clamp(tx, tx + tw - 1.0), y.clamp(ty, ty + th - 1.0));
+
+                if let Some(stroke) = self.strokes.last_mut() {
+                    stroke.push(point);
+                }
+            }
+            "PressOut" => self.tracing = false,
+            _ => {}
+        }
+
+        true
+    }
+}
+
+/// Resident set size from `/proc/self/statm`, on Linux.
+fn resident_memory() -> Option<usize> {
+    const PAGE_SIZE: usize = 4096;
+
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: usize = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * PAGE_SIZE)
+}
@@ -0,0 +1,89 @@
+use serde::Deserialize;
+
+/// A whole-screen color filter applied to frames on their way to the display, for
+/// accessibility modes that work without every component being themed for them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisplayFilter {
+    #[default]
+    None,
+    /// Swap light and dark: each channel becomes 255 minus itself
+    Invert,
+    /// Stretch each channel away from mid gray, so low-contrast text and borders stand
+    /// out. Colors saturate towards black, white and the primaries.
+    HighContrast,
+    /// Simulate deuteranopia (no green cones), for checking a palette during
+    /// development rather than for users
+    Deuteranopia,
+}
+
+impl DisplayFilter {
+    /// Parse `none`, `invert`, `high-contrast` or `deuteranopia`.
+    pub fn parse(filter: &str) -> Option<Self> {
+        match filter {
+            "none" => Some(DisplayFilter::None),
+            "invert" => Some(DisplayFilter::Invert),
+            "high-contrast" => Some(DisplayFilter::HighContrast),
+            "deuteranopia" => Some(DisplayFilter::Deuteranopia),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DisplayFilter::None => "none",
+            DisplayFilter::Invert => "invert",
+            DisplayFilter::HighContrast => "high-contrast",
+            DisplayFilter::Deuteranopia => "deuteranopia",
+        }
+    }
+
+    pub fn is_none(self) -> bool {
+        self == DisplayFilter::None
+    }
+
+    /// Filter an XRGB8888 pixel, keeping its top byte.
+    #[inline(always)]
+    pub fn apply(self, pixel: u32) -> u32 {
+        let r = ((pixel >> 16) & 0xFF) as u8;
+        let g = ((pixel >> 8) & 0xFF) as u8;
+        let b = (pixel & 0xFF) as u8;
+
+        let (r, g, b) = match self {
+            DisplayFilter::None => return pixel,
+            DisplayFilter::Invert => (255 - r, 255 - g, 255 - b),
+            DisplayFilter::HighContrast => (contrast(r), contrast(g), contrast(b)),
+            DisplayFilter::Deuteranopia => deuteranopia(r, g, b),
+        };
+
+        (pixel & 0xFF00_0000) | (r as u32) << 16 | (g as u32) << 8 | b as u32
+    }
+
+    /// Filter pixels in place.
+    pub fn apply_slice(self, pixels: &mut [u32]) {
+        if self.is_none() {
+            return;
+        }
+
+        for pixel in pixels {
+            *pixel = self.apply(*pixel);
+        }
+    }
+}
+
+fn contrast(channel: u8) -> u8 {
+    ((channel as i32 - 128) * 2 + 128).clamp(0, 255) as u8
+}
+
+/// Machado, Oliveira and Fernandes' (2009) matrix for full deuteranopia. Applied to the
+/// gamma-encoded values directly, which is close enough to judge a palette by.
+fn deuteranopia(r: u8, g: u8, b: u8) -> (u8, u8, u8) {
+    let (r, g, b) = (r as f32, g as f32, b as f32);
+    let channel = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+
+    (
+        channel(0.367322 * r + 0.860646 * g - 0.227968 * b),
+        channel(0.280085 * r + 0.672501 * g + 0.047413 * b),
+        channel(-0.011820 * r + 0.042940 * g + 0.968881 * b),
+    )
+}
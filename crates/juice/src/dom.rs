@@ -1,11 +1,14 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use fontdue::Font;
-use fontdue::layout::{CoordinateSystem, Layout as TextLayout, LayoutSettings, TextStyle};
+use fontdue::layout::{CoordinateSystem, Layout as TextLayout, LayoutSettings};
 use rquickjs::function::{Func, MutFn};
 use rquickjs::{Ctx, IntoJs, Object, Value};
+use serde::Deserialize;
 use taffy::{
     AlignContent, AlignItems, AvailableSpace, BoxSizing, Dimension, Display, FlexDirection,
     FlexWrap, Layout, LengthPercentage, LengthPercentageAuto, NodeId, Overflow, Position, Size,
@@ -13,23 +16,165 @@ use taffy::{
 };
 
 use crate::{
-    canvas::RgbColor,
+    a11y::{A11yProps, AnnounceReason, Announcement, LiveRegion},
+    animation::{Animation, AnimationState, Easing},
+    binding::Template,
+    canvas::{self, RgbColor},
+    chart::{Chart, ChartStyle},
+    clock::Clock,
+    decoder::{self, Decoded},
     engine::JsModule,
-    inherited_style::{InheritedStyle, InheritedStyleOverrides, TextAlign},
+    font_fallback::{self, FontRegistry},
+    gauge::Gauge,
+    grid,
+    inherited_style::{
+        Direction, FontStyle, FontVariations, FontWeight, InheritedStyle, InheritedStyleOverrides,
+        LineHeight, TextAlign, TextDecoration, TextOverflow,
+    },
+    keypad::KeypadLayout,
+    markdown::Markdown,
+    mono::RefreshMode,
+    picker::{self, Picker, PickerKind},
+    press_effect::PressEffect,
+    scanner::Scanner,
+    shadow::BoxShadow,
+    skeleton,
+    sprite::SpriteAnimation,
+    state_style::{NodeState, StateOverrides, StateStyle, StateStyles},
+    table::Table,
+    text_input::TextInputState,
+    theme::{self, Themes},
+    transform::Affine,
+    video::ObjectFit,
 };
 
+#[cfg(feature = "lottie")]
+use crate::lottie::LottiePlayer;
+
 pub struct CachedRaster {
     pub data: Vec<u8>,
     pub width: u32,
     pub height: u32,
 }
 
+/// A `cache` element's subtree as last drawn, reused while its signature holds.
+pub struct SubtreeCache {
+    /// XRGB8888, copied straight off the canvas
+    pub pixels: Vec<u32>,
+    pub width: u32,
+    pub height: u32,
+    /// `Dom::subtree_signature` when it was drawn
+    pub signature: u64,
+}
+
 pub struct NodeContext {
     pub kind: NodeKind,
     pub resolved_style: InheritedStyle,
     pub overrides: InheritedStyleOverrides,
     pub render_dirty: bool,
     pub cached_raster: Option<CachedRaster>,
+    /// `decoder::raster_key` of the raster last asked of the decoder, whether or not it's
+    /// done yet
+    pub raster_request: Option<u64>,
+    /// The frame the node was last drawn in, so the caches of nodes gone longest unseen
+    /// are dropped first
+    pub last_drawn: u64,
+    /// Scroll offset of an `overflow: scroll` node's content
+    pub scroll_x: f32,
+    pub scroll_y: f32,
+    /// Horizontal offset of a swipeable row. Unlike scrolling this moves the node itself
+    pub swipe_x: f32,
+    /// Theme variables `color` and `background` were set from, re-resolved when the
+    /// theme changes
+    pub color_variable: Option<String>,
+    pub background_variable: Option<String>,
+    pub border_color_variable: Option<String>,
+    /// Keyframe animation from the `animation` attribute
+    pub animation: Option<Animation>,
+    pub state: NodeState,
+    /// Styles from the `pressedStyle`, `focusedStyle` and `disabledStyle` attributes
+    pub state_styles: StateStyles,
+    /// What `state_styles` currently change about the node
+    pub state_overrides: StateOverrides,
+    /// Paint order among siblings from the `zIndex` style; higher paints later
+    pub z_index: i32,
+    /// From the `opacity` style, fading the node and everything in it as one
+    pub opacity: f32,
+    /// From the `transform` style, moving the drawn node around its center without
+    /// changing its layout
+    pub transform: Option<Affine>,
+    pub a11y: A11yProps,
+    /// Whether `rowGap` and `columnGap` were set, so a later `gap` leaves those axes be
+    pub gap_longhands: (bool, bool),
+    /// Levels of descendants below the node. Removing children doesn't lower it, so it can
+    /// be more than the real height until `check_depth` measures the subtree again
+    pub height: usize,
+}
+
+impl NodeContext {
+    /// Take a decoder's result if it's for the raster last requested, keeping an image's
+    /// full-size pixels to resize from later.
+    pub fn apply_decoded(&mut self, decoded: Decoded) -> bool {
+        if self.raster_request != Some(decoded.key) {
+            return false;
+        }
+
+        if let (
+            NodeKind::Image {
+                data,
+                img_width,
+                img_height,
+                ..
+            },
+            Some(image),
+        ) = (&mut self.kind, decoded.image)
+        {
+            (*data, *img_width, *img_height) = image;
+        }
+
+        if decoded.raster.is_some() {
+            self.cached_raster = decoded.raster;
+        }
+
+        self.render_dirty = true;
+        true
+    }
+
+    /// Bytes held by the node's rasters and cached pixels, which `drop_caches` frees.
+    pub fn cache_bytes(&self) -> usize {
+        let raster = self
+            .cached_raster
+            .as_ref()
+            .map_or(0, |raster| raster.data.len());
+        let kind = match &self.kind {
+            NodeKind::Image { data, .. } => data.len(),
+            NodeKind::Element {
+                subtree_cache: Some(cache),
+                ..
+            } => cache.pixels.len() * 4,
+            _ => 0,
+        };
+
+        raster + kind
+    }
+
+    /// Free the node's rasters and cached pixels, to be made again from their source the
+    /// next time it's drawn. Images are decoded again from `src`.
+    pub fn drop_caches(&mut self) {
+        self.cached_raster = None;
+        self.raster_request = None;
+
+        match &mut self.kind {
+            NodeKind::Image {
+                data,
+                img_width,
+                img_height,
+                ..
+            } => (*data, *img_width, *img_height) = (Arc::default(), 0, 0),
+            NodeKind::Element { subtree_cache, .. } => *subtree_cache = None,
+            _ => {}
+        }
+    }
 }
 
 pub enum NodeKind {
@@ -37,10 +182,28 @@ pub enum NodeKind {
         tag: String,
         background: Option<RgbColor>,
         border_radius: f32,
+        /// Pulling a scroll container down past its top triggers a `Refresh` event
+        refreshable: bool,
+        /// How far the node can be swiped left and right to reveal actions behind it
+        swipe_left: f32,
+        swipe_right: f32,
+        /// How e-paper panels should refresh damage over this node
+        refresh_mode: Option<RefreshMode>,
+        press_effect: Option<PressEffect>,
+        /// Drawn over the `border` widths from the layout
+        border_color: Option<RgbColor>,
+        box_shadow: Option<BoxShadow>,
+        /// Draw the subtree once and blit it until something in it changes
+        cache: bool,
+        subtree_cache: Option<SubtreeCache>,
     },
     Text {
         text: String,
         wrap_width: Option<f32>,
+        /// Key of the host-set value shown instead of `text`, for `<bound>` nodes
+        binding: Option<String>,
+        /// How the bound value is shown, e.g. `{{value | round:1}} °C`
+        format: Option<Template>,
     },
     Svg {
         width: Dimension,
@@ -50,30 +213,156 @@ pub enum NodeKind {
     Image {
         width: Dimension,
         height: Dimension,
+        src: String,
+        /// Full-size RGBA, shared with the image cache
+        data: Arc<[u8]>,
+        img_width: u32,
+        img_height: u32,
+    },
+    /// An `animatedsprite`, playing frames from a sprite sheet image
+    Sprite {
         src: String,
         data: Vec<u8>,
         img_width: u32,
         img_height: u32,
+        animation: SpriteAnimation,
+    },
+    Video {
+        src: String,
+        object_fit: ObjectFit,
+    },
+    Surface {
+        name: String,
+        punch: bool,
+    },
+    Input {
+        placeholder: String,
+        state: TextInputState,
+        /// The built-in keypad shown while focused, from `inputMode`
+        keypad: Option<KeypadLayout>,
+        shuffle_keys: bool,
+    },
+    Picker {
+        picker: Picker,
+    },
+    Gauge {
+        gauge: Gauge,
+    },
+    Chart {
+        chart: Chart,
+    },
+    Table {
+        table: Table,
     },
+    Markdown {
+        markdown: Markdown,
+    },
+    Scanner {
+        scanner: Scanner,
+    },
+    #[cfg(feature = "lottie")]
+    Lottie {
+        player: LottiePlayer,
+    },
+}
+
+/// Screen-space geometry of a `surface` node, reported to the host so it can position a
+/// hardware plane underneath.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SurfaceRect {
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
 }
 
 pub struct Dom {
     tree: TaffyTree<NodeContext>,
     inherited_style: InheritedStyle,
+    themes: Themes,
+    /// Timing curves registered by the app, by name, for `animation` attributes
+    easings: HashMap<String, Easing>,
+    /// Latest values set by the host for `<bound>` nodes, by key
+    bindings: HashMap<String, String>,
+    /// Node that takes all pointer input while set, see `set_input_grab`
+    input_grab: Option<NodeId>,
+    limits: DomLimits,
+    /// Multiplies every font size, see `set_text_scale`
+    text_scale: f32,
+    /// Live regions whose text changed since `take_live_changes`
+    live_changes: Vec<NodeId>,
+    /// Live regions made since `take_live_changes`, whose first content isn't announced
+    new_live_regions: Vec<NodeId>,
+    /// Counts frames drawn, for `NodeContext::last_drawn`
+    frame: u64,
+    /// Time for animations, see `set_clock`
+    clock: Clock,
     pub root_node_id: Option<NodeId>,
 }
 
 impl Dom {
+    /// The style the tree inherits from, as given to `new`.
+    pub fn base_style(&self) -> &InheritedStyle {
+        &self.inherited_style
+    }
+
     pub fn new(inherited_style: InheritedStyle) -> Self {
         Self {
             tree: TaffyTree::new(),
             inherited_style,
+            themes: Themes::default(),
+            easings: HashMap::new(),
+            bindings: HashMap::new(),
+            input_grab: None,
+            limits: DomLimits::default(),
+            text_scale: 1.0,
+            live_changes: Vec::new(),
+            new_live_regions: Vec::new(),
+            frame: 0,
+            clock: Clock::default(),
             root_node_id: None,
         }
     }
 
-    pub fn create_element(&mut self, tag: String) -> u64 {
-        let style = Style::default();
+    pub fn limits(&self) -> DomLimits {
+        self.limits
+    }
+
+    /// Change the caps on the tree's size. Nodes already past them are kept, but can't
+    /// grow further.
+    pub fn set_limits(&mut self, limits: DomLimits) {
+        self.limits = limits;
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Time animations by `clock`, like the renderer's, instead of the system clock.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    /// Drop every node, theme and easing, as when another bundle takes over. Values set
+    /// by the host for `<bound>` nodes are kept.
+    pub fn clear(&mut self) {
+        self.tree = TaffyTree::new();
+        self.themes = Themes::default();
+        self.easings.clear();
+        self.input_grab = None;
+        self.root_node_id = None;
+    }
+
+    pub fn create_element(&mut self, tag: String) -> Result<u64, DomError> {
+        self.check_node_count()?;
+
+        let mut style = Style::default();
+
+        // Rows in a right-to-left base style run the other way from the start
+        if self.inherited_style.direction == Direction::Rtl {
+            style.flex_direction = mirror(style.flex_direction);
+        }
 
         let kind = match tag.as_str() {
             "svg" => NodeKind::Svg {
@@ -81,18 +370,84 @@ impl Dom {
                 height: Dimension::auto(),
                 markup: "".to_string(),
             },
-            "img" => NodeKind::Image {
+            "img" | "image" => NodeKind::Image {
                 width: Dimension::auto(),
                 height: Dimension::auto(),
+                src: "".to_string(),
+                data: Arc::default(),
+                img_width: 0,
+                img_height: 0,
+            },
+            "animatedsprite" => NodeKind::Sprite {
                 src: "".to_string(),
                 data: vec![],
                 img_width: 0,
                 img_height: 0,
+                animation: SpriteAnimation::new(self.clock.now()),
+            },
+            "video" => NodeKind::Video {
+                src: "".to_string(),
+                object_fit: ObjectFit::default(),
+            },
+            "surface" => NodeKind::Surface {
+                name: "".to_string(),
+                punch: true,
+            },
+            "input" => NodeKind::Input {
+                placeholder: "".to_string(),
+                state: TextInputState::default(),
+                keypad: None,
+                shuffle_keys: false,
+            },
+            "wheelpicker" => NodeKind::Picker {
+                picker: Picker::new(PickerKind::Wheel),
+            },
+            "datepicker" => NodeKind::Picker {
+                picker: Picker::new(PickerKind::Date),
+            },
+            "timepicker" => NodeKind::Picker {
+                picker: Picker::new(PickerKind::Time),
+            },
+            "gauge" => NodeKind::Gauge {
+                gauge: Gauge::default(),
+            },
+            "chart" => NodeKind::Chart {
+                chart: Chart::default(),
+            },
+            "table" => NodeKind::Table {
+                table: Table::default(),
+            },
+            "markdown" => NodeKind::Markdown {
+                markdown: Markdown::default(),
+            },
+            "scanner" => NodeKind::Scanner {
+                scanner: Scanner::default(),
+            },
+            // Text showing a value the host sets with `set_binding`, without going
+            // through JS
+            "bound" => NodeKind::Text {
+                text: String::new(),
+                wrap_width: None,
+                binding: None,
+                format: None,
+            },
+            #[cfg(feature = "lottie")]
+            "lottie" => NodeKind::Lottie {
+                player: LottiePlayer::new(self.clock.now()),
             },
             tag => NodeKind::Element {
                 tag: tag.to_string(),
-                background: None,
+                background: (tag == "skeleton").then_some(skeleton::DEFAULT_COLOR),
                 border_radius: 0.0,
+                refreshable: false,
+                swipe_left: 0.0,
+                swipe_right: 0.0,
+                refresh_mode: None,
+                press_effect: None,
+                border_color: None,
+                box_shadow: None,
+                cache: false,
+                subtree_cache: None,
             },
         };
 
@@ -102,11 +457,29 @@ impl Dom {
                 style,
                 NodeContext {
                     kind,
-                    resolved_style: self.inherited_style.clone(),
+                    resolved_style: self.scaled_base_style(),
                     overrides: InheritedStyleOverrides::default(),
 
                     render_dirty: true,
                     cached_raster: None,
+                    raster_request: None,
+                    last_drawn: 0,
+                    scroll_x: 0.0,
+                    scroll_y: 0.0,
+                    swipe_x: 0.0,
+                    color_variable: None,
+                    background_variable: None,
+                    border_color_variable: None,
+                    animation: None,
+                    state: NodeState::default(),
+                    state_styles: StateStyles::default(),
+                    state_overrides: StateOverrides::default(),
+                    z_index: 0,
+                    opacity: 1.0,
+                    transform: None,
+                    a11y: A11yProps::default(),
+                    gap_longhands: (false, false),
+                    height: 0,
                 },
             )
             .unwrap();
@@ -115,10 +488,13 @@ impl Dom {
             self.root_node_id = Some(node_id);
         }
 
-        u64::from(node_id)
+        Ok(u64::from(node_id))
     }
 
-    pub fn create_text_node(&mut self, text: String) -> u64 {
+    pub fn create_text_node(&mut self, text: String) -> Result<u64, DomError> {
+        self.check_node_count()?;
+        self.check_text_length(&text)?;
+
         let style = Style {
             min_size: Size {
                 width: Dimension::length(0.0),
@@ -135,31 +511,164 @@ impl Dom {
                     kind: NodeKind::Text {
                         text,
                         wrap_width: None,
+                        binding: None,
+                        format: None,
                     },
-                    resolved_style: self.inherited_style.clone(),
+                    resolved_style: self.scaled_base_style(),
                     overrides: InheritedStyleOverrides::default(),
 
                     render_dirty: true,
                     cached_raster: None,
+                    raster_request: None,
+                    last_drawn: 0,
+                    scroll_x: 0.0,
+                    scroll_y: 0.0,
+                    swipe_x: 0.0,
+                    color_variable: None,
+                    background_variable: None,
+                    border_color_variable: None,
+                    animation: None,
+                    state: NodeState::default(),
+                    state_styles: StateStyles::default(),
+                    state_overrides: StateOverrides::default(),
+                    z_index: 0,
+                    opacity: 1.0,
+                    transform: None,
+                    a11y: A11yProps::default(),
+                    gap_longhands: (false, false),
+                    height: 0,
                 },
             )
             .unwrap();
 
-        u64::from(node_id)
+        Ok(u64::from(node_id))
+    }
+
+    fn check_node_count(&self) -> Result<(), DomError> {
+        let count = self.tree.total_node_count();
+
+        if count >= self.limits.max_nodes {
+            return Err(DomError::limit(
+                Limit::Nodes,
+                self.limits.max_nodes,
+                count + 1,
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn check_text_length(&self, text: &str) -> Result<(), DomError> {
+        if text.len() > self.limits.max_text_length {
+            return Err(DomError::limit(
+                Limit::TextLength,
+                self.limits.max_text_length,
+                text.len(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Refuse to put `child_id` under `parent_id` if the tree would get too deep.
+    fn check_depth(&mut self, parent_id: NodeId, child_id: NodeId) -> Result<(), DomError> {
+        if self.tree.get_node_context(parent_id).is_none()
+            || self.tree.get_node_context(child_id).is_none()
+        {
+            return Err(DomError {
+                message: "Invalid NodeId".to_string(),
+                limit: None,
+            });
+        }
+
+        let mut parent_depth = 0;
+        let mut ancestor = Some(parent_id);
+
+        while let Some(node_id) = ancestor {
+            ancestor = self.tree.parent(node_id);
+            parent_depth += 1;
+        }
+
+        let mut subtree_height = self.height(child_id);
+
+        // Heights only go up when children are removed, so measure again before refusing
+        // in case the subtree has got shorter since
+        if parent_depth + subtree_height > self.limits.max_depth {
+            subtree_height = self.measure_height(child_id);
+        }
+
+        // The parent's depth counts the root as 1, so this is levels below the root
+        let depth = parent_depth + subtree_height;
+
+        if depth > self.limits.max_depth {
+            return Err(DomError::limit(Limit::Depth, self.limits.max_depth, depth));
+        }
+
+        Ok(())
+    }
+
+    fn height(&self, node_id: NodeId) -> usize {
+        self.tree
+            .get_node_context(node_id)
+            .map_or(0, |ctx| ctx.height)
+    }
+
+    /// Work out the real height of a subtree, correcting the heights stored in it.
+    fn measure_height(&mut self, node_id: NodeId) -> usize {
+        let height = self
+            .tree
+            .children(node_id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|child| self.measure_height(child) + 1)
+            .max()
+            .unwrap_or(0);
+
+        if let Some(ctx) = self.tree.get_node_context_mut(node_id) {
+            ctx.height = height;
+        }
+
+        height
+    }
+
+    /// Raise the heights of `parent_id` and its ancestors to fit a newly inserted child.
+    fn grow_heights(&mut self, parent_id: NodeId, child_id: NodeId) {
+        let mut height = self.height(child_id) + 1;
+        let mut ancestor = Some(parent_id);
+
+        while let Some(node_id) = ancestor {
+            match self.tree.get_node_context_mut(node_id) {
+                Some(ctx) if ctx.height < height => ctx.height = height,
+                _ => break,
+            }
+
+            ancestor = self.tree.parent(node_id);
+            height += 1;
+        }
     }
 
     pub fn append_child(&mut self, parent_id: u64, child_id: u64) -> Result<(), DomError> {
         let parent_id = NodeId::from(parent_id);
         let child_id = NodeId::from(child_id);
 
+        self.check_not_ancestor(parent_id, child_id)?;
+        self.check_depth(parent_id, child_id)?;
+        let same_parent = self.detach(parent_id, child_id)?;
+
         self.tree
             .add_child(parent_id, child_id)
             .map_err(|_| DomError {
                 message: "Invalid NodeId".to_string(),
+                limit: None,
             })?;
+        self.grow_heights(parent_id, child_id);
+
+        if !same_parent {
+            let parent_resolved = self.get_resolved_style(parent_id);
+            self.resolve_subtree(&parent_resolved, child_id);
+        }
 
-        let parent_resolved = self.get_resolved_style(parent_id);
-        self.resolve_subtree(&parent_resolved, child_id);
+        self.note_live_change(parent_id);
         Ok(())
     }
 
@@ -172,17 +681,73 @@ impl Dom {
         let parent_id = NodeId::from(parent_id);
         let child_id = NodeId::from(child_id);
 
+        self.check_not_ancestor(parent_id, child_id)?;
+        self.check_depth(parent_id, child_id)?;
+        let same_parent = self.detach(parent_id, child_id)?;
+
         self.tree
             .insert_child_at_index(parent_id, index, child_id)
             .map_err(|_| DomError {
                 message: "Invalid NodeId".to_string(),
+                limit: None,
             })?;
+        self.grow_heights(parent_id, child_id);
+
+        if !same_parent {
+            let parent_resolved = self.get_resolved_style(parent_id);
+            self.resolve_subtree(&parent_resolved, child_id);
+        }
 
-        let parent_resolved = self.get_resolved_style(parent_id);
-        self.resolve_subtree(&parent_resolved, child_id);
+        self.note_live_change(parent_id);
         Ok(())
     }
 
+    /// Refuse to move a node under itself or one of its own descendants, which would cut
+    /// the subtree off from the root in a loop.
+    fn check_not_ancestor(&self, parent_id: NodeId, child_id: NodeId) -> Result<(), DomError> {
+        let mut ancestor = Some(parent_id);
+
+        while let Some(node_id) = ancestor {
+            if node_id == child_id {
+                return Err(DomError {
+                    message: "Can't insert a node into itself".to_string(),
+                    limit: None,
+                });
+            }
+
+            ancestor = self.tree.parent(node_id);
+        }
+
+        Ok(())
+    }
+
+    /// Remove a node from its current parent before it's inserted under `parent_id`, so
+    /// inserting an attached node moves it like the DOM does. Keyed children reordered by
+    /// Preact keep their layout caches, rasters and scroll state instead of being
+    /// duplicated. Returns whether the node is staying under the same parent, in which case
+    /// its resolved style is still valid.
+    fn detach(&mut self, parent_id: NodeId, child_id: NodeId) -> Result<bool, DomError> {
+        if self.tree.get_node_context(child_id).is_none() {
+            return Err(DomError {
+                message: "Invalid NodeId".to_string(),
+                limit: None,
+            });
+        }
+
+        let Some(old_parent) = self.tree.parent(child_id) else {
+            return Ok(false);
+        };
+
+        self.tree
+            .remove_child(old_parent, child_id)
+            .map_err(|_| DomError {
+                message: "Invalid NodeId".to_string(),
+                limit: None,
+            })?;
+
+        Ok(old_parent == parent_id)
+    }
+
     pub fn remove_child(&mut self, parent_id: u64, child_id: u64) -> Result<(), DomError> {
         let parent_id = NodeId::from(parent_id);
         let child_id = NodeId::from(child_id);
@@ -192,6 +757,7 @@ impl Dom {
             .map(|_| ())
             .map_err(|_| DomError {
                 message: "Invalid NodeId".to_string(),
+                limit: None,
             })
     }
 
@@ -200,6 +766,7 @@ impl Dom {
 
         self.tree.remove(node_id).map(|_| ()).map_err(|_| DomError {
             message: "Invalid NodeId".to_string(),
+            limit: None,
         })
     }
 
@@ -217,35 +784,228 @@ impl Dom {
             .get_node_context_mut(node_id)
             .ok_or_else(|| DomError {
                 message: "Invalid NodeId".to_string(),
+                limit: None,
             })?;
 
+        if key == "animation" {
+            // Re-rendering with an equal animation shouldn't restart it
+            if ctx.animation.as_ref().map(Animation::source) != Some(value.as_str()) {
+                let background = match ctx.kind {
+                    NodeKind::Element { background, .. } => background,
+                    _ => None,
+                };
+                let rest = (ctx.resolved_style.color, background);
+                let themes = &self.themes;
+                ctx.animation = match value.as_str() {
+                    "" => None,
+                    value => Animation::parse(
+                        value,
+                        self.clock.now(),
+                        rest,
+                        |c| themes.resolve(c),
+                        &self.easings,
+                    ),
+                };
+                ctx.render_dirty = true;
+                self.cascade_resolved_style(node_id);
+            }
+
+            return Ok(());
+        }
+
+        let limit = match (&ctx.kind, key.as_str()) {
+            (NodeKind::Text { .. }, "text") => {
+                Some((Limit::TextLength, self.limits.max_text_length))
+            }
+            (NodeKind::Svg { .. }, "markup") => Some((Limit::SvgSize, self.limits.max_svg_size)),
+            _ => None,
+        };
+
+        if let Some((limit, max)) = limit
+            && value.len() > max
+        {
+            return Err(DomError::limit(limit, max, value.len()));
+        }
+
+        let state_style = match key.as_str() {
+            "pressedStyle" => Some(&mut ctx.state_styles.pressed),
+            "focusedStyle" => Some(&mut ctx.state_styles.focused),
+            "disabledStyle" => Some(&mut ctx.state_styles.disabled),
+            _ => None,
+        };
+
+        if let Some(style) = state_style {
+            *style = StateStyle::parse(&value);
+            self.apply_state_styles(node_id);
+            return Ok(());
+        }
+
+        if key == "disabled" {
+            self.set_node_state(node_id, |state| state.disabled = value == "true");
+            return Ok(());
+        }
+
+        match key.as_str() {
+            "accessibilityLabel" => {
+                ctx.a11y.label = Some(value).filter(|label| !label.is_empty());
+                return Ok(());
+            }
+            "accessibilityRole" => {
+                ctx.a11y.role = Some(value).filter(|role| !role.is_empty());
+                return Ok(());
+            }
+            "accessibilityLive" => {
+                let live = LiveRegion::parse(&value);
+
+                if ctx.a11y.live == LiveRegion::Off && live != LiveRegion::Off {
+                    self.new_live_regions.push(node_id);
+                }
+
+                ctx.a11y.live = live;
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let mut text_changed = false;
+
         match &mut ctx.kind {
-            NodeKind::Element { background, .. } => match key.as_str() {
+            NodeKind::Element {
+                background,
+                refreshable,
+                refresh_mode,
+                press_effect,
+                border_color,
+                box_shadow,
+                cache,
+                subtree_cache,
+                ..
+            } => match key.as_str() {
                 "color" => {
-                    ctx.overrides.color = RgbColor::from_string(&value);
+                    ctx.color_variable = theme::variable_name(&value).map(str::to_string);
+                    ctx.overrides.color = self.themes.resolve(&value);
                     needs_cascade = true;
                 }
                 "font" => {
                     ctx.overrides.font_name = Some(value);
                     needs_cascade = true;
                 }
+                "fontWeight" => {
+                    ctx.overrides.font_weight = FontWeight::parse(&value);
+                    needs_cascade = true;
+                }
+                "fontStyle" => {
+                    ctx.overrides.font_style = FontStyle::parse(&value);
+                    needs_cascade = true;
+                }
+                "fontVariationSettings" => {
+                    ctx.overrides.font_variations = FontVariations::parse(&value);
+                    needs_cascade = true;
+                }
                 "textAlign" => {
                     ctx.overrides.text_align = Some(parse_text_align(&value));
                     needs_cascade = true;
                 }
+                "direction" => {
+                    ctx.overrides.direction = Direction::parse(&value);
+                    needs_cascade = true;
+                }
+                "textDecoration" => {
+                    ctx.overrides.text_decoration = TextDecoration::parse(&value);
+                    needs_cascade = true;
+                }
+                "lineHeight" => {
+                    ctx.overrides.line_height = LineHeight::parse(&value);
+                    needs_cascade = true;
+                }
+                "textOverflow" => {
+                    ctx.overrides.text_overflow = TextOverflow::parse(&value);
+                    needs_cascade = true;
+                }
                 "background" => {
-                    *background = RgbColor::from_string(&value);
+                    ctx.background_variable = theme::variable_name(&value).map(str::to_string);
+                    *background = self.themes.resolve(&value);
                     ctx.render_dirty = true;
                 }
+                "refreshable" => *refreshable = value == "true",
+                "refreshMode" => *refresh_mode = RefreshMode::parse(&value),
+                "pressEffect" => *press_effect = PressEffect::parse(&value),
+                "borderColor" => {
+                    ctx.border_color_variable = theme::variable_name(&value).map(str::to_string);
+                    *border_color = self.themes.resolve(&value);
+                    ctx.render_dirty = true;
+                }
+                "boxShadow" => {
+                    *box_shadow = BoxShadow::parse(&value, |color| self.themes.resolve(color));
+                    ctx.render_dirty = true;
+                }
+                "cache" => {
+                    *cache = value == "true";
+                    *subtree_cache = None;
+                }
                 _ => {}
             },
-            NodeKind::Text { text, .. } => match key.as_str() {
+            NodeKind::Text {
+                text,
+                binding,
+                format,
+                ..
+            } => match key.as_str() {
                 "text" => {
+                    text_changed = *text != value;
                     *text = value;
                     ctx.render_dirty = true;
                     // Text content change affects measurement
                     let _ = self.tree.mark_dirty(node_id);
                 }
+                "bind" | "format" => {
+                    if key == "bind" {
+                        *binding = Some(value).filter(|key| !key.is_empty());
+                    } else {
+                        *format = Some(Template::parse(&value)).filter(|_| !value.is_empty());
+                    }
+
+                    let bound = binding.as_ref().and_then(|key| self.bindings.get(key));
+
+                    if let Some(bound) = bound {
+                        *text = format_bound(format.as_ref(), bound);
+                        ctx.render_dirty = true;
+                        let _ = self.tree.mark_dirty(node_id);
+                    }
+                }
+                "color" => {
+                    ctx.color_variable = theme::variable_name(&value).map(str::to_string);
+                    ctx.overrides.color = self.themes.resolve(&value);
+                    needs_cascade = true;
+                }
+                "font" => {
+                    ctx.overrides.font_name = Some(value);
+                    needs_cascade = true;
+                }
+                "fontWeight" => {
+                    ctx.overrides.font_weight = FontWeight::parse(&value);
+                    needs_cascade = true;
+                }
+                "fontStyle" => {
+                    ctx.overrides.font_style = FontStyle::parse(&value);
+                    needs_cascade = true;
+                }
+                "fontVariationSettings" => {
+                    ctx.overrides.font_variations = FontVariations::parse(&value);
+                    needs_cascade = true;
+                }
+                "lineHeight" => {
+                    ctx.overrides.line_height = LineHeight::parse(&value);
+                    needs_cascade = true;
+                }
+                "textOverflow" => {
+                    ctx.overrides.text_overflow = TextOverflow::parse(&value);
+                    needs_cascade = true;
+                }
+                "textDecoration" => {
+                    ctx.overrides.text_decoration = TextDecoration::parse(&value);
+                    needs_cascade = true;
+                }
                 _ => {}
             },
             NodeKind::Svg { markup, .. } => match key.as_str() {
@@ -254,7 +1014,8 @@ impl Dom {
                     ctx.render_dirty = true;
                 }
                 "color" => {
-                    ctx.overrides.color = RgbColor::from_string(&value);
+                    ctx.color_variable = theme::variable_name(&value).map(str::to_string);
+                    ctx.overrides.color = self.themes.resolve(&value);
                     needs_cascade = true;
                 }
                 "font" => {
@@ -274,41 +1035,253 @@ impl Dom {
                 img_height,
                 ..
             } => match key.as_str() {
+                // Decoded off the render thread once it's drawn
                 "src" => {
-                    *src = value.clone();
-                    ctx.render_dirty = true;
-                    // Decode base64 data URL: "data:image/png;base64,..."
-                    if let Some(base64_data) = value.split(',').nth(1).and_then(|s| {
-                        base64::Engine::decode(&base64::engine::general_purpose::STANDARD, s).ok()
-                    }) {
-                        match image::load_from_memory(&base64_data) {
-                            Ok(img) => {
-                                let rgba = img.to_rgba8();
-                                *img_width = rgba.width();
-                                *img_height = rgba.height();
-                                *data = rgba.to_vec();
-                            }
-                            Err(err) => {
-                                println!("Error loading image: {:?}", err);
-                                *data = vec![];
-                                *img_width = 0;
-                                *img_height = 0;
-                            }
-                        }
+                    ctx.render_dirty = true;
+                    (*data, *img_width, *img_height) = (Arc::default(), 0, 0);
+                    *src = value;
+                }
+                _ => {}
+            },
+            NodeKind::Sprite {
+                src,
+                data,
+                img_width,
+                img_height,
+                animation,
+            } => match key.as_str() {
+                "src" => {
+                    ctx.render_dirty = true;
+                    if let Some(image) = decoder::decode_data_url(&value) {
+                        (*data, *img_width, *img_height) = image;
+                        animation.restart(self.clock.now());
                     }
+                    *src = value;
                 }
+                "loop" => animation.looping = value != "false",
                 _ => {}
             },
-        };
-
-        if needs_cascade {
-            self.cascade_resolved_style(node_id);
-        }
-
-        Ok(())
-    }
-
-    pub fn set_attribute_number(
+            NodeKind::Video { src, .. } => {
+                if key == "src" {
+                    *src = value;
+                    ctx.render_dirty = true;
+                }
+            }
+            NodeKind::Surface { name, punch } => match key.as_str() {
+                "name" => *name = value,
+                "punch" => {
+                    *punch = value != "none";
+                    ctx.render_dirty = true;
+                }
+                _ => {}
+            },
+            NodeKind::Input {
+                placeholder,
+                state,
+                keypad,
+                shuffle_keys,
+            } => match key.as_str() {
+                "value" => {
+                    state.set_value(value);
+                    ctx.render_dirty = true;
+                }
+                "placeholder" => {
+                    *placeholder = value;
+                    ctx.render_dirty = true;
+                }
+                "secureTextEntry" => {
+                    state.secure = value == "true";
+                    ctx.render_dirty = true;
+                }
+                "inputMode" => *keypad = KeypadLayout::parse(&value),
+                "shuffleKeys" => *shuffle_keys = value == "true",
+                "revealLastChar" => {
+                    state.reveal_last = value == "true";
+
+                    if !state.reveal_last {
+                        state.hide_revealed();
+                    }
+                    ctx.render_dirty = true;
+                }
+                "color" => {
+                    ctx.color_variable = theme::variable_name(&value).map(str::to_string);
+                    ctx.overrides.color = self.themes.resolve(&value);
+                    needs_cascade = true;
+                }
+                "font" => {
+                    ctx.overrides.font_name = Some(value);
+                    needs_cascade = true;
+                }
+                _ => {}
+            },
+            NodeKind::Picker { picker } => match key.as_str() {
+                "items" => {
+                    picker.set_items(serde_json::from_str(&value).unwrap_or_default());
+                    ctx.render_dirty = true;
+                }
+                "value" => {
+                    picker.set_value(&value);
+                    ctx.render_dirty = true;
+                }
+                "color" => {
+                    ctx.color_variable = theme::variable_name(&value).map(str::to_string);
+                    ctx.overrides.color = self.themes.resolve(&value);
+                    needs_cascade = true;
+                }
+                "font" => {
+                    ctx.overrides.font_name = Some(value);
+                    needs_cascade = true;
+                }
+                _ => {}
+            },
+            NodeKind::Gauge { gauge } => match key.as_str() {
+                "trackColor" => {
+                    gauge.track_color = self.themes.resolve(&value);
+                    ctx.render_dirty = true;
+                }
+                "valueColor" => {
+                    gauge.value_color = self.themes.resolve(&value);
+                    ctx.render_dirty = true;
+                }
+                "ranges" => {
+                    gauge.set_ranges(&value, |color| self.themes.resolve(color));
+                    ctx.render_dirty = true;
+                }
+                "labels" => {
+                    gauge.labels = value != "false";
+                    ctx.render_dirty = true;
+                }
+                "color" => {
+                    ctx.color_variable = theme::variable_name(&value).map(str::to_string);
+                    ctx.overrides.color = self.themes.resolve(&value);
+                    needs_cascade = true;
+                }
+                "font" => {
+                    ctx.overrides.font_name = Some(value);
+                    needs_cascade = true;
+                }
+                _ => {}
+            },
+            NodeKind::Chart { chart } => match key.as_str() {
+                "data" => {
+                    chart.set_values(serde_json::from_str::<Vec<f32>>(&value).unwrap_or_default());
+                    ctx.render_dirty = true;
+                }
+                "type" => {
+                    chart.style = ChartStyle::parse(&value);
+                    ctx.render_dirty = true;
+                }
+                "fillColor" => {
+                    chart.fill_color = self.themes.resolve(&value);
+                    ctx.render_dirty = true;
+                }
+                "color" => {
+                    ctx.color_variable = theme::variable_name(&value).map(str::to_string);
+                    ctx.overrides.color = self.themes.resolve(&value);
+                    needs_cascade = true;
+                }
+                "font" => {
+                    ctx.overrides.font_name = Some(value);
+                    needs_cascade = true;
+                }
+                _ => {}
+            },
+            NodeKind::Table { table } => match key.as_str() {
+                "columns" => {
+                    table.set_columns(&value);
+                    ctx.render_dirty = true;
+                }
+                "rows" => {
+                    table.set_rows(&value, |color| self.themes.resolve(color));
+                    ctx.render_dirty = true;
+                }
+                "headerBackground" => {
+                    table.header_background = self.themes.resolve(&value);
+                    ctx.render_dirty = true;
+                }
+                "stripeColor" => {
+                    table.stripe_color = self.themes.resolve(&value);
+                    ctx.render_dirty = true;
+                }
+                "color" => {
+                    ctx.color_variable = theme::variable_name(&value).map(str::to_string);
+                    ctx.overrides.color = self.themes.resolve(&value);
+                    needs_cascade = true;
+                }
+                "font" => {
+                    ctx.overrides.font_name = Some(value);
+                    needs_cascade = true;
+                }
+                _ => {}
+            },
+            NodeKind::Markdown { markdown } => match key.as_str() {
+                "source" | "boldFont" | "italicFont" | "codeFont" => {
+                    match key.as_str() {
+                        "source" => markdown.set_source(value),
+                        "boldFont" => markdown.bold_font = Some(value),
+                        "italicFont" => markdown.italic_font = Some(value),
+                        _ => markdown.code_font = Some(value),
+                    }
+                    ctx.render_dirty = true;
+                    // Text and fonts change how tall the node is
+                    let _ = self.tree.mark_dirty(node_id);
+                }
+                "linkColor" => {
+                    markdown.link_color = self.themes.resolve(&value);
+                    ctx.render_dirty = true;
+                }
+                "color" => {
+                    ctx.color_variable = theme::variable_name(&value).map(str::to_string);
+                    ctx.overrides.color = self.themes.resolve(&value);
+                    needs_cascade = true;
+                }
+                "font" => {
+                    ctx.overrides.font_name = Some(value);
+                    needs_cascade = true;
+                }
+                _ => {}
+            },
+            NodeKind::Scanner { scanner } => match key.as_str() {
+                "src" => {
+                    scanner.src = value;
+                    scanner.highlights.clear();
+                    ctx.render_dirty = true;
+                }
+                "analyzer" => scanner.analyzer = value,
+                "viewfinder" => {
+                    scanner.viewfinder = value != "false";
+                    ctx.render_dirty = true;
+                }
+                "color" => {
+                    ctx.color_variable = theme::variable_name(&value).map(str::to_string);
+                    ctx.overrides.color = self.themes.resolve(&value);
+                    needs_cascade = true;
+                }
+                _ => {}
+            },
+            #[cfg(feature = "lottie")]
+            NodeKind::Lottie { player } => match key.as_str() {
+                "animationData" => {
+                    player.load(&value, self.clock.now());
+                    ctx.render_dirty = true;
+                }
+                "loop" => player.looping = value != "false",
+                _ => {}
+            },
+        };
+
+        if needs_cascade {
+            self.cascade_resolved_style(node_id);
+        }
+
+        if text_changed {
+            self.note_live_change(node_id);
+        }
+
+        Ok(())
+    }
+
+    pub fn set_attribute_number(
         &mut self,
         node_id: u64,
         key: String,
@@ -322,20 +1295,157 @@ impl Dom {
             .get_node_context_mut(node_id)
             .ok_or_else(|| DomError {
                 message: "Invalid NodeId".to_string(),
+                limit: None,
             })?;
 
         match &mut ctx.kind {
-            NodeKind::Element { border_radius, .. } => match key.as_str() {
+            NodeKind::Element {
+                border_radius,
+                swipe_left,
+                swipe_right,
+                ..
+            } => match key.as_str() {
                 "fontSize" => {
                     ctx.overrides.font_size = Some(value);
                     needs_cascade = true;
                 }
+                "fontWeight" => {
+                    ctx.overrides.font_weight = FontWeight::from_number(value);
+                    needs_cascade = true;
+                }
+                "letterSpacing" => {
+                    ctx.overrides.letter_spacing = Some(value);
+                    needs_cascade = true;
+                }
+                "tabSize" => {
+                    if let Some(tab_size) = tab_size(value) {
+                        ctx.overrides.tab_size = Some(tab_size);
+                        needs_cascade = true;
+                    }
+                }
+                "maxLines" => {
+                    ctx.overrides.max_lines = Some(value.max(0.0) as u32);
+                    needs_cascade = true;
+                }
                 "borderRadius" => {
                     *border_radius = value;
                     ctx.render_dirty = true;
                 }
+                "swipeLeft" => *swipe_left = value.max(0.0),
+                "swipeRight" => *swipe_right = value.max(0.0),
+                _ => {}
+            },
+            NodeKind::Input { .. } if key == "fontSize" => {
+                ctx.overrides.font_size = Some(value);
+                needs_cascade = true;
+            }
+            NodeKind::Picker { picker } => match key.as_str() {
+                "fontSize" => {
+                    ctx.overrides.font_size = Some(value);
+                    needs_cascade = true;
+                }
+                "minYear" => picker.set_year_range(Some(value as i32), None),
+                "maxYear" => picker.set_year_range(None, Some(value as i32)),
+                "minuteStep" => picker.set_minute_step(value as u32),
+                _ => {}
+            },
+            NodeKind::Sprite { animation, .. } => match key.as_str() {
+                "frameWidth" | "frameHeight" => {
+                    if key == "frameWidth" {
+                        animation.frame_width = value.max(0.0) as u32;
+                    } else {
+                        animation.frame_height = value.max(0.0) as u32;
+                    }
+                    ctx.render_dirty = true;
+                    // The frame size is the node's size unless styled otherwise
+                    let _ = self.tree.mark_dirty(node_id);
+                }
+                "frameCount" => animation.frame_count = Some(value.max(0.0) as u32),
+                "fps" => animation.fps = value,
+                _ => {}
+            },
+            NodeKind::Gauge { gauge } => {
+                match key.as_str() {
+                    "fontSize" => {
+                        ctx.overrides.font_size = Some(value);
+                        needs_cascade = true;
+                    }
+                    "value" => gauge.set_value(value, self.clock.now()),
+                    "min" => gauge.min = value,
+                    "max" => gauge.max = value,
+                    "startAngle" => gauge.start_angle = value,
+                    "endAngle" => gauge.end_angle = value,
+                    "ticks" => gauge.major_ticks = value.max(0.0) as u32,
+                    "minorTicks" => gauge.minor_ticks = value.max(0.0) as u32,
+                    "thickness" => gauge.thickness = Some(value.max(0.0)),
+                    "sweepDuration" => {
+                        gauge.sweep_duration = Duration::from_secs_f32(value.max(0.0) / 1000.0)
+                    }
+                    _ => {}
+                }
+                ctx.render_dirty = true;
+            }
+            NodeKind::Chart { chart } => {
+                match key.as_str() {
+                    "fontSize" => {
+                        ctx.overrides.font_size = Some(value);
+                        needs_cascade = true;
+                    }
+                    "capacity" => chart.set_capacity(Some(value.max(1.0) as usize)),
+                    "min" => chart.min = Some(value),
+                    "max" => chart.max = Some(value),
+                    "ticks" => chart.ticks = value.max(0.0) as u32,
+                    "lineWidth" => chart.line_width = value.max(0.0),
+                    _ => {}
+                }
+                ctx.render_dirty = true;
+            }
+            NodeKind::Table { table } => match key.as_str() {
+                "fontSize" => {
+                    ctx.overrides.font_size = Some(value);
+                    needs_cascade = true;
+                }
+                "rowHeight" => {
+                    table.row_height = Some(value);
+                    ctx.render_dirty = true;
+                }
                 _ => {}
             },
+            NodeKind::Scanner { scanner } => {
+                match key.as_str() {
+                    "viewfinderSize" => scanner.viewfinder_size = value.clamp(0.0, 1.0),
+                    "viewfinderAspect" => scanner.viewfinder_aspect = value.max(0.01),
+                    "repeatDelay" => {
+                        scanner.repeat_delay = Duration::from_secs_f32(value.max(0.0) / 1000.0)
+                    }
+                    _ => {}
+                }
+                ctx.render_dirty = true;
+            }
+            NodeKind::Text { .. } | NodeKind::Markdown { .. } if key == "fontSize" => {
+                ctx.overrides.font_size = Some(value);
+                needs_cascade = true;
+            }
+            NodeKind::Text { .. } if key == "letterSpacing" => {
+                ctx.overrides.letter_spacing = Some(value);
+                needs_cascade = true;
+            }
+            NodeKind::Text { .. } if key == "tabSize" => {
+                if let Some(tab_size) = tab_size(value) {
+                    ctx.overrides.tab_size = Some(tab_size);
+                    needs_cascade = true;
+                }
+            }
+            NodeKind::Text { .. } if key == "maxLines" => {
+                ctx.overrides.max_lines = Some(value.max(0.0) as u32);
+                needs_cascade = true;
+            }
+            NodeKind::Text { .. } if key == "fontWeight" => {
+                ctx.overrides.font_weight = FontWeight::from_number(value);
+                needs_cascade = true;
+            }
+            #[cfg(feature = "lottie")]
+            NodeKind::Lottie { player } if key == "speed" => player.speed = value,
             _ => {}
         };
 
@@ -354,44 +1464,89 @@ impl Dom {
     ) -> Result<(), DomError> {
         let node_id = NodeId::from(node_id);
 
+        // Handle non-layout style properties stored on the NodeContext
+        if key == "objectFit" {
+            if let Some(ctx) = self.tree.get_node_context_mut(node_id) {
+                match &mut ctx.kind {
+                    NodeKind::Video { object_fit, .. } => *object_fit = parse_object_fit(&value),
+                    NodeKind::Scanner { scanner } => scanner.object_fit = parse_object_fit(&value),
+                    _ => return Ok(()),
+                }
+                ctx.render_dirty = true;
+            }
+            return Ok(());
+        }
+
+        if key == "transform" {
+            if let Some(ctx) = self.tree.get_node_context_mut(node_id) {
+                ctx.transform = Affine::parse(&value);
+                ctx.render_dirty = true;
+            }
+            return Ok(());
+        }
+
         let style = self.tree.style(node_id).map_err(|_| DomError {
             message: "Could not update style".to_string(),
+            limit: None,
         })?;
 
         let mut style = style.clone();
 
-        if value == "auto" {
-            match key.as_str() {
-                "flexBasis" => style.flex_basis = Dimension::auto(),
-                "width" => style.size.width = Dimension::auto(),
-                "height" => style.size.height = Dimension::auto(),
-                "marginTop" => style.margin.top = LengthPercentageAuto::auto(),
-                "marginRight" => style.margin.right = LengthPercentageAuto::auto(),
-                "marginBottom" => style.margin.bottom = LengthPercentageAuto::auto(),
-                "marginLeft" => style.margin.left = LengthPercentageAuto::auto(),
-                _ => {}
+        if grid::set_style(&mut style, &key, &value) {
+            // Grid values have their own syntax, `auto` included
+        } else if key == "flex" {
+            if let Some((grow, shrink, basis)) = parse_flex(&value) {
+                style.flex_grow = grow;
+                style.flex_shrink = shrink;
+                style.flex_basis = basis;
+            }
+        } else if value == "auto" {
+            for key in self.expand_style_key(node_id, &key) {
+                match key {
+                    "flexBasis" => style.flex_basis = Dimension::auto(),
+                    "width" => style.size.width = Dimension::auto(),
+                    "height" => style.size.height = Dimension::auto(),
+                    "marginTop" => style.margin.top = LengthPercentageAuto::auto(),
+                    "marginRight" => style.margin.right = LengthPercentageAuto::auto(),
+                    "marginBottom" => style.margin.bottom = LengthPercentageAuto::auto(),
+                    "marginLeft" => style.margin.left = LengthPercentageAuto::auto(),
+                    _ => {}
+                }
             }
         } else {
-            match key.as_str() {
-                "alignContent" => style.align_content = parse_align_content(&value),
-                "alignItems" => style.align_items = parse_align_items(&value),
-                "alignSelf" => style.align_self = parse_align_items(&value),
-                "boxSizing" => style.box_sizing = parse_box_sizing(&value),
-                "display" => style.display = parse_display(&value),
-                "flexDirection" => style.flex_direction = parse_flex_direction(&value),
-                "flexWrap" => style.flex_wrap = parse_flex_wrap(&value),
-                "justifyContent" => style.justify_content = parse_align_content(&value),
-                "justifyItems" => style.justify_items = parse_align_items(&value),
-                "justifySelf" => style.justify_self = parse_align_items(&value),
-                "overflowX" => style.overflow.x = parse_overflow(&value),
-                "overflowY" => style.overflow.y = parse_overflow(&value),
-                "position" => style.position = parse_position(&value),
-                _ => {}
+            for key in self.expand_style_key(node_id, &key) {
+                match key {
+                    "alignContent" => style.align_content = parse_align_content(&value),
+                    "alignItems" => style.align_items = parse_align_items(&value),
+                    "alignSelf" => style.align_self = parse_align_items(&value),
+                    "boxSizing" => style.box_sizing = parse_box_sizing(&value),
+                    "display" => style.display = parse_display(&value),
+                    "flexDirection" => style.flex_direction = parse_flex_direction(&value),
+                    "flexWrap" => style.flex_wrap = parse_flex_wrap(&value),
+                    "justifyContent" => style.justify_content = parse_align_content(&value),
+                    "justifyItems" => style.justify_items = parse_align_items(&value),
+                    "justifySelf" => style.justify_self = parse_align_items(&value),
+                    "overflowX" => style.overflow.x = parse_overflow(&value),
+                    "overflowY" => style.overflow.y = parse_overflow(&value),
+                    "position" => style.position = parse_position(&value),
+                    _ => {}
+                }
             }
         }
 
+        // Rows run right to left inside `direction: "rtl"`
+        if key == "flexDirection"
+            && self
+                .tree
+                .get_node_context(node_id)
+                .is_some_and(|ctx| ctx.resolved_style.direction == Direction::Rtl)
+        {
+            style.flex_direction = mirror(style.flex_direction);
+        }
+
         self.tree.set_style(node_id, style).map_err(|_| DomError {
             message: "Could not update style".to_string(),
+            limit: None,
         })
     }
 
@@ -414,38 +1569,100 @@ impl Dom {
             return Ok(());
         }
 
+        if key == "zIndex" {
+            if let Some(ctx) = self.tree.get_node_context_mut(node_id) {
+                ctx.z_index = value as i32;
+                ctx.render_dirty = true;
+            }
+            return Ok(());
+        }
+
+        if key == "opacity" {
+            if let Some(ctx) = self.tree.get_node_context_mut(node_id) {
+                ctx.opacity = if value.is_finite() {
+                    value.clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+                ctx.render_dirty = true;
+            }
+            return Ok(());
+        }
+
         let style = self.tree.style(node_id).map_err(|_| DomError {
             message: "Could not update style".to_string(),
+            limit: None,
         })?;
 
         let mut style = style.clone();
 
-        match key.as_str() {
-            "flexBasis" => style.flex_basis = Dimension::length(value),
-            "flexGrow" => style.flex_grow = value,
-            "flexShrink" => style.flex_shrink = value,
-            "gapHeight" => style.gap.height = LengthPercentage::length(value),
-            "gapWidth" => style.gap.width = LengthPercentage::length(value),
-            "height" => style.size.height = Dimension::length(value),
-            "marginBottom" => style.margin.bottom = LengthPercentageAuto::length(value),
-            "marginLeft" => style.margin.left = LengthPercentageAuto::length(value),
-            "marginRight" => style.margin.right = LengthPercentageAuto::length(value),
-            "marginTop" => style.margin.top = LengthPercentageAuto::length(value),
-            "maxHeight" => style.max_size.height = Dimension::length(value),
-            "maxWidth" => style.max_size.width = Dimension::length(value),
-            "paddingBottom" => style.padding.bottom = LengthPercentage::length(value),
-            "paddingLeft" => style.padding.left = LengthPercentage::length(value),
-            "paddingRight" => style.padding.right = LengthPercentage::length(value),
-            "paddingTop" => style.padding.top = LengthPercentage::length(value),
-            "width" => style.size.width = Dimension::length(value),
-            _ => {}
-        };
+        // A track size in pixels, or a line number for `gridColumn` and `gridRow`
+        grid::set_style(&mut style, &key, &value.to_string());
+
+        for key in self.expand_style_key(node_id, &key) {
+            match key {
+                "borderBottomWidth" => style.border.bottom = LengthPercentage::length(value),
+                "borderLeftWidth" => style.border.left = LengthPercentage::length(value),
+                "borderRightWidth" => style.border.right = LengthPercentage::length(value),
+                "borderTopWidth" => style.border.top = LengthPercentage::length(value),
+                "flex" => {
+                    // `flex: 1` fills the free space evenly, as in CSS and React Native
+                    style.flex_grow = value;
+                    style.flex_shrink = 1.0;
+                    style.flex_basis = Dimension::length(0.0);
+                }
+                "flexBasis" => style.flex_basis = Dimension::length(value),
+                "flexGrow" => style.flex_grow = value,
+                "flexShrink" => style.flex_shrink = value,
+                "gapHeight" => style.gap.height = LengthPercentage::length(value),
+                "gapWidth" => style.gap.width = LengthPercentage::length(value),
+                "height" => style.size.height = Dimension::length(value),
+                "marginBottom" => style.margin.bottom = LengthPercentageAuto::length(value),
+                "marginLeft" => style.margin.left = LengthPercentageAuto::length(value),
+                "marginRight" => style.margin.right = LengthPercentageAuto::length(value),
+                "marginTop" => style.margin.top = LengthPercentageAuto::length(value),
+                "maxHeight" => style.max_size.height = Dimension::length(value),
+                "maxWidth" => style.max_size.width = Dimension::length(value),
+                "paddingBottom" => style.padding.bottom = LengthPercentage::length(value),
+                "paddingLeft" => style.padding.left = LengthPercentage::length(value),
+                "paddingRight" => style.padding.right = LengthPercentage::length(value),
+                "paddingTop" => style.padding.top = LengthPercentage::length(value),
+                "width" => style.size.width = Dimension::length(value),
+                _ => {}
+            }
+        }
 
         self.tree.set_style(node_id, style).map_err(|_| DomError {
             message: "Could not update style".to_string(),
+            limit: None,
         })
     }
 
+    /// `expand_shorthand` for a style set on `node_id`. `rowGap` and `columnGap` win over
+    /// `gap` whichever order they're set in, as in CSS, so `gap` skips the axes they set.
+    fn expand_style_key<'a>(&mut self, node_id: NodeId, key: &'a str) -> Vec<&'a str> {
+        let Some(ctx) = self.tree.get_node_context_mut(node_id) else {
+            return expand_shorthand(key);
+        };
+
+        match key {
+            "rowGap" => ctx.gap_longhands.0 = true,
+            "columnGap" => ctx.gap_longhands.1 = true,
+            _ => {}
+        }
+
+        let (row, column) = ctx.gap_longhands;
+
+        expand_shorthand(key)
+            .into_iter()
+            .filter(|&longhand| match (key, longhand) {
+                ("gap", "gapHeight") => !row,
+                ("gap", "gapWidth") => !column,
+                _ => true,
+            })
+            .collect()
+    }
+
     pub fn set_style_percent(
         &mut self,
         node_id: u64,
@@ -456,32 +1673,38 @@ impl Dom {
 
         let style = self.tree.style(node_id).map_err(|_| DomError {
             message: "Could not update style".to_string(),
+            limit: None,
         })?;
 
         let mut style = style.clone();
         let fraction = value / 100.0;
 
-        match key.as_str() {
-            "flexBasis" => style.flex_basis = Dimension::percent(fraction),
-            "gapHeight" => style.gap.height = LengthPercentage::percent(fraction),
-            "gapWidth" => style.gap.width = LengthPercentage::percent(fraction),
-            "height" => style.size.height = Dimension::percent(fraction),
-            "marginBottom" => style.margin.bottom = LengthPercentageAuto::percent(fraction),
-            "marginLeft" => style.margin.left = LengthPercentageAuto::percent(fraction),
-            "marginRight" => style.margin.right = LengthPercentageAuto::percent(fraction),
-            "marginTop" => style.margin.top = LengthPercentageAuto::percent(fraction),
-            "maxHeight" => style.max_size.height = Dimension::percent(fraction),
-            "maxWidth" => style.max_size.width = Dimension::percent(fraction),
-            "paddingBottom" => style.padding.bottom = LengthPercentage::percent(fraction),
-            "paddingLeft" => style.padding.left = LengthPercentage::percent(fraction),
-            "paddingRight" => style.padding.right = LengthPercentage::percent(fraction),
-            "paddingTop" => style.padding.top = LengthPercentage::percent(fraction),
-            "width" => style.size.width = Dimension::percent(fraction),
-            _ => {}
+        grid::set_style(&mut style, &key, &format!("{value}%"));
+
+        for key in self.expand_style_key(node_id, &key) {
+            match key {
+                "flexBasis" => style.flex_basis = Dimension::percent(fraction),
+                "gapHeight" => style.gap.height = LengthPercentage::percent(fraction),
+                "gapWidth" => style.gap.width = LengthPercentage::percent(fraction),
+                "height" => style.size.height = Dimension::percent(fraction),
+                "marginBottom" => style.margin.bottom = LengthPercentageAuto::percent(fraction),
+                "marginLeft" => style.margin.left = LengthPercentageAuto::percent(fraction),
+                "marginRight" => style.margin.right = LengthPercentageAuto::percent(fraction),
+                "marginTop" => style.margin.top = LengthPercentageAuto::percent(fraction),
+                "maxHeight" => style.max_size.height = Dimension::percent(fraction),
+                "maxWidth" => style.max_size.width = Dimension::percent(fraction),
+                "paddingBottom" => style.padding.bottom = LengthPercentage::percent(fraction),
+                "paddingLeft" => style.padding.left = LengthPercentage::percent(fraction),
+                "paddingRight" => style.padding.right = LengthPercentage::percent(fraction),
+                "paddingTop" => style.padding.top = LengthPercentage::percent(fraction),
+                "width" => style.size.width = Dimension::percent(fraction),
+                _ => {}
+            }
         }
 
         self.tree.set_style(node_id, style).map_err(|_| DomError {
             message: "Could not update style".to_string(),
+            limit: None,
         })
     }
 
@@ -490,6 +1713,7 @@ impl Dom {
 
         let style = self.tree.style(node_id).map_err(|_| DomError {
             message: "Could not update style".to_string(),
+            limit: None,
         })?;
 
         let ctx = self
@@ -497,6 +1721,7 @@ impl Dom {
             .get_node_context(node_id)
             .ok_or_else(|| DomError {
                 message: "Invalid NodeId".to_string(),
+                limit: None,
             })?;
 
         let inherited_style = ctx.resolved_style.with_overrides(&ctx.overrides);
@@ -504,134 +1729,1371 @@ impl Dom {
         let mut style = style.clone();
         let length = value * inherited_style.font_size;
 
-        match key.as_str() {
-            "flexBasis" => style.flex_basis = Dimension::length(length),
-            "gapHeight" => style.gap.height = LengthPercentage::length(length),
-            "gapWidth" => style.gap.width = LengthPercentage::length(length),
-            "height" => style.size.height = Dimension::length(length),
-            "marginBottom" => style.margin.bottom = LengthPercentageAuto::length(length),
-            "marginLeft" => style.margin.left = LengthPercentageAuto::length(length),
-            "marginRight" => style.margin.right = LengthPercentageAuto::length(length),
-            "marginTop" => style.margin.top = LengthPercentageAuto::length(length),
-            "maxHeight" => style.max_size.height = Dimension::length(length),
-            "maxWidth" => style.max_size.width = Dimension::length(length),
-            "paddingBottom" => style.padding.bottom = LengthPercentage::length(length),
-            "paddingLeft" => style.padding.left = LengthPercentage::length(length),
-            "paddingRight" => style.padding.right = LengthPercentage::length(length),
-            "paddingTop" => style.padding.top = LengthPercentage::length(length),
-            "width" => style.size.width = Dimension::length(length),
-            _ => {}
+        grid::set_style(&mut style, &key, &length.to_string());
+
+        for key in self.expand_style_key(node_id, &key) {
+            match key {
+                "borderBottomWidth" => style.border.bottom = LengthPercentage::length(length),
+                "borderLeftWidth" => style.border.left = LengthPercentage::length(length),
+                "borderRightWidth" => style.border.right = LengthPercentage::length(length),
+                "borderTopWidth" => style.border.top = LengthPercentage::length(length),
+                "flexBasis" => style.flex_basis = Dimension::length(length),
+                "gapHeight" => style.gap.height = LengthPercentage::length(length),
+                "gapWidth" => style.gap.width = LengthPercentage::length(length),
+                "height" => style.size.height = Dimension::length(length),
+                "marginBottom" => style.margin.bottom = LengthPercentageAuto::length(length),
+                "marginLeft" => style.margin.left = LengthPercentageAuto::length(length),
+                "marginRight" => style.margin.right = LengthPercentageAuto::length(length),
+                "marginTop" => style.margin.top = LengthPercentageAuto::length(length),
+                "maxHeight" => style.max_size.height = Dimension::length(length),
+                "maxWidth" => style.max_size.width = Dimension::length(length),
+                "paddingBottom" => style.padding.bottom = LengthPercentage::length(length),
+                "paddingLeft" => style.padding.left = LengthPercentage::length(length),
+                "paddingRight" => style.padding.right = LengthPercentage::length(length),
+                "paddingTop" => style.padding.top = LengthPercentage::length(length),
+                "width" => style.size.width = Dimension::length(length),
+                _ => {}
+            }
+        }
+
+        self.tree.set_style(node_id, style).map_err(|_| DomError {
+            message: "Could not update style".to_string(),
+            limit: None,
+        })
+    }
+
+    pub fn compute_layout(&mut self, fonts: &FontRegistry, width: f32, height: f32) {
+        let Some(root) = self.root_node_id else {
+            return;
+        };
+
+        let available = Size {
+            width: AvailableSpace::Definite(width),
+            height: AvailableSpace::Definite(height),
+        };
+
+        self.compute_layout_from(fonts, root, available);
+    }
+
+    /// Lay out a subtree on its own, `width` wide and as tall as its content, e.g. to
+    /// print it. This replaces its layout for the screen, so the whole tree must be laid
+    /// out again afterwards with `restore_subtree_layout`.
+    pub fn compute_subtree_layout(
+        &mut self,
+        fonts: &FontRegistry,
+        node_id: NodeId,
+        width: f32,
+    ) -> Option<Size<f32>> {
+        self.mark_subtree_dirty(node_id);
+
+        let available = Size {
+            width: AvailableSpace::Definite(width),
+            height: AvailableSpace::MaxContent,
+        };
+
+        self.compute_layout_from(fonts, node_id, available);
+        self.get_layout(node_id).map(|layout| layout.size)
+    }
+
+    /// Lay the whole tree out for the screen again after `compute_subtree_layout`.
+    pub fn restore_subtree_layout(
+        &mut self,
+        fonts: &FontRegistry,
+        node_id: NodeId,
+        width: f32,
+        height: f32,
+    ) {
+        self.mark_subtree_dirty(node_id);
+        self.compute_layout(fonts, width, height);
+    }
+
+    /// Change the font for text that doesn't set its own, re-measuring the whole tree.
+    pub fn set_default_font(&mut self, name: String) {
+        self.inherited_style.font_name = name;
+
+        if let Some(root) = self.root_node_id {
+            self.cascade_resolved_style(root);
+        }
+
+        self.fonts_changed();
+    }
+
+    pub fn text_scale(&self) -> f32 {
+        self.text_scale
+    }
+
+    /// Multiply every font size by `scale`, for an accessibility text size setting, and
+    /// lay the tree out again so it reflows around the new sizes. Clamped to 0.5 to 4;
+    /// lengths already set in `em` keep their size.
+    pub fn set_text_scale(&mut self, scale: f32) {
+        let scale = if scale.is_finite() {
+            scale.clamp(0.5, 4.0)
+        } else {
+            1.0
+        };
+
+        if scale == self.text_scale {
+            return;
+        }
+
+        self.text_scale = scale;
+
+        if let Some(root) = self.root_node_id {
+            self.cascade_resolved_style(root);
+            self.mark_subtree_dirty(root);
+            self.mark_subtree_render_dirty(root);
+        }
+    }
+
+    /// The base style with the text scale applied, which the root inherits.
+    fn scaled_base_style(&self) -> InheritedStyle {
+        InheritedStyle {
+            font_size: self.inherited_style.font_size * self.text_scale,
+            ..self.inherited_style.clone()
+        }
+    }
+
+    /// Re-measure and redraw the whole tree after the loaded fonts change, since text in
+    /// a font that was missing was measured in the default one.
+    pub fn fonts_changed(&mut self) {
+        if let Some(root) = self.root_node_id {
+            self.mark_subtree_dirty(root);
+            self.mark_subtree_render_dirty(root);
+        }
+    }
+
+    /// Redraw a node and everything under it from scratch, skipping cached rasters.
+    pub fn mark_subtree_render_dirty(&mut self, node_id: NodeId) {
+        let mut stack = vec![node_id];
+
+        while let Some(node_id) = stack.pop() {
+            let Some(ctx) = self.tree.get_node_context_mut(node_id) else {
+                continue;
+            };
+
+            ctx.render_dirty = true;
+            ctx.raster_request = None;
+
+            if let Ok(children) = self.tree.children(node_id) {
+                stack.extend(children);
+            }
+        }
+    }
+
+    /// Start drawing a new frame, for telling which nodes were drawn in it.
+    pub fn begin_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    pub fn mark_drawn(&mut self, node_id: NodeId) {
+        let frame = self.frame;
+
+        if let Some(ctx) = self.tree.get_node_context_mut(node_id) {
+            ctx.last_drawn = frame;
+        }
+    }
+
+    /// Drop the caches of the nodes drawn least recently until they hold `bytes` at most.
+    /// Caches drawn in the current frame are kept unless `include_current` is set, so a
+    /// budget smaller than one screen doesn't decode it over and over.
+    pub fn trim_caches(&mut self, bytes: usize, include_current: bool) {
+        let Some(root) = self.root_node_id else {
+            return;
+        };
+        let mut cached = Vec::new();
+        let mut total = 0;
+        let mut stack = vec![root];
+
+        while let Some(node_id) = stack.pop() {
+            if let Some(ctx) = self.tree.get_node_context(node_id) {
+                let node_bytes = ctx.cache_bytes();
+
+                if node_bytes > 0 {
+                    total += node_bytes;
+                    cached.push((ctx.last_drawn, node_bytes, node_id));
+                }
+            }
+
+            if let Ok(children) = self.tree.children(node_id) {
+                stack.extend(children);
+            }
+        }
+
+        if total <= bytes {
+            return;
+        }
+
+        cached.sort_unstable_by_key(|(last_drawn, _, _)| *last_drawn);
+
+        for (last_drawn, node_bytes, node_id) in cached {
+            if total <= bytes || (last_drawn == self.frame && !include_current) {
+                break;
+            }
+
+            if let Some(ctx) = self.tree.get_node_context_mut(node_id) {
+                ctx.drop_caches();
+                total -= node_bytes;
+            }
+        }
+    }
+
+    /// Use a raster decoded off the render thread, if its node still wants it. Returns
+    /// whether it did, so there's something new to draw.
+    pub fn apply_decoded(&mut self, decoded: Decoded) -> bool {
+        self.tree
+            .get_node_context_mut(NodeId::from(decoded.node_id))
+            .is_some_and(|ctx| ctx.apply_decoded(decoded))
+    }
+
+    /// Clear the cached layout of a node and everything under it, so none of it is
+    /// reused when laid out with different space.
+    fn mark_subtree_dirty(&mut self, node_id: NodeId) {
+        let mut stack = vec![node_id];
+
+        while let Some(node_id) = stack.pop() {
+            let _ = self.tree.mark_dirty(node_id);
+
+            if let Ok(children) = self.tree.children(node_id) {
+                stack.extend(children);
+            }
+        }
+    }
+
+    fn compute_layout_from(
+        &mut self,
+        fonts: &FontRegistry,
+        root: NodeId,
+        available: Size<AvailableSpace>,
+    ) {
+        let default_font = &self.inherited_style.font_name;
+
+        self.tree
+            .compute_layout_with_measure(
+                root,
+                available,
+                |known_size, available_space, _node_id, context, _style| {
+                    if let Some(NodeContext {
+                        kind: NodeKind::Input { .. },
+                        resolved_style,
+                        ..
+                    }) = context.as_deref()
+                    {
+                        return input_size(fonts, default_font, resolved_style, known_size);
+                    }
+
+                    if let Some(NodeContext {
+                        kind: NodeKind::Picker { picker },
+                        resolved_style,
+                        ..
+                    }) = context.as_deref()
+                    {
+                        let fs = resolved_style.font_size;
+
+                        return Size {
+                            width: known_size
+                                .width
+                                .unwrap_or(fs * 4.0 * picker.wheels.len() as f32),
+                            height: known_size
+                                .height
+                                .unwrap_or(picker::row_height(fs) * picker::VISIBLE_ROWS),
+                        };
+                    }
+
+                    if let Some(NodeContext {
+                        kind: NodeKind::Sprite { animation, .. },
+                        ..
+                    }) = context.as_deref()
+                    {
+                        return Size {
+                            width: known_size.width.unwrap_or(animation.frame_width as f32),
+                            height: known_size.height.unwrap_or(animation.frame_height as f32),
+                        };
+                    }
+
+                    if let Some(NodeContext {
+                        kind: NodeKind::Table { table },
+                        resolved_style,
+                        ..
+                    }) = context.as_deref()
+                    {
+                        let fs = resolved_style.font_size;
+                        // Up to ten rows below the header, unless sized otherwise
+                        let rows = table.rows.len().min(10) + 1;
+
+                        return Size {
+                            width: known_size.width.unwrap_or(fs * 20.0),
+                            height: known_size
+                                .height
+                                .unwrap_or(rows as f32 * table.row_height(fs)),
+                        };
+                    }
+
+                    if let Some(NodeContext {
+                        kind: NodeKind::Chart { .. },
+                        resolved_style,
+                        ..
+                    }) = context.as_deref()
+                    {
+                        let fs = resolved_style.font_size;
+
+                        return Size {
+                            width: known_size.width.unwrap_or(fs * 12.0),
+                            height: known_size.height.unwrap_or(fs * 4.0),
+                        };
+                    }
+
+                    if let Some(NodeContext {
+                        kind: NodeKind::Markdown { markdown },
+                        resolved_style,
+                        ..
+                    }) = context.as_deref()
+                    {
+                        let Some(markdown_fonts) =
+                            markdown.fonts(fonts, resolved_style, default_font)
+                        else {
+                            return Size::ZERO;
+                        };
+
+                        // Wrap at the width we're given, otherwise take the longest line
+                        let max_width = known_size.width.or(match available_space.width {
+                            AvailableSpace::Definite(width) => Some(width),
+                            _ => None,
+                        });
+                        let layout =
+                            markdown.layout(markdown_fonts, resolved_style.font_size, max_width);
+
+                        return Size {
+                            width: known_size
+                                .width
+                                .unwrap_or(layout.width.min(max_width.unwrap_or(f32::INFINITY))),
+                            height: known_size.height.unwrap_or(layout.height),
+                        };
+                    }
+
+                    if let Some(NodeContext {
+                        kind: NodeKind::Gauge { .. },
+                        resolved_style,
+                        ..
+                    }) = context.as_deref()
+                    {
+                        // Square by default, so the dial is as big as it can be
+                        let side = known_size
+                            .width
+                            .or(known_size.height)
+                            .unwrap_or(resolved_style.font_size * 10.0);
+
+                        return Size {
+                            width: known_size.width.unwrap_or(side),
+                            height: known_size.height.unwrap_or(side),
+                        };
+                    }
+
+                    if let Some(NodeContext {
+                        kind:
+                            NodeKind::Text {
+                                text, wrap_width, ..
+                            },
+                        resolved_style,
+                        ..
+                    }) = context
+                    {
+                        let fs = resolved_style.font_size;
+
+                        if let Some(font) =
+                            font_fallback::resolve(fonts, resolved_style, default_font)
+                        {
+                            let chain = fonts.chain(font);
+                            let letter_spacing = resolved_style.letter_spacing;
+                            let max_lines = resolved_style.max_lines as usize;
+                            let single_line = resolved_style.text_overflow != TextOverflow::Wrap
+                                && max_lines == 0;
+                            let clamp = |lines: usize| match max_lines {
+                                0 => lines,
+                                max_lines => lines.min(max_lines),
+                            };
+                            let text = canvas::expand_whitespace(
+                                &chain,
+                                text,
+                                fs,
+                                letter_spacing,
+                                resolved_style.tab_size,
+                                single_line,
+                            );
+                            let text = text.as_ref();
+                            let single_line_width =
+                                canvas::text_width(&chain, text, fs, letter_spacing);
+
+                            let normal = canvas::normal_line_height(font, fs);
+                            let line_height = resolved_style.line_height.resolve(fs, normal);
+
+                            // Determine width following the canonical Taffy pattern:
+                            // known_size is a hard constraint, available_space is
+                            // clamped between min-content and max-content.
+                            let width =
+                                known_size
+                                    .width
+                                    .unwrap_or_else(|| match available_space.width {
+                                        // Truncated text can shrink to nothing
+                                        AvailableSpace::MinContent if single_line => 0.0,
+                                        AvailableSpace::MinContent => canvas::min_content_width(
+                                            &chain,
+                                            text,
+                                            fs,
+                                            letter_spacing,
+                                        ),
+                                        AvailableSpace::MaxContent => single_line_width,
+                                        AvailableSpace::Definite(w) => w.min(single_line_width),
+                                    });
+
+                            if single_line_width > width + 1.0 && !single_line {
+                                // Break lines the way draw_text will
+                                let lines = if max_lines > 0
+                                    || canvas::wraps_by_word(
+                                        &chain,
+                                        text,
+                                        letter_spacing,
+                                        resolved_style.direction,
+                                    ) {
+                                    canvas::wrap_lines(&chain, text, fs, letter_spacing, width)
+                                        .len()
+                                } else {
+                                    let mut text_layout =
+                                        TextLayout::new(CoordinateSystem::PositiveYDown);
+                                    text_layout.reset(&LayoutSettings {
+                                        max_width: Some(width),
+                                        line_height: canvas::line_height_scale(line_height, normal),
+                                        ..LayoutSettings::default()
+                                    });
+                                    canvas::append_text(&mut text_layout, &chain, text, fs);
+                                    text_layout.lines().map_or(1, |lines| lines.len())
+                                };
+                                let h = known_size
+                                    .height
+                                    .unwrap_or(clamp(lines.max(1)) as f32 * line_height);
+                                *wrap_width = Some(width);
+                                Size { width, height: h }
+                            } else {
+                                // Line breaks in the text still start new lines
+                                let lines = clamp(text.split('\n').count());
+                                *wrap_width = None;
+                                Size {
+                                    width,
+                                    height: known_size.height.unwrap_or(lines as f32 * line_height),
+                                }
+                            }
+                        } else {
+                            Size::ZERO
+                        }
+                    } else {
+                        Size::ZERO
+                    }
+                },
+            )
+            .unwrap();
+    }
+
+    /// Edit a text input's state, marking it for redraw. `None` if the node isn't an input.
+    pub fn edit_text_input<R>(
+        &mut self,
+        node_id: NodeId,
+        edit: impl FnOnce(&mut TextInputState) -> R,
+    ) -> Option<R> {
+        let ctx = self.tree.get_node_context_mut(node_id)?;
+
+        let NodeKind::Input { state, .. } = &mut ctx.kind else {
+            return None;
+        };
+
+        ctx.render_dirty = true;
+        Some(edit(state))
+    }
+
+    /// Append values to a chart's data, marking it for redraw. `false` if the node isn't
+    /// a chart.
+    pub fn append_chart_values(&mut self, node_id: NodeId, values: Vec<f32>) -> bool {
+        let Some(ctx) = self.tree.get_node_context_mut(node_id) else {
+            return false;
+        };
+
+        let NodeKind::Chart { chart } = &mut ctx.kind else {
+            return false;
+        };
+
+        chart.append(values);
+        ctx.render_dirty = true;
+        true
+    }
+
+    pub fn text_input(&self, node_id: NodeId) -> Option<&TextInputState> {
+        match &self.tree.get_node_context(node_id)?.kind {
+            NodeKind::Input { state, .. } => Some(state),
+            _ => None,
+        }
+    }
+
+    /// The keypad layout an input wants while focused, and whether to shuffle it.
+    pub fn input_keypad(&self, node_id: NodeId) -> Option<(KeypadLayout, bool)> {
+        match &self.tree.get_node_context(node_id)?.kind {
+            NodeKind::Input {
+                keypad: Some(layout),
+                shuffle_keys,
+                ..
+            } => Some((*layout, *shuffle_keys)),
+            _ => None,
+        }
+    }
+
+    /// Find the text input under a point, if any.
+    pub fn input_at_point(&self, x: f32, y: f32) -> Option<NodeId> {
+        let mut node_id = self.node_at_point(x, y).map(NodeId::from);
+
+        while let Some(id) = node_id {
+            if self.text_input(id).is_some() {
+                return Some(id);
+            }
+
+            node_id = self.input_parent(id);
+        }
+
+        None
+    }
+
+    /// Edit a picker's state, marking it for redraw. The closure also gets the picker's
+    /// font size, which sets its row height. `None` if the node isn't a picker.
+    pub fn edit_picker<R>(
+        &mut self,
+        node_id: NodeId,
+        edit: impl FnOnce(&mut Picker, f32) -> R,
+    ) -> Option<R> {
+        let ctx = self.tree.get_node_context_mut(node_id)?;
+
+        let NodeKind::Picker { picker } = &mut ctx.kind else {
+            return None;
+        };
+
+        ctx.render_dirty = true;
+        Some(edit(picker, ctx.resolved_style.font_size))
+    }
+
+    /// Find the picker wheel under a point, with how many rows the point is from the
+    /// wheel's centre row.
+    pub fn picker_wheel_at_point(&self, x: f32, y: f32) -> Option<(NodeId, usize, f32)> {
+        let mut node_id = self.node_at_point(x, y).map(NodeId::from);
+
+        while let Some(id) = node_id {
+            if let Some(NodeContext {
+                kind: NodeKind::Picker { picker },
+                resolved_style,
+                ..
+            }) = self.get_node(id)
+            {
+                let (px, py, pw, ph) = self.absolute_rect(id)?;
+                let column_width = pw / picker.wheels.len() as f32;
+                let wheel = (((x - px) / column_width) as usize).min(picker.wheels.len() - 1);
+                let rows = (y - (py + ph / 2.0)) / picker::row_height(resolved_style.font_size);
+
+                return Some((id, wheel, rows));
+            }
+
+            node_id = self.input_parent(id);
+        }
+
+        None
+    }
+
+    /// Find the table cell under a point: the table, the row (`None` for the header)
+    /// and the column.
+    pub fn table_cell_at_point(&self, x: f32, y: f32) -> Option<(NodeId, Option<usize>, usize)> {
+        let mut node_id = self.node_at_point(x, y).map(NodeId::from);
+
+        while let Some(id) = node_id {
+            if let Some(NodeContext {
+                kind: NodeKind::Table { table },
+                resolved_style,
+                scroll_y,
+                ..
+            }) = self.get_node(id)
+            {
+                let (tx, ty, tw, _) = self.absolute_rect(id)?;
+                let (row, column) =
+                    table.cell_at((x - tx, y - ty), tw, resolved_style.font_size, *scroll_y)?;
+
+                return Some((id, row, column));
+            }
+
+            node_id = self.input_parent(id);
+        }
+
+        None
+    }
+
+    /// Show a host-supplied value in every `<bound>` node bound to `key`, re-measuring
+    /// just those nodes. Returns whether any node changed, so needs laying out again.
+    pub fn set_binding(&mut self, key: &str, value: String) -> bool {
+        if self.bindings.get(key) == Some(&value) {
+            return false;
+        }
+
+        let mut changed = false;
+        let mut stack: Vec<NodeId> = self.root_node_id.into_iter().collect();
+
+        while let Some(node_id) = stack.pop() {
+            if let Some(ctx) = self.tree.get_node_context_mut(node_id)
+                && let NodeKind::Text {
+                    text,
+                    binding: Some(binding),
+                    format,
+                    ..
+                } = &mut ctx.kind
+                && binding == key
+            {
+                *text = format_bound(format.as_ref(), &value);
+                ctx.render_dirty = true;
+                let _ = self.tree.mark_dirty(node_id);
+                changed = true;
+                self.note_live_change(node_id);
+            }
+
+            if let Ok(children) = self.tree.children(node_id) {
+                stack.extend(children);
+            }
+        }
+
+        self.bindings.insert(key.to_string(), value);
+        changed
+    }
+
+    /// Remember that the content under `node_id` changed, if it's inside a live region
+    /// that's in the tree.
+    fn note_live_change(&mut self, node_id: NodeId) {
+        let ancestors = self.ancestors(node_id);
+
+        if ancestors.last() != self.root_node_id.as_ref() {
+            return;
+        }
+
+        let region = ancestors.into_iter().find(|&id| {
+            self.tree
+                .get_node_context(id)
+                .is_some_and(|ctx| ctx.a11y.live != LiveRegion::Off)
+        });
+
+        if let Some(region) = region
+            && !self.live_changes.contains(&region)
+        {
+            self.live_changes.push(region);
+        }
+    }
+
+    /// Live regions whose content changed since this was last called, in the order they
+    /// changed. Regions that only just became live aren't included, as with ARIA, so
+    /// content rendered along with a region isn't announced.
+    pub fn take_live_changes(&mut self) -> Vec<NodeId> {
+        let new = std::mem::take(&mut self.new_live_regions);
+        let mut changes = std::mem::take(&mut self.live_changes);
+        changes.retain(|id| !new.contains(id));
+        changes
+    }
+
+    /// What a screen reader should say about a node: its label or text, its role and any
+    /// value. `None` if the node isn't in the tree or has nothing to say.
+    pub fn announcement(&self, node_id: NodeId, reason: AnnounceReason) -> Option<Announcement> {
+        let ctx = self.tree.get_node_context(node_id)?;
+
+        let (role, value) = match &ctx.kind {
+            NodeKind::Input { state, .. } => {
+                let value = Some(state.value.clone()).filter(|_| !state.secure);
+                ("text field", value)
+            }
+            NodeKind::Gauge { gauge } => ("gauge", Some(format!("{}", gauge.value()))),
+            NodeKind::Picker { .. } => ("picker", None),
+            NodeKind::Image { .. } | NodeKind::Svg { .. } => ("image", None),
+            NodeKind::Table { .. } => ("table", None),
+            NodeKind::Chart { .. } => ("chart", None),
+            _ => ("", None),
+        };
+
+        let label = match (&ctx.a11y.label, &ctx.kind) {
+            (Some(label), _) => label.clone(),
+            (None, NodeKind::Input { placeholder, .. }) => placeholder.clone(),
+            (None, _) => self.text_content(node_id),
+        };
+
+        if label.is_empty() && value.is_none() {
+            return None;
+        }
+
+        Some(Announcement {
+            node_id: u64::from(node_id),
+            label,
+            role: ctx.a11y.role.clone().unwrap_or_else(|| role.to_string()),
+            value,
+            reason,
+            interrupt: reason == AnnounceReason::LiveRegion
+                && ctx.a11y.live == LiveRegion::Assertive,
+        })
+    }
+
+    /// The visible text under a node, joined with spaces.
+    fn text_content(&self, node_id: NodeId) -> String {
+        let mut parts = Vec::new();
+        let mut stack = vec![node_id];
+
+        while let Some(node_id) = stack.pop() {
+            if self.is_hidden(node_id) {
+                continue;
+            }
+
+            if let Some(NodeContext {
+                kind: NodeKind::Text { text, .. },
+                ..
+            }) = self.tree.get_node_context(node_id)
+            {
+                parts.push(text.trim());
+            }
+
+            if let Ok(children) = self.tree.children(node_id) {
+                stack.extend(children.into_iter().rev());
+            }
+        }
+
+        parts.retain(|part| !part.is_empty());
+        parts.join(" ")
+    }
+
+    /// Every `scanner` node that isn't hidden, with its size.
+    pub fn scanners(&self) -> Vec<(NodeId, (f32, f32))> {
+        let mut scanners = Vec::new();
+        let mut stack: Vec<NodeId> = self.root_node_id.into_iter().collect();
+
+        while let Some(node_id) = stack.pop() {
+            if self.is_hidden(node_id) {
+                continue;
+            }
+
+            if let Some(NodeContext {
+                kind: NodeKind::Scanner { .. },
+                ..
+            }) = self.tree.get_node_context(node_id)
+                && let Ok(layout) = self.tree.layout(node_id)
+            {
+                scanners.push((node_id, (layout.size.width, layout.size.height)));
+            }
+
+            if let Ok(children) = self.tree.children(node_id) {
+                stack.extend(children);
+            }
+        }
+
+        scanners
+    }
+
+    /// Edit a scanner's state, marking it for redraw. `None` if the node isn't a scanner.
+    pub fn edit_scanner<R>(
+        &mut self,
+        node_id: NodeId,
+        edit: impl FnOnce(&mut Scanner) -> R,
+    ) -> Option<R> {
+        let ctx = self.tree.get_node_context_mut(node_id)?;
+
+        let NodeKind::Scanner { scanner } = &mut ctx.kind else {
+            return None;
+        };
+
+        ctx.render_dirty = true;
+        Some(edit(scanner))
+    }
+
+    /// Find the markdown link under a point: the node and the link's URL.
+    pub fn markdown_link_at_point(
+        &self,
+        fonts: &FontRegistry,
+        x: f32,
+        y: f32,
+    ) -> Option<(NodeId, String)> {
+        let mut node_id = self.node_at_point(x, y).map(NodeId::from);
+
+        while let Some(id) = node_id {
+            if let Some(NodeContext {
+                kind: NodeKind::Markdown { markdown },
+                resolved_style,
+                ..
+            }) = self.get_node(id)
+            {
+                let (mx, my, mw, _) = self.absolute_rect(id)?;
+                let markdown_fonts =
+                    markdown.fonts(fonts, resolved_style, &self.inherited_style.font_name)?;
+                let href = markdown.link_at(
+                    markdown_fonts,
+                    resolved_style.font_size,
+                    mw,
+                    (x - mx, y - my),
+                )?;
+
+                return Some((id, href.to_string()));
+            }
+
+            node_id = self.input_parent(id);
+        }
+
+        None
+    }
+
+    pub fn get_layout(&self, node_id: NodeId) -> Option<&Layout> {
+        self.tree.layout(node_id).ok()
+    }
+
+    /// The layout style the node was given, which `compute_layout` lays it out from.
+    pub fn get_style(&self, node_id: NodeId) -> Option<&Style> {
+        self.tree.style(node_id).ok()
+    }
+
+    pub fn get_node(&self, node_id: NodeId) -> Option<&NodeContext> {
+        self.tree.get_node_context(node_id)
+    }
+
+    pub fn get_node_mut(&mut self, node_id: NodeId) -> Option<&mut NodeContext> {
+        self.tree.get_node_context_mut(node_id)
+    }
+
+    pub fn get_children(&self, node_id: NodeId) -> Option<Vec<NodeId>> {
+        self.tree.children(node_id).ok()
+    }
+
+    /// A node's children in the order they're painted: by `zIndex`, then by child order.
+    pub fn paint_order(&self, node_id: NodeId) -> Vec<NodeId> {
+        let mut children = self.tree.children(node_id).unwrap_or_default();
+        children.sort_by_key(|&id| self.tree.get_node_context(id).map_or(0, |ctx| ctx.z_index));
+        children
+    }
+
+    /// Offset applied to a node's children by its scroll position.
+    pub fn scroll_offset(&self, node_id: NodeId) -> (f32, f32) {
+        self.tree
+            .get_node_context(node_id)
+            .map_or((0.0, 0.0), |ctx| (ctx.scroll_x, ctx.scroll_y))
+    }
+
+    /// Maximum scroll offset on each axis, zero for axes that aren't `overflow: scroll`.
+    /// Tables always scroll their rows vertically.
+    pub fn scroll_limits(&self, node_id: NodeId) -> (f32, f32) {
+        let (Ok(style), Ok(layout)) = (self.tree.style(node_id), self.tree.layout(node_id)) else {
+            return (0.0, 0.0);
+        };
+
+        if let Some(NodeContext {
+            kind: NodeKind::Table { table },
+            resolved_style,
+            ..
+        }) = self.tree.get_node_context(node_id)
+        {
+            return (
+                0.0,
+                table.max_scroll(resolved_style.font_size, layout.size.height),
+            );
+        }
+
+        let max_x = if style.overflow.x == Overflow::Scroll {
+            layout.scroll_width()
+        } else {
+            0.0
+        };
+
+        let max_y = if style.overflow.y == Overflow::Scroll {
+            layout.scroll_height()
+        } else {
+            0.0
+        };
+
+        (max_x, max_y)
+    }
+
+    /// Whether pulling a node down past the top of its content should refresh it.
+    pub fn is_refreshable(&self, node_id: NodeId) -> bool {
+        matches!(
+            self.tree.get_node_context(node_id),
+            Some(NodeContext {
+                kind: NodeKind::Element {
+                    refreshable: true,
+                    ..
+                },
+                ..
+            })
+        )
+    }
+
+    /// Which axes of a node cut off its children where they overflow: any `overflow`
+    /// other than `visible`.
+    pub fn clip_axes(&self, node_id: NodeId) -> (bool, bool) {
+        self.tree.style(node_id).map_or((false, false), |style| {
+            (
+                style.overflow.x != Overflow::Visible,
+                style.overflow.y != Overflow::Visible,
+            )
+        })
+    }
+
+    /// Which axes of a node are `overflow: scroll`, or vertical for a table.
+    pub fn scroll_axes(&self, node_id: NodeId) -> (bool, bool) {
+        if let Some(NodeContext {
+            kind: NodeKind::Table { .. },
+            ..
+        }) = self.tree.get_node_context(node_id)
+        {
+            return (false, true);
+        }
+
+        self.tree.style(node_id).map_or((false, false), |style| {
+            (
+                style.overflow.x == Overflow::Scroll,
+                style.overflow.y == Overflow::Scroll,
+            )
+        })
+    }
+
+    /// Set a node's scroll offset directly. Unlike `scroll_by` this isn't clamped, so
+    /// callers can overscroll for bounce effects.
+    pub fn set_scroll_offset(&mut self, node_id: NodeId, x: f32, y: f32) {
+        if let Some(ctx) = self.tree.get_node_context_mut(node_id) {
+            ctx.scroll_x = x;
+            ctx.scroll_y = y;
+        }
+    }
+
+    /// Whether scrolling a node by `dx`, `dy` would move it.
+    fn can_scroll(&self, node_id: NodeId, dx: f32, dy: f32) -> bool {
+        let (max_x, max_y) = self.scroll_limits(node_id);
+        let (scroll_x, scroll_y) = self.scroll_offset(node_id);
+
+        (dx < 0.0 && scroll_x > 0.0)
+            || (dx > 0.0 && scroll_x < max_x)
+            || (dy < 0.0 && scroll_y > 0.0)
+            || (dy > 0.0 && scroll_y < max_y)
+    }
+
+    /// Find the innermost scrollable under a point that can move in the given direction,
+    /// so nested scrollables hand off to their parent once they reach the end.
+    pub fn scrollable_at_point(&self, x: f32, y: f32, dx: f32, dy: f32) -> Option<NodeId> {
+        let mut node_id = self.node_at_point(x, y).map(NodeId::from);
+
+        while let Some(id) = node_id {
+            if self.can_scroll(id, dx, dy) {
+                return Some(id);
+            }
+
+            node_id = self.input_parent(id);
+        }
+
+        None
+    }
+
+    /// Find the innermost node under a point that scrolls on the dominant axis of `dx`,
+    /// `dy`, whether or not it has room to move. Used to overscroll at the ends.
+    pub fn scroll_container_at_point(&self, x: f32, y: f32, dx: f32, dy: f32) -> Option<NodeId> {
+        let vertical = dy.abs() >= dx.abs();
+        let mut node_id = self.node_at_point(x, y).map(NodeId::from);
+
+        while let Some(id) = node_id {
+            let (scroll_x, scroll_y) = self.scroll_axes(id);
+
+            if (vertical && scroll_y) || (!vertical && scroll_x) {
+                return Some(id);
+            }
+
+            node_id = self.input_parent(id);
+        }
+
+        None
+    }
+
+    /// The first scrollable node in paint order, used when input has no position.
+    pub fn first_scrollable(&self) -> Option<NodeId> {
+        let mut stack = vec![self.input_grab().or(self.root_node_id)?];
+
+        while let Some(node_id) = stack.pop() {
+            let (max_x, max_y) = self.scroll_limits(node_id);
+
+            if max_x > 0.0 || max_y > 0.0 {
+                return Some(node_id);
+            }
+
+            stack.extend(self.paint_order(node_id).into_iter().rev());
+        }
+
+        None
+    }
+
+    /// Horizontal offset of a swipeable row.
+    pub fn swipe_offset(&self, node_id: NodeId) -> f32 {
+        self.tree
+            .get_node_context(node_id)
+            .map_or(0.0, |ctx| ctx.swipe_x)
+    }
+
+    pub fn set_swipe_offset(&mut self, node_id: NodeId, x: f32) {
+        if let Some(ctx) = self.tree.get_node_context_mut(node_id) {
+            ctx.swipe_x = x;
+        }
+    }
+
+    /// The (min, max) swipe offset of a node: negative when it can be swiped left.
+    pub fn swipe_bounds(&self, node_id: NodeId) -> (f32, f32) {
+        match self.tree.get_node_context(node_id) {
+            Some(NodeContext {
+                kind:
+                    NodeKind::Element {
+                        swipe_left,
+                        swipe_right,
+                        ..
+                    },
+                ..
+            }) => (-swipe_left, *swipe_right),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    /// Find the innermost swipeable row under a point.
+    pub fn swipeable_at_point(&self, x: f32, y: f32) -> Option<NodeId> {
+        let mut node_id = self.node_at_point(x, y).map(NodeId::from);
+
+        while let Some(id) = node_id {
+            if self.swipe_bounds(id) != (0.0, 0.0) {
+                return Some(id);
+            }
+
+            node_id = self.input_parent(id);
+        }
+
+        None
+    }
+
+    /// Find the nearest element under a point with a `pressEffect`.
+    pub fn press_effect_at_point(&self, x: f32, y: f32) -> Option<(NodeId, PressEffect)> {
+        let mut node_id = self.node_at_point(x, y).map(NodeId::from);
+
+        while let Some(id) = node_id {
+            if let Some(NodeContext {
+                kind:
+                    NodeKind::Element {
+                        press_effect: Some(effect),
+                        ..
+                    },
+                ..
+            }) = self.tree.get_node_context(id)
+            {
+                return Some((id, *effect));
+            }
+
+            node_id = self.input_parent(id);
+        }
+
+        None
+    }
+
+    /// The background showing around a node, from its nearest ancestor with an opaque one.
+    pub fn background_behind(&self, node_id: NodeId) -> Option<RgbColor> {
+        let mut node_id = self.tree.parent(node_id);
+
+        while let Some(id) = node_id {
+            if let Some(NodeContext {
+                kind:
+                    NodeKind::Element {
+                        background: Some(background),
+                        ..
+                    },
+                ..
+            }) = self.tree.get_node_context(id)
+                && background.is_opaque()
+            {
+                return Some(*background);
+            }
+
+            node_id = self.tree.parent(id);
+        }
+
+        None
+    }
+
+    /// Advance every running keyframe animation to `now`. Returns whether any are still
+    /// running, and the nodes whose animations just finished.
+    pub fn step_animations(&mut self, now: Instant) -> (bool, Vec<NodeId>) {
+        let mut running = false;
+        let mut ended = Vec::new();
+        let mut recolored = Vec::new();
+        let mut stack: Vec<NodeId> = self.root_node_id.into_iter().collect();
+
+        while let Some(node_id) = stack.pop() {
+            if let Some(ctx) = self.tree.get_node_context_mut(node_id)
+                && let Some(animation) = &mut ctx.animation
+            {
+                let color = animation.current.color;
+
+                match animation.step(now) {
+                    AnimationState::Running => running = true,
+                    AnimationState::Ended => ended.push(node_id),
+                    AnimationState::Idle => {}
+                }
+
+                if animation.current.color != color {
+                    recolored.push(node_id);
+                }
+            }
+
+            if let Ok(children) = self.tree.children(node_id) {
+                stack.extend(children);
+            }
+        }
+
+        // Text color is inherited, so the node's descendants pick it up too
+        for node_id in recolored {
+            self.cascade_resolved_style(node_id);
+        }
+
+        (running, ended)
+    }
+
+    /// Every keyframe animation in the tree, with its node's id, as
+    /// `Animation::inspect` describes it at `now`.
+    pub fn inspect_animations(&self, now: Instant) -> Vec<serde_json::Value> {
+        let mut animations = Vec::new();
+        let mut stack: Vec<NodeId> = self.root_node_id.into_iter().collect();
+
+        while let Some(node_id) = stack.pop() {
+            if let Some(ctx) = self.tree.get_node_context(node_id)
+                && let Some(animation) = &ctx.animation
+            {
+                let mut inspected = animation.inspect(now);
+                inspected["nodeId"] = u64::from(node_id).into();
+                animations.push(inspected);
+            }
+
+            if let Ok(children) = self.tree.children(node_id) {
+                // Reversed so siblings come out in document order
+                stack.extend(children.into_iter().rev());
+            }
+        }
+
+        animations
+    }
+
+    /// Whether anything in the tree changes every frame without JS: a skeleton's shimmer,
+    /// a playing sprite, a sweeping gauge needle or, with the `lottie` feature, a playing
+    /// Lottie animation.
+    pub fn animates_continuously(&self, now: Instant) -> bool {
+        let Some(root) = self.root_node_id else {
+            return false;
+        };
+        let mut stack = vec![root];
+
+        while let Some(node_id) = stack.pop() {
+            if self.is_hidden(node_id) {
+                continue;
+            }
+
+            if let Some(ctx) = self.tree.get_node_context(node_id)
+                && animates(ctx, now)
+            {
+                return true;
+            }
+
+            if let Ok(children) = self.tree.children(node_id) {
+                stack.extend(children);
+            }
+        }
+
+        false
+    }
+
+    /// A hash of everything about a subtree drawn at `(x, y)` that a `cache` element's
+    /// raster depends on: where each node is, its paint order, scroll and swipe offsets,
+    /// inherited color and size, and animated values. `None` if it can't be cached right now,
+    /// because something in it is waiting to be redrawn, is animating or shows frames
+    /// from outside the tree, like video or a camera.
+    pub fn subtree_signature(
+        &self,
+        node_id: NodeId,
+        (x, y): (f32, f32),
+        now: Instant,
+    ) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        x.to_bits().hash(&mut hasher);
+        y.to_bits().hash(&mut hasher);
+        self.background_behind(node_id).hash(&mut hasher);
+
+        let mut stack = vec![node_id];
+
+        while let Some(node_id) = stack.pop() {
+            let hidden = self.is_hidden(node_id);
+            (u64::from(node_id), hidden).hash(&mut hasher);
+
+            // Hidden subtrees aren't drawn, so their changes don't show
+            if hidden {
+                continue;
+            }
+
+            let ctx = self.tree.get_node_context(node_id)?;
+
+            let volatile = matches!(
+                ctx.kind,
+                NodeKind::Video { .. }
+                    | NodeKind::Surface { .. }
+                    | NodeKind::Input { .. }
+                    | NodeKind::Scanner { .. }
+            );
+
+            if ctx.render_dirty || volatile || animates(ctx, now) {
+                return None;
+            }
+
+            let layout = self.tree.layout(node_id).ok()?;
+            let animated = ctx
+                .animation
+                .as_ref()
+                .map(|a| a.current)
+                .unwrap_or_default();
+
+            for value in [
+                layout.location.x,
+                layout.location.y,
+                layout.size.width,
+                layout.size.height,
+                ctx.scroll_x,
+                ctx.scroll_y,
+                ctx.swipe_x,
+                ctx.resolved_style.font_size,
+                ctx.opacity,
+                animated.opacity,
+                animated.translate.0,
+                animated.translate.1,
+                animated.scale,
+            ] {
+                value.to_bits().hash(&mut hasher);
+            }
+
+            (
+                ctx.z_index,
+                ctx.resolved_style.color,
+                animated.color,
+                animated.background,
+            )
+                .hash(&mut hasher);
+
+            if let Some(Affine { a, b, c, d, e, f }) = ctx.transform {
+                [a, b, c, d, e, f].map(f32::to_bits).hash(&mut hasher);
+            }
+
+            // Reversed so they're popped, and hashed, in the order they're painted
+            stack.extend(self.paint_order(node_id).into_iter().rev());
         }
 
-        self.tree.set_style(node_id, style).map_err(|_| DomError {
-            message: "Could not update style".to_string(),
-        })
+        Some(hasher.finish())
     }
 
-    pub fn compute_layout(&mut self, fonts: &HashMap<String, Font>, width: f32, height: f32) {
+    /// Screen-space rects of nodes with a `refreshMode` hint, in paint order.
+    pub fn refresh_hints(&self) -> Vec<((f32, f32, f32, f32), RefreshMode)> {
+        let mut hints = Vec::new();
         let Some(root) = self.root_node_id else {
-            return;
+            return hints;
         };
+        let mut stack = vec![root];
 
-        self.tree
-            .compute_layout_with_measure(
-                root,
-                Size {
-                    width: AvailableSpace::Definite(width),
-                    height: AvailableSpace::Definite(height),
-                },
-                |known_size, available_space, _node_id, context, _style| {
-                    if let Some(NodeContext {
-                        kind: NodeKind::Text { text, wrap_width },
-                        resolved_style,
+        while let Some(node_id) = stack.pop() {
+            if let Some(NodeContext {
+                kind:
+                    NodeKind::Element {
+                        refresh_mode: Some(mode),
                         ..
-                    }) = context
-                    {
-                        let fs = resolved_style.font_size;
+                    },
+                ..
+            }) = self.tree.get_node_context(node_id)
+                && let Some(rect) = self.absolute_rect(node_id)
+            {
+                hints.push((rect, *mode));
+            }
 
-                        if let Some(font) = fonts.get(&resolved_style.font_name) {
-                            let single_line_width: f32 = text
-                                .chars()
-                                .map(|c| font.metrics(c, fs).advance_width)
-                                .sum();
+            if let Ok(children) = self.tree.children(node_id) {
+                stack.extend(children.into_iter().rev());
+            }
+        }
 
-                            let line_height = font
-                                .horizontal_line_metrics(fs)
-                                .map(|m| m.ascent - m.descent + m.line_gap)
-                                .unwrap_or(fs);
+        hints
+    }
 
-                            // Determine width following the canonical Taffy pattern:
-                            // known_size is a hard constraint, available_space is
-                            // clamped between min-content and max-content.
-                            let width =
-                                known_size
-                                    .width
-                                    .unwrap_or_else(|| match available_space.width {
-                                        AvailableSpace::MinContent => single_line_width,
-                                        AvailableSpace::MaxContent => single_line_width,
-                                        AvailableSpace::Definite(w) => w.min(single_line_width),
-                                    });
+    /// Screen-space rect (x, y, width, height) of a node, accounting for the scroll
+    /// offsets of its ancestors.
+    pub fn absolute_rect(&self, node_id: NodeId) -> Option<(f32, f32, f32, f32)> {
+        let layout = self.tree.layout(node_id).ok()?;
+        let (mut x, mut y) = (
+            layout.location.x + self.swipe_offset(node_id),
+            layout.location.y,
+        );
+        let mut parent = self.tree.parent(node_id);
+
+        while let Some(id) = parent {
+            let location = self.tree.layout(id).ok()?.location;
+            let (scroll_x, scroll_y) = self.scroll_offset(id);
+            x += location.x + self.swipe_offset(id) - scroll_x;
+            y += location.y - scroll_y;
+            parent = self.tree.parent(id);
+        }
 
-                            if single_line_width > width + 1.0 {
-                                let mut text_layout =
-                                    TextLayout::new(CoordinateSystem::PositiveYDown);
-                                text_layout.reset(&LayoutSettings {
-                                    max_width: Some(width),
-                                    ..LayoutSettings::default()
-                                });
-                                text_layout.append(
-                                    std::slice::from_ref(font),
-                                    &TextStyle::new(text, fs, 0),
-                                );
-                                let glyphs = text_layout.glyphs();
-                                let h = known_size.height.unwrap_or_else(|| {
-                                    if glyphs.is_empty() {
-                                        line_height
-                                    } else {
-                                        let last_line_y =
-                                            glyphs.iter().map(|g| g.y).fold(0.0f32, f32::max);
-                                        last_line_y + line_height
-                                    }
-                                });
-                                *wrap_width = Some(width);
-                                Size { width, height: h }
-                            } else {
-                                *wrap_width = None;
-                                Size {
-                                    width,
-                                    height: known_size.height.unwrap_or(line_height),
-                                }
-                            }
-                        } else {
-                            Size::ZERO
-                        }
-                    } else {
-                        Size::ZERO
-                    }
-                },
-            )
-            .unwrap();
+        Some((x, y, layout.size.width, layout.size.height))
     }
 
-    pub fn get_layout(&self, node_id: NodeId) -> Option<&Layout> {
-        self.tree.layout(node_id).ok()
+    /// Whether a node is `display: none`, which hides it and everything under it.
+    pub fn is_hidden(&self, node_id: NodeId) -> bool {
+        self.tree
+            .style(node_id)
+            .is_ok_and(|style| style.display == Display::None)
     }
 
-    pub fn get_node(&self, node_id: NodeId) -> Option<&NodeContext> {
-        self.tree.get_node_context(node_id)
+    pub fn node_at_point(&self, x: f32, y: f32) -> Option<u64> {
+        let root = self.root_node_id?;
+
+        let Some(grab) = self.input_grab() else {
+            return self._node_at_point(root, x, y, 0.0, 0.0);
+        };
+
+        // Only the grabbing subtree can be hit, and presses outside it land on the grab
+        // node itself, so a dialog's backdrop can't be tapped through
+        let (grab_x, grab_y, _, _) = self.absolute_rect(grab)?;
+        let location = self.tree.layout(grab).ok()?.location;
+        let parent_x = grab_x - location.x - self.swipe_offset(grab);
+        let parent_y = grab_y - location.y;
+
+        self._node_at_point(grab, x, y, parent_x, parent_y)
+            .or(Some(u64::from(grab)))
     }
 
-    pub fn get_node_mut(&mut self, node_id: NodeId) -> Option<&mut NodeContext> {
-        self.tree.get_node_context_mut(node_id)
+    /// Send all pointer input to a node and its descendants, as for a confirmation
+    /// dialog or lock screen, or pass `None` to route input normally again. Lookups
+    /// that walk up from the node under a point (scrolling, swiping, pressing) stop at
+    /// the grab node.
+    pub fn set_input_grab(&mut self, node_id: Option<NodeId>) {
+        self.input_grab = node_id;
     }
 
-    pub fn get_children(&self, node_id: NodeId) -> Option<Vec<NodeId>> {
-        self.tree.children(node_id).ok()
+    /// The grab node, if one is set and still in the DOM.
+    pub fn input_grab(&self) -> Option<NodeId> {
+        self.input_grab
+            .filter(|&node_id| self.tree.get_node_context(node_id).is_some())
     }
 
-    pub fn node_at_point(&self, x: f32, y: f32) -> Option<u64> {
-        let root = self.root_node_id?;
-        self._node_at_point(root, x, y, 0.0, 0.0)
+    /// Whether a node can take input while any grab is in place, i.e. it's the grab node
+    /// or inside it.
+    pub fn accepts_input(&self, node_id: NodeId) -> bool {
+        self.input_grab()
+            .is_none_or(|grab| self.ancestors(node_id).contains(&grab))
+    }
+
+    /// The parent to walk up to from a node under a point, which is none at the grab
+    /// node so ancestors outside it can't react.
+    fn input_parent(&self, node_id: NodeId) -> Option<NodeId> {
+        if self.input_grab == Some(node_id) {
+            return None;
+        }
+
+        self.tree.parent(node_id)
     }
 
     fn _node_at_point(
@@ -642,26 +3104,246 @@ impl Dom {
         parent_x: f32,
         parent_y: f32,
     ) -> Option<u64> {
+        if self.is_hidden(node_id) {
+            return None;
+        }
+
         let layout = self.tree.layout(node_id).ok()?;
 
-        let node_x = parent_x + layout.location.x;
+        let node_x = parent_x + layout.location.x + self.swipe_offset(node_id);
         let node_y = parent_y + layout.location.y;
         let Size { width, height } = layout.size;
 
+        // Hit-test where the point falls on the node before it was transformed
+        let (x, y) = match self
+            .tree
+            .get_node_context(node_id)
+            .and_then(|ctx| ctx.transform)
+        {
+            Some(transform) => {
+                let center = (node_x + width / 2.0, node_y + height / 2.0);
+                transform.around(center).invert()?.apply((x, y))
+            }
+            None => (x, y),
+        };
+
         if x < node_x || x >= node_x + width || y < node_y || y >= node_y + height {
             return None;
         }
 
-        // Check children in reverse order (last drawn = foremost)
+        let (scroll_x, scroll_y) = self.scroll_offset(node_id);
+
+        // Check children in reverse paint order (last drawn = foremost)
+        for child_id in self.paint_order(node_id).into_iter().rev() {
+            if let Some(id) =
+                self._node_at_point(child_id, x, y, node_x - scroll_x, node_y - scroll_y)
+            {
+                return Some(id);
+            }
+        }
+
+        Some(u64::from(node_id))
+    }
+
+    /// Collect the screen-space rects of all `surface` nodes, in paint order.
+    pub fn surface_rects(&self) -> Vec<SurfaceRect> {
+        let mut rects = Vec::new();
+
+        if let Some(root) = self.root_node_id {
+            self._surface_rects(root, 0.0, 0.0, &mut rects);
+        }
+
+        rects
+    }
+
+    fn _surface_rects(
+        &self,
+        node_id: NodeId,
+        parent_x: f32,
+        parent_y: f32,
+        rects: &mut Vec<SurfaceRect>,
+    ) {
+        if self.is_hidden(node_id) {
+            return;
+        }
+
+        let Ok(layout) = self.tree.layout(node_id) else {
+            return;
+        };
+
+        let x = parent_x + layout.location.x + self.swipe_offset(node_id);
+        let y = parent_y + layout.location.y;
+
+        if let Some(NodeContext {
+            kind: NodeKind::Surface { name, .. },
+            ..
+        }) = self.tree.get_node_context(node_id)
+        {
+            rects.push(SurfaceRect {
+                name: name.clone(),
+                x: x as i32,
+                y: y as i32,
+                width: layout.size.width as u32,
+                height: layout.size.height as u32,
+            });
+        }
+
+        let (scroll_x, scroll_y) = self.scroll_offset(node_id);
+
         if let Ok(children) = self.tree.children(node_id) {
-            for &child_id in children.iter().rev() {
-                if let Some(id) = self._node_at_point(child_id, x, y, node_x, node_y) {
-                    return Some(id);
+            for child_id in children {
+                self._surface_rects(child_id, x - scroll_x, y - scroll_y, rects);
+            }
+        }
+    }
+
+    /// Add or replace a theme, re-resolving references if it's the active one.
+    pub fn register_theme(&mut self, name: String, variables: HashMap<String, String>) {
+        let active = self.themes.active() == Some(name.as_str());
+        self.themes.register(name, variables);
+
+        if active {
+            self.apply_theme();
+        }
+    }
+
+    /// Add or replace a named cubic-bézier timing curve for `animation` attributes to use
+    /// as their `easing`. Animations already asking for it switch to it mid-flight, so a
+    /// curve can be tuned while watching it. Returns false for built-in names such as
+    /// `ease-in`.
+    pub fn register_easing(&mut self, name: &str, (x1, y1, x2, y2): (f32, f32, f32, f32)) -> bool {
+        let Some(easing) = Easing::custom(name, x1, y1, x2, y2) else {
+            return false;
+        };
+
+        let name = name.trim();
+        self.easings.insert(name.to_string(), easing);
+        let mut stack: Vec<NodeId> = self.root_node_id.into_iter().collect();
+
+        while let Some(node_id) = stack.pop() {
+            if let Some(ctx) = self.tree.get_node_context_mut(node_id)
+                && let Some(animation) = &mut ctx.animation
+                && animation.easing_name() == Some(name)
+            {
+                animation.set_easing(easing);
+                ctx.render_dirty = true;
+            }
+
+            if let Ok(children) = self.tree.children(node_id) {
+                stack.extend(children);
+            }
+        }
+
+        true
+    }
+
+    /// Switch theme. Returns false if it isn't registered or is already active.
+    pub fn set_theme(&mut self, name: &str) -> bool {
+        if !self.themes.set_active(name) {
+            return false;
+        }
+
+        self.apply_theme();
+        true
+    }
+
+    pub fn has_theme(&self, name: &str) -> bool {
+        self.themes.contains(name)
+    }
+
+    pub fn active_theme(&self) -> Option<&str> {
+        self.themes.active()
+    }
+
+    /// A node followed by its ancestors up to the root.
+    pub fn ancestors(&self, node_id: NodeId) -> Vec<NodeId> {
+        let mut ancestors = Vec::new();
+        let mut node_id = Some(node_id).filter(|&id| self.get_node(id).is_some());
+
+        while let Some(id) = node_id {
+            ancestors.push(id);
+            node_id = self.tree.parent(id);
+        }
+
+        ancestors
+    }
+
+    /// Change whether a node is pressed, focused or disabled, swapping in its state
+    /// styles.
+    pub fn set_node_state(&mut self, node_id: NodeId, edit: impl FnOnce(&mut NodeState)) {
+        let Some(ctx) = self.tree.get_node_context_mut(node_id) else {
+            return;
+        };
+
+        let old = ctx.state;
+        edit(&mut ctx.state);
+
+        if ctx.state != old {
+            self.apply_state_styles(node_id);
+        }
+    }
+
+    /// Re-resolve what a node's state styles change, redrawing it if anything did.
+    fn apply_state_styles(&mut self, node_id: NodeId) {
+        let Some(ctx) = self.tree.get_node_context_mut(node_id) else {
+            return;
+        };
+
+        let overrides = ctx
+            .state_styles
+            .resolve(ctx.state, |value| self.themes.resolve(value));
+
+        if overrides == ctx.state_overrides {
+            return;
+        }
+
+        let recolor = overrides.color != ctx.state_overrides.color;
+        ctx.state_overrides = overrides;
+        ctx.render_dirty = true;
+
+        // Children inherit the color
+        if recolor {
+            self.cascade_resolved_style(node_id);
+        }
+    }
+
+    /// Re-resolve every theme variable reference in the tree and redraw it.
+    fn apply_theme(&mut self) {
+        let Some(root) = self.root_node_id else {
+            return;
+        };
+        let mut stack = vec![root];
+
+        while let Some(node_id) = stack.pop() {
+            if let Some(ctx) = self.tree.get_node_context_mut(node_id) {
+                if let Some(variable) = &ctx.color_variable {
+                    ctx.overrides.color = self.themes.get(variable);
+                }
+
+                if let (Some(variable), NodeKind::Element { background, .. }) =
+                    (&ctx.background_variable, &mut ctx.kind)
+                {
+                    *background = self.themes.get(variable);
+                }
+
+                if let (Some(variable), NodeKind::Element { border_color, .. }) =
+                    (&ctx.border_color_variable, &mut ctx.kind)
+                {
+                    *border_color = self.themes.get(variable);
                 }
+
+                ctx.state_overrides = ctx
+                    .state_styles
+                    .resolve(ctx.state, |value| self.themes.resolve(value));
+                ctx.render_dirty = true;
+            }
+
+            if let Ok(children) = self.tree.children(node_id) {
+                stack.extend(children);
             }
         }
 
-        Some(u64::from(node_id))
+        self.cascade_resolved_style(root);
     }
 
     /// Recompute an element's resolved_style from its parent and cascade to children.
@@ -670,7 +3352,7 @@ impl Dom {
             .tree
             .parent(node_id)
             .map(|pid| self.get_resolved_style(pid))
-            .unwrap_or_else(|| self.inherited_style.clone());
+            .unwrap_or_else(|| self.scaled_base_style());
 
         self.resolve_subtree(&parent_resolved, node_id);
     }
@@ -679,7 +3361,7 @@ impl Dom {
     fn get_resolved_style(&self, node_id: NodeId) -> InheritedStyle {
         match self.tree.get_node_context(node_id) {
             Some(ctx) => ctx.resolved_style.clone(),
-            None => self.inherited_style.clone(),
+            None => self.scaled_base_style(),
         }
     }
 
@@ -691,14 +3373,49 @@ impl Dom {
 
         let old_font = ctx.resolved_style.font_name.clone();
         let old_size = ctx.resolved_style.font_size;
+        let old_weight = ctx.resolved_style.font_weight;
+        let old_font_style = ctx.resolved_style.font_style;
+        let old_variations = ctx.resolved_style.font_variations.clone();
+        let old_line_height = ctx.resolved_style.line_height;
+        let old_text_overflow = ctx.resolved_style.text_overflow;
+        let old_max_lines = ctx.resolved_style.max_lines;
+        let old_letter_spacing = ctx.resolved_style.letter_spacing;
+        let old_tab_size = ctx.resolved_style.tab_size;
+        let old_direction = ctx.resolved_style.direction;
 
         ctx.resolved_style = parent_resolved.with_overrides(&ctx.overrides);
 
+        // Inherited sizes were scaled already, so only scale a node's own
+        if ctx.overrides.font_size.is_some() {
+            ctx.resolved_style.font_size *= self.text_scale;
+        }
+
+        if let Some(color) = ctx.state_overrides.color {
+            ctx.resolved_style.color = color;
+        }
+
+        if let Some(color) = ctx.animation.as_ref().and_then(|a| a.current.color) {
+            ctx.resolved_style.color = color;
+        }
+
         let resolved = ctx.resolved_style.clone();
         let is_text = matches!(ctx.kind, NodeKind::Text { .. });
+        let measures_text = is_text || matches!(ctx.kind, NodeKind::Markdown { .. });
 
         // Mark dirty if font properties changed (affects measurement)
-        if is_text && (resolved.font_name != old_font || resolved.font_size != old_size) {
+        if measures_text
+            && (resolved.font_name != old_font
+                || resolved.font_size != old_size
+                || resolved.font_weight != old_weight
+                || resolved.font_style != old_font_style
+                || resolved.font_variations != old_variations
+                || resolved.line_height != old_line_height
+                || resolved.text_overflow != old_text_overflow
+                || resolved.max_lines != old_max_lines
+                || resolved.letter_spacing != old_letter_spacing
+                || resolved.tab_size != old_tab_size
+                || resolved.direction != old_direction)
+        {
             let _ = self.tree.mark_dirty(node_id);
         }
 
@@ -707,6 +3424,15 @@ impl Dom {
             return;
         }
 
+        // Keep rows mirrored while the element is right to left
+        if resolved.direction != old_direction
+            && let Ok(style) = self.tree.style(node_id)
+        {
+            let mut style = style.clone();
+            style.flex_direction = mirror(style.flex_direction);
+            let _ = self.tree.set_style(node_id, style);
+        }
+
         if let Ok(children) = self.tree.children(node_id) {
             for child_id in children {
                 self.resolve_subtree(&resolved, child_id);
@@ -715,18 +3441,184 @@ impl Dom {
     }
 }
 
+/// Whether a node is in the middle of an animation of its own that doesn't go through
+/// the tree: a skeleton's shimmer, a playing sprite or lottie or a sweeping gauge.
+fn animates(ctx: &NodeContext, now: Instant) -> bool {
+    match &ctx.kind {
+        NodeKind::Element { tag, .. } => tag == "skeleton",
+        NodeKind::Sprite {
+            img_width,
+            img_height,
+            animation,
+            ..
+        } => animation.playing(now, *img_width, *img_height),
+        #[cfg(feature = "lottie")]
+        NodeKind::Lottie { player } => player.playing(now),
+        NodeKind::Gauge { gauge } => gauge.sweeping(now),
+        _ => false,
+    }
+}
+
+/// Size of a single-line text input: its style's size if set, otherwise about 20
+/// characters wide and one line tall.
+fn input_size(
+    fonts: &FontRegistry,
+    default_font: &str,
+    resolved_style: &InheritedStyle,
+    known_size: Size<Option<f32>>,
+) -> Size<f32> {
+    let fs = resolved_style.font_size;
+    let line_height = font_fallback::resolve(fonts, resolved_style, default_font)
+        .and_then(|font| font.horizontal_line_metrics(fs))
+        .map(|m| m.ascent - m.descent + m.line_gap)
+        .unwrap_or(fs);
+
+    Size {
+        width: known_size.width.unwrap_or(fs * 10.0),
+        height: known_size.height.unwrap_or(line_height),
+    }
+}
+
+/// Caps on the size of a tree, so a runaway render or a malicious bundle fails with an
+/// error instead of exhausting a small device's memory. Every field is optional in JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DomLimits {
+    /// Nodes alive at once, attached or not
+    #[serde(default = "default_max_nodes")]
+    pub max_nodes: usize,
+    /// Levels of nesting below the root
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize,
+    /// Bytes of text in one text node
+    #[serde(default = "default_max_text_length")]
+    pub max_text_length: usize,
+    /// Bytes of markup in one `svg` node
+    #[serde(default = "default_max_svg_size")]
+    pub max_svg_size: usize,
+}
+
+impl Default for DomLimits {
+    fn default() -> Self {
+        DomLimits {
+            max_nodes: default_max_nodes(),
+            max_depth: default_max_depth(),
+            max_text_length: default_max_text_length(),
+            max_svg_size: default_max_svg_size(),
+        }
+    }
+}
+
+fn default_max_nodes() -> usize {
+    20_000
+}
+
+fn default_max_depth() -> usize {
+    128
+}
+
+fn default_max_text_length() -> usize {
+    64 * 1024
+}
+
+fn default_max_svg_size() -> usize {
+    1024 * 1024
+}
+
+/// Which of the `DomLimits` was hit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Nodes,
+    Depth,
+    TextLength,
+    SvgSize,
+}
+
+impl Limit {
+    /// The name of the limit's field in JSON, as reported to JS
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Limit::Nodes => "maxNodes",
+            Limit::Depth => "maxDepth",
+            Limit::TextLength => "maxTextLength",
+            Limit::SvgSize => "maxSvgSize",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitExceeded {
+    pub limit: Limit,
+    pub max: usize,
+    /// What the change would have made it
+    pub actual: usize,
+}
+
+#[derive(Debug)]
 pub struct DomError {
     pub message: String,
+    /// Set when the change was refused for going over one of the `DomLimits`
+    pub limit: Option<LimitExceeded>,
+}
+
+impl DomError {
+    fn limit(limit: Limit, max: usize, actual: usize) -> Self {
+        DomError {
+            message: format!(
+                "Tree limit exceeded: {} is {}, got {}",
+                limit.as_str(),
+                max,
+                actual
+            ),
+            limit: Some(LimitExceeded { limit, max, actual }),
+        }
+    }
 }
 
 impl<'js> IntoJs<'js> for DomError {
     fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
         let error = Object::new(ctx.clone())?;
         error.set("message", self.message.into_js(ctx))?;
+
+        if let Some(exceeded) = self.limit {
+            error.set("limit", exceeded.limit.as_str())?;
+            error.set("max", exceeded.max)?;
+            error.set("actual", exceeded.actual)?;
+        }
+
         error.into_js(ctx)
     }
 }
 
+/// The text a `<bound>` node shows for a value.
+fn format_bound(format: Option<&Template>, value: &str) -> String {
+    format.map_or_else(|| value.to_string(), |format| format.render(value))
+}
+
+/// The per-side properties a shorthand like `padding`, `marginX`, `gap` or `borderWidth`
+/// sets, or just the property itself.
+fn expand_shorthand(key: &str) -> Vec<&str> {
+    match key {
+        "margin" => vec!["marginTop", "marginRight", "marginBottom", "marginLeft"],
+        "marginX" => vec!["marginLeft", "marginRight"],
+        "marginY" => vec!["marginTop", "marginBottom"],
+        "padding" => vec!["paddingTop", "paddingRight", "paddingBottom", "paddingLeft"],
+        "paddingX" => vec!["paddingLeft", "paddingRight"],
+        "paddingY" => vec!["paddingTop", "paddingBottom"],
+        "gap" => vec!["gapHeight", "gapWidth"],
+        "rowGap" => vec!["gapHeight"],
+        "columnGap" => vec!["gapWidth"],
+        "overflow" => vec!["overflowX", "overflowY"],
+        "borderWidth" => vec![
+            "borderTopWidth",
+            "borderRightWidth",
+            "borderBottomWidth",
+            "borderLeftWidth",
+        ],
+        key => vec![key],
+    }
+}
+
 fn parse_display(str: &str) -> Display {
     match str {
         "block" => Display::Block,
@@ -759,7 +3651,7 @@ fn parse_align_content(str: &str) -> Option<AlignContent> {
         "flex-end" => AlignContent::FlexEnd,
         "flex-start" => AlignContent::FlexStart,
         "space-around" => AlignContent::SpaceAround,
-        "space-between" => AlignContent::SpaceAround,
+        "space-between" => AlignContent::SpaceBetween,
         "space-evenly" => AlignContent::SpaceEvenly,
         "start" => AlignContent::Start,
         "stretch" => AlignContent::Stretch,
@@ -769,6 +3661,62 @@ fn parse_align_content(str: &str) -> Option<AlignContent> {
     Some(result)
 }
 
+/// The `flex` shorthand as grow, shrink and basis: `"none"`, `"auto"`, or up to three
+/// values like `"1"`, `"2 1"` or `"1 0 120px"`. A lone basis like `"120px"` grows and
+/// shrinks from it, as in CSS.
+fn parse_flex(str: &str) -> Option<(f32, f32, Dimension)> {
+    match str.trim() {
+        "none" => return Some((0.0, 0.0, Dimension::auto())),
+        "auto" => return Some((1.0, 1.0, Dimension::auto())),
+        "initial" => return Some((0.0, 1.0, Dimension::auto())),
+        _ => {}
+    }
+
+    let mut numbers = Vec::new();
+    let mut basis = None;
+
+    for part in str.split_whitespace() {
+        if basis.is_none()
+            && numbers.len() < 2
+            && let Ok(number) = part.parse::<f32>()
+            && number.is_finite()
+        {
+            numbers.push(number);
+        } else if basis.is_none() {
+            basis = Some(parse_flex_basis(part)?);
+        } else {
+            return None;
+        }
+    }
+
+    match (numbers.as_slice(), basis) {
+        ([], Some(basis)) => Some((1.0, 1.0, basis)),
+        ([grow], basis) => Some((*grow, 1.0, basis.unwrap_or(Dimension::length(0.0)))),
+        ([grow, shrink], basis) => Some((*grow, *shrink, basis.unwrap_or(Dimension::length(0.0)))),
+        _ => None,
+    }
+}
+
+/// A `tabSize` in spaces, clamped so tab stops can't expand text without bound. `None`
+/// for NaN and infinities.
+fn tab_size(value: f32) -> Option<f32> {
+    value.is_finite().then(|| value.clamp(0.0, 16.0))
+}
+
+fn parse_flex_basis(str: &str) -> Option<Dimension> {
+    if str == "auto" {
+        return Some(Dimension::auto());
+    }
+
+    if let Some(percent) = str.strip_suffix('%') {
+        let percent = percent.parse::<f32>().ok().filter(|n| n.is_finite())?;
+        return Some(Dimension::percent(percent / 100.0));
+    }
+
+    let length = str.strip_suffix("px").unwrap_or(str).parse::<f32>().ok();
+    Some(Dimension::length(length.filter(|n| n.is_finite())?))
+}
+
 fn parse_box_sizing(str: &str) -> BoxSizing {
     match str {
         "border-box" => BoxSizing::BorderBox,
@@ -777,6 +3725,15 @@ fn parse_box_sizing(str: &str) -> BoxSizing {
     }
 }
 
+/// `row` and `row-reverse` swapped, for rows in right-to-left elements.
+pub(crate) fn mirror(direction: FlexDirection) -> FlexDirection {
+    match direction {
+        FlexDirection::Row => FlexDirection::RowReverse,
+        FlexDirection::RowReverse => FlexDirection::Row,
+        column => column,
+    }
+}
+
 fn parse_flex_wrap(str: &str) -> FlexWrap {
     match str {
         "nowrap" => FlexWrap::NoWrap,
@@ -806,6 +3763,15 @@ fn parse_overflow(str: &str) -> Overflow {
     }
 }
 
+fn parse_object_fit(str: &str) -> ObjectFit {
+    match str {
+        "contain" => ObjectFit::Contain,
+        "cover" => ObjectFit::Cover,
+        "fill" => ObjectFit::Fill,
+        _ => ObjectFit::Contain,
+    }
+}
+
 fn parse_position(str: &str) -> Position {
     match str {
         "absolute" => Position::Absolute,
@@ -830,9 +3796,13 @@ impl JsModule for Rc<RefCell<Dom>> {
         js_dom
             .set(
                 "createElement",
-                Func::from(MutFn::from(move |tag: String| {
-                    dom.borrow_mut().create_element(tag)
-                })),
+                Func::from(MutFn::from(
+                    move |ctx: Ctx<'_>, tag: String| -> rquickjs::Result<u64> {
+                        dom.borrow_mut()
+                            .create_element(tag)
+                            .map_err(|err| ctx.throw(err.into_js(&ctx).unwrap()))
+                    },
+                )),
             )
             .unwrap();
 
@@ -840,9 +3810,13 @@ impl JsModule for Rc<RefCell<Dom>> {
         js_dom
             .set(
                 "createTextNode",
-                Func::from(MutFn::from(move |text: String| {
-                    dom.borrow_mut().create_text_node(text)
-                })),
+                Func::from(MutFn::from(
+                    move |ctx: Ctx<'_>, text: String| -> rquickjs::Result<u64> {
+                        dom.borrow_mut()
+                            .create_text_node(text)
+                            .map_err(|err| ctx.throw(err.into_js(&ctx).unwrap()))
+                    },
+                )),
             )
             .unwrap();
 
@@ -0,0 +1,727 @@
+use std::fmt::Debug;
+
+use serde_json::{Map, Value, json};
+use taffy::style::CompactLength;
+use taffy::{Dimension, FlexDirection, LengthPercentage, LengthPercentageAuto, NodeId, Style};
+
+use crate::canvas::RgbColor;
+use crate::dom::{self, Dom, DomError, DomLimits, NodeContext, NodeKind};
+use crate::inherited_style::{Direction, InheritedStyle, InheritedStyleOverrides, LineHeight};
+use crate::picker::PickerKind;
+
+/// Largest length or number taken from a tree, in either direction. Anything bigger is
+/// clamped, so a bogus size can't turn into an infinite layout or a huge allocation.
+const MAX_NUMBER: f64 = 100_000.0;
+
+/// Style properties set as attributes, as the `style` proxy on `JuiceElement` does.
+const ATTRIBUTE_STYLES: &[&str] = &[
+    "background",
+    "borderColor",
+    "borderRadius",
+    "boxShadow",
+    "font",
+    "fontSize",
+    "color",
+    "textOverflow",
+    "maxLines",
+    "letterSpacing",
+    "tabSize",
+    "direction",
+    "textDecoration",
+    "fontWeight",
+    "fontStyle",
+    "fontVariationSettings",
+];
+
+/// Attributes sent as JSON rather than as a string or number.
+const JSON_ATTRIBUTES: &[&str] = &[
+    "pressedStyle",
+    "focusedStyle",
+    "disabledStyle",
+    "animation",
+    "fontVariationSettings",
+];
+
+impl Dom {
+    /// Build a tree from JSON in the shape `JSON.stringify` gives a `JuiceDocument`: each
+    /// element is `{ "tag", "props", "style", "children" }` and each text node is
+    /// `{ "tag": "#text", "text" }`. A root that isn't a `document` gets one around it.
+    ///
+    /// Bundles can be hot-reloaded or delivered over the network, so the bytes aren't
+    /// trusted: this never panics, however malformed they are. Nesting is capped by the
+    /// JSON parser's recursion limit, numbers are clamped to sane sizes and anything of
+    /// the wrong type is ignored. A tree going over `limits` is refused with the limit it
+    /// hit.
+    pub fn try_from_json_bytes(
+        bytes: &[u8],
+        inherited_style: InheritedStyle,
+        limits: DomLimits,
+    ) -> Result<Dom, DomError> {
+        let root: Value = serde_json::from_slice(bytes).map_err(|err| DomError {
+            message: format!("Invalid tree JSON: {}", err),
+            limit: None,
+        })?;
+
+        if !root.is_object() {
+            return Err(DomError {
+                message: "Tree JSON must be an object".to_string(),
+                limit: None,
+            });
+        }
+
+        let mut dom = Dom::new(inherited_style);
+        dom.set_limits(limits);
+        let mut pending = Vec::new();
+
+        if tag(&root) == Some("document") {
+            let document = dom.create_element("document".to_string())?;
+            apply_props(&mut dom, document, &root)?;
+            pending.extend(children(&root).map(|child| (document, child)));
+        } else {
+            let document = dom.create_element("document".to_string())?;
+            pending.push((document, &root));
+        }
+
+        // Depth first without recursion, so the order matches the JSON
+        pending.reverse();
+
+        while let Some((parent_id, node)) = pending.pop() {
+            let node_id = match tag(node) {
+                Some("#text") => {
+                    let text = node.get("text").and_then(Value::as_str).unwrap_or("");
+                    dom.create_text_node(text.to_string())?
+                }
+                Some(tag) if tag != "document" => dom.create_element(tag.to_string())?,
+                _ => continue,
+            };
+
+            dom.append_child(parent_id, node_id)?;
+            apply_props(&mut dom, node_id, node)?;
+
+            let start = pending.len();
+            pending.extend(children(node).map(|child| (node_id, child)));
+            pending[start..].reverse();
+        }
+
+        Ok(dom)
+    }
+
+    /// The tree as it is natively, in the same JSON shape `try_from_json_bytes` reads, for
+    /// inspecting layout, asserting on in tests and attaching to bug reports. Each node
+    /// also has the inherited style it `resolved` to and its `layout` from the last
+    /// `compute_layout`, relative to its parent; parsing the JSON again ignores both.
+    ///
+    /// Only what the renderer kept comes back: colors are resolved from theme variables,
+    /// `em` lengths are in pixels, and shorthands are split into sides. Grid tracks,
+    /// transforms, shadows, animations and widget settings other than values aren't
+    /// written out. Null before there's a document.
+    pub fn to_json(&self) -> Value {
+        match self.root_node_id {
+            Some(root) => node_json(self, root),
+            None => Value::Null,
+        }
+    }
+}
+
+fn node_json(dom: &Dom, node_id: NodeId) -> Value {
+    let (Some(ctx), Some(style)) = (dom.get_node(node_id), dom.get_style(node_id)) else {
+        return Value::Null;
+    };
+
+    let mut node = Map::new();
+    let mut props = Map::new();
+    let mut style_json = layout_style(style, ctx.resolved_style.direction);
+
+    let tag = match &ctx.kind {
+        NodeKind::Element {
+            tag,
+            background,
+            border_radius,
+            refreshable,
+            swipe_left,
+            swipe_right,
+            border_color,
+            cache,
+            ..
+        } => {
+            if let Some(background) = background {
+                style_json.insert("background".into(), color(*background));
+            }
+            if let Some(border_color) = border_color {
+                style_json.insert("borderColor".into(), color(*border_color));
+            }
+            if *border_radius != 0.0 {
+                style_json.insert("borderRadius".into(), json!(border_radius));
+            }
+            if *refreshable {
+                props.insert("refreshable".into(), json!(true));
+            }
+            if *swipe_left != 0.0 {
+                props.insert("swipeLeft".into(), json!(swipe_left));
+            }
+            if *swipe_right != 0.0 {
+                props.insert("swipeRight".into(), json!(swipe_right));
+            }
+            if *cache {
+                props.insert("cache".into(), json!(true));
+            }
+            tag.as_str()
+        }
+        NodeKind::Text {
+            text,
+            binding: Some(binding),
+            ..
+        } => {
+            props.insert("bind".into(), json!(binding));
+            node.insert("text".into(), json!(text));
+            "bound"
+        }
+        NodeKind::Text { text, .. } => {
+            node.insert("text".into(), json!(text));
+            "#text"
+        }
+        NodeKind::Svg { markup, .. } => {
+            props.insert("markup".into(), json!(markup));
+            "svg"
+        }
+        NodeKind::Image { src, .. } => {
+            props.insert("src".into(), json!(src));
+            "img"
+        }
+        NodeKind::Sprite { src, .. } => {
+            props.insert("src".into(), json!(src));
+            "animatedsprite"
+        }
+        NodeKind::Video { src, .. } => {
+            props.insert("src".into(), json!(src));
+            "video"
+        }
+        NodeKind::Surface { name, .. } => {
+            props.insert("name".into(), json!(name));
+            "surface"
+        }
+        NodeKind::Input {
+            placeholder, state, ..
+        } => {
+            props.insert("placeholder".into(), json!(placeholder));
+
+            // Dumps reach devtools, so secure entry never shows what was typed
+            if state.secure {
+                props.insert("secureTextEntry".into(), json!(true));
+            } else {
+                props.insert("value".into(), json!(state.value));
+            }
+            "input"
+        }
+        NodeKind::Picker { picker } => match picker.kind {
+            PickerKind::Wheel => "wheelpicker",
+            PickerKind::Date => "datepicker",
+            PickerKind::Time => "timepicker",
+        },
+        NodeKind::Gauge { .. } => "gauge",
+        NodeKind::Chart { .. } => "chart",
+        NodeKind::Table { .. } => "table",
+        NodeKind::Markdown { .. } => "markdown",
+        NodeKind::Scanner { .. } => "scanner",
+        #[cfg(feature = "lottie")]
+        NodeKind::Lottie { .. } => "lottie",
+    };
+
+    node.insert("tag".into(), json!(tag));
+    node.insert("resolved".into(), resolved(ctx));
+
+    if let Some(layout) = dom.get_layout(node_id) {
+        node.insert(
+            "layout".into(),
+            json!({
+                "x": layout.location.x,
+                "y": layout.location.y,
+                "width": layout.size.width,
+                "height": layout.size.height,
+            }),
+        );
+    }
+
+    // Text nodes are laid out by their parent, so only elements have styles and props
+    if tag == "#text" {
+        return Value::Object(node);
+    }
+
+    overrides(&ctx.overrides, &mut style_json, &mut props);
+
+    if ctx.z_index != 0 {
+        style_json.insert("zIndex".into(), json!(ctx.z_index));
+    }
+    if ctx.opacity != 1.0 {
+        style_json.insert("opacity".into(), json!(ctx.opacity));
+    }
+
+    let children: Vec<Value> = dom
+        .get_children(node_id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|child| node_json(dom, child))
+        .filter(|child| !child.is_null())
+        .collect();
+
+    if !props.is_empty() {
+        node.insert("props".into(), Value::Object(props));
+    }
+    if !style_json.is_empty() {
+        node.insert("style".into(), Value::Object(style_json));
+    }
+    if !children.is_empty() {
+        node.insert("children".into(), Value::Array(children));
+    }
+
+    Value::Object(node)
+}
+
+/// The inherited style a node resolved to, with its font size already scaled.
+fn resolved(ctx: &NodeContext) -> Value {
+    let style = &ctx.resolved_style;
+
+    json!({
+        "color": color(style.color),
+        "font": style.font_name,
+        "fontSize": style.font_size,
+        "fontWeight": style.font_weight.0,
+        "fontStyle": keyword(style.font_style),
+        "fontVariationSettings": style.font_variations.to_string(),
+        "textAlign": keyword(style.text_align),
+        "lineHeight": line_height(style.line_height),
+        "textOverflow": keyword(style.text_overflow),
+        "maxLines": style.max_lines,
+        "letterSpacing": style.letter_spacing,
+        "tabSize": style.tab_size,
+        "direction": keyword(style.direction),
+        "textDecoration": style.text_decoration.to_string(),
+    })
+}
+
+/// The inherited styles a node sets itself. `textAlign` is only read as a prop.
+fn overrides(
+    overrides: &InheritedStyleOverrides,
+    style: &mut Map<String, Value>,
+    props: &mut Map<String, Value>,
+) {
+    if let Some(value) = overrides.color {
+        style.insert("color".into(), color(value));
+    }
+    if let Some(value) = &overrides.font_name {
+        style.insert("font".into(), json!(value));
+    }
+    if let Some(value) = overrides.font_size {
+        style.insert("fontSize".into(), json!(value));
+    }
+    if let Some(value) = overrides.font_weight {
+        style.insert("fontWeight".into(), json!(value.0));
+    }
+    if let Some(value) = overrides.font_style {
+        style.insert("fontStyle".into(), keyword(value));
+    }
+    if let Some(value) = &overrides.font_variations {
+        style.insert("fontVariationSettings".into(), json!(value.to_string()));
+    }
+    if let Some(value) = overrides.text_align {
+        props.insert("textAlign".into(), keyword(value));
+    }
+    if let Some(value) = overrides.line_height {
+        style.insert("lineHeight".into(), line_height(value));
+    }
+    if let Some(value) = overrides.text_overflow {
+        style.insert("textOverflow".into(), keyword(value));
+    }
+    if let Some(value) = overrides.max_lines {
+        style.insert("maxLines".into(), json!(value));
+    }
+    if let Some(value) = overrides.letter_spacing {
+        style.insert("letterSpacing".into(), json!(value));
+    }
+    if let Some(value) = overrides.tab_size {
+        style.insert("tabSize".into(), json!(value));
+    }
+    if let Some(value) = overrides.direction {
+        style.insert("direction".into(), keyword(value));
+    }
+    if let Some(value) = overrides.text_decoration {
+        style.insert("textDecoration".into(), json!(value.to_string()));
+    }
+}
+
+/// The layout style properties that differ from taffy's defaults.
+fn layout_style(style: &Style, direction: Direction) -> Map<String, Value> {
+    let default: Style = Style::default();
+    let mut json = Map::new();
+    let mut insert = |key: &str, value: Option<Value>| {
+        if let Some(value) = value {
+            json.insert(key.to_string(), value);
+        }
+    };
+
+    // Rows are stored mirrored in right-to-left elements, and mirrored again when parsed
+    let flex_direction = match direction {
+        Direction::Rtl => dom::mirror(style.flex_direction),
+        Direction::Ltr => style.flex_direction,
+    };
+
+    insert(
+        "display",
+        changed(style.display, default.display).map(keyword),
+    );
+    insert(
+        "position",
+        changed(style.position, default.position).map(keyword),
+    );
+    insert(
+        "boxSizing",
+        changed(style.box_sizing, default.box_sizing).map(keyword),
+    );
+    insert(
+        "overflowX",
+        changed(style.overflow.x, default.overflow.x).map(keyword),
+    );
+    insert(
+        "overflowY",
+        changed(style.overflow.y, default.overflow.y).map(keyword),
+    );
+    insert(
+        "flexDirection",
+        changed(flex_direction, FlexDirection::default()).map(keyword),
+    );
+    insert(
+        "flexWrap",
+        changed(style.flex_wrap, default.flex_wrap).map(keyword),
+    );
+    insert(
+        "flexGrow",
+        changed(style.flex_grow, default.flex_grow).map(|v| json!(v)),
+    );
+    insert(
+        "flexShrink",
+        changed(style.flex_shrink, default.flex_shrink).map(|v| json!(v)),
+    );
+    insert("flexBasis", dimension(style.flex_basis, default.flex_basis));
+    insert("alignContent", style.align_content.map(keyword));
+    insert("alignItems", style.align_items.map(keyword));
+    insert("alignSelf", style.align_self.map(keyword));
+    insert("justifyContent", style.justify_content.map(keyword));
+    insert("justifyItems", style.justify_items.map(keyword));
+    insert("justifySelf", style.justify_self.map(keyword));
+    insert("width", dimension(style.size.width, default.size.width));
+    insert("height", dimension(style.size.height, default.size.height));
+    insert(
+        "maxWidth",
+        dimension(style.max_size.width, default.max_size.width),
+    );
+    insert(
+        "maxHeight",
+        dimension(style.max_size.height, default.max_size.height),
+    );
+    insert("gapWidth", length_percentage(style.gap.width));
+    insert("gapHeight", length_percentage(style.gap.height));
+
+    let margin = style.margin;
+    for (key, value) in [
+        ("marginTop", margin.top),
+        ("marginRight", margin.right),
+        ("marginBottom", margin.bottom),
+        ("marginLeft", margin.left),
+    ] {
+        insert(key, length_percentage_auto(value));
+    }
+
+    let padding = style.padding;
+    for (key, value) in [
+        ("paddingTop", padding.top),
+        ("paddingRight", padding.right),
+        ("paddingBottom", padding.bottom),
+        ("paddingLeft", padding.left),
+    ] {
+        insert(key, length_percentage(value));
+    }
+
+    let border = style.border;
+    for (key, value) in [
+        ("borderTopWidth", border.top),
+        ("borderRightWidth", border.right),
+        ("borderBottomWidth", border.bottom),
+        ("borderLeftWidth", border.left),
+    ] {
+        insert(key, length_percentage(value));
+    }
+
+    json
+}
+
+fn changed<T: PartialEq>(value: T, default: T) -> Option<T> {
+    (value != default).then_some(value)
+}
+
+/// A taffy enum as the kebab-case keyword it's parsed from, like `"space-between"` for
+/// `SpaceBetween`.
+fn keyword(value: impl Debug) -> Value {
+    let mut keyword = String::new();
+
+    for c in format!("{:?}", value).chars() {
+        if c.is_uppercase() && !keyword.is_empty() {
+            keyword.push('-');
+        }
+        keyword.push(c.to_ascii_lowercase());
+    }
+
+    Value::String(keyword)
+}
+
+/// A length as a number of pixels, `"50%"` or `"auto"`.
+fn length_json(raw: CompactLength) -> Option<Value> {
+    match raw.tag() {
+        CompactLength::LENGTH_TAG => Some(json!(raw.value())),
+        CompactLength::PERCENT_TAG => Some(json!(format!("{}%", raw.value() * 100.0))),
+        CompactLength::AUTO_TAG => Some(json!("auto")),
+        _ => None,
+    }
+}
+
+fn dimension(value: Dimension, default: Dimension) -> Option<Value> {
+    changed(value, default).and_then(|value| length_json(value.into_raw()))
+}
+
+fn length_percentage(value: LengthPercentage) -> Option<Value> {
+    changed(value, LengthPercentage::length(0.0)).and_then(|value| length_json(value.into_raw()))
+}
+
+fn length_percentage_auto(value: LengthPercentageAuto) -> Option<Value> {
+    changed(value, LengthPercentageAuto::length(0.0))
+        .and_then(|value| length_json(value.into_raw()))
+}
+
+fn line_height(value: LineHeight) -> Value {
+    match value {
+        LineHeight::Normal => json!("normal"),
+        LineHeight::Multiplier(multiplier) => json!(multiplier),
+        LineHeight::Pixels(pixels) => json!(format!("{}px", pixels)),
+    }
+}
+
+/// `#rrggbb`, or `#rrggbbaa` when translucent.
+pub(crate) fn color(color: RgbColor) -> Value {
+    let mut hex = format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b);
+
+    if !color.is_opaque() {
+        hex.push_str(&format!("{:02x}", color.a));
+    }
+
+    Value::String(hex)
+}
+
+fn tag(node: &Value) -> Option<&str> {
+    node.get("tag").and_then(Value::as_str)
+}
+
+fn children(node: &Value) -> impl Iterator<Item = &Value> {
+    node.get("children")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|child| child.is_object())
+}
+
+/// A finite number clamped to `MAX_NUMBER`.
+fn number(value: f64) -> Option<f32> {
+    value
+        .is_finite()
+        .then(|| value.clamp(-MAX_NUMBER, MAX_NUMBER) as f32)
+}
+
+/// Split `12px`, `50%` or `1.5em` into the number and unit.
+fn length(value: &str) -> Option<(f32, &str)> {
+    let split = value.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+    let (digits, unit) = value.split_at(split);
+
+    if digits.is_empty() || !matches!(unit, "px" | "%" | "em") {
+        return None;
+    }
+
+    Some((number(digits.parse().ok()?)?, unit))
+}
+
+fn apply_props(dom: &mut Dom, node_id: u64, node: &Value) -> Result<(), DomError> {
+    if let Some(props) = node.get("props").and_then(Value::as_object) {
+        for (key, value) in props {
+            if key != "children" && key != "style" {
+                set_attribute(dom, node_id, key, value)?;
+            }
+        }
+    }
+
+    if let Some(style) = node.get("style").and_then(Value::as_object) {
+        apply_style(dom, node_id, style)?;
+    }
+
+    Ok(())
+}
+
+fn set_attribute(dom: &mut Dom, node_id: u64, key: &str, value: &Value) -> Result<(), DomError> {
+    match value {
+        Value::String(value) => match length(value) {
+            Some((value, "px")) => dom.set_attribute_number(node_id, key.to_string(), value),
+            Some(_) => Ok(()),
+            None => dom.set_attribute_string(node_id, key.to_string(), value.clone()),
+        },
+        Value::Number(value) => match value.as_f64().and_then(number) {
+            Some(value) => dom.set_attribute_number(node_id, key.to_string(), value),
+            None => Ok(()),
+        },
+        Value::Bool(value) => dom.set_attribute_string(node_id, key.to_string(), value.to_string()),
+        Value::Object(_) if JSON_ATTRIBUTES.contains(&key) => {
+            dom.set_attribute_string(node_id, key.to_string(), value.to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn apply_style(dom: &mut Dom, node_id: u64, style: &Map<String, Value>) -> Result<(), DomError> {
+    for (key, value) in style {
+        if key == "lineHeight" {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                Value::Number(value) => value.to_string(),
+                _ => continue,
+            };
+            dom.set_attribute_string(node_id, key.clone(), value)?;
+            continue;
+        }
+
+        if ATTRIBUTE_STYLES.contains(&key.as_str()) {
+            set_attribute(dom, node_id, key, value)?;
+            continue;
+        }
+
+        match value {
+            Value::String(value) if key == "flex" => {
+                dom.set_style_string(node_id, key.clone(), value.clone())?
+            }
+            Value::String(value) => match length(value) {
+                Some((value, "px")) => dom.set_style_number(node_id, key.clone(), value)?,
+                Some((value, "%")) => dom.set_style_percent(node_id, key.clone(), value)?,
+                Some((value, _)) => dom.set_style_em(node_id, key.clone(), value)?,
+                None => dom.set_style_string(node_id, key.clone(), value.clone())?,
+            },
+            Value::Number(value) => {
+                if let Some(value) = value.as_f64().and_then(number) {
+                    dom.set_style_number(node_id, key.clone(), value)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::Limit;
+
+    fn parse(json: &str) -> Result<Dom, DomError> {
+        Dom::try_from_json_bytes(
+            json.as_bytes(),
+            InheritedStyle::new("default"),
+            DomLimits::default(),
+        )
+    }
+
+    #[test]
+    fn truncated_json_is_refused() {
+        let json = r#"{"tag":"document","children":[{"tag":"view","style":{"width":10}}]}"#;
+
+        for end in 0..json.len() {
+            let err = parse(&json[..end]).err().unwrap();
+            assert!(err.message.starts_with("Invalid tree JSON"), "{}", end);
+        }
+
+        assert!(parse(json).is_ok());
+        assert!(parse("[]").is_err());
+        assert!(parse("42").is_err());
+        assert!(
+            Dom::try_from_json_bytes(
+                b"{\"tag\":\"\xff\"}",
+                InheritedStyle::new("default"),
+                DomLimits::default()
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn wrong_types_are_ignored() {
+        let dom = parse(
+            r##"{"tag":"document","props":[],"style":"big","children":[
+                {"tag":7},
+                {"tag":"view","props":"x","style":[1],"children":{"tag":"view"}},
+                {"tag":"view","style":{"width":true,"height":null,"flexDirection":3,"background":{}}},
+                {"tag":"#text","text":5},
+                {"tag":"document"},
+                "text"
+            ]}"##,
+        )
+        .unwrap();
+
+        let json = dom.to_json();
+        let children = json["children"].as_array().unwrap();
+        assert_eq!(children.len(), 3);
+        assert!(children[0].get("children").is_none());
+        assert_eq!(children[2]["text"], "");
+    }
+
+    #[test]
+    fn oversized_numbers_are_clamped() {
+        let mut dom = parse(
+            r#"{"tag":"view","style":{"width":1e300,"height":"99999999999px","flexGrow":1e308}}"#,
+        )
+        .unwrap();
+        dom.compute_layout(&Default::default(), 480.0, 320.0);
+
+        let json = dom.to_json();
+        let view = &json["children"][0];
+        assert_eq!(view["style"]["width"], json!(MAX_NUMBER));
+        assert_eq!(view["style"]["height"], json!(MAX_NUMBER));
+        assert!(view["layout"]["width"].as_f64().unwrap() <= MAX_NUMBER);
+    }
+
+    #[test]
+    fn oversized_trees_hit_their_limits() {
+        let children = vec![r#"{"tag":"view"}"#; 50].join(",");
+        let wide = format!(r#"{{"tag":"view","children":[{}]}}"#, children);
+        let deep = format!(
+            "{}{}",
+            r#"{"tag":"view","children":["#.repeat(20),
+            "]}".repeat(20)
+        );
+        let limits = DomLimits {
+            max_nodes: 10,
+            ..DomLimits::default()
+        };
+
+        let err = Dom::try_from_json_bytes(wide.as_bytes(), InheritedStyle::new("default"), limits)
+            .err()
+            .unwrap();
+        assert_eq!(err.limit.unwrap().limit, Limit::Nodes);
+
+        let limits = DomLimits {
+            max_depth: 10,
+            ..DomLimits::default()
+        };
+
+        let err = Dom::try_from_json_bytes(deep.as_bytes(), InheritedStyle::new("default"), limits)
+            .err()
+            .unwrap();
+        assert_eq!(err.limit.unwrap().limit, Limit::Depth);
+
+        // Past the JSON parser's recursion limit, whatever the tree limits allow
+        let deeper = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        assert!(parse(&deeper).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use fontdue::layout::GlyphRasterConfig;
+use fontdue::{Font, Metrics};
+use image::imageops::{self, FilterType};
+use image::{ImageFormat, RgbaImage};
+use ttf_parser::{Face, GlyphId, RasterImageFormat};
+
+/// Files of fonts with color bitmap glyphs, by `Font::file_hash`. fontdue only reads
+/// outlines, so the bitmaps are read from these. Fonts can be decoded on the decoder's
+/// workers, so this is shared between threads.
+static FONTS: LazyLock<Mutex<HashMap<usize, Arc<Vec<u8>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Keep a font's file if it has color bitmap glyphs, in `sbix` or `CBDT` tables like
+/// Apple Color Emoji and Noto Color Emoji, so they're drawn in color rather than as blank
+/// boxes. Other fonts are ignored, so any font can be passed. Fonts loaded with `addFont`
+/// or `Renderer::add_font` are registered already; hosts passing fonts to `Renderer::new`
+/// register each with the bytes it was parsed from. Returns whether it was kept.
+pub fn register(font: &Font, data: &[u8]) -> bool {
+    let has_color = Face::parse(data, 0)
+        .is_ok_and(|face| face.tables().sbix.is_some() || face.tables().cbdt.is_some());
+
+    if has_color {
+        FONTS
+            .lock()
+            .unwrap()
+            .insert(font.file_hash(), Arc::new(data.to_vec()));
+    }
+
+    has_color
+}
+
+/// Whether `font` was registered with color glyphs.
+pub fn is_color(font: &Font) -> bool {
+    FONTS.lock().unwrap().contains_key(&font.file_hash())
+}
+
+/// A glyph's color bitmap, scaled from the nearest strike to the size asked for: its
+/// placement relative to the pen, as fontdue gives for outlines, and non-premultiplied
+/// RGBA pixels. `None` for fonts without color glyphs, and glyphs without a bitmap.
+pub fn rasterize(font: &Font, key: GlyphRasterConfig) -> Option<(Metrics, Vec<u8>)> {
+    let data = FONTS.lock().unwrap().get(&font.file_hash()).cloned()?;
+    let face = Face::parse(&data, 0).ok()?;
+    let strike = key.px.round().clamp(1.0, u16::MAX as f32) as u16;
+    let image = face.glyph_raster_image(GlyphId(key.glyph_index), strike)?;
+
+    let pixels = match image.format {
+        RasterImageFormat::PNG => image::load_from_memory_with_format(image.data, ImageFormat::Png)
+            .ok()?
+            .to_rgba8(),
+        RasterImageFormat::BitmapPremulBgra32 => {
+            let rgba = image.data.chunks_exact(4).flat_map(|bgra| {
+                let unpremultiply = |c: u8| match bgra[3] {
+                    0 => 0,
+                    a => (c as u32 * 255 / a as u32).min(255) as u8,
+                };
+                [
+                    unpremultiply(bgra[2]),
+                    unpremultiply(bgra[1]),
+                    unpremultiply(bgra[0]),
+                    bgra[3],
+                ]
+            });
+            RgbaImage::from_raw(image.width as u32, image.height as u32, rgba.collect())?
+        }
+        // Monochrome and grayscale bitmaps are drawn from the outlines instead
+        _ => return None,
+    };
+
+    // Strikes come in a few sizes, so scale to the one asked for, smoothly since emoji
+    // are mostly shrunk a lot
+    let scale = key.px / image.pixels_per_em as f32;
+    let width = (pixels.width() as f32 * scale).round().max(1.0) as u32;
+    let height = (pixels.height() as f32 * scale).round().max(1.0) as u32;
+    let pixels = imageops::resize(&pixels, width, height, FilterType::Triangle);
+
+    let metrics = Metrics {
+        xmin: (image.x as f32 * scale).round() as i32,
+        ymin: (image.y as f32 * scale).round() as i32,
+        width: width as usize,
+        height: height as usize,
+        ..font.metrics_indexed(key.glyph_index, key.px)
+    };
+
+    Some((metrics, pixels.into_raw()))
+}
@@ -1,25 +1,108 @@
+use crate::clock::Clock;
 use crate::timers::Timers;
-use rquickjs::{AsyncContext, AsyncRuntime, CatchResultExt, Ctx};
+use rquickjs::function::Func;
+use rquickjs::{AsyncContext, AsyncRuntime, CatchResultExt, Ctx, Function, JsLifetime, Object};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
 
 pub struct Engine {
     js_runtime: AsyncRuntime,
     js_context: AsyncContext,
     timers: Timers,
+    teardown: Teardown,
+    clock: Clock,
+}
+
+type Cleanup = Box<dyn FnOnce()>;
+
+/// Cleanups to run when an engine drops, while its runtime is still alive. Kept in the
+/// context's userdata so modules can add theirs from `register`.
+#[derive(Clone, Default)]
+struct Teardown {
+    cleanups: Rc<RefCell<Vec<Cleanup>>>,
+}
+
+// Holds no JS values itself, only closures that drop them
+unsafe impl<'js> JsLifetime<'js> for Teardown {
+    type Changed<'to> = Teardown;
+}
+
+impl Teardown {
+    /// Run the cleanups in the order they were added, including any added while running.
+    fn run(&self) {
+        loop {
+            let cleanups = std::mem::take(&mut *self.cleanups.borrow_mut());
+
+            if cleanups.is_empty() {
+                break;
+            }
+
+            cleanups.into_iter().for_each(|cleanup| cleanup());
+        }
+    }
 }
 
 pub trait JsModule {
     fn register(&self, ctx: &Ctx<'_>);
+
+    /// Move anything the module stores into a user profile's namespace. Called by
+    /// `Renderer::switch_profile` before the module is registered again.
+    fn set_profile(&self, _profile: &str) {}
+}
+
+/// Settings for a reproducible engine, for golden tests and input replays that must be
+/// bit-exact across runs and machines.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deterministic {
+    /// Seed for `Math.random`
+    pub seed: u64,
+    /// What `Date.now()` returns until time is advanced, in milliseconds since the epoch
+    pub start_time: f64,
 }
 
 impl Engine {
     pub async fn new(modules: &[Box<dyn JsModule>]) -> Self {
+        Self::create(modules, None, Clock::default()).await
+    }
+
+    /// Create an engine whose timers and `Date` follow `clock`, switched to a mock clock
+    /// that only moves with `advance_time`, and whose `Math.random` is seeded. Anything
+    /// else holding a clone of `clock`, like the renderer's animations, moves with it.
+    pub async fn new_deterministic(
+        modules: &[Box<dyn JsModule>],
+        deterministic: Deterministic,
+        clock: Clock,
+    ) -> Self {
+        Self::create(modules, Some(deterministic), clock).await
+    }
+
+    async fn create(
+        modules: &[Box<dyn JsModule>],
+        deterministic: Option<Deterministic>,
+        clock: Clock,
+    ) -> Self {
         let js_runtime = AsyncRuntime::new().unwrap();
         let js_context = AsyncContext::full(&js_runtime).await.unwrap();
-        let timers = Timers::new();
+        let timers = Timers::new(clock.clone());
+        let teardown = Teardown::default();
+
+        if deterministic.is_some() {
+            clock.use_mock();
+        }
 
         js_context
             .with(|ctx| {
+                if ctx.store_userdata(teardown.clone()).is_err() {
+                    eprintln!("Error registering engine teardown");
+                }
+
                 timers.register(&ctx);
+
+                if let Some(deterministic) = deterministic {
+                    register_deterministic(&ctx, deterministic, clock.clone());
+                }
+
                 modules.iter().for_each(|module| module.register(&ctx));
             })
             .await;
@@ -28,6 +111,19 @@ impl Engine {
             js_runtime,
             js_context,
             timers,
+            teardown,
+            clock,
+        }
+    }
+
+    /// Run `cleanup` when the engine drops, before its runtime does, in the order
+    /// cleanups were added. Anything keeping JS values alive outside the runtime, like a
+    /// `Persistent` callback, must let go of them here, or dropping the runtime aborts the
+    /// process; this covers hot reload, which replaces the engine, as well as shutdown.
+    /// Does nothing for a context that isn't an engine's.
+    pub fn on_teardown(ctx: &Ctx<'_>, cleanup: impl FnOnce() + 'static) {
+        if let Some(teardown) = ctx.userdata::<Teardown>() {
+            teardown.cleanups.borrow_mut().push(Box::new(cleanup));
         }
     }
 
@@ -44,6 +140,35 @@ impl Engine {
         &self.js_context
     }
 
+    /// Move the mock clock on by `ms` milliseconds, firing timers as they fall due on the
+    /// way, so intervals run as many times as they would in real time. Does nothing
+    /// unless the engine was created with `new_deterministic`.
+    pub async fn advance_time(&self, ms: u64) {
+        if !self.clock.is_mock() {
+            return;
+        }
+
+        let target = self.clock.now() + Duration::from_millis(ms);
+        self.tick().await;
+
+        while let Some(fire_at) = self
+            .timers
+            .next_fire_at()
+            .filter(|&fire_at| fire_at > self.clock.now() && fire_at <= target)
+        {
+            self.clock.advance_to(fire_at);
+            self.tick().await;
+        }
+
+        self.clock.advance_to(target);
+        self.tick().await;
+    }
+
+    /// Bytes the JS runtime has allocated.
+    pub async fn heap_used(&self) -> usize {
+        self.js_runtime.memory_usage().await.memory_used_size as usize
+    }
+
     pub async fn load(&self, js: &str) {
         self.with_context(|ctx| {
             if let Err(err) = ctx.eval::<(), _>(js).catch(&ctx) {
@@ -64,9 +189,55 @@ impl Engine {
     }
 }
 
+/// Replace `Math.random` with a seeded generator and make `Date` read the mock clock.
+fn register_deterministic(ctx: &Ctx<'_>, deterministic: Deterministic, clock: Clock) {
+    let state = Rc::new(Cell::new(deterministic.seed));
+    let math: Object = ctx.globals().get("Math").unwrap();
+
+    math.set(
+        "random",
+        Func::from(move || {
+            // splitmix64, keeping the top 53 bits for a double in [0, 1)
+            let seed = state.get().wrapping_add(0x9e37_79b9_7f4a_7c15);
+            state.set(seed);
+
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^= z >> 31;
+
+            (z >> 11) as f64 / (1u64 << 53) as f64
+        }),
+    )
+    .unwrap();
+
+    let now = Func::from(move || {
+        let elapsed = clock.mock_elapsed().unwrap_or_default();
+        deterministic.start_time + elapsed.as_millis() as f64
+    });
+
+    let install: Function = ctx
+        .eval(
+            r#"(now) => {
+                const SystemDate = Date;
+                globalThis.Date = class Date extends SystemDate {
+                    constructor(...args) {
+                        if (args.length === 0) super(now());
+                        else super(...args);
+                    }
+                    static now() {
+                        return now();
+                    }
+                };
+            }"#,
+        )
+        .unwrap();
+
+    install.call::<_, ()>((now,)).unwrap();
+}
+
 impl Drop for Engine {
     fn drop(&mut self) {
-        // Clear Persistent values before the Runtime drops, otherwise it aborts.
-        self.timers.clear();
+        self.teardown.run();
     }
 }
@@ -0,0 +1,298 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use fontdue::Font;
+
+use crate::inherited_style::{FontStyle, FontWeight, InheritedStyle};
+use crate::variable_font::{self, Axis, Coordinates};
+
+thread_local! {
+    /// Font names already warned about, so each is only reported once
+    static WARNED: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+/// One of a family's fonts, for text at a weight and style.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontFace {
+    /// The loaded font drawing this face
+    pub font: String,
+    pub weight: FontWeight,
+    pub style: FontStyle,
+}
+
+/// Loaded fonts by name, and the ones to try, in order, for characters a font doesn't
+/// have, like "°" or CJK in a Latin font. Without a fallback they'd be drawn as boxes.
+/// Fonts can also be grouped into families, like "Inter", whose faces text picks by
+/// `fontWeight` and `fontStyle`. Variable fonts are drawn at the instance text's style
+/// asks for, each made the first time it's asked for and kept as a font of its own.
+#[derive(Default)]
+pub struct FontRegistry {
+    fonts: HashMap<String, Font>,
+    fallbacks: Vec<String>,
+    families: HashMap<String, Vec<FontFace>>,
+    /// The axes of the loaded fonts that are variable
+    axes: HashMap<String, Vec<Axis>>,
+    /// Instances asked for that haven't been made yet, by name, with the font and axis
+    /// values to make them from
+    wanted: RefCell<HashMap<String, (String, Coordinates)>>,
+}
+
+impl FontRegistry {
+    pub fn new(fonts: HashMap<String, Font>) -> Self {
+        let axes = fonts
+            .iter()
+            .filter_map(|(name, font)| Some((name.clone(), variable_font::axes(font)?)))
+            .collect();
+
+        FontRegistry {
+            fonts,
+            fallbacks: Vec::new(),
+            families: HashMap::new(),
+            axes,
+            wanted: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Font> {
+        self.fonts.get(name)
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.fonts.contains_key(name)
+    }
+
+    /// Load a font, replacing any already loaded under `name`.
+    pub fn insert(&mut self, name: String, font: Font) {
+        // Instances of a font being replaced would be stale
+        let prefix = format!("{}[", name);
+        self.fonts
+            .retain(|other, _| !(other.starts_with(&prefix) && other.ends_with(']')));
+
+        match variable_font::axes(&font) {
+            Some(axes) => self.axes.insert(name.clone(), axes),
+            None => self.axes.remove(&name),
+        };
+
+        self.fonts.insert(name, font);
+    }
+
+    /// The name of the font to draw `name` in for text in `style`. For a variable font
+    /// that's the instance at the style's `fontVariationSettings`, with weight following
+    /// `fontWeight` and italic following `fontStyle`, if the font has those axes and the
+    /// settings don't set them. Instances are made by `make_instances`; until then, and
+    /// for static fonts or the default instance, it's `name`.
+    pub fn instance<'a>(&'a self, name: &'a str, style: &InheritedStyle) -> &'a str {
+        let Some(axes) = self.axes.get(name) else {
+            return name;
+        };
+
+        let coordinates: Coordinates = axes
+            .iter()
+            .filter_map(|axis| {
+                let value = match (style.font_variations.get(axis.tag), &axis.tag) {
+                    (Some(value), _) => value,
+                    (None, b"wght") => style.font_weight.0 as f32,
+                    (None, b"ital") if style.font_style == FontStyle::Italic => 1.0,
+                    _ => return None,
+                };
+
+                let value = value.clamp(axis.min, axis.max);
+                (value != axis.default).then_some((axis.tag, value))
+            })
+            .collect();
+
+        if coordinates.is_empty() {
+            return name;
+        }
+
+        let settings: Vec<String> = coordinates
+            .iter()
+            .map(|(tag, value)| format!("{}={}", String::from_utf8_lossy(tag), value))
+            .collect();
+        let instance = format!("{}[{}]", name, settings.join(","));
+
+        match self.fonts.get_key_value(&instance) {
+            Some((instance, _)) => instance,
+            None => {
+                self.wanted
+                    .borrow_mut()
+                    .insert(instance, (name.to_string(), coordinates));
+                name
+            }
+        }
+    }
+
+    /// Make the variable font instances text asked for since the last call. Returns
+    /// whether there were any, so text using them is measured and drawn again.
+    pub fn make_instances(&mut self) -> bool {
+        let wanted = std::mem::take(self.wanted.get_mut());
+
+        for (instance, (name, coordinates)) in &wanted {
+            let Some(font) = self.fonts.get(name) else {
+                continue;
+            };
+
+            // A font that can't be instanced is drawn at its default instance, rather than
+            // trying again every frame
+            let font = variable_font::instance(font, coordinates).unwrap_or_else(|err| {
+                eprintln!("Warning: can't make the instance {}: {}", instance, err);
+                font.clone()
+            });
+
+            self.fonts.insert(instance.clone(), font);
+        }
+
+        !wanted.is_empty()
+    }
+
+    /// Add a face to `family`, replacing any it has at the same weight and style. The
+    /// face's font is looked up by name when text is drawn, so it can be loaded again
+    /// later without adding the face again.
+    pub fn add_face(&mut self, family: &str, face: FontFace) {
+        let faces = self.families.entry(family.to_string()).or_default();
+        faces.retain(|other| (other.weight, other.style) != (face.weight, face.style));
+        faces.push(face);
+    }
+
+    pub fn contains_family(&self, family: &str) -> bool {
+        self.families.contains_key(family)
+    }
+
+    /// The name of the font to draw `name` in at `weight` and `style`. For a family that's
+    /// its closest face, matched as CSS does: the style first, then the nearest weight,
+    /// looking lighter first below 400 and heavier first above 500. Other names are
+    /// fonts, which are used as they are.
+    pub fn face<'a>(&'a self, name: &'a str, weight: FontWeight, style: FontStyle) -> &'a str {
+        let Some(faces) = self.families.get(name) else {
+            return name;
+        };
+
+        // Italic text falls back to upright faces if there's no italic one, and the
+        // other way round
+        let styled = faces.iter().any(|face| face.style == style);
+
+        faces
+            .iter()
+            .filter(|face| !styled || face.style == style)
+            .min_by_key(|face| weight_preference(weight.0, face.weight.0))
+            .map_or(name, |face| face.font.as_str())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.fonts.keys().map(String::as_str)
+    }
+
+    /// Any loaded font, for when the one wanted is missing.
+    pub fn any(&self) -> Option<&Font> {
+        self.fonts.values().next()
+    }
+
+    /// Set the fonts to try for characters missing from a node's own font, most preferred
+    /// first. Names that aren't loaded are skipped until they are.
+    pub fn set_fallbacks(&mut self, names: Vec<String>) {
+        self.fallbacks = names;
+    }
+
+    pub fn fallbacks(&self) -> &[String] {
+        &self.fallbacks
+    }
+
+    /// `font` followed by the loaded fallbacks, for `pick` to choose from per character.
+    pub fn chain<'a>(&'a self, font: &'a Font) -> Vec<&'a Font> {
+        let fallbacks = self
+            .fallbacks
+            .iter()
+            .filter_map(|name| self.fonts.get(name))
+            .filter(|fallback| !std::ptr::eq(*fallback, font));
+
+        std::iter::once(font).chain(fallbacks).collect()
+    }
+}
+
+/// The font in a chain to draw `c` with: the first with a glyph for it, or the first
+/// font if none have one. Whitespace and control characters always use the first.
+pub fn pick<'a>(chain: &[&'a Font], c: char) -> &'a Font {
+    if !c.is_whitespace() && !c.is_control() {
+        for font in chain {
+            if font.lookup_glyph_index(c) != 0 {
+                return font;
+            }
+        }
+    }
+
+    chain[0]
+}
+
+/// The font in a chain a laid out glyph came from.
+pub fn by_hash<'a>(chain: &[&'a Font], font_hash: usize) -> &'a Font {
+    chain
+        .iter()
+        .find(|font| font.file_hash() == font_hash)
+        .unwrap_or(&chain[0])
+}
+
+/// How well a face of `weight` suits text asking for `wanted`, lowest best, following
+/// CSS's font matching.
+fn weight_preference(wanted: u16, weight: u16) -> (u8, u16) {
+    let tier = match wanted {
+        // Light text looks lighter first
+        ..400 => u8::from(weight > wanted),
+        // Regular text looks up to 500 first, then lighter, then heavier
+        400..=500 if (wanted..=500).contains(&weight) => 0,
+        400..=500 if weight < wanted => 1,
+        400..=500 => 2,
+        // Bold text looks heavier first
+        _ => u8::from(weight < wanted),
+    };
+
+    (tier, wanted.abs_diff(weight))
+}
+
+/// The name of the font to use for text in `style`: its font, or its family's face for
+/// its weight and style, if that's loaded. Otherwise `default`, likewise, with a warning
+/// the first time each missing name is asked for. Text in a font that was never loaded
+/// would otherwise measure as nothing and silently disappear. Variable fonts give the
+/// instance for the style, as `FontRegistry::instance` does.
+pub fn resolve_name<'a>(
+    fonts: &'a FontRegistry,
+    style: &'a InheritedStyle,
+    default: &'a str,
+) -> &'a str {
+    let name = fonts.face(&style.font_name, style.font_weight, style.font_style);
+
+    if fonts.contains_key(name) {
+        return fonts.instance(name, style);
+    }
+
+    WARNED.with_borrow_mut(|warned| {
+        if !warned.contains(name) {
+            eprintln!(
+                "Warning: font \"{}\" isn't loaded, using \"{}\" instead",
+                name, default
+            );
+            warned.insert(name.to_string());
+        }
+    });
+
+    fonts.instance(
+        fonts.face(default, style.font_weight, style.font_style),
+        style,
+    )
+}
+
+/// Look up the font for text in `style`, falling back to `default` as `resolve_name`
+/// does. `None` only if the default font is missing too.
+pub fn resolve<'a>(
+    fonts: &'a FontRegistry,
+    style: &InheritedStyle,
+    default: &str,
+) -> Option<&'a Font> {
+    fonts.get(resolve_name(fonts, style, default))
+}
+
+/// Whether text in `style` should be marked as missing its font, which only happens in
+/// debug builds so a missing asset is obvious on a development device.
+pub fn mark_missing(fonts: &FontRegistry, style: &InheritedStyle) -> bool {
+    let name = fonts.face(&style.font_name, style.font_weight, style.font_style);
+    cfg!(debug_assertions) && !fonts.contains_key(name)
+}
@@ -0,0 +1,336 @@
+use std::cell::RefCell;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+use std::time::UNIX_EPOCH;
+
+use rquickjs::function::{Func, Opt};
+use rquickjs::{ArrayBuffer, Ctx, FromJs, IntoJs, Object, TypedArray, Value};
+
+use crate::engine::JsModule;
+
+/// Exposes a `fs` global to JS with file access restricted to a sandbox directory.
+///
+/// Paths from JS are always resolved relative to `root`: a leading `/` refers to the
+/// sandbox root, and `..` components can't climb above it. The sandbox is `root/default`
+/// until a user profile is active, then that profile's directory under `root/profiles`,
+/// so no sandbox contains another.
+pub struct Fs {
+    base: PathBuf,
+    root: RefCell<Rc<PathBuf>>,
+}
+
+impl Fs {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let base = root.into();
+
+        Self {
+            root: RefCell::new(Rc::new(default_root(&base))),
+            base,
+        }
+    }
+
+    /// Resolve a JS-supplied path to a real path inside the sandbox.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, FsError> {
+        resolve(&self.root.borrow(), path)
+    }
+}
+
+/// The directory files live in under a module's root before any profile is active,
+/// created if needed. It's a sibling of `profiles` rather than `base` itself, which
+/// would let the default app reach into every profile.
+pub(crate) fn default_root(base: &Path) -> PathBuf {
+    create_root(base.join("default"))
+}
+
+/// The directory a profile's files live in under a module's root, created if needed.
+pub(crate) fn profile_root(base: &Path, profile: &str) -> PathBuf {
+    create_root(base.join("profiles").join(profile))
+}
+
+fn create_root(root: PathBuf) -> PathBuf {
+    if let Err(err) = std::fs::create_dir_all(&root) {
+        eprintln!(
+            "Error creating profile directory {}: {}",
+            root.display(),
+            err
+        );
+    }
+
+    root
+}
+
+pub(crate) fn resolve(root: &Path, path: &str) -> Result<PathBuf, FsError> {
+    let mut resolved = PathBuf::new();
+
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return Err(FsError::new(path, "path escapes the sandbox"));
+                }
+            }
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+
+    let full = root.join(resolved);
+
+    // Lexical checks don't catch symlinks pointing outside the sandbox, so compare the
+    // canonical form of the nearest existing ancestor against the canonical root.
+    let canonical_root = root
+        .canonicalize()
+        .map_err(|err| FsError::from_io(path, err))?;
+
+    let existing = full
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(root);
+
+    let canonical = existing
+        .canonicalize()
+        .map_err(|err| FsError::from_io(path, err))?;
+
+    if !canonical.starts_with(&canonical_root) {
+        return Err(FsError::new(path, "path escapes the sandbox"));
+    }
+
+    Ok(full)
+}
+
+/// File contents returned to JS: a string when an encoding was requested, otherwise an
+/// `ArrayBuffer`.
+pub enum FileContents {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl<'js> IntoJs<'js> for FileContents {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        match self {
+            FileContents::Text(text) => text.into_js(ctx),
+            FileContents::Binary(data) => ArrayBuffer::new(ctx.clone(), data)?.into_js(ctx),
+        }
+    }
+}
+
+/// Data passed to `writeFile`: a string, an `ArrayBuffer` or a `Uint8Array`.
+pub struct FileData(pub Vec<u8>);
+
+impl<'js> FromJs<'js> for FileData {
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> rquickjs::Result<Self> {
+        if let Some(text) = value.as_string() {
+            return Ok(FileData(text.to_string()?.into_bytes()));
+        }
+
+        if let Some(bytes) = ArrayBuffer::from_value(value.clone())
+            .as_ref()
+            .and_then(ArrayBuffer::as_bytes)
+        {
+            return Ok(FileData(bytes.to_vec()));
+        }
+
+        if let Ok(array) = TypedArray::<u8>::from_js(ctx, value.clone())
+            && let Some(bytes) = array.as_bytes()
+        {
+            return Ok(FileData(bytes.to_vec()));
+        }
+
+        Err(rquickjs::Error::new_from_js(
+            value.type_name(),
+            "string, ArrayBuffer or Uint8Array",
+        ))
+    }
+}
+
+pub struct FileStat {
+    pub size: u64,
+    pub is_file: bool,
+    pub is_directory: bool,
+    pub mtime_ms: f64,
+}
+
+impl<'js> IntoJs<'js> for FileStat {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        let stat = Object::new(ctx.clone())?;
+        stat.set("size", self.size as f64)?;
+        stat.set("isFile", self.is_file)?;
+        stat.set("isDirectory", self.is_directory)?;
+        stat.set("mtimeMs", self.mtime_ms)?;
+        stat.into_js(ctx)
+    }
+}
+
+#[derive(Debug)]
+pub struct FsError {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl FsError {
+    fn new(path: &str, message: &str) -> Self {
+        FsError {
+            code: "EACCES",
+            message: format!("{}: {}", path, message),
+        }
+    }
+
+    fn from_io(path: &str, err: io::Error) -> Self {
+        let code = match err.kind() {
+            io::ErrorKind::NotFound => "ENOENT",
+            io::ErrorKind::PermissionDenied => "EACCES",
+            io::ErrorKind::AlreadyExists => "EEXIST",
+            io::ErrorKind::NotADirectory => "ENOTDIR",
+            io::ErrorKind::IsADirectory => "EISDIR",
+            _ => "EIO",
+        };
+
+        FsError {
+            code,
+            message: format!("{}: {}", path, err),
+        }
+    }
+}
+
+impl<'js> IntoJs<'js> for FsError {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        let error = Object::new(ctx.clone())?;
+        error.set("code", self.code)?;
+        error.set("message", self.message)?;
+        error.into_js(ctx)
+    }
+}
+
+impl JsModule for Fs {
+    fn register(&self, ctx: &Ctx<'_>) {
+        let fs = Object::new(ctx.clone()).unwrap();
+        let sandbox = self.root.borrow().clone();
+
+        let root = sandbox.clone();
+        fs.set(
+            "readFile",
+            Func::from(
+                move |ctx: Ctx<'_>,
+                      path: String,
+                      encoding: Opt<String>|
+                      -> rquickjs::Result<FileContents> {
+                    resolve(&root, &path)
+                        .and_then(|full| {
+                            std::fs::read(full).map_err(|err| FsError::from_io(&path, err))
+                        })
+                        .and_then(|data| match encoding.0.as_deref() {
+                            None => Ok(FileContents::Binary(data)),
+                            Some("utf8" | "utf-8") => String::from_utf8(data)
+                                .map(FileContents::Text)
+                                .map_err(|_| FsError::new(&path, "file is not valid UTF-8")),
+                            Some(other) => Err(FsError::new(
+                                &path,
+                                &format!("unsupported encoding '{}'", other),
+                            )),
+                        })
+                        .map_err(|err| ctx.throw(err.into_js(&ctx).unwrap()))
+                },
+            ),
+        )
+        .unwrap();
+
+        let root = sandbox.clone();
+        fs.set(
+            "writeFile",
+            Func::from(
+                move |ctx: Ctx<'_>, path: String, data: FileData| -> rquickjs::Result<()> {
+                    resolve(&root, &path)
+                        .and_then(|full| {
+                            std::fs::write(full, data.0).map_err(|err| FsError::from_io(&path, err))
+                        })
+                        .map_err(|err| ctx.throw(err.into_js(&ctx).unwrap()))
+                },
+            ),
+        )
+        .unwrap();
+
+        let root = sandbox.clone();
+        fs.set(
+            "readdir",
+            Func::from(
+                move |ctx: Ctx<'_>, path: String| -> rquickjs::Result<Vec<String>> {
+                    resolve(&root, &path)
+                        .and_then(|full| {
+                            std::fs::read_dir(full).map_err(|err| FsError::from_io(&path, err))
+                        })
+                        .map(|entries| {
+                            let mut names: Vec<String> = entries
+                                .flatten()
+                                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                                .collect();
+                            names.sort();
+                            names
+                        })
+                        .map_err(|err| ctx.throw(err.into_js(&ctx).unwrap()))
+                },
+            ),
+        )
+        .unwrap();
+
+        let root = sandbox.clone();
+        fs.set(
+            "stat",
+            Func::from(
+                move |ctx: Ctx<'_>, path: String| -> rquickjs::Result<FileStat> {
+                    resolve(&root, &path)
+                        .and_then(|full| {
+                            std::fs::metadata(full).map_err(|err| FsError::from_io(&path, err))
+                        })
+                        .map(|metadata| FileStat {
+                            size: metadata.len(),
+                            is_file: metadata.is_file(),
+                            is_directory: metadata.is_dir(),
+                            mtime_ms: metadata
+                                .modified()
+                                .ok()
+                                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                                .map_or(0.0, |duration| duration.as_secs_f64() * 1000.0),
+                        })
+                        .map_err(|err| ctx.throw(err.into_js(&ctx).unwrap()))
+                },
+            ),
+        )
+        .unwrap();
+
+        ctx.globals().set("fs", fs).unwrap();
+    }
+
+    fn set_profile(&self, profile: &str) {
+        *self.root.borrow_mut() = Rc::new(profile_root(&self.base, profile));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::JsModule;
+
+    #[test]
+    fn default_root_cant_reach_profiles() {
+        let base = std::env::temp_dir().join(format!("juice-fs-{}", std::process::id()));
+        let profile = profile_root(&base, "x");
+        std::fs::write(profile.join("a.txt"), "x").unwrap();
+
+        let fs = Fs::new(&base);
+        assert!(fs.resolve("../profiles/x/a.txt").is_err());
+        assert!(fs.resolve("/../profiles/x").is_err());
+        assert!(!fs.resolve("profiles/x/a.txt").unwrap().exists());
+        assert!(
+            fs.resolve("a.txt")
+                .unwrap()
+                .starts_with(base.join("default"))
+        );
+
+        fs.set_profile("x");
+        assert!(fs.resolve("a.txt").unwrap().exists());
+        assert!(fs.resolve("../default").is_err());
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
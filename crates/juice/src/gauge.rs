@@ -0,0 +1,166 @@
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::canvas::RgbColor;
+
+/// A colored band of a gauge's scale, like a red zone.
+#[derive(Debug, Clone, Copy)]
+pub struct GaugeRange {
+    pub from: f32,
+    pub to: f32,
+    pub color: RgbColor,
+}
+
+#[derive(Deserialize)]
+struct RangeSpec {
+    from: f32,
+    to: f32,
+    color: String,
+}
+
+/// The needle moving from one value to another.
+#[derive(Debug, Clone, Copy)]
+struct Sweep {
+    from: f32,
+    started: Instant,
+}
+
+/// State of a `gauge` node: a dial with a scale along an arc and a needle pointing at
+/// the value. Angles are in degrees clockwise from 12 o'clock.
+#[derive(Debug, Clone)]
+pub struct Gauge {
+    pub min: f32,
+    pub max: f32,
+    pub start_angle: f32,
+    pub end_angle: f32,
+    /// Intervals between labelled major ticks
+    pub major_ticks: u32,
+    /// Ticks between each pair of major ticks
+    pub minor_ticks: u32,
+    /// Width of the arc. Defaults to a tenth of the radius.
+    pub thickness: Option<f32>,
+    pub track_color: Option<RgbColor>,
+    /// Fills the arc from `min` up to the value when set
+    pub value_color: Option<RgbColor>,
+    pub ranges: Vec<GaugeRange>,
+    pub labels: bool,
+    /// How long the needle takes to sweep to a new value
+    pub sweep_duration: Duration,
+    value: f32,
+    sweep: Option<Sweep>,
+}
+
+impl Default for Gauge {
+    fn default() -> Self {
+        Gauge {
+            min: 0.0,
+            max: 100.0,
+            start_angle: -135.0,
+            end_angle: 135.0,
+            major_ticks: 10,
+            minor_ticks: 4,
+            thickness: None,
+            track_color: None,
+            value_color: None,
+            ranges: Vec::new(),
+            labels: true,
+            sweep_duration: Duration::from_millis(500),
+            value: 0.0,
+            sweep: None,
+        }
+    }
+}
+
+impl Gauge {
+    /// The value the needle is heading for.
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Point the needle at a new value, sweeping there from wherever it is now. The
+    /// needle starts at `min`, so the first value sweeps up from the bottom of the scale.
+    pub fn set_value(&mut self, value: f32, now: Instant) {
+        if value == self.value {
+            return;
+        }
+
+        let from = self.displayed_value(now);
+        self.value = value;
+        self.sweep = Some(Sweep { from, started: now });
+    }
+
+    /// Parse the `ranges` attribute: JSON `[{from, to, color}]`, with colors resolved by
+    /// `resolve_color`.
+    pub fn set_ranges(&mut self, json: &str, resolve_color: impl Fn(&str) -> Option<RgbColor>) {
+        let specs: Vec<RangeSpec> = serde_json::from_str(json).unwrap_or_default();
+
+        self.ranges = specs
+            .into_iter()
+            .filter_map(|spec| {
+                Some(GaugeRange {
+                    from: spec.from,
+                    to: spec.to,
+                    color: resolve_color(&spec.color)?,
+                })
+            })
+            .collect();
+    }
+
+    fn sweep_progress(&self, now: Instant) -> Option<(Sweep, f32)> {
+        let sweep = self.sweep?;
+        let elapsed = now.saturating_duration_since(sweep.started).as_secs_f32();
+        let t = elapsed / self.sweep_duration.as_secs_f32().max(f32::EPSILON);
+
+        (t < 1.0).then_some((sweep, t))
+    }
+
+    /// Where the needle points at `now`, partway through a sweep.
+    pub fn displayed_value(&self, now: Instant) -> f32 {
+        match self.sweep_progress(now) {
+            Some((sweep, t)) => {
+                // Ease out, like a damped needle settling
+                let eased = 1.0 - (1.0 - t).powi(3);
+                sweep.from + (self.value - sweep.from) * eased
+            }
+            None => self.value,
+        }
+    }
+
+    /// Whether the needle is still moving.
+    pub fn sweeping(&self, now: Instant) -> bool {
+        self.sweep_progress(now).is_some()
+    }
+
+    /// The angle of a value on the scale, clamped to its ends.
+    pub fn angle_of(&self, value: f32) -> f32 {
+        let span = self.max - self.min;
+        let t = if span == 0.0 {
+            0.0
+        } else {
+            ((value - self.min) / span).clamp(0.0, 1.0)
+        };
+
+        self.start_angle + (self.end_angle - self.start_angle) * t
+    }
+
+    /// Every tick's value, and whether it's a major one.
+    pub fn ticks(&self) -> impl Iterator<Item = (f32, bool)> + '_ {
+        let per_major = self.minor_ticks + 1;
+        let count = self.major_ticks * per_major;
+
+        (0..=count).filter(|_| self.major_ticks > 0).map(move |i| {
+            let value = self.min + (self.max - self.min) * i as f32 / count as f32;
+            (value, i % per_major == 0)
+        })
+    }
+}
+
+/// A tick label: whole numbers without decimals, others to one place.
+pub fn format_label(value: f32) -> String {
+    if (value - value.round()).abs() < 0.001 {
+        format!("{}", value.round())
+    } else {
+        format!("{:.1}", value)
+    }
+}
@@ -0,0 +1,157 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use fontdue::layout::GlyphRasterConfig;
+use fontdue::{Font, Metrics};
+
+use crate::emoji;
+
+/// Most coverage bitmap bytes kept until `set_budget` says otherwise. A few thousand
+/// glyphs at UI sizes, which is every glyph on most screens.
+const DEFAULT_BUDGET: usize = 2 * 1024 * 1024;
+/// Most advance widths kept, for the same reason
+const MAX_ADVANCES: usize = 16 * 1024;
+
+thread_local! {
+    /// Shared by measuring and drawing, which run on the same thread
+    static CACHE: RefCell<GlyphCache> = RefCell::new(GlyphCache::new());
+}
+
+/// A rasterized glyph: where it sits relative to the pen, and one byte of coverage per
+/// pixel, or RGBA pixels for a color emoji.
+pub struct Glyph {
+    pub metrics: Metrics,
+    pub coverage: Vec<u8>,
+    /// Non-premultiplied RGBA, drawn as is rather than in the text color
+    pub color: Option<Vec<u8>>,
+}
+
+impl Glyph {
+    fn bytes(&self) -> usize {
+        self.coverage.len() + self.color.as_ref().map_or(0, Vec::len)
+    }
+}
+
+struct GlyphCache {
+    /// Keyed by font, glyph and size, as fontdue's layouts give them, with when each was
+    /// last used
+    glyphs: HashMap<GlyphRasterConfig, (Rc<Glyph>, u64)>,
+    /// Advance widths by font hash, character and size in bits
+    advances: HashMap<(usize, char, u32), f32>,
+    bytes: usize,
+    budget: usize,
+    /// Counts lookups, to order glyphs by when they were last used
+    uses: u64,
+}
+
+impl GlyphCache {
+    fn new() -> Self {
+        GlyphCache {
+            glyphs: HashMap::new(),
+            advances: HashMap::new(),
+            bytes: 0,
+            budget: DEFAULT_BUDGET,
+            uses: 0,
+        }
+    }
+
+    /// Drop the least recently used glyphs until they take `bytes` at most.
+    fn trim(&mut self, bytes: usize) {
+        if self.bytes <= bytes {
+            return;
+        }
+
+        let mut by_use: Vec<(u64, GlyphRasterConfig)> = self
+            .glyphs
+            .iter()
+            .map(|(key, (_, used))| (*used, *key))
+            .collect();
+        by_use.sort_unstable_by_key(|(used, _)| *used);
+
+        for (_, key) in by_use {
+            if self.bytes <= bytes {
+                break;
+            }
+
+            if let Some((glyph, _)) = self.glyphs.remove(&key) {
+                self.bytes -= glyph.bytes();
+            }
+        }
+    }
+}
+
+/// Rasterize a glyph from a fontdue layout, or reuse it from the last time.
+pub fn rasterize(font: &Font, key: GlyphRasterConfig) -> Rc<Glyph> {
+    CACHE.with_borrow_mut(|cache| {
+        cache.uses += 1;
+        let uses = cache.uses;
+
+        if let Some((glyph, used)) = cache.glyphs.get_mut(&key) {
+            *used = uses;
+            return glyph.clone();
+        }
+
+        let glyph = match emoji::rasterize(font, key) {
+            Some((metrics, pixels)) => Glyph {
+                metrics,
+                coverage: Vec::new(),
+                color: Some(pixels),
+            },
+            None => {
+                let (metrics, coverage) = font.rasterize_config(key);
+                Glyph {
+                    metrics,
+                    coverage,
+                    color: None,
+                }
+            }
+        };
+        let glyph = Rc::new(glyph);
+
+        // Trimming to three quarters leaves room to add glyphs for a while before the
+        // next sort
+        if cache.bytes + glyph.bytes() > cache.budget {
+            cache.trim(cache.budget * 3 / 4);
+        }
+
+        cache.bytes += glyph.bytes();
+        cache.glyphs.insert(key, (glyph.clone(), uses));
+        glyph
+    })
+}
+
+/// How far the pen moves after `c` at `font_size`.
+pub fn advance(font: &Font, c: char, font_size: f32) -> f32 {
+    CACHE.with_borrow_mut(|cache| {
+        if cache.advances.len() >= MAX_ADVANCES {
+            cache.advances.clear();
+        }
+
+        *cache
+            .advances
+            .entry((font.file_hash(), c, font_size.to_bits()))
+            .or_insert_with(|| font.metrics(c, font_size).advance_width)
+    })
+}
+
+/// Change how many bytes of glyphs are kept, dropping the least recently used if they're
+/// over it.
+pub fn set_budget(bytes: usize) {
+    CACHE.with_borrow_mut(|cache| {
+        cache.budget = bytes;
+        cache.trim(bytes);
+    });
+}
+
+/// Drop the least recently used glyphs until they take `bytes` at most, and every
+/// advance width if that's nothing.
+pub fn trim(bytes: usize) {
+    CACHE.with_borrow_mut(|cache| {
+        cache.trim(bytes);
+
+        if bytes == 0 {
+            cache.advances = HashMap::new();
+        }
+    });
+}
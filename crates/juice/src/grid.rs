@@ -0,0 +1,212 @@
+use taffy::{
+    GridAutoFlow, GridPlacement, GridTemplateComponent, GridTemplateRepetition, Line,
+    MaxTrackSizingFunction, MinTrackSizingFunction, RepetitionCount, Style, TrackSizingFunction,
+    style_helpers::{auto, fit_content, fr, length, line, max_content, min_content, percent, span},
+};
+
+/// Apply a grid property given in CSS syntax, like `gridTemplateColumns: "repeat(3, 1fr)"`
+/// or `gridColumn: "1 / span 2"`. Returns whether the key was a grid property. Values
+/// that don't parse leave the property as it was.
+pub fn set_style(style: &mut Style, key: &str, value: &str) -> bool {
+    match key {
+        "gridAutoColumns" => {
+            if let Some(tracks) = parse_tracks(value) {
+                style.grid_auto_columns = tracks;
+            }
+        }
+        "gridAutoFlow" => style.grid_auto_flow = parse_auto_flow(value),
+        "gridAutoRows" => {
+            if let Some(tracks) = parse_tracks(value) {
+                style.grid_auto_rows = tracks;
+            }
+        }
+        "gridColumn" => {
+            if let Some(placement) = parse_placement(value) {
+                style.grid_column = placement;
+            }
+        }
+        "gridRow" => {
+            if let Some(placement) = parse_placement(value) {
+                style.grid_row = placement;
+            }
+        }
+        "gridTemplateColumns" => {
+            if let Some(template) = parse_template(value) {
+                style.grid_template_columns = template;
+            }
+        }
+        "gridTemplateRows" => {
+            if let Some(template) = parse_template(value) {
+                style.grid_template_rows = template;
+            }
+        }
+        _ => return false,
+    }
+
+    true
+}
+
+fn parse_auto_flow(str: &str) -> GridAutoFlow {
+    match str {
+        "column" => GridAutoFlow::Column,
+        "column dense" => GridAutoFlow::ColumnDense,
+        "row dense" | "dense" => GridAutoFlow::RowDense,
+        _ => GridAutoFlow::Row,
+    }
+}
+
+/// A placement like `"2"`, `"span 2"`, `"1 / 3"`, `"1 / span 2"` or `"-1"`.
+fn parse_placement(str: &str) -> Option<Line<GridPlacement>> {
+    let (start, end) = match str.split_once('/') {
+        Some((start, end)) => (parse_line(start)?, parse_line(end)?),
+        None => (parse_line(str)?, GridPlacement::Auto),
+    };
+
+    Some(Line { start, end })
+}
+
+fn parse_line(str: &str) -> Option<GridPlacement> {
+    let str = str.trim();
+
+    if str == "auto" {
+        return Some(GridPlacement::Auto);
+    }
+
+    if let Some(count) = str.strip_prefix("span") {
+        return count
+            .trim()
+            .parse()
+            .ok()
+            .filter(|&count| count > 0)
+            .map(span);
+    }
+
+    // Line 0 doesn't exist; lines count from 1, or from -1 at the end
+    str.parse().ok().filter(|&index| index != 0).map(line)
+}
+
+/// A track list like `"120px 1fr 2fr"`, with `repeat()` allowed.
+fn parse_template(str: &str) -> Option<Vec<GridTemplateComponent<String>>> {
+    split_tracks(str)
+        .into_iter()
+        .map(|token| match function_args(token, "repeat") {
+            Some(args) => parse_repeat(args),
+            None => parse_track(token).map(GridTemplateComponent::Single),
+        })
+        .collect()
+}
+
+/// A track list without `repeat()`, as for `gridAutoRows`.
+fn parse_tracks(str: &str) -> Option<Vec<TrackSizingFunction>> {
+    split_tracks(str).into_iter().map(parse_track).collect()
+}
+
+fn parse_repeat(args: &str) -> Option<GridTemplateComponent<String>> {
+    let (count, tracks) = args.split_once(',')?;
+    let count = match count.trim() {
+        "auto-fill" => RepetitionCount::AutoFill,
+        "auto-fit" => RepetitionCount::AutoFit,
+        count => RepetitionCount::Count(count.parse().ok().filter(|&count| count > 0)?),
+    };
+    let tracks = parse_tracks(tracks)?;
+
+    if tracks.is_empty() {
+        return None;
+    }
+
+    Some(GridTemplateComponent::Repeat(GridTemplateRepetition {
+        count,
+        line_names: vec![Vec::new(); tracks.len() + 1],
+        tracks,
+    }))
+}
+
+/// One track size: a length like `120` or `120px`, a percentage, `1fr`, `auto`,
+/// `min-content`, `max-content`, `minmax(min, max)` or `fit-content(limit)`.
+fn parse_track(str: &str) -> Option<TrackSizingFunction> {
+    if let Some(args) = function_args(str, "minmax") {
+        let (min, max) = args.split_once(',')?;
+        return Some(TrackSizingFunction {
+            min: parse_min(min.trim())?,
+            max: parse_max(max.trim())?,
+        });
+    }
+
+    if let Some(limit) = function_args(str, "fit-content") {
+        let limit = limit.trim();
+        return Some(match limit.strip_suffix('%') {
+            Some(value) => fit_content(percent(value.parse::<f32>().ok()? / 100.0)),
+            None => fit_content(length(parse_length(limit)?)),
+        });
+    }
+
+    if let Some(value) = str.strip_suffix("fr") {
+        return Some(fr(value.parse::<f32>().ok()?));
+    }
+
+    Some(TrackSizingFunction {
+        min: parse_min(str)?,
+        max: parse_max(str)?,
+    })
+}
+
+fn parse_min(str: &str) -> Option<MinTrackSizingFunction> {
+    Some(match str {
+        "auto" => auto(),
+        "min-content" => min_content(),
+        "max-content" => max_content(),
+        str => match str.strip_suffix('%') {
+            Some(value) => percent(value.parse::<f32>().ok()? / 100.0),
+            None => length(parse_length(str)?),
+        },
+    })
+}
+
+fn parse_max(str: &str) -> Option<MaxTrackSizingFunction> {
+    match str.strip_suffix("fr") {
+        Some(value) => Some(fr(value.parse::<f32>().ok()?)),
+        None => parse_min(str).map(MaxTrackSizingFunction::from),
+    }
+}
+
+fn parse_length(str: &str) -> Option<f32> {
+    str.strip_suffix("px").unwrap_or(str).parse().ok()
+}
+
+/// The arguments of `name(...)`, if the token is a call to it.
+fn function_args<'a>(token: &'a str, name: &str) -> Option<&'a str> {
+    token
+        .strip_prefix(name)?
+        .strip_prefix('(')?
+        .strip_suffix(')')
+}
+
+/// Split a track list on whitespace outside parentheses, so `repeat(2, 1fr 2fr)` stays
+/// one token.
+fn split_tracks(str: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut depth = 0;
+    let mut start = None;
+
+    for (index, char) in str.char_indices() {
+        match char {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            char if char.is_whitespace() && depth == 0 => {
+                if let Some(start) = start.take() {
+                    tokens.push(&str[start..index]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        start.get_or_insert(index);
+    }
+
+    if let Some(start) = start {
+        tokens.push(&str[start..]);
+    }
+
+    tokens
+}
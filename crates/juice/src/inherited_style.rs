@@ -1,3 +1,5 @@
+use std::fmt;
+
 use crate::canvas::RgbColor;
 
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
@@ -8,25 +10,309 @@ pub enum TextAlign {
     Right,
 }
 
+/// Which way text and rows run, from the `direction` style.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Direction {
+    #[default]
+    Ltr,
+    /// Text paragraphs start on the right, and `row` flex containers lay out right to
+    /// left
+    Rtl,
+}
+
+impl Direction {
+    /// Parse `ltr` or `rtl`.
+    pub fn parse(str: &str) -> Option<Self> {
+        match str {
+            "ltr" => Some(Direction::Ltr),
+            "rtl" => Some(Direction::Rtl),
+            _ => None,
+        }
+    }
+
+    /// Where text lines up by default: the side lines start on.
+    pub fn start(self) -> TextAlign {
+        match self {
+            Direction::Ltr => TextAlign::Left,
+            Direction::Rtl => TextAlign::Right,
+        }
+    }
+}
+
+/// Height of each line of text, from the `lineHeight` style.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LineHeight {
+    /// From the font's metrics
+    #[default]
+    Normal,
+    /// A multiple of the font size, like a unitless CSS `line-height`
+    Multiplier(f32),
+    Pixels(f32),
+}
+
+impl LineHeight {
+    /// Parse `normal`, a multiplier like `1.5` or pixels like `24px`.
+    pub fn parse(str: &str) -> Option<Self> {
+        let str = str.trim();
+
+        if str == "normal" {
+            return Some(LineHeight::Normal);
+        }
+
+        let (value, pixels) = match str.strip_suffix("px") {
+            Some(value) => (value, true),
+            None => (str, false),
+        };
+        let value = value.parse().ok().filter(|value: &f32| *value >= 0.0)?;
+
+        Some(if pixels {
+            LineHeight::Pixels(value)
+        } else {
+            LineHeight::Multiplier(value)
+        })
+    }
+
+    /// The line height in pixels, given the font size and the height the font's metrics
+    /// give a line.
+    pub fn resolve(self, font_size: f32, normal: f32) -> f32 {
+        match self {
+            LineHeight::Normal => normal,
+            LineHeight::Multiplier(multiplier) => font_size * multiplier,
+            LineHeight::Pixels(pixels) => pixels,
+        }
+    }
+}
+
+/// What happens to text too wide for its container, from the `textOverflow` style.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TextOverflow {
+    /// Lines wrap at the container width
+    #[default]
+    Wrap,
+    /// Kept to one line, or to `maxLines`, and cut off at the container's edge
+    Clip,
+    /// Kept to one line, or to `maxLines`, with the last truncated with "…"
+    Ellipsis,
+}
+
+impl TextOverflow {
+    /// Parse `clip` or `ellipsis`.
+    pub fn parse(str: &str) -> Option<Self> {
+        match str {
+            "clip" => Some(TextOverflow::Clip),
+            "ellipsis" => Some(TextOverflow::Ellipsis),
+            _ => None,
+        }
+    }
+}
+
+/// Lines drawn with text, from the `textDecoration` style.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TextDecoration {
+    pub underline: bool,
+    pub line_through: bool,
+}
+
+impl TextDecoration {
+    /// Parse `none`, `underline`, `line-through` or both separated by a space.
+    pub fn parse(str: &str) -> Option<Self> {
+        let mut decoration = TextDecoration::default();
+
+        for line in str.split_whitespace() {
+            match line {
+                "none" => {}
+                "underline" => decoration.underline = true,
+                "line-through" => decoration.line_through = true,
+                _ => return None,
+            }
+        }
+
+        Some(decoration)
+    }
+
+    pub fn is_none(self) -> bool {
+        self == TextDecoration::default()
+    }
+}
+
+impl fmt::Display for TextDecoration {
+    /// As CSS, which `parse` reads back.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.underline, self.line_through) {
+            (false, false) => write!(f, "none"),
+            (true, false) => write!(f, "underline"),
+            (false, true) => write!(f, "line-through"),
+            (true, true) => write!(f, "underline line-through"),
+        }
+    }
+}
+
+/// How heavy text is, from the `fontWeight` style: 100 (thin) to 900 (black), as in CSS.
+/// Picks a face of a font family registered with `Renderer::add_font_face`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FontWeight(pub u16);
+
+impl FontWeight {
+    pub const NORMAL: FontWeight = FontWeight(400);
+    pub const BOLD: FontWeight = FontWeight(700);
+
+    /// Parse `normal`, `bold` or a number from 1 to 1000.
+    pub fn parse(str: &str) -> Option<Self> {
+        match str.trim() {
+            "normal" => Some(FontWeight::NORMAL),
+            "bold" => Some(FontWeight::BOLD),
+            number => number.parse().ok().and_then(Self::from_number),
+        }
+    }
+
+    pub fn from_number(number: f32) -> Option<Self> {
+        (1.0..=1000.0)
+            .contains(&number)
+            .then(|| FontWeight(number.round() as u16))
+    }
+}
+
+impl Default for FontWeight {
+    fn default() -> Self {
+        FontWeight::NORMAL
+    }
+}
+
+/// Variation axis values for variable fonts, from the `fontVariationSettings` style,
+/// sorted by axis tag. Weight (`wght`) and italic (`ital`) also follow `fontWeight` and
+/// `fontStyle` unless they're set here.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FontVariations(pub Vec<([u8; 4], f32)>);
+
+impl FontVariations {
+    /// Parse `normal`, CSS's `"'wght' 550, 'wdth' 90"`, or the same as a JSON object,
+    /// `{"wght": 550, "wdth": 90}`, as a map from JS arrives.
+    pub fn parse(str: &str) -> Option<Self> {
+        let str = str.trim();
+        let mut axes = Vec::new();
+
+        if str.starts_with('{') {
+            let map: serde_json::Map<String, serde_json::Value> = serde_json::from_str(str).ok()?;
+
+            for (tag, value) in map {
+                axes.push((axis_tag(&tag)?, value.as_f64()? as f32));
+            }
+        } else if str != "normal" {
+            for setting in str.split(',') {
+                let (tag, value) = setting.trim().split_once(char::is_whitespace)?;
+                let tag = tag
+                    .strip_prefix(['\'', '"'])
+                    .and_then(|tag| tag.strip_suffix(['\'', '"']))?;
+
+                axes.push((axis_tag(tag)?, value.trim().parse().ok()?));
+            }
+        }
+
+        // A later setting of the same axis wins, as in CSS
+        axes.reverse();
+        axes.sort_by_key(|(tag, _)| *tag);
+        axes.dedup_by_key(|(tag, _)| *tag);
+        Some(FontVariations(axes))
+    }
+
+    pub fn get(&self, tag: [u8; 4]) -> Option<f32> {
+        self.0
+            .iter()
+            .find(|(axis, _)| *axis == tag)
+            .map(|(_, value)| *value)
+    }
+}
+
+impl fmt::Display for FontVariations {
+    /// As CSS, which `parse` reads back.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "normal");
+        }
+
+        for (i, (tag, value)) in self.0.iter().enumerate() {
+            let separator = if i == 0 { "" } else { ", " };
+            write!(
+                f,
+                "{}'{}' {}",
+                separator,
+                String::from_utf8_lossy(tag),
+                value
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A four-letter axis tag like `wght`.
+fn axis_tag(tag: &str) -> Option<[u8; 4]> {
+    tag.as_bytes()
+        .try_into()
+        .ok()
+        .filter(|tag: &[u8; 4]| tag.iter().all(|c| (0x20..0x7F).contains(c)))
+}
+
+/// Upright or slanted text, from the `fontStyle` style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FontStyle {
+    #[default]
+    Normal,
+    Italic,
+}
+
+impl FontStyle {
+    /// Parse `normal` or `italic`. `oblique` is taken as `italic`, since both pick the
+    /// family's slanted face.
+    pub fn parse(str: &str) -> Option<Self> {
+        match str {
+            "normal" => Some(FontStyle::Normal),
+            "italic" | "oblique" => Some(FontStyle::Italic),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct InheritedStyle {
     pub color: RgbColor,
+    /// A font, or a family whose face is picked by `font_weight` and `font_style`
     pub font_name: String,
     pub font_size: f32,
+    pub font_weight: FontWeight,
+    pub font_style: FontStyle,
+    /// Axis values picking an instance of a variable font
+    pub font_variations: FontVariations,
     pub text_align: TextAlign,
+    pub line_height: LineHeight,
+    pub text_overflow: TextOverflow,
+    /// Most lines text wraps to before the rest is cut off, or 0 for no limit
+    pub max_lines: u32,
+    /// Extra space after each character, in pixels
+    pub letter_spacing: f32,
+    /// Distance between tab stops, in widths of a space
+    pub tab_size: f32,
+    pub direction: Direction,
+    pub text_decoration: TextDecoration,
 }
 
 impl InheritedStyle {
     pub fn new(default_font: &str) -> Self {
         InheritedStyle {
-            color: RgbColor {
-                r: 255,
-                g: 255,
-                b: 255,
-            },
+            color: RgbColor::rgb(255, 255, 255),
             font_name: default_font.to_string(),
             font_size: 24.0,
+            font_weight: FontWeight::NORMAL,
+            font_style: FontStyle::Normal,
+            font_variations: FontVariations::default(),
             text_align: TextAlign::default(),
+            line_height: LineHeight::default(),
+            text_overflow: TextOverflow::default(),
+            max_lines: 0,
+            letter_spacing: 0.0,
+            tab_size: 8.0,
+            direction: Direction::default(),
+            text_decoration: TextDecoration::default(),
         }
     }
 
@@ -38,7 +324,24 @@ impl InheritedStyle {
                 .clone()
                 .unwrap_or_else(|| self.font_name.clone()),
             font_size: overrides.font_size.unwrap_or(self.font_size),
-            text_align: overrides.text_align.unwrap_or(self.text_align),
+            font_weight: overrides.font_weight.unwrap_or(self.font_weight),
+            font_style: overrides.font_style.unwrap_or(self.font_style),
+            font_variations: overrides
+                .font_variations
+                .clone()
+                .unwrap_or_else(|| self.font_variations.clone()),
+            // Setting a direction aligns text to its start, as CSS's default `start` does
+            text_align: overrides
+                .text_align
+                .or(overrides.direction.map(Direction::start))
+                .unwrap_or(self.text_align),
+            line_height: overrides.line_height.unwrap_or(self.line_height),
+            text_overflow: overrides.text_overflow.unwrap_or(self.text_overflow),
+            max_lines: overrides.max_lines.unwrap_or(self.max_lines),
+            letter_spacing: overrides.letter_spacing.unwrap_or(self.letter_spacing),
+            tab_size: overrides.tab_size.unwrap_or(self.tab_size),
+            direction: overrides.direction.unwrap_or(self.direction),
+            text_decoration: overrides.text_decoration.unwrap_or(self.text_decoration),
         }
     }
 }
@@ -48,5 +351,15 @@ pub struct InheritedStyleOverrides {
     pub color: Option<RgbColor>,
     pub font_name: Option<String>,
     pub font_size: Option<f32>,
+    pub font_weight: Option<FontWeight>,
+    pub font_style: Option<FontStyle>,
+    pub font_variations: Option<FontVariations>,
     pub text_align: Option<TextAlign>,
+    pub line_height: Option<LineHeight>,
+    pub text_overflow: Option<TextOverflow>,
+    pub max_lines: Option<u32>,
+    pub letter_spacing: Option<f32>,
+    pub tab_size: Option<f32>,
+    pub direction: Option<Direction>,
+    pub text_decoration: Option<TextDecoration>,
 }
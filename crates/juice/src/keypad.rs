@@ -0,0 +1,128 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// Built-in on-screen keypads, chosen per input with the `inputMode` attribute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeypadLayout {
+    /// Digits, a decimal point and backspace
+    Numeric,
+    /// Digits, backspace and OK, which submits
+    Pin,
+}
+
+impl KeypadLayout {
+    /// Parse an `inputMode` value. `None` for modes without a built-in keypad, like `text`.
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "numeric" | "decimal" => Some(KeypadLayout::Numeric),
+            "pin" => Some(KeypadLayout::Pin),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeypadKey {
+    Char(char),
+    Backspace,
+    Enter,
+}
+
+impl KeypadKey {
+    pub fn label(&self) -> String {
+        match self {
+            KeypadKey::Char(c) => c.to_string(),
+            KeypadKey::Backspace => "Del".to_string(),
+            KeypadKey::Enter => "OK".to_string(),
+        }
+    }
+}
+
+const COLUMNS: usize = 3;
+const ROWS: usize = 4;
+
+/// Share of the screen height the keypad takes, docked at the bottom
+const HEIGHT_FRACTION: f32 = 0.4;
+
+/// A keypad shown while an input with a numeric or PIN `inputMode` is focused.
+#[derive(Debug, Clone)]
+pub struct Keypad {
+    /// Row by row, top left first
+    keys: Vec<KeypadKey>,
+    /// The key a press in progress started on, and whether it's still over it. The key
+    /// is drawn highlighted while it is, and fires if the press ends there.
+    pub pressed: Option<(usize, bool)>,
+}
+
+impl Keypad {
+    /// Lay out a keypad. With `shuffle`, the digits go in random positions so onlookers
+    /// and smudges can't give away a PIN; the other keys stay put.
+    pub fn new(layout: KeypadLayout, shuffle: bool) -> Self {
+        let mut digits: Vec<char> = "1234567890".chars().collect();
+
+        if shuffle {
+            shuffle_in_place(&mut digits);
+        }
+
+        let mut keys: Vec<KeypadKey> = digits.into_iter().map(KeypadKey::Char).collect();
+        let zero = keys.pop().unwrap();
+
+        match layout {
+            KeypadLayout::Numeric => {
+                keys.extend([KeypadKey::Char('.'), zero, KeypadKey::Backspace]);
+            }
+            KeypadLayout::Pin => {
+                keys.extend([KeypadKey::Backspace, zero, KeypadKey::Enter]);
+            }
+        }
+
+        Keypad {
+            keys,
+            pressed: None,
+        }
+    }
+
+    /// Where the keypad sits on a screen of the given size.
+    pub fn bounds(screen_width: f32, screen_height: f32) -> (f32, f32, f32, f32) {
+        let height = (screen_height * HEIGHT_FRACTION).floor();
+        (0.0, screen_height - height, screen_width, height)
+    }
+
+    /// Each key with its rect, within the keypad's bounds.
+    pub fn keys(
+        &self,
+        (x, y, w, h): (f32, f32, f32, f32),
+    ) -> impl Iterator<Item = (KeypadKey, (f32, f32, f32, f32))> + '_ {
+        let key_w = w / COLUMNS as f32;
+        let key_h = h / ROWS as f32;
+
+        self.keys.iter().enumerate().map(move |(i, key)| {
+            let column = (i % COLUMNS) as f32;
+            let row = (i / COLUMNS) as f32;
+            (*key, (x + column * key_w, y + row * key_h, key_w, key_h))
+        })
+    }
+
+    /// Index of the key at a point, if it's on the keypad.
+    pub fn key_at(&self, bounds: (f32, f32, f32, f32), px: f32, py: f32) -> Option<usize> {
+        self.keys(bounds)
+            .position(|(_, (x, y, w, h))| px >= x && px < x + w && py >= y && py < y + h)
+    }
+
+    pub fn key(&self, index: usize) -> Option<KeypadKey> {
+        self.keys.get(index).copied()
+    }
+}
+
+/// Fisher-Yates with a xorshift generator seeded from the OS-randomised hasher keys, which
+/// is unpredictable enough for key positions without pulling in an RNG crate.
+fn shuffle_in_place<T>(items: &mut [T]) {
+    let mut state = RandomState::new().build_hasher().finish() | 1;
+
+    for i in (1..items.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        items.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+}
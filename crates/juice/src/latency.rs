@@ -0,0 +1,209 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use rquickjs::{Ctx, IntoJs, Object, Value};
+
+/// Most finished samples kept for percentiles; older ones are dropped first
+const MAX_SAMPLES: usize = 1000;
+/// Frames rendered but not yet reported flushed, beyond which the oldest are forgotten,
+/// e.g. when the host never reports flushes
+const MAX_UNFLUSHED: usize = 64;
+
+/// Measures input-to-photon latency: from when the kernel timestamped an input event,
+/// through dispatching it and rendering the frame it changed, to the frame reaching the
+/// panel. Cloned handles share the same samples, so the thread flushing frames can
+/// report them.
+///
+/// Times are on the wall clock, which evdev timestamps events with by default. Events
+/// coalesced into one frame count from the earliest, and input that changes nothing on
+/// screen isn't counted.
+#[derive(Clone, Default)]
+pub struct LatencyRecorder {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// The earliest input since the last frame, and when it was dispatched
+    pending: Option<(SystemTime, Option<SystemTime>)>,
+    /// Tags of frames rendered from input, waiting to be flushed
+    unflushed: HashMap<u64, Stamps>,
+    next_tag: u64,
+    samples: VecDeque<Stamps>,
+}
+
+#[derive(Clone, Copy)]
+struct Stamps {
+    input: SystemTime,
+    dispatched: SystemTime,
+    rendered: SystemTime,
+    flushed: SystemTime,
+}
+
+impl LatencyRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An input event happened at `at`, as the kernel timestamped it.
+    pub fn input(&self, at: SystemTime) {
+        let mut inner = self.inner.lock().unwrap();
+
+        match &mut inner.pending {
+            Some((input, _)) => *input = (*input).min(at),
+            None => inner.pending = Some((at, None)),
+        }
+    }
+
+    /// The pending input has been handled, natively or by JS.
+    pub fn dispatched(&self) {
+        if let Some((_, dispatched @ None)) = &mut self.inner.lock().unwrap().pending {
+            *dispatched = Some(SystemTime::now());
+        }
+    }
+
+    /// A render pass finished, drawing a frame if `drew`. Returns the tag to report the
+    /// frame flushed with, if it shows pending input.
+    pub fn rendered(&self, drew: bool) -> Option<u64> {
+        let mut inner = self.inner.lock().unwrap();
+        let (input, dispatched) = inner.pending.take()?;
+
+        if !drew {
+            return None;
+        }
+
+        let now = SystemTime::now();
+        let tag = inner.next_tag;
+        inner.next_tag += 1;
+
+        if inner.unflushed.len() >= MAX_UNFLUSHED
+            && let Some(&oldest) = inner.unflushed.keys().min()
+        {
+            inner.unflushed.remove(&oldest);
+        }
+
+        inner.unflushed.insert(
+            tag,
+            Stamps {
+                input,
+                dispatched: dispatched.unwrap_or(now),
+                rendered: now,
+                flushed: now,
+            },
+        );
+
+        Some(tag)
+    }
+
+    /// The frame rendered with `tag` reached the panel at `at`. Frames merged into a later
+    /// one before flushing are reported with it.
+    pub fn flushed(&self, tag: u64, at: SystemTime) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some(mut stamps) = inner.unflushed.remove(&tag) {
+            stamps.flushed = at;
+
+            if inner.samples.len() >= MAX_SAMPLES {
+                inner.samples.pop_front();
+            }
+            inner.samples.push_back(stamps);
+        }
+    }
+
+    /// Percentiles over the most recent samples.
+    pub fn stats(&self) -> LatencyStats {
+        let inner = self.inner.lock().unwrap();
+        let stage = |from: fn(&Stamps) -> SystemTime, to: fn(&Stamps) -> SystemTime| {
+            Percentiles::of(
+                inner
+                    .samples
+                    .iter()
+                    .map(|stamps| to(stamps).duration_since(from(stamps)).unwrap_or_default())
+                    .collect(),
+            )
+        };
+
+        LatencyStats {
+            samples: inner.samples.len(),
+            dispatch: stage(|s| s.input, |s| s.dispatched),
+            render: stage(|s| s.dispatched, |s| s.rendered),
+            flush: stage(|s| s.rendered, |s| s.flushed),
+            total: stage(|s| s.input, |s| s.flushed),
+        }
+    }
+
+    /// Forget every sample, e.g. after changing a setting being tuned.
+    pub fn reset(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.samples.clear();
+        inner.unflushed.clear();
+        inner.pending = None;
+    }
+}
+
+/// Latency percentiles by stage, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LatencyStats {
+    pub samples: usize,
+    /// From the kernel's timestamp to the event being handled
+    pub dispatch: Percentiles,
+    /// From handling the event to the frame it changed being drawn
+    pub render: Percentiles,
+    /// From drawing the frame to it reaching the panel
+    pub flush: Percentiles,
+    /// Input to photon, end to end
+    pub total: Percentiles,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Percentiles {
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl Percentiles {
+    fn of(mut durations: Vec<Duration>) -> Self {
+        if durations.is_empty() {
+            return Percentiles::default();
+        }
+
+        durations.sort_unstable();
+        let at = |percentile: usize| {
+            let index = (durations.len() * percentile).div_ceil(100).max(1) - 1;
+            durations[index].as_secs_f64() * 1000.0
+        };
+
+        Percentiles {
+            p50: at(50),
+            p90: at(90),
+            p99: at(99),
+            max: at(100),
+        }
+    }
+}
+
+impl<'js> IntoJs<'js> for Percentiles {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        let percentiles = Object::new(ctx.clone())?;
+        percentiles.set("p50", self.p50)?;
+        percentiles.set("p90", self.p90)?;
+        percentiles.set("p99", self.p99)?;
+        percentiles.set("max", self.max)?;
+        Ok(percentiles.into_value())
+    }
+}
+
+impl<'js> IntoJs<'js> for LatencyStats {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        let stats = Object::new(ctx.clone())?;
+        stats.set("samples", self.samples)?;
+        stats.set("dispatch", self.dispatch)?;
+        stats.set("render", self.render)?;
+        stats.set("flush", self.flush)?;
+        stats.set("total", self.total)?;
+        Ok(stats.into_value())
+    }
+}
@@ -0,0 +1,800 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
+
+use fontdue::{Font, FontSettings};
+use image::imageops::FilterType;
+use rquickjs::function::Func;
+use rquickjs::{Ctx, IntoJs, Object, Value};
+use serde::Deserialize;
+
+use crate::canvas::{self, Canvas, RgbColor, TextRun};
+use crate::engine::JsModule;
+use crate::inherited_style::TextAlign;
+use crate::layers::LayerStack;
+use crate::pointer::{Corner, CornerTaps, Pointer};
+use crate::renderer::Renderer;
+
+/// The file describing an app in its bundle's directory
+pub const MANIFEST: &str = "manifest.json";
+
+/// How far a press has to travel sideways to turn the page, as a share of the width
+const SWIPE_FRACTION: f32 = 0.2;
+
+/// Settings for a `Launcher`, read from the host config. Every field but `dir` is
+/// optional in JSON.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LauncherConfig {
+    /// The folder of app bundles: a directory per app, holding a `manifest.json`
+    pub dir: PathBuf,
+    /// How often the folder is checked for apps added, removed or updated, in
+    /// milliseconds
+    #[serde(default = "default_rescan_ms")]
+    pub rescan_ms: u64,
+    /// Apps kept running in the background to switch back to. Past this, the one shown
+    /// longest ago is closed.
+    #[serde(default = "default_max_running")]
+    pub max_running: usize,
+    /// Width and height of the icons on the home grid
+    #[serde(default = "default_icon_size")]
+    pub icon_size: u32,
+    /// Any CSS color, behind the home grid
+    #[serde(default = "default_background")]
+    pub background: String,
+    /// Any CSS color, for the app names under the icons
+    #[serde(default = "default_color")]
+    pub color: String,
+    /// A TTF or OTF to write app names in. Without one, only the icons are drawn.
+    pub font: Option<PathBuf>,
+}
+
+fn default_rescan_ms() -> u64 {
+    2000
+}
+
+fn default_max_running() -> usize {
+    3
+}
+
+fn default_icon_size() -> u32 {
+    96
+}
+
+fn default_background() -> String {
+    "#101010".to_string()
+}
+
+fn default_color() -> String {
+    "#fff".to_string()
+}
+
+#[derive(Debug)]
+pub struct LauncherError {
+    pub message: String,
+}
+
+/// What an app bundle's `manifest.json` says about it. Paths are relative to the
+/// bundle's directory, and can't leave it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AppManifest {
+    pub name: String,
+    /// A PNG, JPEG or WebP for the home grid
+    pub icon: Option<PathBuf>,
+    /// The JS bundle to run
+    pub entry: PathBuf,
+    /// Native modules the app needs, like `fs`. It only gets those the host grants too.
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+/// An app found in the launcher's folder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppBundle {
+    /// The name of the bundle's directory, unique in the folder
+    pub id: String,
+    pub dir: PathBuf,
+    pub manifest: AppManifest,
+    /// When the entry and icon were last changed, to spot updated bundles
+    modified: (Option<SystemTime>, Option<SystemTime>),
+}
+
+impl AppBundle {
+    /// Read the bundle in `dir`, checking its manifest.
+    pub fn open(dir: &Path) -> Result<Self, LauncherError> {
+        let error = |message: String| LauncherError {
+            message: format!("{}: {}", dir.display(), message),
+        };
+
+        let id = dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| error("not a valid app id".to_string()))?
+            .to_string();
+        let json = std::fs::read_to_string(dir.join(MANIFEST))
+            .map_err(|err| error(format!("can't read {}: {}", MANIFEST, err)))?;
+        let manifest: AppManifest = serde_json::from_str(&json)
+            .map_err(|err| error(format!("invalid {}: {}", MANIFEST, err)))?;
+
+        let inside = |path: &Path| {
+            path.components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        };
+
+        if !inside(&manifest.entry) || manifest.icon.as_deref().is_some_and(|icon| !inside(icon)) {
+            return Err(error("paths must be inside the bundle".to_string()));
+        }
+
+        let modified = |path: &Path| {
+            std::fs::metadata(dir.join(path))
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        };
+
+        Ok(AppBundle {
+            modified: (
+                modified(&manifest.entry),
+                manifest.icon.as_deref().and_then(modified),
+            ),
+            id,
+            dir: dir.to_path_buf(),
+            manifest,
+        })
+    }
+
+    pub fn entry(&self) -> PathBuf {
+        self.dir.join(&self.manifest.entry)
+    }
+
+    pub fn icon(&self) -> Option<PathBuf> {
+        Some(self.dir.join(self.manifest.icon.as_ref()?))
+    }
+
+    /// Read the app's JS bundle.
+    pub fn load(&self) -> Result<String, LauncherError> {
+        std::fs::read_to_string(self.entry()).map_err(|err| LauncherError {
+            message: format!("Can't read the app {}: {}", self.id, err),
+        })
+    }
+}
+
+impl<'js> IntoJs<'js> for AppBundle {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        let app = Object::new(ctx.clone())?;
+        app.set("id", self.id.as_str())?;
+        app.set("name", self.manifest.name.as_str())?;
+        Ok(app.into_value())
+    }
+}
+
+/// Every app bundle in `dir`, by name. Bundles that can't be read are left out, with
+/// why in the errors.
+pub fn scan(dir: &Path) -> (Vec<AppBundle>, Vec<LauncherError>) {
+    let mut apps = Vec::new();
+    let mut errors = Vec::new();
+
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            errors.push(LauncherError {
+                message: format!("Can't read the app folder {}: {}", dir.display(), err),
+            });
+            return (apps, errors);
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if !path.is_dir() {
+            continue;
+        }
+
+        match AppBundle::open(&path) {
+            Ok(app) => apps.push(app),
+            Err(err) => errors.push(err),
+        }
+    }
+
+    apps.sort_by(|a, b| {
+        (a.manifest.name.to_lowercase(), &a.id).cmp(&(b.manifest.name.to_lowercase(), &b.id))
+    });
+
+    (apps, errors)
+}
+
+/// Where the tiles of the home grid go.
+#[derive(Debug, Clone, Copy)]
+pub struct HomeLayout {
+    pub columns: usize,
+    pub rows: usize,
+    /// Width and height of a tile: an icon with its name under it
+    pub tile: (f32, f32),
+    /// Top left of the first tile
+    pub origin: (f32, f32),
+    pub font_size: f32,
+    /// Height of the page dots along the bottom
+    pub dots: f32,
+}
+
+impl HomeLayout {
+    pub fn new(width: f32, height: f32, icon_size: f32) -> Self {
+        let font_size = (icon_size / 6.0).clamp(10.0, 20.0).round();
+        let dots = (font_size * 1.5).round();
+        let tile = (icon_size * 1.5, icon_size + font_size * 2.5);
+        let columns = ((width / tile.0) as usize).max(1);
+        let rows = (((height - dots) / tile.1) as usize).max(1);
+
+        HomeLayout {
+            columns,
+            rows,
+            tile,
+            origin: (
+                ((width - columns as f32 * tile.0) / 2.0).max(0.0),
+                ((height - dots - rows as f32 * tile.1) / 2.0).max(0.0),
+            ),
+            font_size,
+            dots,
+        }
+    }
+
+    pub fn per_page(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    /// The index on its page of the tile at a point, if any.
+    pub fn tile_at(&self, x: f32, y: f32) -> Option<usize> {
+        let column = (x - self.origin.0) / self.tile.0;
+        let row = (y - self.origin.1) / self.tile.1;
+
+        (column >= 0.0 && row >= 0.0 && column < self.columns as f32 && row < self.rows as f32)
+            .then(|| row as usize * self.columns + column as usize)
+    }
+
+    /// (x, y, w, h) of the tile at an index on its page.
+    pub fn tile_rect(&self, index: usize) -> (f32, f32, f32, f32) {
+        let (column, row) = (index % self.columns, index / self.columns);
+
+        (
+            self.origin.0 + column as f32 * self.tile.0,
+            self.origin.1 + row as f32 * self.tile.1,
+            self.tile.0,
+            self.tile.1,
+        )
+    }
+}
+
+/// What the home grid or JS asked the launcher to do at its next tick
+enum Request {
+    Home,
+    Launch(String),
+}
+
+struct LauncherState {
+    config: LauncherConfig,
+    apps: Vec<AppBundle>,
+    /// Icons scaled to fit `icon_size`, as RGBA with their width and height, by app id
+    icons: HashMap<String, (Vec<u8>, u32, u32)>,
+    font: Option<Font>,
+    layout: HomeLayout,
+    /// The home grid as last drawn
+    home: Canvas,
+    /// Whether the home grid needs drawing again
+    dirty: bool,
+    page: usize,
+    /// Running apps' ids, the one shown longest ago first
+    running: Vec<String>,
+    /// The app shown, or `None` for the home grid
+    active: Option<String>,
+    /// Whether the stack is set up for what's shown
+    shown: bool,
+    request: Option<Request>,
+    /// An app asked for that isn't running, for the host to start
+    launch: Option<AppBundle>,
+    /// Where a press on the home grid started
+    press: Option<(f32, f32)>,
+    home_taps: CornerTaps,
+    scanned: Instant,
+}
+
+impl LauncherState {
+    fn set_apps(&mut self, apps: Vec<AppBundle>) {
+        let width = self.config.icon_size;
+
+        self.icons.clear();
+
+        for app in &apps {
+            let Some(path) = app.icon() else {
+                continue;
+            };
+
+            match image::open(&path) {
+                Ok(icon) => {
+                    let icon = icon.resize(width, width, FilterType::Triangle).to_rgba8();
+                    let (w, h) = icon.dimensions();
+                    self.icons.insert(app.id.clone(), (icon.into_raw(), w, h));
+                }
+                Err(err) => eprintln!("Can't load the icon {}: {}", path.display(), err),
+            }
+        }
+
+        self.apps = apps;
+        self.page = self.page.min(self.pages() - 1);
+        self.dirty = true;
+    }
+
+    /// Show the active app's layer and hide the others, or set up for the home grid, if
+    /// that's changed.
+    fn show(&mut self, stack: &mut LayerStack) {
+        if self.shown {
+            return;
+        }
+
+        self.shown = true;
+
+        for id in &self.running {
+            stack.set_visible(id, self.active.as_ref() == Some(id));
+        }
+
+        match &self.active {
+            Some(id) => {
+                stack.set_backdrop(None);
+
+                // Apps cover the whole screen whatever layer they're on, so the home grid
+                // never shows through where they don't draw
+                if let Some(renderer) = stack.layer_mut(id) {
+                    renderer.set_transparent(false);
+                }
+            }
+            None => self.dirty = true,
+        }
+    }
+
+    fn pages(&self) -> usize {
+        self.apps.len().div_ceil(self.layout.per_page()).max(1)
+    }
+
+    fn turn_page(&mut self, by: isize) {
+        let page = self.page.saturating_add_signed(by).min(self.pages() - 1);
+
+        if page != self.page {
+            self.page = page;
+            self.dirty = true;
+        }
+    }
+
+    fn draw_home(&mut self) {
+        let background = RgbColor::from_string(&self.config.background)
+            .unwrap_or(RgbColor::rgb(0x10, 0x10, 0x10));
+        let color =
+            RgbColor::from_string(&self.config.color).unwrap_or(RgbColor::rgb(0xff, 0xff, 0xff));
+        let placeholder = RgbColor::rgb(0x40, 0x40, 0x40);
+        let layout = self.layout;
+        let icon_size = self.config.icon_size as f32;
+        let pages = self.pages();
+        let canvas = &mut self.home;
+
+        canvas.clear(background);
+
+        let per_page = layout.per_page();
+        let start = self.page * per_page;
+
+        for (index, app) in self.apps.iter().skip(start).take(per_page).enumerate() {
+            let (x, y, w, _) = layout.tile_rect(index);
+            let icon_x = x + (w - icon_size) / 2.0;
+            let icon_y = y + layout.font_size / 2.0;
+
+            match self.icons.get(&app.id) {
+                Some((pixels, icon_w, icon_h)) => canvas.blit_rgba(
+                    pixels,
+                    *icon_w,
+                    *icon_h,
+                    (icon_x + (icon_size - *icon_w as f32) / 2.0) as i32,
+                    (icon_y + (icon_size - *icon_h as f32) / 2.0) as i32,
+                ),
+                None => canvas.tint(
+                    (icon_x, icon_y, icon_size, icon_size),
+                    icon_size / 5.0,
+                    None,
+                    placeholder,
+                    255,
+                ),
+            }
+
+            let Some(font) = &self.font else {
+                continue;
+            };
+
+            let fonts = [font];
+            let name = &app.manifest.name;
+            let label = canvas::truncate_with_ellipsis(&fonts, name, layout.font_size, 0.0, w)
+                .unwrap_or_else(|| name.clone());
+
+            canvas.draw_text(
+                &TextRun {
+                    align: TextAlign::Center,
+                    ..TextRun::new(&fonts, layout.font_size, color)
+                },
+                &label,
+                x,
+                icon_y + icon_size + layout.font_size / 2.0,
+                None,
+                w,
+            );
+        }
+
+        if pages > 1 {
+            let radius = layout.dots / 6.0;
+            let gap = radius * 4.0;
+            let left = (canvas.width as f32 - (pages - 1) as f32 * gap) / 2.0;
+            let y = canvas.height as f32 - layout.dots / 2.0;
+
+            for page in 0..pages {
+                let dot = if page == self.page {
+                    color
+                } else {
+                    placeholder
+                };
+                canvas.fill_circle((left + page as f32 * gap, y), radius, dot);
+            }
+        }
+
+        self.dirty = false;
+    }
+}
+
+/// A minimal kiosk shell over a `LayerStack`: a home grid of the app bundles in a
+/// folder, drawn natively under the stack's layers, that starts apps as layers and
+/// switches between them. Apps go under any layers the host pushed, so system layers
+/// like a lock screen stay on top. The folder is watched, so apps added or removed show
+/// up on the grid and running apps reload when their bundle changes.
+///
+/// Tapping an app asks the host to start it, through `take_launch` and `open`, since
+/// the host owns what apps are given: fonts, modules and data. Apps switched away from
+/// keep running hidden, up to `max_running`. Five taps in the bottom-left corner go
+/// back home, as does the JS `launcher.home()`; register a clone with each app's
+/// renderer to give it the `launcher` global.
+#[derive(Clone)]
+pub struct Launcher {
+    state: Rc<RefCell<LauncherState>>,
+}
+
+impl Launcher {
+    /// Scan the folder for apps, for a `width` by `height` stack. Bundles that can't be
+    /// read are left off the grid, with why in the errors.
+    pub fn new(
+        config: LauncherConfig,
+        width: u32,
+        height: u32,
+    ) -> Result<(Self, Vec<LauncherError>), LauncherError> {
+        let font = match &config.font {
+            Some(path) => {
+                let data = std::fs::read(path).map_err(|err| LauncherError {
+                    message: format!("Can't read the launcher font {}: {}", path.display(), err),
+                })?;
+                let font = Font::from_bytes(data, FontSettings::default()).map_err(|err| {
+                    LauncherError {
+                        message: format!("Invalid launcher font {}: {}", path.display(), err),
+                    }
+                })?;
+                Some(font)
+            }
+            None => None,
+        };
+
+        let (apps, errors) = scan(&config.dir);
+        let layout = HomeLayout::new(width as f32, height as f32, config.icon_size as f32);
+
+        let mut state = LauncherState {
+            config,
+            apps: Vec::new(),
+            icons: HashMap::new(),
+            font,
+            layout,
+            home: Canvas::new(width, height),
+            dirty: true,
+            page: 0,
+            running: Vec::new(),
+            active: None,
+            shown: false,
+            request: None,
+            launch: None,
+            press: None,
+            home_taps: CornerTaps::new(Corner::BottomLeft),
+            scanned: Instant::now(),
+        };
+        state.set_apps(apps);
+
+        Ok((
+            Launcher {
+                state: Rc::new(RefCell::new(state)),
+            },
+            errors,
+        ))
+    }
+
+    pub fn apps(&self) -> Vec<AppBundle> {
+        self.state.borrow().apps.clone()
+    }
+
+    /// The app shown, or `None` for the home grid.
+    pub fn active(&self) -> Option<String> {
+        self.state.borrow().active.clone()
+    }
+
+    /// Show an app at the next tick, starting it if it isn't running. Returns whether
+    /// there's an app with that id.
+    pub fn launch(&self, id: &str) -> bool {
+        let mut state = self.state.borrow_mut();
+        let found = state.apps.iter().any(|app| app.id == id);
+
+        if found {
+            state.request = Some(Request::Launch(id.to_string()));
+        }
+
+        found
+    }
+
+    /// Go back to the home grid at the next tick, e.g. from a hardware button.
+    pub fn home(&self) {
+        self.state.borrow_mut().request = Some(Request::Home);
+    }
+
+    /// An app to start, asked for since the last call. Load `AppBundle::load` into a new
+    /// renderer the stack's size and hand it to `open`.
+    pub fn take_launch(&self) -> Option<AppBundle> {
+        self.state.borrow_mut().launch.take()
+    }
+
+    /// Add a started app to the stack and show it, closing the app shown longest ago if
+    /// more than `max_running` are running.
+    pub fn open(&self, stack: &mut LayerStack, app: &AppBundle, renderer: Renderer) {
+        let mut state = self.state.borrow_mut();
+        state.running.retain(|id| *id != app.id);
+        stack.remove(&app.id);
+
+        // Running apps are the bottom layers, so the newest goes above them
+        stack.insert(state.running.len(), &app.id, renderer);
+        state.running.push(app.id.clone());
+
+        while state.running.len() > state.config.max_running.max(1) {
+            let closed = state.running.remove(0);
+            stack.remove(&closed);
+        }
+
+        state.active = Some(app.id.clone());
+        state.shown = false;
+        state.show(stack);
+    }
+
+    /// Close a running app, going home if it's shown.
+    pub fn close(&self, stack: &mut LayerStack, id: &str) {
+        let mut state = self.state.borrow_mut();
+
+        if let Some(index) = state.running.iter().position(|running| running == id) {
+            state.running.remove(index);
+            stack.remove(id);
+        }
+
+        if state.active.as_deref() == Some(id) {
+            state.active = None;
+            state.shown = false;
+            state.show(stack);
+        }
+    }
+
+    /// Rescan the folder when due, and carry out requests to switch apps. Call before the
+    /// stack's own `tick`.
+    pub async fn tick(&self, stack: &mut LayerStack) {
+        let reloads = self.rescan(stack);
+
+        for (id, js) in reloads {
+            if let Some(renderer) = stack.layer_mut(&id) {
+                renderer.reload(&js).await;
+            }
+        }
+
+        let mut state = self.state.borrow_mut();
+
+        match state.request.take() {
+            Some(Request::Home) if state.active.is_some() => {
+                state.active = None;
+                state.shown = false;
+            }
+            Some(Request::Launch(id)) if state.running.contains(&id) => {
+                state.running.retain(|running| *running != id);
+                state.running.push(id.clone());
+                state.active = Some(id);
+                state.shown = false;
+            }
+            Some(Request::Launch(id)) => {
+                state.launch = state.apps.iter().find(|app| app.id == id).cloned();
+            }
+            _ => {}
+        }
+
+        state.show(stack);
+    }
+
+    /// Check the folder for changes if it's been `rescan_ms` since the last check,
+    /// closing apps that were removed. Returns the running apps whose bundles changed,
+    /// with their new JS.
+    fn rescan(&self, stack: &mut LayerStack) -> Vec<(String, String)> {
+        let mut state = self.state.borrow_mut();
+
+        if state.scanned.elapsed() < Duration::from_millis(state.config.rescan_ms) {
+            return Vec::new();
+        }
+
+        state.scanned = Instant::now();
+
+        let (apps, errors) = scan(&state.config.dir);
+
+        if apps == state.apps {
+            return Vec::new();
+        }
+
+        for err in errors {
+            eprintln!("Launcher: {}", err.message);
+        }
+
+        let mut reloads = Vec::new();
+
+        for id in state.running.clone() {
+            let old = state.apps.iter().find(|app| app.id == id);
+
+            match apps.iter().find(|app| app.id == id) {
+                Some(app) if old.is_some_and(|old| old.modified.0 != app.modified.0) => {
+                    match app.load() {
+                        Ok(js) => reloads.push((id, js)),
+                        Err(err) => eprintln!("Launcher: {}", err.message),
+                    }
+                }
+                Some(_) => {}
+                None => {
+                    state.running.retain(|running| *running != id);
+                    stack.remove(&id);
+
+                    if state.active.as_ref() == Some(&id) {
+                        state.active = None;
+                        state.shown = false;
+                    }
+                }
+            }
+        }
+
+        state.set_apps(apps);
+        reloads
+    }
+
+    /// Draw the home grid if it's shown and changed, then render the stack. Returns
+    /// whether anything was composited, as `LayerStack::render` does.
+    pub fn render(&self, stack: &mut LayerStack) -> bool {
+        let mut state = self.state.borrow_mut();
+
+        if state.active.is_none() && state.dirty {
+            state.draw_home();
+            stack.set_backdrop(Some(state.home.pixels.clone()));
+        }
+
+        stack.render()
+    }
+
+    /// Send a pointer event to the stack, or to the home grid when it's shown and no
+    /// layer takes the event. Counts taps towards the gesture going home.
+    pub async fn dispatch_pointer_event(
+        &self,
+        stack: &mut LayerStack,
+        event_name: &str,
+        x: f32,
+        y: f32,
+        pointer: Pointer,
+    ) {
+        let on_home = {
+            let mut state = self.state.borrow_mut();
+            let (width, height) = (state.home.width as f32, state.home.height as f32);
+
+            if event_name == "PressIn"
+                && state.active.is_some()
+                && state.home_taps.press(x, y, width, height)
+            {
+                state.request = Some(Request::Home);
+                return;
+            }
+
+            state.active.is_none() && (state.press.is_some() || !stack.takes_input_at(x, y))
+        };
+
+        if !on_home {
+            stack
+                .dispatch_pointer_event(event_name, x, y, pointer)
+                .await;
+            return;
+        }
+
+        let mut state = self.state.borrow_mut();
+
+        match event_name {
+            "PressIn" => state.press = Some((x, y)),
+            "PressOut" => {
+                let Some((start_x, start_y)) = state.press.take() else {
+                    return;
+                };
+
+                let swipe = x - start_x;
+
+                if swipe.abs() >= state.home.width as f32 * SWIPE_FRACTION {
+                    state.turn_page(if swipe < 0.0 { 1 } else { -1 });
+                    return;
+                }
+
+                let tile = state.layout.tile_at(start_x, start_y);
+
+                if tile.is_some() && tile == state.layout.tile_at(x, y) {
+                    let index = state.page * state.layout.per_page() + tile.unwrap_or(0);
+
+                    if let Some(app) = state.apps.get(index) {
+                        state.request = Some(Request::Launch(app.id.clone()));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Send the wheel to the stack, or turn the home grid's page with it.
+    pub async fn dispatch_wheel(
+        &self,
+        stack: &mut LayerStack,
+        x: f32,
+        y: f32,
+        notches_x: f32,
+        notches_y: f32,
+    ) {
+        let on_home = {
+            let state = self.state.borrow();
+            state.active.is_none() && !stack.takes_input_at(x, y)
+        };
+
+        if on_home {
+            let notches = if notches_x != 0.0 {
+                notches_x
+            } else {
+                notches_y
+            };
+            self.state.borrow_mut().turn_page(notches.signum() as isize);
+        } else {
+            stack.dispatch_wheel(x, y, notches_x, notches_y).await;
+        }
+    }
+}
+
+impl JsModule for Launcher {
+    fn register(&self, ctx: &Ctx<'_>) {
+        let launcher = Object::new(ctx.clone()).unwrap();
+
+        let state = self.state.clone();
+        launcher
+            .set("apps", Func::from(move || state.borrow().apps.clone()))
+            .unwrap();
+
+        let this = self.clone();
+        launcher
+            .set("launch", Func::from(move |id: String| this.launch(&id)))
+            .unwrap();
+
+        let this = self.clone();
+        launcher
+            .set("home", Func::from(move || this.home()))
+            .unwrap();
+
+        let state = self.state.clone();
+        launcher
+            .set("current", Func::from(move || state.borrow().active.clone()))
+            .unwrap();
+
+        ctx.globals().set("launcher", launcher).unwrap();
+    }
+}
@@ -0,0 +1,303 @@
+use crate::{
+    canvas::Canvas,
+    damage::{DamageRect, DamageTracker},
+    dom::SurfaceRect,
+    pointer::Pointer,
+    renderer::Renderer,
+};
+
+/// One app or overlay in a `LayerStack`, with its own engine, bundle and DOM.
+pub struct Layer {
+    pub name: String,
+    pub renderer: Renderer,
+    pub visible: bool,
+}
+
+/// A stack of independent renderers composited bottom to top, so a system layer (OTA
+/// progress, error banners) can sit above whichever app is running. Layers above the
+/// first are cleared to transparent before they render, so only what they draw covers
+/// the layers below.
+///
+/// Presses go to the topmost visible layer that drew something under them, and stay
+/// with it until released, unless a layer has grabbed input.
+pub struct LayerStack {
+    pub canvas: Canvas,
+    layers: Vec<Layer>,
+    damage_tracker: DamageTracker,
+    damage: Vec<DamageRect>,
+    /// Set when layers are added, removed, shown or hidden, to composite on the next render
+    changed: bool,
+    /// The layer the current press started on
+    pressed: Option<usize>,
+    /// The layer taking all input, see `set_input_grab`
+    grab: Option<usize>,
+    /// Pixels under the layers, see `set_backdrop`
+    backdrop: Option<Vec<u32>>,
+}
+
+impl LayerStack {
+    pub fn new(width: u32, height: u32) -> Self {
+        LayerStack {
+            canvas: Canvas::new(width, height),
+            layers: Vec::new(),
+            damage_tracker: DamageTracker::default(),
+            damage: Vec::new(),
+            changed: true,
+            pressed: None,
+            grab: None,
+            backdrop: None,
+        }
+    }
+
+    /// Add a layer above the others. Its renderer's canvas should be the stack's size.
+    pub fn push(&mut self, name: &str, renderer: Renderer) {
+        self.insert(self.layers.len(), name, renderer);
+    }
+
+    /// Add a layer at `index` from the bottom, under the layers from there up, e.g. an app
+    /// going under system layers that must stay on top.
+    pub fn insert(&mut self, index: usize, name: &str, mut renderer: Renderer) {
+        let index = index.min(self.layers.len());
+
+        if index == 0
+            && let Some(base) = self.layers.first_mut()
+        {
+            base.renderer.set_transparent(true);
+        }
+
+        renderer.set_transparent(index > 0 || self.backdrop.is_some());
+        self.layers.insert(
+            index,
+            Layer {
+                name: name.to_string(),
+                renderer,
+                visible: true,
+            },
+        );
+
+        self.pressed = None;
+        self.changed = true;
+        self.grab = self
+            .grab
+            .map(|grab| if grab >= index { grab + 1 } else { grab });
+    }
+
+    /// Take a layer out of the stack, returning its renderer.
+    pub fn remove(&mut self, name: &str) -> Option<Renderer> {
+        let index = self.index(name)?;
+
+        self.pressed = None;
+        self.changed = true;
+        self.grab = match self.grab {
+            Some(grab) if grab == index => None,
+            Some(grab) if grab > index => Some(grab - 1),
+            grab => grab,
+        };
+
+        let renderer = self.layers.remove(index).renderer;
+
+        if let Some(base) = self.layers.first_mut() {
+            base.renderer.set_transparent(self.backdrop.is_some());
+        }
+
+        Some(renderer)
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&Renderer> {
+        Some(&self.layers[self.index(name)?].renderer)
+    }
+
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut Renderer> {
+        let index = self.index(name)?;
+        Some(&mut self.layers[index].renderer)
+    }
+
+    pub fn layers_mut(&mut self) -> impl Iterator<Item = &mut Layer> {
+        self.layers.iter_mut()
+    }
+
+    /// Show or hide a layer. Hidden layers keep running but aren't drawn or sent input.
+    pub fn set_visible(&mut self, name: &str, visible: bool) {
+        if let Some(index) = self.index(name)
+            && self.layers[index].visible != visible
+        {
+            self.layers[index].visible = visible;
+            self.changed = true;
+
+            if self.pressed == Some(index) {
+                self.pressed = None;
+            }
+        }
+    }
+
+    /// Send all pointer, wheel and key input to one layer, whatever is drawn where, so a
+    /// system dialog or lock screen can't be bypassed by pressing the app beneath it.
+    /// `None` routes input normally again. The grab is dropped if the layer is removed.
+    pub fn set_input_grab(&mut self, name: Option<&str>) {
+        self.grab = name.and_then(|name| self.index(name));
+        self.pressed = None;
+    }
+
+    /// Draw `pixels` under the layers instead of black, e.g. a screen drawn natively
+    /// rather than by a renderer. They should be the canvas's size; `None` goes back to
+    /// black. The first layer is transparent too while there's a backdrop.
+    pub fn set_backdrop(&mut self, pixels: Option<Vec<u32>>) {
+        if pixels.is_some() != self.backdrop.is_some()
+            && let Some(base) = self.layers.first_mut()
+        {
+            base.renderer.set_transparent(pixels.is_some());
+        }
+
+        self.backdrop = pixels;
+        self.changed = true;
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name == name)
+    }
+
+    pub async fn tick(&mut self) {
+        for layer in &mut self.layers {
+            layer.renderer.tick().await;
+        }
+    }
+
+    /// Render every layer that needs it and composite them. Returns whether anything was
+    /// composited; `damage()` then lists what changed.
+    pub fn render(&mut self) -> bool {
+        let mut rendered = std::mem::take(&mut self.changed);
+
+        for layer in &mut self.layers {
+            // Hidden layers still render, so they're up to date when shown
+            rendered |= layer.renderer.render() && layer.visible;
+        }
+
+        if !rendered {
+            return false;
+        }
+
+        self.composite();
+        self.damage = self.damage_tracker.diff(&self.canvas);
+        true
+    }
+
+    /// Draw the visible layers over each other, skipping pixels a layer left transparent.
+    fn composite(&mut self) {
+        match &self.backdrop {
+            Some(backdrop) if backdrop.len() == self.canvas.pixels.len() => {
+                self.canvas.pixels.copy_from_slice(backdrop);
+            }
+            _ => self.canvas.pixels.fill(0xFF00_0000),
+        }
+
+        for layer in self.layers.iter().filter(|layer| layer.visible) {
+            let source = &layer.renderer.canvas;
+
+            if (source.width, source.height) != (self.canvas.width, self.canvas.height) {
+                continue;
+            }
+
+            for (pixel, &drawn) in self.canvas.pixels.iter_mut().zip(&source.pixels) {
+                if drawn >> 24 != 0 {
+                    *pixel = drawn;
+                }
+            }
+        }
+    }
+
+    /// Regions of the composited canvas that changed in the last render.
+    pub fn damage(&self) -> &[DamageRect] {
+        &self.damage
+    }
+
+    /// Every `<surface>` in the visible layers, bottom layer first.
+    pub fn surfaces(&self) -> impl Iterator<Item = &SurfaceRect> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.visible)
+            .flat_map(|layer| layer.renderer.surfaces())
+    }
+
+    /// The topmost visible layer that drew something at a point.
+    fn layer_at_point(&self, x: f32, y: f32) -> Option<usize> {
+        if x < 0.0 || y < 0.0 || x >= self.canvas.width as f32 || y >= self.canvas.height as f32 {
+            return None;
+        }
+
+        let offset = y as usize * self.canvas.width as usize + x as usize;
+
+        self.layers.iter().rposition(|layer| {
+            layer.visible
+                && layer
+                    .renderer
+                    .canvas
+                    .pixels
+                    .get(offset)
+                    .is_some_and(|pixel| pixel >> 24 != 0)
+        })
+    }
+
+    /// Whether input at a point goes to a layer, rather than to whatever is under the
+    /// stack.
+    pub fn takes_input_at(&self, x: f32, y: f32) -> bool {
+        self.grab.is_some() || self.pressed.is_some() || self.layer_at_point(x, y).is_some()
+    }
+
+    pub async fn dispatch_xy_event(&mut self, event_name: &str, x: f32, y: f32) {
+        self.dispatch_pointer_event(event_name, x, y, Pointer::default())
+            .await;
+    }
+
+    /// Send a press event to the layer it's over. Moves and releases go to the layer the
+    /// press started on, even if they stray over another.
+    pub async fn dispatch_pointer_event(
+        &mut self,
+        event_name: &str,
+        x: f32,
+        y: f32,
+        pointer: Pointer,
+    ) {
+        let index = match event_name {
+            _ if self.grab.is_some() => self.grab,
+            "PressIn" => {
+                self.pressed = self.layer_at_point(x, y);
+                self.pressed
+            }
+            "PressOut" => self.pressed.take(),
+            _ => self.pressed.or_else(|| self.layer_at_point(x, y)),
+        };
+
+        if let Some(layer) = index.and_then(|index| self.layers.get_mut(index)) {
+            layer
+                .renderer
+                .dispatch_pointer_event(event_name, x, y, pointer)
+                .await;
+        }
+    }
+
+    pub async fn dispatch_wheel(&mut self, x: f32, y: f32, notches_x: f32, notches_y: f32) {
+        if let Some(index) = self.grab.or_else(|| self.layer_at_point(x, y)) {
+            self.layers[index]
+                .renderer
+                .dispatch_wheel(x, y, notches_x, notches_y)
+                .await;
+        }
+    }
+
+    /// The renderer keys and typed text should go to: the grabbing layer, or the topmost
+    /// visible layer with a focused input, or else the topmost visible layer.
+    pub fn focused_mut(&mut self) -> Option<&mut Renderer> {
+        if let Some(grab) = self.grab {
+            return Some(&mut self.layers[grab].renderer);
+        }
+
+        let visible = || self.layers.iter().enumerate().filter(|(_, l)| l.visible);
+        let index = visible()
+            .filter(|(_, layer)| layer.renderer.focused_input().is_some())
+            .map(|(index, _)| index)
+            .next_back()
+            .or_else(|| visible().map(|(index, _)| index).next_back())?;
+
+        Some(&mut self.layers[index].renderer)
+    }
+}
@@ -1,6 +1,66 @@
+pub mod a11y;
+pub mod animation;
+pub mod bidi;
+pub mod binding;
+pub mod calibration;
+pub mod capabilities;
 pub mod canvas;
+pub mod chart;
+pub mod clock;
+pub mod color;
+pub mod damage;
+pub mod decoder;
+pub mod decoration;
+pub mod diagnostics;
+pub mod display_filter;
 pub mod dom;
+pub mod dom_json;
+pub mod emoji;
 pub mod engine;
+pub mod font_fallback;
+pub mod fs;
+pub mod gauge;
+pub mod glyph_cache;
+pub mod grid;
 pub mod inherited_style;
+pub mod keypad;
+pub mod latency;
+pub mod layers;
+#[cfg(feature = "launcher")]
+pub mod launcher;
+#[cfg(feature = "lock")]
+pub mod lock;
+pub mod log;
+#[cfg(feature = "lottie")]
+pub mod lottie;
+pub mod markdown;
+pub mod memory;
+pub mod metrics;
+pub mod mono;
+pub mod permissions;
+pub mod picker;
+pub mod pointer;
+pub mod press_effect;
+pub mod print;
 pub mod renderer;
+#[cfg(feature = "sandbox")]
+pub mod sandbox;
+pub mod scanner;
+pub mod schema;
+pub mod scroll;
+pub mod shadow;
+#[cfg(feature = "shaping")]
+pub mod shaping;
+pub mod skeleton;
+pub mod splash;
+pub mod sprite;
+pub mod state_style;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod table;
+pub mod text_input;
+pub mod theme;
 pub mod timers;
+pub mod transform;
+pub mod variable_font;
+pub mod video;
@@ -0,0 +1,244 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use rquickjs::function::Func;
+use rquickjs::{Ctx, IntoJs, Object, Value};
+use zeroize::Zeroize;
+
+use crate::engine::JsModule;
+use crate::layers::LayerStack;
+
+/// Settings for a `LockScreen`.
+#[derive(Debug, Clone)]
+pub struct LockConfig {
+    /// How long without input before the screen locks. `None` only locks when asked to.
+    pub timeout: Option<Duration>,
+    /// Wrong PINs allowed before entry is locked out
+    pub attempts: u32,
+    /// How long the first lockout lasts. Each wrong PIN after it doubles the wait, up to
+    /// `max_lockout`.
+    pub lockout: Duration,
+    pub max_lockout: Duration,
+}
+
+impl Default for LockConfig {
+    fn default() -> Self {
+        LockConfig {
+            timeout: Some(Duration::from_secs(5 * 60)),
+            attempts: 3,
+            lockout: Duration::from_secs(30),
+            max_lockout: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+/// Host callback checking an entered PIN, e.g. against a hash in secure storage.
+pub type PinVerifier = Box<dyn FnMut(&str) -> bool>;
+
+/// The outcome of submitting a PIN.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Attempt {
+    Unlocked,
+    Wrong,
+    /// Too many wrong PINs; the PIN wasn't checked
+    LockedOut,
+}
+
+impl Attempt {
+    fn as_str(self) -> &'static str {
+        match self {
+            Attempt::Unlocked => "unlocked",
+            Attempt::Wrong => "wrong",
+            Attempt::LockedOut => "lockedOut",
+        }
+    }
+}
+
+/// Where a `LockScreen` is up to, for its UI to show.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LockStatus {
+    pub locked: bool,
+    /// Wrong PINs since the last unlock
+    pub failures: u32,
+    /// How long until a PIN can be tried again, or zero
+    pub retry_in: Duration,
+}
+
+impl<'js> IntoJs<'js> for LockStatus {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        let status = Object::new(ctx.clone())?;
+        status.set("locked", self.locked)?;
+        status.set("failures", self.failures)?;
+        status.set("retryIn", self.retry_in.as_secs_f64())?;
+        Ok(status.into_value())
+    }
+}
+
+struct LockState {
+    config: LockConfig,
+    verify: PinVerifier,
+    locked: bool,
+    last_input: Instant,
+    /// Wrong PINs since the last unlock
+    failures: u32,
+    locked_out_until: Option<Instant>,
+}
+
+impl LockState {
+    fn submit(&mut self, pin: &str, now: Instant) -> Attempt {
+        if !self.locked {
+            return Attempt::Unlocked;
+        }
+
+        if self.retry_in(now) > Duration::ZERO {
+            return Attempt::LockedOut;
+        }
+
+        if (self.verify)(pin) {
+            self.locked = false;
+            self.failures = 0;
+            self.locked_out_until = None;
+            self.last_input = now;
+            return Attempt::Unlocked;
+        }
+
+        self.failures += 1;
+
+        if let Some(extra) = self.failures.checked_sub(self.config.attempts) {
+            let lockout = self
+                .config
+                .lockout
+                .saturating_mul(2u32.saturating_pow(extra))
+                .min(self.config.max_lockout);
+            self.locked_out_until = Some(now + lockout);
+        }
+
+        Attempt::Wrong
+    }
+
+    fn status(&self, now: Instant) -> LockStatus {
+        LockStatus {
+            locked: self.locked,
+            failures: self.failures,
+            retry_in: self.retry_in(now),
+        }
+    }
+
+    fn retry_in(&self, now: Instant) -> Duration {
+        self.locked_out_until
+            .map_or(Duration::ZERO, |until| until.saturating_duration_since(now))
+    }
+}
+
+/// Locks a `LayerStack` behind a PIN layer after a period without input. While locked,
+/// the layer is shown and grabs all input, so nothing beneath it can be pressed; a
+/// correct PIN hides it again. After `attempts` wrong PINs, entry is locked out for a
+/// while, doubling with each further wrong PIN.
+///
+/// Register a clone with the lock layer's renderer, which gets a `lockScreen` global to
+/// submit PINs from its UI. Starts locked.
+#[derive(Clone)]
+pub struct LockScreen {
+    layer: String,
+    state: Rc<RefCell<LockState>>,
+    /// Whether the layer is currently shown and grabbing input
+    shown: Rc<RefCell<bool>>,
+}
+
+impl LockScreen {
+    pub fn new(
+        layer: &str,
+        config: LockConfig,
+        verify: impl FnMut(&str) -> bool + 'static,
+    ) -> Self {
+        LockScreen {
+            layer: layer.to_string(),
+            state: Rc::new(RefCell::new(LockState {
+                config,
+                verify: Box::new(verify),
+                locked: true,
+                last_input: Instant::now(),
+                failures: 0,
+                locked_out_until: None,
+            })),
+            shown: Rc::new(RefCell::new(false)),
+        }
+    }
+
+    /// Restart the idle timeout. Call for every input event the host reads.
+    pub fn activity(&self) {
+        self.state.borrow_mut().last_input = Instant::now();
+    }
+
+    /// Lock now, e.g. from a hardware button.
+    pub fn lock(&self) {
+        self.state.borrow_mut().locked = true;
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.state.borrow().locked
+    }
+
+    /// Check a PIN, as the JS `lockScreen.submit` does.
+    pub fn submit(&self, pin: &str) -> Attempt {
+        self.state.borrow_mut().submit(pin, Instant::now())
+    }
+
+    /// Whether it's locked and how entry is going, as the JS `lockScreen.status` returns.
+    pub fn status(&self) -> LockStatus {
+        self.state.borrow().status(Instant::now())
+    }
+
+    /// Lock if the timeout has passed, and show or hide the lock layer to match. Call
+    /// before the stack's own `tick`.
+    pub fn tick(&self, stack: &mut LayerStack) {
+        let locked = {
+            let mut state = self.state.borrow_mut();
+
+            if let Some(timeout) = state.config.timeout
+                && state.last_input.elapsed() >= timeout
+            {
+                state.locked = true;
+            }
+
+            state.locked
+        };
+
+        let mut shown = self.shown.borrow_mut();
+
+        if *shown != locked {
+            *shown = locked;
+            stack.set_visible(&self.layer, locked);
+            stack.set_input_grab(locked.then_some(self.layer.as_str()));
+        }
+    }
+}
+
+impl JsModule for LockScreen {
+    fn register(&self, ctx: &Ctx<'_>) {
+        let lock_screen = Object::new(ctx.clone()).unwrap();
+
+        let state = self.state.clone();
+        lock_screen
+            .set(
+                "submit",
+                Func::from(move |mut pin: String| {
+                    let attempt = state.borrow_mut().submit(&pin, Instant::now());
+                    pin.zeroize();
+                    attempt.as_str()
+                }),
+            )
+            .unwrap();
+
+        let state = self.state.clone();
+        lock_screen
+            .set(
+                "status",
+                Func::from(move || state.borrow().status(Instant::now())),
+            )
+            .unwrap();
+
+        ctx.globals().set("lockScreen", lock_screen).unwrap();
+    }
+}
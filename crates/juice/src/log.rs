@@ -0,0 +1,418 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+use crate::pointer::{Corner, CornerTaps};
+
+/// How serious a log entry is, from `console.debug` up to `console.error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    #[default]
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 4] = [
+        LogLevel::Debug,
+        LogLevel::Info,
+        LogLevel::Warn,
+        LogLevel::Error,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub level: LogLevel,
+    pub message: String,
+    pub time: SystemTime,
+}
+
+impl LogEntry {
+    /// Time of day in UTC as `HH:MM:SS`, since devices in the field often have no zone set.
+    pub fn time_of_day(&self) -> String {
+        let secs = self
+            .time
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        format!(
+            "{:02}:{:02}:{:02}",
+            secs / 3600 % 24,
+            secs / 60 % 60,
+            secs % 60
+        )
+    }
+}
+
+/// Settings for a `LogStore`. Every field is optional in JSON.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogConfig {
+    /// Entries kept in memory for the log viewer. The oldest are dropped past this.
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+    /// File entries are appended to. `None` keeps them in memory only.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// Size the file may reach before it's rotated to `<path>.1`
+    #[serde(default = "default_max_file_bytes")]
+    pub max_file_bytes: u64,
+    /// Rotated files kept, `<path>.1` being the newest
+    #[serde(default = "default_files")]
+    pub files: usize,
+    /// Entries below this level are dropped
+    #[serde(default)]
+    pub level: LogLevel,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            capacity: default_capacity(),
+            path: None,
+            max_file_bytes: default_max_file_bytes(),
+            files: default_files(),
+            level: LogLevel::Debug,
+        }
+    }
+}
+
+fn default_capacity() -> usize {
+    500
+}
+
+fn default_max_file_bytes() -> u64 {
+    256 * 1024
+}
+
+fn default_files() -> usize {
+    3
+}
+
+struct LogState {
+    config: LogConfig,
+    entries: VecDeque<LogEntry>,
+    /// Size of the current log file
+    file_bytes: u64,
+    /// Bumped for every entry, so the viewer knows when to redraw
+    version: u64,
+}
+
+impl LogState {
+    fn push(&mut self, entry: LogEntry) {
+        if self.config.path.is_some() {
+            self.write(&entry);
+        }
+
+        while self.entries.len() >= self.config.capacity.max(1) {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(entry);
+        self.version += 1;
+    }
+
+    fn write(&mut self, entry: &LogEntry) {
+        let millis = entry
+            .time
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis());
+        let line = format!(
+            "{}.{:03} {} {}\n",
+            millis / 1000,
+            millis % 1000,
+            entry.level.as_str(),
+            entry.message
+        );
+
+        if self.file_bytes > 0 && self.file_bytes + line.len() as u64 > self.config.max_file_bytes {
+            self.rotate();
+        }
+
+        let Some(path) = &self.config.path else {
+            return;
+        };
+
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(line.as_bytes()));
+
+        match result {
+            Ok(()) => self.file_bytes += line.len() as u64,
+            Err(err) => eprintln!("Error writing log: {}", err),
+        }
+    }
+
+    /// Shift `<path>.1` to `<path>.2` and so on, dropping the oldest, then move the
+    /// current file to `<path>.1`.
+    fn rotate(&mut self) {
+        let Some(path) = &self.config.path else {
+            return;
+        };
+
+        let rotated = |n: usize| {
+            let mut name = OsString::from(path.as_os_str());
+            name.push(format!(".{n}"));
+            PathBuf::from(name)
+        };
+
+        let result = if self.config.files == 0 {
+            File::create(path).map(|_| ())
+        } else {
+            let _ = fs::remove_file(rotated(self.config.files));
+
+            for n in (1..self.config.files).rev() {
+                let _ = fs::rename(rotated(n), rotated(n + 1));
+            }
+
+            fs::rename(path, rotated(1))
+        };
+
+        if let Err(err) = result {
+            eprintln!("Error rotating log: {}", err);
+        }
+
+        self.file_bytes = 0;
+    }
+}
+
+/// Keeps recent log entries in a ring buffer for the on-device log viewer, and appends
+/// them to a file rotated by size, so problems can be looked into after the fact without
+/// a shell on the device.
+///
+/// The host's console sink pushes to it; pass a clone to `Renderer::set_log_store` for
+/// the viewer.
+#[derive(Clone)]
+pub struct LogStore {
+    state: Rc<RefCell<LogState>>,
+}
+
+impl LogStore {
+    pub fn new(config: LogConfig) -> Self {
+        if let Some(dir) = config.path.as_ref().and_then(|path| path.parent())
+            && let Err(err) = fs::create_dir_all(dir)
+        {
+            eprintln!("Error creating log directory: {}", err);
+        }
+
+        let file_bytes = config
+            .path
+            .as_ref()
+            .and_then(|path| fs::metadata(path).ok())
+            .map_or(0, |metadata| metadata.len());
+
+        LogStore {
+            state: Rc::new(RefCell::new(LogState {
+                config,
+                entries: VecDeque::new(),
+                file_bytes,
+                version: 0,
+            })),
+        }
+    }
+
+    pub fn push(&self, level: LogLevel, message: &str) {
+        let mut state = self.state.borrow_mut();
+
+        if level < state.config.level {
+            return;
+        }
+
+        state.push(LogEntry {
+            level,
+            message: message.to_string(),
+            time: SystemTime::now(),
+        });
+    }
+
+    /// Entries at or above a level, oldest first.
+    pub fn entries(&self, min_level: LogLevel) -> Vec<LogEntry> {
+        self.state
+            .borrow()
+            .entries
+            .iter()
+            .filter(|entry| entry.level >= min_level)
+            .cloned()
+            .collect()
+    }
+
+    /// Changes whenever an entry is added.
+    pub fn version(&self) -> u64 {
+        self.state.borrow().version
+    }
+}
+
+/// A button in the log viewer's toolbar.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogViewerButton {
+    /// Show entries at or above a level
+    Filter(LogLevel),
+    Close,
+}
+
+impl LogViewerButton {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LogViewerButton::Filter(LogLevel::Debug) => "All",
+            LogViewerButton::Filter(LogLevel::Info) => "Info",
+            LogViewerButton::Filter(LogLevel::Warn) => "Warn",
+            LogViewerButton::Filter(LogLevel::Error) => "Error",
+            LogViewerButton::Close => "Close",
+        }
+    }
+}
+
+/// A full-screen overlay listing a `LogStore`'s entries, newest at the bottom, with a
+/// toolbar to filter by level. Dragging scrolls back through older entries. Opened by the
+/// host, or by tapping the top-left corner five times in quick succession.
+pub struct LogViewer {
+    store: LogStore,
+    pub visible: bool,
+    pub min_level: LogLevel,
+    /// How far back from the newest entry the list is scrolled, in pixels
+    pub scroll: f32,
+    /// The pointer y and scroll a drag started at
+    drag: Option<(f32, f32)>,
+    corner_taps: CornerTaps,
+    /// The store version last drawn
+    drawn: u64,
+}
+
+impl LogViewer {
+    pub fn new(store: LogStore) -> Self {
+        LogViewer {
+            store,
+            visible: false,
+            min_level: LogLevel::Debug,
+            scroll: 0.0,
+            drag: None,
+            corner_taps: CornerTaps::new(Corner::TopLeft),
+            drawn: 0,
+        }
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+        self.scroll = 0.0;
+        self.drag = None;
+    }
+
+    /// Font size for the screen, and the height of each row and of the toolbar.
+    pub fn metrics(screen_height: f32) -> (f32, f32, f32) {
+        let font_size = (screen_height / 32.0).clamp(10.0, 20.0).round();
+        let row_height = (font_size * 1.4).round();
+        (font_size, row_height, row_height * 2.0)
+    }
+
+    /// Each toolbar button with its rect, spread across the top of the screen.
+    pub fn buttons(
+        screen_width: f32,
+        screen_height: f32,
+    ) -> impl Iterator<Item = (LogViewerButton, (f32, f32, f32, f32))> {
+        let (_, _, toolbar_height) = Self::metrics(screen_height);
+        let buttons = LogLevel::ALL
+            .map(LogViewerButton::Filter)
+            .into_iter()
+            .chain([LogViewerButton::Close]);
+        let width = screen_width / 5.0;
+
+        buttons
+            .enumerate()
+            .map(move |(i, button)| (button, (i as f32 * width, 0.0, width, toolbar_height)))
+    }
+
+    /// The entries shown with the current filter, oldest first.
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.store.entries(self.min_level)
+    }
+
+    /// Whether entries were added since the last call, so a visible viewer needs redrawing.
+    pub fn take_changed(&mut self) -> bool {
+        let version = self.store.version();
+        let changed = version != self.drawn;
+        self.drawn = version;
+        changed
+    }
+
+    /// Scroll back through older entries by `dy` pixels, or forward if negative.
+    pub fn scroll_by(&mut self, dy: f32, screen_height: f32) {
+        let (_, row_height, toolbar_height) = Self::metrics(screen_height);
+        let content = self.entries().len() as f32 * row_height;
+        let max = (content - (screen_height - toolbar_height)).max(0.0);
+
+        self.scroll = (self.scroll + dy).clamp(0.0, max);
+    }
+
+    /// Handle a pointer event. While hidden, only counts corner taps towards opening;
+    /// while shown, takes every event. Returns whether the event was consumed.
+    pub fn handle_pointer(
+        &mut self,
+        event_name: &str,
+        x: f32,
+        y: f32,
+        screen_width: f32,
+        screen_height: f32,
+    ) -> bool {
+        if !self.visible {
+            if event_name != "PressIn" {
+                return false;
+            }
+
+            if !self.corner_taps.press(x, y, screen_width, screen_height) {
+                return false;
+            }
+
+            self.set_visible(true);
+            return true;
+        }
+
+        match event_name {
+            "PressIn" => {
+                let button = Self::buttons(screen_width, screen_height)
+                    .find(|&(_, (bx, by, bw, bh))| x >= bx && x < bx + bw && y >= by && y < by + bh)
+                    .map(|(button, _)| button);
+
+                match button {
+                    Some(LogViewerButton::Filter(level)) => {
+                        self.min_level = level;
+                        self.scroll = 0.0;
+                    }
+                    Some(LogViewerButton::Close) => self.set_visible(false),
+                    None => self.drag = Some((y, self.scroll)),
+                }
+            }
+            "PressMove" => {
+                if let Some((start_y, start_scroll)) = self.drag {
+                    self.scroll = start_scroll;
+                    self.scroll_by(y - start_y, screen_height);
+                }
+            }
+            "PressOut" => self.drag = None,
+            _ => {}
+        }
+
+        true
+    }
+}
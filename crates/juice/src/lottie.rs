@@ -0,0 +1,751 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::Instant;
+
+use resvg::tiny_skia::{Pixmap, Transform as PixmapTransform};
+use resvg::usvg::{Options, Tree};
+use serde_json::Value;
+
+use crate::animation::Easing;
+
+/// Most memory one node's cached frames may take. Frames past this are rasterized again
+/// each time they're shown.
+const CACHE_BUDGET: usize = 16 * 1024 * 1024;
+
+/// Handle length, relative to the radius, for a cubic bezier approximating a quarter of
+/// an ellipse.
+const KAPPA: f32 = 0.552_284_8;
+
+/// Parents deeper than this are ignored, which also stops cycles in malformed files.
+const MAX_PARENT_DEPTH: usize = 16;
+
+/// A 2D affine transform, `x' = a x + c y + e` and `y' = b x + d y + f`.
+#[derive(Debug, Clone, Copy)]
+struct Matrix([f32; 6]);
+
+impl Matrix {
+    const IDENTITY: Matrix = Matrix([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    fn translate(x: f32, y: f32) -> Self {
+        Matrix([1.0, 0.0, 0.0, 1.0, x, y])
+    }
+
+    fn scale(x: f32, y: f32) -> Self {
+        Matrix([x, 0.0, 0.0, y, 0.0, 0.0])
+    }
+
+    /// Clockwise, as the y axis points down.
+    fn rotate(degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Matrix([cos, sin, -sin, cos, 0.0, 0.0])
+    }
+
+    /// This transform applied after `other`.
+    fn then_after(self, other: Matrix) -> Self {
+        let [a, b, c, d, e, f] = self.0;
+        let [oa, ob, oc, od, oe, of] = other.0;
+
+        Matrix([
+            a * oa + c * ob,
+            b * oa + d * ob,
+            a * oc + c * od,
+            b * oc + d * od,
+            a * oe + c * of + e,
+            b * oe + d * of + f,
+        ])
+    }
+
+    fn apply(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let [a, b, c, d, e, f] = self.0;
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+
+    /// How much the transform scales lengths on average, for stroke widths.
+    fn length_scale(&self) -> f32 {
+        let [a, b, c, d, ..] = self.0;
+        (a * d - b * c).abs().sqrt()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Keyframe {
+    time: f32,
+    start: Vec<f32>,
+    end: Vec<f32>,
+    easing: Easing,
+    /// Jump to the next value rather than interpolating
+    hold: bool,
+}
+
+/// A possibly animated value, flattened to numbers. Paths flatten to whether they're
+/// closed followed by each vertex and its in and out tangents.
+#[derive(Debug, Clone)]
+enum Property {
+    Static(Vec<f32>),
+    Animated(Vec<Keyframe>),
+    /// A position with x and y animated separately
+    Split(Box<Property>, Box<Property>),
+}
+
+impl Property {
+    fn parse(value: Option<&Value>, default: &[f32]) -> Self {
+        let Some(value) = value else {
+            return Property::Static(default.to_vec());
+        };
+
+        if value.get("s").and_then(Value::as_bool) == Some(true) {
+            let (x, y) = default.split_at(default.len().min(1));
+            return Property::Split(
+                Box::new(Property::parse(value.get("x"), x)),
+                Box::new(Property::parse(value.get("y"), y)),
+            );
+        }
+
+        let k = value.get("k");
+        let frames = k
+            .and_then(Value::as_array)
+            .filter(|frames| frames.first().is_some_and(|f| f.get("t").is_some()));
+
+        let Some(frames) = frames else {
+            return Property::Static(k.and_then(numbers).unwrap_or_else(|| default.to_vec()));
+        };
+
+        let mut keyframes: Vec<Keyframe> = Vec::new();
+
+        for frame in frames {
+            // Older files leave `s` off the last keyframe, which holds the previous end
+            let start = frame
+                .get("s")
+                .and_then(numbers)
+                .or_else(|| keyframes.last().map(|k| k.end.clone()));
+            let Some(start) = start else {
+                continue;
+            };
+            let handle = |key: &str, axis: &str, default: f32| {
+                let value = frame.get(key).and_then(|handle| handle.get(axis));
+                value
+                    .and_then(|v| v.as_f64().or_else(|| v.get(0)?.as_f64()))
+                    .map_or(default, |v| v as f32)
+            };
+
+            keyframes.push(Keyframe {
+                time: frame.get("t").and_then(Value::as_f64).unwrap_or(0.0) as f32,
+                start,
+                end: frame.get("e").and_then(numbers).unwrap_or_default(),
+                easing: Easing::CubicBezier(
+                    handle("o", "x", 0.0).clamp(0.0, 1.0),
+                    handle("o", "y", 0.0),
+                    handle("i", "x", 1.0).clamp(0.0, 1.0),
+                    handle("i", "y", 1.0),
+                ),
+                hold: frame.get("h").and_then(Value::as_i64) == Some(1),
+            });
+        }
+
+        // Newer files leave out `e`, as each keyframe ends where the next starts
+        for i in 1..keyframes.len() {
+            if keyframes[i - 1].end.is_empty() {
+                keyframes[i - 1].end = keyframes[i].start.clone();
+            }
+        }
+
+        if keyframes.is_empty() {
+            Property::Static(default.to_vec())
+        } else {
+            Property::Animated(keyframes)
+        }
+    }
+
+    fn at(&self, frame: f32) -> Vec<f32> {
+        match self {
+            Property::Static(value) => value.clone(),
+            Property::Split(x, y) => [x.scalar(frame), y.scalar(frame)].to_vec(),
+            Property::Animated(keyframes) => {
+                if frame <= keyframes[0].time {
+                    return keyframes[0].start.clone();
+                }
+
+                for pair in keyframes.windows(2) {
+                    let (from, to) = (&pair[0], &pair[1]);
+
+                    if frame < to.time {
+                        // Shapes with different vertex counts can't be blended
+                        if from.hold || from.end.len() != from.start.len() {
+                            return from.start.clone();
+                        }
+
+                        let t = from
+                            .easing
+                            .apply((frame - from.time) / (to.time - from.time));
+
+                        return from
+                            .start
+                            .iter()
+                            .zip(&from.end)
+                            .map(|(a, b)| a + (b - a) * t)
+                            .collect();
+                    }
+                }
+
+                keyframes[keyframes.len() - 1].start.clone()
+            }
+        }
+    }
+
+    fn scalar(&self, frame: f32) -> f32 {
+        self.at(frame).first().copied().unwrap_or(0.0)
+    }
+
+    fn point(&self, frame: f32) -> (f32, f32) {
+        let value = self.at(frame);
+        (
+            value.first().copied().unwrap_or(0.0),
+            value.get(1).copied().unwrap_or(0.0),
+        )
+    }
+}
+
+/// Numbers from a static value or keyframe: a number, an array of numbers or a path.
+fn numbers(value: &Value) -> Option<Vec<f32>> {
+    match value {
+        Value::Number(n) => Some(vec![n.as_f64()? as f32]),
+        Value::Array(items) if items.first().is_some_and(Value::is_object) => numbers(&items[0]),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| item.as_f64().map(|n| n as f32))
+            .collect(),
+        Value::Object(_) => {
+            let points = |key: &str| -> Option<Vec<(f32, f32)>> {
+                value
+                    .get(key)?
+                    .as_array()?
+                    .iter()
+                    .map(|p| Some((p.get(0)?.as_f64()? as f32, p.get(1)?.as_f64()? as f32)))
+                    .collect()
+            };
+            let (vertices, ins, outs) = (points("v")?, points("i")?, points("o")?);
+            let closed = value.get("c").and_then(Value::as_bool).unwrap_or(false);
+            let mut flat = vec![if closed { 1.0 } else { 0.0 }];
+
+            for ((v, i), o) in vertices.iter().zip(&ins).zip(&outs) {
+                flat.extend([v.0, v.1, i.0, i.1, o.0, o.1]);
+            }
+
+            Some(flat)
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Transform {
+    anchor: Property,
+    position: Property,
+    scale: Property,
+    rotation: Property,
+    opacity: Property,
+}
+
+impl Transform {
+    fn parse(value: Option<&Value>) -> Self {
+        let get = |key: &str| value.and_then(|v| v.get(key));
+
+        Transform {
+            anchor: Property::parse(get("a"), &[0.0, 0.0]),
+            position: Property::parse(get("p"), &[0.0, 0.0]),
+            scale: Property::parse(get("s"), &[100.0, 100.0]),
+            rotation: Property::parse(get("r"), &[0.0]),
+            opacity: Property::parse(get("o"), &[100.0]),
+        }
+    }
+
+    fn matrix(&self, frame: f32) -> Matrix {
+        let (ax, ay) = self.anchor.point(frame);
+        let (px, py) = self.position.point(frame);
+        let (sx, sy) = self.scale.point(frame);
+
+        Matrix::translate(px, py)
+            .then_after(Matrix::rotate(self.rotation.scalar(frame)))
+            .then_after(Matrix::scale(sx / 100.0, sy / 100.0))
+            .then_after(Matrix::translate(-ax, -ay))
+    }
+
+    fn opacity(&self, frame: f32) -> f32 {
+        (self.opacity.scalar(frame) / 100.0).clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Shape {
+    Group(Vec<Shape>),
+    Rect {
+        position: Property,
+        size: Property,
+        roundness: Property,
+    },
+    Ellipse {
+        position: Property,
+        size: Property,
+    },
+    Path(Property),
+    Fill {
+        color: Property,
+        opacity: Property,
+        even_odd: bool,
+    },
+    Stroke {
+        color: Property,
+        opacity: Property,
+        width: Property,
+    },
+    Transform(Transform),
+}
+
+/// Parse a layer's or group's shape items, skipping hidden ones and those outside the
+/// supported subset, like gradients, trim paths and repeaters.
+fn parse_shapes(items: Option<&Value>) -> Vec<Shape> {
+    let items = items.and_then(Value::as_array).into_iter().flatten();
+
+    items
+        .filter(|item| item.get("hd").and_then(Value::as_bool) != Some(true))
+        .filter_map(|item| {
+            let property = |key: &str, default: &[f32]| Property::parse(item.get(key), default);
+
+            Some(match item.get("ty")?.as_str()? {
+                "gr" => Shape::Group(parse_shapes(item.get("it"))),
+                "rc" => Shape::Rect {
+                    position: property("p", &[0.0, 0.0]),
+                    size: property("s", &[0.0, 0.0]),
+                    roundness: property("r", &[0.0]),
+                },
+                "el" => Shape::Ellipse {
+                    position: property("p", &[0.0, 0.0]),
+                    size: property("s", &[0.0, 0.0]),
+                },
+                "sh" => Shape::Path(property("ks", &[])),
+                "fl" => Shape::Fill {
+                    color: property("c", &[0.0, 0.0, 0.0]),
+                    opacity: property("o", &[100.0]),
+                    even_odd: item.get("r").and_then(Value::as_i64) == Some(2),
+                },
+                "st" => Shape::Stroke {
+                    color: property("c", &[0.0, 0.0, 0.0]),
+                    opacity: property("o", &[100.0]),
+                    width: property("w", &[1.0]),
+                },
+                "tr" => Shape::Transform(Transform::parse(Some(item))),
+                _ => return None,
+            })
+        })
+        .collect()
+}
+
+/// A bezier vertex: its point and absolute in and out control points.
+type Vertex = [(f32, f32); 3];
+
+impl Shape {
+    /// The outline of a geometry shape at a frame, or `None` for styles and groups.
+    fn outline(&self, frame: f32) -> Option<(Vec<Vertex>, bool)> {
+        match self {
+            Shape::Rect {
+                position,
+                size,
+                roundness,
+            } => {
+                let (cx, cy) = position.point(frame);
+                let (w, h) = size.point(frame);
+                let r = roundness.scalar(frame).clamp(0.0, w.min(h) / 2.0);
+                let (x0, y0, x1, y1) = (cx - w / 2.0, cy - h / 2.0, cx + w / 2.0, cy + h / 2.0);
+                let k = r * (1.0 - KAPPA);
+
+                Some((
+                    vec![
+                        [(x0 + r, y0), (x0 + k, y0), (x0 + r, y0)],
+                        [(x1 - r, y0), (x1 - r, y0), (x1 - k, y0)],
+                        [(x1, y0 + r), (x1, y0 + k), (x1, y0 + r)],
+                        [(x1, y1 - r), (x1, y1 - r), (x1, y1 - k)],
+                        [(x1 - r, y1), (x1 - k, y1), (x1 - r, y1)],
+                        [(x0 + r, y1), (x0 + r, y1), (x0 + k, y1)],
+                        [(x0, y1 - r), (x0, y1 - k), (x0, y1 - r)],
+                        [(x0, y0 + r), (x0, y0 + r), (x0, y0 + k)],
+                    ],
+                    true,
+                ))
+            }
+            Shape::Ellipse { position, size } => {
+                let (cx, cy) = position.point(frame);
+                let (w, h) = size.point(frame);
+                let (rx, ry) = (w / 2.0, h / 2.0);
+                let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+
+                Some((
+                    vec![
+                        [(cx, cy - ry), (cx - kx, cy - ry), (cx + kx, cy - ry)],
+                        [(cx + rx, cy), (cx + rx, cy - ky), (cx + rx, cy + ky)],
+                        [(cx, cy + ry), (cx + kx, cy + ry), (cx - kx, cy + ry)],
+                        [(cx - rx, cy), (cx - rx, cy + ky), (cx - rx, cy - ky)],
+                    ],
+                    true,
+                ))
+            }
+            Shape::Path(path) => {
+                let flat = path.at(frame);
+                let (closed, points) = flat.split_first()?;
+                let vertices = points
+                    .chunks_exact(6)
+                    .map(|p| {
+                        let (x, y) = (p[0], p[1]);
+                        [(x, y), (x + p[2], y + p[3]), (x + p[4], y + p[5])]
+                    })
+                    .collect();
+
+                Some((vertices, *closed != 0.0))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// SVG path data for an outline, transformed by `matrix`.
+fn path_data(data: &mut String, (vertices, closed): (Vec<Vertex>, bool), matrix: Matrix) {
+    let Some(first) = vertices.first() else {
+        return;
+    };
+    let point = |data: &mut String, p: (f32, f32)| {
+        let (x, y) = matrix.apply(p);
+        let _ = write!(data, " {:.2} {:.2}", x, y);
+    };
+
+    data.push_str(" M");
+    point(data, first[0]);
+
+    let segments = vertices.windows(2).map(|pair| (pair[0], pair[1]));
+    let closing = closed.then(|| (vertices[vertices.len() - 1], *first));
+
+    for (from, to) in segments.chain(closing) {
+        data.push_str(" C");
+        point(data, from[2]);
+        point(data, to[1]);
+        point(data, to[0]);
+    }
+
+    if closed {
+        data.push_str(" Z");
+    }
+}
+
+/// A Lottie color, whose channels are 0–1 (or 0–255 in some older files), as SVG.
+fn svg_color(channels: &[f32]) -> String {
+    let scale = if channels.iter().take(3).any(|c| *c > 1.0) {
+        1.0
+    } else {
+        255.0
+    };
+    let channel = |i: usize| (channels.get(i).copied().unwrap_or(0.0) * scale).clamp(0.0, 255.0);
+
+    format!("rgb({:.0},{:.0},{:.0})", channel(0), channel(1), channel(2))
+}
+
+/// SVG for a group's items. Items are listed top first, and each fill or stroke paints
+/// every shape listed before it in the group, including inside nested groups. Also
+/// returns the group's outlines so an enclosing group's styles can paint them.
+fn render_group(items: &[Shape], frame: f32, matrix: Matrix, opacity: f32) -> (String, String) {
+    let (matrix, opacity) = match items.iter().find_map(|item| match item {
+        Shape::Transform(transform) => Some(transform),
+        _ => None,
+    }) {
+        Some(transform) => (
+            matrix.then_after(transform.matrix(frame)),
+            opacity * transform.opacity(frame),
+        ),
+        None => (matrix, opacity),
+    };
+
+    let mut outlines = String::new();
+    let mut painted = Vec::new();
+
+    for item in items {
+        match item {
+            Shape::Group(items) => {
+                let (svg, group_outlines) = render_group(items, frame, matrix, opacity);
+                painted.push(svg);
+                outlines.push_str(&group_outlines);
+            }
+            Shape::Fill {
+                color,
+                opacity: fill_opacity,
+                even_odd,
+            } => painted.push(format!(
+                r#"<path d="{}" fill="{}" fill-opacity="{:.3}" fill-rule="{}"/>"#,
+                outlines,
+                svg_color(&color.at(frame)),
+                opacity * fill_opacity.scalar(frame) / 100.0,
+                if *even_odd { "evenodd" } else { "nonzero" },
+            )),
+            Shape::Stroke {
+                color,
+                opacity: stroke_opacity,
+                width,
+            } => painted.push(format!(
+                r#"<path d="{}" fill="none" stroke="{}" stroke-opacity="{:.3}" stroke-width="{:.2}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+                outlines,
+                svg_color(&color.at(frame)),
+                opacity * stroke_opacity.scalar(frame) / 100.0,
+                width.scalar(frame) * matrix.length_scale(),
+            )),
+            Shape::Transform(_) => {}
+            geometry => {
+                if let Some(outline) = geometry.outline(frame) {
+                    path_data(&mut outlines, outline, matrix);
+                }
+            }
+        }
+    }
+
+    // Later items are painted first, underneath
+    painted.reverse();
+    (painted.concat(), outlines)
+}
+
+#[derive(Debug, Clone)]
+struct Layer {
+    index: Option<i64>,
+    parent: Option<i64>,
+    in_point: f32,
+    out_point: f32,
+    start_time: f32,
+    transform: Transform,
+    /// Empty for layers that only move others, like nulls
+    shapes: Vec<Shape>,
+}
+
+/// A parsed Lottie (bodymovin) animation. Only the common subset is supported: shape
+/// layers with rects, ellipses and paths, solid fills and strokes, and transforms with
+/// parenting. Precomps, images, text, masks, mattes, gradients and effects are skipped.
+#[derive(Debug, Clone)]
+pub struct Composition {
+    pub width: f32,
+    pub height: f32,
+    pub frame_rate: f32,
+    pub in_point: f32,
+    pub out_point: f32,
+    layers: Vec<Layer>,
+}
+
+impl Composition {
+    pub fn parse(json: &str) -> Option<Self> {
+        let root: Value = serde_json::from_str(json)
+            .map_err(|err| eprintln!("Invalid Lottie animation: {}", err))
+            .ok()?;
+        let number = |value: &Value, key: &str, default: f32| {
+            value
+                .get(key)
+                .and_then(Value::as_f64)
+                .map_or(default, |n| n as f32)
+        };
+
+        let layers = root
+            .get("layers")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter(|layer| layer.get("hd").and_then(Value::as_bool) != Some(true))
+            .map(|layer| {
+                let is_shape = layer.get("ty").and_then(Value::as_i64) == Some(4);
+
+                Layer {
+                    index: layer.get("ind").and_then(Value::as_i64),
+                    parent: layer.get("parent").and_then(Value::as_i64),
+                    in_point: number(layer, "ip", f32::MIN),
+                    out_point: number(layer, "op", f32::MAX),
+                    start_time: number(layer, "st", 0.0),
+                    transform: Transform::parse(layer.get("ks")),
+                    shapes: if is_shape {
+                        parse_shapes(layer.get("shapes"))
+                    } else {
+                        Vec::new()
+                    },
+                }
+            })
+            .collect();
+
+        Some(Composition {
+            width: number(&root, "w", 0.0),
+            height: number(&root, "h", 0.0),
+            frame_rate: number(&root, "fr", 30.0).max(1.0),
+            in_point: number(&root, "ip", 0.0),
+            out_point: number(&root, "op", 0.0),
+            layers,
+        })
+    }
+
+    /// Frames in one play through.
+    pub fn frame_count(&self) -> u32 {
+        (self.out_point - self.in_point).max(1.0) as u32
+    }
+
+    fn layer_matrix(&self, layer: &Layer, frame: f32, depth: usize) -> Matrix {
+        let local = layer.transform.matrix(frame - layer.start_time);
+        let parent = layer
+            .parent
+            .filter(|_| depth < MAX_PARENT_DEPTH)
+            .and_then(|parent| self.layers.iter().find(|l| l.index == Some(parent)));
+
+        match parent {
+            Some(parent) => self
+                .layer_matrix(parent, frame, depth + 1)
+                .then_after(local),
+            None => local,
+        }
+    }
+
+    /// The animation at a frame as an SVG document.
+    pub fn svg(&self, frame: f32) -> String {
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = self.width,
+            h = self.height,
+        );
+
+        // Layers are listed top first
+        for layer in self.layers.iter().rev() {
+            if layer.shapes.is_empty() || frame < layer.in_point || frame >= layer.out_point {
+                continue;
+            }
+
+            let local_frame = frame - layer.start_time;
+            let [a, b, c, d, e, f] = self.layer_matrix(layer, frame, 0).0;
+            let (content, _) = render_group(&layer.shapes, local_frame, Matrix::IDENTITY, 1.0);
+
+            let _ = write!(
+                svg,
+                r#"<g transform="matrix({} {} {} {} {} {})" opacity="{:.3}">{}</g>"#,
+                a,
+                b,
+                c,
+                d,
+                e,
+                f,
+                layer.transform.opacity(local_frame),
+                content
+            );
+        }
+
+        svg.push_str("</svg>");
+        svg
+    }
+
+    /// Rasterize a frame (counted from the start) to premultiplied RGBA, scaled to fit
+    /// `width` × `height` and centred.
+    pub fn rasterize(&self, index: u32, width: u32, height: u32) -> Option<Vec<u8>> {
+        let svg = self.svg(self.in_point + index as f32);
+        let tree = Tree::from_str(&svg, &Options::default())
+            .map_err(|err| eprintln!("Error rendering Lottie frame: {:?}", err))
+            .ok()?;
+        let mut pixmap = Pixmap::new(width, height)?;
+
+        let scale = (width as f32 / self.width.max(1.0)).min(height as f32 / self.height.max(1.0));
+        let transform = PixmapTransform::from_row(
+            scale,
+            0.0,
+            0.0,
+            scale,
+            (width as f32 - self.width * scale) / 2.0,
+            (height as f32 - self.height * scale) / 2.0,
+        );
+
+        resvg::render(&tree, transform, &mut pixmap.as_mut());
+        Some(pixmap.take())
+    }
+}
+
+/// Playback state of a `lottie` node, with rasterized frames cached per node size.
+pub struct LottiePlayer {
+    composition: Option<Composition>,
+    pub looping: bool,
+    pub speed: f32,
+    started: Instant,
+    cache: HashMap<u32, Vec<u8>>,
+    cache_size: (u32, u32),
+    cached_bytes: usize,
+}
+
+impl LottiePlayer {
+    /// A player with nothing loaded yet, counting from `started`.
+    pub fn new(started: Instant) -> Self {
+        LottiePlayer {
+            composition: None,
+            looping: true,
+            speed: 1.0,
+            started,
+            cache: HashMap::new(),
+            cache_size: (0, 0),
+            cached_bytes: 0,
+        }
+    }
+
+    /// Load an animation from its JSON and play it from the start, as of `now`.
+    pub fn load(&mut self, json: &str, now: Instant) {
+        self.composition = Composition::parse(json);
+        self.started = now;
+        self.clear_cache();
+    }
+
+    fn clear_cache(&mut self) {
+        self.cache.clear();
+        self.cached_bytes = 0;
+    }
+
+    fn elapsed_frames(&self, now: Instant) -> Option<f32> {
+        let composition = self.composition.as_ref()?;
+        let secs = now.saturating_duration_since(self.started).as_secs_f32();
+        Some(secs * composition.frame_rate * self.speed.max(0.0))
+    }
+
+    /// The frame to show at `now`, counted from the start. Without looping the animation
+    /// stops on its last frame.
+    pub fn frame(&self, now: Instant) -> Option<u32> {
+        let frames = self.composition.as_ref()?.frame_count();
+        let elapsed = self.elapsed_frames(now)? as u32;
+
+        Some(if self.looping {
+            elapsed % frames
+        } else {
+            elapsed.min(frames - 1)
+        })
+    }
+
+    /// Whether frames are still changing, so the renderer should keep redrawing.
+    pub fn playing(&self, now: Instant) -> bool {
+        match (&self.composition, self.elapsed_frames(now)) {
+            (Some(composition), Some(elapsed)) => {
+                self.looping || elapsed < composition.frame_count() as f32
+            }
+            _ => false,
+        }
+    }
+
+    /// A frame as premultiplied RGBA at the given size, from the cache if it's there.
+    pub fn rasterize(&mut self, index: u32, width: u32, height: u32) -> Option<Cow<'_, [u8]>> {
+        if self.cache_size != (width, height) {
+            self.clear_cache();
+            self.cache_size = (width, height);
+        }
+
+        if !self.cache.contains_key(&index) {
+            let data = self.composition.as_ref()?.rasterize(index, width, height)?;
+
+            if self.cached_bytes + data.len() > CACHE_BUDGET {
+                return Some(Cow::Owned(data));
+            }
+
+            self.cached_bytes += data.len();
+            self.cache.insert(index, data);
+        }
+
+        self.cache
+            .get(&index)
+            .map(|data| Cow::Borrowed(data.as_slice()))
+    }
+}
@@ -0,0 +1,547 @@
+use fontdue::Font;
+use fontdue::layout::{
+    CoordinateSystem, GlyphPosition, Layout as TextLayout, LayoutSettings, TextStyle,
+};
+
+use crate::canvas::RgbColor;
+use crate::font_fallback::{self, FontRegistry};
+use crate::inherited_style::{FontStyle, FontWeight, InheritedStyle};
+
+/// Inline styling of a run of text.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SpanStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+    /// Index of the link's URL in `Markdown::hrefs`
+    pub link: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: SpanStyle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Heading {
+        level: u8,
+        spans: Vec<Span>,
+    },
+    Paragraph(Vec<Span>),
+    /// A list item, its bullet or number, and how deeply it's nested
+    ListItem {
+        marker: String,
+        depth: usize,
+        spans: Vec<Span>,
+    },
+    /// A fenced code block, drawn as is in the code font
+    Code(String),
+}
+
+/// Relative font sizes of headings, from `#` to `######`
+const HEADING_SCALES: [f32; 6] = [2.0, 1.5, 1.25, 1.0, 1.0, 1.0];
+
+/// Parse a line's inline markup: `**bold**`, `*italic*` (or underscores), `` `code` ``
+/// and `[links](url)`, whose URLs are pushed to `hrefs`. Markers without a closing pair
+/// are kept as text, and a backslash escapes the next character.
+fn parse_inline(text: &str, hrefs: &mut Vec<String>) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut style = SpanStyle::default();
+    let mut current = String::new();
+    let mut rest = text;
+
+    let flush = |spans: &mut Vec<Span>, current: &mut String, style: SpanStyle| {
+        if !current.is_empty() {
+            spans.push(Span {
+                text: std::mem::take(current),
+                style,
+            });
+        }
+    };
+
+    while let Some(c) = rest.chars().next() {
+        let after = &rest[c.len_utf8()..];
+        let previous = current.chars().last();
+
+        if c == '\\'
+            && let Some(escaped) = after.chars().next()
+        {
+            current.push(escaped);
+            rest = &after[escaped.len_utf8()..];
+            continue;
+        }
+
+        if c == '`'
+            && let Some(end) = after.find('`')
+        {
+            flush(&mut spans, &mut current, style);
+            spans.push(Span {
+                text: after[..end].to_string(),
+                style: SpanStyle {
+                    code: true,
+                    ..style
+                },
+            });
+            rest = &after[end + 1..];
+            continue;
+        }
+
+        if c == '['
+            && let Some(close) = after.find("](")
+            && let Some(end) = after[close + 2..].find(')')
+        {
+            flush(&mut spans, &mut current, style);
+            hrefs.push(after[close + 2..close + 2 + end].to_string());
+
+            let link_style = SpanStyle {
+                link: Some(hrefs.len() - 1),
+                ..style
+            };
+            let inner = parse_inline(&after[..close], &mut Vec::new());
+
+            spans.extend(inner.into_iter().map(|span| Span {
+                text: span.text,
+                style: SpanStyle {
+                    bold: span.style.bold || style.bold,
+                    italic: span.style.italic || style.italic,
+                    code: span.style.code,
+                    ..link_style
+                },
+            }));
+            rest = &after[close + 2 + end + 1..];
+            continue;
+        }
+
+        if c == '*' || c == '_' {
+            let double = after.starts_with(c);
+            let marker = if double { &rest[..2] } else { &rest[..1] };
+            let remaining = &rest[marker.len()..];
+            let open = if double { style.bold } else { style.italic };
+            // Underscores inside words, like snake_case, are just underscores
+            let intraword = c == '_'
+                && if open {
+                    remaining.chars().next().is_some_and(char::is_alphanumeric)
+                } else {
+                    previous.is_some_and(char::is_alphanumeric)
+                };
+
+            if !intraword && (open || remaining.contains(marker)) {
+                flush(&mut spans, &mut current, style);
+
+                if double {
+                    style.bold = !style.bold;
+                } else {
+                    style.italic = !style.italic;
+                }
+
+                rest = remaining;
+                continue;
+            }
+        }
+
+        current.push(c);
+        rest = after;
+    }
+
+    flush(&mut spans, &mut current, style);
+    spans
+}
+
+/// Parse a list item's marker, returning the bullet or number to show, the nesting depth
+/// and the item's text.
+fn parse_list_item(line: &str) -> Option<(String, usize, &str)> {
+    let trimmed = line.trim_start();
+    let depth = (line.len() - trimmed.len()) / 2;
+
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(text) = trimmed.strip_prefix(bullet) {
+            return Some(("•".to_string(), depth, text));
+        }
+    }
+
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    let after = &trimmed[digits..];
+
+    if digits > 0
+        && let Some(text) = after
+            .strip_prefix(". ")
+            .or_else(|| after.strip_prefix(") "))
+    {
+        return Some((format!("{}.", &trimmed[..digits]), depth, text));
+    }
+
+    None
+}
+
+/// Parse Markdown source into blocks, and the URLs of its links.
+pub fn parse(source: &str) -> (Vec<Block>, Vec<String>) {
+    let mut blocks = Vec::new();
+    let mut hrefs = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code: Option<Vec<&str>> = None;
+
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>, hrefs: &mut Vec<String>| {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(parse_inline(&paragraph.join(" "), hrefs)));
+            paragraph.clear();
+        }
+    };
+
+    for line in source.lines() {
+        let trimmed = line.trim();
+
+        if let Some(lines) = &mut code {
+            if trimmed.starts_with("```") {
+                blocks.push(Block::Code(lines.join("\n")));
+                code = None;
+            } else {
+                lines.push(line);
+            }
+            continue;
+        }
+
+        if trimmed.starts_with("```") {
+            flush(&mut paragraph, &mut blocks, &mut hrefs);
+            code = Some(Vec::new());
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            flush(&mut paragraph, &mut blocks, &mut hrefs);
+            continue;
+        }
+
+        let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+
+        if (1..=6).contains(&hashes)
+            && let Some(text) = trimmed[hashes..].strip_prefix(' ')
+        {
+            flush(&mut paragraph, &mut blocks, &mut hrefs);
+            blocks.push(Block::Heading {
+                level: hashes as u8,
+                spans: parse_inline(text.trim(), &mut hrefs),
+            });
+            continue;
+        }
+
+        if let Some((marker, depth, text)) = parse_list_item(line) {
+            flush(&mut paragraph, &mut blocks, &mut hrefs);
+            blocks.push(Block::ListItem {
+                marker,
+                depth,
+                spans: parse_inline(text.trim(), &mut hrefs),
+            });
+            continue;
+        }
+
+        paragraph.push(trimmed);
+    }
+
+    flush(&mut paragraph, &mut blocks, &mut hrefs);
+
+    // An unclosed fence runs to the end
+    if let Some(lines) = code {
+        blocks.push(Block::Code(lines.join("\n")));
+    }
+
+    (blocks, hrefs)
+}
+
+/// The fonts a `markdown` node draws with, in the order of `FONT_REGULAR` and friends.
+pub type MarkdownFonts<'a> = [&'a Font; 4];
+
+const FONT_REGULAR: usize = 0;
+const FONT_BOLD: usize = 1;
+const FONT_ITALIC: usize = 2;
+const FONT_CODE: usize = 3;
+
+/// A glyph laid out for drawing, relative to the node's top left.
+#[derive(Debug, Clone, Copy)]
+pub struct PlacedGlyph {
+    pub glyph: GlyphPosition<SpanStyle>,
+    /// Where the glyph's layout starts within the node
+    pub origin: (f32, f32),
+    /// Top and bottom of the glyph's line within the node
+    pub line: (f32, f32),
+    pub baseline: f32,
+}
+
+/// Consecutive glyphs on one line sharing a style, like a link or a code span.
+#[derive(Debug, Clone, Copy)]
+pub struct Run {
+    /// Bounds relative to the node's top left
+    pub rect: (f32, f32, f32, f32),
+    pub baseline: f32,
+    pub style: SpanStyle,
+}
+
+/// Everything a `markdown` node draws, laid out for one width.
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownLayout {
+    pub glyphs: Vec<PlacedGlyph>,
+    /// Vertical extent (top, bottom) of each code block's background
+    pub code_blocks: Vec<(f32, f32)>,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl MarkdownLayout {
+    /// Runs of glyphs whose style `matches`, split at line breaks and between links.
+    pub fn runs(&self, fonts: MarkdownFonts, matches: impl Fn(&SpanStyle) -> bool) -> Vec<Run> {
+        let mut runs: Vec<Run> = Vec::new();
+        let mut previous: Option<&PlacedGlyph> = None;
+
+        for placed in &self.glyphs {
+            let glyph = &placed.glyph;
+
+            if !matches(&glyph.user_data) {
+                previous = None;
+                continue;
+            }
+
+            let advance = fonts[glyph.font_index]
+                .metrics_indexed(glyph.key.glyph_index, glyph.key.px)
+                .advance_width;
+            let left = placed.origin.0 + glyph.x;
+            let right = left + advance.max(glyph.width as f32);
+
+            let continues = previous.is_some_and(|previous| {
+                previous.line == placed.line
+                    && previous.origin == placed.origin
+                    && previous.glyph.user_data.link == glyph.user_data.link
+            });
+
+            match runs.last_mut() {
+                Some(run) if continues => run.rect.2 = right - run.rect.0,
+                _ => runs.push(Run {
+                    rect: (
+                        left,
+                        placed.line.0,
+                        right - left,
+                        placed.line.1 - placed.line.0,
+                    ),
+                    baseline: placed.baseline,
+                    style: glyph.user_data,
+                }),
+            }
+
+            previous = Some(placed);
+        }
+
+        runs
+    }
+}
+
+/// State of a `markdown` node: its parsed source and the fonts for its styles.
+#[derive(Debug, Clone, Default)]
+pub struct Markdown {
+    source: String,
+    pub blocks: Vec<Block>,
+    pub hrefs: Vec<String>,
+    /// Font names for bold, italic and code text, each the node's font if unset
+    pub bold_font: Option<String>,
+    pub italic_font: Option<String>,
+    pub code_font: Option<String>,
+    /// Color of link text and underlines. Defaults to the text color.
+    pub link_color: Option<RgbColor>,
+}
+
+impl Markdown {
+    pub fn set_source(&mut self, source: String) {
+        if source != self.source {
+            (self.blocks, self.hrefs) = parse(&source);
+            self.source = source;
+        }
+    }
+
+    /// Look up the node's fonts for text in `style`, falling back to `default` as
+    /// `font_fallback::resolve_name` does. Bold and italic text without fonts of their
+    /// own use the bold and italic faces of the node's font family, or instances of its
+    /// font if that's variable, or the regular font otherwise. `None` if the regular font
+    /// isn't loaded.
+    pub fn fonts<'a>(
+        &self,
+        fonts: &'a FontRegistry,
+        style: &InheritedStyle,
+        default: &str,
+    ) -> Option<MarkdownFonts<'a>> {
+        let regular = fonts.get(font_fallback::resolve_name(fonts, style, default))?;
+        let face = |font_weight, font_style| {
+            let style = InheritedStyle {
+                font_weight,
+                font_style,
+                ..style.clone()
+            };
+            let name = fonts.face(&style.font_name, font_weight, font_style);
+
+            fonts.get(fonts.instance(name, &style)).unwrap_or(regular)
+        };
+        let named = |name: &Option<String>, face: &'a Font| {
+            name.as_ref()
+                .and_then(|name| fonts.get(name))
+                .unwrap_or(face)
+        };
+
+        Some([
+            regular,
+            named(&self.bold_font, face(FontWeight::BOLD, style.font_style)),
+            named(
+                &self.italic_font,
+                face(style.font_weight, FontStyle::Italic),
+            ),
+            named(&self.code_font, regular),
+        ])
+    }
+
+    /// The URL of the link at a point relative to the node's top left, laid out `width`
+    /// wide.
+    pub fn link_at(
+        &self,
+        fonts: MarkdownFonts,
+        font_size: f32,
+        width: f32,
+        (x, y): (f32, f32),
+    ) -> Option<&str> {
+        let layout = self.layout(fonts, font_size, Some(width));
+
+        layout
+            .runs(fonts, |style| style.link.is_some())
+            .into_iter()
+            .find(|run| {
+                let (rx, ry, rw, rh) = run.rect;
+                x >= rx && x < rx + rw && y >= ry && y < ry + rh
+            })
+            .and_then(|run| self.hrefs.get(run.style.link?))
+            .map(String::as_str)
+    }
+
+    /// Lay out every block top to bottom, wrapping at `max_width` if given.
+    pub fn layout(
+        &self,
+        fonts: MarkdownFonts,
+        font_size: f32,
+        max_width: Option<f32>,
+    ) -> MarkdownLayout {
+        let mut output = MarkdownLayout::default();
+        let mut text_layout = TextLayout::new(CoordinateSystem::PositiveYDown);
+        let mut y = 0.0;
+        let block_gap = font_size / 2.0;
+        let indent = font_size * 1.5;
+
+        let mut append = |output: &mut MarkdownLayout,
+                          spans: &[(&str, SpanStyle, f32)],
+                          x: f32,
+                          y: f32|
+         -> f32 {
+            text_layout.reset(&LayoutSettings {
+                max_width: max_width.map(|width| (width - x).max(0.0)),
+                ..LayoutSettings::default()
+            });
+
+            for &(text, style, size) in spans {
+                let font_index = if style.code {
+                    FONT_CODE
+                } else if style.bold {
+                    FONT_BOLD
+                } else if style.italic {
+                    FONT_ITALIC
+                } else {
+                    FONT_REGULAR
+                };
+
+                text_layout.append(
+                    &fonts,
+                    &TextStyle::with_user_data(text, size, font_index, style),
+                );
+            }
+
+            let glyphs = text_layout.glyphs();
+
+            for line in text_layout.lines().into_iter().flatten() {
+                let top = y + line.baseline_y - line.max_ascent;
+                let bottom = y + line.baseline_y - line.min_descent;
+
+                for glyph in &glyphs[line.glyph_start..=line.glyph_end] {
+                    output.width = output.width.max(x + glyph.x + glyph.width as f32);
+                    output.glyphs.push(PlacedGlyph {
+                        glyph: *glyph,
+                        origin: (x, y),
+                        line: (top, bottom),
+                        baseline: y + line.baseline_y,
+                    });
+                }
+            }
+
+            text_layout.height()
+        };
+
+        for (index, block) in self.blocks.iter().enumerate() {
+            if index > 0 {
+                y += block_gap;
+            }
+
+            match block {
+                Block::Heading { level, spans } => {
+                    let size = font_size * HEADING_SCALES[(*level as usize - 1).min(5)];
+                    let runs: Vec<_> = spans
+                        .iter()
+                        .map(|span| {
+                            let style = SpanStyle {
+                                bold: true,
+                                ..span.style
+                            };
+                            (span.text.as_str(), style, size)
+                        })
+                        .collect();
+
+                    y += append(&mut output, &runs, 0.0, y);
+                }
+                Block::Paragraph(spans) => {
+                    let runs: Vec<_> = spans
+                        .iter()
+                        .map(|span| (span.text.as_str(), span.style, font_size))
+                        .collect();
+
+                    y += append(&mut output, &runs, 0.0, y);
+                }
+                Block::ListItem {
+                    marker,
+                    depth,
+                    spans,
+                } => {
+                    let x = *depth as f32 * indent;
+                    let runs: Vec<_> = spans
+                        .iter()
+                        .map(|span| (span.text.as_str(), span.style, font_size))
+                        .collect();
+                    let marker = [(marker.as_str(), SpanStyle::default(), font_size)];
+
+                    let marker_height = append(&mut output, &marker, x, y);
+                    y += append(&mut output, &runs, x + indent, y).max(marker_height);
+                }
+                Block::Code(text) => {
+                    let style = SpanStyle {
+                        code: true,
+                        ..SpanStyle::default()
+                    };
+                    let top = y;
+
+                    y += block_gap;
+                    y += append(
+                        &mut output,
+                        &[(text.as_str(), style, font_size)],
+                        block_gap,
+                        y,
+                    );
+                    y += block_gap;
+                    output.code_blocks.push((top, y));
+                }
+            }
+        }
+
+        output.height = y;
+        output
+    }
+}
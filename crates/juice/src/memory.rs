@@ -0,0 +1,136 @@
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+/// Where the kernel reports memory pressure stall information, on Linux 4.20 and later
+const PSI_PATH: &str = "/proc/pressure/memory";
+/// Share of the last 10 seconds some task spent stalled on memory, as a percentage,
+/// at which caches are trimmed
+const MODERATE_STALL: f32 = 10.0;
+const CRITICAL_STALL: f32 = 40.0;
+
+/// How hard to trim caches, from `Renderer::trim_memory` or the kernel's memory
+/// pressure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrimLevel {
+    /// Cut caches to half their budget, dropping what's gone longest without being drawn
+    Moderate,
+    /// Drop every cache and preloaded asset. What's on screen is decoded again the next
+    /// time it's drawn.
+    Critical,
+}
+
+impl TrimLevel {
+    /// Parse `moderate` or `critical`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "moderate" => Some(TrimLevel::Moderate),
+            "critical" => Some(TrimLevel::Critical),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TrimLevel::Moderate => "moderate",
+            TrimLevel::Critical => "critical",
+        }
+    }
+}
+
+/// Bytes the renderer's caches can hold before the least recently drawn are dropped.
+/// Every field is optional in JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryBudget {
+    /// Decoded images, SVG and sprite rasters, and cached subtrees
+    #[serde(default = "default_rasters")]
+    pub rasters: usize,
+    /// Rasterized glyphs
+    #[serde(default = "default_glyphs")]
+    pub glyphs: usize,
+    /// Full-size decoded images, shared by the nodes showing them
+    #[serde(default = "default_images")]
+    pub images: usize,
+}
+
+impl Default for MemoryBudget {
+    fn default() -> Self {
+        MemoryBudget {
+            rasters: default_rasters(),
+            glyphs: default_glyphs(),
+            images: default_images(),
+        }
+    }
+}
+
+fn default_rasters() -> usize {
+    32 * 1024 * 1024
+}
+
+fn default_glyphs() -> usize {
+    2 * 1024 * 1024
+}
+
+fn default_images() -> usize {
+    8 * 1024 * 1024
+}
+
+/// Watches the kernel's pressure stall information for memory, so caches can be trimmed
+/// before the OOM killer steps in.
+pub struct PressureMonitor {
+    path: PathBuf,
+    last_reported: Option<TrimLevel>,
+}
+
+impl PressureMonitor {
+    /// `None` if the kernel doesn't report memory pressure.
+    pub fn open() -> Option<Self> {
+        let path = PathBuf::from(PSI_PATH);
+
+        if !path.exists() {
+            return None;
+        }
+
+        Some(PressureMonitor {
+            path,
+            last_reported: None,
+        })
+    }
+
+    /// The pressure now: `None` below the moderate threshold.
+    pub fn read_level(&self) -> Option<TrimLevel> {
+        let psi = std::fs::read_to_string(&self.path).ok()?;
+        let stall = parse_avg10(&psi)?;
+
+        if stall >= CRITICAL_STALL {
+            Some(TrimLevel::Critical)
+        } else if stall >= MODERATE_STALL {
+            Some(TrimLevel::Moderate)
+        } else {
+            None
+        }
+    }
+
+    /// Read the pressure, returning a level when it's risen to one since the last read.
+    /// Staying at a level reports it once; dropping below it and rising again reports it
+    /// again.
+    pub fn poll(&mut self) -> Option<TrimLevel> {
+        let level = self.read_level();
+        let rose = level > self.last_reported;
+
+        self.last_reported = level;
+        level.filter(|_| rose)
+    }
+}
+
+/// `avg10` from the `some` line, like `some avg10=1.53 avg60=0.87 avg300=0.21 total=...`.
+fn parse_avg10(psi: &str) -> Option<f32> {
+    psi.lines()
+        .find(|line| line.starts_with("some "))?
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("avg10="))?
+        .parse()
+        .ok()
+}
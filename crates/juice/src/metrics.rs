@@ -0,0 +1,266 @@
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rquickjs::function::{Func, Opt};
+use rquickjs::{Ctx, FromJs, Object, Value};
+
+use crate::engine::JsModule;
+
+/// Settings for the `metrics` queue.
+#[derive(Debug, Clone)]
+pub struct MetricsConfig {
+    /// File the queue is kept in, one JSON event per line
+    pub path: PathBuf,
+    /// Largest the queue may grow to on disk. The oldest events are dropped to make room.
+    pub max_bytes: usize,
+    /// Most events handed to the transport at once
+    pub batch_size: usize,
+}
+
+impl MetricsConfig {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        MetricsConfig {
+            path: path.into(),
+            max_bytes: 1024 * 1024,
+            batch_size: 50,
+        }
+    }
+}
+
+/// How often the queue's file is rewritten after old events were dropped to make room
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Host callback uploading a batch of events, each a JSON object with `event`, `props`
+/// and `timestamp` (milliseconds since the epoch). Returns whether the batch was
+/// accepted; rejected batches stay queued for the next flush. It's called on the queue's
+/// own thread, so it can block on the network without holding up rendering.
+pub type MetricsTransport = Box<dyn FnMut(&[String]) -> bool + Send>;
+
+/// A single unit of work for the queue's thread.
+enum Request {
+    Track(String),
+    Flush,
+}
+
+struct MetricsQueue {
+    config: MetricsConfig,
+    transport: MetricsTransport,
+    events: VecDeque<String>,
+    /// Bytes the events take on disk, counting newlines
+    bytes: usize,
+    /// Whether events were dropped since the file was last rewritten, so it no longer
+    /// matches the queue
+    dirty: bool,
+}
+
+impl MetricsQueue {
+    fn push(&mut self, event: String) {
+        let size = event.len() + 1;
+
+        if size > self.config.max_bytes {
+            return;
+        }
+
+        while self.bytes + size > self.config.max_bytes
+            && let Some(oldest) = self.events.pop_front()
+        {
+            self.bytes -= oldest.len() + 1;
+            self.dirty = true;
+        }
+
+        self.bytes += size;
+        self.events.push_back(event);
+
+        // Once the file is stale, the next save writes this event along with the rest
+        if !self.dirty {
+            self.append();
+        }
+    }
+
+    /// Send queued events in batches until the transport rejects one or the queue is
+    /// empty.
+    fn flush(&mut self) {
+        let mut sent = 0;
+
+        while !self.events.is_empty() {
+            let count = self.config.batch_size.max(1).min(self.events.len());
+            let batch: Vec<String> = self.events.range(..count).cloned().collect();
+
+            if !(self.transport)(&batch) {
+                break;
+            }
+
+            for event in self.events.drain(..count) {
+                self.bytes -= event.len() + 1;
+            }
+
+            sent += count;
+        }
+
+        if sent > 0 || self.dirty {
+            self.save();
+        }
+    }
+
+    /// Add the newest event to the end of the file.
+    fn append(&self) {
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.config.path)
+            .and_then(|mut file| writeln!(file, "{}", self.events.back().unwrap()));
+
+        if let Err(err) = result {
+            eprintln!("Error writing metrics: {}", err);
+        }
+    }
+
+    /// Rewrite the whole file after events were removed, replacing it atomically so a
+    /// crash can't leave it half written.
+    fn save(&mut self) {
+        self.dirty = false;
+
+        let temp = self.config.path.with_extension("tmp");
+        let contents: String = self
+            .events
+            .iter()
+            .map(|event| event.clone() + "\n")
+            .collect();
+        let result = fs::write(&temp, contents).and_then(|_| fs::rename(&temp, &self.config.path));
+
+        if let Err(err) = result {
+            eprintln!("Error writing metrics: {}", err);
+        }
+    }
+}
+
+fn run_worker(mut queue: MetricsQueue, rx: mpsc::Receiver<Request>, pending: Arc<AtomicUsize>) {
+    loop {
+        match rx.recv_timeout(SAVE_INTERVAL) {
+            Ok(Request::Track(event)) => queue.push(event),
+            Ok(Request::Flush) => queue.flush(),
+            Err(RecvTimeoutError::Timeout) if queue.dirty => queue.save(),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                if queue.dirty {
+                    queue.save();
+                }
+                break;
+            }
+        }
+
+        pending.store(queue.events.len(), Ordering::Relaxed);
+    }
+}
+
+/// Exposes a `metrics` global to JS for usage analytics. `metrics.track(event, props)`
+/// queues an event on disk, so it survives restarts and time offline; the host calls
+/// `flush` when it's online to upload queued events through its transport. The queue
+/// lives on its own thread, so neither tracking nor uploading blocks rendering.
+///
+/// Register a clone with the renderer and keep one to flush.
+#[derive(Clone)]
+pub struct Metrics {
+    tx: mpsc::Sender<Request>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl Metrics {
+    /// Open the queue, picking up events left from earlier runs.
+    pub fn new(
+        config: MetricsConfig,
+        transport: impl FnMut(&[String]) -> bool + Send + 'static,
+    ) -> Self {
+        let events: VecDeque<String> = fs::read_to_string(&config.path)
+            .map(|contents| {
+                contents
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let bytes = events.iter().map(|event| event.len() + 1).sum();
+        let pending = Arc::new(AtomicUsize::new(events.len()));
+
+        let queue = MetricsQueue {
+            config,
+            transport: Box::new(transport),
+            events,
+            bytes,
+            dirty: false,
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let worker_pending = pending.clone();
+        std::thread::spawn(move || run_worker(queue, rx, worker_pending));
+
+        Metrics { tx, pending }
+    }
+
+    /// Queue an event, as `metrics.track` does. `props` is a JSON value.
+    pub fn track(&self, event: &str, props: serde_json::Value) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis() as u64);
+        let event = serde_json::json!({
+            "event": event,
+            "props": props,
+            "timestamp": timestamp,
+        });
+
+        let _ = self.tx.send(Request::Track(event.to_string()));
+    }
+
+    /// Upload queued events, oldest first, on the queue's thread.
+    pub fn flush(&self) {
+        let _ = self.tx.send(Request::Flush);
+    }
+
+    /// How many events were waiting to be sent when the queue's thread last handled a
+    /// request.
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::Relaxed)
+    }
+}
+
+/// Event properties from JS, as JSON.
+struct Props(serde_json::Value);
+
+impl<'js> FromJs<'js> for Props {
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> rquickjs::Result<Self> {
+        let json = match ctx.json_stringify(value)? {
+            Some(json) => json.to_string()?,
+            None => return Ok(Props(serde_json::Value::Null)),
+        };
+
+        Ok(Props(serde_json::from_str(&json).unwrap_or_default()))
+    }
+}
+
+impl JsModule for Metrics {
+    fn register(&self, ctx: &Ctx<'_>) {
+        let metrics = Object::new(ctx.clone()).unwrap();
+
+        let this = self.clone();
+        metrics
+            .set(
+                "track",
+                Func::from(move |event: String, props: Opt<Props>| {
+                    this.track(
+                        &event,
+                        props.0.map_or(serde_json::Value::Null, |props| props.0),
+                    );
+                }),
+            )
+            .unwrap();
+
+        ctx.globals().set("metrics", metrics).unwrap();
+    }
+}
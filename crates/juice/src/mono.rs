@@ -0,0 +1,351 @@
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+use crate::damage::{DamageRect, MAX_DAMAGE_RECTS, merge_rects};
+
+/// Pixel format of a grayscale panel.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MonoFormat {
+    /// One byte per pixel, quantized to `MonoConfig::levels` shades
+    #[default]
+    Gray8,
+    /// One bit per pixel, 1 for white
+    Mono1,
+}
+
+/// How luma is quantized to the available shades.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Dither {
+    /// Round each pixel on its own: crisp text, banded gradients
+    #[default]
+    Threshold,
+    /// 4x4 Bayer matrix. Stable across partial refreshes since it only depends on position
+    Ordered,
+    /// Error diffusion within each converted rect: the smoothest gradients, but a region
+    /// can shift slightly when redrawn on its own
+    FloydSteinberg,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonoConfig {
+    #[serde(default)]
+    pub format: MonoFormat,
+    #[serde(default)]
+    pub dither: Dither,
+    /// Luma (0–255) at or above which a pixel is white when thresholding to 1 bit
+    #[serde(default = "default_threshold")]
+    pub threshold: u8,
+    /// Shades for `Gray8`, e.g. 16 for a 4-bit e-paper panel. `Mono1` always has 2.
+    #[serde(default = "default_levels")]
+    pub levels: u16,
+    /// Swap black and white, for panels that light up on 0
+    #[serde(default)]
+    pub invert: bool,
+}
+
+fn default_threshold() -> u8 {
+    128
+}
+
+fn default_levels() -> u16 {
+    256
+}
+
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// ITU-R BT.601 luma of an XRGB8888 pixel.
+#[inline(always)]
+pub fn luma(pixel: u32) -> u8 {
+    let r = (pixel >> 16) & 0xFF;
+    let g = (pixel >> 8) & 0xFF;
+    let b = pixel & 0xFF;
+    ((r * 77 + g * 150 + b * 29) >> 8) as u8
+}
+
+/// Converts XRGB8888 pixels for grayscale and monochrome panels.
+pub struct MonoConverter {
+    config: MonoConfig,
+}
+
+impl MonoConverter {
+    pub fn new(config: MonoConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn format(&self) -> MonoFormat {
+        self.config.format
+    }
+
+    fn levels(&self) -> u16 {
+        match self.config.format {
+            MonoFormat::Mono1 => 2,
+            MonoFormat::Gray8 => self.config.levels.clamp(2, 256),
+        }
+    }
+
+    /// Quantize a rect of an XRGB8888 buffer `width` pixels wide to one gray byte per
+    /// pixel, rows `rect.width` long.
+    pub fn gray8(&self, pixels: &[u32], width: u32, rect: DamageRect) -> Vec<u8> {
+        let levels = self.levels();
+        let step = 255.0 / (levels - 1) as f32;
+        let mut out = Vec::with_capacity((rect.width * rect.height) as usize);
+
+        // Error carried to this row and the next, for Floyd-Steinberg
+        let mut errors = vec![0.0f32; rect.width as usize + 2];
+        let mut next_errors = vec![0.0f32; rect.width as usize + 2];
+
+        for y in rect.y..rect.y + rect.height {
+            let row = (y * width) as usize;
+
+            for (i, x) in (rect.x..rect.x + rect.width).enumerate() {
+                let value = luma(pixels[row + x as usize]) as f32;
+
+                let quantized = match self.config.dither {
+                    Dither::Threshold if levels == 2 => {
+                        if value >= self.config.threshold as f32 {
+                            255.0
+                        } else {
+                            0.0
+                        }
+                    }
+                    Dither::Threshold => (value / step).round() * step,
+                    Dither::Ordered => {
+                        let bias = BAYER_4X4[(y % 4) as usize][(x % 4) as usize] as f32;
+                        let offset = ((bias + 0.5) / 16.0 - 0.5) * step;
+                        ((value + offset) / step).round() * step
+                    }
+                    Dither::FloydSteinberg => {
+                        let wanted = value + errors[i + 1];
+                        let quantized = (wanted / step).round() * step;
+                        let error = wanted - quantized.clamp(0.0, 255.0);
+
+                        errors[i + 2] += error * 7.0 / 16.0;
+                        next_errors[i] += error * 3.0 / 16.0;
+                        next_errors[i + 1] += error * 5.0 / 16.0;
+                        next_errors[i + 2] += error / 16.0;
+                        quantized
+                    }
+                };
+
+                let gray = quantized.clamp(0.0, 255.0) as u8;
+                out.push(if self.config.invert { 255 - gray } else { gray });
+            }
+
+            std::mem::swap(&mut errors, &mut next_errors);
+            next_errors.fill(0.0);
+        }
+
+        out
+    }
+
+    /// Quantize a rect to 1 bit per pixel, MSB first, each row padded to a whole byte.
+    pub fn mono1(&self, pixels: &[u32], width: u32, rect: DamageRect) -> Vec<u8> {
+        let gray = self.gray8(pixels, width, rect);
+        let stride = rect.width.div_ceil(8) as usize;
+        let mut out = vec![0u8; stride * rect.height as usize];
+
+        for (y, row) in gray.chunks_exact(rect.width.max(1) as usize).enumerate() {
+            for (x, &value) in row.iter().enumerate() {
+                if value >= 128 {
+                    out[y * stride + x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Quantize a rect in place, writing the shades back as XRGB8888 for backends that take
+    /// color pixels but drive a grayscale panel.
+    pub fn apply_xrgb(&self, pixels: &mut [u32], width: u32, rect: DamageRect) {
+        let gray = self.gray8(pixels, width, rect);
+
+        for (i, y) in (rect.y..rect.y + rect.height).enumerate() {
+            let row = (y * width + rect.x) as usize;
+            let shades = &gray[i * rect.width as usize..(i + 1) * rect.width as usize];
+
+            for (pixel, &value) in pixels[row..row + rect.width as usize]
+                .iter_mut()
+                .zip(shades)
+            {
+                let value = value as u32;
+                *pixel = 0xFF00_0000 | value << 16 | value << 8 | value;
+            }
+        }
+    }
+}
+
+/// Whether a panel should update just the damaged area or redraw everything, and how.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RefreshMode {
+    /// Quickest, black and white only on e-paper. For small, frequently changing content
+    /// like a clock's digits
+    Fast,
+    /// Updates the damaged area, but e-paper accumulates ghosting over repeated partial
+    /// updates
+    Partial,
+    /// Slow and flashes on e-paper, but clears ghosting
+    Full,
+}
+
+impl RefreshMode {
+    /// Parse a node's `refreshMode` hint.
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "fast" => Some(RefreshMode::Fast),
+            "partial" => Some(RefreshMode::Partial),
+            "full" => Some(RefreshMode::Full),
+            _ => None,
+        }
+    }
+
+    /// The mode for refreshing damage from two frames at once: the more thorough of the two.
+    pub fn combine(self, other: RefreshMode) -> RefreshMode {
+        match (self, other) {
+            (RefreshMode::Full, _) | (_, RefreshMode::Full) => RefreshMode::Full,
+            (RefreshMode::Partial, _) | (_, RefreshMode::Partial) => RefreshMode::Partial,
+            _ => RefreshMode::Fast,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshConfig {
+    /// Minimum milliseconds between refreshes; damage in between is batched into one
+    #[serde(default)]
+    pub min_interval_ms: u64,
+    /// Do a full refresh after this many partial or fast ones; 0 never forces one
+    #[serde(default)]
+    pub full_every: u32,
+    /// Make the next refresh full once this many seconds have passed since the last full
+    /// one; 0 never forces one
+    #[serde(default)]
+    pub full_interval_secs: u64,
+    /// Do a full refresh when the batched damage covers more than this fraction of the
+    /// screen, since a full update costs about the same
+    #[serde(default = "default_full_area")]
+    pub full_area: f32,
+}
+
+fn default_full_area() -> f32 {
+    1.0
+}
+
+impl Default for RefreshConfig {
+    fn default() -> Self {
+        Self {
+            min_interval_ms: 0,
+            full_every: 0,
+            full_interval_secs: 0,
+            full_area: default_full_area(),
+        }
+    }
+}
+
+/// Batches damage for panels with slow refreshes and decides when each refresh should be
+/// fast, partial or full. With the default config every poll refreshes pending damage at
+/// once in the mode it was submitted with.
+pub struct RefreshScheduler {
+    config: RefreshConfig,
+    width: u32,
+    height: u32,
+    pending: Vec<DamageRect>,
+    pending_mode: Option<RefreshMode>,
+    last_refresh: Option<Instant>,
+    /// Panels are cleared when they're initialized, which counts as a full refresh
+    last_full: Instant,
+    partials: u32,
+}
+
+impl RefreshScheduler {
+    pub fn new(config: RefreshConfig, width: u32, height: u32) -> Self {
+        Self {
+            config,
+            width,
+            height,
+            pending: Vec::new(),
+            pending_mode: None,
+            last_refresh: None,
+            last_full: Instant::now(),
+            partials: 0,
+        }
+    }
+
+    /// Queue damage to be refreshed in at least the given mode.
+    pub fn add_damage(&mut self, damage: &[DamageRect], mode: RefreshMode) {
+        if damage.is_empty() {
+            return;
+        }
+
+        self.pending.extend_from_slice(damage);
+        merge_rects(&mut self.pending, MAX_DAMAGE_RECTS);
+
+        self.pending_mode = Some(match self.pending_mode {
+            Some(pending) => pending.combine(mode),
+            None => mode,
+        });
+    }
+
+    /// When the pending damage may be refreshed, or `None` if there isn't any.
+    pub fn next_due(&self) -> Option<Instant> {
+        if self.pending.is_empty() {
+            return None;
+        }
+
+        let interval = Duration::from_millis(self.config.min_interval_ms);
+
+        Some(match self.last_refresh {
+            Some(last) => last + interval,
+            None => Instant::now(),
+        })
+    }
+
+    /// Take the pending damage if a refresh is due, with the mode to refresh it in. A full
+    /// refresh should redraw the whole screen whatever the damage.
+    pub fn poll(&mut self, now: Instant) -> Option<(RefreshMode, Vec<DamageRect>)> {
+        if self.pending.is_empty() {
+            return None;
+        }
+
+        let interval = Duration::from_millis(self.config.min_interval_ms);
+
+        if let Some(last) = self.last_refresh
+            && now < last + interval
+        {
+            return None;
+        }
+
+        let damage = std::mem::take(&mut self.pending);
+        let damaged_area: u64 = damage
+            .iter()
+            .map(|rect| rect.width as u64 * rect.height as u64)
+            .sum();
+        let screen_area = self.width as u64 * self.height as u64;
+
+        let full_interval = Duration::from_secs(self.config.full_interval_secs);
+        let full = (self.config.full_every > 0 && self.partials >= self.config.full_every)
+            || (self.config.full_interval_secs > 0 && now >= self.last_full + full_interval)
+            || damaged_area as f32 > screen_area as f32 * self.config.full_area;
+
+        let mode = match self.pending_mode.take() {
+            _ if full => RefreshMode::Full,
+            Some(mode) => mode,
+            None => RefreshMode::Partial,
+        };
+
+        self.last_refresh = Some(now);
+
+        if mode == RefreshMode::Full {
+            self.partials = 0;
+            self.last_full = now;
+        } else {
+            self.partials += 1;
+        }
+
+        Some((mode, damage))
+    }
+}
@@ -0,0 +1,336 @@
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
+
+use rquickjs::function::Func;
+use rquickjs::{CatchResultExt, Ctx, Function, Value};
+
+use crate::engine::JsModule;
+
+/// Grants every permission, for first-party bundles
+pub const ALL: &str = "*";
+
+/// Copies a global a guarded module defined into a frozen stand-in whose functions check
+/// its permission on each call, so the module's own functions are never reachable from
+/// JS, not even through property descriptors or prototypes. Objects have their methods
+/// checked as `<permission>.<method>`, and objects in them as `<permission>.<key>`;
+/// functions and classes are checked as the permission itself.
+const GUARD_JS: &str = r#"
+(value, permission, allows) => {
+  const check = (name) => {
+    if (!allows(name)) {
+      throw { code: "EPERM", message: name + ": permission denied" };
+    }
+  };
+
+  const skip = new Set(["length", "name", "prototype", "caller", "arguments", "constructor"]);
+  const guarded = new Map();
+
+  // Every key on target and its prototypes, short of the built-in ones every object has
+  const keys = (target) => {
+    const found = new Set();
+
+    for (
+      let o = target;
+      o && o !== Object.prototype && o !== Function.prototype;
+      o = Object.getPrototypeOf(o)
+    ) {
+      Object.getOwnPropertyNames(o).forEach((key) => found.add(key));
+    }
+
+    return [...found].filter((key) => !skip.has(key));
+  };
+
+  const guard = (target, name) => {
+    if (guarded.has(target)) {
+      return guarded.get(target);
+    }
+
+    let copy;
+
+    if (typeof target === "function") {
+      copy = function (...args) {
+        check(name);
+        return new.target
+          ? Reflect.construct(target, args, new.target === copy ? target : new.target)
+          : Reflect.apply(target, this, args);
+      };
+      copy.prototype = target.prototype;
+    } else {
+      copy = {};
+    }
+
+    guarded.set(target, copy);
+
+    for (const key of keys(target)) {
+      const member = target[key];
+      const memberName = name + "." + key;
+
+      if (typeof member === "function") {
+        copy[key] = function (...args) {
+          check(memberName);
+          return Reflect.apply(member, target, args);
+        };
+      } else if (member !== null && typeof member === "object") {
+        copy[key] = guard(member, memberName);
+      } else {
+        copy[key] = member;
+      }
+    }
+
+    return Object.freeze(copy);
+  };
+
+  return guard(value, permission);
+}
+"#;
+
+/// What a bundle is allowed to reach through native modules, by permission name, deny by
+/// default. A permission like `fs` covers every method of the modules guarded with it,
+/// while `fs.readFile` only covers that one. Clones share their grants, so granting or
+/// revoking reaches every renderer given one.
+#[derive(Debug, Clone, Default)]
+pub struct Permissions {
+    granted: Rc<RefCell<BTreeSet<String>>>,
+}
+
+impl Permissions {
+    pub fn new<S: Into<String>>(granted: impl IntoIterator<Item = S>) -> Self {
+        Permissions {
+            granted: Rc::new(RefCell::new(granted.into_iter().map(Into::into).collect())),
+        }
+    }
+
+    /// Every permission, for a bundle that's part of the firmware.
+    pub fn all() -> Self {
+        Permissions::new([ALL])
+    }
+
+    /// The permissions an app's manifest asks for that the host grants it, from grants by
+    /// app id. Returns the ones asked for but not granted too, to warn about.
+    pub fn for_app(
+        id: &str,
+        requested: &[String],
+        grants: &HashMap<String, Vec<String>>,
+    ) -> (Self, Vec<String>) {
+        let granted = grants.get(id).map_or(&[][..], Vec::as_slice);
+        let (allowed, denied): (Vec<String>, Vec<String>) = requested
+            .iter()
+            .cloned()
+            .partition(|permission| covers(granted, permission));
+
+        (Permissions::new(allowed), denied)
+    }
+
+    /// Whether `permission` is granted, directly or by a permission covering it.
+    pub fn allows(&self, permission: &str) -> bool {
+        let granted = self.granted.borrow();
+        granted.contains(ALL)
+            || ancestors(permission).any(|permission| granted.contains(permission))
+    }
+
+    /// Whether `permission` or any permission under it is granted, so a module guarded
+    /// with it has something to offer.
+    pub fn allows_any(&self, permission: &str) -> bool {
+        self.allows(permission)
+            || self
+                .granted
+                .borrow()
+                .iter()
+                .any(|granted| ancestors(granted).any(|parent| parent == permission))
+    }
+
+    pub fn grant(&self, permission: &str) {
+        self.granted.borrow_mut().insert(permission.to_string());
+    }
+
+    /// Take a permission away. Calls check their permission each time, so this applies
+    /// at once, even to modules already registered.
+    pub fn revoke(&self, permission: &str) {
+        self.granted.borrow_mut().remove(permission);
+    }
+
+    pub fn granted(&self) -> Vec<String> {
+        self.granted.borrow().iter().cloned().collect()
+    }
+
+    /// Wrap a module so it's only registered when `permission` or part of it is granted,
+    /// and every call to the globals it defines checks the permission first, throwing an
+    /// `EPERM` error without it.
+    pub fn guard(&self, permission: &str, module: Box<dyn JsModule>) -> Box<dyn JsModule> {
+        Box::new(Guarded {
+            permission: permission.to_string(),
+            permissions: self.clone(),
+            module,
+        })
+    }
+}
+
+/// `permission` and the permissions covering it: `a.b.c`, `a.b` and `a`.
+fn ancestors(permission: &str) -> impl Iterator<Item = &str> {
+    std::iter::successors(Some(permission), |permission| {
+        permission.rsplit_once('.').map(|(parent, _)| parent)
+    })
+}
+
+fn covers(granted: &[String], permission: &str) -> bool {
+    ancestors(permission).any(|permission| granted.iter().any(|granted| granted == permission))
+        || granted.iter().any(|granted| granted == ALL)
+}
+
+/// A module behind a permission, see `Permissions::guard`.
+struct Guarded {
+    permission: String,
+    permissions: Permissions,
+    module: Box<dyn JsModule>,
+}
+
+impl JsModule for Guarded {
+    fn register(&self, ctx: &Ctx<'_>) {
+        if !self.permissions.allows_any(&self.permission) {
+            return;
+        }
+
+        let globals = ctx.globals();
+        let before: HashMap<String, Value> = globals.props().flatten().collect();
+
+        self.module.register(ctx);
+
+        let guard: Function = match ctx.eval(GUARD_JS).catch(ctx) {
+            Ok(guard) => guard,
+            Err(err) => {
+                eprintln!("Error guarding {}: {}", self.permission, err);
+                return;
+            }
+        };
+
+        let defined: Vec<(String, Value)> = globals
+            .props()
+            .flatten()
+            .filter(|(key, value): &(String, Value)| {
+                value.is_object() && before.get(key) != Some(value)
+            })
+            .collect();
+
+        for (key, value) in defined {
+            let permissions = self.permissions.clone();
+            let allows = Func::from(move |name: String| permissions.allows(&name));
+
+            match guard.call::<_, Value>((value, self.permission.as_str(), allows)) {
+                Ok(guarded) => globals.set(key, guarded).unwrap(),
+                Err(err) => eprintln!("Error guarding {}: {}", key, err),
+            }
+        }
+    }
+
+    fn set_profile(&self, profile: &str) {
+        self.module.set_profile(profile);
+    }
+}
+
+impl JsModule for Permissions {
+    fn register(&self, ctx: &Ctx<'_>) {
+        let permissions = rquickjs::Object::new(ctx.clone()).unwrap();
+
+        let this = self.clone();
+        permissions
+            .set(
+                "has",
+                Func::from(move |permission: String| this.allows(&permission)),
+            )
+            .unwrap();
+
+        let this = self.clone();
+        permissions
+            .set("granted", Func::from(move || this.granted()))
+            .unwrap();
+
+        ctx.globals().set("permissions", permissions).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rquickjs::{Context, Runtime};
+
+    use super::*;
+
+    /// A module with a global object of native methods, like `fs`.
+    struct Gpio;
+
+    impl JsModule for Gpio {
+        fn register(&self, ctx: &Ctx<'_>) {
+            let gpio = rquickjs::Object::new(ctx.clone()).unwrap();
+            gpio.set("read", Func::from(|pin: i32| pin)).unwrap();
+            gpio.set("write", Func::from(|pin: i32| pin)).unwrap();
+            ctx.globals().set("gpio", gpio).unwrap();
+        }
+    }
+
+    /// Register `Gpio` behind `permissions` and run `js` against it.
+    fn eval(permissions: &Permissions, js: &str) -> String {
+        let runtime = Runtime::new().unwrap();
+        let context = Context::full(&runtime).unwrap();
+        let module = permissions.guard("gpio", Box::new(Gpio));
+
+        context.with(|ctx| {
+            module.register(&ctx);
+            let result = ctx.eval::<String, _>(js).catch(&ctx);
+            result.unwrap_or_else(|err| panic!("{}", err))
+        })
+    }
+
+    const CALL: &str = r#"
+        var call = (f) => {
+          try {
+            return String(f());
+          } catch (e) {
+            return e.code;
+          }
+        };
+    "#;
+
+    #[test]
+    fn allows_granted_methods_only() {
+        let permissions = Permissions::new(["gpio.read"]);
+        let js = format!("{CALL} [call(() => gpio.read(1)), call(() => gpio.write(1))].join()");
+
+        assert_eq!(eval(&permissions, &js), "1,EPERM");
+    }
+
+    #[test]
+    fn denies_through_descriptors_and_prototypes() {
+        let permissions = Permissions::new(["gpio.read"]);
+        let js = format!(
+            r#"{CALL} [
+                call(() => Object.getOwnPropertyDescriptor(gpio, "write").value(1)),
+                call(() => Object.getOwnPropertyDescriptors(gpio).write.value(1)),
+                ...Reflect.ownKeys(gpio).map((key) => call(() => gpio[key](1))),
+                call(() => Object.getPrototypeOf(gpio) === Object.prototype),
+                call(() => Object.isFrozen(gpio)),
+            ].join()"#
+        );
+
+        assert_eq!(eval(&permissions, &js), "EPERM,EPERM,1,EPERM,true,true");
+    }
+
+    #[test]
+    fn revoke_applies_to_registered_modules() {
+        let permissions = Permissions::new(["gpio"]);
+        let runtime = Runtime::new().unwrap();
+        let context = Context::full(&runtime).unwrap();
+        let module = permissions.guard("gpio", Box::new(Gpio));
+
+        context.with(|ctx| {
+            module.register(&ctx);
+            let js = format!("{CALL} call(() => gpio.read(2))");
+            assert_eq!(ctx.eval::<String, _>(js.as_str()).unwrap(), "2");
+
+            permissions.revoke("gpio");
+            let js = format!(
+                r#"{CALL} call(() => Object.getOwnPropertyDescriptor(gpio, "read").value(2))"#
+            );
+            assert_eq!(ctx.eval::<String, _>(js.as_str()).unwrap(), "EPERM");
+        });
+    }
+}
@@ -0,0 +1,356 @@
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::scroll;
+
+/// Rows a wheel shows when it isn't given a height: the selection and two either side.
+pub const VISIBLE_ROWS: f32 = 5.0;
+
+/// Speed in items/s below which a spinning wheel snaps to the nearest item.
+const SNAP_VELOCITY: f32 = 3.0;
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Height of a wheel row for a font size.
+pub fn row_height(font_size: f32) -> f32 {
+    font_size * 2.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PickerKind {
+    /// One wheel of arbitrary items, from the `items` attribute
+    Wheel,
+    /// Year, month and day wheels with a `YYYY-MM-DD` value
+    Date,
+    /// Hour and minute wheels with a 24-hour `HH:MM` value
+    Time,
+}
+
+/// One spinning column of a picker.
+#[derive(Debug, Default)]
+pub struct Wheel {
+    pub items: Vec<String>,
+    /// Scroll position in items; whole numbers centre an item
+    pub position: f32,
+    /// Items/s while spinning after a fling
+    pub velocity: f32,
+    /// Item to spring to instead of the nearest, e.g. after tapping a row
+    pub snap_to: Option<usize>,
+}
+
+impl Wheel {
+    fn new(items: Vec<String>) -> Self {
+        Wheel {
+            items,
+            ..Default::default()
+        }
+    }
+
+    fn max_position(&self) -> f32 {
+        self.items.len().saturating_sub(1) as f32
+    }
+
+    /// The item nearest the centre.
+    pub fn selected(&self) -> usize {
+        self.position.round().clamp(0.0, self.max_position()) as usize
+    }
+
+    /// Centre an item immediately.
+    pub fn select(&mut self, index: usize) {
+        self.position = index.min(self.items.len().saturating_sub(1)) as f32;
+        self.velocity = 0.0;
+        self.snap_to = None;
+    }
+
+    /// Move by a number of items under the finger, with movement past the ends halved
+    /// for a rubber-band feel.
+    pub fn drag(&mut self, items: f32) {
+        self.velocity = 0.0;
+        self.snap_to = None;
+        self.position = scroll::drag_offset(self.position, items, (0.0, self.max_position()), true);
+    }
+
+    /// Advance a released wheel by `dt` seconds: coast while it's fast, then spring onto
+    /// an item. Returns whether it has settled.
+    pub fn step(&mut self, dt: f32, deceleration: f32) -> bool {
+        let bounds = (0.0, self.max_position());
+
+        if self.snap_to.is_none()
+            && scroll::overscroll(self.position, bounds) == 0.0
+            && self.velocity.abs() > SNAP_VELOCITY
+        {
+            self.velocity *= (-deceleration * dt).exp();
+            self.position += self.velocity * dt;
+            return false;
+        }
+
+        let target = match self.snap_to {
+            Some(index) => index as f32,
+            None => self.position.round(),
+        }
+        .clamp(bounds.0, bounds.1);
+
+        self.velocity = scroll::spring_velocity(self.velocity, self.position - target, dt);
+        self.position += self.velocity * dt;
+
+        if (self.position - target).abs() < 0.01 && self.velocity.abs() < 0.5 {
+            self.select(target as usize);
+            return true;
+        }
+
+        false
+    }
+}
+
+/// State of a `wheelpicker`, `datepicker` or `timepicker` node.
+#[derive(Debug)]
+pub struct Picker {
+    pub kind: PickerKind,
+    pub wheels: Vec<Wheel>,
+    min_year: i32,
+    max_year: i32,
+    minute_step: u32,
+    /// The value as JS last saw it, so `Change` only fires when it differs
+    reported: String,
+}
+
+impl Picker {
+    /// A picker showing its first item, today's date (UTC) or midnight.
+    pub fn new(kind: PickerKind) -> Self {
+        let mut picker = Picker {
+            kind,
+            wheels: Vec::new(),
+            min_year: 1900,
+            max_year: 2100,
+            minute_step: 1,
+            reported: String::new(),
+        };
+
+        match kind {
+            PickerKind::Wheel => picker.wheels.push(Wheel::default()),
+            PickerKind::Date => {
+                let (year, month, day) = today();
+                picker.wheels = vec![Wheel::default(), Wheel::default(), Wheel::default()];
+                picker.build_date_wheels(year, month, day);
+            }
+            PickerKind::Time => {
+                let hours = (0..24).map(|h| format!("{:02}", h)).collect();
+                picker.wheels = vec![Wheel::new(hours), Wheel::default()];
+                picker.build_minutes(0);
+            }
+        }
+
+        picker.reported = picker.value();
+        picker
+    }
+
+    /// Replace a wheel picker's items, keeping the selected item if it's still there.
+    pub fn set_items(&mut self, items: Vec<String>) {
+        let previous = self.value();
+        let wheel = &mut self.wheels[0];
+
+        wheel.items = items;
+        let index = wheel.items.iter().position(|item| *item == previous);
+        wheel.select(index.unwrap_or(0));
+        self.reported = self.value();
+    }
+
+    /// Select a value without firing `Change`. Returns false if it doesn't parse or isn't
+    /// one of the items.
+    pub fn set_value(&mut self, value: &str) -> bool {
+        let applied = match self.kind {
+            PickerKind::Wheel => match self.wheels[0].items.iter().position(|i| i == value) {
+                Some(index) => {
+                    self.wheels[0].select(index);
+                    true
+                }
+                None => false,
+            },
+            PickerKind::Date => match parse_date(value) {
+                Some((year, month, day)) => {
+                    self.min_year = self.min_year.min(year);
+                    self.max_year = self.max_year.max(year);
+                    self.build_date_wheels(year, month, day);
+                    true
+                }
+                None => false,
+            },
+            PickerKind::Time => match parse_time(value) {
+                Some((hour, minute)) => {
+                    self.wheels[0].select(hour as usize);
+                    self.build_minutes(minute);
+                    true
+                }
+                None => false,
+            },
+        };
+
+        self.reported = self.value();
+        applied
+    }
+
+    /// The selected value: the item for a wheel picker, `YYYY-MM-DD` or `HH:MM`.
+    pub fn value(&self) -> String {
+        match self.kind {
+            PickerKind::Wheel => self.wheels[0]
+                .items
+                .get(self.wheels[0].selected())
+                .cloned()
+                .unwrap_or_default(),
+            PickerKind::Date => {
+                let (year, month, day) = self.selected_date();
+                format!("{:04}-{:02}-{:02}", year, month, day)
+            }
+            PickerKind::Time => format!(
+                "{:02}:{:02}",
+                self.wheels[0].selected(),
+                self.selected_minute()
+            ),
+        }
+    }
+
+    /// Index of the selected item of the first wheel.
+    pub fn selected_index(&self) -> usize {
+        self.wheels[0].selected()
+    }
+
+    pub fn set_year_range(&mut self, min: Option<i32>, max: Option<i32>) {
+        let (year, month, day) = self.selected_date();
+        self.min_year = min.unwrap_or(self.min_year);
+        self.max_year = max.unwrap_or(self.max_year).max(self.min_year);
+
+        if self.kind == PickerKind::Date {
+            self.build_date_wheels(year.clamp(self.min_year, self.max_year), month, day);
+            self.reported = self.value();
+        }
+    }
+
+    pub fn set_minute_step(&mut self, step: u32) {
+        self.minute_step = step.clamp(1, 30);
+
+        if self.kind == PickerKind::Time {
+            self.build_minutes(self.selected_minute());
+            self.reported = self.value();
+        }
+    }
+
+    /// Called when a wheel comes to rest. Updates dependent wheels, like the number of
+    /// days in the selected month, and returns the value if it changed since JS last
+    /// saw it.
+    pub fn settle(&mut self) -> Option<String> {
+        if self.kind == PickerKind::Date && self.wheels.iter().all(|w| w.velocity == 0.0) {
+            let (year, month, day) = self.selected_date();
+            self.build_date_wheels(year, month, day);
+        }
+
+        let value = self.value();
+
+        if value == self.reported {
+            return None;
+        }
+
+        self.reported = value.clone();
+        Some(value)
+    }
+
+    fn selected_date(&self) -> (i32, u32, u32) {
+        if self.kind != PickerKind::Date {
+            return (self.min_year, 1, 1);
+        }
+
+        let year = self.min_year + self.wheels[0].selected() as i32;
+        let month = self.wheels[1].selected() as u32 + 1;
+        let day = (self.wheels[2].selected() as u32 + 1).min(days_in_month(year, month));
+        (year, month, day)
+    }
+
+    fn selected_minute(&self) -> u32 {
+        self.wheels[1].selected() as u32 * self.minute_step
+    }
+
+    fn build_date_wheels(&mut self, year: i32, month: u32, day: u32) {
+        let years = (self.min_year..=self.max_year)
+            .map(|y| y.to_string())
+            .collect();
+        let days = (1..=days_in_month(year, month))
+            .map(|d| d.to_string())
+            .collect();
+
+        for (wheel, (items, index)) in self.wheels.iter_mut().zip([
+            (years, (year - self.min_year) as usize),
+            (
+                MONTHS.iter().map(|m| m.to_string()).collect(),
+                month as usize - 1,
+            ),
+            (days, day as usize - 1),
+        ]) {
+            if wheel.items != items {
+                wheel.items = items;
+            }
+            wheel.select(index);
+        }
+    }
+
+    fn build_minutes(&mut self, minute: u32) {
+        let step = self.minute_step;
+        self.wheels[1].items = (0..60)
+            .step_by(step as usize)
+            .map(|m| format!("{:02}", m))
+            .collect();
+        self.wheels[1].select(((minute + step / 2) / step) as usize);
+    }
+}
+
+fn parse_date(value: &str) -> Option<(i32, u32, u32)> {
+    let mut parts = value.trim().splitn(3, '-');
+    let year: i32 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+
+    ((1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day))
+        .then_some((year, month, day))
+}
+
+fn parse_time(value: &str) -> Option<(u32, u32)> {
+    let (hour, minute) = value.trim().split_once(':')?;
+    let hour: u32 = hour.parse().ok()?;
+    let minute: u32 = minute.parse().ok()?;
+
+    (hour < 24 && minute < 60).then_some((hour, minute))
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Today's UTC date, from days since the epoch using Howard Hinnant's civil calendar
+/// algorithm.
+fn today() -> (i32, u32, u32) {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+    (year, month, day)
+}
+
+/// A picker wheel coasting or snapping after release.
+pub struct WheelSpin {
+    pub node_id: taffy::NodeId,
+    pub wheel: usize,
+    pub last_step: Instant,
+}
@@ -0,0 +1,108 @@
+use std::time::{Duration, Instant};
+
+/// The kind of device behind a pointer event.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PointerTool {
+    #[default]
+    Touch,
+    Pen,
+    Eraser,
+    Mouse,
+}
+
+impl PointerTool {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PointerTool::Touch => "touch",
+            PointerTool::Pen => "pen",
+            PointerTool::Eraser => "eraser",
+            PointerTool::Mouse => "mouse",
+        }
+    }
+}
+
+/// Device state sent to JS alongside a pointer event's position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pointer {
+    pub tool: PointerTool,
+    /// Normalized to 0.0–1.0. Devices that can't sense pressure report 0.5 while pressed
+    /// and 0.0 otherwise, as browsers do.
+    pub pressure: f32,
+}
+
+impl Pointer {
+    pub fn new(tool: PointerTool, pressure: f32) -> Self {
+        Self {
+            tool,
+            pressure: pressure.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Default for Pointer {
+    fn default() -> Self {
+        Self {
+            tool: PointerTool::Touch,
+            pressure: 0.5,
+        }
+    }
+}
+
+/// Taps within `CORNER_TAP_WINDOW` that complete a corner gesture
+const CORNER_TAPS: usize = 5;
+const CORNER_TAP_WINDOW: Duration = Duration::from_secs(3);
+/// Size of the square in the corner counting towards the gesture
+const CORNER_SIZE: f32 = 48.0;
+
+/// A corner of the screen watched by `CornerTaps`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+}
+
+/// Spots five quick taps in a screen corner, for opening built-in overlays on devices
+/// with nothing but a touchscreen. Taps still reach the UI underneath; only the one
+/// completing the gesture should be consumed.
+#[derive(Debug, Clone)]
+pub struct CornerTaps {
+    corner: Corner,
+    taps: Vec<Instant>,
+}
+
+impl CornerTaps {
+    pub fn new(corner: Corner) -> Self {
+        CornerTaps {
+            corner,
+            taps: Vec::new(),
+        }
+    }
+
+    /// Count a press. Returns whether it completed the gesture.
+    pub fn press(&mut self, x: f32, y: f32, screen_width: f32, screen_height: f32) -> bool {
+        let now = Instant::now();
+        self.taps
+            .retain(|&tap| now.duration_since(tap) < CORNER_TAP_WINDOW);
+
+        let in_corner = match self.corner {
+            Corner::TopLeft => x < CORNER_SIZE && y < CORNER_SIZE,
+            Corner::TopRight => x >= screen_width - CORNER_SIZE && y < CORNER_SIZE,
+            Corner::BottomLeft => x < CORNER_SIZE && y >= screen_height - CORNER_SIZE,
+        };
+
+        if !in_corner {
+            self.taps.clear();
+            return false;
+        }
+
+        self.taps.push(now);
+
+        if self.taps.len() < CORNER_TAPS {
+            return false;
+        }
+
+        self.taps.clear();
+        true
+    }
+}
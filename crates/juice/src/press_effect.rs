@@ -0,0 +1,119 @@
+use std::time::{Duration, Instant};
+
+use taffy::NodeId;
+
+/// Time to reach the fully pressed look.
+const PRESS_DURATION: Duration = Duration::from_millis(80);
+
+/// Time to return to normal after release.
+const RELEASE_DURATION: Duration = Duration::from_millis(150);
+
+/// Time for a ripple to spread across the whole node.
+const RIPPLE_DURATION: Duration = Duration::from_millis(350);
+
+/// How much `scale` shrinks a node by when fully pressed.
+pub const PRESSED_SCALE: f32 = 0.95;
+
+/// Opacity of the `highlight` tint when fully pressed.
+pub const HIGHLIGHT_ALPHA: f32 = 0.12;
+
+/// Opacity of a `ripple` before it fades.
+pub const RIPPLE_ALPHA: f32 = 0.2;
+
+/// Feedback drawn natively while an element is pressed, from the `pressEffect` attribute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PressEffect {
+    /// Shrink slightly towards the centre
+    Scale,
+    /// Tint with the text color
+    Highlight,
+    /// A circle of the text color spreading from the press point
+    Ripple,
+}
+
+impl PressEffect {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "scale" => Some(PressEffect::Scale),
+            "highlight" => Some(PressEffect::Highlight),
+            "ripple" => Some(PressEffect::Ripple),
+            _ => None,
+        }
+    }
+}
+
+/// A press effect animating on one node. Progress comes from the clock rather than
+/// frame counts, so a slow JS handler delays frames but not the animation.
+pub struct PressFeedback {
+    pub node_id: NodeId,
+    pub effect: PressEffect,
+    /// Where the press landed, for ripples
+    pub origin: (f32, f32),
+    pub pressed_at: Instant,
+    pub released_at: Option<Instant>,
+}
+
+impl PressFeedback {
+    pub fn new(node_id: NodeId, effect: PressEffect, origin: (f32, f32), now: Instant) -> Self {
+        PressFeedback {
+            node_id,
+            effect,
+            origin,
+            pressed_at: now,
+            released_at: None,
+        }
+    }
+
+    pub fn release(&mut self, now: Instant) {
+        self.released_at.get_or_insert(now);
+    }
+
+    /// How pressed the node looks, from 0 (normal) to 1 (fully pressed).
+    pub fn amount(&self, now: Instant) -> f32 {
+        let ramp = |at: Instant| {
+            fraction(
+                at.saturating_duration_since(self.pressed_at),
+                PRESS_DURATION,
+            )
+        };
+
+        match self.released_at {
+            None => ramp(now),
+            Some(released) => {
+                let fade = fraction(now.saturating_duration_since(released), RELEASE_DURATION);
+                ramp(released) * (1.0 - fade)
+            }
+        }
+    }
+
+    /// Opacity of a ripple, which shows fully from the moment of the press and fades
+    /// after release.
+    pub fn ripple_opacity(&self, now: Instant) -> f32 {
+        match self.released_at {
+            None => 1.0,
+            Some(released) => {
+                1.0 - fraction(now.saturating_duration_since(released), RELEASE_DURATION)
+            }
+        }
+    }
+
+    /// How far a ripple has spread, from 0 to 1 (covering the node).
+    pub fn spread(&self, now: Instant) -> f32 {
+        let t = fraction(
+            now.saturating_duration_since(self.pressed_at),
+            RIPPLE_DURATION,
+        );
+        // Ease out so the ripple starts fast
+        1.0 - (1.0 - t) * (1.0 - t)
+    }
+
+    /// Whether the effect has played out, so it can stop being drawn.
+    pub fn finished(&self, now: Instant) -> bool {
+        self.released_at
+            .is_some_and(|released| now.saturating_duration_since(released) >= RELEASE_DURATION)
+    }
+}
+
+fn fraction(elapsed: Duration, total: Duration) -> f32 {
+    (elapsed.as_secs_f32() / total.as_secs_f32()).min(1.0)
+}
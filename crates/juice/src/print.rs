@@ -0,0 +1,101 @@
+use crate::{
+    canvas::{Canvas, RgbColor},
+    damage::DamageRect,
+    mono::{Dither, MonoConfig, MonoConverter, MonoFormat},
+};
+
+/// How subtrees are rendered for printing.
+#[derive(Debug, Clone)]
+pub struct PrintOptions {
+    /// Printable width in dots, e.g. 384 for 58mm or 576 for 80mm paper at 203 dpi. The
+    /// subtree is laid out this wide, and as tall as its content.
+    pub width: u32,
+    pub dither: Dither,
+    /// Luma (0–255) at or above which a dot is left blank when thresholding
+    pub threshold: u8,
+    /// Paper color the subtree is drawn over
+    pub background: RgbColor,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        PrintOptions {
+            width: 576,
+            dither: Dither::Threshold,
+            threshold: 128,
+            background: RgbColor::rgb(255, 255, 255),
+        }
+    }
+}
+
+/// Host callback receiving subtrees printed from JS.
+pub type PrintHandler = Box<dyn FnMut(PrintRaster)>;
+
+/// A rendered subtree as 1 bit per dot, MSB first, 1 for black, each row padded to a
+/// whole byte.
+#[derive(Debug, Clone)]
+pub struct PrintRaster {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// Rows sent per ESC/POS raster command. Printers buffer a whole command, and many
+/// can't hold a tall receipt in one.
+const ESCPOS_BAND_ROWS: u32 = 128;
+
+impl PrintRaster {
+    /// Convert a rendered canvas for printing.
+    pub fn from_canvas(canvas: &Canvas, options: &PrintOptions) -> Self {
+        let converter = MonoConverter::new(MonoConfig {
+            format: MonoFormat::Mono1,
+            dither: options.dither,
+            threshold: options.threshold,
+            levels: 2,
+            invert: true,
+        });
+        let rect = DamageRect {
+            x: 0,
+            y: 0,
+            width: canvas.width,
+            height: canvas.height,
+        };
+
+        PrintRaster {
+            width: canvas.width,
+            height: canvas.height,
+            data: converter.mono1(&canvas.pixels, canvas.width, rect),
+        }
+    }
+
+    pub fn bytes_per_row(&self) -> usize {
+        self.width.div_ceil(8) as usize
+    }
+
+    /// ESC/POS commands printing the raster with `GS v 0`, after resetting the printer
+    /// and optionally followed by a feed and partial cut.
+    pub fn to_escpos(&self, cut: bool) -> Vec<u8> {
+        let stride = self.bytes_per_row();
+        let mut out = Vec::with_capacity(self.data.len() + 16);
+
+        // ESC @: initialize
+        out.extend_from_slice(&[0x1B, 0x40]);
+
+        for band in self.data.chunks(stride.max(1) * ESCPOS_BAND_ROWS as usize) {
+            let rows = band.len() / stride.max(1);
+
+            // GS v 0, normal density, then width in bytes and height in dots, little endian
+            out.extend_from_slice(&[0x1D, 0x76, 0x30, 0x00]);
+            out.extend_from_slice(&(stride as u16).to_le_bytes());
+            out.extend_from_slice(&(rows as u16).to_le_bytes());
+            out.extend_from_slice(band);
+        }
+
+        if cut {
+            // GS V 66 n: feed n dots then partial cut
+            out.extend_from_slice(&[0x1D, 0x56, 0x42, 0x60]);
+        }
+
+        out
+    }
+}
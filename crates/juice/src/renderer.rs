@@ -1,32 +1,149 @@
-use base64::engine::general_purpose;
 use embedded_graphics::{
     pixelcolor::Rgb888,
     prelude::*,
-    primitives::{CornerRadii, PrimitiveStyle, Rectangle, RoundedRectangle},
+    primitives::{
+        Arc, CornerRadii, Line, PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, RoundedRectangle,
+        StrokeAlignment,
+    },
 };
 use fontdue::{Font, FontSettings};
-use resvg::{tiny_skia::Pixmap, usvg::Tree};
 use rquickjs::{
     CatchResultExt, Ctx, Function, Object, Persistent,
     prelude::{Func, MutFn},
 };
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+    time::{Instant, SystemTime},
+};
 use taffy::NodeId;
 
 use crate::{
-    canvas::Canvas,
-    dom::{Dom, NodeKind},
-    engine::{Engine, JsModule},
-    inherited_style::InheritedStyle,
+    a11y::{AnnounceHandler, AnnounceReason, Announcement},
+    animation::AnimatedValues,
+    canvas::{self, Canvas, RgbColor, TextRun},
+    capabilities::Capabilities,
+    chart::{Chart, ChartStyle},
+    clock::Clock,
+    damage::{DamageRect, DamageTracker, MAX_DAMAGE_RECTS, merge_rects},
+    decoder::{self, DecodeJob, Decoder, ImageCache, Preload},
+    diagnostics::{COLOR_BARS, Diagnostics, DiagnosticsLayout},
+    display_filter::DisplayFilter,
+    dom::{Dom, NodeKind, SubtreeCache, SurfaceRect},
+    engine::{Deterministic, Engine, JsModule},
+    font_fallback::{self, FontFace, FontRegistry},
+    gauge::{self, Gauge},
+    glyph_cache,
+    inherited_style::{FontStyle, FontWeight, InheritedStyle, TextAlign, TextOverflow},
+    keypad::{Keypad, KeypadKey},
+    latency::{LatencyRecorder, LatencyStats},
+    log::{LogLevel, LogStore, LogViewer, LogViewerButton},
+    markdown::{Markdown, MarkdownFonts},
+    memory::{MemoryBudget, TrimLevel},
+    mono::RefreshMode,
+    picker::{self, Picker, WheelSpin},
+    pointer::Pointer,
+    press_effect::{self, PressEffect, PressFeedback},
+    print::{PrintHandler, PrintOptions, PrintRaster},
+    scanner::{FrameAnalyzer, Scanner},
+    scroll::{self, DragState, Fling, Momentum, Refresh, ScrollConfig, ScrollDrag, SwipeSnap},
+    skeleton,
+    splash::{Splash, SplashConfig, SplashError},
+    table::{CellStyle, Column, Table},
+    text_input::{EditKey, MASK_CHAR, TextInputState},
+    theme::AutoTheme,
+    transform::Affine,
+    video::VideoFrame,
 };
 
+/// What belongs to the running app rather than the host: interactions in progress, focus,
+/// requests not handled yet and the frames it was shown. `switch_profile` starts the next
+/// bundle with a fresh one, so nothing carries over from the last user's.
+#[derive(Default)]
+struct AppState {
+    video_frames: HashMap<String, VideoFrame>,
+    /// Video sources with a frame `<scanner>` nodes haven't analyzed yet
+    unanalyzed: HashSet<String>,
+    surfaces: Vec<SurfaceRect>,
+    /// The scrollable most recently scrolled, which receives encoder input
+    scroll_target: Option<NodeId>,
+    scroll_drag: Option<ScrollDrag>,
+    fling: Option<Fling>,
+    refresh: Option<Refresh>,
+    swipe_snaps: Vec<SwipeSnap>,
+    wheel_spins: Vec<WheelSpin>,
+    /// Press effects animating, including ones fading out after release
+    press_feedback: Vec<PressFeedback>,
+    /// The node under the current press and its ancestors, shown in their `pressedStyle`
+    pressed: Vec<NodeId>,
+    /// The swipeable row left open, and its offset
+    open_swipe: Option<(NodeId, f32)>,
+    /// The text input receiving typed text and IME composition
+    focused: Option<NodeId>,
+    /// The built-in keypad for the focused input's `inputMode`
+    keypad: Option<Keypad>,
+    /// Nodes JS asked to print, printed on the next tick
+    pending_prints: Rc<RefCell<Vec<NodeId>>>,
+    /// Assets JS asked to preload, handed to the decoder on the next tick
+    pending_preloads: Rc<RefCell<Vec<Preload>>>,
+    /// The theme JS last switched to, applied with `set_theme` on the next tick
+    pending_theme: Rc<RefCell<Option<String>>>,
+    /// Whether JS has called `renderer.ready()`
+    app_ready: Rc<Cell<bool>>,
+    /// Whether the last render drew something that changes every frame, like a skeleton
+    /// shimmer or a playing sprite
+    redraw_continuously: bool,
+}
+
 pub struct Renderer {
     pub engine: Engine,
     pub canvas: Canvas,
     pub dom: Rc<RefCell<Dom>>,
 
     modules: Vec<Box<dyn JsModule>>,
-    fonts: Rc<RefCell<HashMap<String, Font>>>,
+    app: AppState,
+    fonts: Rc<RefCell<FontRegistry>>,
+    decoder: Decoder,
+    /// Decoded images by `src`, kept across decoders and reloads
+    images: ImageCache,
+    analyzers: HashMap<String, Box<dyn FrameAnalyzer>>,
+    /// Receives subtrees printed from JS, with the options to render them with
+    print_handler: Option<(PrintOptions, PrintHandler)>,
+    /// How much the caches can hold before the least recently drawn are dropped
+    memory_budget: MemoryBudget,
+    /// Receives what a screen reader should say, when the host has one
+    announce_handler: Option<AnnounceHandler>,
+    damage_tracker: DamageTracker,
+    damage: Vec<DamageRect>,
+    /// Regions to report as damaged on the next render even if their pixels didn't change
+    invalidated: Rc<RefCell<Vec<Invalidation>>>,
+    refresh_mode: RefreshMode,
+    scroll_config: ScrollConfig,
+    /// Clear the canvas to transparent before each render, for layers over other layers
+    transparent: bool,
+    /// Accessibility filter applied to frames as they're flushed, leaving the canvas as is
+    display_filter: Rc<Cell<DisplayFilter>>,
+    /// Switches theme from ambient light readings, when the host enables it
+    auto_theme: Option<AutoTheme>,
+    /// The on-device log viewer, once the host provides a log store
+    log_viewer: Option<LogViewer>,
+    /// The built-in self-test screen, once the host enables it
+    diagnostics: Option<Diagnostics>,
+    /// Covers the app from startup until it's ready, then fades out
+    splash: Option<Splash>,
+    /// The user profile whose bundle and storage are active
+    profile: Option<String>,
+    /// Seed and start time for reproducible engines, in tests
+    deterministic: Option<Deterministic>,
+    /// Time for timers and animations, shared with the tree and a deterministic engine
+    clock: Clock,
+    /// Input-to-photon latency samples, when the host measures them
+    latency: Rc<RefCell<Option<LatencyRecorder>>>,
+    /// The latency tag of the frame the last render drew, if it showed input
+    latency_tag: Option<u64>,
     event_callback: Rc<RefCell<Option<Persistent<Function<'static>>>>>,
     should_update: Rc<RefCell<bool>>,
 }
@@ -38,13 +155,41 @@ impl Renderer {
         base_style: InheritedStyle,
         modules: Vec<Box<dyn JsModule>>,
     ) -> Self {
+        let images = ImageCache::default();
+        let clock = Clock::default();
+        let mut dom = Dom::new(base_style);
+        dom.set_clock(clock.clone());
+
         let renderer = Self {
             engine: Engine::new(&modules).await,
             canvas,
-            fonts: Rc::new(RefCell::new(fonts)),
-            dom: Rc::new(RefCell::new(Dom::new(base_style))),
+            fonts: Rc::new(RefCell::new(FontRegistry::new(fonts))),
+            dom: Rc::new(RefCell::new(dom)),
+            decoder: Decoder::new(images.clone()),
+            images,
+            analyzers: HashMap::new(),
+            print_handler: None,
+            memory_budget: MemoryBudget::default(),
+            announce_handler: None,
+            damage_tracker: DamageTracker::default(),
+            damage: Vec::new(),
+            invalidated: Rc::new(RefCell::new(Vec::new())),
+            refresh_mode: RefreshMode::Partial,
+            scroll_config: ScrollConfig::default(),
+            transparent: false,
+            display_filter: Rc::new(Cell::new(DisplayFilter::None)),
+            auto_theme: None,
+            log_viewer: None,
+            diagnostics: None,
+            splash: None,
+            profile: None,
+            deterministic: None,
+            clock,
+            latency: Rc::new(RefCell::new(None)),
+            latency_tag: None,
             event_callback: Rc::new(RefCell::new(None)),
             should_update: Rc::new(RefCell::new(false)),
+            app: AppState::default(),
             modules,
         };
 
@@ -59,30 +204,229 @@ impl Renderer {
         renderer
     }
 
-    pub async fn tick(&self) {
+    pub async fn tick(&mut self) {
+        // Input is dispatched as it arrives, before the tick after it
+        if let Some(latency) = &*self.latency.borrow() {
+            latency.dispatched();
+        }
+
+        // Keep drawing once the app is ready, to see when its assets are decoded and to
+        // fade the splash out
+        if self.app.redraw_continuously || (self.splash.is_some() && self.app.app_ready.get()) {
+            *self.should_update.borrow_mut() = true;
+        }
+
+        self.step_refresh().await;
+        self.step_fling().await;
+        self.step_swipes();
+        self.step_wheels().await;
+        self.step_press_feedback();
+        self.step_animations().await;
+        self.step_scanners().await;
+        self.step_preloads();
+        self.step_theme().await;
+        self.step_decodes();
+        self.step_font_instances();
+        self.step_prints();
         self.engine.tick().await;
+        self.step_input_grab().await;
+        self.step_announcements();
+
+        if let Some(viewer) = &mut self.log_viewer
+            && viewer.visible
+            && viewer.take_changed()
+        {
+            *self.should_update.borrow_mut() = true;
+        }
+
+        let now = self.clock.now();
+
+        if self
+            .diagnostics
+            .as_ref()
+            .is_some_and(|diagnostics| diagnostics.needs_sample(now))
+        {
+            let heap = self.engine.heap_used().await;
+
+            if let Some(diagnostics) = &mut self.diagnostics {
+                diagnostics.sample(heap, now);
+            }
+
+            *self.should_update.borrow_mut() = true;
+        }
     }
 
     pub fn flush(&mut self, display: &mut impl DrawTarget<Color = Rgb888>) {
-        self.canvas.draw_to_drawtarget(display);
+        let filter = self.display_filter.get();
+        self.canvas
+            .draw_to_drawtarget_with(display, |pixel| filter.apply(pixel));
+    }
+
+    /// Switch the accessibility filter, redrawing the whole screen with it. It's applied
+    /// by `flush`; hosts that read `canvas` directly apply `display_filter()` themselves.
+    pub fn set_display_filter(&mut self, filter: DisplayFilter) {
+        set_display_filter(
+            &self.display_filter,
+            &self.invalidated,
+            &self.should_update,
+            filter,
+            (self.canvas.width, self.canvas.height),
+        );
+    }
+
+    pub fn display_filter(&self) -> DisplayFilter {
+        self.display_filter.get()
     }
 
     pub fn render(&mut self) -> bool {
+        let drew = self.draw();
+
+        self.latency_tag = self
+            .latency
+            .borrow()
+            .as_ref()
+            .and_then(|latency| latency.rendered(drew));
+
+        drew
+    }
+
+    fn draw(&mut self) -> bool {
         if *self.should_update.borrow() {
             *self.should_update.borrow_mut() = false;
 
+            // Drawn instead of the app, so it works however broken the app is
+            if let Some(diagnostics) = self.diagnostics.as_ref().filter(|d| d.visible) {
+                let dom = self.dom.borrow();
+                let fonts = self.fonts.borrow();
+                let base = dom.base_style();
+                let font = fonts
+                    .get(fonts.face(&base.font_name, base.font_weight, base.font_style))
+                    .or_else(|| fonts.any());
+                let mut font_names: Vec<&str> = fonts.names().collect();
+                font_names.sort_unstable();
+
+                render_diagnostics(
+                    &mut self.canvas,
+                    font,
+                    &font_names,
+                    dom.root_node_id.is_some(),
+                    diagnostics,
+                );
+
+                self.damage = self.damage_tracker.diff(&self.canvas);
+                self.refresh_mode = RefreshMode::Partial;
+                return true;
+            }
+
+            let indicator = self.refresh_indicator();
+            let focused = self.focused_input();
+
+            if focused.is_none() {
+                self.app.keypad = None;
+            }
+
             let mut dom = self.dom.borrow_mut();
 
             if let Some(root) = dom.root_node_id {
+                // The app draws into its own frame while the splash is up
+                if let Some(splash) = &mut self.splash {
+                    splash.swap(&mut self.canvas);
+                }
+
+                if self.transparent {
+                    self.canvas.pixels.fill(0);
+                }
+
+                dom.begin_frame();
                 render_node(
                     &mut dom,
                     &mut self.canvas,
                     &*self.fonts.borrow(),
+                    &self.app.video_frames,
+                    &self.decoder,
                     root,
-                    0.0,
-                    0.0,
+                    (0.0, 0.0),
                 );
 
+                dom.trim_caches(self.memory_budget.rasters, false);
+
+                let now = self.clock.now();
+
+                for feedback in &self.app.press_feedback {
+                    render_press_feedback(&dom, &mut self.canvas, feedback, now);
+                }
+
+                if let Some((node_id, refreshing)) = indicator {
+                    render_refresh_indicator(&dom, &mut self.canvas, node_id, refreshing);
+                }
+
+                if let (Some(keypad), Some(input)) =
+                    (&self.app.keypad, focused.and_then(|id| dom.get_node(id)))
+                    && let Some(font) = font_fallback::resolve(
+                        &self.fonts.borrow(),
+                        &input.resolved_style,
+                        &dom.base_style().font_name,
+                    )
+                {
+                    let bounds =
+                        Keypad::bounds(self.canvas.width as f32, self.canvas.height as f32);
+                    render_keypad(&mut self.canvas, font, keypad, bounds);
+                }
+
+                if let Some(viewer) = self.log_viewer.as_mut().filter(|viewer| viewer.visible)
+                    && let Some(font) = font_fallback::resolve(
+                        &self.fonts.borrow(),
+                        &dom.get_node(root).unwrap().resolved_style,
+                        &dom.base_style().font_name,
+                    )
+                {
+                    viewer.take_changed();
+                    render_log_viewer(&mut self.canvas, font, viewer);
+                }
+
+                if let Some(splash) = &mut self.splash {
+                    splash.swap(&mut self.canvas);
+
+                    // Decodes this frame asked for are outstanding until a later tick
+                    if self.app.app_ready.get() && self.decoder.is_idle() {
+                        splash.start_fade(now);
+                    }
+
+                    if !splash.compose(&mut self.canvas, now) {
+                        self.splash = None;
+                    }
+                }
+
+                self.app.surfaces = dom.surface_rects();
+                self.app.redraw_continuously = dom.animates_continuously(now);
+                self.damage = self.damage_tracker.diff(&self.canvas);
+
+                let invalidated = std::mem::take(&mut *self.invalidated.borrow_mut());
+
+                if !invalidated.is_empty() {
+                    let (width, height) = (self.canvas.width, self.canvas.height);
+
+                    self.damage
+                        .extend(invalidated.into_iter().filter_map(|invalidation| {
+                            let rect = match invalidation {
+                                Invalidation::Node(node_id) => dom.absolute_rect(node_id)?,
+                                Invalidation::Rect(rect) => rect,
+                            };
+
+                            DamageRect::clamped(rect, width, height)
+                        }));
+                    merge_rects(&mut self.damage, MAX_DAMAGE_RECTS);
+                }
+                self.refresh_mode = damage_refresh_mode(&self.damage, &dom);
+
+                return true;
+            }
+
+            // Shown from the first frame, before JS has a tree
+            if let Some(splash) = &self.splash {
+                splash.compose(&mut self.canvas, self.clock.now());
+                self.damage = self.damage_tracker.diff(&self.canvas);
+                self.refresh_mode = RefreshMode::Partial;
                 return true;
             }
         }
@@ -90,11 +434,410 @@ impl Renderer {
         false
     }
 
+    /// Cover the screen with a splash from the next frame until JS calls
+    /// `renderer.ready()` and every image and SVG its tree has drawn is decoded, then
+    /// cross-fade into the app. Call before loading the bundle, so the app's first frames
+    /// are never seen.
+    pub fn show_splash(&mut self, config: &SplashConfig) -> Result<(), SplashError> {
+        self.splash = Some(Splash::new(config, self.canvas.width, self.canvas.height)?);
+        *self.should_update.borrow_mut() = true;
+        Ok(())
+    }
+
+    /// Whether the splash is still showing, including while it fades out.
+    pub fn splash_visible(&self) -> bool {
+        self.splash.is_some()
+    }
+
+    /// Change how much the image, SVG, subtree and glyph caches can hold. Whatever was
+    /// drawn least recently is dropped to stay under it.
+    pub fn set_memory_budget(&mut self, budget: MemoryBudget) {
+        self.memory_budget = budget;
+        glyph_cache::set_budget(budget.glyphs);
+        self.images.set_budget(budget.images);
+        self.dom.borrow_mut().trim_caches(budget.rasters, false);
+    }
+
+    /// Free memory held by caches, e.g. when the system is running low, then tell JS
+    /// with a `MemoryPressure` event on the document so the app can free its own.
+    /// `Moderate` halves the caches, dropping what's gone longest without being drawn;
+    /// `Critical` drops them all, along with preloaded assets.
+    pub async fn trim_memory(&mut self, level: TrimLevel) {
+        let (rasters, glyphs, images) = match level {
+            TrimLevel::Moderate => (
+                self.memory_budget.rasters / 2,
+                self.memory_budget.glyphs / 2,
+                self.memory_budget.images / 2,
+            ),
+            TrimLevel::Critical => (0, 0, 0),
+        };
+
+        self.dom
+            .borrow_mut()
+            .trim_caches(rasters, level == TrimLevel::Critical);
+        glyph_cache::trim(glyphs);
+        self.images.trim(images);
+
+        if level == TrimLevel::Critical {
+            self.decoder.preloaded().clear();
+        }
+
+        let root = self.dom.borrow().root_node_id;
+
+        if let Some(root) = root {
+            self.dispatch_event(u64::from(root), "MemoryPressure", |_ctx, details| {
+                details.set("level", level.as_str()).unwrap();
+            })
+            .await;
+        }
+    }
+
+    /// Leave whatever the tree doesn't draw over transparent (alpha 0) rather than keeping
+    /// the last frame's pixels, for a renderer composited over others in a `LayerStack`.
+    pub fn set_transparent(&mut self, transparent: bool) {
+        self.transparent = transparent;
+
+        if transparent {
+            self.canvas.pixels.fill(0);
+        }
+
+        *self.should_update.borrow_mut() = true;
+    }
+
+    /// Regions of the canvas that changed in the last render, so hosts can copy and
+    /// flush only those. Empty if the render didn't change any pixels.
+    pub fn damage(&self) -> &[DamageRect] {
+        &self.damage
+    }
+
+    /// Redraw a node and everything in it, and report its box as damaged on the next
+    /// render even if no pixels change, e.g. after native code changed what a node shows
+    /// or the panel lost what was there.
+    pub fn invalidate(&mut self, node_id: NodeId) {
+        invalidate(&self.dom, &self.invalidated, node_id);
+        *self.should_update.borrow_mut() = true;
+    }
+
+    /// Send all pointer input to a node and its descendants until cleared with `None`, so
+    /// a confirmation dialog or lock screen can't be bypassed by pressing what's beneath
+    /// it. Presses outside the node land on the node itself. A focused input outside it
+    /// is blurred.
+    pub async fn set_input_grab(&mut self, node_id: Option<NodeId>) {
+        self.dom.borrow_mut().set_input_grab(node_id);
+        self.step_input_grab().await;
+    }
+
+    /// Blur the focused input if a grab was set outside it, including from JS.
+    async fn step_input_grab(&mut self) {
+        let outside = self
+            .focused_input()
+            .is_some_and(|id| !self.dom.borrow().accepts_input(id));
+
+        if outside {
+            self.focus(None).await;
+        }
+    }
+
+    /// Report a region `(x, y, w, h)` of the canvas as damaged on the next render even if
+    /// no pixels change.
+    pub fn invalidate_rect(&mut self, rect: (f32, f32, f32, f32)) {
+        self.invalidated.borrow_mut().push(Invalidation::Rect(rect));
+        *self.should_update.borrow_mut() = true;
+    }
+
+    /// How the last render's damage should be refreshed on e-paper, from the `refreshMode`
+    /// hints of the nodes it covers.
+    pub fn refresh_mode(&self) -> RefreshMode {
+        self.refresh_mode
+    }
+
+    /// Geometry of every `<surface>` node as of the last render, so the host can position
+    /// hardware planes (video, camera) underneath the holes they punch.
+    pub fn surfaces(&self) -> &[SurfaceRect] {
+        &self.app.surfaces
+    }
+
+    /// Replace the current frame for a video source. Any `<video src="...">` node with a
+    /// matching `src` shows it on the next render. Frames with fewer pixels than their
+    /// size needs are skipped, keeping the previous one.
+    pub fn set_video_frame(&mut self, source: &str, frame: VideoFrame) {
+        if !frame.is_complete() {
+            eprintln!(
+                "set_video_frame: skipping a {}x{} frame for \"{}\" with only {} pixels",
+                frame.width,
+                frame.height,
+                source,
+                frame.pixels.len()
+            );
+            return;
+        }
+
+        self.app.video_frames.insert(source.to_string(), frame);
+        self.app.unanalyzed.insert(source.to_string());
+        *self.should_update.borrow_mut() = true;
+    }
+
+    /// Load a font after construction, e.g. one downloaded by the app, replacing any
+    /// already loaded under `name`. Text asking for it is re-measured and redrawn.
+    pub fn add_font(&mut self, name: &str, bytes: &[u8]) -> Result<(), FontError> {
+        let font = Font::from_bytes(bytes, FontSettings::default()).map_err(|err| FontError {
+            message: format!("Can't load font \"{}\": {}", name, err),
+        })?;
+
+        crate::emoji::register(&font, bytes);
+        crate::decoration::register(&font, bytes);
+        crate::variable_font::register(&font, bytes);
+        #[cfg(feature = "shaping")]
+        crate::shaping::register(&font, bytes.to_vec());
+
+        self.fonts.borrow_mut().insert(name.to_string(), font);
+        self.relayout_for_fonts();
+        Ok(())
+    }
+
+    /// Switch the font used by text that doesn't set one, as given by the base style to
+    /// `new`. `name` can be a font family. Returns false, changing nothing, if no font or
+    /// family called `name` is loaded.
+    pub fn set_default_font(&mut self, name: &str) -> bool {
+        let fonts = self.fonts.borrow();
+        let loaded = fonts.contains_key(name) || fonts.contains_family(name);
+        drop(fonts);
+
+        if !loaded {
+            return false;
+        }
+
+        self.dom.borrow_mut().set_default_font(name.to_string());
+        self.relayout_for_fonts();
+        true
+    }
+
+    /// Make the loaded font `name` the face of `family` for text at `weight` and `style`,
+    /// so text with `font: family` picks it by its `fontWeight` and `fontStyle`. Text
+    /// asks for the family's nearest face when it has none at exactly that weight and
+    /// style. Returns false if no font called `name` is loaded.
+    pub fn add_font_face(
+        &mut self,
+        family: &str,
+        name: &str,
+        weight: FontWeight,
+        style: FontStyle,
+    ) -> bool {
+        if !add_font_face(&mut self.fonts.borrow_mut(), family, name, weight, style) {
+            return false;
+        }
+
+        self.relayout_for_fonts();
+        true
+    }
+
+    /// Set the fonts to try, in order, for characters missing from a node's own font, like
+    /// a symbol or CJK font behind a Latin one. Names that aren't loaded yet are skipped
+    /// until they are. Text is re-measured and redrawn.
+    pub fn set_font_fallbacks(&mut self, names: Vec<String>) {
+        self.fonts.borrow_mut().set_fallbacks(names);
+        self.relayout_for_fonts();
+    }
+
+    /// Scale every font size by `factor`, e.g. 1.3 for a "large text" accessibility
+    /// setting. The tree is laid out again, so containers grow and text rewraps around
+    /// the bigger sizes. Clamped to 0.5 to 4.
+    pub fn set_text_scale(&mut self, factor: f32) {
+        let mut dom = self.dom.borrow_mut();
+        dom.set_text_scale(factor);
+
+        let (width, height) = (self.canvas.width as f32, self.canvas.height as f32);
+        dom.compute_layout(&self.fonts.borrow(), width, height);
+        *self.should_update.borrow_mut() = true;
+    }
+
+    pub fn text_scale(&self) -> f32 {
+        self.dom.borrow().text_scale()
+    }
+
+    fn relayout_for_fonts(&mut self) {
+        let size = (self.canvas.width as f32, self.canvas.height as f32);
+        fonts_changed(&self.dom, &self.fonts.borrow(), size);
+        *self.should_update.borrow_mut() = true;
+    }
+
+    /// Show a value in every `<bound bind="...">` node bound to `key`, for readouts that
+    /// update many times a second. Only those nodes are re-measured, and JS isn't
+    /// involved, so it doesn't re-render anything. Nodes bound later start with the
+    /// latest value.
+    pub fn set_binding(&mut self, key: &str, value: impl Display) {
+        let mut dom = self.dom.borrow_mut();
+
+        if dom.set_binding(key, value.to_string()) {
+            let (width, height) = (self.canvas.width as f32, self.canvas.height as f32);
+            dom.compute_layout(&self.fonts.borrow(), width, height);
+            *self.should_update.borrow_mut() = true;
+        }
+    }
+
+    /// Register a decoder for `<scanner analyzer="...">` nodes. On the next tick after a
+    /// new frame arrives for a scanner's `src`, its analyzer gets the part of the frame in
+    /// the viewfinder, and each detection is dispatched to JS as a `Scan` event.
+    pub fn register_analyzer(&mut self, name: &str, analyzer: impl FrameAnalyzer + 'static) {
+        self.analyzers.insert(name.to_string(), Box::new(analyzer));
+    }
+
+    /// Remove a video source's frame, e.g. when a camera stream stops.
+    pub fn clear_video_frame(&mut self, source: &str) {
+        if self.app.video_frames.remove(source).is_some() {
+            *self.should_update.borrow_mut() = true;
+        }
+    }
+
+    /// Handle `renderer.print(nodeId)` calls from JS: each subtree is rendered with
+    /// `options` and the raster handed to `handler`, e.g. to send to a receipt printer
+    /// with `PrintRaster::to_escpos`.
+    pub fn set_print_handler(
+        &mut self,
+        options: PrintOptions,
+        handler: impl FnMut(PrintRaster) + 'static,
+    ) {
+        self.print_handler = Some((options, Box::new(handler)));
+    }
+
+    /// Render a subtree for printing: laid out on its own `options.width` dots wide and
+    /// as tall as its content, then thresholded or dithered to 1 bit. The screen's
+    /// layout is restored afterwards. `None` if the node doesn't exist.
+    pub fn print(&mut self, node_id: NodeId, options: &PrintOptions) -> Option<PrintRaster> {
+        let fonts = self.fonts.borrow();
+        let mut dom = self.dom.borrow_mut();
+
+        dom.get_node(node_id)?;
+
+        let size = dom.compute_subtree_layout(&fonts, node_id, options.width as f32)?;
+        let location = dom.get_layout(node_id).map(|layout| layout.location);
+        let mut canvas = Canvas::new(options.width, size.height.ceil().max(1.0) as u32);
+        canvas.clear(options.background);
+
+        if let Some(location) = location {
+            // Printed straight away, so nothing can be left to decode
+            let video_frames = &self.app.video_frames;
+            render_node(
+                &mut dom,
+                &mut canvas,
+                &fonts,
+                video_frames,
+                &Decoder::inline(self.images.clone()),
+                node_id,
+                (-location.x, -location.y),
+            );
+        }
+
+        let (width, height) = (self.canvas.width as f32, self.canvas.height as f32);
+        dom.restore_subtree_layout(&fonts, node_id, width, height);
+        *self.should_update.borrow_mut() = true;
+
+        Some(PrintRaster::from_canvas(&canvas, options))
+    }
+
+    /// Pass what a screen reader should say to `handler`, e.g. to pipe it to espeak or a
+    /// TTS chip: the focused node whenever focus moves, and a live region (a node with
+    /// `accessibilityLive`) whenever the text inside it changes.
+    pub fn on_announce(&mut self, handler: impl FnMut(Announcement) + 'static) {
+        self.announce_handler = Some(Box::new(handler));
+    }
+
+    fn announce(&mut self, node_id: NodeId, reason: AnnounceReason) {
+        let Some(handler) = &mut self.announce_handler else {
+            return;
+        };
+
+        if let Some(announcement) = self.dom.borrow().announcement(node_id, reason) {
+            handler(announcement);
+        }
+    }
+
+    /// Announce the live regions that changed since the last tick.
+    fn step_announcements(&mut self) {
+        let changed = self.dom.borrow_mut().take_live_changes();
+
+        for node_id in changed {
+            self.announce(node_id, AnnounceReason::LiveRegion);
+        }
+    }
+
+    /// Use the rasters the decoder has finished, redrawing if any were still wanted.
+    fn step_decodes(&mut self) {
+        let mut dom = self.dom.borrow_mut();
+        let mut applied = false;
+
+        for decoded in self.decoder.finished() {
+            applied |= dom.apply_decoded(decoded);
+        }
+
+        if applied {
+            *self.should_update.borrow_mut() = true;
+        }
+    }
+
+    /// Make the variable font instances text drawn since the last tick asked for, e.g.
+    /// after a state style changed its weight, and draw it again with them.
+    fn step_font_instances(&mut self) {
+        if self.fonts.borrow_mut().make_instances() {
+            self.relayout_for_fonts();
+        }
+    }
+
+    /// Switch to the theme JS asked for, firing `ThemeChange` as a host switch does.
+    async fn step_theme(&mut self) {
+        let pending = self.app.pending_theme.borrow_mut().take();
+
+        if let Some(name) = pending {
+            self.set_theme(&name).await;
+        }
+    }
+
+    fn step_preloads(&mut self) {
+        for asset in self.app.pending_preloads.borrow_mut().drain(..) {
+            self.decoder.preload(asset);
+        }
+    }
+
+    /// Read images whose `src` is a path rather than a data URL from `dir`, such as the
+    /// folder the bundle came from. Paths are resolved inside it, so `..` can't climb
+    /// out. Without one, only data URLs are shown.
+    pub fn set_asset_dir(&self, dir: Option<PathBuf>) {
+        self.images.set_asset_dir(dir);
+    }
+
+    /// Decode an image, SVG or font in the background, ahead of the screen that shows
+    /// it. The same as `renderer.preload` in JS.
+    pub fn preload(&self, asset: Preload) {
+        self.decoder.preload(asset);
+    }
+
+    /// Print the subtrees JS asked for with the host's handler.
+    fn step_prints(&mut self) {
+        let pending = std::mem::take(&mut *self.app.pending_prints.borrow_mut());
+
+        if pending.is_empty() {
+            return;
+        }
+
+        let Some((options, mut handler)) = self.print_handler.take() else {
+            eprintln!("renderer.print called without a print handler");
+            return;
+        };
+
+        for node_id in pending {
+            if let Some(raster) = self.print(node_id, &options) {
+                handler(raster);
+            }
+        }
+
+        self.print_handler = Some((options, handler));
+    }
+
     pub async fn dispatch_event(
         &self,
         node_id: u64,
         event_name: &str,
-        build_details: impl FnOnce(Ctx, &Object),
+        build_details: impl for<'js> FnOnce(Ctx<'js>, &Object<'js>),
     ) {
         let Some(callback) = self.event_callback.borrow().clone() else {
             eprintln!("Could not borrow callback");
@@ -121,7 +864,58 @@ impl Renderer {
             .await;
     }
 
-    pub async fn dispatch_xy_event(&self, event_name: &str, x: f32, y: f32) {
+    pub async fn dispatch_xy_event(&mut self, event_name: &str, x: f32, y: f32) {
+        self.dispatch_pointer_event(event_name, x, y, Pointer::default())
+            .await;
+    }
+
+    /// Like `dispatch_xy_event`, but with the tool and pressure reported by the input
+    /// device, for stylus digitizers and pressure-sensitive touchscreens.
+    ///
+    /// Presses that drag past the touch slop over a scrollable scroll it natively: the
+    /// pressed node gets `PressCancel` and JS sees no further events for that press.
+    pub async fn dispatch_pointer_event(
+        &mut self,
+        event_name: &str,
+        x: f32,
+        y: f32,
+        pointer: Pointer,
+    ) {
+        let (width, height) = (self.canvas.width as f32, self.canvas.height as f32);
+
+        if let Some(diagnostics) = &mut self.diagnostics
+            && diagnostics.handle_pointer(event_name, x, y, width, height)
+        {
+            *self.should_update.borrow_mut() = true;
+            return;
+        }
+
+        if let Some(viewer) = &mut self.log_viewer
+            && viewer.handle_pointer(event_name, x, y, width, height)
+        {
+            *self.should_update.borrow_mut() = true;
+            return;
+        }
+
+        if self.handle_keypad(event_name, x, y).await {
+            return;
+        }
+
+        match event_name {
+            "PressIn" => self.start_press_feedback(x, y),
+            "PressOut" => self.release_press_feedback(),
+            _ => {}
+        }
+
+        if self.handle_scroll_drag(event_name, x, y).await {
+            return;
+        }
+
+        if event_name == "PressIn" {
+            let input = self.dom.borrow().input_at_point(x, y);
+            self.focus(input).await;
+        }
+
         let node_id = self.dom.borrow().node_at_point(x, y);
 
         let Some(node_id) = node_id else {
@@ -131,220 +925,3112 @@ impl Renderer {
         self.dispatch_event(node_id, event_name, |_ctx, details| {
             details.set("x", x).unwrap();
             details.set("y", y).unwrap();
+            details.set("pressure", pointer.pressure).unwrap();
+            details.set("tool", pointer.tool.as_str()).unwrap();
         })
         .await;
     }
 
-    pub async fn reload(&mut self, js: &str) {
-        self.event_callback.borrow_mut().take();
+    pub fn set_scroll_config(&mut self, config: ScrollConfig) {
+        self.scroll_config = config;
+    }
 
-        self.engine = Engine::new(&self.modules).await;
+    /// Scroll the scrollable under the pointer by a number of wheel notches. Notches follow
+    /// the evdev `REL_WHEEL`/`REL_HWHEEL` and SDL conventions: positive `notches_y`
+    /// scrolls up, positive `notches_x` scrolls right.
+    pub async fn dispatch_wheel(&mut self, x: f32, y: f32, notches_x: f32, notches_y: f32) {
+        let dx = self.scroll_config.notches_to_pixels(notches_x);
+        let dy = -self.scroll_config.notches_to_pixels(notches_y);
 
-        self.engine
-            .with_context(|ctx| {
-                self.register(&ctx);
-                self.dom.register(&ctx);
-            })
-            .await;
+        if let Some(viewer) = self.log_viewer.as_mut().filter(|viewer| viewer.visible) {
+            viewer.scroll_by(-dy, self.canvas.height as f32);
+            *self.should_update.borrow_mut() = true;
+            return;
+        }
 
-        self.engine.load(js).await;
+        let node_id = self.dom.borrow().scrollable_at_point(x, y, dx, dy);
+
+        if let Some(node_id) = node_id {
+            self.scroll_node(node_id, dx, dy).await;
+        }
     }
-}
 
-impl Drop for Renderer {
-    fn drop(&mut self) {
-        self.event_callback.borrow_mut().take();
+    /// The focused text input, if it's still in the tree.
+    pub fn focused_input(&self) -> Option<NodeId> {
+        let dom = self.dom.borrow();
+        self.app.focused.filter(|&id| dom.text_input(id).is_some())
     }
-}
 
-fn render_node(
-    dom: &mut Dom,
-    canvas: &mut Canvas,
-    fonts: &HashMap<String, Font>,
-    node_id: NodeId,
-    parent_x: f32,
-    parent_y: f32,
-) {
-    let layout = dom.get_layout(node_id).unwrap();
+    /// Move focus to a text input, or clear it, dispatching `Blur` and `Focus`. Pressing
+    /// an input focuses it; pressing anywhere else blurs it. Any composition in progress
+    /// on the old input is dropped.
+    pub async fn focus(&mut self, node_id: Option<NodeId>) {
+        let old = self.focused_input();
 
-    let x = parent_x + layout.location.x;
-    let y = parent_y + layout.location.y;
-    let w = layout.size.width;
-    let h = layout.size.height;
+        if old == node_id {
+            return;
+        }
 
-    let Some(ctx) = dom.get_node_mut(node_id) else {
-        return;
-    };
+        self.app.focused = node_id;
+        self.app.keypad = node_id
+            .and_then(|id| self.dom.borrow().input_keypad(id))
+            .map(|(layout, shuffle)| Keypad::new(layout, shuffle));
+        *self.should_update.borrow_mut() = true;
 
-    let render_w = w as u32;
-    let render_h = h as u32;
+        {
+            let mut dom = self.dom.borrow_mut();
+            let blurred = old.map_or_else(Vec::new, |id| dom.ancestors(id));
+            let focused = node_id.map_or_else(Vec::new, |id| dom.ancestors(id));
 
-    match &mut ctx.kind {
-        NodeKind::Element {
-            background: Some(bg),
-            border_radius,
-            ..
-        } => {
-            let color = Rgb888::new(bg.r, bg.g, bg.b);
-            let style = PrimitiveStyle::with_fill(color);
+            for &id in &blurred {
+                dom.set_node_state(id, |state| state.focused = false);
+            }
 
-            let rect = Rectangle::new(
-                Point::new(x as i32, y as i32),
-                Size::new(render_w, render_h),
+            for &id in &focused {
+                dom.set_node_state(id, |state| state.focused = true);
+            }
+        }
+
+        if let Some(old) = old {
+            self.dom.borrow_mut().edit_text_input(old, |state| {
+                state.focused = false;
+                state.composition = None;
+                state.hide_revealed();
+            });
+            self.dispatch_event(u64::from(old), "Blur", |_, _| {}).await;
+        }
+
+        if let Some(new) = node_id
+            && self
+                .dom
+                .borrow_mut()
+                .edit_text_input(new, |state| state.focused = true)
+                .is_some()
+        {
+            self.announce(new, AnnounceReason::Focus);
+            self.dispatch_event(u64::from(new), "Focus", |_, _| {})
+                .await;
+        }
+    }
+
+    /// Insert committed text at the caret of the focused input, e.g. from a hardware
+    /// keyboard, dispatching `Input`.
+    pub async fn insert_text(&mut self, text: &str) {
+        let Some(node_id) = self.focused_input() else {
+            return;
+        };
+
+        self.dom
+            .borrow_mut()
+            .edit_text_input(node_id, |state| state.insert(text));
+
+        self.dispatch_input(node_id, "Input").await;
+    }
+
+    /// Apply an editing key to the focused input, dispatching `Input` if the value
+    /// changed, or `Submit` for `EditKey::Enter`.
+    pub async fn press_edit_key(&mut self, key: EditKey) {
+        let Some(node_id) = self.focused_input() else {
+            return;
+        };
+
+        if key == EditKey::Enter {
+            self.dispatch_input(node_id, "Submit").await;
+            return;
+        }
+
+        let changed = self
+            .dom
+            .borrow_mut()
+            .edit_text_input(node_id, |state| state.edit(key))
+            .unwrap_or(false);
+
+        *self.should_update.borrow_mut() = true;
+
+        if changed {
+            self.dispatch_input(node_id, "Input").await;
+        }
+    }
+
+    /// Enable the on-device log viewer over entries from `store`, opened with
+    /// `set_log_viewer_visible` or by tapping the top-left corner five times quickly.
+    /// `None` removes it.
+    pub fn set_log_store(&mut self, store: Option<LogStore>) {
+        self.log_viewer = store.map(LogViewer::new);
+        *self.should_update.borrow_mut() = true;
+    }
+
+    /// Every running or finished keyframe animation as a JSON array, for the dev server's
+    /// animation inspector: node id, timing, progress, easing, current and target values,
+    /// and keyframes.
+    pub fn inspect_animations(&self) -> String {
+        serde_json::Value::from(self.dom.borrow().inspect_animations(self.clock.now())).to_string()
+    }
+
+    /// Start measuring input-to-photon latency, returning the recorder to report flushes
+    /// to, from the thread flushing frames if need be. Pass `record_input` each input
+    /// event's kernel timestamp before dispatching it, and once the frame `latency_tag`
+    /// gives after a render reaches the panel, call `LatencyRecorder::flushed` with the
+    /// tag. Percentiles are in `latency_stats`, or `renderer.latencyStats()` in JS.
+    pub fn track_latency(&mut self) -> LatencyRecorder {
+        self.latency
+            .borrow_mut()
+            .get_or_insert_with(LatencyRecorder::new)
+            .clone()
+    }
+
+    /// An input event about to be dispatched was timestamped `at` by the kernel. Ignored
+    /// unless `track_latency` was called.
+    pub fn record_input(&self, at: SystemTime) {
+        if let Some(latency) = &*self.latency.borrow() {
+            latency.input(at);
+        }
+    }
+
+    /// The tag to report the frame the last `render` drew as flushed with, if it showed
+    /// input being measured.
+    pub fn latency_tag(&self) -> Option<u64> {
+        self.latency_tag
+    }
+
+    /// Latency percentiles so far, if `track_latency` was called.
+    pub fn latency_stats(&self) -> Option<LatencyStats> {
+        self.latency.borrow().as_ref().map(LatencyRecorder::stats)
+    }
+
+    /// Show or hide the log viewer, if there's a log store.
+    pub fn set_log_viewer_visible(&mut self, visible: bool) {
+        if let Some(viewer) = &mut self.log_viewer {
+            viewer.set_visible(visible);
+            *self.should_update.borrow_mut() = true;
+        }
+    }
+
+    pub fn log_viewer_visible(&self) -> bool {
+        self.log_viewer
+            .as_ref()
+            .is_some_and(|viewer| viewer.visible)
+    }
+
+    /// Enable the built-in diagnostics screen, opened with `set_diagnostics_visible` or by
+    /// tapping the top-right corner five times quickly. `input_devices` describes what the
+    /// host found, for the screen to list.
+    pub fn enable_diagnostics(&mut self, input_devices: Vec<String>) {
+        self.diagnostics = Some(Diagnostics::new(input_devices));
+    }
+
+    /// Show or hide the diagnostics screen, if enabled.
+    pub fn set_diagnostics_visible(&mut self, visible: bool) {
+        if let Some(diagnostics) = &mut self.diagnostics {
+            diagnostics.set_visible(visible);
+            *self.should_update.borrow_mut() = true;
+        }
+    }
+
+    pub fn diagnostics_visible(&self) -> bool {
+        self.diagnostics
+            .as_ref()
+            .is_some_and(|diagnostics| diagnostics.visible)
+    }
+
+    /// Route presses on the built-in keypad to its keys rather than the UI underneath.
+    /// Returns whether the event was consumed.
+    async fn handle_keypad(&mut self, event_name: &str, x: f32, y: f32) -> bool {
+        let bounds = Keypad::bounds(self.canvas.width as f32, self.canvas.height as f32);
+
+        let Some(keypad) = &mut self.app.keypad else {
+            return false;
+        };
+
+        let key = keypad.key_at(bounds, x, y);
+
+        let activated = match (event_name, keypad.pressed) {
+            ("PressIn", _) => {
+                let Some(key) = key else {
+                    return false;
+                };
+                keypad.pressed = Some((key, true));
+                None
+            }
+            ("PressMove", Some((pressed, _))) => {
+                keypad.pressed = Some((pressed, key == Some(pressed)));
+                None
+            }
+            ("PressOut", Some((pressed, over))) => {
+                keypad.pressed = None;
+                over.then(|| keypad.key(pressed)).flatten()
+            }
+            (_, Some(_)) => None,
+            (_, None) => return false,
+        };
+
+        *self.should_update.borrow_mut() = true;
+
+        match activated {
+            Some(KeypadKey::Char(c)) => self.insert_text(&c.to_string()).await,
+            Some(KeypadKey::Backspace) => self.press_edit_key(EditKey::Backspace).await,
+            Some(KeypadKey::Enter) => self.press_edit_key(EditKey::Enter).await,
+            None => {}
+        }
+
+        true
+    }
+
+    /// Show IME preedit text inline at the caret of the focused input, underlined,
+    /// dispatching `CompositionStart` for the first update and `CompositionUpdate` for each.
+    pub async fn set_composition(&mut self, text: &str) {
+        let Some(node_id) = self.focused_input() else {
+            return;
+        };
+
+        let started = self
+            .dom
+            .borrow_mut()
+            .edit_text_input(node_id, |state| {
+                state.composition.replace(text.to_string()).is_none()
+            })
+            .unwrap_or(false);
+
+        *self.should_update.borrow_mut() = true;
+
+        let text = text.to_string();
+
+        if started {
+            let text = text.clone();
+
+            self.dispatch_event(u64::from(node_id), "CompositionStart", |_ctx, details| {
+                details.set("text", text).unwrap();
+            })
+            .await;
+        }
+
+        self.dispatch_event(u64::from(node_id), "CompositionUpdate", |_ctx, details| {
+            details.set("text", text).unwrap();
+        })
+        .await;
+    }
+
+    /// End composition, inserting the converted text (e.g. the chosen characters for a
+    /// pinyin or kana reading) and dispatching `CompositionEnd` and `Input`. An empty
+    /// string cancels the composition.
+    pub async fn commit_composition(&mut self, text: &str) {
+        let Some(node_id) = self.focused_input() else {
+            return;
+        };
+
+        self.dom.borrow_mut().edit_text_input(node_id, |state| {
+            state.composition = None;
+            state.insert(text);
+        });
+
+        let committed = text.to_string();
+
+        self.dispatch_event(u64::from(node_id), "CompositionEnd", |_ctx, details| {
+            details.set("text", committed).unwrap();
+        })
+        .await;
+
+        if text.is_empty() {
+            *self.should_update.borrow_mut() = true;
+        } else {
+            self.dispatch_input(node_id, "Input").await;
+        }
+    }
+
+    /// Redraw an input after an edit and tell JS its value.
+    async fn dispatch_input(&mut self, node_id: NodeId, event_name: &str) {
+        *self.should_update.borrow_mut() = true;
+
+        let value = self
+            .dom
+            .borrow()
+            .text_input(node_id)
+            .map(|state| state.value.clone())
+            .unwrap_or_default();
+
+        self.dispatch_event(u64::from(node_id), event_name, |_ctx, details| {
+            details.set("value", value).unwrap();
+        })
+        .await;
+    }
+
+    /// Let ambient light readings switch between a light and a dark theme.
+    pub fn set_auto_theme(&mut self, auto_theme: Option<AutoTheme>) {
+        self.auto_theme = auto_theme;
+    }
+
+    /// Switch to a theme registered from JS, redrawing everything that refers to its
+    /// variables and dispatching `ThemeChange` on the document. Returns false if the theme
+    /// isn't registered or is already active.
+    pub async fn set_theme(&mut self, name: &str) -> bool {
+        let root = {
+            let mut dom = self.dom.borrow_mut();
+
+            if !dom.set_theme(name) {
+                return false;
+            }
+
+            dom.root_node_id
+        };
+
+        *self.should_update.borrow_mut() = true;
+
+        if let Some(root) = root {
+            let theme = name.to_string();
+
+            self.dispatch_event(u64::from(root), "ThemeChange", |_ctx, details| {
+                details.set("theme", theme).unwrap();
+            })
+            .await;
+        }
+
+        true
+    }
+
+    /// Report an ambient light reading in lux, dispatching `AmbientLight` on the document
+    /// and switching theme if auto theming is on and a threshold was crossed.
+    pub async fn dispatch_ambient_light(&mut self, lux: f32) {
+        let root = self.dom.borrow().root_node_id;
+
+        if let Some(root) = root {
+            self.dispatch_event(u64::from(root), "AmbientLight", |_ctx, details| {
+                details.set("lux", lux).unwrap();
+            })
+            .await;
+        }
+
+        let theme = self.auto_theme.as_ref().and_then(|auto_theme| {
+            auto_theme
+                .theme_for(lux, self.dom.borrow().active_theme())
+                .map(str::to_string)
+        });
+
+        if let Some(theme) = theme {
+            self.set_theme(&theme).await;
+        }
+    }
+
+    /// Scroll vertically by rotary encoder detents, for input without a pointer position.
+    /// This goes to the scrollable most recently scrolled, or the first one in the tree.
+    pub async fn dispatch_encoder(&mut self, notches: f32) {
+        let dy = -self.scroll_config.notches_to_pixels(notches);
+
+        if let Some(viewer) = self.log_viewer.as_mut().filter(|viewer| viewer.visible) {
+            viewer.scroll_by(-dy, self.canvas.height as f32);
+            *self.should_update.borrow_mut() = true;
+            return;
+        }
+
+        let node_id = {
+            let dom = self.dom.borrow();
+
+            self.app
+                .scroll_target
+                .filter(|&id| dom.get_node(id).is_some() && dom.accepts_input(id))
+                .or_else(|| dom.first_scrollable())
+        };
+
+        if let Some(node_id) = node_id {
+            self.scroll_node(node_id, 0.0, dy).await;
+        }
+    }
+
+    async fn scroll_node(&mut self, node_id: NodeId, dx: f32, dy: f32) {
+        if self
+            .app
+            .fling
+            .as_ref()
+            .is_some_and(|fling| fling.node_id == node_id)
+        {
+            self.app.fling = None;
+        }
+
+        let offset = self.dom.borrow().scroll_offset(node_id);
+        let (bounds_x, bounds_y) = self.scroll_bounds(node_id);
+
+        let x = (offset.0 + dx).clamp(bounds_x.0, bounds_x.1);
+        let y = (offset.1 + dy).clamp(bounds_y.0, bounds_y.1);
+        self.set_scroll(node_id, x, y).await;
+    }
+
+    /// The (min, max) scroll offset of each axis of a node. A node that's refreshing can
+    /// rest above the top of its content, leaving room for the indicator.
+    fn scroll_bounds(&self, node_id: NodeId) -> ((f32, f32), (f32, f32)) {
+        let max = self.dom.borrow().scroll_limits(node_id);
+
+        let min_y = match &self.app.refresh {
+            Some(refresh) if refresh.node_id == node_id => -scroll::REFRESH_INDICATOR_HEIGHT,
+            _ => 0.0,
+        };
+
+        ((0.0, max.0), (min_y, max.1))
+    }
+
+    /// Move a node's scroll offset, telling JS about the new position and any change in
+    /// how far it's overscrolled.
+    async fn set_scroll(&mut self, node_id: NodeId, x: f32, y: f32) {
+        let (old, max) = {
+            let dom = self.dom.borrow();
+            (dom.scroll_offset(node_id), dom.scroll_limits(node_id))
+        };
+
+        if old == (x, y) {
+            return;
+        }
+
+        self.dom.borrow_mut().set_scroll_offset(node_id, x, y);
+        self.app.scroll_target = Some(node_id);
+        *self.should_update.borrow_mut() = true;
+
+        self.dispatch_event(u64::from(node_id), "Scroll", |_ctx, details| {
+            details.set("scrollX", x).unwrap();
+            details.set("scrollY", y).unwrap();
+        })
+        .await;
+
+        let old_over = (
+            scroll::overscroll(old.0, (0.0, max.0)),
+            scroll::overscroll(old.1, (0.0, max.1)),
+        );
+        let over = (
+            scroll::overscroll(x, (0.0, max.0)),
+            scroll::overscroll(y, (0.0, max.1)),
+        );
+
+        if over != old_over {
+            self.dispatch_event(u64::from(node_id), "Overscroll", |_ctx, details| {
+                details.set("overscrollX", over.0).unwrap();
+                details.set("overscrollY", over.1).unwrap();
+            })
+            .await;
+        }
+    }
+
+    /// Track a press for drag scrolling. Returns true if the event was consumed by a
+    /// scroll and shouldn't reach JS.
+    async fn handle_scroll_drag(&mut self, event_name: &str, x: f32, y: f32) -> bool {
+        let now = self.clock.now();
+
+        match event_name {
+            "PressIn" => {
+                // Touching a coasting list stops it
+                self.app.fling = None;
+                self.app.scroll_drag = Some(ScrollDrag::new(now, x, y));
+
+                // Touching anywhere outside an open row closes it
+                if let Some((node_id, _)) = self.app.open_swipe
+                    && !self.swipe_contains(node_id, x, y)
+                {
+                    self.snap_swipe(node_id, 0.0, 0.0).await;
+                }
+
+                false
+            }
+            "PressMove" => {
+                let Some(drag) = &mut self.app.scroll_drag else {
+                    return false;
+                };
+
+                drag.tracker.add(now, x, y);
+
+                match drag.state {
+                    DragState::Passthrough => false,
+                    DragState::Pending => {
+                        let (dx, dy) = (x - drag.start.0, y - drag.start.1);
+
+                        if dx.hypot(dy) < scroll::TOUCH_SLOP {
+                            return false;
+                        }
+
+                        // Sideways drags swipe rows; otherwise content moves opposite to
+                        // the finger
+                        let (start_x, start_y) = drag.start;
+                        let state = {
+                            let dom = self.dom.borrow();
+
+                            let swipe = if dx.abs() > dy.abs() {
+                                dom.swipeable_at_point(start_x, start_y)
+                            } else {
+                                None
+                            };
+
+                            // Vertical drags on a picker spin the wheel under the finger
+                            let wheel = if dy.abs() >= dx.abs() {
+                                dom.picker_wheel_at_point(start_x, start_y)
+                            } else {
+                                None
+                            };
+
+                            let picking =
+                                wheel.map(|(node_id, wheel, _)| DragState::Picking(node_id, wheel));
+
+                            picking.or(swipe.map(DragState::Swiping)).or_else(|| {
+                                dom.scrollable_at_point(start_x, start_y, -dx, -dy)
+                                    .or_else(|| {
+                                        if self.scroll_config.bounce {
+                                            dom.scroll_container_at_point(
+                                                start_x, start_y, -dx, -dy,
+                                            )
+                                        } else {
+                                            None
+                                        }
+                                    })
+                                    .map(DragState::Scrolling)
+                            })
+                        };
+
+                        let Some(state) = state else {
+                            drag.state = DragState::Passthrough;
+                            return false;
+                        };
+
+                        drag.state = state;
+                        drag.last = (x, y);
+
+                        if let DragState::Swiping(node_id) = state {
+                            self.app.swipe_snaps.retain(|snap| snap.node_id != node_id);
+                        }
+
+                        if let DragState::Picking(node_id, wheel) = state {
+                            self.app
+                                .wheel_spins
+                                .retain(|spin| (spin.node_id, spin.wheel) != (node_id, wheel));
+                        }
+
+                        self.release_press_feedback();
+
+                        let pressed = self.dom.borrow().node_at_point(start_x, start_y);
+
+                        if let Some(pressed) = pressed {
+                            self.dispatch_event(pressed, "PressCancel", |_ctx, details| {
+                                details.set("x", x).unwrap();
+                                details.set("y", y).unwrap();
+                            })
+                            .await;
+                        }
+
+                        true
+                    }
+                    DragState::Scrolling(node_id) => {
+                        let (dx, dy) = (drag.last.0 - x, drag.last.1 - y);
+                        drag.last = (x, y);
+
+                        let (offset, axes) = {
+                            let dom = self.dom.borrow();
+                            (dom.scroll_offset(node_id), dom.scroll_axes(node_id))
+                        };
+                        let bounds = self.scroll_bounds(node_id);
+
+                        let bounce = self.scroll_config.bounce;
+                        let next_x = if axes.0 {
+                            scroll::drag_offset(offset.0, dx, bounds.0, bounce)
+                        } else {
+                            offset.0
+                        };
+                        let next_y = if axes.1 {
+                            scroll::drag_offset(offset.1, dy, bounds.1, bounce)
+                        } else {
+                            offset.1
+                        };
+
+                        self.set_scroll(node_id, next_x, next_y).await;
+                        true
+                    }
+                    DragState::Swiping(node_id) => {
+                        // Rows follow the finger
+                        let dx = x - drag.last.0;
+                        drag.last = (x, y);
+
+                        let mut dom = self.dom.borrow_mut();
+                        let next = scroll::drag_offset(
+                            dom.swipe_offset(node_id),
+                            dx,
+                            dom.swipe_bounds(node_id),
+                            self.scroll_config.bounce,
+                        );
+
+                        dom.set_swipe_offset(node_id, next);
+                        *self.should_update.borrow_mut() = true;
+                        true
+                    }
+                    DragState::Picking(node_id, wheel) => {
+                        // Dragging up brings later items into the middle
+                        let dy = y - drag.last.1;
+                        drag.last = (x, y);
+
+                        self.dom
+                            .borrow_mut()
+                            .edit_picker(node_id, |picker, font_size| {
+                                picker.wheels[wheel].drag(-dy / picker::row_height(font_size));
+                            });
+                        *self.should_update.borrow_mut() = true;
+                        true
+                    }
+                }
+            }
+            "PressOut" => {
+                let Some(mut drag) = self.app.scroll_drag.take() else {
+                    return false;
+                };
+
+                drag.tracker.add(now, x, y);
+                let velocity = drag.tracker.velocity();
+
+                match drag.state {
+                    DragState::Scrolling(node_id) => {
+                        self.release_scroll(node_id, velocity, now).await;
+                        true
+                    }
+                    DragState::Swiping(node_id) => {
+                        let target = {
+                            let dom = self.dom.borrow();
+                            scroll::swipe_snap_target(
+                                dom.swipe_offset(node_id),
+                                velocity.0,
+                                dom.swipe_bounds(node_id),
+                            )
+                        };
+
+                        self.snap_swipe(node_id, target, velocity.0).await;
+                        true
+                    }
+                    DragState::Picking(node_id, wheel) => {
+                        self.dom
+                            .borrow_mut()
+                            .edit_picker(node_id, |picker, font_size| {
+                                picker.wheels[wheel].velocity =
+                                    -velocity.1 / picker::row_height(font_size);
+                            });
+                        self.spin_wheel(node_id, wheel, now);
+                        true
+                    }
+                    DragState::Pending => {
+                        let link =
+                            self.dom
+                                .borrow()
+                                .markdown_link_at_point(&self.fonts.borrow(), x, y);
+
+                        if let Some((node_id, href)) = link {
+                            self.dispatch_event(
+                                u64::from(node_id),
+                                "LinkPress",
+                                |_ctx, details| {
+                                    details.set("href", href).unwrap();
+                                },
+                            )
+                            .await;
+                        }
+
+                        let cell = self.dom.borrow().table_cell_at_point(x, y);
+
+                        if let Some((node_id, row, column)) = cell {
+                            self.dispatch_event(
+                                u64::from(node_id),
+                                "CellPress",
+                                |_ctx, details| {
+                                    details
+                                        .set("row", row.map_or(-1, |row| row as i32))
+                                        .unwrap();
+                                    details.set("column", column).unwrap();
+                                },
+                            )
+                            .await;
+                        }
+
+                        // Tapping a row above or below a picker's selection moves to it
+                        let tapped = self.dom.borrow().picker_wheel_at_point(x, y);
+
+                        if let Some((node_id, wheel, rows)) = tapped
+                            && rows.abs() >= 0.5
+                        {
+                            self.dom.borrow_mut().edit_picker(node_id, |picker, _| {
+                                let wheel = &mut picker.wheels[wheel];
+                                let target = wheel.position.round() + rows.round();
+                                wheel.snap_to = Some(target.max(0.0) as usize);
+                            });
+                            self.spin_wheel(node_id, wheel, now);
+                        }
+
+                        // Tapping an open row closes it
+                        if let Some((node_id, _)) = self.app.open_swipe
+                            && self.swipe_contains(node_id, x, y)
+                        {
+                            self.snap_swipe(node_id, 0.0, 0.0).await;
+                        }
+
+                        false
+                    }
+                    DragState::Passthrough => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Fling a dragged scrollable with the pointer's release velocity, refreshing it first
+    /// if it was pulled down far enough.
+    async fn release_scroll(&mut self, node_id: NodeId, (vx, vy): (f32, f32), now: Instant) {
+        let (offset, axes, refreshable) = {
+            let dom = self.dom.borrow();
+            (
+                dom.scroll_offset(node_id),
+                dom.scroll_axes(node_id),
+                dom.is_refreshable(node_id),
+            )
+        };
+
+        if refreshable && self.app.refresh.is_none() && offset.1 <= -scroll::REFRESH_TRIGGER {
+            self.start_refresh(node_id).await;
+        }
+
+        let velocity = (
+            if axes.0 { -vx } else { 0.0 },
+            if axes.1 { -vy } else { 0.0 },
+        );
+
+        let bounds = self.scroll_bounds(node_id);
+
+        self.app.fling = Momentum::from_release(velocity, offset, bounds).map(|momentum| Fling {
+            node_id,
+            momentum,
+            last_step: now,
+        });
+    }
+
+    /// Whether a point lies within a swipeable row as currently drawn.
+    fn swipe_contains(&self, node_id: NodeId, x: f32, y: f32) -> bool {
+        self.dom
+            .borrow()
+            .absolute_rect(node_id)
+            .is_some_and(|(rx, ry, rw, rh)| x >= rx && x < rx + rw && y >= ry && y < ry + rh)
+    }
+
+    /// Spring a swipeable row open or closed, telling JS with a `SwipeAction` event when
+    /// that changes which side is revealed.
+    async fn snap_swipe(&mut self, node_id: NodeId, target: f32, velocity: f32) {
+        self.app.swipe_snaps.retain(|snap| snap.node_id != node_id);
+        self.app.swipe_snaps.push(SwipeSnap {
+            node_id,
+            target,
+            velocity,
+            last_step: self.clock.now(),
+        });
+
+        let previous = match self.app.open_swipe {
+            Some((id, offset)) if id == node_id => offset,
+            _ => 0.0,
+        };
+
+        if target != 0.0 {
+            self.app.open_swipe = Some((node_id, target));
+        } else if previous != 0.0 {
+            self.app.open_swipe = None;
+        }
+
+        if target != previous {
+            let direction = if target < 0.0 {
+                "left"
+            } else if target > 0.0 {
+                "right"
+            } else {
+                "none"
+            };
+
+            self.dispatch_event(u64::from(node_id), "SwipeAction", |_ctx, details| {
+                details.set("direction", direction).unwrap();
+            })
+            .await;
+        }
+    }
+
+    /// Advance swipeable rows springing open or closed.
+    fn step_swipes(&mut self) {
+        if self.app.swipe_snaps.is_empty() {
+            return;
+        }
+
+        let now = self.clock.now();
+        let mut dom = self.dom.borrow_mut();
+
+        self.app.swipe_snaps.retain_mut(|snap| {
+            if dom.get_node(snap.node_id).is_none() {
+                return false;
+            }
+
+            let dt = now.duration_since(snap.last_step).as_secs_f32().min(0.05);
+            snap.last_step = now;
+
+            let (next, done) = snap.step(dom.swipe_offset(snap.node_id), dt);
+            dom.set_swipe_offset(snap.node_id, next);
+            !done
+        });
+
+        *self.should_update.borrow_mut() = true;
+    }
+
+    /// Start the press effect of the element under a press, if it has one, and show the
+    /// pressed node and its ancestors in their `pressedStyle`.
+    fn start_press_feedback(&mut self, x: f32, y: f32) {
+        {
+            let mut dom = self.dom.borrow_mut();
+            let node_id = dom.node_at_point(x, y).map(NodeId::from);
+            self.app.pressed = node_id.map_or_else(Vec::new, |id| dom.ancestors(id));
+
+            for &node_id in &self.app.pressed {
+                dom.set_node_state(node_id, |state| state.pressed = true);
+            }
+
+            *self.should_update.borrow_mut() = true;
+        }
+
+        let Some((node_id, effect)) = self.dom.borrow().press_effect_at_point(x, y) else {
+            return;
+        };
+
+        self.app.press_feedback.push(PressFeedback::new(
+            node_id,
+            effect,
+            (x, y),
+            self.clock.now(),
+        ));
+        *self.should_update.borrow_mut() = true;
+    }
+
+    /// Animate press effects back out when the press ends or turns into a drag, and
+    /// drop the pressed styles.
+    fn release_press_feedback(&mut self) {
+        let now = self.clock.now();
+        let mut dom = self.dom.borrow_mut();
+
+        for node_id in self.app.pressed.drain(..) {
+            dom.set_node_state(node_id, |state| state.pressed = false);
+            *self.should_update.borrow_mut() = true;
+        }
+
+        for feedback in &mut self.app.press_feedback {
+            feedback.release(now);
+        }
+    }
+
+    /// Keep redrawing while press effects animate, dropping finished ones.
+    fn step_press_feedback(&mut self) {
+        if self.app.press_feedback.is_empty() {
+            return;
+        }
+
+        let now = self.clock.now();
+        let dom = self.dom.borrow();
+
+        self.app
+            .press_feedback
+            .retain(|feedback| !feedback.finished(now) && dom.get_node(feedback.node_id).is_some());
+        *self.should_update.borrow_mut() = true;
+    }
+
+    /// Advance keyframe animations, redrawing while any run and dispatching
+    /// `AnimationEnd` as each finishes.
+    async fn step_animations(&mut self) {
+        let (running, ended) = self.dom.borrow_mut().step_animations(self.clock.now());
+
+        if running || !ended.is_empty() {
+            *self.should_update.borrow_mut() = true;
+        }
+
+        for node_id in ended {
+            self.dispatch_event(u64::from(node_id), "AnimationEnd", |_ctx, _details| {})
+                .await;
+        }
+    }
+
+    /// Run analyzers over new frames for `<scanner>` nodes, dispatching `Scan` for each
+    /// detection not already reported.
+    async fn step_scanners(&mut self) {
+        if self.app.unanalyzed.is_empty() {
+            return;
+        }
+
+        let now = self.clock.now();
+        let mut scans = Vec::new();
+
+        {
+            let mut dom = self.dom.borrow_mut();
+
+            for (node_id, size) in dom.scanners() {
+                dom.edit_scanner(node_id, |scanner| {
+                    if !self.app.unanalyzed.contains(&scanner.src) {
+                        return;
+                    }
+
+                    let (Some(frame), Some(analyzer)) = (
+                        self.app.video_frames.get(&scanner.src),
+                        self.analyzers.get_mut(&scanner.analyzer),
+                    ) else {
+                        return;
+                    };
+
+                    let detections = analyzer.analyze(frame, scanner.frame_region(frame, size));
+                    scanner.highlights = detections.iter().filter_map(|d| d.bounds).collect();
+
+                    for detection in detections {
+                        if scanner.should_report(&detection.text, now) {
+                            scans.push((node_id, detection));
+                        }
+                    }
+                });
+            }
+        }
+
+        self.app.unanalyzed.clear();
+
+        for (node_id, detection) in scans {
+            self.dispatch_event(u64::from(node_id), "Scan", |_ctx, details| {
+                details.set("text", detection.text).unwrap();
+                details.set("format", detection.format).unwrap();
+            })
+            .await;
+        }
+    }
+
+    /// Let a released picker wheel coast and snap onto an item.
+    fn spin_wheel(&mut self, node_id: NodeId, wheel: usize, now: Instant) {
+        self.app
+            .wheel_spins
+            .retain(|spin| (spin.node_id, spin.wheel) != (node_id, wheel));
+        self.app.wheel_spins.push(WheelSpin {
+            node_id,
+            wheel,
+            last_step: now,
+        });
+    }
+
+    /// Advance spinning picker wheels, dispatching `Change` when one settles on a new
+    /// value.
+    async fn step_wheels(&mut self) {
+        if self.app.wheel_spins.is_empty() {
+            return;
+        }
+
+        let now = self.clock.now();
+        let deceleration = self.scroll_config.deceleration;
+        let mut changes = Vec::new();
+
+        {
+            let mut dom = self.dom.borrow_mut();
+
+            self.app.wheel_spins.retain_mut(|spin| {
+                let dt = now.duration_since(spin.last_step).as_secs_f32().min(0.05);
+                spin.last_step = now;
+
+                let step = |picker: &mut Picker, _| {
+                    let settled = picker.wheels.get_mut(spin.wheel)?.step(dt, deceleration);
+                    settled.then(|| (picker.settle(), picker.selected_index()))
+                };
+
+                match dom.edit_picker(spin.node_id, step) {
+                    Some(Some((change, index))) => {
+                        if let Some(value) = change {
+                            changes.push((spin.node_id, value, index));
+                        }
+                        false
+                    }
+                    Some(None) => true,
+                    None => false,
+                }
+            });
+        }
+
+        *self.should_update.borrow_mut() = true;
+
+        for (node_id, value, index) in changes {
+            self.dispatch_event(u64::from(node_id), "Change", |_ctx, details| {
+                details.set("value", value).unwrap();
+                details.set("index", index).unwrap();
+            })
+            .await;
+        }
+    }
+
+    /// Advance momentum scrolling by the time since the last step.
+    async fn step_fling(&mut self) {
+        let Some(node_id) = self.app.fling.as_ref().map(|fling| fling.node_id) else {
+            return;
+        };
+
+        if self.dom.borrow().get_node(node_id).is_none() {
+            self.app.fling = None;
+            return;
+        }
+
+        let offset = self.dom.borrow().scroll_offset(node_id);
+        let bounds = self.scroll_bounds(node_id);
+
+        let Some(fling) = &mut self.app.fling else {
+            return;
+        };
+
+        let now = self.clock.now();
+        // Cap the step so a stalled frame doesn't launch the list
+        let dt = now.duration_since(fling.last_step).as_secs_f32().min(0.05);
+        fling.last_step = now;
+
+        let (next, done) = fling.momentum.step(dt, offset, bounds, &self.scroll_config);
+
+        if done {
+            self.app.fling = None;
+        }
+
+        self.set_scroll(node_id, next.0, next.1).await;
+    }
+
+    /// Tell JS a refreshable node was pulled down far enough. The node holds the
+    /// indicator open until JS calls `complete` on the event details.
+    async fn start_refresh(&mut self, node_id: NodeId) {
+        let complete = Rc::new(Cell::new(false));
+
+        self.app.refresh = Some(Refresh {
+            node_id,
+            started: self.clock.now(),
+            complete: complete.clone(),
+        });
+
+        self.dispatch_event(u64::from(node_id), "Refresh", |ctx, details| {
+            let callback = Function::new(ctx, move || complete.set(true)).unwrap();
+            details.set("complete", callback).unwrap();
+        })
+        .await;
+    }
+
+    /// Keep the refresh spinner animating, and once JS has completed the refresh let the
+    /// node spring back over the indicator.
+    async fn step_refresh(&mut self) {
+        let Some(refresh) = &self.app.refresh else {
+            return;
+        };
+
+        let node_id = refresh.node_id;
+
+        if self.dom.borrow().get_node(node_id).is_none() {
+            self.app.refresh = None;
+            return;
+        }
+
+        if !refresh.complete.get() {
+            *self.should_update.borrow_mut() = true;
+            return;
+        }
+
+        self.app.refresh = None;
+
+        let dragging = matches!(
+            &self.app.scroll_drag,
+            Some(ScrollDrag { state: DragState::Scrolling(id), .. }) if *id == node_id
+        );
+
+        if !dragging {
+            let offset = self.dom.borrow().scroll_offset(node_id);
+            let bounds = self.scroll_bounds(node_id);
+
+            if let Some(momentum) = Momentum::from_release((0.0, 0.0), offset, bounds) {
+                self.app.fling = Some(Fling {
+                    node_id,
+                    momentum,
+                    last_step: self.clock.now(),
+                });
+            }
+        }
+
+        // Redraw once more to clear the spinner if the node isn't moving
+        *self.should_update.borrow_mut() = true;
+    }
+
+    /// The refreshable node whose indicator should be drawn, and when it started
+    /// refreshing if it has been released.
+    fn refresh_indicator(&self) -> Option<(NodeId, Option<Instant>)> {
+        if let Some(refresh) = &self.app.refresh {
+            return Some((refresh.node_id, Some(refresh.started)));
+        }
+
+        let dragging = self
+            .app
+            .scroll_drag
+            .as_ref()
+            .and_then(|drag| match drag.state {
+                DragState::Scrolling(node_id) => Some(node_id),
+                _ => None,
+            });
+
+        dragging
+            .or_else(|| self.app.fling.as_ref().map(|fling| fling.node_id))
+            .map(|node_id| (node_id, None))
+    }
+
+    /// Swap to another user's bundle and storage without restarting, for shared devices
+    /// with per-operator settings. The DOM and JS state are thrown away, modules that
+    /// store data (like `fs` and `sqlite`) move to the profile's own directory, and `js`
+    /// is loaded fresh. Profile names must be a single path component.
+    pub async fn switch_profile(&mut self, name: &str, js: &str) -> Result<(), ProfileError> {
+        let mut components = Path::new(name).components();
+
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        ) {
+            return Err(ProfileError {
+                message: format!("Invalid profile name '{name}'"),
+            });
+        }
+
+        // JS callbacks holding the old shared state go with the old engine in `reload`
+        self.app = AppState::default();
+        self.invalidated.borrow_mut().clear();
+        self.dom.borrow_mut().clear();
+        self.profile = Some(name.to_string());
+
+        for module in &self.modules {
+            module.set_profile(name);
+        }
+
+        self.reload(js).await;
+        *self.should_update.borrow_mut() = true;
+        Ok(())
+    }
+
+    /// The profile last switched to, if any.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Make the renderer reproducible for golden tests and input replays: JS timers,
+    /// `Date` and native animations follow a mock clock moved by `advance_time`, and
+    /// `Math.random` is seeded. Recreates the engine, so call it before loading the
+    /// bundle. `None` goes back to the system clock.
+    pub async fn set_deterministic(&mut self, deterministic: Option<Deterministic>) {
+        self.deterministic = deterministic;
+        // Decoding on other threads would make what's drawn depend on their timing
+        self.decoder = match deterministic {
+            Some(_) => Decoder::inline(self.images.clone()),
+            None => Decoder::new(self.images.clone()),
+        };
+
+        if deterministic.is_none() {
+            self.clock.use_system();
+        }
+
+        self.reload("").await;
+    }
+
+    /// Advance the mock clock by `ms` milliseconds, firing JS timers and stepping native
+    /// animations as it goes. Does nothing without `set_deterministic`.
+    pub async fn advance_time(&mut self, ms: u64) {
+        self.engine.advance_time(ms).await;
+        self.tick().await;
+    }
+
+    pub async fn reload(&mut self, js: &str) {
+        self.engine = match self.deterministic {
+            Some(deterministic) => {
+                Engine::new_deterministic(&self.modules, deterministic, self.clock.clone()).await
+            }
+            None => Engine::new(&self.modules).await,
+        };
+
+        self.engine
+            .with_context(|ctx| {
+                self.register(&ctx);
+                self.dom.register(&ctx);
+            })
+            .await;
+
+        self.engine.load(js).await;
+    }
+}
+
+#[derive(Debug)]
+pub struct ProfileError {
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub struct FontError {
+    pub message: String,
+}
+
+/// Something to report as damaged on the next render.
+enum Invalidation {
+    Node(NodeId),
+    Rect((f32, f32, f32, f32)),
+}
+
+fn set_display_filter(
+    display_filter: &Cell<DisplayFilter>,
+    invalidated: &RefCell<Vec<Invalidation>>,
+    should_update: &RefCell<bool>,
+    filter: DisplayFilter,
+    (width, height): (u32, u32),
+) {
+    if display_filter.replace(filter) != filter {
+        // Every pixel looks different, even though none changed on the canvas
+        let screen = (0.0, 0.0, width as f32, height as f32);
+        invalidated.borrow_mut().push(Invalidation::Rect(screen));
+        *should_update.borrow_mut() = true;
+    }
+}
+
+/// Lay the tree out again after the fonts or the default font change.
+fn add_font_face(
+    fonts: &mut FontRegistry,
+    family: &str,
+    name: &str,
+    weight: FontWeight,
+    style: FontStyle,
+) -> bool {
+    if !fonts.contains_key(name) {
+        return false;
+    }
+
+    let font = name.to_string();
+    fonts.add_face(
+        family,
+        FontFace {
+            font,
+            weight,
+            style,
+        },
+    );
+    true
+}
+
+fn fonts_changed(dom: &RefCell<Dom>, fonts: &FontRegistry, (width, height): (f32, f32)) {
+    let mut dom = dom.borrow_mut();
+    dom.fonts_changed();
+    dom.compute_layout(fonts, width, height);
+}
+
+fn invalidate(dom: &RefCell<Dom>, invalidated: &RefCell<Vec<Invalidation>>, node_id: NodeId) {
+    dom.borrow_mut().mark_subtree_render_dirty(node_id);
+    invalidated.borrow_mut().push(Invalidation::Node(node_id));
+}
+
+fn render_node(
+    dom: &mut Dom,
+    canvas: &mut Canvas,
+    fonts: &FontRegistry,
+    video_frames: &HashMap<String, VideoFrame>,
+    decoder: &Decoder,
+    node_id: NodeId,
+    (parent_x, parent_y): (f32, f32),
+) {
+    // Hidden subtrees keep their state but aren't drawn
+    if dom.is_hidden(node_id) {
+        return;
+    }
+
+    // Fully transparent subtrees still take presses but have nothing to draw
+    if dom.get_node(node_id).is_some_and(|ctx| ctx.opacity <= 0.0) {
+        return;
+    }
+
+    dom.mark_drawn(node_id);
+    let now = dom.clock().now();
+
+    let layout = dom.get_layout(node_id).unwrap();
+
+    let x = parent_x + layout.location.x + dom.swipe_offset(node_id);
+    let y = parent_y + layout.location.y;
+    let w = layout.size.width;
+    let h = layout.size.height;
+    let border = layout.border;
+    let default_font = dom.base_style().font_name.clone();
+
+    // A `cache` element's subtree is blitted from its last drawing while nothing in it
+    // has changed. Only boxes drawn whole can be kept, and composited ones are redrawn.
+    let cache_rect = (x as i32, y as i32, w as u32, h as u32);
+    let caching = dom.get_node(node_id).is_some_and(|ctx| {
+        matches!(ctx.kind, NodeKind::Element { cache: true, .. })
+            && ctx.opacity >= 1.0
+            && ctx.transform.is_none()
+            && !ctx
+                .animation
+                .as_ref()
+                .is_some_and(|a| a.current.composites())
+    }) && canvas.clip_contains(cache_rect);
+
+    if caching
+        && let Some(signature) = dom.subtree_signature(node_id, (x, y), now)
+        && let Some(ctx) = dom.get_node(node_id)
+        && let NodeKind::Element {
+            border_radius,
+            box_shadow,
+            subtree_cache: Some(cache),
+            ..
+        } = &ctx.kind
+        && cache.signature == signature
+    {
+        if let Some(shadow) = box_shadow {
+            let border_radius = ctx.state_overrides.border_radius.unwrap_or(*border_radius);
+            shadow.render(canvas, (x, y, w, h), border_radius);
+        }
+
+        let dst = (
+            x.trunc(),
+            y.trunc(),
+            cache.width as f32,
+            cache.height as f32,
+        );
+        let everywhere = (0.0, 0.0, canvas.width as f32, canvas.height as f32);
+        canvas.blit_scaled_xrgb(&cache.pixels, cache.width, cache.height, dst, everywhere);
+        return;
+    }
+
+    let Some(ctx) = dom.get_node_mut(node_id) else {
+        return;
+    };
+    let font = font_fallback::resolve(fonts, &ctx.resolved_style, &default_font);
+
+    let render_w = w as u32;
+    let render_h = h as u32;
+
+    let mut animated = ctx.animation.as_ref().map(|a| a.current);
+
+    // A static opacity is composited the same way as an animated one, multiplying it
+    if ctx.opacity < 1.0 {
+        animated.get_or_insert_with(AnimatedValues::default).opacity *= ctx.opacity;
+    }
+
+    // Keep what's behind a node that will be composited, to draw it back over later
+    let transform = ctx.transform;
+    let backdrop = (animated.is_some_and(|a| a.composites()) || transform.is_some())
+        .then(|| canvas.copy_rect(x as i32, y as i32, render_w, render_h));
+
+    match &mut ctx.kind {
+        NodeKind::Element {
+            tag,
+            background,
+            border_radius,
+            border_color,
+            box_shadow,
+            ..
+        } => {
+            let state = ctx.state_overrides;
+            let border_radius = state.border_radius.unwrap_or(*border_radius);
+
+            if let Some(shadow) = box_shadow {
+                shadow.render(canvas, (x, y, w, h), border_radius);
+            }
+
+            let background = animated
+                .and_then(|a| a.background)
+                .or(state.background)
+                .or(*background);
+
+            if let Some(bg) = background {
+                // Translucent backgrounds are blended over what's already drawn: scrims
+                // and overlays
+                let rect = (x, y, render_w as f32, render_h as f32);
+                canvas.fill_rounded_rect(rect, border_radius, bg);
+
+                if tag == "skeleton" {
+                    skeleton::render_shimmer(canvas, (x, y, w, h), border_radius);
+                }
+            }
+
+            if let Some(color) = state.border_color.or(*border_color) {
+                render_border(canvas, (x, y, w, h), border, border_radius, color);
+            }
+            ctx.render_dirty = false;
+        }
+
+        NodeKind::Text {
+            text, wrap_width, ..
+        } => {
+            // A magenta box, so a missing font can't go unnoticed on a development device
+            if font_fallback::mark_missing(fonts, &ctx.resolved_style) {
+                const MISSING_FONT: Rgb888 = Rgb888::new(0xff, 0x00, 0xff);
+
+                let rect = Rectangle::new(
+                    Point::new(x as i32, y as i32),
+                    Size::new(render_w, render_h),
+                );
+                let _ = rect
+                    .into_styled(PrimitiveStyle::with_fill(MISSING_FONT))
+                    .draw(canvas);
+            }
+
+            if let Some(font) = font {
+                let chain = fonts.chain(font);
+                let style = &ctx.resolved_style;
+                let single_line = style.text_overflow != TextOverflow::Wrap && style.max_lines == 0;
+                let text = canvas::expand_whitespace(
+                    &chain,
+                    text,
+                    style.font_size,
+                    style.letter_spacing,
+                    style.tab_size,
+                    single_line,
+                );
+
+                // Broken into lines here to cut them, so they're drawn without wrapping
+                let clamped = (style.max_lines > 0).then(|| {
+                    let lines = match wrap_width {
+                        Some(_) => canvas::wrap_lines(
+                            &chain,
+                            &text,
+                            style.font_size,
+                            style.letter_spacing,
+                            w,
+                        ),
+                        None => text.split('\n').map(str::to_string).collect(),
+                    };
+
+                    canvas::clamp_lines(
+                        &chain,
+                        lines,
+                        style.font_size,
+                        style.letter_spacing,
+                        w,
+                        style.max_lines as usize,
+                        style.text_overflow == TextOverflow::Ellipsis,
+                    )
+                });
+                let truncated = match style.text_overflow {
+                    TextOverflow::Ellipsis if single_line => canvas::truncate_with_ellipsis(
+                        &chain,
+                        &text,
+                        style.font_size,
+                        style.letter_spacing,
+                        w,
+                    ),
+                    _ => None,
+                };
+                let clip = style.text_overflow == TextOverflow::Clip;
+
+                if clip {
+                    canvas.push_clip((x, y, w, h));
+                }
+
+                canvas.draw_text(
+                    &TextRun {
+                        align: style.text_align,
+                        direction: style.direction,
+                        line_height: style.line_height,
+                        letter_spacing: style.letter_spacing,
+                        decoration: style.text_decoration,
+                        ..TextRun::new(&chain, style.font_size, style.color)
+                    },
+                    clamped.as_deref().or(truncated.as_deref()).unwrap_or(&text),
+                    x,
+                    y,
+                    // Wrap to the laid out width, which flex may have grown or shrunk
+                    // since the text was measured
+                    wrap_width.filter(|_| clamped.is_none()).map(|_| w),
+                    w,
+                );
+
+                if clip {
+                    canvas.pop_clip();
+                }
+            }
+            ctx.render_dirty = false;
+        }
+
+        NodeKind::Input {
+            placeholder, state, ..
+        } => {
+            if let Some(font) = font {
+                render_text_input(
+                    canvas,
+                    &fonts.chain(font),
+                    &ctx.resolved_style,
+                    placeholder,
+                    state,
+                    (x, y, w),
+                );
+            }
+            ctx.render_dirty = false;
+        }
+
+        NodeKind::Picker { picker } => {
+            if let Some(font) = font {
+                render_picker(canvas, font, &ctx.resolved_style, picker, (x, y, w, h));
+            }
+            ctx.render_dirty = false;
+        }
+
+        NodeKind::Gauge { gauge } => {
+            render_gauge(canvas, font, &ctx.resolved_style, gauge, (x, y, w, h), now);
+            ctx.render_dirty = false;
+        }
+
+        NodeKind::Table { table } => {
+            if let Some(font) = font {
+                let rect = (x, y, w, h);
+                render_table(canvas, font, &ctx.resolved_style, table, ctx.scroll_y, rect);
+            }
+            ctx.render_dirty = false;
+        }
+
+        NodeKind::Markdown { markdown } => {
+            if let Some(markdown_fonts) = markdown.fonts(fonts, &ctx.resolved_style, &default_font)
+            {
+                let rect = (x, y, w, h);
+                render_markdown(canvas, markdown_fonts, &ctx.resolved_style, markdown, rect);
+            }
+            ctx.render_dirty = false;
+        }
+
+        NodeKind::Chart { chart } => {
+            render_chart(canvas, font, &ctx.resolved_style, chart, (x, y, w, h));
+            ctx.render_dirty = false;
+        }
+
+        NodeKind::Svg { markup, .. } => {
+            if render_w > 0 && render_h > 0 {
+                let size_changed = ctx
+                    .cached_raster
+                    .as_ref()
+                    .is_none_or(|c| c.width != render_w || c.height != render_h);
+
+                if ctx.render_dirty || size_changed {
+                    let current_color = ctx.resolved_style.with_overrides(&ctx.overrides).color;
+                    let color_hex = format!(
+                        "#{:02x}{:02x}{:02x}",
+                        current_color.r, current_color.g, current_color.b
+                    );
+                    let markup = markup.replace("currentColor", &color_hex);
+                    let key = decoder::raster_key((&markup, render_w, render_h));
+
+                    if ctx.raster_request != Some(key) {
+                        ctx.raster_request = Some(key);
+                        let job = DecodeJob::Svg {
+                            markup,
+                            width: render_w,
+                            height: render_h,
+                        };
+
+                        if let Some(decoded) = decoder.request(node_id.into(), key, job) {
+                            ctx.apply_decoded(decoded);
+                        }
+                    }
+                }
+
+                // The previous raster stands in until the new one is ready
+                if let Some(cache) = &ctx.cached_raster
+                    && (cache.width, cache.height) == (render_w, render_h)
+                {
+                    canvas.blit_premultiplied_rgba(
+                        &cache.data,
+                        cache.width,
+                        cache.height,
+                        x as i32,
+                        y as i32,
+                    );
+                }
+            }
+            ctx.render_dirty = false;
+        }
+
+        NodeKind::Image {
+            src,
+            data,
+            img_width,
+            img_height,
+            ..
+        } => {
+            if !src.is_empty() && render_w > 0 && render_h > 0 {
+                let size_changed = ctx
+                    .cached_raster
+                    .as_ref()
+                    .is_none_or(|c| c.width != render_w || c.height != render_h);
+
+                if ctx.render_dirty || size_changed {
+                    let key = decoder::raster_key((&*src, render_w, render_h));
+
+                    if ctx.raster_request != Some(key) {
+                        ctx.raster_request = Some(key);
+
+                        // Only decoded once, then scaled from the full-size pixels
+                        let job = if data.is_empty() {
+                            DecodeJob::Image {
+                                src: src.clone(),
+                                width: render_w,
+                                height: render_h,
+                            }
+                        } else {
+                            DecodeJob::Resize {
+                                data: data.clone(),
+                                img_width: *img_width,
+                                img_height: *img_height,
+                                width: render_w,
+                                height: render_h,
+                            }
+                        };
+
+                        if let Some(decoded) = decoder.request(node_id.into(), key, job) {
+                            ctx.apply_decoded(decoded);
+                        }
+                    }
+                }
+
+                if let Some(cache) = &ctx.cached_raster
+                    && (cache.width, cache.height) == (render_w, render_h)
+                {
+                    canvas.blit_rgba(&cache.data, cache.width, cache.height, x as i32, y as i32);
+                }
+            }
+            ctx.render_dirty = false;
+        }
+
+        NodeKind::Sprite {
+            data,
+            img_width,
+            img_height,
+            animation,
+            ..
+        } => {
+            let (columns, rows) = animation.grid(*img_width, *img_height);
+
+            if render_w > 0
+                && render_h > 0
+                && let Some((column, row)) = animation.frame(now, *img_width, *img_height)
+            {
+                // Scale every frame to the node's size once and cache the resulting sheet,
+                // so each render is a plain blit of one frame
+                let (sheet_w, sheet_h) = (columns * render_w, rows * render_h);
+                let needs_rasterize = ctx.render_dirty
+                    || ctx
+                        .cached_raster
+                        .as_ref()
+                        .is_none_or(|c| c.width != sheet_w || c.height != sheet_h);
+
+                if needs_rasterize
+                    && let Some(sheet) =
+                        image::RgbaImage::from_raw(*img_width, *img_height, data.clone())
+                {
+                    // Scale frame by frame so filtering doesn't bleed between them
+                    let mut scaled = image::RgbaImage::new(sheet_w, sheet_h);
+                    let (frame_w, frame_h) = (animation.frame_width, animation.frame_height);
+
+                    for cell in 0..columns * rows {
+                        let (cx, cy) = (cell % columns, cell / columns);
+                        let frame = image::imageops::crop_imm(
+                            &sheet,
+                            cx * frame_w,
+                            cy * frame_h,
+                            frame_w,
+                            frame_h,
+                        )
+                        .to_image();
+                        let frame = if (frame_w, frame_h) == (render_w, render_h) {
+                            frame
+                        } else {
+                            image::imageops::resize(
+                                &frame,
+                                render_w,
+                                render_h,
+                                image::imageops::FilterType::Triangle,
+                            )
+                        };
+
+                        image::imageops::replace(
+                            &mut scaled,
+                            &frame,
+                            (cx * render_w) as i64,
+                            (cy * render_h) as i64,
+                        );
+                    }
+
+                    ctx.cached_raster = Some(crate::dom::CachedRaster {
+                        data: scaled.into_raw(),
+                        width: sheet_w,
+                        height: sheet_h,
+                    });
+                }
+
+                if let Some(cache) = &ctx.cached_raster {
+                    canvas.blit_rgba_region(
+                        &cache.data,
+                        cache.width,
+                        (column * render_w, row * render_h, render_w, render_h),
+                        x as i32,
+                        y as i32,
+                    );
+                }
+            }
+            ctx.render_dirty = false;
+        }
+
+        NodeKind::Video { src, object_fit } => {
+            if let Some(frame) = video_frames.get(src.as_str()) {
+                let dst = object_fit.fit(frame.width, frame.height, x, y, w, h);
+                canvas.blit_scaled_xrgb(
+                    &frame.pixels,
+                    frame.width,
+                    frame.height,
+                    dst,
+                    (x, y, w, h),
+                );
+            }
+            ctx.render_dirty = false;
+        }
+
+        NodeKind::Scanner { scanner } => {
+            let frame = video_frames.get(scanner.src.as_str());
+            render_scanner(canvas, &ctx.resolved_style, scanner, frame, (x, y, w, h));
+            ctx.render_dirty = false;
+        }
+
+        NodeKind::Surface { punch, .. } => {
+            if *punch {
+                canvas.punch_hole(x as i32, y as i32, w as u32, h as u32);
+            }
+            ctx.render_dirty = false;
+        }
+
+        #[cfg(feature = "lottie")]
+        NodeKind::Lottie { player } => {
+            if render_w > 0
+                && render_h > 0
+                && let Some(index) = player.frame(now)
+                && let Some(data) = player.rasterize(index, render_w, render_h)
+            {
+                canvas.blit_premultiplied_rgba(&data, render_w, render_h, x as i32, y as i32);
+            }
+            ctx.render_dirty = false;
+        }
+    }
+
+    let (scroll_x, scroll_y) = dom.scroll_offset(node_id);
+
+    // Children are cut off at the inside of the border on axes that don't overflow
+    let clip = match dom.clip_axes(node_id) {
+        (false, false) => None,
+        (clip_x, clip_y) => Some((
+            if clip_x {
+                x + border.left
+            } else {
+                f32::MIN / 2.0
+            },
+            if clip_y {
+                y + border.top
+            } else {
+                f32::MIN / 2.0
+            },
+            if clip_x {
+                w - border.left - border.right
+            } else {
+                f32::MAX
+            },
+            if clip_y {
+                h - border.top - border.bottom
+            } else {
+                f32::MAX
+            },
+        )),
+    };
+
+    if let Some(clip) = clip {
+        canvas.push_clip(clip);
+    }
+
+    for child_id in dom.paint_order(node_id) {
+        render_node(
+            dom,
+            canvas,
+            fonts,
+            video_frames,
+            decoder,
+            child_id,
+            (x - scroll_x, y - scroll_y),
+        );
+    }
+
+    if clip.is_some() {
+        canvas.pop_clip();
+    }
+
+    if caching {
+        let cache = dom
+            .subtree_signature(node_id, (x, y), now)
+            .map(|signature| {
+                let (x, y, w, h) = cache_rect;
+                let (pixels, width, height) = canvas.copy_rect(x, y, w, h);
+                SubtreeCache {
+                    pixels,
+                    width,
+                    height,
+                    signature,
+                }
+            });
+
+        if let Some(NodeKind::Element { subtree_cache, .. }) =
+            dom.get_node_mut(node_id).map(|ctx| &mut ctx.kind)
+        {
+            *subtree_cache = cache;
+        }
+    }
+
+    if let Some(backdrop) = backdrop {
+        let animated = animated.unwrap_or_default();
+        composite(canvas, animated, transform, backdrop, (x, y, w, h));
+    }
+}
+
+/// Redraw a node's box, as just drawn with its children, faded by its opacity and moved
+/// by its transform and an animation's, both around the box's center. This is only
+/// visual: layout still uses the untransformed box, as does hit-testing for animations.
+fn composite(
+    canvas: &mut Canvas,
+    animated: AnimatedValues,
+    transform: Option<Affine>,
+    (backdrop, w, h): (Vec<u32>, u32, u32),
+    (x, y, node_w, node_h): (f32, f32, f32, f32),
+) {
+    let center = (x + node_w / 2.0, y + node_h / 2.0);
+
+    // Both copies are clipped to the canvas the same way
+    let (x, y) = (x.max(0.0).trunc(), y.max(0.0).trunc());
+    let (drawn, _, _) = canvas.copy_rect(x as i32, y as i32, w, h);
+    let everywhere = (0.0, 0.0, canvas.width as f32, canvas.height as f32);
+
+    canvas.blit_scaled_xrgb(&backdrop, w, h, (x, y, w as f32, h as f32), everywhere);
+
+    let (tx, ty) = animated.translate;
+    let map = Affine::translate(x, y)
+        .then(transform.unwrap_or(Affine::IDENTITY).around(center))
+        .then(Affine::scale(animated.scale, animated.scale).around(center))
+        .then(Affine::translate(tx, ty));
+    let alpha = (animated.opacity.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    canvas.blend_affine_xrgb(&drawn, w, h, map, alpha);
+}
+
+/// Draw a border of the `border` widths around the inside of a node's box. Even borders
+/// follow the corner radius; uneven ones are drawn square. A translucent color is
+/// blended, following the radius either way.
+fn render_border(
+    canvas: &mut Canvas,
+    (x, y, w, h): (f32, f32, f32, f32),
+    border: taffy::Rect<f32>,
+    radius: f32,
+    color: RgbColor,
+) {
+    let widths = [border.top, border.right, border.bottom, border.left];
+
+    if !color.is_opaque() {
+        if widths.iter().all(|&width| width <= 0.0) {
+            return;
+        }
+
+        let inner = (
+            x + border.left,
+            y + border.top,
+            w - border.left - border.right,
+            h - border.top - border.bottom,
+        );
+        let inner_radius = (radius - border.top.max(border.left)).max(0.0);
+
+        canvas.blend_rounded_rect((x, y, w, h), radius, color, |cx, cy| {
+            if canvas::rounded_rect_contains(inner, inner_radius, cx, cy) {
+                0
+            } else {
+                255
+            }
+        });
+        return;
+    }
+
+    let color = Rgb888::new(color.r, color.g, color.b);
+
+    if widths.iter().all(|&width| width == border.top) {
+        if border.top <= 0.0 {
+            return;
+        }
+
+        let style = PrimitiveStyleBuilder::new()
+            .stroke_color(color)
+            .stroke_width(border.top as u32)
+            .stroke_alignment(StrokeAlignment::Inside)
+            .build();
+        let rect = Rectangle::new(
+            Point::new(x as i32, y as i32),
+            Size::new(w as u32, h as u32),
+        );
+        // Clamped like fill_rounded_rect's, as larger radii draw garbage in small boxes
+        let r = radius.min(w / 2.0).min(h / 2.0).max(0.0) as u32;
+
+        let _ = RoundedRectangle::new(rect, CornerRadii::new(Size::new(r, r)))
+            .into_styled(style)
+            .draw(canvas);
+        return;
+    }
+
+    let sides = [
+        (x, y, w, border.top),
+        (x + w - border.right, y, border.right, h),
+        (x, y + h - border.bottom, w, border.bottom),
+        (x, y, border.left, h),
+    ];
+
+    for (x, y, w, h) in sides {
+        if w > 0.0 && h > 0.0 {
+            let _ = Rectangle::new(
+                Point::new(x as i32, y as i32),
+                Size::new(w as u32, h as u32),
+            )
+            .into_styled(PrimitiveStyle::with_fill(color))
+            .draw(canvas);
+        }
+    }
+}
+
+/// Draw a single-line text input's value, or its placeholder in a dimmed color, with any
+/// IME composition underlined and a caret when focused.
+fn render_text_input(
+    canvas: &mut Canvas,
+    fonts: &[&Font],
+    style: &InheritedStyle,
+    placeholder: &str,
+    state: &TextInputState,
+    (x, y, w): (f32, f32, f32),
+) {
+    let fs = style.font_size;
+    let color = style.color;
+    let mask = if font_fallback::pick(fonts, MASK_CHAR).lookup_glyph_index(MASK_CHAR) != 0 {
+        MASK_CHAR
+    } else {
+        '*'
+    };
+    let (text, composition) = state.display_text(mask);
+
+    if text.is_empty() {
+        canvas.draw_text(
+            &TextRun::new(fonts, fs, dimmed(color)),
+            placeholder,
+            x,
+            y,
+            None,
+            w,
+        );
+    } else {
+        canvas.draw_text(&TextRun::new(fonts, fs, color), &text, x, y, None, w);
+    }
+
+    let advance = |text: &str| canvas::text_width(fonts, text, fs, 0.0);
+    let line_height = fonts[0]
+        .horizontal_line_metrics(fs)
+        .map(|m| m.ascent - m.descent + m.line_gap)
+        .unwrap_or(fs);
+    let fill = PrimitiveStyle::with_fill(Rgb888::new(color.r, color.g, color.b));
+
+    let composition_x = x + advance(&text[..composition.start]);
+    let caret_x = composition_x + advance(&text[composition]);
+
+    if caret_x > composition_x {
+        let _ = Rectangle::new(
+            Point::new(composition_x as i32, (y + line_height) as i32 - 2),
+            Size::new((caret_x - composition_x).ceil() as u32, 1),
+        )
+        .into_styled(fill)
+        .draw(canvas);
+    }
+
+    if state.focused {
+        let _ = Rectangle::new(
+            Point::new(caret_x as i32, y as i32),
+            Size::new(1, line_height as u32),
+        )
+        .into_styled(fill)
+        .draw(canvas);
+    }
+}
+
+/// Draw a press effect over its node as it currently looks.
+fn render_press_feedback(dom: &Dom, canvas: &mut Canvas, feedback: &PressFeedback, now: Instant) {
+    let (Some(ctx), Some(rect)) = (
+        dom.get_node(feedback.node_id),
+        dom.absolute_rect(feedback.node_id),
+    ) else {
+        return;
+    };
+
+    let radius = match ctx.kind {
+        NodeKind::Element { border_radius, .. } => border_radius,
+        _ => 0.0,
+    };
+    let color = ctx.resolved_style.color;
+    let (x, y, w, h) = rect;
+
+    match feedback.effect {
+        PressEffect::Scale => {
+            let scale = 1.0 - (1.0 - press_effect::PRESSED_SCALE) * feedback.amount(now);
+
+            if scale >= 1.0 {
+                return;
+            }
+
+            // Shrink what was drawn for the node into the background behind it
+            let (pixels, copied_w, copied_h) =
+                canvas.copy_rect(x as i32, y as i32, w as u32, h as u32);
+            let (x, y) = (x.max(0.0).trunc(), y.max(0.0).trunc());
+            let (w, h) = (copied_w as f32, copied_h as f32);
+            let behind = dom
+                .background_behind(feedback.node_id)
+                .unwrap_or(RgbColor::rgb(0, 0, 0));
+
+            canvas.tint((x, y, w, h), 0.0, None, behind, 255);
+            canvas.blit_scaled_xrgb(
+                &pixels,
+                copied_w,
+                copied_h,
+                (
+                    x + w * (1.0 - scale) / 2.0,
+                    y + h * (1.0 - scale) / 2.0,
+                    w * scale,
+                    h * scale,
+                ),
+                (x, y, w, h),
+            );
+        }
+        PressEffect::Highlight => {
+            let alpha = press_effect::HIGHLIGHT_ALPHA * feedback.amount(now) * 255.0;
+            canvas.tint(rect, radius, None, color, alpha as u8);
+        }
+        PressEffect::Ripple => {
+            // Spread until the circle reaches the node's farthest corner
+            let (ox, oy) = feedback.origin;
+            let reach = [(x, y), (x + w, y), (x, y + h), (x + w, y + h)]
+                .into_iter()
+                .map(|(cx, cy)| (cx - ox).hypot(cy - oy))
+                .fold(0.0, f32::max);
+            let alpha = press_effect::RIPPLE_ALPHA * feedback.ripple_opacity(now) * 255.0;
+
+            canvas.tint(
+                rect,
+                radius,
+                Some((ox, oy, reach * feedback.spread(now))),
+                color,
+                alpha as u8,
+            );
+        }
+    }
+}
+
+/// Halfway to mid-grey, which reads as secondary text on light and dark backgrounds.
+fn dimmed(color: RgbColor) -> RgbColor {
+    let dim = |c: u8| ((c as u16 + 128) / 2) as u8;
+
+    RgbColor {
+        r: dim(color.r),
+        g: dim(color.g),
+        b: dim(color.b),
+        a: color.a,
+    }
+}
+
+/// Draw a picker's wheels side by side, with the row under the centre in the text color
+/// between two rules and the rows around it dimmed. Rows that would cross the node's
+/// edges are left out.
+fn render_picker(
+    canvas: &mut Canvas,
+    font: &Font,
+    style: &InheritedStyle,
+    picker: &Picker,
+    (x, y, w, h): (f32, f32, f32, f32),
+) {
+    let fs = style.font_size;
+    let row_height = picker::row_height(fs);
+    let center_y = y + h / 2.0;
+    let line_height = font
+        .horizontal_line_metrics(fs)
+        .map(|m| m.ascent - m.descent)
+        .unwrap_or(fs);
+    let column_width = w / picker.wheels.len() as f32;
+    let secondary = dimmed(style.color);
+
+    for (column, wheel) in picker.wheels.iter().enumerate() {
+        let column_x = x + column as f32 * column_width;
+        let reach = h / 2.0 / row_height;
+        let first = (wheel.position - reach).floor().max(0.0) as usize;
+        let last = (wheel.position + reach).ceil().max(0.0) as usize;
+
+        for (index, item) in wheel.items.iter().enumerate().take(last + 1).skip(first) {
+            let offset = index as f32 - wheel.position;
+            let row_y = center_y + offset * row_height - row_height / 2.0;
+
+            if row_y < y || row_y + row_height > y + h {
+                continue;
+            }
+
+            let color = if offset.abs() < 0.5 {
+                style.color
+            } else {
+                secondary
+            };
+
+            canvas.draw_text(
+                &TextRun {
+                    align: TextAlign::Center,
+                    direction: style.direction,
+                    ..TextRun::new(&[font], fs, color)
+                },
+                item,
+                column_x,
+                row_y + (row_height - line_height) / 2.0,
+                None,
+                column_width,
+            );
+        }
+    }
+
+    let rule = PrimitiveStyle::with_fill(Rgb888::new(secondary.r, secondary.g, secondary.b));
+
+    for rule_y in [center_y - row_height / 2.0, center_y + row_height / 2.0] {
+        let _ = Rectangle::new(Point::new(x as i32, rule_y as i32), Size::new(w as u32, 1))
+            .into_styled(rule)
+            .draw(canvas);
+    }
+}
+
+/// Draw a gauge: the track arc with its colored ranges and value fill, ticks inside it
+/// with labels at the major ones, and the needle pointing at the displayed value.
+fn render_gauge(
+    canvas: &mut Canvas,
+    font: Option<&Font>,
+    style: &InheritedStyle,
+    gauge: &Gauge,
+    (x, y, w, h): (f32, f32, f32, f32),
+    now: Instant,
+) {
+    let center = (x + w / 2.0, y + h / 2.0);
+    let radius = w.min(h) / 2.0;
+    let thickness = gauge.thickness.unwrap_or(radius / 10.0);
+    // Stroke centered inside the node's bounds
+    let arc_radius = radius - thickness / 2.0 - 1.0;
+    let angles = (gauge.start_angle, gauge.end_angle);
+    let track = gauge.track_color.unwrap_or_else(|| dimmed(style.color));
+    let value = gauge.displayed_value(now);
+
+    if arc_radius <= 0.0 {
+        return;
+    }
+
+    canvas.stroke_arc(center, arc_radius, thickness, angles, track);
+
+    for range in &gauge.ranges {
+        let range_angles = (gauge.angle_of(range.from), gauge.angle_of(range.to));
+        canvas.stroke_arc(center, arc_radius, thickness, range_angles, range.color);
+    }
+
+    if let Some(color) = gauge.value_color {
+        let value_angles = (gauge.start_angle, gauge.angle_of(value));
+        canvas.stroke_arc(center, arc_radius, thickness, value_angles, color);
+    }
+
+    let point = |angle: f32, distance: f32| {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        (center.0 + sin * distance, center.1 - cos * distance)
+    };
+    let tick_start = arc_radius - thickness / 2.0 - 2.0;
+    let major_length = thickness * 1.5;
+    let fs = style.font_size;
+
+    for (tick, major) in gauge.ticks() {
+        let angle = gauge.angle_of(tick);
+        let (length, width) = if major {
+            (major_length, 2.0)
+        } else {
+            (major_length / 2.0, 1.0)
+        };
+
+        canvas.stroke_line(
+            point(angle, tick_start),
+            point(angle, tick_start - length),
+            width,
+            style.color,
+        );
+
+        if major
+            && gauge.labels
+            && let Some(font) = font
+        {
+            let line_height = font
+                .horizontal_line_metrics(fs)
+                .map(|m| m.ascent - m.descent)
+                .unwrap_or(fs);
+            let (label_x, label_y) = point(angle, tick_start - major_length - fs);
+            let label_width = fs * 4.0;
+
+            canvas.draw_text(
+                &TextRun {
+                    align: TextAlign::Center,
+                    direction: style.direction,
+                    ..TextRun::new(&[font], fs, style.color)
+                },
+                &gauge::format_label(tick),
+                label_x - label_width / 2.0,
+                label_y - line_height / 2.0,
+                None,
+                label_width,
+            );
+        }
+    }
+
+    let needle_width = (thickness / 3.0).max(2.0);
+
+    canvas.stroke_line(
+        center,
+        point(gauge.angle_of(value), arc_radius),
+        needle_width,
+        style.color,
+    );
+    canvas.fill_circle(center, needle_width * 1.5, style.color);
+}
+
+/// Draw a chart: a value axis with labelled ticks down the left if it has ticks, then its
+/// values as a line, a filled area or bars across the rest, newest at the right.
+fn render_chart(
+    canvas: &mut Canvas,
+    font: Option<&Font>,
+    style: &InheritedStyle,
+    chart: &Chart,
+    (x, y, w, h): (f32, f32, f32, f32),
+) {
+    let (min, max) = chart.range();
+    let fs = style.font_size;
+    let fill = chart.fill_color.unwrap_or(style.color);
+    let mut plot = (x, y, w, h);
+
+    if chart.ticks > 0 {
+        let labels: Vec<(f32, String)> = (0..=chart.ticks)
+            .map(|i| {
+                let value = min + (max - min) * i as f32 / chart.ticks as f32;
+                (value, gauge::format_label(value))
+            })
+            .collect();
+        let advance = |text: &str| -> f32 {
+            font.map_or(0.0, |font| {
+                text.chars()
+                    .map(|c| glyph_cache::advance(font, c, fs))
+                    .sum()
+            })
+        };
+        let label_width = labels
+            .iter()
+            .map(|(_, label)| advance(label))
+            .fold(0.0, f32::max);
+        let line_height = font
+            .and_then(|font| font.horizontal_line_metrics(fs))
+            .map_or(0.0, |m| m.ascent - m.descent);
+        let tick_length = 4.0;
+        let axis_x = (x + label_width + tick_length + 2.0).round();
+        let axis_color = dimmed(style.color);
+
+        // Inset by half a line so the labels at the ends aren't cut off
+        plot = (
+            axis_x + 1.0,
+            y + line_height / 2.0,
+            x + w - axis_x - 1.0,
+            h - line_height,
+        );
+        let bottom = plot.1 + plot.3;
+
+        canvas.stroke_line((axis_x, plot.1), (axis_x, bottom), 1.0, axis_color);
+        canvas.stroke_line((axis_x, bottom), (x + w, bottom), 1.0, axis_color);
+
+        for (value, label) in labels {
+            let tick_y = bottom - (value - min) / (max - min) * plot.3;
+
+            canvas.stroke_line(
+                (axis_x - tick_length, tick_y),
+                (axis_x, tick_y),
+                1.0,
+                axis_color,
+            );
+
+            if let Some(font) = font {
+                canvas.draw_text(
+                    &TextRun {
+                        align: TextAlign::Right,
+                        direction: style.direction,
+                        ..TextRun::new(&[font], fs, style.color)
+                    },
+                    &label,
+                    x,
+                    tick_y - line_height / 2.0,
+                    None,
+                    label_width,
+                );
+            }
+        }
+    }
+
+    let (plot_x, plot_y, plot_w, plot_h) = plot;
+    let values = chart.values();
+
+    if values.is_empty() || plot_w <= 0.0 || plot_h <= 0.0 {
+        return;
+    }
+
+    let bottom = plot_y + plot_h;
+    let to_y = |value: f32| bottom - ((value - min) / (max - min)).clamp(0.0, 1.0) * plot_h;
+    let slots = chart.slots();
+    // Empty slots come first, so the newest value is always at the right
+    let first_slot = slots - values.len();
+
+    if chart.style == ChartStyle::Bar {
+        let slot_width = plot_w / slots as f32;
+        let baseline = to_y(0.0_f32.clamp(min, max));
+
+        for (i, &value) in values.iter().enumerate() {
+            let left = plot_x + (first_slot + i) as f32 * slot_width + slot_width * 0.1;
+            let top = to_y(value).min(baseline);
+            let height = (to_y(value).max(baseline) - top).max(1.0);
+
+            canvas.blend_rounded_rect((left, top, slot_width * 0.8, height), 0.0, fill, |_, _| 255);
+        }
+        return;
+    }
+
+    let step = if slots > 1 {
+        plot_w / (slots - 1) as f32
+    } else {
+        0.0
+    };
+    let points: Vec<(f32, f32)> = values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| (plot_x + (first_slot + i) as f32 * step, to_y(value)))
+        .collect();
+
+    if chart.style == ChartStyle::Area {
+        fill_under(canvas, &points, bottom, fill);
+    }
+
+    for segment in points.windows(2) {
+        canvas.stroke_line(segment[0], segment[1], chart.line_width, style.color);
+    }
+
+    // Round off the joins between segments, and show a lone value as a dot
+    for &point in &points[1..points.len().saturating_sub(1).max(1)] {
+        canvas.fill_circle(point, chart.line_width / 2.0, style.color);
+    }
+
+    if points.len() == 1 {
+        canvas.fill_circle(points[0], chart.line_width, style.color);
+    }
+}
+
+/// Draw a table: the rows in view scrolled by `scroll_y`, then the header row over the top
+/// of them.
+fn render_table(
+    canvas: &mut Canvas,
+    font: &Font,
+    style: &InheritedStyle,
+    table: &Table,
+    scroll_y: f32,
+    (x, y, w, h): (f32, f32, f32, f32),
+) {
+    let row_height = table.row_height(style.font_size);
+    let widths = table.column_widths(w);
+    let body_height = (h - row_height).max(0.0);
+
+    // Draw the rows onto a copy of what's behind them, so any scrolled partly out of view
+    // are cut off at the header and the bottom edge
+    let (body_x, body_y) = (x as i32, (y + row_height) as i32);
+    let (pixels, body_w, body_h) = canvas.copy_rect(body_x, body_y, w as u32, body_height as u32);
+    let origin = (body_x.max(0) as f32, body_y.max(0) as f32);
+    let mut body = Canvas::from_pixels(body_w, body_h, pixels);
+
+    let first = (scroll_y / row_height).floor().max(0.0) as usize;
+    let last =
+        (((scroll_y + body_height) / row_height).ceil().max(0.0) as usize).min(table.rows.len());
+
+    for index in first..last {
+        let row_y = y + row_height * (index + 1) as f32 - scroll_y;
+        let background = table.stripe_color.filter(|_| index % 2 == 1);
+        let cells = table.rows[index]
+            .iter()
+            .map(|cell| (cell.text.as_str(), cell.style));
+
+        let layout = RowLayout {
+            columns: &table.columns,
+            widths: &widths,
+            rect: (x - origin.0, row_y - origin.1, w, row_height),
+        };
+
+        render_table_row(&mut body, font, style, layout, cells, background);
+    }
+
+    let (body_w, body_h) = (body_w as f32, body_h as f32);
+    let body_rect = (origin.0, origin.1, body_w, body_h);
+    canvas.blit_scaled_xrgb(&body.pixels, body.width, body.height, body_rect, body_rect);
+
+    let titles = table
+        .columns
+        .iter()
+        .map(|column| (column.title.as_str(), CellStyle::default()));
+
+    let layout = RowLayout {
+        columns: &table.columns,
+        widths: &widths,
+        rect: (x, y, w, row_height),
+    };
+
+    render_table_row(canvas, font, style, layout, titles, table.header_background);
+
+    let rule_y = y + row_height - 0.5;
+    canvas.stroke_line((x, rule_y), (x + w, rule_y), 1.0, dimmed(style.color));
+}
+
+/// Draw a scanner: the latest frame, then the viewfinder over it, dimming the rest of
+/// the frame, and outlines around what was last detected.
+fn render_scanner(
+    canvas: &mut Canvas,
+    style: &InheritedStyle,
+    scanner: &Scanner,
+    frame: Option<&VideoFrame>,
+    (x, y, w, h): (f32, f32, f32, f32),
+) {
+    const MASK_ALPHA: u8 = 128;
+    const BLACK: RgbColor = RgbColor::rgb(0, 0, 0);
+
+    let thickness = (style.font_size / 6.0).max(2.0);
+
+    if let Some(frame) = frame {
+        let dst = scanner
+            .object_fit
+            .fit(frame.width, frame.height, x, y, w, h);
+        canvas.blit_scaled_xrgb(&frame.pixels, frame.width, frame.height, dst, (x, y, w, h));
+
+        let (dx, dy, dw, dh) = dst;
+        let scale_x = dw / frame.width as f32;
+        let scale_y = dh / frame.height as f32;
+
+        for &(fx, fy, fw, fh) in &scanner.highlights {
+            let left = dx + fx * scale_x;
+            let top = dy + fy * scale_y;
+            let right = left + fw * scale_x;
+            let bottom = top + fh * scale_y;
+
+            for (from, to) in [
+                ((left, top), (right, top)),
+                ((right, top), (right, bottom)),
+                ((right, bottom), (left, bottom)),
+                ((left, bottom), (left, top)),
+            ] {
+                canvas.stroke_line(from, to, thickness, style.color);
+            }
+        }
+    }
+
+    if !scanner.viewfinder {
+        return;
+    }
+
+    let (vx, vy, vw, vh) = scanner.viewfinder_rect((x, y, w, h));
+
+    for rect in [
+        (x, y, w, vy - y),
+        (x, vy + vh, w, y + h - vy - vh),
+        (x, vy, vx - x, vh),
+        (vx + vw, vy, x + w - vx - vw, vh),
+    ] {
+        canvas.tint(rect, 0.0, None, BLACK, MASK_ALPHA);
+    }
+
+    // Brackets at the corners of the viewfinder
+    let arm = vw.min(vh) * 0.15;
+    let inset = thickness / 2.0;
+
+    for (cx, cy, sx, sy) in [
+        (vx + inset, vy + inset, 1.0, 1.0),
+        (vx + vw - inset, vy + inset, -1.0, 1.0),
+        (vx + inset, vy + vh - inset, 1.0, -1.0),
+        (vx + vw - inset, vy + vh - inset, -1.0, -1.0),
+    ] {
+        canvas.stroke_line(
+            (cx - sx * inset, cy),
+            (cx + sx * arm, cy),
+            thickness,
+            style.color,
+        );
+        canvas.stroke_line(
+            (cx, cy - sy * inset),
+            (cx, cy + sy * arm),
+            thickness,
+            style.color,
+        );
+    }
+}
+
+/// Draw a markdown node: code backgrounds, then the text, then link underlines.
+fn render_markdown(
+    canvas: &mut Canvas,
+    fonts: MarkdownFonts,
+    style: &InheritedStyle,
+    markdown: &Markdown,
+    (x, y, w, _h): (f32, f32, f32, f32),
+) {
+    const CODE_ALPHA: u8 = 32;
+
+    let layout = markdown.layout(fonts, style.font_size, Some(w));
+    let radius = style.font_size / 4.0;
+    let link_color = markdown.link_color.unwrap_or(style.color);
+
+    for &(top, bottom) in &layout.code_blocks {
+        let rect = (x, y + top, w, bottom - top);
+        canvas.tint(rect, radius, None, style.color, CODE_ALPHA);
+    }
+
+    for run in layout.runs(fonts, |span| span.code) {
+        let (rx, ry, rw, rh) = run.rect;
+        // Pad inline code a little, but not code blocks, which have their own padding
+        let pad = if layout
+            .code_blocks
+            .iter()
+            .any(|&(top, bottom)| ry >= top && ry < bottom)
+        {
+            0.0
+        } else {
+            radius
+        };
+        let rect = (x + rx - pad, y + ry, rw + pad * 2.0, rh);
+        canvas.tint(rect, radius, None, style.color, CODE_ALPHA);
+    }
+
+    for placed in &layout.glyphs {
+        let glyph = &placed.glyph;
+        let color = if glyph.user_data.link.is_some() {
+            link_color
+        } else {
+            style.color
+        };
+
+        canvas.draw_glyph(
+            fonts[glyph.font_index],
+            glyph,
+            x + placed.origin.0,
+            y + placed.origin.1,
+            color,
+        );
+    }
+
+    for run in layout.runs(fonts, |span| span.link.is_some()) {
+        let (rx, _, rw, _) = run.rect;
+        let underline_y = y + run.baseline + (style.font_size / 12.0).max(1.0);
+        let thickness = (style.font_size / 16.0).max(1.0);
+        canvas.stroke_line(
+            (x + rx, underline_y),
+            (x + rx + rw, underline_y),
+            thickness,
+            link_color,
+        );
+    }
+}
+
+/// Where a table row is drawn, and the columns across it with the width each was given.
+struct RowLayout<'a> {
+    columns: &'a [Column],
+    widths: &'a [f32],
+    rect: (f32, f32, f32, f32),
+}
+
+/// Draw one row of a table, each cell's text cut off at the edge of its column.
+fn render_table_row<'a>(
+    canvas: &mut Canvas,
+    font: &Font,
+    style: &InheritedStyle,
+    layout: RowLayout,
+    cells: impl Iterator<Item = (&'a str, CellStyle)>,
+    background: Option<RgbColor>,
+) {
+    let RowLayout {
+        columns,
+        widths,
+        rect: (x, y, w, h),
+    } = layout;
+    let fs = style.font_size;
+    let padding = fs / 2.0;
+    let line_height = font
+        .horizontal_line_metrics(fs)
+        .map(|m| m.ascent - m.descent)
+        .unwrap_or(fs);
+
+    if let Some(background) = background {
+        canvas.blend_rounded_rect((x, y, w, h), 0.0, background, |_, _| 255);
+    }
+
+    let mut cell_x = x;
+
+    for ((text, cell_style), (column, &width)) in cells.zip(columns.iter().zip(widths)) {
+        if let Some(background) = cell_style.background {
+            canvas.blend_rounded_rect((cell_x, y, width, h), 0.0, background, |_, _| 255);
+        }
+
+        let text_width = width - padding * 2.0;
+        let mut advance = 0.0;
+        let fitting = text
+            .char_indices()
+            .find(|&(_, c)| {
+                advance += glyph_cache::advance(font, c, fs);
+                advance > text_width
+            })
+            .map_or(text, |(end, _)| &text[..end]);
+
+        canvas.draw_text(
+            &TextRun {
+                align: column.align,
+                direction: style.direction,
+                ..TextRun::new(&[font], fs, cell_style.color.unwrap_or(style.color))
+            },
+            fitting,
+            cell_x + padding,
+            y + (h - line_height) / 2.0,
+            None,
+            text_width.max(0.0),
+        );
+
+        cell_x += width;
+    }
+}
+
+/// Blend a translucent fill between a line through `points` (left to right) and `bottom`.
+fn fill_under(canvas: &mut Canvas, points: &[(f32, f32)], bottom: f32, color: RgbColor) {
+    const ALPHA: f32 = 96.0;
+
+    for segment in points.windows(2) {
+        let ((x0, y0), (x1, y1)) = (segment[0], segment[1]);
+
+        for px in x0.floor() as i32..x1.ceil() as i32 {
+            let cx = px as f32 + 0.5;
+
+            if cx < x0 || cx >= x1 {
+                continue;
+            }
+
+            let line_y = y0 + (y1 - y0) * (cx - x0) / (x1 - x0);
+            let top = line_y.floor() as i32;
+
+            // Cover the pixel the line crosses only as far as it's below the line
+            let coverage = 1.0 - (line_y - top as f32);
+            canvas.blend_pixel(px, top, color, (ALPHA * coverage) as u8);
+
+            for py in top + 1..bottom.ceil() as i32 {
+                canvas.blend_pixel(px, py, color, ALPHA as u8);
+            }
+        }
+    }
+}
+
+/// Draw the built-in keypad as a grid of rounded keys docked at the bottom of the screen.
+fn render_keypad(canvas: &mut Canvas, font: &Font, keypad: &Keypad, bounds: (f32, f32, f32, f32)) {
+    const GAP: f32 = 4.0;
+    const PANEL: Rgb888 = Rgb888::new(0x20, 0x20, 0x20);
+    const KEY: RgbColor = RgbColor::rgb(0x40, 0x40, 0x40);
+    const PRESSED_KEY: RgbColor = RgbColor::rgb(0x70, 0x70, 0x70);
+    const LABEL: RgbColor = RgbColor::rgb(0xff, 0xff, 0xff);
+
+    let (x, y, w, h) = bounds;
+
+    let _ = Rectangle::new(
+        Point::new(x as i32, y as i32),
+        Size::new(w as u32, h as u32),
+    )
+    .into_styled(PrimitiveStyle::with_fill(PANEL))
+    .draw(canvas);
+
+    for (i, (key, (kx, ky, kw, kh))) in keypad.keys(bounds).enumerate() {
+        let color = if keypad.pressed == Some((i, true)) {
+            PRESSED_KEY
+        } else {
+            KEY
+        };
+        let rect = (kx + GAP, ky + GAP, kw - GAP * 2.0, kh - GAP * 2.0);
+        canvas.fill_rounded_rect(rect, 6.0, color);
+
+        let fs = (kh * 0.4).round();
+        let line_height = font
+            .horizontal_line_metrics(fs)
+            .map(|m| m.ascent - m.descent)
+            .unwrap_or(fs);
+
+        canvas.draw_text(
+            &TextRun {
+                align: TextAlign::Center,
+                ..TextRun::new(&[font], fs, LABEL)
+            },
+            &key.label(),
+            kx,
+            ky + (kh - line_height) / 2.0,
+            None,
+            kw,
+        );
+    }
+}
+
+/// Draw the log viewer over the whole screen: a toolbar of level filters, then one entry
+/// per row, newest at the bottom.
+fn render_log_viewer(canvas: &mut Canvas, font: &Font, viewer: &LogViewer) {
+    const PANEL: Rgb888 = Rgb888::new(0x10, 0x10, 0x10);
+    const BUTTON: RgbColor = RgbColor::rgb(0x40, 0x40, 0x40);
+    const SELECTED_BUTTON: RgbColor = RgbColor::rgb(0x20, 0x60, 0xa0);
+    const LABEL: RgbColor = RgbColor::rgb(0xff, 0xff, 0xff);
+
+    let (width, height) = (canvas.width as f32, canvas.height as f32);
+    let (font_size, row_height, toolbar_height) = LogViewer::metrics(height);
+    let normal = canvas::normal_line_height(font, font_size);
+
+    let _ = Rectangle::new(Point::zero(), Size::new(canvas.width, canvas.height))
+        .into_styled(PrimitiveStyle::with_fill(PANEL))
+        .draw(canvas);
+
+    for (button, (bx, by, bw, bh)) in LogViewer::buttons(width, height) {
+        let color = if button == LogViewerButton::Filter(viewer.min_level) {
+            SELECTED_BUTTON
+        } else {
+            BUTTON
+        };
+        canvas.fill_rounded_rect((bx + 4.0, by + 4.0, bw - 8.0, bh - 8.0), 6.0, color);
+
+        canvas.draw_text(
+            &TextRun {
+                align: TextAlign::Center,
+                ..TextRun::new(&[font], font_size, LABEL)
+            },
+            button.label(),
+            bx,
+            by + (bh - normal) / 2.0,
+            None,
+            bw,
+        );
+    }
+
+    let list_height = height - toolbar_height;
+    let entries = viewer.entries();
+    let mut y = height + viewer.scroll - entries.len() as f32 * row_height;
+
+    canvas.push_clip((0.0, toolbar_height, width, list_height));
+
+    for entry in &entries {
+        if y + row_height > toolbar_height && y < height {
+            let color = match entry.level {
+                LogLevel::Debug => RgbColor::from_array([0x90, 0x90, 0x90]),
+                LogLevel::Info => LABEL,
+                LogLevel::Warn => RgbColor::from_array([0xff, 0xc0, 0x40]),
+                LogLevel::Error => RgbColor::from_array([0xff, 0x60, 0x60]),
+            };
+            let line = format!(
+                "{} {:5} {}",
+                entry.time_of_day(),
+                entry.level.as_str(),
+                entry.message.replace('\n', " ")
+            );
+
+            canvas.draw_text(
+                &TextRun::new(&[font], font_size, color),
+                &line,
+                4.0,
+                y + (row_height - normal) / 2.0,
+                None,
+                width,
             );
+        }
+
+        y += row_height;
+    }
+
+    canvas.pop_clip();
+}
+
+/// Draw the diagnostics screen over the whole canvas: test patterns, system info, the
+/// touch trace area and its buttons.
+fn render_diagnostics(
+    canvas: &mut Canvas,
+    font: Option<&Font>,
+    font_names: &[&str],
+    app_rendered: bool,
+    diagnostics: &Diagnostics,
+) {
+    const PANEL: Rgb888 = Rgb888::new(0x10, 0x10, 0x10);
+    const BORDER: Rgb888 = Rgb888::new(0x60, 0x60, 0x60);
+    const TRACE: Rgb888 = Rgb888::new(0x40, 0xff, 0x40);
+    const BUTTON: RgbColor = RgbColor::rgb(0x40, 0x40, 0x40);
+    const LABEL: RgbColor = RgbColor::rgb(0xff, 0xff, 0xff);
+
+    let (width, height) = (canvas.width as f32, canvas.height as f32);
+    let layout = DiagnosticsLayout::new(width, height);
+    let fill = |canvas: &mut Canvas, (x, y, w, h): (f32, f32, f32, f32), color: Rgb888| {
+        let _ = Rectangle::new(
+            Point::new(x as i32, y as i32),
+            Size::new(w.max(0.0) as u32, h.max(0.0) as u32),
+        )
+        .into_styled(PrimitiveStyle::with_fill(color))
+        .draw(canvas);
+    };
+
+    fill(canvas, (0.0, 0.0, width, height), PANEL);
+
+    let (bx, by, bw, bh) = layout.bars;
+    let bar_width = bw / COLOR_BARS.len() as f32;
+
+    for (i, [r, g, b]) in COLOR_BARS.into_iter().enumerate() {
+        let x = bx + (i as f32 * bar_width).round();
+        let next = bx + ((i + 1) as f32 * bar_width).round();
+        fill(canvas, (x, by, next - x, bh), Rgb888::new(r, g, b));
+    }
+
+    let (gx, gy, gw, gh) = layout.gradient;
+
+    for col in 0..gw as u32 {
+        let level = (col as f32 / (gw - 1.0).max(1.0) * 255.0).round() as u8;
+        fill(
+            canvas,
+            (gx + col as f32, gy, 1.0, gh),
+            Rgb888::new(level, level, level),
+        );
+    }
+
+    let (tx, ty, tw, th) = layout.touch;
+    let _ = Rectangle::new(
+        Point::new(tx as i32, ty as i32),
+        Size::new(tw as u32, th.max(0.0) as u32),
+    )
+    .into_styled(PrimitiveStyle::with_stroke(BORDER, 1))
+    .draw(canvas);
+
+    for stroke in &diagnostics.strokes {
+        for pair in stroke.windows(2) {
+            let [(x0, y0), (x1, y1)] = [pair[0], pair[1]];
+            let _ = Line::new(
+                Point::new(x0 as i32, y0 as i32),
+                Point::new(x1 as i32, y1 as i32),
+            )
+            .into_styled(PrimitiveStyle::with_stroke(TRACE, 3))
+            .draw(canvas);
+        }
+    }
+
+    let last_point = diagnostics.strokes.last().and_then(|stroke| stroke.last());
+
+    if let Some(&(px, py)) = last_point {
+        let _ = Line::new(
+            Point::new(tx as i32, py as i32),
+            Point::new((tx + tw) as i32, py as i32),
+        )
+        .into_styled(PrimitiveStyle::with_stroke(BORDER, 1))
+        .draw(canvas);
+        let _ = Line::new(
+            Point::new(px as i32, ty as i32),
+            Point::new(px as i32, (ty + th) as i32),
+        )
+        .into_styled(PrimitiveStyle::with_stroke(BORDER, 1))
+        .draw(canvas);
+    }
+
+    for rect in [layout.clear, layout.close] {
+        let (x, y, w, h) = rect;
+        canvas.fill_rounded_rect((x + 4.0, y + 4.0, w - 8.0, h - 8.0), 6.0, BUTTON);
+    }
+
+    let Some(font) = font else {
+        return;
+    };
+
+    let megabytes = |bytes: usize| bytes as f32 / (1024.0 * 1024.0);
+    let input_devices = if diagnostics.input_devices.is_empty() {
+        "none found".to_string()
+    } else {
+        diagnostics.input_devices.join(", ")
+    };
+    let fonts = if font_names.is_empty() {
+        "none loaded".to_string()
+    } else {
+        font_names.join(", ")
+    };
+    let resident = diagnostics.resident.map_or("unknown".to_string(), |bytes| {
+        format!("{:.1} MB", megabytes(bytes))
+    });
+    let lines = [
+        format!("juice {} on QuickJS", env!("CARGO_PKG_VERSION")),
+        format!("Display: {}x{}", canvas.width, canvas.height),
+        format!("Input: {}", input_devices),
+        format!("Fonts: {}", fonts),
+        format!(
+            "Memory: {} resident, {:.1} MB JS heap",
+            resident,
+            megabytes(diagnostics.js_heap)
+        ),
+        format!(
+            "App: {}",
+            if app_rendered {
+                "rendering"
+            } else {
+                "nothing rendered"
+            }
+        ),
+    ];
+
+    let (ix, iy, iw, ih) = layout.info;
+    let normal = canvas::normal_line_height(font, layout.font_size);
+    canvas.push_clip((ix, iy, iw, ih));
+
+    for (i, line) in lines.iter().enumerate() {
+        canvas.draw_text(
+            &TextRun::new(&[font], layout.font_size, LABEL),
+            line,
+            ix + 8.0,
+            iy + i as f32 * layout.line_height + (layout.line_height - normal) / 2.0,
+            None,
+            iw,
+        );
+    }
+
+    canvas.pop_clip();
+
+    if let Some(&(px, py)) = last_point {
+        canvas.draw_text(
+            &TextRun::new(&[font], layout.font_size, LABEL),
+            &format!("{:.0}, {:.0}", px, py),
+            tx + 8.0,
+            ty + 8.0,
+            None,
+            tw,
+        );
+    }
 
-            if *border_radius > 0.0 {
-                let r = *border_radius as u32;
-                let _ = RoundedRectangle::new(rect, CornerRadii::new(Size::new(r, r)))
-                    .into_styled(style)
-                    .draw(canvas);
-            } else {
-                let _ = rect.into_styled(style).draw(canvas);
-            }
-            ctx.render_dirty = false;
-        }
+    for (rect, label) in [(layout.clear, "Clear"), (layout.close, "Close")] {
+        let (x, y, w, h) = rect;
+        canvas.draw_text(
+            &TextRun {
+                align: TextAlign::Center,
+                ..TextRun::new(&[font], layout.font_size, LABEL)
+            },
+            label,
+            x,
+            y + (h - normal) / 2.0,
+            None,
+            w,
+        );
+    }
+}
 
-        NodeKind::Text { text, wrap_width } => {
-            if let Some(font) = fonts.get(&ctx.resolved_style.font_name) {
-                canvas.draw_text(
-                    font,
-                    text,
-                    ctx.resolved_style.font_size,
-                    ctx.resolved_style.color,
-                    x,
-                    y,
-                    *wrap_width,
-                    ctx.resolved_style.text_align,
-                    w,
-                );
-            }
-            ctx.render_dirty = false;
-        }
+/// Pick the refresh mode for a frame's damage: full if it touches a node hinted `full`,
+/// fast if it all lies within nodes hinted `fast`, otherwise partial.
+fn damage_refresh_mode(damage: &[DamageRect], dom: &Dom) -> RefreshMode {
+    let hints = dom.refresh_hints();
+    let overlaps = |rect: &DamageRect, &(x, y, w, h): &(f32, f32, f32, f32)| {
+        (rect.x as f32) < x + w
+            && x < (rect.x + rect.width) as f32
+            && (rect.y as f32) < y + h
+            && y < (rect.y + rect.height) as f32
+    };
+    let contains = |rect: &DamageRect, &(x, y, w, h): &(f32, f32, f32, f32)| {
+        x <= rect.x as f32
+            && y <= rect.y as f32
+            && (rect.x + rect.width) as f32 <= x + w
+            && (rect.y + rect.height) as f32 <= y + h
+    };
 
-        NodeKind::Svg { markup, .. } => {
-            if render_w > 0 && render_h > 0 {
-                // Use cached raster if available and not dirty
-                let needs_rasterize = ctx.render_dirty
-                    || ctx
-                        .cached_raster
-                        .as_ref()
-                        .map_or(true, |c| c.width != render_w || c.height != render_h);
+    if hints.iter().any(|(hint_rect, mode)| {
+        *mode == RefreshMode::Full && damage.iter().any(|rect| overlaps(rect, hint_rect))
+    }) {
+        return RefreshMode::Full;
+    }
 
-                if needs_rasterize {
-                    let current_color = ctx.resolved_style.with_overrides(&ctx.overrides).color;
-                    let color_hex = format!(
-                        "#{:02x}{:02x}{:02x}",
-                        current_color.r, current_color.g, current_color.b
-                    );
+    let all_fast = !damage.is_empty()
+        && damage.iter().all(|rect| {
+            hints
+                .iter()
+                .any(|(hint_rect, mode)| *mode == RefreshMode::Fast && contains(rect, hint_rect))
+        });
 
-                    let resolved = markup.replace("currentColor", &color_hex);
-                    let options = resvg::usvg::Options::default();
-
-                    match Tree::from_str(&resolved, &options) {
-                        Ok(tree) => {
-                            if let Some(mut pixmap) = Pixmap::new(render_w, render_h) {
-                                let svg_size = tree.size();
-                                let sx = render_w as f32 / svg_size.width();
-                                let sy = render_h as f32 / svg_size.height();
-                                let transform = resvg::tiny_skia::Transform::from_scale(sx, sy);
-
-                                resvg::render(&tree, transform, &mut pixmap.as_mut());
-
-                                let data = pixmap.data().to_vec();
-                                canvas.blit_premultiplied_rgba(
-                                    &data, render_w, render_h, x as i32, y as i32,
-                                );
-                                ctx.cached_raster = Some(crate::dom::CachedRaster {
-                                    data,
-                                    width: render_w,
-                                    height: render_h,
-                                });
-                            }
-                        }
-                        Err(err) => {
-                            println!("Error parsing SVG: {:?}", err);
-                        }
-                    }
-                } else if let Some(cache) = &ctx.cached_raster {
-                    canvas.blit_premultiplied_rgba(
-                        &cache.data,
-                        cache.width,
-                        cache.height,
-                        x as i32,
-                        y as i32,
-                    );
-                }
-            }
-            ctx.render_dirty = false;
-        }
+    if all_fast {
+        RefreshMode::Fast
+    } else {
+        RefreshMode::Partial
+    }
+}
 
-        NodeKind::Image {
-            data,
-            img_width,
-            img_height,
-            ..
-        } => {
-            if !data.is_empty() && *img_width > 0 && *img_height > 0 && render_w > 0 && render_h > 0
-            {
-                // Use cached raster if available and not dirty
-                let needs_rasterize = ctx.render_dirty
-                    || ctx
-                        .cached_raster
-                        .as_ref()
-                        .map_or(true, |c| c.width != render_w || c.height != render_h);
-
-                if needs_rasterize {
-                    if *img_width == render_w && *img_height == render_h {
-                        // No resize needed, blit directly and cache the raw data
-                        canvas.blit_rgba(data, *img_width, *img_height, x as i32, y as i32);
-                        ctx.cached_raster = Some(crate::dom::CachedRaster {
-                            data: data.clone(),
-                            width: render_w,
-                            height: render_h,
-                        });
-                    } else if let Some(src_img) =
-                        image::RgbaImage::from_raw(*img_width, *img_height, data.clone())
-                    {
-                        let resized = image::imageops::resize(
-                            &src_img,
-                            render_w,
-                            render_h,
-                            image::imageops::FilterType::Triangle,
-                        );
-                        let resized_data = resized.into_raw();
-                        canvas.blit_rgba(&resized_data, render_w, render_h, x as i32, y as i32);
-                        ctx.cached_raster = Some(crate::dom::CachedRaster {
-                            data: resized_data,
-                            width: render_w,
-                            height: render_h,
-                        });
-                    }
-                } else if let Some(cache) = &ctx.cached_raster {
-                    canvas.blit_rgba(&cache.data, cache.width, cache.height, x as i32, y as i32);
-                }
-            }
-            ctx.render_dirty = false;
-        }
+/// Draw the pull-to-refresh spinner in the gap above a refreshable node's content. While
+/// pulling, the arc grows towards the trigger distance; once refreshing it spins.
+fn render_refresh_indicator(
+    dom: &Dom,
+    canvas: &mut Canvas,
+    node_id: NodeId,
+    refreshing: Option<Instant>,
+) {
+    const DIAMETER: u32 = 24;
+    const STROKE_WIDTH: u32 = 3;
 
-        _ => {}
+    if !dom.is_refreshable(node_id) {
+        return;
     }
 
-    if let Some(children) = dom.get_children(node_id) {
-        for child_id in children {
-            render_node(dom, canvas, fonts, child_id, x, y);
-        }
+    let gap = -dom.scroll_offset(node_id).1;
+
+    let (Some(ctx), Some((x, y, w, h))) = (dom.get_node(node_id), dom.absolute_rect(node_id))
+    else {
+        return;
+    };
+
+    if gap <= 0.0 {
+        return;
     }
+
+    let (start, sweep) = match refreshing {
+        Some(started) => (
+            dom.clock()
+                .now()
+                .saturating_duration_since(started)
+                .as_secs_f32()
+                * 360.0,
+            270.0,
+        ),
+        None => {
+            let progress = (gap / scroll::REFRESH_TRIGGER).min(1.0);
+            (progress * 270.0 - 90.0, progress * 270.0)
+        }
+    };
+
+    // Slide the spinner down from behind the top edge until the indicator space is open
+    let center_y =
+        y + gap.min(scroll::REFRESH_INDICATOR_HEIGHT) - scroll::REFRESH_INDICATOR_HEIGHT / 2.0;
+    let center = Point::new((x + w / 2.0) as i32, center_y as i32);
+
+    let color = ctx.resolved_style.color;
+    let style = PrimitiveStyle::with_stroke(Rgb888::new(color.r, color.g, color.b), STROKE_WIDTH);
+    let bounds = Rectangle::new(
+        Point::new(x as i32, y as i32),
+        Size::new(w as u32, h as u32),
+    );
+
+    let _ = Arc::with_center(
+        center,
+        DIAMETER,
+        Angle::from_degrees(start),
+        Angle::from_degrees(sweep),
+    )
+    .into_styled(style)
+    .draw(&mut canvas.clipped(&bounds));
 }
 
 impl JsModule for Renderer {
     fn register(&self, ctx: &Ctx<'_>) {
         let renderer = Object::new(ctx.clone()).unwrap();
 
+        renderer
+            .set("capabilities", Capabilities::current())
+            .unwrap();
+
         let dom_cell = self.dom.clone();
         let should_update_cell = self.should_update.clone();
         let event_callback_cell = self.event_callback.clone();
         let fonts_cell = self.fonts.clone();
-        let fonts_for_add = self.fonts.clone();
+
+        let event_callback = self.event_callback.clone();
+        Engine::on_teardown(ctx, move || {
+            event_callback.borrow_mut().take();
+        });
         let canvas_width = self.canvas.width as f32;
         let canvas_height = self.canvas.height as f32;
 
@@ -355,6 +4041,14 @@ impl JsModule for Renderer {
                     move |event_callback: Persistent<Function<'static>>| {
                         let mut dom = dom_cell.borrow_mut();
                         dom.compute_layout(&*fonts_cell.borrow(), canvas_width, canvas_height);
+
+                        // Measured in the default instance of variable fonts whose other
+                        // instances were asked for the first time
+                        if fonts_cell.borrow_mut().make_instances() {
+                            dom.fonts_changed();
+                            dom.compute_layout(&fonts_cell.borrow(), canvas_width, canvas_height);
+                        }
+
                         *should_update_cell.borrow_mut() = true;
                         *event_callback_cell.borrow_mut() = Some(event_callback);
                     },
@@ -362,25 +4056,386 @@ impl JsModule for Renderer {
             )
             .unwrap();
 
+        let app_ready = self.app.app_ready.clone();
+        let should_update_for_ready = self.should_update.clone();
+
+        renderer
+            .set(
+                "ready",
+                Func::from(move || {
+                    app_ready.set(true);
+                    *should_update_for_ready.borrow_mut() = true;
+                }),
+            )
+            .unwrap();
+
+        let dom_for_fonts = self.dom.clone();
+        let fonts_for_add = self.fonts.clone();
+        let should_update_for_fonts = self.should_update.clone();
+        let preloaded = self.decoder.preloaded();
+
         renderer
             .set(
                 "addFont",
                 Func::from(MutFn::from(move |name: String, src: String| {
-                    match src.split(',').nth(1).and_then(|str| {
-                        base64::Engine::decode(&general_purpose::STANDARD, str).ok()
-                    }) {
-                        Some(data) => {
-                            let font = Font::from_bytes(data, FontSettings::default()).unwrap();
+                    let font = match preloaded.take_font(&src) {
+                        Some(font) => Ok(font),
+                        None => decoder::decode_font(&src),
+                    };
+
+                    match font {
+                        Ok(font) => {
                             fonts_for_add.borrow_mut().insert(name, font);
+                            fonts_changed(
+                                &dom_for_fonts,
+                                &fonts_for_add.borrow(),
+                                (canvas_width, canvas_height),
+                            );
+                            *should_update_for_fonts.borrow_mut() = true;
+                        }
+                        Err(err) => {
+                            println!("addFont: can't load font \"{}\": {}", name, err);
+                        }
+                    }
+                })),
+            )
+            .unwrap();
+
+        let pending_preloads = self.app.pending_preloads.clone();
+
+        renderer
+            .set(
+                "preload",
+                Func::from(move |assets: Vec<HashMap<String, String>>| {
+                    for asset in assets {
+                        let kind = asset.get("type").map(String::as_str).unwrap_or_default();
+                        let src = asset.get("src").cloned().unwrap_or_default();
+
+                        match Preload::parse(kind, src) {
+                            Some(preload) => pending_preloads.borrow_mut().push(preload),
+                            None => println!("preload: unknown asset type \"{}\"", kind),
+                        }
+                    }
+                }),
+            )
+            .unwrap();
+
+        let dom_for_fonts = self.dom.clone();
+        let fonts_for_default = self.fonts.clone();
+        let should_update_for_fonts = self.should_update.clone();
+
+        renderer
+            .set(
+                "setDefaultFont",
+                Func::from(MutFn::from(move |name: String| {
+                    let fonts = fonts_for_default.borrow();
+
+                    if !fonts.contains_key(&name) && !fonts.contains_family(&name) {
+                        return false;
+                    }
+
+                    drop(fonts);
+
+                    dom_for_fonts.borrow_mut().set_default_font(name);
+                    fonts_changed(
+                        &dom_for_fonts,
+                        &fonts_for_default.borrow(),
+                        (canvas_width, canvas_height),
+                    );
+                    *should_update_for_fonts.borrow_mut() = true;
+                    true
+                })),
+            )
+            .unwrap();
+
+        let dom_for_fonts = self.dom.clone();
+        let fonts_for_faces = self.fonts.clone();
+        let should_update_for_fonts = self.should_update.clone();
+
+        renderer
+            .set(
+                "addFontFace",
+                Func::from(MutFn::from(
+                    move |family: String, name: String, weight: f64, style: String| {
+                        let (Some(weight), Some(style)) = (
+                            FontWeight::from_number(weight as f32),
+                            FontStyle::parse(&style),
+                        ) else {
+                            return false;
+                        };
+
+                        if !add_font_face(
+                            &mut fonts_for_faces.borrow_mut(),
+                            &family,
+                            &name,
+                            weight,
+                            style,
+                        ) {
+                            return false;
                         }
-                        None => {
-                            println!("addFont: font not a valid base64 URL");
+
+                        fonts_changed(
+                            &dom_for_fonts,
+                            &fonts_for_faces.borrow(),
+                            (canvas_width, canvas_height),
+                        );
+                        *should_update_for_fonts.borrow_mut() = true;
+                        true
+                    },
+                )),
+            )
+            .unwrap();
+
+        let dom_for_fonts = self.dom.clone();
+        let fonts_for_fallbacks = self.fonts.clone();
+        let should_update_for_fonts = self.should_update.clone();
+
+        renderer
+            .set(
+                "setFontFallbacks",
+                Func::from(MutFn::from(move |names: Vec<String>| {
+                    fonts_for_fallbacks.borrow_mut().set_fallbacks(names);
+                    fonts_changed(
+                        &dom_for_fonts,
+                        &fonts_for_fallbacks.borrow(),
+                        (canvas_width, canvas_height),
+                    );
+                    *should_update_for_fonts.borrow_mut() = true;
+                })),
+            )
+            .unwrap();
+
+        let dom_for_scale = self.dom.clone();
+        let fonts_for_scale = self.fonts.clone();
+        let should_update_for_scale = self.should_update.clone();
+
+        renderer
+            .set(
+                "setTextScale",
+                Func::from(MutFn::from(move |factor: f64| {
+                    let mut dom = dom_for_scale.borrow_mut();
+                    dom.set_text_scale(factor as f32);
+                    dom.compute_layout(&fonts_for_scale.borrow(), canvas_width, canvas_height);
+                    *should_update_for_scale.borrow_mut() = true;
+                })),
+            )
+            .unwrap();
+
+        let display_filter = self.display_filter.clone();
+        let invalidated = self.invalidated.clone();
+        let should_update_for_filter = self.should_update.clone();
+        let screen_size = (self.canvas.width, self.canvas.height);
+
+        renderer
+            .set(
+                "setDisplayFilter",
+                Func::from(MutFn::from(move |filter: String| {
+                    let Some(filter) = DisplayFilter::parse(&filter) else {
+                        return false;
+                    };
+
+                    set_display_filter(
+                        &display_filter,
+                        &invalidated,
+                        &should_update_for_filter,
+                        filter,
+                        screen_size,
+                    );
+                    true
+                })),
+            )
+            .unwrap();
+
+        let dom_for_theme = self.dom.clone();
+        let should_update_for_theme = self.should_update.clone();
+
+        renderer
+            .set(
+                "registerTheme",
+                Func::from(MutFn::from(
+                    move |name: String, variables: HashMap<String, String>| {
+                        dom_for_theme.borrow_mut().register_theme(name, variables);
+                        // Redraw in case the active theme was replaced
+                        *should_update_for_theme.borrow_mut() = true;
+                    },
+                )),
+            )
+            .unwrap();
+
+        let dom_for_theme = self.dom.clone();
+        let pending_theme = self.app.pending_theme.clone();
+        let should_update_for_theme = self.should_update.clone();
+
+        renderer
+            .set(
+                "setTheme",
+                Func::from(MutFn::from(move |name: String| {
+                    // Events can't be dispatched from inside a JS call, so the switch
+                    // happens on the next tick. Compare with a switch already waiting, so
+                    // two calls in a row report as they would one after the other.
+                    let dom = dom_for_theme.borrow();
+                    let mut pending = pending_theme.borrow_mut();
+                    let current = pending.as_deref().or(dom.active_theme());
+                    let changes = dom.has_theme(&name) && current != Some(name.as_str());
+
+                    if changes {
+                        *pending = Some(name);
+                        *should_update_for_theme.borrow_mut() = true;
+                    }
+
+                    changes
+                })),
+            )
+            .unwrap();
+
+        let dom_for_easing = self.dom.clone();
+        let should_update_for_easing = self.should_update.clone();
+
+        renderer
+            .set(
+                "registerEasing",
+                Func::from(MutFn::from(
+                    move |name: String, x1: f32, y1: f32, x2: f32, y2: f32| {
+                        let registered = dom_for_easing
+                            .borrow_mut()
+                            .register_easing(&name, (x1, y1, x2, y2));
+
+                        if registered {
+                            *should_update_for_easing.borrow_mut() = true;
                         }
+
+                        registered
+                    },
+                )),
+            )
+            .unwrap();
+
+        // For IMEs written in JS. These edit the input directly; the element wrapper
+        // dispatches the composition and input events itself
+        let dom_for_input = self.dom.clone();
+        let should_update_for_input = self.should_update.clone();
+
+        renderer
+            .set(
+                "setComposition",
+                Func::from(MutFn::from(move |node_id: u64, text: Option<String>| {
+                    dom_for_input
+                        .borrow_mut()
+                        .edit_text_input(NodeId::from(node_id), |state| state.composition = text);
+                    *should_update_for_input.borrow_mut() = true;
+                })),
+            )
+            .unwrap();
+
+        let pending_prints = self.app.pending_prints.clone();
+
+        renderer
+            .set(
+                "print",
+                Func::from(move |node_id: u64| {
+                    pending_prints.borrow_mut().push(NodeId::from(node_id));
+                }),
+            )
+            .unwrap();
+
+        let dom_for_invalidate = self.dom.clone();
+        let invalidated = self.invalidated.clone();
+        let should_update_for_invalidate = self.should_update.clone();
+
+        renderer
+            .set(
+                "invalidate",
+                Func::from(move |node_id: u64| {
+                    invalidate(&dom_for_invalidate, &invalidated, NodeId::from(node_id));
+                    *should_update_for_invalidate.borrow_mut() = true;
+                }),
+            )
+            .unwrap();
+
+        let invalidated = self.invalidated.clone();
+        let should_update_for_invalidate = self.should_update.clone();
+
+        renderer
+            .set(
+                "invalidateRect",
+                Func::from(move |x: f32, y: f32, w: f32, h: f32| {
+                    invalidated
+                        .borrow_mut()
+                        .push(Invalidation::Rect((x, y, w, h)));
+                    *should_update_for_invalidate.borrow_mut() = true;
+                }),
+            )
+            .unwrap();
+
+        let latency = self.latency.clone();
+
+        renderer
+            .set(
+                "latencyStats",
+                Func::from(move || latency.borrow().as_ref().map(LatencyRecorder::stats)),
+            )
+            .unwrap();
+
+        let dom_for_dump = self.dom.clone();
+
+        renderer
+            .set(
+                "dumpTree",
+                Func::from(move || dom_for_dump.borrow().to_json().to_string()),
+            )
+            .unwrap();
+
+        let dom_for_grab = self.dom.clone();
+
+        renderer
+            .set(
+                "setInputGrab",
+                Func::from(move |node_id: Option<u64>| {
+                    dom_for_grab
+                        .borrow_mut()
+                        .set_input_grab(node_id.map(NodeId::from));
+                }),
+            )
+            .unwrap();
+
+        let dom_for_chart = self.dom.clone();
+        let should_update_for_chart = self.should_update.clone();
+
+        renderer
+            .set(
+                "appendChartData",
+                Func::from(MutFn::from(move |node_id: u64, values: Vec<f32>| {
+                    let appended = dom_for_chart
+                        .borrow_mut()
+                        .append_chart_values(NodeId::from(node_id), values);
+
+                    if appended {
+                        *should_update_for_chart.borrow_mut() = true;
                     }
                 })),
             )
             .unwrap();
 
+        let dom_for_input = self.dom.clone();
+        let should_update_for_input = self.should_update.clone();
+
+        renderer
+            .set(
+                "insertText",
+                Func::from(MutFn::from(move |node_id: u64, text: String| {
+                    *should_update_for_input.borrow_mut() = true;
+
+                    dom_for_input
+                        .borrow_mut()
+                        .edit_text_input(NodeId::from(node_id), |state| {
+                            state.composition = None;
+                            state.insert(&text);
+                            state.value.clone()
+                        })
+                })),
+            )
+            .unwrap();
+
         ctx.globals().set("renderer", renderer).unwrap();
     }
 }
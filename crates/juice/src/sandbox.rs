@@ -0,0 +1,880 @@
+use std::io::{self, Read, Write};
+use std::os::fd::{AsFd, OwnedFd};
+use std::os::unix::net::UnixStream;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::canvas::Canvas;
+use crate::damage::DamageRect;
+use crate::display_filter::DisplayFilter;
+use crate::memory::TrimLevel;
+use crate::mono::RefreshMode;
+use crate::pointer::{Pointer, PointerTool};
+use crate::renderer::Renderer;
+
+/// Environment variable telling the app process its stdin is its end of a socket pair
+/// with the display process
+const SOCKET_VAR: &str = "JUICE_SANDBOX";
+/// Environment variable giving the app process the display size, as `WIDTHxHEIGHT`
+const SIZE_VAR: &str = "JUICE_SANDBOX_SIZE";
+
+/// Largest message either side accepts, so a misbehaving app process can't make the
+/// display process allocate without bound. Fits a full frame of a 4K panel.
+const MAX_MESSAGE: usize = 64 * 1024 * 1024;
+
+/// Restart delays never grow past this
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(30);
+/// An app process that ran for this long before dying is restarted without backing off
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+// Message kinds, display process to app process
+const POINTER: u8 = 1;
+const ENCODER: u8 = 2;
+const AMBIENT_LIGHT: u8 = 3;
+const TRIM_MEMORY: u8 = 4;
+const RELOAD: u8 = 5;
+const WHEEL: u8 = 6;
+
+// App process to display process
+const FRAME: u8 = 16;
+
+/// Settings for running the app in a child process, read from the host config. Every
+/// field is optional in JSON.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxConfig {
+    /// How long to wait before restarting an app process that crashed, in milliseconds.
+    /// Doubled each time it crashes again soon after starting, up to 30 seconds.
+    #[serde(default = "default_restart_delay_ms")]
+    pub restart_delay_ms: u64,
+    /// How long the app process can go without sending a frame, in milliseconds, before
+    /// it's taken to be stuck, e.g. in an endless JS loop, and restarted
+    #[serde(default = "default_hang_timeout_ms")]
+    pub hang_timeout_ms: u64,
+    /// Bytes of address space the app process can have, so running out of memory takes
+    /// down the app rather than the device. Applied by the host, when it spawns the app.
+    pub memory_limit: Option<u64>,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        SandboxConfig {
+            restart_delay_ms: default_restart_delay_ms(),
+            hang_timeout_ms: default_hang_timeout_ms(),
+            memory_limit: None,
+        }
+    }
+}
+
+fn default_restart_delay_ms() -> u64 {
+    500
+}
+
+fn default_hang_timeout_ms() -> u64 {
+    10_000
+}
+
+#[derive(Debug)]
+pub struct SandboxError {
+    pub message: String,
+}
+
+impl From<io::Error> for SandboxError {
+    fn from(err: io::Error) -> Self {
+        SandboxError {
+            message: err.to_string(),
+        }
+    }
+}
+
+/// Input and host events the display process forwards to the app process, which
+/// dispatches them to its renderer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SandboxEvent {
+    /// `Renderer::dispatch_pointer_event`
+    Pointer {
+        name: String,
+        x: f32,
+        y: f32,
+        pointer: Pointer,
+    },
+    /// `Renderer::dispatch_encoder`
+    Encoder { notches: f32 },
+    /// `Renderer::dispatch_wheel`
+    Wheel {
+        x: f32,
+        y: f32,
+        notches_x: f32,
+        notches_y: f32,
+    },
+    /// `Renderer::dispatch_ambient_light`
+    AmbientLight { lux: f32 },
+    /// `Renderer::trim_memory`
+    TrimMemory(TrimLevel),
+    /// `Renderer::reload`, with a new bundle
+    Reload(String),
+}
+
+impl SandboxEvent {
+    /// Dispatch the event to the app process's renderer, as the host would without a
+    /// sandbox.
+    pub async fn dispatch(self, renderer: &mut Renderer) {
+        match self {
+            SandboxEvent::Pointer {
+                name,
+                x,
+                y,
+                pointer,
+            } => renderer.dispatch_pointer_event(&name, x, y, pointer).await,
+            SandboxEvent::Encoder { notches } => renderer.dispatch_encoder(notches).await,
+            SandboxEvent::Wheel {
+                x,
+                y,
+                notches_x,
+                notches_y,
+            } => renderer.dispatch_wheel(x, y, notches_x, notches_y).await,
+            SandboxEvent::AmbientLight { lux } => renderer.dispatch_ambient_light(lux).await,
+            SandboxEvent::TrimMemory(level) => renderer.trim_memory(level).await,
+            SandboxEvent::Reload(bundle) => renderer.reload(&bundle).await,
+        }
+    }
+
+    fn encode(&self) -> (u8, Vec<u8>) {
+        let mut payload = Vec::new();
+
+        let kind = match self {
+            SandboxEvent::Pointer {
+                name,
+                x,
+                y,
+                pointer,
+            } => {
+                payload.push(name.len().min(255) as u8);
+                payload.extend_from_slice(&name.as_bytes()[..name.len().min(255)]);
+                payload.extend_from_slice(&x.to_le_bytes());
+                payload.extend_from_slice(&y.to_le_bytes());
+                payload.push(pointer.tool as u8);
+                payload.extend_from_slice(&pointer.pressure.to_le_bytes());
+                POINTER
+            }
+            SandboxEvent::Encoder { notches } => {
+                payload.extend_from_slice(&notches.to_le_bytes());
+                ENCODER
+            }
+            SandboxEvent::Wheel {
+                x,
+                y,
+                notches_x,
+                notches_y,
+            } => {
+                for value in [x, y, notches_x, notches_y] {
+                    payload.extend_from_slice(&value.to_le_bytes());
+                }
+                WHEEL
+            }
+            SandboxEvent::AmbientLight { lux } => {
+                payload.extend_from_slice(&lux.to_le_bytes());
+                AMBIENT_LIGHT
+            }
+            SandboxEvent::TrimMemory(level) => {
+                payload.push(*level as u8);
+                TRIM_MEMORY
+            }
+            SandboxEvent::Reload(bundle) => {
+                payload.extend_from_slice(bundle.as_bytes());
+                RELOAD
+            }
+        };
+
+        (kind, payload)
+    }
+
+    fn decode(kind: u8, payload: Vec<u8>) -> Option<Self> {
+        let mut bytes = Bytes(&payload);
+
+        match kind {
+            POINTER => {
+                let length = bytes.u8()? as usize;
+                let name = String::from_utf8(bytes.take(length)?.to_vec()).ok()?;
+                let (x, y) = (bytes.f32()?, bytes.f32()?);
+                let tool = match bytes.u8()? {
+                    1 => PointerTool::Pen,
+                    2 => PointerTool::Eraser,
+                    3 => PointerTool::Mouse,
+                    _ => PointerTool::Touch,
+                };
+                let pointer = Pointer::new(tool, bytes.f32()?);
+
+                Some(SandboxEvent::Pointer {
+                    name,
+                    x,
+                    y,
+                    pointer,
+                })
+            }
+            ENCODER => Some(SandboxEvent::Encoder {
+                notches: bytes.f32()?,
+            }),
+            WHEEL => Some(SandboxEvent::Wheel {
+                x: bytes.f32()?,
+                y: bytes.f32()?,
+                notches_x: bytes.f32()?,
+                notches_y: bytes.f32()?,
+            }),
+            AMBIENT_LIGHT => Some(SandboxEvent::AmbientLight { lux: bytes.f32()? }),
+            TRIM_MEMORY => Some(SandboxEvent::TrimMemory(match bytes.u8()? {
+                0 => TrimLevel::Moderate,
+                _ => TrimLevel::Critical,
+            })),
+            RELOAD => String::from_utf8(payload).ok().map(SandboxEvent::Reload),
+            _ => None,
+        }
+    }
+}
+
+/// What changed on the display process's canvas from the app's latest frames.
+pub struct SandboxFrame {
+    pub damage: Vec<DamageRect>,
+    pub refresh_mode: RefreshMode,
+}
+
+/// Where the app process is in its life.
+enum AppState {
+    Running {
+        child: Child,
+        since: Instant,
+        /// Events waiting for the writer thread, so a stuck app can't block input
+        events: mpsc::Sender<(u8, Vec<u8>)>,
+        frames: mpsc::Receiver<(u8, Vec<u8>)>,
+        last_frame: Instant,
+    },
+    /// Gone, and restarting at `at`
+    Restarting { at: Instant },
+}
+
+/// The display process's side of the sandbox: it owns the display and input, and runs
+/// the JS app in a child process that renders into a canvas of its own. The child sends
+/// back the damaged parts of each frame and gets input events in return, so a crash, an
+/// out-of-memory kill or a hang only takes down the child. The last frame stays on the
+/// display while the app restarts.
+pub struct SandboxHost {
+    command: Box<dyn FnMut() -> Command>,
+    width: u32,
+    height: u32,
+    state: AppState,
+    restart_delay: Duration,
+    backoff: Duration,
+    hang_timeout: Duration,
+    display_filter: DisplayFilter,
+    /// The latest bundle from a hot reload, sent again to each new app process
+    bundle: Option<String>,
+}
+
+impl SandboxHost {
+    /// Start the app process for a `width` by `height` display. `command` makes the
+    /// command to run it, usually the host's own executable, which should run the app
+    /// with a `SandboxClient` when `SandboxClient::connect` finds one. It's called again
+    /// each time the app restarts. The app process talks to this one over its stdin, one
+    /// end of a socket pair no other process can connect to, so `command` shouldn't set
+    /// stdin itself.
+    pub fn spawn(
+        command: impl FnMut() -> Command + 'static,
+        config: &SandboxConfig,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, SandboxError> {
+        let restart_delay = Duration::from_millis(config.restart_delay_ms);
+
+        let mut host = SandboxHost {
+            command: Box::new(command),
+            width,
+            height,
+            state: AppState::Restarting { at: Instant::now() },
+            restart_delay,
+            backoff: restart_delay,
+            hang_timeout: Duration::from_millis(config.hang_timeout_ms),
+            display_filter: DisplayFilter::None,
+            bundle: None,
+        };
+
+        host.state = host.start()?;
+        Ok(host)
+    }
+
+    /// Spawn the app process with its end of a new socket pair, sending it the latest
+    /// hot-reloaded bundle if there is one.
+    fn start(&mut self) -> Result<AppState, SandboxError> {
+        let (stream, app_stream) = UnixStream::pair()?;
+
+        let child = (self.command)()
+            .env(SOCKET_VAR, "1")
+            .env(SIZE_VAR, format!("{}x{}", self.width, self.height))
+            .stdin(Stdio::from(OwnedFd::from(app_stream)))
+            .spawn()
+            .map_err(|err| SandboxError {
+                message: format!("Can't start the app process: {}", err),
+            })?;
+
+        let (events, frames) = connect(stream)?;
+
+        if let Some(bundle) = &self.bundle {
+            let _ = events.send(SandboxEvent::Reload(bundle.clone()).encode());
+        }
+
+        let now = Instant::now();
+
+        Ok(AppState::Running {
+            child,
+            since: now,
+            events,
+            frames,
+            last_frame: now,
+        })
+    }
+
+    /// Forward an event to the app. Events while it's restarting are dropped.
+    pub fn send(&mut self, event: SandboxEvent) {
+        if let SandboxEvent::Reload(bundle) = &event {
+            self.bundle = Some(bundle.clone());
+        }
+
+        if let AppState::Running { events, .. } = &self.state {
+            let _ = events.send(event.encode());
+        }
+    }
+
+    /// The filter the app last set with `renderer.setDisplayFilter`.
+    pub fn display_filter(&self) -> DisplayFilter {
+        self.display_filter
+    }
+
+    /// Whether the app process is connected and drawing.
+    pub fn is_running(&self) -> bool {
+        matches!(self.state, AppState::Running { .. })
+    }
+
+    /// Copy the frames the app sent since the last poll onto `canvas`, returning what
+    /// changed, or `None` if nothing did. Also connects, watches and restarts the app
+    /// process, so call it every frame.
+    pub fn poll(&mut self, canvas: &mut Canvas) -> Option<SandboxFrame> {
+        let now = Instant::now();
+
+        match &mut self.state {
+            AppState::Running {
+                frames, last_frame, ..
+            } => {
+                let mut changed: Option<SandboxFrame> = None;
+
+                loop {
+                    match frames.try_recv() {
+                        Ok((FRAME, payload)) => {
+                            *last_frame = now;
+
+                            let Some((mode, filter, damage)) = apply_frame(&payload, canvas) else {
+                                self.crashed("sent a malformed frame");
+                                return changed;
+                            };
+
+                            self.display_filter = filter;
+
+                            if !damage.is_empty() {
+                                let frame = changed.get_or_insert(SandboxFrame {
+                                    damage: Vec::new(),
+                                    refresh_mode: RefreshMode::Fast,
+                                });
+                                frame.damage.extend(damage);
+                                frame.refresh_mode = frame.refresh_mode.combine(mode);
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(mpsc::TryRecvError::Empty) => {
+                            if now.duration_since(*last_frame) > self.hang_timeout {
+                                self.crashed("stopped responding");
+                            }
+
+                            return changed;
+                        }
+                        Err(mpsc::TryRecvError::Disconnected) => {
+                            self.crashed("disconnected");
+                            return changed;
+                        }
+                    }
+                }
+            }
+            AppState::Restarting { at } => {
+                if now >= *at {
+                    match self.start() {
+                        Ok(state) => self.state = state,
+                        Err(err) => {
+                            eprintln!("[sandbox] {}", err.message);
+                            self.state = AppState::Restarting {
+                                at: now + self.backoff,
+                            };
+                        }
+                    }
+                }
+
+                None
+            }
+        }
+    }
+
+    /// Take the child out of the current state, leaving it restarting.
+    fn take_child(&mut self) -> Child {
+        let state = std::mem::replace(&mut self.state, AppState::Restarting { at: Instant::now() });
+
+        match state {
+            AppState::Running { child, .. } => child,
+            AppState::Restarting { .. } => unreachable!("no app process to take"),
+        }
+    }
+
+    /// Kill what's left of the app process and restart it after the backoff, which
+    /// doubles if it hadn't been running long.
+    fn crashed(&mut self, reason: &str) {
+        let since = match &self.state {
+            AppState::Running { since, .. } => *since,
+            AppState::Restarting { .. } => return,
+        };
+
+        let mut child = self.take_child();
+        let _ = child.kill();
+        let status = child.wait();
+
+        if since.elapsed() >= STABLE_RUN {
+            self.backoff = self.restart_delay;
+        }
+
+        eprintln!(
+            "[sandbox] app process {} ({}), restarting in {}ms",
+            reason,
+            status.map_or_else(|err| err.to_string(), |status| status.to_string()),
+            self.backoff.as_millis()
+        );
+
+        self.state = AppState::Restarting {
+            at: Instant::now() + self.backoff,
+        };
+        self.backoff = (self.backoff * 2).min(MAX_RESTART_DELAY);
+    }
+}
+
+impl Drop for SandboxHost {
+    fn drop(&mut self) {
+        if let AppState::Running { child, .. } = &mut self.state {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Start threads writing events to and reading frames from a connected app process.
+type Channels = (mpsc::Sender<(u8, Vec<u8>)>, mpsc::Receiver<(u8, Vec<u8>)>);
+
+fn connect(stream: UnixStream) -> io::Result<Channels> {
+    stream.set_nonblocking(false)?;
+    let mut reader = stream.try_clone()?;
+    let mut writer = stream;
+
+    let (events, event_rx) = mpsc::channel::<(u8, Vec<u8>)>();
+    let (frame_tx, frames) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        while let Ok((kind, payload)) = event_rx.recv() {
+            if write_message(&mut writer, kind, &payload).is_err() {
+                break;
+            }
+        }
+
+        let _ = writer.shutdown(std::net::Shutdown::Both);
+    });
+
+    std::thread::spawn(move || {
+        while let Ok(message) = read_message(&mut reader) {
+            if frame_tx.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok((events, frames))
+}
+
+/// Copy a frame's damaged rects onto the canvas. `None`, leaving the canvas untouched, if
+/// any part of the frame doesn't fit it.
+fn apply_frame(
+    payload: &[u8],
+    canvas: &mut Canvas,
+) -> Option<(RefreshMode, DisplayFilter, Vec<DamageRect>)> {
+    let mut bytes = Bytes(payload);
+
+    let mode = match bytes.u8()? {
+        0 => RefreshMode::Fast,
+        1 => RefreshMode::Partial,
+        _ => RefreshMode::Full,
+    };
+    let filter = match bytes.u8()? {
+        1 => DisplayFilter::Invert,
+        2 => DisplayFilter::HighContrast,
+        3 => DisplayFilter::Deuteranopia,
+        _ => DisplayFilter::None,
+    };
+
+    let count = bytes.u32()?;
+    let mut rects = Vec::new();
+
+    // Check every rect before drawing any of them, so a bad frame can't leave half of
+    // itself on screen
+    for _ in 0..count {
+        let rect = DamageRect {
+            x: bytes.u32()?,
+            y: bytes.u32()?,
+            width: bytes.u32()?,
+            height: bytes.u32()?,
+        };
+
+        if rect.x.checked_add(rect.width)? > canvas.width
+            || rect.y.checked_add(rect.height)? > canvas.height
+        {
+            return None;
+        }
+
+        if rect.width == 0 || rect.height == 0 {
+            continue;
+        }
+
+        let pixels = bytes.take(rect.width as usize * rect.height as usize * 4)?;
+        rects.push((rect, pixels));
+    }
+
+    let mut damage = Vec::with_capacity(rects.len());
+
+    for (rect, pixels) in rects {
+        let rows = pixels.chunks_exact(rect.width as usize * 4);
+
+        for (row, pixels) in (rect.y..rect.y + rect.height).zip(rows) {
+            let start = (row * canvas.width + rect.x) as usize;
+
+            for (pixel, bytes) in canvas.pixels[start..start + rect.width as usize]
+                .iter_mut()
+                .zip(pixels.chunks_exact(4))
+            {
+                *pixel = u32::from_le_bytes(bytes.try_into().unwrap());
+            }
+        }
+
+        damage.push(rect);
+    }
+
+    Some((mode, filter, damage))
+}
+
+/// The app process's side of the sandbox: events from the display process come in, and
+/// frames go out.
+pub struct SandboxClient {
+    pub width: u32,
+    pub height: u32,
+    writer: UnixStream,
+    events: mpsc::Receiver<SandboxEvent>,
+}
+
+impl SandboxClient {
+    /// Connect to the display process, if this process was started as a sandboxed app by
+    /// a `SandboxHost`. `None` otherwise, when the host should run as usual.
+    pub fn connect() -> Option<Result<Self, SandboxError>> {
+        std::env::var_os(SOCKET_VAR)?;
+
+        let size = std::env::var(SIZE_VAR).ok().and_then(|size| {
+            let (width, height) = size.split_once('x')?;
+            Some((width.parse().ok()?, height.parse().ok()?))
+        });
+
+        let Some((width, height)) = size else {
+            return Some(Err(SandboxError {
+                message: format!("{} isn't set to a size", SIZE_VAR),
+            }));
+        };
+
+        Some(Self::open(width, height))
+    }
+
+    /// Take over stdin, which the host set to this process's end of a socket pair.
+    fn open(width: u32, height: u32) -> Result<Self, SandboxError> {
+        let fd = io::stdin().as_fd().try_clone_to_owned()?;
+        let writer = UnixStream::from(fd);
+
+        writer.peer_addr().map_err(|err| SandboxError {
+            message: format!("stdin isn't a socket from the display process: {}", err),
+        })?;
+
+        let mut reader = writer.try_clone()?;
+        let (event_tx, events) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            while let Ok((kind, payload)) = read_message(&mut reader) {
+                if let Some(event) = SandboxEvent::decode(kind, payload)
+                    && event_tx.send(event).is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        Ok(SandboxClient {
+            width,
+            height,
+            writer,
+            events,
+        })
+    }
+
+    /// The next event from the display process, if one is waiting.
+    pub fn try_recv(&self) -> Option<SandboxEvent> {
+        self.events.try_recv().ok()
+    }
+
+    /// Send what `renderer` drew to the display process. Call it every frame, with
+    /// whether `Renderer::render` drew anything: frames with nothing drawn are how the
+    /// display process knows the app hasn't hung. An error means the display process has
+    /// gone, and the app should exit.
+    pub fn present(&mut self, renderer: &Renderer, rendered: bool) -> Result<(), SandboxError> {
+        let damage = if rendered { renderer.damage() } else { &[] };
+        let canvas = &renderer.canvas;
+
+        let mut payload = vec![
+            match renderer.refresh_mode() {
+                RefreshMode::Fast => 0,
+                RefreshMode::Partial => 1,
+                RefreshMode::Full => 2,
+            },
+            match renderer.display_filter() {
+                DisplayFilter::None => 0,
+                DisplayFilter::Invert => 1,
+                DisplayFilter::HighContrast => 2,
+                DisplayFilter::Deuteranopia => 3,
+            },
+        ];
+        payload.extend_from_slice(&(damage.len() as u32).to_le_bytes());
+
+        for rect in damage {
+            for value in [rect.x, rect.y, rect.width, rect.height] {
+                payload.extend_from_slice(&value.to_le_bytes());
+            }
+
+            for row in rect.y..rect.y + rect.height {
+                let start = (row * canvas.width + rect.x) as usize;
+
+                for pixel in &canvas.pixels[start..start + rect.width as usize] {
+                    payload.extend_from_slice(&pixel.to_le_bytes());
+                }
+            }
+        }
+
+        write_message(&mut self.writer, FRAME, &payload).map_err(|err| SandboxError {
+            message: format!("The display process has gone: {}", err),
+        })
+    }
+}
+
+/// Messages are a kind byte, then the payload's length as a little-endian `u32`, then
+/// the payload.
+fn write_message(writer: &mut impl Write, kind: u8, payload: &[u8]) -> io::Result<()> {
+    let mut header = [kind, 0, 0, 0, 0];
+    header[1..].copy_from_slice(&(payload.len() as u32).to_le_bytes());
+    writer.write_all(&header)?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+fn read_message(reader: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+    let mut header = [0; 5];
+    reader.read_exact(&mut header)?;
+
+    let length = u32::from_le_bytes(header[1..].try_into().unwrap()) as usize;
+
+    if length > MAX_MESSAGE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} byte message", length),
+        ));
+    }
+
+    let mut payload = vec![0; length];
+    reader.read_exact(&mut payload)?;
+
+    Ok((header[0], payload))
+}
+
+/// Reads values off the front of a payload, `None` once it runs out.
+struct Bytes<'a>(&'a [u8]);
+
+impl<'a> Bytes<'a> {
+    fn take(&mut self, count: usize) -> Option<&'a [u8]> {
+        if count > self.0.len() {
+            return None;
+        }
+
+        let (taken, rest) = self.0.split_at(count);
+        self.0 = rest;
+        Some(taken)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events() -> Vec<SandboxEvent> {
+        vec![
+            SandboxEvent::Pointer {
+                name: "PressIn".to_string(),
+                x: 12.5,
+                y: 40.0,
+                pointer: Pointer::new(PointerTool::Pen, 0.5),
+            },
+            SandboxEvent::Encoder { notches: -2.0 },
+            SandboxEvent::Wheel {
+                x: 1.0,
+                y: 2.0,
+                notches_x: 0.0,
+                notches_y: 3.0,
+            },
+            SandboxEvent::AmbientLight { lux: 300.0 },
+            SandboxEvent::TrimMemory(TrimLevel::Critical),
+        ]
+    }
+
+    #[test]
+    fn events_round_trip() {
+        for event in events() {
+            let (kind, payload) = event.encode();
+            assert_eq!(SandboxEvent::decode(kind, payload), Some(event));
+        }
+
+        let (kind, payload) = SandboxEvent::Reload("bundle".to_string()).encode();
+        assert_eq!(
+            SandboxEvent::decode(kind, payload),
+            Some(SandboxEvent::Reload("bundle".to_string()))
+        );
+    }
+
+    #[test]
+    fn truncated_events_are_dropped() {
+        for event in events() {
+            let (kind, payload) = event.encode();
+
+            for end in 0..payload.len() {
+                assert_eq!(
+                    SandboxEvent::decode(kind, payload[..end].to_vec()),
+                    None,
+                    "{:?} cut to {}",
+                    event,
+                    end
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_events_are_dropped() {
+        // Unknown kind, and a frame sent the wrong way
+        assert_eq!(SandboxEvent::decode(99, vec![0; 16]), None);
+        assert_eq!(SandboxEvent::decode(FRAME, vec![0; 16]), None);
+
+        // A name longer than the payload, and one that isn't UTF-8
+        assert_eq!(SandboxEvent::decode(POINTER, vec![255, b'a']), None);
+        let mut payload = vec![2, 0xff, 0xfe];
+        payload.extend_from_slice(&[0; 13]);
+        assert_eq!(SandboxEvent::decode(POINTER, payload), None);
+
+        assert_eq!(SandboxEvent::decode(RELOAD, vec![0xff]), None);
+    }
+
+    #[test]
+    fn oversized_messages_are_refused() {
+        let mut message = vec![RELOAD];
+        message.extend_from_slice(&(MAX_MESSAGE as u32 + 1).to_le_bytes());
+        let err = read_message(&mut message.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // A header or payload cut short
+        assert!(read_message(&mut [RELOAD, 4, 0].as_slice()).is_err());
+        assert!(read_message(&mut [RELOAD, 4, 0, 0, 0, b'a'].as_slice()).is_err());
+
+        let mut written = Vec::new();
+        write_message(&mut written, RELOAD, b"abc").unwrap();
+        assert_eq!(
+            read_message(&mut written.as_slice()).unwrap(),
+            (RELOAD, b"abc".to_vec())
+        );
+    }
+
+    fn frame(rects: &[(u32, u32, u32, u32)], pixels: usize) -> Vec<u8> {
+        let mut payload = vec![1, 0];
+        payload.extend_from_slice(&(rects.len() as u32).to_le_bytes());
+
+        for &(x, y, width, height) in rects {
+            for value in [x, y, width, height] {
+                payload.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        payload.extend(std::iter::repeat_n(0xaa, pixels * 4));
+        payload
+    }
+
+    #[test]
+    fn bad_frames_leave_the_canvas_alone() {
+        let mut canvas = Canvas::new(4, 4);
+        let blank = canvas.pixels.clone();
+
+        let bad = [
+            // Truncated header
+            vec![1],
+            // More rects than sent
+            frame(&[], 0)
+                .into_iter()
+                .take(2)
+                .chain(u32::MAX.to_le_bytes())
+                .collect(),
+            // Pixels cut short
+            frame(&[(0, 0, 2, 2)], 3),
+            // Past the canvas, and overflowing
+            frame(&[(3, 0, 2, 1)], 2),
+            frame(&[(u32::MAX, 0, 1, 1)], 1),
+            frame(&[(0, u32::MAX, 1, 1)], 1),
+        ];
+
+        for payload in bad {
+            assert!(apply_frame(&payload, &mut canvas).is_none());
+            assert_eq!(canvas.pixels, blank);
+        }
+
+        // A good rect before a bad one isn't drawn either
+        let mut payload = frame(&[(0, 0, 1, 1)], 1);
+        payload[2..6].copy_from_slice(&2u32.to_le_bytes());
+        payload.extend_from_slice(&[9, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+        assert!(apply_frame(&payload, &mut canvas).is_none());
+        assert_eq!(canvas.pixels, blank);
+
+        let (_, _, damage) = apply_frame(&frame(&[(1, 1, 2, 2)], 4), &mut canvas).unwrap();
+        assert_eq!(damage.len(), 1);
+        assert_eq!(canvas.pixels[5], 0xaaaa_aaaa);
+        assert_eq!(canvas.pixels[0], blank[0]);
+    }
+}
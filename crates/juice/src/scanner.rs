@@ -0,0 +1,126 @@
+use std::time::{Duration, Instant};
+
+use crate::video::{ObjectFit, VideoFrame};
+
+/// Something found in a camera frame, like a decoded barcode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    /// The decoded contents
+    pub text: String,
+    /// What was decoded, e.g. `"qr"` or `"ean13"`. Passed through to JS as is.
+    pub format: String,
+    /// Where it was found `(x, y, w, h)` in frame pixels, for the viewfinder to
+    /// highlight, if the analyzer knows
+    pub bounds: Option<(f32, f32, f32, f32)>,
+}
+
+/// A host-supplied decoder run on the frames of `<scanner>` nodes, registered with
+/// `Renderer::register_analyzer`.
+pub trait FrameAnalyzer {
+    /// Look for things in the `region` `(x, y, w, h)` of a frame, in frame pixels.
+    fn analyze(&mut self, frame: &VideoFrame, region: (u32, u32, u32, u32)) -> Vec<Detection>;
+}
+
+impl<F> FrameAnalyzer for F
+where
+    F: FnMut(&VideoFrame, (u32, u32, u32, u32)) -> Vec<Detection>,
+{
+    fn analyze(&mut self, frame: &VideoFrame, region: (u32, u32, u32, u32)) -> Vec<Detection> {
+        self(frame, region)
+    }
+}
+
+/// State of a `scanner` node: the video source it shows and analyzes, and its viewfinder.
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    pub src: String,
+    /// Name of the registered analyzer to run
+    pub analyzer: String,
+    pub object_fit: ObjectFit,
+    /// Draw the viewfinder and only analyze the part of the frame inside it
+    pub viewfinder: bool,
+    /// Viewfinder width as a fraction of the node's width
+    pub viewfinder_size: f32,
+    /// Viewfinder width over height: 1 for QR codes, wider for 1D barcodes
+    pub viewfinder_aspect: f32,
+    /// How long the same text must go unseen before it's reported again
+    pub repeat_delay: Duration,
+    /// Bounds of the latest detections in frame pixels, highlighted until a frame
+    /// without any
+    pub highlights: Vec<(f32, f32, f32, f32)>,
+    last_report: Option<(String, Instant)>,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Scanner {
+            src: String::new(),
+            analyzer: "barcode".to_string(),
+            object_fit: ObjectFit::Cover,
+            viewfinder: true,
+            viewfinder_size: 0.7,
+            viewfinder_aspect: 1.0,
+            repeat_delay: Duration::from_millis(1500),
+            highlights: Vec::new(),
+            last_report: None,
+        }
+    }
+}
+
+impl Scanner {
+    /// The viewfinder `(x, y, w, h)` centered in a node `w` by `h`, kept inside it.
+    pub fn viewfinder_rect(&self, (x, y, w, h): (f32, f32, f32, f32)) -> (f32, f32, f32, f32) {
+        let aspect = self.viewfinder_aspect.max(0.01);
+        let mut vw = w * self.viewfinder_size.clamp(0.0, 1.0);
+        let mut vh = vw / aspect;
+
+        if vh > h {
+            vh = h;
+            vw = vh * aspect;
+        }
+
+        (x + (w - vw) / 2.0, y + (h - vh) / 2.0, vw, vh)
+    }
+
+    /// The part of a frame to analyze for a node `w` by `h`: what shows through the
+    /// viewfinder, or all of it that's visible without one.
+    pub fn frame_region(&self, frame: &VideoFrame, (w, h): (f32, f32)) -> (u32, u32, u32, u32) {
+        let (dx, dy, dw, dh) = self
+            .object_fit
+            .fit(frame.width, frame.height, 0.0, 0.0, w, h);
+        let (vx, vy, vw, vh) = if self.viewfinder {
+            self.viewfinder_rect((0.0, 0.0, w, h))
+        } else {
+            (0.0, 0.0, w, h)
+        };
+
+        let scale_x = frame.width as f32 / dw.max(1.0);
+        let scale_y = frame.height as f32 / dh.max(1.0);
+        let to_frame = |value: f32, offset: f32, scale: f32, size: u32| {
+            (((value - offset) * scale).max(0.0) as u32).min(size)
+        };
+
+        let x0 = to_frame(vx, dx, scale_x, frame.width);
+        let y0 = to_frame(vy, dy, scale_y, frame.height);
+        let x1 = to_frame(vx + vw, dx, scale_x, frame.width);
+        let y1 = to_frame(vy + vh, dy, scale_y, frame.height);
+
+        (x0, y0, x1 - x0, y1 - y0)
+    }
+
+    /// Whether to report a detection, skipping text reported within the repeat delay
+    /// so holding a code in view doesn't fire over and over.
+    pub fn should_report(&mut self, text: &str, now: Instant) -> bool {
+        if let Some((last, at)) = &mut self.last_report
+            && last == text
+            && now.duration_since(*at) < self.repeat_delay
+        {
+            // Still in view, so keep waiting from now
+            *at = now;
+            return false;
+        }
+
+        self.last_report = Some((text.to_string(), now));
+        true
+    }
+}
@@ -0,0 +1,210 @@
+use crate::capabilities::Capabilities;
+
+/// Where `juice-schema` writes the declarations, relative to the repository root
+pub const TYPESCRIPT_PATH: &str = "packages/juice/src/schema.ts";
+
+/// What a style property accepts, as `Dom::try_from_json_bytes` and the `dom` global's
+/// `setStyle*` and `setAttribute*` calls take it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueType {
+    Number,
+    /// Any string, like a color, font name or transform
+    Text,
+    /// A number, or a string in the property's own syntax, like grid tracks
+    NumberOrText,
+    /// Pixels, as a number or `"12px"`. Set as an attribute, so other units are dropped.
+    Pixels,
+    /// Pixels, or `"1.5em"` of the inherited font size
+    Length,
+    /// A `Length`, or `"50%"` of the parent
+    LengthPercent,
+    /// A `LengthPercent`, or `"auto"`
+    LengthPercentAuto,
+    /// One of a set of words
+    Keyword(&'static [&'static str]),
+    /// CSS's `font-variation-settings`, like `"'wght' 550"`, or axis values by tag
+    Variations,
+    /// A style property `value_type` doesn't know about yet
+    Unknown,
+}
+
+const ALIGN_CONTENT: &[&str] = &[
+    "stretch",
+    "start",
+    "end",
+    "flex-start",
+    "center",
+    "flex-end",
+    "space-between",
+    "space-around",
+    "space-evenly",
+];
+const ALIGN_ITEMS: &[&str] = &[
+    "stretch",
+    "start",
+    "end",
+    "flex-start",
+    "center",
+    "flex-end",
+    "baseline",
+];
+const OVERFLOW: &[&str] = &["visible", "hidden", "clip", "scroll"];
+
+/// The type of each of `Capabilities::style_properties`.
+pub fn value_type(property: &str) -> ValueType {
+    use ValueType::*;
+
+    match property {
+        "alignContent" | "justifyContent" => Keyword(ALIGN_CONTENT),
+        "alignItems" | "alignSelf" | "justifyItems" | "justifySelf" => Keyword(ALIGN_ITEMS),
+        "background" | "borderColor" | "boxShadow" | "color" | "font" | "transform" => Text,
+        "borderBottomWidth" | "borderLeftWidth" | "borderRightWidth" | "borderTopWidth"
+        | "borderWidth" => Length,
+        "borderRadius" | "fontSize" | "letterSpacing" => Pixels,
+        "boxSizing" => Keyword(&["border-box", "content-box"]),
+        "direction" => Keyword(&["ltr", "rtl"]),
+        "display" => Keyword(&["block", "flex", "grid", "none"]),
+        "flex" | "fontWeight" | "lineHeight" => NumberOrText,
+        "flexBasis" | "height" | "width" | "margin" | "marginBottom" | "marginLeft"
+        | "marginRight" | "marginTop" | "marginX" | "marginY" => LengthPercentAuto,
+        "flexDirection" => Keyword(&["row", "column", "row-reverse", "column-reverse"]),
+        "flexGrow" | "flexShrink" | "maxLines" | "opacity" | "tabSize" | "zIndex" => Number,
+        "flexWrap" => Keyword(&["nowrap", "wrap", "wrap-reverse"]),
+        "fontStyle" => Keyword(&["normal", "italic", "oblique"]),
+        "fontVariationSettings" => Variations,
+        "gap" | "gapHeight" | "gapWidth" | "columnGap" | "rowGap" | "maxHeight" | "maxWidth"
+        | "padding" | "paddingBottom" | "paddingLeft" | "paddingRight" | "paddingTop"
+        | "paddingX" | "paddingY" => LengthPercent,
+        "gridAutoColumns"
+        | "gridAutoRows"
+        | "gridColumn"
+        | "gridRow"
+        | "gridTemplateColumns"
+        | "gridTemplateRows" => NumberOrText,
+        "gridAutoFlow" => Keyword(&["row", "column", "dense", "row dense", "column dense"]),
+        "objectFit" => Keyword(&["contain", "cover", "fill"]),
+        "overflow" | "overflowX" | "overflowY" => Keyword(OVERFLOW),
+        "position" => Keyword(&["relative", "absolute"]),
+        "textAlign" => Keyword(&["left", "center", "right"]),
+        "textDecoration" => Keyword(&[
+            "none",
+            "underline",
+            "line-through",
+            "underline line-through",
+        ]),
+        "textOverflow" => Keyword(&["clip", "ellipsis"]),
+        _ => Unknown,
+    }
+}
+
+impl ValueType {
+    fn typescript(self) -> String {
+        match self {
+            ValueType::Number => "number".to_string(),
+            ValueType::Text => "string".to_string(),
+            ValueType::NumberOrText => "number | string".to_string(),
+            ValueType::Pixels => "JuicePixels".to_string(),
+            ValueType::Length => "JuiceLength".to_string(),
+            ValueType::LengthPercent => "JuiceLength | `${number}%`".to_string(),
+            ValueType::LengthPercentAuto => "JuiceLength | `${number}%` | \"auto\"".to_string(),
+            ValueType::Keyword(words) => union(words),
+            ValueType::Variations => "string | Record<string, number>".to_string(),
+            ValueType::Unknown => "unknown".to_string(),
+        }
+    }
+}
+
+/// `name type`, or a union too long for one line with a member on each, as Prettier
+/// formats them.
+fn wrap(name: &str, ts: &str, indent: &str) -> String {
+    if name.len() + ts.len() + 2 <= 80 {
+        return format!("{} {}", name, ts);
+    }
+
+    let members: String = ts
+        .split(" | ")
+        .map(|member| format!("\n{}  | {}", indent, member))
+        .collect();
+
+    format!("{}{}", name, members)
+}
+
+fn union(words: &[&str]) -> String {
+    words
+        .iter()
+        .map(|word| format!("{:?}", word))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// TypeScript declarations for the tree JSON `Dom::try_from_json_bytes` accepts and the
+/// styles it understands, from the same lists as `renderer.capabilities`. Errors with the
+/// style properties `value_type` has no type for.
+pub fn typescript() -> Result<String, String> {
+    let capabilities = Capabilities::current();
+    let unknown: Vec<&str> = capabilities
+        .style_properties
+        .iter()
+        .copied()
+        .filter(|property| value_type(property) == ValueType::Unknown)
+        .collect();
+
+    if !unknown.is_empty() {
+        return Err(format!(
+            "No type for style properties {}; add them to schema::value_type",
+            unknown.join(", ")
+        ));
+    }
+
+    let styles: String = capabilities
+        .style_properties
+        .iter()
+        .map(|property| {
+            let ts = value_type(property).typescript();
+            format!("{};\n", wrap(&format!("  {}?:", property), &ts, "  "))
+        })
+        .collect();
+
+    Ok(format!(
+        r##"// Generated from the juice crate by `cargo run -p juice --features lottie --bin
+// juice-schema -- --write`. Don't edit by hand.
+
+export const SCHEMA_VERSION = {version};
+
+/** Element tags with native behaviour; others are drawn like `box`. */
+{node_types};
+
+/** Pixels, as a number or `"12px"`. */
+export type JuicePixels = number | `${{number}}px`;
+
+/** Pixels, or ems of the inherited font size. */
+export type JuiceLength = JuicePixels | `${{number}}em`;
+
+/** Every style property the renderer understands. */
+export interface JuiceStyleSchema {{
+{styles}}}
+
+/** An element in tree JSON, as `JSON.stringify(document)` gives. */
+export interface JuiceElementJson {{
+  tag: JuiceNodeType | (string & {{}});
+  props?: Record<string, string | number | boolean | object>;
+  style?: JuiceStyleSchema;
+  children?: JuiceNodeJson[];
+}}
+
+/** A text node in tree JSON. */
+export interface JuiceTextJson {{
+  tag: "#text";
+  text: string;
+}}
+
+export type JuiceNodeJson = JuiceElementJson | JuiceTextJson;
+"##,
+        version = capabilities.schema_version,
+        node_types = wrap(
+            "export type JuiceNodeType =",
+            &union(capabilities.node_types),
+            ""
+        ),
+    ))
+}
@@ -0,0 +1,302 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Distance a press must move before it becomes a scroll drag.
+pub const TOUCH_SLOP: f32 = 8.0;
+
+/// Fling speed in px/s below which a release doesn't start momentum scrolling.
+const MIN_FLING_VELOCITY: f32 = 50.0;
+
+/// Speed in px/s at which momentum scrolling stops.
+const STOP_VELOCITY: f32 = 10.0;
+
+/// How far back velocity samples are considered when a drag is released.
+const VELOCITY_WINDOW: Duration = Duration::from_millis(100);
+
+/// Stiffness of the spring pulling an overscrolled node back into range, in 1/s².
+/// Damping is set for a critically damped spring so it settles without oscillating.
+const SPRING_STIFFNESS: f32 = 200.0;
+
+/// How far a refreshable node must be pulled past its top to trigger a refresh.
+pub const REFRESH_TRIGGER: f32 = 64.0;
+
+/// Space held open above a refreshable node's content while it refreshes.
+pub const REFRESH_INDICATOR_HEIGHT: f32 = 48.0;
+
+/// How scroll input is converted to scroll distance and motion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollConfig {
+    /// Lines scrolled per wheel notch or encoder detent.
+    pub lines_per_notch: f32,
+    /// Height of a line in pixels.
+    pub line_height: f32,
+    /// Exponential decay rate of fling velocity, per second. Higher stops sooner.
+    pub deceleration: f32,
+    /// Allow dragging and flinging past the ends, springing back on release.
+    pub bounce: bool,
+}
+
+impl ScrollConfig {
+    /// Scroll distance in pixels for a number of notches.
+    pub fn notches_to_pixels(&self, notches: f32) -> f32 {
+        notches * self.lines_per_notch * self.line_height
+    }
+}
+
+impl Default for ScrollConfig {
+    fn default() -> Self {
+        Self {
+            lines_per_notch: 3.0,
+            line_height: 16.0,
+            deceleration: 2.0,
+            bounce: true,
+        }
+    }
+}
+
+/// Estimates pointer velocity from recent positions.
+#[derive(Default)]
+pub struct VelocityTracker {
+    samples: VecDeque<(Instant, f32, f32)>,
+}
+
+impl VelocityTracker {
+    pub fn add(&mut self, time: Instant, x: f32, y: f32) {
+        self.samples.push_back((time, x, y));
+
+        while let Some(&(oldest, _, _)) = self.samples.front() {
+            if time.duration_since(oldest) <= VELOCITY_WINDOW {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    /// Average velocity in px/s over the sample window.
+    pub fn velocity(&self) -> (f32, f32) {
+        let (Some(&(t0, x0, y0)), Some(&(t1, x1, y1))) =
+            (self.samples.front(), self.samples.back())
+        else {
+            return (0.0, 0.0);
+        };
+
+        let dt = t1.duration_since(t0).as_secs_f32();
+
+        if dt <= 0.0 {
+            return (0.0, 0.0);
+        }
+
+        ((x1 - x0) / dt, (y1 - y0) / dt)
+    }
+}
+
+/// Amount an offset lies outside `bounds` (min, max): negative before the start, positive
+/// past the end.
+pub fn overscroll(offset: f32, bounds: (f32, f32)) -> f32 {
+    offset - offset.clamp(bounds.0, bounds.1)
+}
+
+/// Apply a drag delta to a scroll offset, with movement past the ends halved for a
+/// rubber-band feel when bouncing is enabled.
+pub fn drag_offset(offset: f32, delta: f32, bounds: (f32, f32), bounce: bool) -> f32 {
+    let next = offset + delta;
+
+    if !bounce {
+        return next.clamp(bounds.0, bounds.1);
+    }
+
+    if overscroll(next, bounds) != 0.0 {
+        offset + delta * 0.5
+    } else {
+        next
+    }
+}
+
+/// Momentum scrolling on one node after a fling: velocity decays exponentially while in
+/// range, and a spring pulls the offset back once it overshoots an end.
+pub struct Momentum {
+    pub velocity_x: f32,
+    pub velocity_y: f32,
+}
+
+impl Momentum {
+    /// Start momentum from a release velocity, or `None` if it's too slow to fling and
+    /// there's no overscroll to settle. `bounds` holds the (min, max) offset of each axis.
+    pub fn from_release(
+        velocity: (f32, f32),
+        offset: (f32, f32),
+        bounds: ((f32, f32), (f32, f32)),
+    ) -> Option<Self> {
+        let speed = velocity.0.hypot(velocity.1);
+        let overscrolled =
+            overscroll(offset.0, bounds.0) != 0.0 || overscroll(offset.1, bounds.1) != 0.0;
+
+        if speed < MIN_FLING_VELOCITY && !overscrolled {
+            return None;
+        }
+
+        Some(Momentum {
+            velocity_x: velocity.0,
+            velocity_y: velocity.1,
+        })
+    }
+
+    /// Advance by `dt` seconds, returning the new offset and whether motion has settled.
+    pub fn step(
+        &mut self,
+        dt: f32,
+        offset: (f32, f32),
+        bounds: ((f32, f32), (f32, f32)),
+        config: &ScrollConfig,
+    ) -> ((f32, f32), bool) {
+        let (x, done_x) = step_axis(&mut self.velocity_x, offset.0, bounds.0, dt, config);
+        let (y, done_y) = step_axis(&mut self.velocity_y, offset.1, bounds.1, dt, config);
+        ((x, y), done_x && done_y)
+    }
+}
+
+fn step_axis(
+    velocity: &mut f32,
+    offset: f32,
+    bounds: (f32, f32),
+    dt: f32,
+    config: &ScrollConfig,
+) -> (f32, bool) {
+    let (min, max) = bounds;
+    let over = overscroll(offset, bounds);
+
+    if over == 0.0 {
+        *velocity *= (-config.deceleration * dt).exp();
+    } else {
+        // Spring towards the nearest end
+        *velocity = spring_velocity(*velocity, over, dt);
+    }
+
+    let mut next = offset + *velocity * dt;
+
+    if !config.bounce {
+        if next != next.clamp(min, max) {
+            *velocity = 0.0;
+        }
+        next = next.clamp(min, max);
+    }
+
+    // Crossing back into range from an overshoot finishes the bounce
+    if (over < 0.0 && next >= min) || (over > 0.0 && next <= max) {
+        *velocity = 0.0;
+        return (next.clamp(min, max), true);
+    }
+
+    if velocity.abs() < STOP_VELOCITY {
+        let settled = overscroll(next, bounds);
+
+        if settled.abs() < 0.5 {
+            *velocity = 0.0;
+            return (next.clamp(min, max), true);
+        }
+    }
+
+    (next, false)
+}
+
+/// Advance the velocity of a critically damped spring `displacement` away from rest.
+pub fn spring_velocity(velocity: f32, displacement: f32, dt: f32) -> f32 {
+    let damping = 2.0 * SPRING_STIFFNESS.sqrt();
+    velocity + (-SPRING_STIFFNESS * displacement - damping * velocity) * dt
+}
+
+/// Where a swipeable row should settle when released: a quick flick opens or closes it
+/// in the direction of travel, otherwise it snaps open once dragged past halfway.
+pub fn swipe_snap_target(offset: f32, velocity: f32, bounds: (f32, f32)) -> f32 {
+    let (min, max) = bounds;
+
+    if velocity <= -MIN_FLING_VELOCITY {
+        if offset > 0.0 { 0.0 } else { min }
+    } else if velocity >= MIN_FLING_VELOCITY {
+        if offset < 0.0 { 0.0 } else { max }
+    } else if offset < min / 2.0 {
+        min
+    } else if offset > max / 2.0 {
+        max
+    } else {
+        0.0
+    }
+}
+
+/// A swipeable row springing open or closed after release.
+pub struct SwipeSnap {
+    pub node_id: taffy::NodeId,
+    pub target: f32,
+    pub velocity: f32,
+    pub last_step: Instant,
+}
+
+impl SwipeSnap {
+    /// Advance by `dt` seconds, returning the new offset and whether it has settled.
+    pub fn step(&mut self, offset: f32, dt: f32) -> (f32, bool) {
+        self.velocity = spring_velocity(self.velocity, offset - self.target, dt);
+        let next = offset + self.velocity * dt;
+
+        if (next - self.target).abs() < 0.5 && self.velocity.abs() < STOP_VELOCITY {
+            self.velocity = 0.0;
+            return (self.target, true);
+        }
+
+        (next, false)
+    }
+}
+
+/// A node coasting after a fling.
+pub struct Fling {
+    pub node_id: taffy::NodeId,
+    pub momentum: Momentum,
+    pub last_step: Instant,
+}
+
+/// An in-progress press that may turn into a scroll drag.
+pub struct ScrollDrag {
+    /// Where the press started
+    pub start: (f32, f32),
+    /// Last position applied to the scroll offset
+    pub last: (f32, f32),
+    pub tracker: VelocityTracker,
+    pub state: DragState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DragState {
+    /// Hasn't moved past the touch slop yet
+    Pending,
+    /// Scrolling a node
+    Scrolling(taffy::NodeId),
+    /// Swiping a row sideways
+    Swiping(taffy::NodeId),
+    /// Spinning one wheel of a picker
+    Picking(taffy::NodeId, usize),
+    /// Moved, but there was nothing to scroll; events pass through to JS
+    Passthrough,
+}
+
+impl ScrollDrag {
+    pub fn new(time: Instant, x: f32, y: f32) -> Self {
+        let mut tracker = VelocityTracker::default();
+        tracker.add(time, x, y);
+
+        Self {
+            start: (x, y),
+            last: (x, y),
+            tracker,
+            state: DragState::Pending,
+        }
+    }
+}
+
+/// A refreshable node waiting for JS to finish refreshing it.
+pub struct Refresh {
+    pub node_id: taffy::NodeId,
+    pub started: Instant,
+    /// Set by the `complete` callback passed to JS with the `Refresh` event
+    pub complete: Rc<Cell<bool>>,
+}
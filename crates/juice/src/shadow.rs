@@ -0,0 +1,176 @@
+use std::f32::consts::SQRT_2;
+
+use crate::canvas::{self, Canvas, RgbColor};
+
+/// Color of a shadow that doesn't give one: black at 30%
+const DEFAULT_COLOR: RgbColor = RgbColor {
+    r: 0,
+    g: 0,
+    b: 0,
+    a: 77,
+};
+
+/// A `boxShadow`, drawn behind an element's box and outside it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxShadow {
+    pub offset_x: f32,
+    pub offset_y: f32,
+    /// How far the edge fades out over, as in CSS: twice the Gaussian's standard deviation
+    pub blur: f32,
+    /// How much bigger than the box the shadow is, before blurring
+    pub spread: f32,
+    pub color: RgbColor,
+}
+
+impl BoxShadow {
+    /// Parse a CSS box shadow like `0 4px 12px rgba(0, 0, 0, 0.3)`: the x and y offsets,
+    /// then optionally a blur radius and a spread, with the color before or after the
+    /// lengths. `none` gives `None`, as does anything else that doesn't parse.
+    pub fn parse(value: &str, resolve_color: impl Fn(&str) -> Option<RgbColor>) -> Option<Self> {
+        let value = value.trim();
+
+        if value == "none" {
+            return None;
+        }
+
+        let mut tokens = split_tokens(value);
+
+        // The color comes before or after all the lengths
+        let color = if tokens.last().is_some_and(|token| length(token).is_none()) {
+            tokens.pop()
+        } else if tokens.first().is_some_and(|token| length(token).is_none()) {
+            Some(tokens.remove(0))
+        } else {
+            None
+        };
+
+        let color = match color {
+            Some(color) => resolve_color(color)?,
+            None => DEFAULT_COLOR,
+        };
+        let lengths: Vec<f32> = tokens.into_iter().map(length).collect::<Option<_>>()?;
+
+        let (offset_x, offset_y, blur, spread) = match lengths[..] {
+            [x, y] => (x, y, 0.0, 0.0),
+            [x, y, blur] => (x, y, blur, 0.0),
+            [x, y, blur, spread] => (x, y, blur, spread),
+            _ => return None,
+        };
+
+        Some(BoxShadow {
+            offset_x,
+            offset_y,
+            blur: blur.max(0.0),
+            spread,
+            color,
+        })
+    }
+
+    /// Draw the shadow for a box at `rect` with corner `radius`. Only the part outside
+    /// the box is drawn, so a translucent background doesn't show it through.
+    pub fn render(&self, canvas: &mut Canvas, (x, y, w, h): (f32, f32, f32, f32), radius: f32) {
+        let shadow = (
+            x + self.offset_x - self.spread,
+            y + self.offset_y - self.spread,
+            (w + self.spread * 2.0).max(0.0),
+            (h + self.spread * 2.0).max(0.0),
+        );
+        let shadow_radius = if radius > 0.0 {
+            (radius + self.spread).max(0.0)
+        } else {
+            0.0
+        };
+
+        let sigma = self.blur / 2.0;
+        // A Gaussian is all but gone three standard deviations out
+        let reach = sigma * 3.0 + 1.0;
+
+        let x0 = (shadow.0 - reach).floor().max(0.0) as i32;
+        let y0 = (shadow.1 - reach).floor().max(0.0) as i32;
+        let x1 = (shadow.0 + shadow.2 + reach)
+            .ceil()
+            .min(canvas.width as f32) as i32;
+        let y1 = (shadow.1 + shadow.3 + reach)
+            .ceil()
+            .min(canvas.height as f32) as i32;
+
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let (cx, cy) = (px as f32 + 0.5, py as f32 + 0.5);
+
+                if canvas::rounded_rect_contains((x, y, w, h), radius, cx, cy) {
+                    continue;
+                }
+
+                let distance = rounded_rect_distance(shadow, shadow_radius, cx, cy);
+
+                // Blurring an edge with a Gaussian gives its CDF across it, which is
+                // exact for straight edges and close enough around corners
+                let coverage = if sigma > 0.0 {
+                    0.5 * erfc(distance / (sigma * SQRT_2))
+                } else {
+                    (0.5 - distance).clamp(0.0, 1.0)
+                };
+
+                if coverage > 0.0 {
+                    canvas.blend_pixel(px, py, self.color, (coverage * 255.0) as u8);
+                }
+            }
+        }
+    }
+}
+
+/// Split on whitespace, keeping the arguments of `rgb(...)` and the like together.
+fn split_tokens(value: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut depth = 0;
+    let mut start = None;
+
+    for (i, c) in value.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if c.is_whitespace() && depth == 0 => {
+                if let Some(start) = start.take() {
+                    tokens.push(&value[start..i]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        start.get_or_insert(i);
+    }
+
+    if let Some(start) = start {
+        tokens.push(&value[start..]);
+    }
+
+    tokens
+}
+
+/// Pixels, with or without `px`.
+fn length(token: &str) -> Option<f32> {
+    let length: f32 = token.strip_suffix("px").unwrap_or(token).parse().ok()?;
+    length.is_finite().then_some(length)
+}
+
+/// Signed distance from a point to a rect with rounded corners, negative inside.
+fn rounded_rect_distance((x, y, w, h): (f32, f32, f32, f32), radius: f32, px: f32, py: f32) -> f32 {
+    let radius = radius.min(w / 2.0).min(h / 2.0).max(0.0);
+    let qx = (px - (x + w / 2.0)).abs() - w / 2.0 + radius;
+    let qy = (py - (y + h / 2.0)).abs() - h / 2.0 + radius;
+
+    qx.max(0.0).hypot(qy.max(0.0)) + qx.max(qy).min(0.0) - radius
+}
+
+/// Complementary error function, from Abramowitz and Stegun's 7.1.26 approximation.
+fn erfc(x: f32) -> f32 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let poly = t
+        * (0.254_829_6
+            + t * (-0.284_496_7 + t * (1.421_413_7 + t * (-1.453_152 + t * 1.061_405_4))));
+    let erfc = poly * (-x * x).exp();
+
+    if x >= 0.0 { erfc } else { 2.0 - erfc }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use fontdue::Font;
+use rustybuzz::{Face, UnicodeBuffer};
+
+use crate::bidi;
+use crate::font_fallback;
+use crate::glyph_cache;
+use crate::inherited_style::Direction;
+
+/// Font files by `Font::file_hash`. fontdue doesn't keep the bytes it parsed, and
+/// rustybuzz reads the tables from them. Fonts can be decoded on the decoder's workers,
+/// so this is shared between threads.
+static FACES: LazyLock<Mutex<HashMap<usize, Arc<Vec<u8>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// A glyph placed by the shaper, in pixels at the size it was shaped at.
+#[derive(Debug, Clone, Copy)]
+pub struct ShapedGlyph {
+    pub font_hash: usize,
+    pub glyph_index: u16,
+    /// How far the pen moves after it
+    pub advance: f32,
+    /// Where it's drawn relative to the pen, with y up
+    pub x_offset: f32,
+    pub y_offset: f32,
+}
+
+/// Keep a font's file so text in it can be shaped. Fonts loaded with `addFont` or
+/// `Renderer::add_font` are registered already; hosts passing fonts to `Renderer::new`
+/// register each with the bytes it was parsed from.
+pub fn register(font: &Font, data: Vec<u8>) {
+    FACES
+        .lock()
+        .unwrap()
+        .insert(font.file_hash(), Arc::new(data));
+}
+
+/// Whether text in `font` is shaped, rather than laid out a character at a time.
+pub fn can_shape(font: &Font) -> bool {
+    FACES.lock().unwrap().contains_key(&font.file_hash())
+}
+
+/// Shape one line of `text` in the chain `fonts`, in a paragraph going `direction`. The
+/// line is split into runs by direction, then by the first font with each character as
+/// `canvas::append_text` does, and the glyphs come out in display order, left to right.
+/// Runs in a font that isn't registered are placed a character at a time. `None` if the
+/// first font isn't registered.
+pub fn shape(
+    fonts: &[&Font],
+    text: &str,
+    font_size: f32,
+    direction: Direction,
+) -> Option<Vec<ShapedGlyph>> {
+    if !can_shape(fonts[0]) {
+        return None;
+    }
+
+    let mut glyphs = Vec::new();
+
+    for (range, rtl) in bidi::visual_runs(text, direction) {
+        let text = &text[range];
+        let mut font_runs = Vec::new();
+        let mut run: Option<(usize, &Font)> = None;
+
+        for (i, c) in text.char_indices() {
+            let font = font_fallback::pick(fonts, c);
+
+            match run {
+                Some((_, current)) if std::ptr::eq(current, font) => {}
+                Some((start, current)) => {
+                    font_runs.push((current, &text[start..i]));
+                    run = Some((i, font));
+                }
+                None => run = Some((i, font)),
+            }
+        }
+
+        if let Some((start, current)) = run {
+            font_runs.push((current, &text[start..]));
+        }
+
+        // The first of a right-to-left run's fonts is read first, so goes on the right
+        if rtl {
+            font_runs.reverse();
+        }
+
+        for (font, text) in font_runs {
+            shape_run(font, text, font_size, rtl, &mut glyphs);
+        }
+    }
+
+    Some(glyphs)
+}
+
+fn shape_run(font: &Font, text: &str, font_size: f32, rtl: bool, glyphs: &mut Vec<ShapedGlyph>) {
+    let data = FACES.lock().unwrap().get(&font.file_hash()).cloned();
+    let Some(face) = data.as_deref().and_then(|data| Face::from_slice(data, 0)) else {
+        let start = glyphs.len();
+
+        glyphs.extend(
+            text.chars()
+                .filter(|c| !c.is_control())
+                .map(|c| ShapedGlyph {
+                    font_hash: font.file_hash(),
+                    glyph_index: font.lookup_glyph_index(c),
+                    advance: glyph_cache::advance(font, c, font_size),
+                    x_offset: 0.0,
+                    y_offset: 0.0,
+                }),
+        );
+
+        if rtl {
+            glyphs[start..].reverse();
+        }
+
+        return;
+    };
+
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(text);
+    buffer.guess_segment_properties();
+    buffer.set_direction(match rtl {
+        true => rustybuzz::Direction::RightToLeft,
+        false => rustybuzz::Direction::LeftToRight,
+    });
+
+    let shaped = rustybuzz::shape(&face, &[], buffer);
+    let scale = font_size / face.units_per_em() as f32;
+
+    for (info, position) in shaped.glyph_infos().iter().zip(shaped.glyph_positions()) {
+        glyphs.push(ShapedGlyph {
+            font_hash: font.file_hash(),
+            glyph_index: info.glyph_id as u16,
+            advance: position.x_advance as f32 * scale,
+            x_offset: position.x_offset as f32 * scale,
+            y_offset: position.y_offset as f32 * scale,
+        });
+    }
+}
@@ -0,0 +1,45 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::canvas::{Canvas, RgbColor};
+
+/// Time for the shimmer to sweep across the screen once.
+const SHIMMER_PERIOD_MS: u128 = 1500;
+
+/// Width of the shimmer band as a share of the screen width.
+const BAND_FRACTION: f32 = 0.3;
+
+/// Opacity of the shimmer at the middle of the band.
+const SHIMMER_ALPHA: f32 = 0.6;
+
+/// Color of a `skeleton` node without a `background`.
+pub const DEFAULT_COLOR: RgbColor = RgbColor::rgb(0xe0, 0xe0, 0xe0);
+
+/// Draw the shimmer over a skeleton block: a soft band of lighter color that sweeps left
+/// to right across the whole screen. The band's position comes from the wall clock, so
+/// every skeleton on screen shimmers in step without any JS.
+pub fn render_shimmer(canvas: &mut Canvas, rect: (f32, f32, f32, f32), radius: f32) {
+    let screen_width = canvas.width as f32;
+    let half_band = screen_width * BAND_FRACTION / 2.0;
+
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let progress = (millis % SHIMMER_PERIOD_MS) as f32 / SHIMMER_PERIOD_MS as f32;
+    let center = -half_band + progress * (screen_width + half_band * 2.0);
+
+    let (x, _, w, _) = rect;
+
+    if center + half_band < x || center - half_band > x + w {
+        return;
+    }
+
+    let white = RgbColor::rgb(0xff, 0xff, 0xff);
+
+    canvas.blend_rounded_rect(rect, radius, white, |px, _| {
+        let falloff = (1.0 - (px - center).abs() / half_band).max(0.0);
+        // Smoothstep so the band has no hard edges
+        let eased = falloff * falloff * (3.0 - 2.0 * falloff);
+        (eased * SHIMMER_ALPHA * 255.0) as u8
+    });
+}
@@ -0,0 +1,165 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::canvas::{Canvas, RgbColor};
+
+/// What to show from startup until the app is ready, read from the host config. Every
+/// field is optional in JSON.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SplashConfig {
+    /// Any CSS color, filling the screen behind the image
+    #[serde(default = "default_background")]
+    pub background: String,
+    /// A PNG, JPEG or WebP drawn at its own size in the middle of the screen
+    pub image: Option<PathBuf>,
+    /// How long the splash takes to cross-fade into the app, in milliseconds
+    #[serde(default = "default_fade_ms")]
+    pub fade_ms: u64,
+}
+
+impl Default for SplashConfig {
+    fn default() -> Self {
+        SplashConfig {
+            background: default_background(),
+            image: None,
+            fade_ms: default_fade_ms(),
+        }
+    }
+}
+
+fn default_background() -> String {
+    "#000".to_string()
+}
+
+fn default_fade_ms() -> u64 {
+    300
+}
+
+#[derive(Debug)]
+pub struct SplashError {
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SplashState {
+    /// Covering the app until JS calls `renderer.ready()` and the assets its tree drew
+    /// are decoded
+    Waiting,
+    Fading {
+        start: Instant,
+    },
+}
+
+/// A splash screen covering the app while it starts, so the first frames with icons and
+/// images still decoding are never seen. The app keeps drawing underneath, into its own
+/// frame, and the splash cross-fades into that frame once it's ready.
+pub struct Splash {
+    /// The splash as drawn, XRGB8888 at the canvas size
+    pixels: Vec<u32>,
+    /// The app's frame, kept between renders so only what changed in it is redrawn
+    app_pixels: Vec<u32>,
+    fade: Duration,
+    state: SplashState,
+}
+
+impl Splash {
+    /// Draw the splash for a `width` by `height` canvas.
+    pub fn new(config: &SplashConfig, width: u32, height: u32) -> Result<Self, SplashError> {
+        let background = RgbColor::from_string(&config.background).ok_or_else(|| SplashError {
+            message: format!("Invalid splash background {}", config.background),
+        })?;
+
+        let mut canvas = Canvas::new(width, height);
+        canvas.clear(background);
+
+        if let Some(path) = &config.image {
+            let image = image::open(path)
+                .map_err(|err| SplashError {
+                    message: format!("Can't load the splash image {}: {}", path.display(), err),
+                })?
+                .to_rgba8();
+            let (image_w, image_h) = image.dimensions();
+
+            canvas.blit_rgba(
+                image.as_raw(),
+                image_w,
+                image_h,
+                (width as i32 - image_w as i32) / 2,
+                (height as i32 - image_h as i32) / 2,
+            );
+        }
+
+        Ok(Splash {
+            app_pixels: vec![0xFF00_0000; canvas.pixels.len()],
+            pixels: canvas.pixels,
+            fade: Duration::from_millis(config.fade_ms),
+            state: SplashState::Waiting,
+        })
+    }
+
+    /// Whether it's still waiting for the app, rather than fading out.
+    pub fn is_waiting(&self) -> bool {
+        self.state == SplashState::Waiting
+    }
+
+    /// Start cross-fading into the app.
+    pub fn start_fade(&mut self, now: Instant) {
+        if self.is_waiting() {
+            self.state = SplashState::Fading { start: now };
+        }
+    }
+
+    /// Swap the app's frame onto the canvas to draw the app into, and the splash frame
+    /// last shown off it. Called again after drawing to swap them back.
+    pub fn swap(&mut self, canvas: &mut Canvas) {
+        std::mem::swap(&mut canvas.pixels, &mut self.app_pixels);
+    }
+
+    /// Draw the splash over the app's frame, faded as far as it's got by `now`. Returns
+    /// false once the fade is over, leaving just the app on the canvas.
+    pub fn compose(&self, canvas: &mut Canvas, now: Instant) -> bool {
+        let progress = match self.state {
+            SplashState::Waiting => 0.0,
+            SplashState::Fading { .. } if self.fade.is_zero() => 1.0,
+            SplashState::Fading { start } => {
+                now.saturating_duration_since(start).as_secs_f32() / self.fade.as_secs_f32()
+            }
+        };
+
+        if progress >= 1.0 {
+            canvas.pixels.copy_from_slice(&self.app_pixels);
+            return false;
+        }
+
+        let alpha = (progress * 255.0).round() as u32;
+
+        for ((pixel, &splash), &app) in canvas
+            .pixels
+            .iter_mut()
+            .zip(&self.pixels)
+            .zip(&self.app_pixels)
+        {
+            *pixel = mix(splash, app, alpha);
+        }
+
+        true
+    }
+}
+
+/// Blend two XRGB8888 pixels, from all `a` at 0 to all `b` at 255.
+fn mix(a: u32, b: u32, alpha: u32) -> u32 {
+    if alpha == 0 {
+        return a;
+    }
+
+    let channel = |shift: u32| {
+        let a = (a >> shift) & 0xFF;
+        let b = (b >> shift) & 0xFF;
+        ((a * (255 - alpha) + b * alpha) / 255) << shift
+    };
+
+    0xFF00_0000 | channel(16) | channel(8) | channel(0)
+}
@@ -0,0 +1,87 @@
+use std::time::Instant;
+
+/// Playback settings and clock of an `animatedsprite` node, whose `src` is a sheet of
+/// equally sized frames packed left to right, top to bottom.
+#[derive(Debug, Clone)]
+pub struct SpriteAnimation {
+    /// Size of one frame in the sheet, in image pixels. Zero until set.
+    pub frame_width: u32,
+    pub frame_height: u32,
+    /// Frames to play, for sheets whose last row isn't full. Defaults to every frame
+    /// the sheet holds.
+    pub frame_count: Option<u32>,
+    pub fps: f32,
+    pub looping: bool,
+    started: Instant,
+}
+
+impl SpriteAnimation {
+    /// An animation playing from `started`, with the default settings.
+    pub fn new(started: Instant) -> Self {
+        SpriteAnimation {
+            frame_width: 0,
+            frame_height: 0,
+            frame_count: None,
+            fps: 12.0,
+            looping: true,
+            started,
+        }
+    }
+
+    /// Play from the first frame as of `now`.
+    pub fn restart(&mut self, now: Instant) {
+        self.started = now;
+    }
+
+    /// Frames across and down a sheet of the given size.
+    pub fn grid(&self, sheet_width: u32, sheet_height: u32) -> (u32, u32) {
+        if self.frame_width == 0 || self.frame_height == 0 {
+            return (0, 0);
+        }
+
+        (
+            sheet_width / self.frame_width,
+            sheet_height / self.frame_height,
+        )
+    }
+
+    fn frames(&self, sheet_width: u32, sheet_height: u32) -> u32 {
+        let (columns, rows) = self.grid(sheet_width, sheet_height);
+        let available = columns * rows;
+
+        self.frame_count
+            .map_or(available, |count| count.min(available))
+    }
+
+    fn elapsed_frames(&self, now: Instant) -> u32 {
+        let secs = now.saturating_duration_since(self.started).as_secs_f32();
+        (secs * self.fps.max(0.0)) as u32
+    }
+
+    /// The frame to show at `now`: its column and row in the sheet. Without looping the
+    /// animation stops on its last frame.
+    pub fn frame(&self, now: Instant, sheet_width: u32, sheet_height: u32) -> Option<(u32, u32)> {
+        let frames = self.frames(sheet_width, sheet_height);
+
+        if frames == 0 {
+            return None;
+        }
+
+        let elapsed = self.elapsed_frames(now);
+        let index = if self.looping {
+            elapsed % frames
+        } else {
+            elapsed.min(frames - 1)
+        };
+        let (columns, _) = self.grid(sheet_width, sheet_height);
+
+        Some((index % columns, index / columns))
+    }
+
+    /// Whether the frame shown is still changing.
+    pub fn playing(&self, now: Instant, sheet_width: u32, sheet_height: u32) -> bool {
+        let frames = self.frames(sheet_width, sheet_height);
+
+        frames > 1 && self.fps > 0.0 && (self.looping || self.elapsed_frames(now) < frames)
+    }
+}
@@ -0,0 +1,536 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use rquickjs::function::{Func, Rest};
+use rquickjs::prelude::Async;
+use rquickjs::{Array, ArrayBuffer, Ctx, FromJs, IntoJs, Object, Value};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{Connection, params_from_iter};
+
+use crate::engine::JsModule;
+use crate::fs::{self, FileData};
+
+/// Exposes a `sqlite` global to JS. Each opened database gets its own worker thread, so
+/// queries never block rendering; every call returns a Promise.
+///
+/// Database paths are resolved inside `root` with the same sandboxing rules as [`fs::Fs`].
+/// `":memory:"` opens a private in-memory database. As for `fs`, paths are resolved inside
+/// `root/default`, or the active profile's directory under `root/profiles`.
+pub struct Sqlite {
+    base: PathBuf,
+    root: RefCell<Rc<PathBuf>>,
+}
+
+impl Sqlite {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let base = root.into();
+
+        Self {
+            root: RefCell::new(Rc::new(fs::default_root(&base))),
+            base,
+        }
+    }
+}
+
+impl JsModule for Sqlite {
+    fn register(&self, ctx: &Ctx<'_>) {
+        let sqlite = Object::new(ctx.clone()).unwrap();
+
+        let root = self.root.borrow().clone();
+        sqlite
+            .set(
+                "open",
+                Func::from(
+                    move |ctx: Ctx<'_>, path: String| -> rquickjs::Result<Database> {
+                        open(&root, &path).map_err(|err| ctx.throw(err.into_js(&ctx).unwrap()))
+                    },
+                ),
+            )
+            .unwrap();
+
+        ctx.globals().set("sqlite", sqlite).unwrap();
+    }
+
+    fn set_profile(&self, profile: &str) {
+        *self.root.borrow_mut() = Rc::new(fs::profile_root(&self.base, profile));
+    }
+}
+
+fn open(root: &std::path::Path, path: &str) -> Result<Database, SqliteError> {
+    let conn = if path == ":memory:" {
+        Connection::open_in_memory()
+    } else {
+        let full = fs::resolve(root, path).map_err(|err| SqliteError {
+            message: err.message,
+        })?;
+        Connection::open(full)
+    }
+    .map_err(SqliteError::from)?;
+
+    conn.set_prepared_statement_cache_capacity(64);
+
+    let (tx, rx) = mpsc::channel::<Request>();
+    std::thread::spawn(move || run_worker(conn, rx));
+
+    Ok(Database { tx })
+}
+
+/// A single unit of work for a database's worker thread.
+enum Request {
+    Run {
+        sql: String,
+        params: Vec<SqlValue>,
+        reply: Reply<RunResult>,
+    },
+    Query {
+        sql: String,
+        params: Vec<SqlValue>,
+        /// Stop stepping after the first row, for `get`
+        first: bool,
+        reply: Reply<Rows>,
+    },
+    Transaction {
+        statements: Vec<(String, Vec<SqlValue>)>,
+        reply: Reply<Vec<RunResult>>,
+    },
+    Close {
+        reply: Reply<()>,
+    },
+}
+
+fn run_worker(mut conn: Connection, rx: mpsc::Receiver<Request>) {
+    for request in rx {
+        match request {
+            Request::Run { sql, params, reply } => reply.send(run(&conn, &sql, params)),
+            Request::Query {
+                sql,
+                params,
+                first,
+                reply,
+            } => reply.send(query(&conn, &sql, params, first)),
+            Request::Transaction { statements, reply } => {
+                let result = conn
+                    .transaction()
+                    .map_err(SqliteError::from)
+                    .and_then(|tx| {
+                        let results = statements
+                            .into_iter()
+                            .map(|(sql, params)| run(&tx, &sql, params))
+                            .collect::<Result<Vec<_>, _>>()?;
+
+                        // Dropping `tx` without committing rolls back on error above
+                        tx.commit()?;
+                        Ok(results)
+                    });
+
+                reply.send(result);
+            }
+            Request::Close { reply } => {
+                reply.send(Ok(()));
+                break;
+            }
+        }
+    }
+}
+
+fn run(conn: &Connection, sql: &str, params: Vec<SqlValue>) -> Result<RunResult, SqliteError> {
+    let changes = conn
+        .prepare_cached(sql)?
+        .execute(params_from_iter(params))?;
+
+    Ok(RunResult {
+        changes,
+        last_insert_rowid: conn.last_insert_rowid(),
+    })
+}
+
+fn query(
+    conn: &Connection,
+    sql: &str,
+    params: Vec<SqlValue>,
+    first: bool,
+) -> Result<Rows, SqliteError> {
+    let mut stmt = conn.prepare_cached(sql)?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let column_count = columns.len();
+
+    let rows = stmt
+        .query_map(params_from_iter(params), |row| {
+            (0..column_count)
+                .map(|i| row.get::<_, SqlValue>(i))
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })?
+        .take(if first { 1 } else { usize::MAX })
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(Rows { columns, rows })
+}
+
+/// Handle to an open database, converted to a JS object with Promise-returning methods.
+pub struct Database {
+    tx: mpsc::Sender<Request>,
+}
+
+impl<'js> IntoJs<'js> for Database {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        let db = Object::new(ctx.clone())?;
+        let tx = Rc::new(self.tx);
+
+        db.set("run", run_fn(ctx, tx.clone(), None))?;
+        db.set("all", all_fn(ctx, tx.clone(), None))?;
+        db.set("get", get_fn(ctx, tx.clone(), None))?;
+
+        let prepare_tx = tx.clone();
+        db.set(
+            "prepare",
+            Func::from(
+                move |ctx: Ctx<'js>, sql: String| -> rquickjs::Result<Object<'js>> {
+                    let stmt = Object::new(ctx.clone())?;
+                    stmt.set("sql", sql.clone())?;
+                    stmt.set("run", run_fn(&ctx, prepare_tx.clone(), Some(sql.clone())))?;
+                    stmt.set("all", all_fn(&ctx, prepare_tx.clone(), Some(sql.clone())))?;
+                    stmt.set("get", get_fn(&ctx, prepare_tx.clone(), Some(sql)))?;
+                    Ok(stmt)
+                },
+            ),
+        )?;
+
+        let transaction_tx = tx.clone();
+        db.set(
+            "transaction",
+            Func::from(Async(move |ctx: Ctx<'js>, statements: Vec<Statement>| {
+                let statements = statements.into_iter().map(|s| (s.0, s.1)).collect();
+                let pending = send(&transaction_tx, |reply| Request::Transaction {
+                    statements,
+                    reply,
+                });
+                settle(ctx, pending)
+            })),
+        )?;
+
+        let close_tx = tx;
+        db.set(
+            "close",
+            Func::from(Async(move |ctx: Ctx<'js>| {
+                let pending = send(&close_tx, |reply| Request::Close { reply });
+                settle(ctx, pending)
+            })),
+        )?;
+
+        db.into_js(ctx)
+    }
+}
+
+/// Build a `run(sql, params)` function, or `run(params)` when bound to a prepared statement.
+fn run_fn<'js>(
+    ctx: &Ctx<'js>,
+    tx: Rc<mpsc::Sender<Request>>,
+    prepared: Option<String>,
+) -> rquickjs::Result<Value<'js>> {
+    Func::from(Async(move |ctx: Ctx<'js>, args: Rest<Value<'js>>| {
+        let pending = statement_args(&ctx, prepared.as_deref(), args.0)
+            .map(|(sql, params)| send(&tx, |reply| Request::Run { sql, params, reply }));
+        settle_args(ctx, pending)
+    }))
+    .into_js(ctx)
+}
+
+/// Build an `all(sql, params)` function returning every row as an object.
+fn all_fn<'js>(
+    ctx: &Ctx<'js>,
+    tx: Rc<mpsc::Sender<Request>>,
+    prepared: Option<String>,
+) -> rquickjs::Result<Value<'js>> {
+    Func::from(Async(move |ctx: Ctx<'js>, args: Rest<Value<'js>>| {
+        let pending = statement_args(&ctx, prepared.as_deref(), args.0).map(|(sql, params)| {
+            send(&tx, |reply| Request::Query {
+                sql,
+                params,
+                first: false,
+                reply,
+            })
+        });
+        settle_args(ctx, pending)
+    }))
+    .into_js(ctx)
+}
+
+/// Build a `get(sql, params)` function returning the first row, or `null`.
+fn get_fn<'js>(
+    ctx: &Ctx<'js>,
+    tx: Rc<mpsc::Sender<Request>>,
+    prepared: Option<String>,
+) -> rquickjs::Result<Value<'js>> {
+    Func::from(Async(move |ctx: Ctx<'js>, args: Rest<Value<'js>>| {
+        let pending = statement_args(&ctx, prepared.as_deref(), args.0).map(|(sql, params)| {
+            send(&tx, |reply| Request::Query {
+                sql,
+                params,
+                first: true,
+                reply,
+            })
+        });
+
+        async move {
+            let rows = settle_args(ctx, pending).await?;
+            Ok::<_, rquickjs::Error>(FirstRow(rows))
+        }
+    }))
+    .into_js(ctx)
+}
+
+/// Split call arguments into SQL and parameters. Prepared statements only take parameters.
+fn statement_args<'js>(
+    ctx: &Ctx<'js>,
+    prepared: Option<&str>,
+    args: Vec<Value<'js>>,
+) -> rquickjs::Result<(String, Vec<SqlValue>)> {
+    let mut args = args.into_iter();
+
+    let sql = match prepared {
+        Some(sql) => sql.to_string(),
+        None => match args.next() {
+            Some(value) => String::from_js(ctx, value)?,
+            None => return Err(rquickjs::Error::new_from_js("undefined", "string")),
+        },
+    };
+
+    let params = match args.next() {
+        Some(value) if !value.is_undefined() && !value.is_null() => {
+            Vec::<JsSqlValue>::from_js(ctx, value)?
+                .into_iter()
+                .map(|v| v.0)
+                .collect()
+        }
+        _ => vec![],
+    };
+
+    Ok((sql, params))
+}
+
+fn send<T>(tx: &mpsc::Sender<Request>, build: impl FnOnce(Reply<T>) -> Request) -> Pending<T> {
+    let (reply, pending) = oneshot();
+
+    // If the worker has gone away the reply is dropped unsent, which settles the Pending
+    // with a "database is closed" error.
+    let _ = tx.send(build(reply));
+    pending
+}
+
+async fn settle<'js, T>(ctx: Ctx<'js>, pending: Pending<T>) -> rquickjs::Result<T> {
+    pending
+        .await
+        .map_err(|err| ctx.throw(err.into_js(&ctx).unwrap()))
+}
+
+async fn settle_args<'js, T>(
+    ctx: Ctx<'js>,
+    pending: rquickjs::Result<Pending<T>>,
+) -> rquickjs::Result<T> {
+    settle(ctx, pending?).await
+}
+
+/// One `[sql, params?]` entry passed to `transaction()`.
+struct Statement(String, Vec<SqlValue>);
+
+impl<'js> FromJs<'js> for Statement {
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> rquickjs::Result<Self> {
+        let array = Array::from_js(ctx, value)?;
+        let sql: String = array.get(0)?;
+        let params: Option<Vec<JsSqlValue>> = array.get(1)?;
+
+        Ok(Statement(
+            sql,
+            params
+                .unwrap_or_default()
+                .into_iter()
+                .map(|v| v.0)
+                .collect(),
+        ))
+    }
+}
+
+/// A SQL value converted from JS. Whole numbers bind as integers, booleans as 0/1, and
+/// `ArrayBuffer`/`Uint8Array` as blobs.
+struct JsSqlValue(SqlValue);
+
+impl<'js> FromJs<'js> for JsSqlValue {
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> rquickjs::Result<Self> {
+        let sql_value = if value.is_null() || value.is_undefined() {
+            SqlValue::Null
+        } else if let Some(b) = value.as_bool() {
+            SqlValue::Integer(b as i64)
+        } else if let Some(i) = value.as_int() {
+            SqlValue::Integer(i as i64)
+        } else if let Some(f) = value.as_float() {
+            if f.fract() == 0.0 && f.abs() < 9_007_199_254_740_992.0 {
+                SqlValue::Integer(f as i64)
+            } else {
+                SqlValue::Real(f)
+            }
+        } else if let Some(s) = value.as_string() {
+            SqlValue::Text(s.to_string()?)
+        } else {
+            SqlValue::Blob(FileData::from_js(ctx, value)?.0)
+        };
+
+        Ok(JsSqlValue(sql_value))
+    }
+}
+
+fn sql_value_into_js<'js>(value: SqlValue, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+    match value {
+        SqlValue::Null => Ok(Value::new_null(ctx.clone())),
+        SqlValue::Integer(i) => (i as f64).into_js(ctx),
+        SqlValue::Real(f) => f.into_js(ctx),
+        SqlValue::Text(s) => s.into_js(ctx),
+        SqlValue::Blob(data) => ArrayBuffer::new(ctx.clone(), data)?.into_js(ctx),
+    }
+}
+
+pub struct RunResult {
+    pub changes: usize,
+    pub last_insert_rowid: i64,
+}
+
+impl<'js> IntoJs<'js> for RunResult {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        let result = Object::new(ctx.clone())?;
+        result.set("changes", self.changes as f64)?;
+        result.set("lastInsertRowid", self.last_insert_rowid as f64)?;
+        result.into_js(ctx)
+    }
+}
+
+pub struct Rows {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<SqlValue>>,
+}
+
+fn row_into_js<'js>(
+    columns: &[String],
+    row: Vec<SqlValue>,
+    ctx: &Ctx<'js>,
+) -> rquickjs::Result<Value<'js>> {
+    let object = Object::new(ctx.clone())?;
+
+    for (column, value) in columns.iter().zip(row) {
+        object.set(column.as_str(), sql_value_into_js(value, ctx)?)?;
+    }
+
+    object.into_js(ctx)
+}
+
+impl<'js> IntoJs<'js> for Rows {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        let array = Array::new(ctx.clone())?;
+
+        for (i, row) in self.rows.into_iter().enumerate() {
+            array.set(i, row_into_js(&self.columns, row, ctx)?)?;
+        }
+
+        array.into_js(ctx)
+    }
+}
+
+struct FirstRow(Rows);
+
+impl<'js> IntoJs<'js> for FirstRow {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        let Rows { columns, rows } = self.0;
+
+        match rows.into_iter().next() {
+            Some(row) => row_into_js(&columns, row, ctx),
+            None => Ok(Value::new_null(ctx.clone())),
+        }
+    }
+}
+
+pub struct SqliteError {
+    pub message: String,
+}
+
+impl From<rusqlite::Error> for SqliteError {
+    fn from(err: rusqlite::Error) -> Self {
+        SqliteError {
+            message: err.to_string(),
+        }
+    }
+}
+
+impl<'js> IntoJs<'js> for SqliteError {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        let error = Object::new(ctx.clone())?;
+        error.set("message", self.message.into_js(ctx))?;
+        error.into_js(ctx)
+    }
+}
+
+/// Shared slot between a worker-side [`Reply`] and a JS-side [`Pending`] future.
+struct Slot<T> {
+    value: Option<Result<T, SqliteError>>,
+    waker: Option<Waker>,
+}
+
+struct Reply<T>(Option<Arc<Mutex<Slot<T>>>>);
+
+struct Pending<T>(Arc<Mutex<Slot<T>>>);
+
+fn oneshot<T>() -> (Reply<T>, Pending<T>) {
+    let slot = Arc::new(Mutex::new(Slot {
+        value: None,
+        waker: None,
+    }));
+
+    (Reply(Some(slot.clone())), Pending(slot))
+}
+
+impl<T> Reply<T> {
+    fn send(mut self, value: Result<T, SqliteError>) {
+        if let Some(slot) = self.0.take() {
+            let mut slot = slot.lock().unwrap();
+            slot.value = Some(value);
+
+            if let Some(waker) = slot.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<T> Drop for Reply<T> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.0.take() {
+            let mut slot = slot.lock().unwrap();
+            slot.value = Some(Err(SqliteError {
+                message: "database is closed".to_string(),
+            }));
+
+            if let Some(waker) = slot.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<T> Future for Pending<T> {
+    type Output = Result<T, SqliteError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.0.lock().unwrap();
+
+        match slot.value.take() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
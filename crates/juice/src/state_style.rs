@@ -0,0 +1,86 @@
+use serde::Deserialize;
+
+use crate::canvas::RgbColor;
+
+/// Styles swapped in natively while a node is in some state, from the `pressedStyle`,
+/// `focusedStyle` and `disabledStyle` attributes. Only properties that don't change
+/// layout can be used, so state changes only redraw.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateStyle {
+    /// Hex color or `var(--name)`
+    pub background: Option<String>,
+    /// Hex color or `var(--name)`, inherited by children like the `color` style
+    pub color: Option<String>,
+    pub border_radius: Option<f32>,
+    /// Hex color or `var(--name)`
+    pub border_color: Option<String>,
+}
+
+impl StateStyle {
+    /// Parse the JSON object the TS side sends. Empty or invalid JSON clears the style.
+    pub fn parse(value: &str) -> Option<Self> {
+        serde_json::from_str(value).ok()
+    }
+}
+
+/// What a node is doing, for picking its state styles.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NodeState {
+    /// Under a press, or an ancestor of what's under it
+    pub pressed: bool,
+    /// The focused input, or an ancestor of it
+    pub focused: bool,
+    /// From the `disabled` attribute
+    pub disabled: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StateStyles {
+    pub pressed: Option<StateStyle>,
+    pub focused: Option<StateStyle>,
+    pub disabled: Option<StateStyle>,
+}
+
+/// The state style values currently in force on a node.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StateOverrides {
+    pub background: Option<RgbColor>,
+    pub color: Option<RgbColor>,
+    pub border_radius: Option<f32>,
+    pub border_color: Option<RgbColor>,
+}
+
+impl StateStyles {
+    /// Merge the styles for a state, property by property. `disabledStyle` wins over
+    /// `pressedStyle`, which wins over `focusedStyle`, and disabled nodes don't look
+    /// pressed.
+    pub fn resolve(
+        &self,
+        state: NodeState,
+        resolve_color: impl Fn(&str) -> Option<RgbColor>,
+    ) -> StateOverrides {
+        let active = [
+            (state.disabled, &self.disabled),
+            (state.pressed && !state.disabled, &self.pressed),
+            (state.focused, &self.focused),
+        ];
+        let mut overrides = StateOverrides::default();
+
+        for style in active
+            .into_iter()
+            .filter_map(|(active, style)| style.as_ref().filter(|_| active))
+        {
+            let color = |value: &Option<String>| value.as_deref().and_then(&resolve_color);
+
+            overrides.background = overrides.background.or_else(|| color(&style.background));
+            overrides.color = overrides.color.or_else(|| color(&style.color));
+            overrides.border_radius = overrides.border_radius.or(style.border_radius);
+            overrides.border_color = overrides
+                .border_color
+                .or_else(|| color(&style.border_color));
+        }
+
+        overrides
+    }
+}
@@ -0,0 +1,242 @@
+use serde::Deserialize;
+
+use crate::{canvas::RgbColor, inherited_style::TextAlign};
+
+/// How wide a table column is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnWidth {
+    /// Pixels
+    Fixed(f32),
+    /// Percent of the table's width
+    Percent(f32),
+    /// A share of the width left after fixed and percent columns
+    Fraction(f32),
+}
+
+impl ColumnWidth {
+    fn parse(spec: &WidthSpec) -> Self {
+        match spec {
+            WidthSpec::Pixels(width) => ColumnWidth::Fixed(width.max(0.0)),
+            WidthSpec::Text(text) => {
+                let text = text.trim();
+
+                if let Some(percent) = text.strip_suffix('%').and_then(|n| n.parse().ok()) {
+                    ColumnWidth::Percent(f32::max(percent, 0.0))
+                } else if let Some(fraction) = text.strip_suffix("fr").and_then(|n| n.parse().ok())
+                {
+                    ColumnWidth::Fraction(f32::max(fraction, 0.0))
+                } else if let Ok(width) = text.strip_suffix("px").unwrap_or(text).parse() {
+                    ColumnWidth::Fixed(f32::max(width, 0.0))
+                } else {
+                    ColumnWidth::Fraction(1.0)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub title: String,
+    pub width: ColumnWidth,
+    pub align: TextAlign,
+}
+
+/// Colors overriding the table's for one cell.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CellStyle {
+    pub color: Option<RgbColor>,
+    pub background: Option<RgbColor>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cell {
+    pub text: String,
+    pub style: CellStyle,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WidthSpec {
+    Pixels(f32),
+    Text(String),
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum AlignSpec {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Deserialize)]
+struct ColumnSpec {
+    #[serde(default)]
+    title: String,
+    width: Option<WidthSpec>,
+    #[serde(default)]
+    align: AlignSpec,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CellSpec {
+    Text(String),
+    Number(f64),
+    Styled {
+        #[serde(default)]
+        text: String,
+        color: Option<String>,
+        background: Option<String>,
+    },
+}
+
+/// State of a `table` node: its columns and rows. The node draws only the rows in view
+/// below a header row that stays put, and scrolls its rows itself.
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<Cell>>,
+    /// Height of the header and each row. Defaults to twice the font size.
+    pub row_height: Option<f32>,
+    pub header_background: Option<RgbColor>,
+    /// Background of every other row, starting with the second
+    pub stripe_color: Option<RgbColor>,
+}
+
+impl Table {
+    /// Parse the `columns` attribute: JSON `[{title, width, align}]`, where width is
+    /// pixels, a percentage like `"25%"` or a share like `"2fr"` (`"1fr"` by default).
+    pub fn set_columns(&mut self, json: &str) {
+        let specs: Vec<ColumnSpec> = serde_json::from_str(json).unwrap_or_default();
+
+        self.columns = specs
+            .into_iter()
+            .map(|spec| Column {
+                title: spec.title,
+                width: spec
+                    .width
+                    .as_ref()
+                    .map_or(ColumnWidth::Fraction(1.0), ColumnWidth::parse),
+                align: match spec.align {
+                    AlignSpec::Left => TextAlign::Left,
+                    AlignSpec::Center => TextAlign::Center,
+                    AlignSpec::Right => TextAlign::Right,
+                },
+            })
+            .collect();
+    }
+
+    /// Parse the `rows` attribute: JSON arrays of cells, each a string, a number or
+    /// `{text, color, background}`, with colors resolved by `resolve_color`.
+    pub fn set_rows(&mut self, json: &str, resolve_color: impl Fn(&str) -> Option<RgbColor>) {
+        let specs: Vec<Vec<CellSpec>> = serde_json::from_str(json).unwrap_or_default();
+
+        self.rows = specs
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|cell| match cell {
+                        CellSpec::Text(text) => Cell {
+                            text,
+                            style: CellStyle::default(),
+                        },
+                        CellSpec::Number(number) => Cell {
+                            text: number.to_string(),
+                            style: CellStyle::default(),
+                        },
+                        CellSpec::Styled {
+                            text,
+                            color,
+                            background,
+                        } => Cell {
+                            text,
+                            style: CellStyle {
+                                color: color.as_deref().and_then(&resolve_color),
+                                background: background.as_deref().and_then(&resolve_color),
+                            },
+                        },
+                    })
+                    .collect()
+            })
+            .collect();
+    }
+
+    pub fn row_height(&self, font_size: f32) -> f32 {
+        self.row_height.unwrap_or(font_size * 2.0).max(1.0)
+    }
+
+    /// Height of every row together, not counting the header.
+    pub fn body_height(&self, font_size: f32) -> f32 {
+        self.rows.len() as f32 * self.row_height(font_size)
+    }
+
+    /// How far the rows can scroll in a table `height` tall.
+    pub fn max_scroll(&self, font_size: f32, height: f32) -> f32 {
+        let visible = height - self.row_height(font_size);
+        (self.body_height(font_size) - visible).max(0.0)
+    }
+
+    /// Each column's width in a table `width` wide: fixed and percent columns first,
+    /// then fractions of whatever is left.
+    pub fn column_widths(&self, width: f32) -> Vec<f32> {
+        let claimed: f32 = self
+            .columns
+            .iter()
+            .map(|column| match column.width {
+                ColumnWidth::Fixed(pixels) => pixels,
+                ColumnWidth::Percent(percent) => width * percent / 100.0,
+                ColumnWidth::Fraction(_) => 0.0,
+            })
+            .sum();
+        let fractions: f32 = self
+            .columns
+            .iter()
+            .map(|column| match column.width {
+                ColumnWidth::Fraction(fraction) => fraction,
+                _ => 0.0,
+            })
+            .sum();
+        let per_fraction = if fractions > 0.0 {
+            (width - claimed).max(0.0) / fractions
+        } else {
+            0.0
+        };
+
+        self.columns
+            .iter()
+            .map(|column| match column.width {
+                ColumnWidth::Fixed(pixels) => pixels,
+                ColumnWidth::Percent(percent) => width * percent / 100.0,
+                ColumnWidth::Fraction(fraction) => fraction * per_fraction,
+            })
+            .collect()
+    }
+
+    /// The row and column at a point relative to the table's top left, scrolled by
+    /// `scroll_y`. The row is `None` for the header.
+    pub fn cell_at(
+        &self,
+        (x, y): (f32, f32),
+        width: f32,
+        font_size: f32,
+        scroll_y: f32,
+    ) -> Option<(Option<usize>, usize)> {
+        let mut left = 0.0;
+        let column = self.column_widths(width).iter().position(|column_width| {
+            left += column_width;
+            x < left
+        })?;
+        let row_height = self.row_height(font_size);
+
+        if y < row_height {
+            return Some((None, column));
+        }
+
+        let row = ((y - row_height + scroll_y) / row_height).floor();
+
+        (row >= 0.0 && (row as usize) < self.rows.len()).then_some((Some(row as usize), column))
+    }
+}
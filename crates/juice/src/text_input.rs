@@ -0,0 +1,200 @@
+use std::fmt;
+use std::ops::Range;
+
+use zeroize::Zeroize;
+
+/// Drawn in place of each character of a secure input
+pub const MASK_CHAR: char = '•';
+
+/// Editing keys a host can send to the focused text input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditKey {
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+    /// Fires `Submit` rather than editing
+    Enter,
+}
+
+/// Value, caret and in-progress IME composition of an `<input>` node.
+#[derive(Default)]
+pub struct TextInputState {
+    pub value: String,
+    /// Byte offset of the caret in `value`, always on a char boundary
+    pub caret: usize,
+    /// Preedit text from an IME, drawn underlined at the caret but not yet part of the
+    /// value
+    pub composition: Option<String>,
+    pub focused: bool,
+    /// Mask the value when drawing, keep it out of `Debug` output, and zero its memory
+    /// when it's replaced or dropped
+    pub secure: bool,
+    /// For secure inputs, show the most recently typed character until the next edit
+    pub reveal_last: bool,
+    /// Byte offset of the character currently shown unmasked
+    revealed: Option<usize>,
+}
+
+impl fmt::Debug for TextInputState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (value, composition): (&dyn fmt::Debug, &dyn fmt::Debug) = if self.secure {
+            (
+                &"<redacted>",
+                &self.composition.as_ref().map(|_| "<redacted>"),
+            )
+        } else {
+            (&self.value, &self.composition)
+        };
+
+        f.debug_struct("TextInputState")
+            .field("value", value)
+            .field("caret", &self.caret)
+            .field("composition", composition)
+            .field("focused", &self.focused)
+            .field("secure", &self.secure)
+            .finish()
+    }
+}
+
+impl Drop for TextInputState {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl TextInputState {
+    /// Replace the value, e.g. from a controlled JS prop. The caret moves to the end
+    /// unless the value is unchanged.
+    pub fn set_value(&mut self, mut value: String) {
+        if value != self.value {
+            self.clear();
+            self.caret = value.len();
+            std::mem::swap(&mut self.value, &mut value);
+        }
+
+        value.zeroize();
+    }
+
+    /// Empty the value and any composition, zeroing the memory they occupied.
+    pub fn clear(&mut self) {
+        self.value.zeroize();
+        self.caret = 0;
+        self.revealed = None;
+
+        if let Some(mut composition) = self.composition.take() {
+            composition.zeroize();
+        }
+    }
+
+    pub fn insert(&mut self, text: &str) {
+        // Grow into a fresh buffer ourselves so a secure value isn't left behind in the
+        // old allocation
+        if self.secure && self.value.capacity() - self.value.len() < text.len() {
+            let mut grown = String::with_capacity((self.value.len() + text.len()) * 2);
+            grown.push_str(&self.value);
+            std::mem::swap(&mut self.value, &mut grown);
+            grown.zeroize();
+        }
+
+        self.value.insert_str(self.caret, text);
+        self.caret += text.len();
+        self.revealed = if self.reveal_last && !text.is_empty() {
+            self.previous_boundary()
+        } else {
+            None
+        };
+    }
+
+    /// Stop showing the last typed character of a secure input, e.g. on blur.
+    pub fn hide_revealed(&mut self) {
+        self.revealed = None;
+    }
+
+    /// Apply an editing key. Returns whether the value changed.
+    pub fn edit(&mut self, key: EditKey) -> bool {
+        self.revealed = None;
+
+        match key {
+            EditKey::Backspace => {
+                let Some(previous) = self.previous_boundary() else {
+                    return false;
+                };
+                self.value.replace_range(previous..self.caret, "");
+                self.caret = previous;
+                true
+            }
+            EditKey::Delete => {
+                let Some(next) = self.next_boundary() else {
+                    return false;
+                };
+                self.value.replace_range(self.caret..next, "");
+                true
+            }
+            EditKey::Left => {
+                self.caret = self.previous_boundary().unwrap_or(self.caret);
+                false
+            }
+            EditKey::Right => {
+                self.caret = self.next_boundary().unwrap_or(self.caret);
+                false
+            }
+            EditKey::Home => {
+                self.caret = 0;
+                false
+            }
+            EditKey::End => {
+                self.caret = self.value.len();
+                false
+            }
+            EditKey::Enter => false,
+        }
+    }
+
+    /// The text to draw: the value, masked with `mask` if secure, with any composition
+    /// spliced in at the caret. The range is where the composition sits in the text; the
+    /// caret is drawn at its end.
+    pub fn display_text(&self, mask: char) -> (String, Range<usize>) {
+        let (before, after) = self.value.split_at(self.caret);
+        let composition = self.composition.as_deref().unwrap_or("");
+        let mut text = String::new();
+
+        self.push_display(&mut text, before, Some(0), mask);
+        let start = text.len();
+        self.push_display(&mut text, composition, None, mask);
+        let end = text.len();
+        self.push_display(&mut text, after, Some(self.caret), mask);
+
+        (text, start..end)
+    }
+
+    /// Append part of the display text, masked if secure. `offset` is where the part
+    /// starts in the value, to find the revealed character.
+    fn push_display(&self, text: &mut String, part: &str, offset: Option<usize>, mask: char) {
+        if !self.secure {
+            text.push_str(part);
+            return;
+        }
+
+        for (i, c) in part.char_indices() {
+            let revealed = self.revealed.is_some() && self.revealed == offset.map(|o| o + i);
+            text.push(if revealed { c } else { mask });
+        }
+    }
+
+    fn previous_boundary(&self) -> Option<usize> {
+        self.value[..self.caret]
+            .char_indices()
+            .next_back()
+            .map(|(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        self.value[self.caret..]
+            .chars()
+            .next()
+            .map(|c| self.caret + c.len_utf8())
+    }
+}
@@ -0,0 +1,120 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::canvas::RgbColor;
+
+/// Named color palettes that `color` and `background` can refer to as `var(--name)`.
+/// Switching theme re-resolves every reference natively, so a light/dark swap doesn't
+/// need a JS re-render.
+#[derive(Default)]
+pub struct Themes {
+    themes: HashMap<String, HashMap<String, RgbColor>>,
+    active: Option<String>,
+}
+
+impl Themes {
+    /// Add or replace a theme. Values that aren't `#rrggbb` colors are skipped.
+    pub fn register(&mut self, name: String, variables: HashMap<String, String>) {
+        let variables = variables
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let key = key.strip_prefix("--").map(str::to_string).unwrap_or(key);
+                Some((key, RgbColor::from_string(&value)?))
+            })
+            .collect();
+
+        self.themes.insert(name, variables);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.themes.contains_key(name)
+    }
+
+    /// Make a registered theme active. Returns false if it isn't registered or is
+    /// already active.
+    pub fn set_active(&mut self, name: &str) -> bool {
+        if !self.themes.contains_key(name) || self.active.as_deref() == Some(name) {
+            return false;
+        }
+
+        self.active = Some(name.to_string());
+        true
+    }
+
+    pub fn active(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    /// Look up a variable in the active theme.
+    pub fn get(&self, variable: &str) -> Option<RgbColor> {
+        self.themes
+            .get(self.active.as_deref()?)?
+            .get(variable)
+            .copied()
+    }
+
+    /// Parse a color attribute: either `#rrggbb` or a `var(--name)` reference.
+    pub fn resolve(&self, value: &str) -> Option<RgbColor> {
+        match variable_name(value) {
+            Some(variable) => self.get(variable),
+            None => RgbColor::from_string(value),
+        }
+    }
+}
+
+/// The variable a `var(--name)` value refers to.
+pub fn variable_name(value: &str) -> Option<&str> {
+    value
+        .trim()
+        .strip_prefix("var(--")?
+        .strip_suffix(')')
+        .map(str::trim)
+}
+
+/// Switches between a light and a dark theme as ambient light changes. The gap between
+/// the thresholds stops the theme flickering when the reading hovers around one.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoTheme {
+    /// Switch to `dark` when lux falls below this
+    #[serde(default = "default_dark_below")]
+    pub dark_below: f32,
+    /// Switch back to `light` when lux rises above this
+    #[serde(default = "default_light_above")]
+    pub light_above: f32,
+    #[serde(default = "default_light")]
+    pub light: String,
+    #[serde(default = "default_dark")]
+    pub dark: String,
+}
+
+fn default_dark_below() -> f32 {
+    10.0
+}
+
+fn default_light_above() -> f32 {
+    30.0
+}
+
+fn default_light() -> String {
+    "light".to_string()
+}
+
+fn default_dark() -> String {
+    "dark".to_string()
+}
+
+impl AutoTheme {
+    /// The theme to switch to for a lux reading, or `None` to stay on the current one.
+    pub fn theme_for(&self, lux: f32, current: Option<&str>) -> Option<&str> {
+        let wanted = if lux < self.dark_below {
+            &self.dark
+        } else if lux > self.light_above {
+            &self.light
+        } else {
+            return None;
+        };
+
+        (current != Some(wanted.as_str())).then_some(wanted.as_str())
+    }
+}
@@ -4,7 +4,8 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use std::time::{Duration, Instant};
 
-use crate::engine::JsModule;
+use crate::clock::Clock;
+use crate::engine::{Engine, JsModule};
 
 #[derive(Debug)]
 struct Timer {
@@ -18,19 +19,22 @@ struct Timer {
 pub struct Timers {
     timers: Rc<RefCell<Vec<Timer>>>,
     next_id: Rc<RefCell<u32>>,
+    clock: Clock,
 }
 
 impl Timers {
-    pub fn new() -> Self {
+    /// Timers that fall due by `clock`.
+    pub fn new(clock: Clock) -> Self {
         Timers {
             timers: Rc::new(RefCell::new(Vec::new())),
             next_id: Rc::new(RefCell::new(1)),
+            clock,
         }
     }
 
     /// Fire any expired timers. Intervals are rescheduled; timeouts are removed.
     pub fn tick(&self, ctx: &Ctx<'_>) {
-        let now = Instant::now();
+        let now = self.clock.now();
 
         let ready: Vec<Persistent<Function<'static>>> = {
             let mut timers = self.timers.borrow_mut();
@@ -59,7 +63,12 @@ impl Timers {
         }
     }
 
-    /// Drop all timers. Must be called before the Runtime is dropped.
+    /// When the next timer is due.
+    pub fn next_fire_at(&self) -> Option<Instant> {
+        self.timers.borrow().iter().map(|t| t.fire_at).min()
+    }
+
+    /// Drop all timers. Done when the engine they're registered with drops.
     pub fn clear(&self) {
         self.timers.borrow_mut().clear();
     }
@@ -77,8 +86,12 @@ impl JsModule for Timers {
         let timers = self.timers.clone();
         let next_id = self.next_id.clone();
 
+        let timers_cell = timers.clone();
+        Engine::on_teardown(ctx, move || timers_cell.borrow_mut().clear());
+
         let timers_cell = timers.clone();
         let id_cell = next_id.clone();
+        let clock = self.clock.clone();
         ctx.globals()
             .set(
                 "setTimeout",
@@ -90,7 +103,7 @@ impl JsModule for Timers {
                         timers_cell.borrow_mut().push(Timer {
                             id,
                             callback,
-                            fire_at: Instant::now() + delay,
+                            fire_at: clock.now() + delay,
                             interval: None,
                         });
 
@@ -113,6 +126,7 @@ impl JsModule for Timers {
 
         let timers_cell = timers.clone();
         let id_cell = next_id.clone();
+        let clock = self.clock.clone();
 
         ctx.globals()
             .set(
@@ -125,7 +139,7 @@ impl JsModule for Timers {
                         timers_cell.borrow_mut().push(Timer {
                             id,
                             callback,
-                            fire_at: Instant::now() + interval,
+                            fire_at: clock.now() + interval,
                             interval: Some(interval),
                         });
 
@@ -0,0 +1,169 @@
+use std::f32::consts::PI;
+
+/// A 2D affine map taking (x, y) to (a x + c y + e, b x + d y + f), as in CSS `matrix()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Affine {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+}
+
+impl Affine {
+    pub const IDENTITY: Affine = Affine {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 1.0,
+        e: 0.0,
+        f: 0.0,
+    };
+
+    pub fn translate(x: f32, y: f32) -> Self {
+        Affine {
+            e: x,
+            f: y,
+            ..Affine::IDENTITY
+        }
+    }
+
+    pub fn scale(x: f32, y: f32) -> Self {
+        Affine {
+            a: x,
+            d: y,
+            ..Affine::IDENTITY
+        }
+    }
+
+    /// Clockwise on screen, since y points down.
+    pub fn rotate(radians: f32) -> Self {
+        let (sin, cos) = radians.sin_cos();
+
+        Affine {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            ..Affine::IDENTITY
+        }
+    }
+
+    /// This map followed by `next`.
+    pub fn then(self, next: Affine) -> Self {
+        Affine {
+            a: next.a * self.a + next.c * self.b,
+            b: next.b * self.a + next.d * self.b,
+            c: next.a * self.c + next.c * self.d,
+            d: next.b * self.c + next.d * self.d,
+            e: next.a * self.e + next.c * self.f + next.e,
+            f: next.b * self.e + next.d * self.f + next.f,
+        }
+    }
+
+    /// The same map applied around `(x, y)` instead of the origin.
+    pub fn around(self, (x, y): (f32, f32)) -> Self {
+        Affine::translate(-x, -y)
+            .then(self)
+            .then(Affine::translate(x, y))
+    }
+
+    /// `None` if it squashes everything onto a line or point, like `scale(0)`.
+    pub fn invert(self) -> Option<Self> {
+        let det = self.a * self.d - self.b * self.c;
+
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let (a, b, c, d) = (self.d / det, -self.b / det, -self.c / det, self.a / det);
+
+        Some(Affine {
+            a,
+            b,
+            c,
+            d,
+            e: -(a * self.e + c * self.f),
+            f: -(b * self.e + d * self.f),
+        })
+    }
+
+    pub fn apply(self, (x, y): (f32, f32)) -> (f32, f32) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+
+    /// Parse a CSS `transform` made of `translate()`, `translateX()`, `translateY()`,
+    /// `scale()`, `scaleX()`, `scaleY()` and `rotate()`, applied right to left as in
+    /// CSS. Lengths are pixels with or without `px`; angles are `deg`, `rad`, `grad` or
+    /// `turn`. `none` gives `None`, as does anything else that doesn't parse.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut rest = value.trim();
+
+        if rest == "none" {
+            return None;
+        }
+
+        let mut transform = Affine::IDENTITY;
+
+        while !rest.is_empty() {
+            let (name, after) = rest.split_once('(')?;
+            let (args, after) = after.split_once(')')?;
+            rest = after.trim_start();
+
+            let args: Vec<&str> = args
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|arg| !arg.is_empty())
+                .collect();
+
+            let function = match (name.trim(), &args[..]) {
+                ("translate", [x]) => Affine::translate(length(x)?, 0.0),
+                ("translate", [x, y]) => Affine::translate(length(x)?, length(y)?),
+                ("translateX", [x]) => Affine::translate(length(x)?, 0.0),
+                ("translateY", [y]) => Affine::translate(0.0, length(y)?),
+                ("scale", [s]) => Affine::scale(number(s)?, number(s)?),
+                ("scale", [x, y]) => Affine::scale(number(x)?, number(y)?),
+                ("scaleX", [x]) => Affine::scale(number(x)?, 1.0),
+                ("scaleY", [y]) => Affine::scale(1.0, number(y)?),
+                ("rotate", [angle]) => Affine::rotate(self::angle(angle)?),
+                _ => return None,
+            };
+
+            // Functions further right apply first
+            transform = function.then(transform);
+        }
+
+        Some(transform)
+    }
+}
+
+fn number(value: &str) -> Option<f32> {
+    let number: f32 = value.parse().ok()?;
+    number.is_finite().then_some(number)
+}
+
+/// Pixels, with or without `px`.
+fn length(value: &str) -> Option<f32> {
+    number(value.strip_suffix("px").unwrap_or(value))
+}
+
+/// In radians. A bare `0` is allowed, as in CSS.
+fn angle(value: &str) -> Option<f32> {
+    let units = [
+        ("deg", PI / 180.0),
+        ("grad", PI / 200.0),
+        ("rad", 1.0),
+        ("turn", PI * 2.0),
+    ];
+
+    for (unit, radians) in units {
+        if let Some(value) = value.strip_suffix(unit) {
+            return Some(number(value)? * radians);
+        }
+    }
+
+    (number(value)? == 0.0).then_some(0.0)
+}
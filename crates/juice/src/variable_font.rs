@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use fontdue::{Font, FontSettings};
+use ttf_parser::{Face, GlyphId, OutlineBuilder, RawFace, Tag};
+
+/// Variable fonts by `Font::file_hash`, with the data instances are made from. fontdue
+/// only draws a variable font's default instance, so other instances are made into
+/// static fonts of their own.
+static FONTS: LazyLock<Mutex<HashMap<usize, Arc<VariableFont>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Tables describing variations, or hinting that no longer fits the changed outlines,
+/// left out of instances
+const DROPPED_TABLES: &[&[u8; 4]] = &[
+    b"fvar", b"gvar", b"avar", b"cvar", b"HVAR", b"VVAR", b"MVAR", b"STAT", b"fpgm", b"prep",
+    b"cvt ", b"hdmx", b"LTSH", b"VDMX", b"DSIG",
+];
+
+/// One of a variable font's axes, like weight (`wght`) or width (`wdth`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Axis {
+    pub tag: [u8; 4],
+    pub min: f32,
+    pub default: f32,
+    pub max: f32,
+}
+
+/// Values of a variable font's axes, by tag
+pub type Coordinates = Vec<([u8; 4], f32)>;
+
+struct VariableFont {
+    data: Vec<u8>,
+    axes: Vec<Axis>,
+}
+
+/// Keep `font`'s data if it's a variable TrueType font, so instances can be made of it.
+/// Returns whether it is one. Variable CFF2 fonts aren't supported, so are only drawn at
+/// their default instance.
+pub fn register(font: &Font, data: &[u8]) -> bool {
+    let Ok(face) = Face::parse(data, 0) else {
+        return false;
+    };
+
+    if !face.is_variable() || face.tables().glyf.is_none() {
+        return false;
+    }
+
+    let axes = face
+        .variation_axes()
+        .into_iter()
+        .map(|axis| Axis {
+            tag: axis.tag.to_bytes(),
+            min: axis.min_value,
+            default: axis.def_value,
+            max: axis.max_value,
+        })
+        .collect();
+
+    FONTS.lock().unwrap().insert(
+        font.file_hash(),
+        Arc::new(VariableFont {
+            data: data.to_vec(),
+            axes,
+        }),
+    );
+
+    true
+}
+
+/// The axes of a font `register` found to be variable, or `None` for a static font.
+pub fn axes(font: &Font) -> Option<Vec<Axis>> {
+    FONTS
+        .lock()
+        .unwrap()
+        .get(&font.file_hash())
+        .map(|variable| variable.axes.clone())
+}
+
+/// Make the instance of a variable font at `coordinates`, given by axis tag. Axes left
+/// out stay at their defaults.
+pub fn instance(font: &Font, coordinates: &[([u8; 4], f32)]) -> Result<Font, String> {
+    let variable = FONTS
+        .lock()
+        .unwrap()
+        .get(&font.file_hash())
+        .cloned()
+        .ok_or("not a variable font")?;
+
+    let data = instance_data(&variable.data, coordinates)?;
+    let font = Font::from_bytes(&*data, FontSettings::default()).map_err(str::to_string)?;
+    crate::emoji::register(&font, &data);
+    crate::decoration::register(&font, &data);
+
+    #[cfg(feature = "shaping")]
+    crate::shaping::register(&font, data);
+
+    Ok(font)
+}
+
+/// A static TrueType font of the variable font `data` at `coordinates`: its outlines and
+/// advances with the variations applied, and every other table copied as it is.
+fn instance_data(data: &[u8], coordinates: &[([u8; 4], f32)]) -> Result<Vec<u8>, String> {
+    let mut face = Face::parse(data, 0).map_err(|err| err.to_string())?;
+    let raw = RawFace::parse(data, 0).map_err(|err| err.to_string())?;
+
+    for (tag, value) in coordinates {
+        face.set_variation(Tag::from_bytes(tag), *value);
+    }
+
+    let glyph_count = face.number_of_glyphs();
+    let mut glyf = Vec::new();
+    let mut loca = Vec::with_capacity((glyph_count as usize + 1) * 4);
+    let mut hmtx = Vec::with_capacity(glyph_count as usize * 4);
+    let mut advance_max = 0;
+
+    for id in 0..glyph_count {
+        let mut outline = Outline::default();
+        face.outline_glyph(GlyphId(id), &mut outline);
+
+        let advance = face.glyph_hor_advance(GlyphId(id)).unwrap_or(0);
+        let left_bearing = outline.bounds().map_or(0, |(x_min, ..)| x_min);
+        advance_max = advance_max.max(advance);
+
+        loca.extend_from_slice(&(glyf.len() as u32).to_be_bytes());
+        outline.write(&mut glyf);
+        hmtx.extend_from_slice(&advance.to_be_bytes());
+        hmtx.extend_from_slice(&left_bearing.to_be_bytes());
+    }
+
+    loca.extend_from_slice(&(glyf.len() as u32).to_be_bytes());
+
+    let table = |tag: &[u8; 4]| {
+        raw.table(Tag::from_bytes(tag))
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| format!("no {} table", String::from_utf8_lossy(tag)))
+    };
+
+    // Long loca offsets, and a metric for every glyph
+    let mut head = table(b"head")?;
+    let mut hhea = table(b"hhea")?;
+
+    if head.len() < 54 || hhea.len() < 36 {
+        return Err("head or hhea table is too short".to_string());
+    }
+
+    head[8..12].fill(0);
+    head[50..52].copy_from_slice(&1u16.to_be_bytes());
+    hhea[10..12].copy_from_slice(&advance_max.to_be_bytes());
+    hhea[34..36].copy_from_slice(&glyph_count.to_be_bytes());
+
+    let mut tables: Vec<([u8; 4], Vec<u8>)> = vec![
+        (*b"glyf", glyf),
+        (*b"loca", loca),
+        (*b"hmtx", hmtx),
+        (*b"head", head),
+        (*b"hhea", hhea),
+    ];
+
+    for record in raw.table_records {
+        let tag = record.tag.to_bytes();
+        let replaced = tables.iter().any(|(other, _)| *other == tag);
+
+        if !replaced
+            && !DROPPED_TABLES.contains(&&tag)
+            && let Some(data) = raw.table(record.tag)
+        {
+            tables.push((tag, data.to_vec()));
+        }
+    }
+
+    Ok(write_font(tables))
+}
+
+/// Assemble tables into a TrueType font file, with the directory sorted by tag and the
+/// checksums filled in.
+fn write_font(mut tables: Vec<([u8; 4], Vec<u8>)>) -> Vec<u8> {
+    tables.sort_by_key(|(tag, _)| *tag);
+
+    let count = tables.len() as u16;
+    let entry_selector = 15 - count.leading_zeros() as u16;
+    let search_range = (1 << entry_selector) * 16;
+
+    let mut font = Vec::new();
+    font.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+
+    for value in [
+        count,
+        search_range,
+        entry_selector,
+        count * 16 - search_range,
+    ] {
+        font.extend_from_slice(&value.to_be_bytes());
+    }
+
+    let mut offset = 12 + tables.len() * 16;
+
+    for (tag, data) in &tables {
+        font.extend_from_slice(tag);
+        font.extend_from_slice(&checksum(data).to_be_bytes());
+        font.extend_from_slice(&(offset as u32).to_be_bytes());
+        font.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        offset += data.len().next_multiple_of(4);
+    }
+
+    let mut head_offset = 0;
+
+    for (tag, data) in &tables {
+        if tag == b"head" {
+            head_offset = font.len();
+        }
+
+        font.extend_from_slice(data);
+        font.resize(font.len().next_multiple_of(4), 0);
+    }
+
+    let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&font));
+    font[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    font
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+/// A glyph's contours as TrueType points: on-curve points, and off-curve quadratic
+/// control points.
+#[derive(Default)]
+struct Outline {
+    contours: Vec<Vec<(f32, f32, bool)>>,
+}
+
+impl Outline {
+    fn push(&mut self, x: f32, y: f32, on_curve: bool) {
+        if let Some(contour) = self.contours.last_mut() {
+            contour.push((x, y, on_curve));
+        }
+    }
+
+    /// The contours' points rounded to font units, leaving out on-curve points halfway
+    /// between two control points, which TrueType implies. Rounding them instead would
+    /// bend the curves.
+    fn points(&self) -> Vec<Vec<(i16, i16, bool)>> {
+        self.contours
+            .iter()
+            .map(|contour| {
+                let count = contour.len();
+
+                (0..count)
+                    .filter(|&i| {
+                        let (x, y, on_curve) = contour[i];
+                        let (x0, y0, before) = contour[(i + count - 1) % count];
+                        let (x1, y1, after) = contour[(i + 1) % count];
+
+                        !(on_curve
+                            && !before
+                            && !after
+                            && count > 2
+                            && x == (x0 + x1) / 2.0
+                            && y == (y0 + y1) / 2.0)
+                    })
+                    .map(|i| {
+                        let (x, y, on_curve) = contour[i];
+                        (x.round() as i16, y.round() as i16, on_curve)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// x min, y min, x max and y max of the points, or `None` for an empty glyph.
+    fn bounds(&self) -> Option<(i16, i16, i16, i16)> {
+        let mut points = self.contours.iter().flatten();
+        let &(x, y, _) = points.next()?;
+        let (x, y) = (x.round() as i16, y.round() as i16);
+
+        Some(points.fold((x, y, x, y), |(x0, y0, x1, y1), &(x, y, _)| {
+            let (x, y) = (x.round() as i16, y.round() as i16);
+            (x0.min(x), y0.min(y), x1.max(x), y1.max(y))
+        }))
+    }
+
+    /// Append the glyph to a `glyf` table, padded to 4 bytes. Empty glyphs take no
+    /// space.
+    fn write(&self, glyf: &mut Vec<u8>) {
+        let Some((x_min, y_min, x_max, y_max)) = self.bounds() else {
+            return;
+        };
+
+        let contours = self.points();
+
+        for value in [contours.len() as i16, x_min, y_min, x_max, y_max] {
+            glyf.extend_from_slice(&value.to_be_bytes());
+        }
+
+        let mut end = 0;
+
+        for contour in &contours {
+            end += contour.len() as u16;
+            glyf.extend_from_slice(&(end - 1).to_be_bytes());
+        }
+
+        // No instructions
+        glyf.extend_from_slice(&0u16.to_be_bytes());
+
+        // Coordinates are deltas from the point before, in a byte when they fit
+        let mut flags = Vec::new();
+        let mut xs = Vec::new();
+        let mut ys = Vec::new();
+        let mut last = (0i16, 0i16);
+
+        for &(x, y, on_curve) in contours.iter().flatten() {
+            let mut flag = u8::from(on_curve);
+            flag |= delta(x.wrapping_sub(last.0), &mut xs) << 1;
+            flag |= delta(y.wrapping_sub(last.1), &mut ys) << 2;
+            flags.push(flag);
+            last = (x, y);
+        }
+
+        glyf.extend(flags);
+        glyf.extend(xs);
+        glyf.extend(ys);
+        glyf.resize(glyf.len().next_multiple_of(4), 0);
+    }
+}
+
+/// Write a coordinate delta, returning its flag bits shifted one right for x, or two
+/// right for y.
+fn delta(delta: i16, coordinates: &mut Vec<u8>) -> u8 {
+    match delta {
+        // Same as the point before
+        0 => 0x08,
+        // A byte, with the sign in the flag
+        -255..=255 => {
+            coordinates.push(delta.unsigned_abs() as u8);
+            if delta > 0 { 0x09 } else { 0x01 }
+        }
+        _ => {
+            coordinates.extend_from_slice(&delta.to_be_bytes());
+            0x00
+        }
+    }
+}
+
+impl OutlineBuilder for Outline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.contours.push(Vec::new());
+        self.push(x, y, true);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.push(x, y, true);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.push(x1, y1, false);
+        self.push(x, y, true);
+    }
+
+    /// Only CFF outlines have cubic curves, but approximate one with a quadratic curve
+    /// just in case
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (x0, y0) = self
+            .contours
+            .last()
+            .and_then(|contour| contour.last())
+            .map_or((x, y), |&(x0, y0, _)| (x0, y0));
+
+        self.quad_to(
+            (3.0 * (x1 + x2) - x0 - x) / 4.0,
+            (3.0 * (y1 + y2) - y0 - y) / 4.0,
+            x,
+            y,
+        );
+    }
+
+    /// Contours end back at their first point, which TrueType closes to implicitly.
+    fn close(&mut self) {
+        if let Some(contour) = self.contours.last_mut()
+            && contour.len() > 1
+            && contour.first() == contour.last()
+        {
+            contour.pop();
+        }
+    }
+}
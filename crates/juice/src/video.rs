@@ -0,0 +1,149 @@
+use serde::Deserialize;
+
+/// Pixel layouts accepted for host-supplied video frames.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PixelFormat {
+    /// Packed 8-bit R, G, B.
+    Rgb888,
+    /// Packed 8-bit R, G, B, A. Alpha is ignored; video is composited opaque.
+    Rgba8888,
+    /// Native-endian XRGB8888 words, as produced by DRM/KMS and most decoders.
+    Xrgb8888,
+    /// YUV 4:2:2 packed as Y0 U Y1 V — the default format of most V4L2 webcams.
+    Yuyv,
+}
+
+impl PixelFormat {
+    /// Bytes each pixel takes in a raw frame of this format.
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Rgb888 => 3,
+            PixelFormat::Rgba8888 | PixelFormat::Xrgb8888 => 4,
+            PixelFormat::Yuyv => 2,
+        }
+    }
+}
+
+/// How a video frame is scaled into its node's box.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ObjectFit {
+    /// Scale to fit entirely inside the box, preserving aspect ratio (letterboxed).
+    #[default]
+    Contain,
+    /// Scale to cover the whole box, preserving aspect ratio (cropped).
+    Cover,
+    /// Stretch to exactly fill the box.
+    Fill,
+}
+
+/// A single decoded frame, stored as XRGB8888 ready for blitting.
+pub struct VideoFrame {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u32>,
+}
+
+impl VideoFrame {
+    /// Convert a raw frame to XRGB8888. This is plain CPU work with no renderer state, so
+    /// hosts can call it on their capture/decode thread and hand the result over.
+    ///
+    /// Returns `None` if `data` is too short for the given dimensions.
+    pub fn new(width: u32, height: u32, format: PixelFormat, data: &[u8]) -> Option<Self> {
+        let count = (width as usize).checked_mul(height as usize)?;
+        // Bytes the frame takes at `size` bytes per pixel, `None` if that overflows
+        let bytes = |size: usize| count.checked_mul(size);
+
+        let pixels = match format {
+            PixelFormat::Rgb888 => data
+                .get(..bytes(3)?)?
+                .chunks_exact(3)
+                .map(|px| pack(px[0], px[1], px[2]))
+                .collect(),
+            PixelFormat::Rgba8888 => data
+                .get(..bytes(4)?)?
+                .chunks_exact(4)
+                .map(|px| pack(px[0], px[1], px[2]))
+                .collect(),
+            PixelFormat::Xrgb8888 => data
+                .get(..bytes(4)?)?
+                .chunks_exact(4)
+                .map(|px| 0xFF00_0000 | u32::from_ne_bytes([px[0], px[1], px[2], px[3]]))
+                .collect(),
+            PixelFormat::Yuyv => {
+                if !width.is_multiple_of(2) {
+                    return None;
+                }
+
+                let mut pixels = Vec::with_capacity(count);
+
+                for px in data.get(..bytes(2)?)?.chunks_exact(4) {
+                    let (y0, u, y1, v) = (px[0], px[1], px[2], px[3]);
+                    pixels.push(yuv_to_xrgb(y0, u, v));
+                    pixels.push(yuv_to_xrgb(y1, u, v));
+                }
+
+                pixels
+            }
+        };
+
+        Some(VideoFrame {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Whether `pixels` holds all `width` x `height` pixels, which frames built by hand
+    /// rather than with `new` might not.
+    pub fn is_complete(&self) -> bool {
+        (self.width as usize)
+            .checked_mul(self.height as usize)
+            .is_some_and(|count| self.pixels.len() >= count)
+    }
+}
+
+impl ObjectFit {
+    /// Compute the destination rect `(x, y, w, h)` for a `src_w` x `src_h` frame drawn into
+    /// a box. With `Cover` the rect overflows the box and must be clipped to it.
+    pub fn fit(
+        self,
+        src_w: u32,
+        src_h: u32,
+        box_x: f32,
+        box_y: f32,
+        box_w: f32,
+        box_h: f32,
+    ) -> (f32, f32, f32, f32) {
+        let sx = box_w / src_w as f32;
+        let sy = box_h / src_h as f32;
+
+        let scale = match self {
+            ObjectFit::Fill => return (box_x, box_y, box_w, box_h),
+            ObjectFit::Contain => sx.min(sy),
+            ObjectFit::Cover => sx.max(sy),
+        };
+
+        let w = src_w as f32 * scale;
+        let h = src_h as f32 * scale;
+        (box_x + (box_w - w) / 2.0, box_y + (box_h - h) / 2.0, w, h)
+    }
+}
+
+#[inline(always)]
+fn pack(r: u8, g: u8, b: u8) -> u32 {
+    0xFF00_0000 | (r as u32) << 16 | (g as u32) << 8 | b as u32
+}
+
+/// BT.601 limited-range YUV to RGB, in fixed point.
+#[inline(always)]
+fn yuv_to_xrgb(y: u8, u: u8, v: u8) -> u32 {
+    let c = (y as i32 - 16).max(0) * 298;
+    let d = u as i32 - 128;
+    let e = v as i32 - 128;
+
+    let r = ((c + 409 * e + 128) >> 8).clamp(0, 255) as u8;
+    let g = ((c - 100 * d - 208 * e + 128) >> 8).clamp(0, 255) as u8;
+    let b = ((c + 516 * d + 128) >> 8).clamp(0, 255) as u8;
+    pack(r, g, b)
+}
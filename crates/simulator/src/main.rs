@@ -6,9 +6,17 @@ use embedded_graphics_simulator::{
     OutputSettingsBuilder, SimulatorDisplay, SimulatorEvent, Window, sdl2::MouseButton,
 };
 use juice::canvas::{Canvas, RgbColor};
-use juice::inherited_style::{InheritedStyle, TextAlign};
+use juice::fs::Fs;
+use juice::inherited_style::{
+    Direction, FontStyle, FontVariations, FontWeight, InheritedStyle, LineHeight, TextAlign,
+    TextDecoration, TextOverflow,
+};
+use juice::log::{LogConfig, LogStore};
+use juice::pointer::{Pointer, PointerTool};
 use juice::renderer::Renderer;
+use juice_dev::DevRequest;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use crate::console::Console;
@@ -21,8 +29,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let canvas = Canvas::new(DISPLAY_WIDTH, DISPLAY_HEIGHT);
     let fonts = HashMap::new();
     let default_font = "Roboto-Regular";
+    let data_dir = std::env::var("JUICE_DATA_DIR").unwrap_or_else(|_| "data".to_string());
+    std::fs::create_dir_all(&data_dir)?;
+
+    let logs = LogStore::new(LogConfig::default());
 
-    let reload_rx = juice_dev::spawn_reload_listener();
+    let dev = juice_dev::DevChannel::connect();
 
     // create the juice renderer
     let mut renderer = Renderer::new(
@@ -32,17 +44,48 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             color: RgbColor::from_array([255, 255, 255]),
             font_name: default_font.to_string(),
             font_size: 24.0,
+            font_weight: FontWeight::NORMAL,
+            font_style: FontStyle::Normal,
+            font_variations: FontVariations::default(),
             text_align: TextAlign::Left,
+            line_height: LineHeight::Normal,
+            text_overflow: TextOverflow::Wrap,
+            max_lines: 0,
+            letter_spacing: 0.0,
+            tab_size: 8.0,
+            direction: Direction::Ltr,
+            text_decoration: TextDecoration::default(),
         },
-        vec![Box::new(Console {})],
+        vec![
+            Box::new(Console { logs: logs.clone() }),
+            Box::new(Fs::new(data_dir)),
+        ],
     )
     .await;
 
+    // Clicking the top-left corner five times opens the log viewer
+    renderer.set_log_store(Some(logs));
+
+    // and the top-right corner opens the diagnostics screen
+    renderer.enable_diagnostics(vec!["mouse: SDL window".to_string()]);
+
     println!("Created renderer");
 
-    let bundle = std::fs::read_to_string("dist/bundle.js").expect("Run 'npm run build' first");
+    // JUICE_BUNDLE=dist/gallery.js runs the example gallery instead of the app
+    let bundle_path =
+        std::env::var("JUICE_BUNDLE").unwrap_or_else(|_| "dist/bundle.js".to_string());
+    let bundle = std::fs::read_to_string(&bundle_path).expect("Run 'npm run build' first");
     renderer.engine.load(&bundle).await;
 
+    // Images with a path for their `src` are read from next to the bundle, or JUICE_ASSETS
+    let asset_dir = match std::env::var("JUICE_ASSETS") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => Path::new(&bundle_path)
+            .parent()
+            .map_or_else(|| ".".into(), Path::to_path_buf),
+    };
+    renderer.set_asset_dir(Some(asset_dir));
+
     let mut display = SimulatorDisplay::<Rgb888>::new(Size::new(DISPLAY_WIDTH, DISPLAY_HEIGHT));
 
     let output_settings = OutputSettingsBuilder::new().build();
@@ -50,6 +93,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut frame_interval = tokio::time::interval(Duration::from_millis(16));
 
+    // SDL wheel events carry no position, so track the pointer to route them
+    let mut mouse_position = Point::zero();
+    let mut mouse_down = false;
+
     // main event loop
 
     loop {
@@ -64,8 +111,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     point,
                     mouse_btn: MouseButton::Left,
                 } => {
+                    mouse_down = true;
+                    let pointer = Pointer::new(PointerTool::Mouse, 0.5);
                     renderer
-                        .dispatch_xy_event("PressIn", point.x as f32, point.y as f32)
+                        .dispatch_pointer_event("PressIn", point.x as f32, point.y as f32, pointer)
                         .await;
                 }
 
@@ -73,8 +122,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     point,
                     mouse_btn: MouseButton::Left,
                 } => {
+                    mouse_down = false;
+                    let pointer = Pointer::new(PointerTool::Mouse, 0.0);
                     renderer
-                        .dispatch_xy_event("PressOut", point.x as f32, point.y as f32)
+                        .dispatch_pointer_event("PressOut", point.x as f32, point.y as f32, pointer)
+                        .await;
+                }
+
+                SimulatorEvent::MouseMove { point } => {
+                    mouse_position = point;
+
+                    // Dragging with the left button held scrolls and drags like a touch
+                    if mouse_down {
+                        let pointer = Pointer::new(PointerTool::Mouse, 0.5);
+                        renderer
+                            .dispatch_pointer_event(
+                                "PressMove",
+                                mouse_position.x as f32,
+                                mouse_position.y as f32,
+                                pointer,
+                            )
+                            .await;
+                    }
+                }
+
+                SimulatorEvent::MouseWheel { scroll_delta, .. } => {
+                    renderer
+                        .dispatch_wheel(
+                            mouse_position.x as f32,
+                            mouse_position.y as f32,
+                            scroll_delta.x as f32,
+                            scroll_delta.y as f32,
+                        )
                         .await;
                 }
 
@@ -88,9 +167,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             renderer.flush(&mut display);
         }
 
-        if let Ok(new_bundle) = reload_rx.try_recv() {
+        if let Some(new_bundle) = dev.try_recv_bundle() {
             println!("[dev] reloading bundle...");
             renderer.reload(&new_bundle).await;
         }
+
+        while let Some(request) = dev.try_recv_request() {
+            match request {
+                DevRequest::Animations => dev.reply_animations(&renderer.inspect_animations()),
+            }
+        }
     }
 }
@@ -2,3 +2,6 @@ import { render } from "@juice/core";
 import { App } from "./app.js";
 
 render(<App />);
+
+// Fades out the host's splash screen once the icons are decoded
+renderer.ready();
@@ -0,0 +1,104 @@
+import { Box } from "@juice/core";
+import type { FunctionComponent } from "preact";
+import { useState } from "preact/hooks";
+import { AnimationPage } from "./pages/AnimationPage.js";
+import { ButtonsPage } from "./pages/ButtonsPage.js";
+import { ChartsPage } from "./pages/ChartsPage.js";
+import { InputsPage } from "./pages/InputsPage.js";
+import { ListsPage } from "./pages/ListsPage.js";
+import { MarkdownPage } from "./pages/MarkdownPage.js";
+import { MediaPage } from "./pages/MediaPage.js";
+import { TextPage } from "./pages/TextPage.js";
+
+export interface Page {
+  /** Shown on the tab, and the page's snapshot name. */
+  name: string;
+  component: FunctionComponent;
+}
+
+export const pages: Page[] = [
+  { name: "text", component: TextPage },
+  { name: "buttons", component: ButtonsPage },
+  { name: "lists", component: ListsPage },
+  { name: "charts", component: ChartsPage },
+  { name: "inputs", component: InputsPage },
+  { name: "media", component: MediaPage },
+  { name: "animation", component: AnimationPage },
+  { name: "markdown", component: MarkdownPage },
+];
+
+let setPageFromOutside: ((name: string) => void) | undefined;
+
+/** Switch to the page called `name`. Returns false if there isn't one. */
+export function showPage(name: string): boolean {
+  if (!setPageFromOutside || !pages.some((page) => page.name === name)) {
+    return false;
+  }
+
+  setPageFromOutside(name);
+  return true;
+}
+
+export function Gallery() {
+  const [current, setCurrent] = useState(pages[0].name);
+  setPageFromOutside = setCurrent;
+
+  const Page = pages.find((page) => page.name === current)?.component;
+
+  return (
+    <Box
+      style={{
+        display: "flex",
+        flexDirection: "column",
+        width: "100%",
+        height: "100%",
+        background: "#101418",
+        color: "#e8eaed",
+        font: "Roboto-Regular",
+        fontSize: 16,
+      }}
+    >
+      <Box
+        style={{
+          display: "flex",
+          flexDirection: "row",
+          overflowX: "scroll",
+          background: "#1b2128",
+          flexShrink: 0,
+        }}
+      >
+        {pages.map((page) => (
+          <Box
+            key={page.name}
+            onPress={() => setCurrent(page.name)}
+            pressEffect="highlight"
+            accessibilityRole="tab"
+            style={{
+              paddingX: 16,
+              paddingY: 12,
+              flexShrink: 0,
+              borderBottomWidth: 3,
+              borderColor: page.name === current ? "#8ab4f8" : "#1b2128",
+              color: page.name === current ? "#ffffff" : "#9aa0a6",
+            }}
+          >
+            {page.name}
+          </Box>
+        ))}
+      </Box>
+
+      <Box
+        style={{
+          display: "flex",
+          flexDirection: "column",
+          flex: 1,
+          overflowY: "scroll",
+          padding: 16,
+          gap: 20,
+        }}
+      >
+        {Page && <Page />}
+      </Box>
+    </Box>
+  );
+}
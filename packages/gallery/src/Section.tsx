@@ -0,0 +1,36 @@
+import { Box } from "@juice/core";
+import type { ComponentChildren } from "preact";
+
+export interface SectionProps {
+  title: string;
+  children?: ComponentChildren;
+}
+
+/** A titled group of examples on a page. */
+export function Section({ title, children }: SectionProps) {
+  return (
+    <Box style={{ display: "flex", flexDirection: "column", gap: 8 }}>
+      <Box style={{ font: "Roboto-Bold", fontSize: 18, color: "#8ab4f8" }}>
+        {title}
+      </Box>
+      {children}
+    </Box>
+  );
+}
+
+/** Examples side by side, wrapping onto more rows when they don't fit. */
+export function Row({ children }: { children?: ComponentChildren }) {
+  return (
+    <Box
+      style={{
+        display: "flex",
+        flexDirection: "row",
+        flexWrap: "wrap",
+        alignItems: "center",
+        gap: 12,
+      }}
+    >
+      {children}
+    </Box>
+  );
+}
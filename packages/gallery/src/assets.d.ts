@@ -0,0 +1,9 @@
+declare module "*.png" {
+  const src: string;
+  export default src;
+}
+
+declare module "*.ttf" {
+  const src: string;
+  export default src;
+}
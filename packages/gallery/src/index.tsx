@@ -0,0 +1,20 @@
+import { registerEasing, render } from "@juice/core";
+import robotoBold from "../../app/src/fonts/Roboto-Bold.ttf";
+import robotoLight from "../../app/src/fonts/Roboto-Light.ttf";
+import robotoRegular from "../../app/src/fonts/Roboto-Regular.ttf";
+import { Gallery, pages, showPage } from "./Gallery.js";
+
+renderer.addFont("Roboto-Regular", robotoRegular);
+renderer.addFont("Roboto-Bold", robotoBold);
+renderer.addFont("Roboto-Light", robotoLight);
+
+registerEasing("snappy", 0.2, 0.9, 0.1, 1);
+
+// For the snapshot runner in crates/gallery, which shows each page in turn
+(globalThis as any).gallery = {
+  pages: pages.map((page) => page.name),
+  show: showPage,
+};
+
+render(<Gallery />);
+renderer.ready();
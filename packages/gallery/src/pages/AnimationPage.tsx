@@ -0,0 +1,73 @@
+import { Box, type JuiceAnimation } from "@juice/core";
+import { Section } from "../Section.js";
+
+// "snappy" is registered in index.tsx
+const easings = ["linear", "ease-in-out", "steps(4)", "snappy"];
+
+function slide(easing: string): JuiceAnimation {
+  return {
+    keyframes: [{ translateX: 0 }, { translateX: 240 }],
+    duration: 1500,
+    iterations: "infinite",
+    direction: "alternate",
+    easing,
+  };
+}
+
+export function AnimationPage() {
+  return (
+    <>
+      <Section title="Easings">
+        {easings.map((easing) => (
+          <Box
+            key={easing}
+            style={{ display: "flex", flexDirection: "row", alignItems: "center" }}
+          >
+            <Box style={{ width: 120, color: "#9aa0a6" }}>{easing}</Box>
+            <Box
+              animation={slide(easing)}
+              style={{
+                width: 24,
+                height: 24,
+                borderRadius: 12,
+                background: "#8ab4f8",
+              }}
+            />
+          </Box>
+        ))}
+      </Section>
+
+      <Section title="Opacity, scale and color">
+        <Box style={{ display: "flex", flexDirection: "row", gap: 24 }}>
+          <Box
+            animation={{
+              keyframes: [{ opacity: 1 }, { opacity: 0.2 }],
+              duration: 1000,
+              iterations: "infinite",
+              direction: "alternate",
+            }}
+            style={{ width: 64, height: 64, background: "#f28b82" }}
+          />
+          <Box
+            animation={{
+              keyframes: [{ scale: 1 }, { scale: 1.4, offset: 0.3 }, { scale: 1 }],
+              duration: 1200,
+              iterations: "infinite",
+              easing: "ease",
+            }}
+            style={{ width: 64, height: 64, background: "#fdd663" }}
+          />
+          <Box
+            animation={{
+              keyframes: [{ background: "#81c995" }, { background: "#5f4b8b" }],
+              duration: 2000,
+              iterations: "infinite",
+              direction: "alternate",
+            }}
+            style={{ width: 64, height: 64 }}
+          />
+        </Box>
+      </Section>
+    </>
+  );
+}
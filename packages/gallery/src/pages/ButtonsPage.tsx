@@ -0,0 +1,110 @@
+import { Box, type BoxProps } from "@juice/core";
+import { useState } from "preact/hooks";
+import { Row, Section } from "../Section.js";
+
+function Button(props: BoxProps) {
+  return (
+    <Box
+      accessibilityRole="button"
+      {...props}
+      style={{
+        paddingX: 20,
+        paddingY: 12,
+        borderRadius: 8,
+        background: "#3c4a5c",
+        ...props.style,
+      }}
+    />
+  );
+}
+
+export function ButtonsPage() {
+  const [count, setCount] = useState(0);
+  const [lastEvent, setLastEvent] = useState("none yet");
+
+  return (
+    <>
+      <Section title="Press effects">
+        <Row>
+          <Button pressEffect="scale" onPress={() => setCount(count + 1)}>
+            Scale
+          </Button>
+          <Button pressEffect="highlight" onPress={() => setCount(count + 1)}>
+            Highlight
+          </Button>
+          <Button pressEffect="ripple" onPress={() => setCount(count + 1)}>
+            Ripple
+          </Button>
+        </Row>
+        <Box>Pressed {count} times</Box>
+      </Section>
+
+      <Section title="State styles">
+        <Row>
+          <Button
+            pressedStyle={{ background: "#8ab4f8", color: "#101418" }}
+            onPress={() => {}}
+          >
+            pressedStyle
+          </Button>
+          <Button
+            disabled
+            disabledStyle={{ background: "#2a2f36", color: "#5f6368" }}
+          >
+            Disabled
+          </Button>
+        </Row>
+      </Section>
+
+      <Section title="Events">
+        <Button
+          onPressIn={() => setLastEvent("PressIn")}
+          onPressOut={() => setLastEvent("PressOut")}
+          onPressMove={(e) =>
+            setLastEvent(
+              `PressMove ${Math.round(e.details.x)}, ${Math.round(e.details.y)}`,
+            )
+          }
+          style={{ height: 80, justifyContent: "center", alignItems: "center" }}
+        >
+          Press and drag here
+        </Button>
+        <Box style={{ color: "#9aa0a6" }}>Last event: {lastEvent}</Box>
+      </Section>
+
+      <Section title="Borders, radii and shadows">
+        <Row>
+          <Box
+            style={{ width: 80, height: 60, borderWidth: 2, borderColor: "#8ab4f8" }}
+          />
+          <Box
+            style={{
+              width: 80,
+              height: 60,
+              borderRadius: 30,
+              background: "#f28b82",
+            }}
+          />
+          <Box
+            style={{
+              width: 80,
+              height: 60,
+              borderRadius: 8,
+              background: "#fdd663",
+              boxShadow: "0 4px 12px rgba(0, 0, 0, 0.6)",
+            }}
+          />
+          <Box
+            style={{
+              width: 80,
+              height: 60,
+              background: "#81c995",
+              transform: "rotate(10deg)",
+              opacity: 0.7,
+            }}
+          />
+        </Row>
+      </Section>
+    </>
+  );
+}
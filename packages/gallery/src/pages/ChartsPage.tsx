@@ -0,0 +1,67 @@
+import { Box, Chart, Gauge, Table } from "@juice/core";
+import { Row, Section } from "../Section.js";
+
+// Fixed data, so snapshots don't change between runs
+const readings = [12, 18, 15, 22, 30, 26, 34, 31, 38, 36, 42, 40];
+
+export function ChartsPage() {
+  return (
+    <>
+      <Section title="Charts">
+        <Row>
+          {(["line", "area", "bar"] as const).map((type) => (
+            <Box key={type} style={{ display: "flex", flexDirection: "column" }}>
+              <Chart
+                type={type}
+                data={readings}
+                min={0}
+                max={50}
+                ticks={type === "line" ? 5 : undefined}
+                fillColor="#8ab4f8"
+                style={{ width: 220, height: 120, color: "#8ab4f8" }}
+              />
+              <Box style={{ color: "#9aa0a6", fontSize: 12 }}>{type}</Box>
+            </Box>
+          ))}
+        </Row>
+      </Section>
+
+      <Section title="Gauges">
+        <Row>
+          <Gauge value={72} style={{ width: 160, color: "#e8eaed" }} />
+          <Gauge
+            value={3400}
+            min={0}
+            max={8000}
+            ticks={8}
+            minorTicks={4}
+            ranges={[{ from: 6000, to: 8000, color: "#f28b82" }]}
+            style={{ width: 160, color: "#e8eaed" }}
+          />
+        </Row>
+      </Section>
+
+      <Section title="Table">
+        <Table
+          columns={[
+            { title: "Sensor" },
+            { title: "Reading", width: 100, align: "right" },
+            { title: "Status", width: "25%", align: "center" },
+          ]}
+          rows={[
+            ["Temperature", "21.5 °C", "ok"],
+            ["Humidity", "48 %", "ok"],
+            [
+              "Pressure",
+              "982 hPa",
+              { text: "low", color: "#101418", background: "#fdd663" },
+            ],
+            ["CO₂", "640 ppm", "ok"],
+          ]}
+          headerBackground="#3c4a5c"
+          stripeColor="#1b2128"
+        />
+      </Section>
+    </>
+  );
+}
@@ -0,0 +1,47 @@
+import { Box, DatePicker, Input, TimePicker, WheelPicker } from "@juice/core";
+import { useState } from "preact/hooks";
+import { Row, Section } from "../Section.js";
+
+const inputStyle = {
+  width: 300,
+  padding: 10,
+  borderRadius: 6,
+  borderWidth: 1,
+  borderColor: "#5f6368",
+  background: "#1b2128",
+};
+
+export function InputsPage() {
+  const [name, setName] = useState("");
+  const [picked, setPicked] = useState("Medium");
+
+  return (
+    <>
+      <Section title="Text inputs">
+        <Input
+          placeholder="Your name"
+          value={name}
+          onInput={(e) => setName(e.details.value)}
+          focusedStyle={{ borderColor: "#8ab4f8" }}
+          style={inputStyle}
+        />
+        <Input placeholder="PIN" inputMode="pin" secureTextEntry style={inputStyle} />
+        <Box style={{ color: "#9aa0a6" }}>Hello, {name || "stranger"}</Box>
+      </Section>
+
+      <Section title="Pickers">
+        <Row>
+          <WheelPicker
+            items={["Small", "Medium", "Large", "Extra large"]}
+            value={picked}
+            onChange={(e) => setPicked(e.details.value)}
+            style={{ width: 160, height: 150 }}
+          />
+          <DatePicker value="2024-02-29" style={{ width: 260, height: 150 }} />
+          <TimePicker value="09:30" minuteStep={15} style={{ width: 140, height: 150 }} />
+        </Row>
+        <Box style={{ color: "#9aa0a6" }}>Size: {picked}</Box>
+      </Section>
+    </>
+  );
+}
@@ -0,0 +1,90 @@
+import { Box } from "@juice/core";
+import { useState } from "preact/hooks";
+import { Section } from "../Section.js";
+
+const initialItems = Array.from({ length: 12 }, (_, i) => `Message ${i + 1}`);
+
+export function ListsPage() {
+  const [items, setItems] = useState(initialItems);
+  const [refreshes, setRefreshes] = useState(0);
+
+  return (
+    <>
+      <Section title="Pull to refresh, swipe to delete">
+        <Box
+          refreshable
+          onRefresh={(e) => {
+            setRefreshes(refreshes + 1);
+            setTimeout(() => e.details.complete(), 500);
+          }}
+          style={{
+            display: "flex",
+            flexDirection: "column",
+            height: 300,
+            overflowY: "scroll",
+            borderRadius: 8,
+            background: "#1b2128",
+          }}
+        >
+          {items.map((item, i) => (
+            <Box
+              key={item}
+              swipeLeft={96}
+              onSwipeAction={(e) => {
+                if (e.details.direction === "left") {
+                  setItems(items.filter((other) => other !== item));
+                }
+              }}
+              style={{
+                paddingX: 16,
+                paddingY: 14,
+                flexShrink: 0,
+                background: i % 2 ? "#1b2128" : "#222a33",
+              }}
+            >
+              {item}
+            </Box>
+          ))}
+        </Box>
+        <Box style={{ color: "#9aa0a6" }}>
+          {items.length} messages, refreshed {refreshes} times
+        </Box>
+      </Section>
+
+      <Section title="Grid">
+        <Box
+          style={{
+            display: "grid",
+            gridTemplateColumns: "1fr 2fr 1fr",
+            gap: 8,
+          }}
+        >
+          {["A", "B", "C", "D", "E", "F"].map((cell) => (
+            <Box
+              key={cell}
+              style={{
+                padding: 12,
+                textAlign: "center",
+                borderRadius: 4,
+                background: "#3c4a5c",
+              }}
+            >
+              {cell}
+            </Box>
+          ))}
+          <Box
+            style={{
+              gridColumn: "1 / 4",
+              padding: 12,
+              textAlign: "center",
+              borderRadius: 4,
+              background: "#5f4b8b",
+            }}
+          >
+            Spanning all three columns
+          </Box>
+        </Box>
+      </Section>
+    </>
+  );
+}
@@ -0,0 +1,38 @@
+import { Markdown } from "@juice/core";
+import { useState } from "preact/hooks";
+import { Section } from "../Section.js";
+
+const notes = `# Release notes
+
+Version **2.4** brings *faster* scrolling and a new \`<chart>\` element.
+
+- Charts
+  - line, area and bar
+- Gauges with colored ranges
+1. Update the firmware
+2. Restart the device
+
+See [the changelog](https://example.com/changelog) for everything else.
+
+\`\`\`
+juice --version
+\`\`\`
+`;
+
+export function MarkdownPage() {
+  const [link, setLink] = useState("");
+
+  return (
+    <Section title="Markdown">
+      <Markdown
+        source={notes}
+        boldFont="Roboto-Bold"
+        codeFont="Roboto-Light"
+        linkColor="#8ab4f8"
+        onLinkPress={(e) => setLink(e.details.href)}
+        style={{ width: "100%" }}
+      />
+      {link && <Markdown source={`Pressed *${link}*`} />}
+    </Section>
+  );
+}
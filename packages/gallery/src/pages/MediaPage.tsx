@@ -0,0 +1,92 @@
+import { AnimatedSprite, Box, Lottie, Skeleton } from "@juice/core";
+import testImage from "../../../app/src/testimg.png";
+import { Row, Section } from "../Section.js";
+
+const star = `<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24">
+  <path fill="#fdd663" d="M12 2l3 7h7l-5.5 4.5 2 7.5-6.5-4.5-6.5 4.5 2-7.5L2 9h7z"/>
+</svg>`;
+
+// A dot growing and fading, in the subset of Lottie the renderer plays
+const pulse = {
+  v: "5.7.0",
+  fr: 30,
+  ip: 0,
+  op: 30,
+  w: 100,
+  h: 100,
+  layers: [
+    {
+      ty: 4,
+      ks: {
+        o: {
+          a: 1,
+          k: [
+            { t: 0, s: [100] },
+            { t: 30, s: [0] },
+          ],
+        },
+        p: { a: 0, k: [50, 50] },
+        s: {
+          a: 1,
+          k: [
+            { t: 0, s: [20, 20] },
+            { t: 30, s: [100, 100] },
+          ],
+        },
+      },
+      shapes: [
+        { ty: "el", s: { a: 0, k: [80, 80] }, p: { a: 0, k: [0, 0] } },
+        { ty: "fl", c: { a: 0, k: [0.54, 0.71, 0.97, 1] } },
+      ],
+    },
+  ],
+};
+
+export function MediaPage() {
+  const hasLottie = renderer.capabilities?.features.includes("lottie") ?? false;
+
+  return (
+    <>
+      <Section title="Images">
+        <Row>
+          {(["contain", "cover", "fill"] as const).map((objectFit) => (
+            <Box key={objectFit} style={{ display: "flex", flexDirection: "column" }}>
+              <img
+                src={testImage}
+                style={{ width: 160, height: 100, objectFit, background: "#1b2128" }}
+              />
+              <Box style={{ color: "#9aa0a6", fontSize: 12 }}>{objectFit}</Box>
+            </Box>
+          ))}
+        </Row>
+      </Section>
+
+      <Section title="SVG">
+        <Row>
+          {[24, 48, 96].map((size) => (
+            <svg key={size} markup={star} style={{ width: size, height: size }} />
+          ))}
+        </Row>
+      </Section>
+
+      <Section title="Sprites, Lottie and skeletons">
+        <Row>
+          <AnimatedSprite
+            src={testImage}
+            frameWidth={256}
+            frameHeight={256}
+            fps={2}
+            style={{ width: 96, height: 96 }}
+          />
+          {hasLottie && (
+            <Lottie animationData={pulse} loop style={{ width: 96, height: 96 }} />
+          )}
+          <Box style={{ display: "flex", flexDirection: "column", gap: 8 }}>
+            <Skeleton style={{ width: 200, height: 16, borderRadius: 4 }} />
+            <Skeleton style={{ width: 140, height: 16, borderRadius: 4 }} />
+          </Box>
+        </Row>
+      </Section>
+    </>
+  );
+}
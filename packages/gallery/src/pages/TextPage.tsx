@@ -0,0 +1,54 @@
+import { Box } from "@juice/core";
+import { Row, Section } from "../Section.js";
+
+const sample = "The quick brown fox jumps over the lazy dog.";
+
+export function TextPage() {
+  return (
+    <>
+      <Section title="Fonts and sizes">
+        <Box style={{ font: "Roboto-Light", fontSize: 32 }}>Light 32</Box>
+        <Box style={{ fontSize: 24 }}>Regular 24</Box>
+        <Box style={{ font: "Roboto-Bold", fontSize: 16 }}>Bold 16</Box>
+        <Box style={{ fontSize: 12, color: "#9aa0a6" }}>Regular 12, grey</Box>
+      </Section>
+
+      <Section title="Alignment and wrapping">
+        {(["left", "center", "right"] as const).map((textAlign) => (
+          <Box
+            key={textAlign}
+            style={{ textAlign, background: "#1b2128", padding: 6 }}
+          >
+            {textAlign}: {sample}
+          </Box>
+        ))}
+        <Box
+          style={{
+            width: 240,
+            textOverflow: "ellipsis",
+            background: "#1b2128",
+            padding: 6,
+          }}
+        >
+          Ellipsis: {sample}
+        </Box>
+        <Box style={{ width: 320, lineHeight: 1.6 }}>
+          Line height 1.6: {sample} {sample}
+        </Box>
+      </Section>
+
+      <Section title="Spacing, decoration and direction">
+        <Row>
+          <Box style={{ letterSpacing: 4 }}>Spaced out</Box>
+          <Box style={{ textDecoration: "underline" }}>Underlined</Box>
+          <Box style={{ textDecoration: "line-through", color: "#9aa0a6" }}>
+            Struck through
+          </Box>
+        </Row>
+        <Box style={{ direction: "rtl", background: "#1b2128", padding: 6 }}>
+          Right to left: שלום עולם (hello world)
+        </Box>
+      </Section>
+    </>
+  );
+}
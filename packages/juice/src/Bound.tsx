@@ -0,0 +1,29 @@
+import type { JuiceElementProps } from "./JuiceElement.js";
+
+export interface BoundProps extends Omit<JuiceElementProps, "children"> {
+  /** Key of the value to show, as set by the host with `Renderer::set_binding`. */
+  bind: string;
+  /**
+   * How to show the value, evaluated natively: text with `{{value}}` placeholders, each
+   * optionally piped through `round:places`, `scale:factor`, `pad:width:fill`,
+   * `padEnd:width:fill`, `upper`, `lower` or `default:text`, e.g.
+   * `"{{value | round:1}} °C"`.
+   */
+  format?: string;
+}
+
+declare module "preact" {
+  namespace JSX {
+    interface IntrinsicElements {
+      bound: BoundProps;
+    }
+  }
+}
+
+/**
+ * Text showing a value the host updates natively, like a sensor reading. Updates skip
+ * JS and re-measure only this node, so readouts can change many times a second.
+ */
+export function Bound(props: BoundProps) {
+  return <bound {...props} />;
+}
@@ -1,6 +1,31 @@
 import type { JuiceElementProps } from "./JuiceElement.js";
 
-export interface BoxProps extends JuiceElementProps {}
+export interface BoxProps extends JuiceElementProps {
+  /**
+   * Pulling a scrollable box down past its top shows a spinner and fires `onRefresh`.
+   * The spinner stays until the handler calls `event.details.complete()`.
+   */
+  refreshable?: boolean;
+  /** How far the box can be swiped left, revealing whatever is drawn behind it. */
+  swipeLeft?: number;
+  /** How far the box can be swiped right. */
+  swipeRight?: number;
+  /**
+   * How e-paper panels refresh changes within the box: `fast` for small, frequently
+   * updated content like clock digits, `full` to clear ghosting whenever it changes.
+   */
+  refreshMode?: "fast" | "partial" | "full";
+  /**
+   * Feedback animated natively while the box is pressed, so it responds at once even
+   * if the JS handler is slow: shrink slightly, tint, or a ripple from the touch point.
+   */
+  pressEffect?: "scale" | "highlight" | "ripple";
+  /**
+   * Draw the box and everything in it once, then reuse the pixels until something
+   * inside changes. For complex static content like headers; give it a background.
+   */
+  cache?: boolean;
+}
 
 declare module "preact" {
   namespace JSX {
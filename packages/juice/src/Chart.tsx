@@ -0,0 +1,20 @@
+import type { JuiceChartElementProps } from "./JuiceChartElement.js";
+
+export type ChartProps = JuiceChartElementProps;
+
+declare module "preact" {
+  namespace JSX {
+    interface IntrinsicElements {
+      chart: ChartProps;
+    }
+  }
+}
+
+/**
+ * Plots `data` as a line, filled area or bars, with an optional value axis. Without
+ * `ticks` it's a bare sparkline. To stream readings, render a `<chart>` with a ref and
+ * call `appendData` on the element.
+ */
+export function Chart(props: ChartProps) {
+  return <chart {...props} />;
+}
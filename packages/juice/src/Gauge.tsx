@@ -0,0 +1,19 @@
+import type { JuiceGaugeElementProps } from "./JuiceGaugeElement.js";
+
+export type GaugeProps = JuiceGaugeElementProps;
+
+declare module "preact" {
+  namespace JSX {
+    interface IntrinsicElements {
+      gauge: GaugeProps;
+    }
+  }
+}
+
+/**
+ * A dial with ticks along an arc and a needle pointing at `value`, which sweeps natively
+ * to each new value. Square by default; `color` draws the ticks, labels and needle.
+ */
+export function Gauge(props: GaugeProps) {
+  return <gauge {...props} />;
+}
@@ -0,0 +1,21 @@
+import type { JuiceImgElementProps } from "./JuiceImgElement.js";
+
+export type ImageProps = JuiceImgElementProps;
+
+declare module "preact" {
+  namespace JSX {
+    interface IntrinsicElements {
+      img: ImageProps;
+      image: ImageProps;
+    }
+  }
+}
+
+/**
+ * Shows an image scaled to the node's size. PNG, JPEG, GIF and WebP are decoded in the
+ * background the first time it's on screen, and shared by every image with the same
+ * `src`; nothing is drawn until then.
+ */
+export function Image(props: ImageProps) {
+  return <img {...props} />;
+}
@@ -0,0 +1,19 @@
+import type { JuiceInputElementProps } from "./JuiceInputElement.js";
+
+export type InputProps = JuiceInputElementProps;
+
+declare module "preact" {
+  namespace JSX {
+    interface IntrinsicElements {
+      input: InputProps;
+    }
+  }
+}
+
+/**
+ * A single-line text field. Tapping it focuses it; the host then delivers typed text
+ * and IME compositions, firing `onInput` with the new value.
+ */
+export function Input(props: InputProps) {
+  return <input {...props} />;
+}
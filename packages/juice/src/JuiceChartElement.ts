@@ -0,0 +1,48 @@
+import type { JuiceElementProps } from "./JuiceElement.js";
+import JuiceLayoutElement from "./JuiceLayoutElement.js";
+
+export interface JuiceChartElementProps extends JuiceElementProps {
+  /** The values to plot, oldest first. */
+  data?: number[];
+  /** `line` (the default), `area` or `bar`. */
+  type?: "line" | "area" | "bar";
+  /**
+   * Values to keep, dropping the oldest as more are appended. Points are spaced for this
+   * many, so a stream fills the chart from the right and scrolls left.
+   */
+  capacity?: number;
+  /** Ends of the value axis, each defaulting to the lowest or highest value shown. */
+  min?: number;
+  max?: number;
+  /** Labelled intervals on a value axis down the left. 0, the default, draws no axis. */
+  ticks?: number;
+  /** Width of the line in pixels, 2 by default. */
+  lineWidth?: number;
+  /** Color of bars and the area under the line, `color` by default. */
+  fillColor?: string;
+}
+
+/**
+ * A `chart` node. Streams of readings can be appended natively with `appendData`, without
+ * resending the values already plotted.
+ */
+export class JuiceChartElement extends JuiceLayoutElement<JuiceChartElementProps> {
+  constructor() {
+    super("chart");
+  }
+
+  setAttribute(key: string, value: unknown): void {
+    // the bridge only carries strings and numbers
+    if (key === "data" && Array.isArray(value)) {
+      super.setAttribute(key, JSON.stringify(value));
+      (this.props as Record<string, unknown>)[key] = value;
+    } else {
+      super.setAttribute(key, value);
+    }
+  }
+
+  /** Add values to the end of the chart's data and redraw it. */
+  appendData(...values: number[]) {
+    renderer.appendChartData(this.nodeId, values);
+  }
+}
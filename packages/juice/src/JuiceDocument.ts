@@ -1,11 +1,27 @@
+import { JuiceChartElement } from "./JuiceChartElement.js";
 import { JuiceElement } from "./JuiceElement.js";
 import { PressEvent } from "./JuiceEvent.js";
+import { JuiceGaugeElement } from "./JuiceGaugeElement.js";
 import { JuiceImgElement } from "./JuiceImgElement.js";
+import { JuiceInputElement } from "./JuiceInputElement.js";
+import { JuiceLottieElement } from "./JuiceLottieElement.js";
+import { JuiceWheelPickerElement } from "./JuiceWheelPickerElement.js";
 import JuiceLayoutElement from "./JuiceLayoutElement.js";
 import type { JuiceNode } from "./JuiceNode.js";
 import { JuiceSvgElement } from "./JuiceSvgElement.js";
+import { JuiceTableElement } from "./JuiceTableElement.js";
 import { JuiceTextNode } from "./JuiceTextNode.js";
 
+/** Thrown by the `dom` global, e.g. when a change would take the tree past its limits. */
+export interface DomError {
+  message: string;
+  /** The limit that was hit, when the change was refused for the tree's size. */
+  limit?: "maxNodes" | "maxDepth" | "maxTextLength" | "maxSvgSize";
+  max?: number;
+  /** What the change would have made it. */
+  actual?: number;
+}
+
 export class JuiceDocument extends JuiceLayoutElement {
   constructor() {
     super("document");
@@ -22,13 +38,18 @@ export class JuiceDocument extends JuiceLayoutElement {
           new PressEvent(
             "Press",
             pressedNode,
-            event.details as { x: number; y: number },
+            event.details,
           ),
         );
       }
 
       pressedNode = undefined;
     });
+
+    // a press that turned into a scroll drag shouldn't become a Press
+    this.addEventListener("PressCancel", () => {
+      pressedNode = undefined;
+    });
   }
 
   createElement(tag: string): JuiceElement {
@@ -42,8 +63,20 @@ export class JuiceDocument extends JuiceLayoutElement {
       } else {
         return new JuiceElement(tagName);
       }
-    } else if (tagName === "img") {
-      return new JuiceImgElement();
+    } else if (tagName === "img" || tagName === "image") {
+      return new JuiceImgElement(tagName);
+    } else if (tagName === "input") {
+      return new JuiceInputElement();
+    } else if (tagName === "wheelpicker") {
+      return new JuiceWheelPickerElement();
+    } else if (tagName === "chart") {
+      return new JuiceChartElement();
+    } else if (tagName === "table") {
+      return new JuiceTableElement();
+    } else if (tagName === "gauge") {
+      return new JuiceGaugeElement();
+    } else if (tagName === "lottie") {
+      return new JuiceLottieElement();
     } else {
       return new JuiceLayoutElement(tagName);
     }
@@ -1,40 +1,199 @@
 import type { ComponentChildren } from "preact";
 import type { UIEventListener, UIEventMap } from "./JuiceEvent.js";
 import { JuiceNode } from "./JuiceNode.js";
+import type { JuiceStyleSchema } from "./schema.js";
 
 export type JuiceElementProps = {
   [K in keyof UIEventMap as `on${Capitalize<K>}`]?: UIEventListener<K>;
 } & {
   style?: JuiceElementStyle;
+  /**
+   * Keyframe animation run natively, so it keeps time without re-rendering. Changing
+   * the animation restarts it; passing an equal one again doesn't.
+   */
+  animation?: JuiceAnimation;
+  /**
+   * Applied natively while the element or something inside it is pressed, without a
+   * re-render.
+   */
+  pressedStyle?: JuiceStateStyle;
+  /** Applied natively while the element is or contains the focused input. */
+  focusedStyle?: JuiceStateStyle;
+  /** Applied while `disabled` is set, overriding `pressedStyle` and `focusedStyle`. */
+  disabledStyle?: JuiceStateStyle;
+  disabled?: boolean;
+  /** Spoken by screen readers instead of the text inside the element. */
+  accessibilityLabel?: string;
+  /** What kind of control the element is, spoken after its label, e.g. `"button"`. */
+  accessibilityRole?: string;
+  /**
+   * Announce the element whenever the text inside it changes: `polite` waits for speech
+   * in progress, `assertive` interrupts it.
+   */
+  accessibilityLive?: "off" | "polite" | "assertive";
+  /**
+   * Most lines the text inside wraps to, like React Native's `numberOfLines`: the same
+   * as the `maxLines` style.
+   */
+  maxLines?: number;
   children?: ComponentChildren;
 };
 
-export interface JuiceElementStyle {
+/** Style properties that can change with the element's state without affecting layout. */
+export interface JuiceStateStyle {
+  background?: string;
+  /** Text color, inherited by children like the `color` style. */
+  color?: string;
+  borderRadius?: number;
+  borderColor?: string;
+}
+
+const stateStyles = ["pressedStyle", "focusedStyle", "disabledStyle"];
+
+export interface JuiceKeyframe {
+  /** Position in the animation from 0 to 1. Defaults to evenly spaced. */
+  offset?: number;
+  opacity?: number;
+  translateX?: number;
+  translateY?: number;
+  scale?: number;
+  /** Text color, inherited by children like the `color` style. */
+  color?: string;
+  background?: string;
+}
+
+export interface JuiceAnimation {
+  keyframes: JuiceKeyframe[];
+  /** Length of one iteration in milliseconds. */
+  duration: number;
+  /** Milliseconds before the animation starts. */
+  delay?: number;
+  /** How many times to play, which can be fractional. Defaults to 1. */
+  iterations?: number | "infinite";
+  /**
+   * Applied between each pair of keyframes: `linear` (the default), `ease`, `ease-in`,
+   * `ease-out`, `ease-in-out`, `cubic-bezier(x1, y1, x2, y2)`, `steps(n)` or the name
+   * of a curve added with `registerEasing`.
+   */
+  easing?: string;
+  /** `alternate` plays every other iteration backwards. */
+  direction?: "normal" | "alternate";
+  /** `forwards` keeps the last frame once finished instead of returning to normal. */
+  fill?: "none" | "forwards";
+}
+
+/**
+ * Extends the generated schema, so a type here the renderer doesn't accept fails to
+ * compile, and properties only in the schema are still allowed.
+ */
+export interface JuiceElementStyle extends JuiceStyleSchema {
   display?: "block" | "flex" | "grid" | "none";
+  /** How wrapped lines share the cross axis when `flexWrap` is on. */
+  alignContent?:
+    | "stretch"
+    | "flex-start"
+    | "center"
+    | "flex-end"
+    | "space-between"
+    | "space-around"
+    | "space-evenly";
   alignItems?: "stretch" | "flex-start" | "center" | "flex-end";
   alignSelf?: "stretch" | "flex-start" | "center" | "flex-end";
   background?: string;
+  /** Color of the border drawn over the `borderWidth`, as hex or `var(--name)`. */
+  borderColor?: string;
   borderRadius?: number;
+  /** Border width on all sides, taking up space in the layout like padding. */
+  borderWidth?: number;
+  borderTopWidth?: number;
+  borderRightWidth?: number;
+  borderBottomWidth?: number;
+  borderLeftWidth?: number;
+  /**
+   * A shadow drawn behind the box: x and y offsets, then optional blur and spread, and a
+   * color, e.g. `"0 4px 12px rgba(0, 0, 0, 0.3)"`.
+   */
+  boxShadow?: string;
   color?: string;
+  /**
+   * Which way text and rows run, inherited by children. `rtl` starts paragraphs and
+   * aligns text on the right, and lays out `row` containers right to left.
+   */
+  direction?: "ltr" | "rtl";
+  /**
+   * Shorthand for `flexGrow`, `flexShrink` and `flexBasis`, e.g. `1`, `"1 1 auto"`,
+   * `"2 0 120px"`, `"auto"` or `"none"`. A plain number grows from a basis of 0.
+   */
+  flex?: string | number;
   flexDirection?: "row" | "column";
   flexGrow?: number;
   flexShrink?: number;
-  flexBasis?: number;
+  /** Size along the main axis before growing or shrinking: pixels, percent or `"auto"`. */
+  flexBasis?: JuiceStyleSchema["flexBasis"];
+  /** Move children that don't fit onto further lines instead of overflowing. */
+  flexWrap?: "nowrap" | "wrap" | "wrap-reverse";
+  /** A font, or a family added with `renderer.addFontFace` (inherited). */
   font?: string;
   fontSize?: number;
+  /** Picks the family's face nearest this weight, from 100 to 900 (inherited). */
+  fontWeight?: number | "normal" | "bold";
+  /** Picks the family's italic face, or its upright one if it has none (inherited). */
+  fontStyle?: "normal" | "italic" | "oblique";
+  /**
+   * Positions along a variable font's axes, like `"'wght' 550, 'wdth' 90"` or
+   * `{ wdth: 90 }`. `wght` and `ital` follow `fontWeight` and `fontStyle` unless set
+   * here (inherited).
+   */
+  fontVariationSettings?: string | Record<string, number>;
   gap?: number;
   gapWidth?: number;
   gapHeight?: number;
-  height?: string | number;
+  /** Gap between rows, overriding `gap`. */
+  rowGap?: number;
+  /** Gap between columns, overriding `gap`. */
+  columnGap?: number;
+  /** Grid tracks, e.g. `"120px 1fr 2fr"` or `"repeat(3, 1fr)"`. */
+  gridTemplateColumns?: string | number;
+  gridTemplateRows?: string | number;
+  /** Size of tracks added for items placed outside the template. */
+  gridAutoColumns?: string | number;
+  gridAutoRows?: string | number;
+  gridAutoFlow?: "row" | "column" | "row dense" | "column dense";
+  /** A line (`2`, or `-1` for the last), `"span 2"` or `"1 / 3"`. */
+  gridColumn?: string | number;
+  gridRow?: string | number;
+  height?: JuiceStyleSchema["height"];
   justifyContent?:
     | "stretch"
     | "flex-start"
     | "center"
     | "flex-end"
     | "space-between"
-    | "space-around";
+    | "space-around"
+    | "space-evenly";
+  /** Default inline-axis alignment of grid children within their areas. */
+  justifyItems?: "stretch" | "flex-start" | "center" | "flex-end";
+  /** Inline-axis alignment of this grid child, overriding the parent's `justifyItems`. */
   justifySelf?: "stretch" | "flex-start" | "center" | "flex-end";
   textAlign?: "left" | "center" | "right";
+  /** Height of each line of text: a multiple of the font size, or pixels like `"24px"`. */
+  lineHeight?: number | string;
+  /** Extra space after each character of text, in pixels. */
+  letterSpacing?: number;
+  /** Distance between tab stops in text, in widths of a space (inherited). */
+  tabSize?: number;
+  /**
+   * Keep text to one line, or to `maxLines`, cut off or truncated with "…" when it
+   * doesn't fit.
+   */
+  textOverflow?: "clip" | "ellipsis";
+  /** Most lines text wraps to before the rest is cut off, or 0 for no limit (inherited). */
+  maxLines?: number;
+  /**
+   * Lines drawn under or through text, inherited by children, placed and sized by the
+   * font's own underline and strikeout metrics.
+   */
+  textDecoration?: "none" | "underline" | "line-through" | "underline line-through";
   margin?: number;
   marginBottom?: number;
   marginLeft?: number;
@@ -42,6 +201,13 @@ export interface JuiceElementStyle {
   marginTop?: number;
   marginX?: number;
   marginY?: number;
+  objectFit?: "contain" | "cover" | "fill";
+  /** From 0 to 1, fading the element and everything in it together. */
+  opacity?: number;
+  /** Anything but `visible` cuts children off at the inside of the border. */
+  overflow?: "visible" | "hidden" | "clip" | "scroll";
+  overflowX?: "visible" | "hidden" | "clip" | "scroll";
+  overflowY?: "visible" | "hidden" | "clip" | "scroll";
   padding?: number;
   paddingBottom?: number;
   paddingLeft?: number;
@@ -49,7 +215,14 @@ export interface JuiceElementStyle {
   paddingTop?: number;
   paddingX?: number;
   paddingY?: number;
-  width?: string | number;
+  /**
+   * Moves the drawn element around its center without changing the layout, e.g.
+   * `"translate(20px, 0) rotate(45deg) scale(1.5)"`. Presses follow it.
+   */
+  transform?: string;
+  width?: JuiceStyleSchema["width"];
+  /** Paint order among siblings; higher values are drawn over lower ones and hit first. */
+  zIndex?: number;
 }
 
 export class JuiceElement<
@@ -81,11 +254,43 @@ export class JuiceElement<
       }
     } else if (typeof value === "number") {
       dom.setAttributeNumber(this.nodeId, key, value);
+    } else if (typeof value === "boolean") {
+      dom.setAttributeString(this.nodeId, key, String(value));
+    } else if (stateStyles.includes(key) && typeof value === "object" && value) {
+      dom.setAttributeString(this.nodeId, key, JSON.stringify(value));
+    } else if (
+      key === "fontVariationSettings" &&
+      typeof value === "object" &&
+      value
+    ) {
+      dom.setAttributeString(this.nodeId, key, JSON.stringify(value));
+    } else if (key === "animation" && typeof value === "object" && value) {
+      // the bridge only carries strings and numbers, and JSON has no Infinity
+      const animation = value as JuiceAnimation;
+      const iterations =
+        animation.iterations === Infinity ? "infinite" : animation.iterations;
+      dom.setAttributeString(
+        this.nodeId,
+        key,
+        JSON.stringify({ ...animation, iterations }),
+      );
     }
   }
 
   removeAttribute(key: string): void {
-    delete (this.props as Record<string, unknown>)[key];
+    const props = this.props as Record<string, unknown>;
+
+    // Preact removes boolean attributes when they become false
+    if (this.nodeId && typeof props[key] === "boolean") {
+      dom.setAttributeString(this.nodeId, key, "false");
+    } else if (
+      this.nodeId &&
+      (key === "animation" || stateStyles.includes(key))
+    ) {
+      dom.setAttributeString(this.nodeId, key, "");
+    }
+
+    delete props[key];
   }
 
   findElementByNodeId(id: number): JuiceNode | undefined {
@@ -115,10 +320,37 @@ export class JuiceElement<
       if (typeof key === "symbol") return false;
       style[key] = value;
 
+      if (key === "lineHeight") {
+        // Plain numbers are multiples of the font size, so send it as written
+        if (this.nodeId) dom.setAttributeString(this.nodeId, key, String(value));
+        return true;
+      }
+
+      if (key === "flex" && typeof value === "string") {
+        // A lone basis like "120px" isn't a grow factor, so send it as written
+        if (this.nodeId) dom.setStyleString(this.nodeId, key, value);
+        return true;
+      }
+
       if (
-        ["background", "borderRadius", "font", "fontSize", "color"].includes(
-          key,
-        )
+        [
+          "background",
+          "borderColor",
+          "borderRadius",
+          "boxShadow",
+          "font",
+          "fontSize",
+          "color",
+          "textOverflow",
+          "maxLines",
+          "letterSpacing",
+          "tabSize",
+          "direction",
+          "textDecoration",
+          "fontWeight",
+          "fontStyle",
+          "fontVariationSettings",
+        ].includes(key)
       ) {
         this.setAttribute(key, value);
         return true;
@@ -146,9 +378,6 @@ export class JuiceElement<
         this.style.paddingRight = value;
         this.style.paddingBottom = value;
         this.style.paddingLeft = value;
-      } else if (key === "gap") {
-        this.style.gapHeight = value;
-        this.style.gapWidth = value;
       } else if (this.nodeId) {
         if (typeof value === "string") {
           const match = value.match(/^(\d+(\.\d+)?)(px|%|em)$/);
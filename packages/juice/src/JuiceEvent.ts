@@ -24,13 +24,112 @@ export class JuiceEvent<
   }
 }
 
-export class PressEvent extends JuiceEvent<{ x: number; y: number }> {}
+export type PointerTool = "touch" | "pen" | "eraser" | "mouse";
+
+export interface PressDetails extends Record<string, unknown> {
+  x: number;
+  y: number;
+  /** 0–1; devices without pressure sensing report 0.5 while pressed. */
+  pressure: number;
+  tool: PointerTool;
+}
+
+export class PressEvent extends JuiceEvent<PressDetails> {}
+
+export class ScrollEvent extends JuiceEvent<{
+  scrollX: number;
+  scrollY: number;
+}> {}
+
+export class OverscrollEvent extends JuiceEvent<{
+  overscrollX: number;
+  overscrollY: number;
+}> {}
+
+export class RefreshEvent extends JuiceEvent<{
+  /** Call when the refresh has finished to retract the indicator. */
+  complete: () => void;
+}> {}
+
+export class SwipeActionEvent extends JuiceEvent<{
+  /** Which way the row was swiped open, or `none` once it closes. */
+  direction: "left" | "right" | "none";
+}> {}
+
+export class AmbientLightEvent extends JuiceEvent<{
+  lux: number;
+}> {}
+
+export class ThemeChangeEvent extends JuiceEvent<{
+  /** The theme switched to. */
+  theme: string;
+}> {}
+
+export class MemoryPressureEvent extends JuiceEvent<{
+  /** `critical` once the renderer has dropped all its caches. */
+  level: "moderate" | "critical";
+}> {}
+
+export class InputEvent extends JuiceEvent<{
+  /** The input's value after the edit. */
+  value: string;
+}> {}
+
+export class CompositionEvent extends JuiceEvent<{
+  /** Preedit text for `CompositionStart`/`CompositionUpdate`, committed text for `CompositionEnd`. */
+  text: string;
+}> {}
+
+export class ChangeEvent extends JuiceEvent<{
+  /** The picker's new value: the item, `YYYY-MM-DD` or `HH:MM`. */
+  value: string;
+  /** Index of the item selected on the first wheel. */
+  index: number;
+}> {}
+
+export class CellPressEvent extends JuiceEvent<{
+  /** Index of the row pressed, or -1 for the header. */
+  row: number;
+  column: number;
+}> {}
+
+export class LinkPressEvent extends JuiceEvent<{
+  /** URL of the link pressed, as written in the Markdown. */
+  href: string;
+}> {}
+
+export class ScanEvent extends JuiceEvent<{
+  /** What was decoded, e.g. the contents of a barcode. */
+  text: string;
+  /** The kind of code, as named by the analyzer, e.g. `"qr"` or `"ean13"`. */
+  format: string;
+}> {}
 
 export interface UIEventMap {
   PressIn: PressEvent;
   PressOut: PressEvent;
   Press: PressEvent;
   PressMove: PressEvent;
+  PressCancel: JuiceEvent<{ x: number; y: number }>;
+  Scroll: ScrollEvent;
+  Overscroll: OverscrollEvent;
+  Refresh: RefreshEvent;
+  SwipeAction: SwipeActionEvent;
+  AmbientLight: AmbientLightEvent;
+  ThemeChange: ThemeChangeEvent;
+  MemoryPressure: MemoryPressureEvent;
+  Focus: JuiceEvent;
+  Blur: JuiceEvent;
+  Input: InputEvent;
+  Submit: InputEvent;
+  CompositionStart: CompositionEvent;
+  CompositionUpdate: CompositionEvent;
+  CompositionEnd: CompositionEvent;
+  Change: ChangeEvent;
+  AnimationEnd: JuiceEvent;
+  CellPress: CellPressEvent;
+  LinkPress: LinkPressEvent;
+  Scan: ScanEvent;
 }
 
 export type UIEventListener<Event extends keyof UIEventMap> = (
@@ -0,0 +1,54 @@
+import type { JuiceElementProps } from "./JuiceElement.js";
+import JuiceLayoutElement from "./JuiceLayoutElement.js";
+
+/** A colored band of a gauge's scale, like a red zone. */
+export interface GaugeRange {
+  from: number;
+  to: number;
+  color: string;
+}
+
+export interface JuiceGaugeElementProps extends JuiceElementProps {
+  /** The value the needle points at; it sweeps there when this changes. */
+  value: number;
+  /** Ends of the scale, 0 and 100 by default. */
+  min?: number;
+  max?: number;
+  /**
+   * Angles of the scale's ends in degrees clockwise from 12 o'clock, -135 and 135 by
+   * default.
+   */
+  startAngle?: number;
+  endAngle?: number;
+  /** Intervals between labelled major ticks, 10 by default. 0 hides the ticks. */
+  ticks?: number;
+  /** Ticks between each pair of major ticks, 4 by default. */
+  minorTicks?: number;
+  /** Width of the arc in pixels. Defaults to a tenth of the radius. */
+  thickness?: number;
+  /** Color of the arc, a dimmed `color` by default. */
+  trackColor?: string;
+  /** Fills the arc from `min` up to the value in this color. */
+  valueColor?: string;
+  ranges?: GaugeRange[];
+  /** Label the major ticks with their values, which is the default. */
+  labels?: boolean;
+  /** How long the needle takes to sweep to a new value in milliseconds, 500 by default. */
+  sweepDuration?: number;
+}
+
+export class JuiceGaugeElement extends JuiceLayoutElement<JuiceGaugeElementProps> {
+  constructor() {
+    super("gauge");
+  }
+
+  setAttribute(key: string, value: unknown): void {
+    // the bridge only carries strings and numbers
+    if (key === "ranges" && typeof value === "object" && value) {
+      super.setAttribute(key, JSON.stringify(value));
+      (this.props as Record<string, unknown>)[key] = value;
+    } else {
+      super.setAttribute(key, value);
+    }
+  }
+}
@@ -1,14 +1,15 @@
 import type { JuiceElementProps } from "./JuiceElement.js";
 import JuiceLayoutElement from "./JuiceLayoutElement.js";
 
-interface JuiceImgElementProps extends JuiceElementProps {
+export interface JuiceImgElementProps extends JuiceElementProps {
+  /** A data URL, or a path in the host's asset folder. */
   src?: string;
   width?: number | string;
   height?: number | string;
 }
 
 export class JuiceImgElement extends JuiceLayoutElement<JuiceImgElementProps> {
-  constructor() {
-    super("img");
+  constructor(tagName: "img" | "image" = "img") {
+    super(tagName);
   }
 }
@@ -0,0 +1,78 @@
+import type { JuiceElementProps } from "./JuiceElement.js";
+import { CompositionEvent, InputEvent } from "./JuiceEvent.js";
+import JuiceLayoutElement from "./JuiceLayoutElement.js";
+
+export interface JuiceInputElementProps extends JuiceElementProps {
+  value?: string;
+  placeholder?: string;
+  /** Mask the value, e.g. for passwords and PINs, and keep it out of DOM dumps. */
+  secureTextEntry?: boolean;
+  /** With `secureTextEntry`, show the last typed character until the next edit. */
+  revealLastChar?: boolean;
+  /**
+   * Show a built-in keypad at the bottom of the screen while focused: `numeric` (or
+   * `decimal`) for digits and a decimal point, `pin` for digits and OK, which submits.
+   */
+  inputMode?: "text" | "numeric" | "decimal" | "pin";
+  /** Put the keypad's digits in a random order each time it's shown. */
+  shuffleKeys?: boolean;
+}
+
+/**
+ * A single-line text field edited natively by the host. Input methods written in JS
+ * drive it through `setComposition` and `commitComposition`, which fire the same
+ * events as a host IME.
+ */
+export class JuiceInputElement extends JuiceLayoutElement<JuiceInputElementProps> {
+  private composing = false;
+
+  constructor() {
+    super("input");
+  }
+
+  /** Show preedit text inline at the caret, underlined, without changing the value. */
+  setComposition(text: string) {
+    renderer.setComposition(this.nodeId, text);
+
+    if (!this.composing) {
+      this.composing = true;
+      this.dispatchEvent(new CompositionEvent("CompositionStart", this, { text }));
+    }
+
+    this.dispatchEvent(new CompositionEvent("CompositionUpdate", this, { text }));
+  }
+
+  /** Replace any composition with `text` at the caret; an empty string cancels it. */
+  commitComposition(text: string) {
+    if (this.composing) {
+      this.composing = false;
+      this.dispatchEvent(new CompositionEvent("CompositionEnd", this, { text }));
+    }
+
+    if (text) {
+      this.insertText(text);
+    } else {
+      renderer.setComposition(this.nodeId, null);
+    }
+  }
+
+  /** Insert text at the caret, as if typed. */
+  insertText(text: string) {
+    const value = renderer.insertText(this.nodeId, text);
+
+    if (value !== undefined) {
+      this.dispatchEvent(new InputEvent("Input", this, { value }));
+    }
+  }
+
+  toJSON() {
+    // keep passwords out of DOM dumps
+    const json = super.toJSON();
+
+    if (this.props.secureTextEntry && this.props.value !== undefined) {
+      return { ...json, props: { ...json.props, value: "<redacted>" } };
+    }
+
+    return json;
+  }
+}
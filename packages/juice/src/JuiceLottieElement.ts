@@ -0,0 +1,27 @@
+import type { JuiceElementProps } from "./JuiceElement.js";
+import JuiceLayoutElement from "./JuiceLayoutElement.js";
+
+export interface JuiceLottieElementProps extends JuiceElementProps {
+  /** The exported Lottie (bodymovin) JSON, parsed or as a string. */
+  animationData?: object | string;
+  /** Play the animation repeatedly, which is the default. */
+  loop?: boolean;
+  /** Playback rate, where 1 is the animation's own frame rate. */
+  speed?: number;
+}
+
+export class JuiceLottieElement extends JuiceLayoutElement<JuiceLottieElementProps> {
+  constructor() {
+    super("lottie");
+  }
+
+  setAttribute(key: string, value: unknown): void {
+    // the bridge only carries strings and numbers
+    if (key === "animationData" && typeof value === "object" && value) {
+      super.setAttribute(key, JSON.stringify(value));
+      (this.props as Record<string, unknown>)[key] = value;
+    } else {
+      super.setAttribute(key, value);
+    }
+  }
+}
@@ -48,6 +48,7 @@ export class JuiceNode {
   }
 
   insertBefore(node: JuiceNode, child: JuiceNode | null): JuiceNode {
+    this.detach(node);
     node.parentNode = this;
     const idx = child ? this.childNodes.indexOf(child) : -1;
 
@@ -64,6 +65,20 @@ export class JuiceNode {
     return node;
   }
 
+  /**
+   * Inserting a node that's already attached moves it, as Preact expects when it
+   * reorders keyed children. The native insert does its own detaching, so this only
+   * updates `childNodes`.
+   */
+  private detach(node: JuiceNode) {
+    const siblings = node.parentNode?.childNodes;
+    const idx = siblings?.indexOf(node) ?? -1;
+
+    if (siblings && idx >= 0) {
+      siblings.splice(idx, 1);
+    }
+  }
+
   private getEventListeners(type: string) {
     const listeners = this.eventListeners.get(type);
 
@@ -109,6 +124,7 @@ export class JuiceNode {
   }
 
   appendChild(node: JuiceNode): JuiceNode {
+    this.detach(node);
     node.parentNode = this;
     this.childNodes.push(node);
 
@@ -136,6 +152,7 @@ export class JuiceNode {
   }
 
   prepend(node: JuiceNode): JuiceNode {
+    this.detach(node);
     node.parentNode = this;
     this.childNodes.unshift(node);
 
@@ -0,0 +1,41 @@
+import type { JuiceElementProps } from "./JuiceElement.js";
+import JuiceLayoutElement from "./JuiceLayoutElement.js";
+
+export interface TableColumn {
+  title?: string;
+  /** Pixels, a percentage like `"25%"` or a share of the rest like `"2fr"` (`"1fr"` by default). */
+  width?: number | string;
+  align?: "left" | "center" | "right";
+}
+
+/** A cell's text, optionally with its own colors. */
+export type TableCell =
+  | string
+  | number
+  | { text: string; color?: string; background?: string };
+
+export interface JuiceTableElementProps extends JuiceElementProps {
+  columns: TableColumn[];
+  rows: TableCell[][];
+  /** Height of the header and each row. Defaults to twice the font size. */
+  rowHeight?: number;
+  headerBackground?: string;
+  /** Background of every other row. */
+  stripeColor?: string;
+}
+
+export class JuiceTableElement extends JuiceLayoutElement<JuiceTableElementProps> {
+  constructor() {
+    super("table");
+  }
+
+  setAttribute(key: string, value: unknown): void {
+    // the bridge only carries strings and numbers
+    if ((key === "columns" || key === "rows") && Array.isArray(value)) {
+      super.setAttribute(key, JSON.stringify(value));
+      (this.props as Record<string, unknown>)[key] = value;
+    } else {
+      super.setAttribute(key, value);
+    }
+  }
+}
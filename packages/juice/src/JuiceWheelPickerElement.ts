@@ -0,0 +1,24 @@
+import type { JuiceElementProps } from "./JuiceElement.js";
+import JuiceLayoutElement from "./JuiceLayoutElement.js";
+
+export interface JuiceWheelPickerElementProps extends JuiceElementProps {
+  items?: string[];
+  /** The selected item. */
+  value?: string;
+}
+
+export class JuiceWheelPickerElement extends JuiceLayoutElement<JuiceWheelPickerElementProps> {
+  constructor() {
+    super("wheelpicker");
+  }
+
+  setAttribute(key: string, value: unknown): void {
+    // the bridge only carries strings and numbers
+    if (key === "items" && Array.isArray(value)) {
+      super.setAttribute(key, JSON.stringify(value));
+      (this.props as Record<string, unknown>)[key] = value;
+    } else {
+      super.setAttribute(key, value);
+    }
+  }
+}
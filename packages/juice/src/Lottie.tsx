@@ -0,0 +1,20 @@
+import type { JuiceLottieElementProps } from "./JuiceLottieElement.js";
+
+export type LottieProps = JuiceLottieElementProps;
+
+declare module "preact" {
+  namespace JSX {
+    interface IntrinsicElements {
+      lottie: LottieProps;
+    }
+  }
+}
+
+/**
+ * Plays a Lottie animation natively, scaled to fit the node's size. Needs the `lottie`
+ * feature on the juice crate; shape layers with fills, strokes and transforms are
+ * supported.
+ */
+export function Lottie(props: LottieProps) {
+  return <lottie {...props} />;
+}
@@ -0,0 +1,34 @@
+import type { JuiceElementProps } from "./JuiceElement.js";
+
+export interface MarkdownProps extends JuiceElementProps {
+  /**
+   * The text to show. Supports `#` headings, `**bold**`, `*italic*`, `` `code` `` spans
+   * and fenced blocks, `-` and `1.` lists (indent two spaces to nest) and `[links](url)`.
+   */
+  source: string;
+  /**
+   * Fonts for bold, italic and code text. Bold and italic default to the faces of the
+   * node's font family, if it is one; otherwise each is the node's font.
+   */
+  boldFont?: string;
+  italicFont?: string;
+  codeFont?: string;
+  /** Color of links and their underlines. Defaults to the text color. */
+  linkColor?: string;
+}
+
+declare module "preact" {
+  namespace JSX {
+    interface IntrinsicElements {
+      markdown: MarkdownProps;
+    }
+  }
+}
+
+/**
+ * Renders a subset of Markdown natively as wrapped rich text, for help screens, release
+ * notes and messages delivered as Markdown. Fires `onLinkPress` with the link's `href`.
+ */
+export function Markdown(props: MarkdownProps) {
+  return <markdown {...props} />;
+}
@@ -0,0 +1,43 @@
+import type { JuiceElementProps } from "./JuiceElement.js";
+import type { JuiceWheelPickerElementProps } from "./JuiceWheelPickerElement.js";
+
+export type WheelPickerProps = JuiceWheelPickerElementProps;
+
+export interface DatePickerProps extends JuiceElementProps {
+  /** `YYYY-MM-DD`; defaults to today (UTC). */
+  value?: string;
+  minYear?: number;
+  maxYear?: number;
+}
+
+export interface TimePickerProps extends JuiceElementProps {
+  /** 24-hour `HH:MM`; defaults to midnight. */
+  value?: string;
+  /** Minutes between items on the minute wheel. */
+  minuteStep?: number;
+}
+
+declare module "preact" {
+  namespace JSX {
+    interface IntrinsicElements {
+      wheelpicker: WheelPickerProps;
+      datepicker: DatePickerProps;
+      timepicker: TimePickerProps;
+    }
+  }
+}
+
+/** A spinning wheel of items. Fires `onChange` when it comes to rest on a new item. */
+export function WheelPicker(props: WheelPickerProps) {
+  return <wheelpicker {...props} />;
+}
+
+/** Year, month and day wheels. Fires `onChange` with the new `YYYY-MM-DD` date. */
+export function DatePicker(props: DatePickerProps) {
+  return <datepicker {...props} />;
+}
+
+/** Hour and minute wheels. Fires `onChange` with the new `HH:MM` time. */
+export function TimePicker(props: TimePickerProps) {
+  return <timepicker {...props} />;
+}
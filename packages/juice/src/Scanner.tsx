@@ -0,0 +1,35 @@
+import type { JuiceElementProps } from "./JuiceElement.js";
+
+export interface ScannerProps extends JuiceElementProps {
+  /** The video source to show and analyze, as passed to `set_video_frame`. */
+  src: string;
+  /** Name of the host-registered analyzer to run, `"barcode"` by default. */
+  analyzer?: string;
+  /**
+   * Draw a viewfinder and only analyze the part of the frame inside it, which is the
+   * default. Outlines around detections and the viewfinder brackets use `color`.
+   */
+  viewfinder?: boolean;
+  /** Viewfinder width as a fraction of the node's width, 0.7 by default. */
+  viewfinderSize?: number;
+  /** Viewfinder width over height: 1 (the default) for QR codes, wider for 1D barcodes. */
+  viewfinderAspect?: number;
+  /** Milliseconds the same text must go unseen before it fires `onScan` again. */
+  repeatDelay?: number;
+}
+
+declare module "preact" {
+  namespace JSX {
+    interface IntrinsicElements {
+      scanner: ScannerProps;
+    }
+  }
+}
+
+/**
+ * Shows a camera feed with a viewfinder, feeding each new frame to a decoder the host
+ * registered and firing `onScan` with what it finds, like the text of a barcode.
+ */
+export function Scanner(props: ScannerProps) {
+  return <scanner {...props} />;
+}
@@ -0,0 +1,19 @@
+import type { JuiceElementProps } from "./JuiceElement.js";
+
+export type SkeletonProps = JuiceElementProps;
+
+declare module "preact" {
+  namespace JSX {
+    interface IntrinsicElements {
+      skeleton: SkeletonProps;
+    }
+  }
+}
+
+/**
+ * A loading placeholder block with a shimmer that animates natively. Size and round it
+ * with `style`; `background` sets the block color (light grey by default).
+ */
+export function Skeleton(props: SkeletonProps) {
+  return <skeleton {...props} />;
+}
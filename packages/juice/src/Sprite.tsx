@@ -0,0 +1,31 @@
+import type { JuiceElementProps } from "./JuiceElement.js";
+
+export interface AnimatedSpriteProps extends JuiceElementProps {
+  /** The sprite sheet as a data URL, with frames packed left to right, top to bottom. */
+  src: string;
+  /** Size of one frame in the sheet, in image pixels. Also the node's default size. */
+  frameWidth: number;
+  frameHeight: number;
+  /** Frames to play, when the sheet's last row isn't full. Defaults to all of them. */
+  frameCount?: number;
+  /** Frames per second, 12 by default. */
+  fps?: number;
+  /** Play repeatedly, which is the default; otherwise stop on the last frame. */
+  loop?: boolean;
+}
+
+declare module "preact" {
+  namespace JSX {
+    interface IntrinsicElements {
+      animatedsprite: AnimatedSpriteProps;
+    }
+  }
+}
+
+/**
+ * Plays the frames of a sprite sheet natively at a fixed rate. A lighter alternative to
+ * `Lottie` for small looping animations like spinners and status icons.
+ */
+export function AnimatedSprite(props: AnimatedSpriteProps) {
+  return <animatedsprite {...props} />;
+}
@@ -0,0 +1,20 @@
+import type { JuiceElementProps } from "./JuiceElement.js";
+
+export interface SurfaceProps extends JuiceElementProps {
+  /** Identifies the surface to the host, which positions a hardware plane under it. */
+  name: string;
+  /** `transparent` (default) clears the rect to alpha 0; `none` leaves it untouched. */
+  punch?: "transparent" | "none";
+}
+
+declare module "preact" {
+  namespace JSX {
+    interface IntrinsicElements {
+      surface: SurfaceProps;
+    }
+  }
+}
+
+export function Surface(props: SurfaceProps) {
+  return <surface {...props} />;
+}
@@ -0,0 +1,19 @@
+import type { JuiceTableElementProps } from "./JuiceTableElement.js";
+
+export type TableProps = JuiceTableElementProps;
+
+declare module "preact" {
+  namespace JSX {
+    interface IntrinsicElements {
+      table: TableProps;
+    }
+  }
+}
+
+/**
+ * A data table drawn natively: a header row that stays put over rows that scroll, with
+ * only the rows in view drawn. Fires `onCellPress` with the row and column tapped.
+ */
+export function Table(props: TableProps) {
+  return <table {...props} />;
+}
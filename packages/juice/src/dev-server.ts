@@ -7,9 +7,14 @@ interface DevServerOptions {
   port: number;
 }
 
+/** How long `/animations` waits for a device to answer */
+const REQUEST_TIMEOUT_MS = 2000;
+
 export async function startDevServer({ entryPoint, port }: DevServerOptions) {
   let currentBundle = "";
   const clients = new Set<WebSocket>();
+  // Answers to `/animations` requests, resolved by the first device to reply
+  let animationWaiters: ((animations: unknown) => void)[] = [];
 
   const ctx = await context({
     entryPoints: [entryPoint],
@@ -56,6 +61,27 @@ export async function startDevServer({ entryPoint, port }: DevServerOptions) {
     if (req.url === "/bundle.js") {
       res.writeHead(200, { "Content-Type": "application/javascript" });
       res.end(currentBundle);
+    } else if (req.url === "/animations") {
+      // Ask the running app for its native animations, for tuning motion on the device
+      const timeout = setTimeout(() => {
+        animationWaiters = animationWaiters.filter((waiter) => waiter !== reply);
+        res.writeHead(504);
+        res.end("No device answered\n");
+      }, REQUEST_TIMEOUT_MS);
+
+      const reply = (animations: unknown) => {
+        clearTimeout(timeout);
+        res.writeHead(200, { "Content-Type": "application/json" });
+        res.end(JSON.stringify(animations, null, 2));
+      };
+
+      animationWaiters.push(reply);
+
+      for (const client of clients) {
+        if (client.readyState === 1) {
+          client.send(JSON.stringify({ type: "animations" }));
+        }
+      }
     } else {
       res.writeHead(404);
       res.end();
@@ -72,6 +98,21 @@ export async function startDevServer({ entryPoint, port }: DevServerOptions) {
     clients.add(ws);
     console.log(`[dev] client connected (${clients.size} total)`);
     if (currentBundle) ws.send(currentBundle);
+    ws.on("message", (data) => {
+      let message: { type?: string; animations?: unknown };
+
+      try {
+        message = JSON.parse(data.toString());
+      } catch {
+        return;
+      }
+
+      if (message.type === "animations") {
+        const waiters = animationWaiters;
+        animationWaiters = [];
+        for (const waiter of waiters) waiter(message.animations);
+      }
+    });
     ws.on("close", () => {
       clients.delete(ws);
       console.log(`[dev] client disconnected (${clients.size} total)`);
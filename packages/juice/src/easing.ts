@@ -0,0 +1,15 @@
+/**
+ * Register a cubic-bézier timing curve that animations can use by name as their
+ * `easing`, e.g. `registerEasing("snappy", 0.2, 0.9, 0.1, 1)`. Registering a name again
+ * replaces the curve, including for animations already running. Returns false for
+ * built-in names such as `ease-in`.
+ */
+export function registerEasing(
+  name: string,
+  x1: number,
+  y1: number,
+  x2: number,
+  y2: number,
+): boolean {
+  return renderer.registerEasing(name, x1, y1, x2, y2);
+}
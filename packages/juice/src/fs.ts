@@ -0,0 +1,23 @@
+export interface FileStat {
+  size: number;
+  isFile: boolean;
+  isDirectory: boolean;
+  mtimeMs: number;
+}
+
+export interface FsError {
+  code: string;
+  message: string;
+}
+
+export interface JuiceFs {
+  readFile(path: string): ArrayBuffer;
+  readFile(path: string, encoding: "utf8" | "utf-8"): string;
+  writeFile(path: string, data: string | ArrayBuffer | Uint8Array): void;
+  readdir(path: string): string[];
+  stat(path: string): FileStat;
+}
+
+declare global {
+  const fs: JuiceFs;
+}
@@ -1,4 +1,71 @@
 export * from "./Box.js";
-export { JuiceElementProps as UIElementProps } from "./JuiceElement.js";
-export { JuiceEvent as UIEvent, PressEvent } from "./JuiceEvent.js";
+export * from "./Bound.js";
+export * from "./Chart.js";
+export * from "./easing.js";
+export * from "./Image.js";
+export * from "./Input.js";
+export * from "./Lottie.js";
+export * from "./Markdown.js";
+export * from "./memory.js";
+export * from "./Picker.js";
+export type { FileStat, FsError, JuiceFs } from "./fs.js";
+export type { DomError } from "./JuiceDocument.js";
+export type { JuiceLauncher, LauncherApp } from "./launcher.js";
+export type { JuiceLockScreen, LockAttempt, LockStatus } from "./lock.js";
+export type { JuiceMetrics } from "./metrics.js";
+export type { JuicePermissions, PermissionError } from "./permissions.js";
+export * from "./Gauge.js";
+export type { GaugeRange } from "./JuiceGaugeElement.js";
+export type { TableCell, TableColumn } from "./JuiceTableElement.js";
+export { JuiceChartElement } from "./JuiceChartElement.js";
+export { JuiceInputElement } from "./JuiceInputElement.js";
+export { JuiceElement, JuiceElementProps as UIElementProps } from "./JuiceElement.js";
+export type {
+  JuiceAnimation,
+  JuiceKeyframe,
+  JuiceStateStyle,
+} from "./JuiceElement.js";
+export {
+  AmbientLightEvent,
+  CellPressEvent,
+  ChangeEvent,
+  CompositionEvent,
+  InputEvent,
+  JuiceEvent as UIEvent,
+  LinkPressEvent,
+  MemoryPressureEvent,
+  OverscrollEvent,
+  PressEvent,
+  RefreshEvent,
+  ScanEvent,
+  ScrollEvent,
+  SwipeActionEvent,
+  ThemeChangeEvent,
+} from "./JuiceEvent.js";
+export type { PointerTool, PressDetails } from "./JuiceEvent.js";
 export { render } from "./render.js";
+export { SCHEMA_VERSION } from "./schema.js";
+export type {
+  JuiceElementJson,
+  JuiceLength,
+  JuiceNodeJson,
+  JuiceNodeType,
+  JuicePixels,
+  JuiceStyleSchema,
+  JuiceTextJson,
+} from "./schema.js";
+export type { JuiceRenderer, RendererCapabilities } from "./render.js";
+export type {
+  JuiceSqlite,
+  RunResult,
+  SqlRow,
+  SqliteDatabase,
+  SqliteStatement,
+  SqlValue,
+} from "./sqlite.js";
+export * from "./Scanner.js";
+export * from "./Skeleton.js";
+export * from "./Sprite.js";
+export * from "./Surface.js";
+export * from "./Table.js";
+export * from "./theme.js";
@@ -0,0 +1,22 @@
+export interface LauncherApp {
+  /** The name of the app bundle's directory. */
+  id: string;
+  /** The name from the app's manifest. */
+  name: string;
+}
+
+export interface JuiceLauncher {
+  /** The apps on the home grid, by name. */
+  apps(): LauncherApp[];
+  /** Show an app, starting it if it isn't running. False if there's no such app. */
+  launch(id: string): boolean;
+  /** Go back to the home grid. */
+  home(): void;
+  /** The app shown, or null on the home grid. */
+  current(): string | null;
+}
+
+declare global {
+  /** Only present in apps started from a launcher. */
+  const launcher: JuiceLauncher;
+}
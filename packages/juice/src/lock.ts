@@ -0,0 +1,20 @@
+export type LockAttempt = "unlocked" | "wrong" | "lockedOut";
+
+export interface LockStatus {
+  locked: boolean;
+  /** Wrong PINs since the last unlock. */
+  failures: number;
+  /** Seconds until a PIN can be tried again, or 0. */
+  retryIn: number;
+}
+
+export interface JuiceLockScreen {
+  /** Check a PIN with the host. `lockedOut` means it wasn't checked. */
+  submit(pin: string): LockAttempt;
+  status(): LockStatus;
+}
+
+declare global {
+  /** Only present in the lock layer, when the host registers a `LockScreen`. */
+  const lockScreen: JuiceLockScreen;
+}
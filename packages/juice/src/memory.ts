@@ -0,0 +1,10 @@
+import { document } from "./JuiceDocument.js";
+import type { UIEventListener } from "./JuiceEvent.js";
+
+/**
+ * Listen for the system running low on memory, after the renderer has trimmed its own
+ * caches, so the app can free what it's holding. Returns an unsubscribe function.
+ */
+export function onMemoryPressure(listener: UIEventListener<"MemoryPressure">) {
+  return document.addEventListener("MemoryPressure", listener);
+}
@@ -0,0 +1,12 @@
+export interface JuiceMetrics {
+  /**
+   * Queue an analytics event on disk, to be uploaded by the host when it's online.
+   * `props` must be JSON-serializable.
+   */
+  track(event: string, props?: Record<string, unknown>): void;
+}
+
+declare global {
+  /** Only present when the host registers the `metrics` plugin. */
+  const metrics: JuiceMetrics;
+}
@@ -0,0 +1,16 @@
+export interface PermissionError {
+  code: "EPERM";
+  message: string;
+}
+
+export interface JuicePermissions {
+  /** Whether a permission, like `fs` or `fs.readFile`, is granted. */
+  has(permission: string): boolean;
+  /** Every permission granted, or `["*"]` for all of them. */
+  granted(): string[];
+}
+
+declare global {
+  /** Present when the host gives the renderer its `Permissions`. */
+  const permissions: JuicePermissions;
+}
@@ -14,9 +14,113 @@ export type RendererEventCallback = (
   event: { type: string; details: Record<string, unknown> },
 ) => void;
 
+/** What the renderer on this device supports, for feature detection. */
+export interface RendererCapabilities {
+  /** Bumped when the renderer changes incompatibly. */
+  schemaVersion: number;
+  /** The juice crate version. */
+  version: string;
+  /** Element tags with native behaviour; others are drawn like `box`. */
+  nodeTypes: string[];
+  styleProperties: string[];
+  /** Optional modules compiled in, like `sqlite`, `lottie`, `lock` or `shaping`. */
+  features: string[];
+  binaryProtocol: boolean;
+}
+
 export interface JuiceRenderer {
+  /** Missing on firmware from before capabilities were reported. */
+  readonly capabilities?: RendererCapabilities;
   update(eventCallback: RendererEventCallback): void;
+  /** Load a font from a base64 data URL. Text already using `name` is re-laid out. */
   addFont(name: string, contents: string): void;
+  /**
+   * Make the loaded font `name` the face of `family` at `weight` (100 to 900) and
+   * `style`, so text with `font: family` picks it by `fontWeight` and `fontStyle`.
+   * Returns false if no font called `name` is loaded.
+   */
+  addFontFace(
+    family: string,
+    name: string,
+    weight: number,
+    style: "normal" | "italic",
+  ): boolean;
+  /**
+   * Switch the font used by text that doesn't set `font`, which can be a family. Returns
+   * false if no font or family called `name` is loaded.
+   */
+  setDefaultFont(name: string): boolean;
+  /**
+   * Fonts to try, in order, for characters missing from a node's own font. Names that
+   * aren't loaded yet are skipped until they are.
+   */
+  setFontFallbacks(names: string[]): void;
+  /**
+   * Multiply every font size by `factor`, e.g. for a "large text" setting, and reflow the
+   * layout around the new sizes. Clamped to 0.5 to 4.
+   */
+  setTextScale(factor: number): void;
+  /** Filter the whole screen for accessibility. Returns false for an unknown filter. */
+  setDisplayFilter(filter: "none" | "invert" | "high-contrast" | "deuteranopia"): boolean;
+  registerTheme(name: string, variables: Record<string, string>): void;
+  setTheme(name: string): boolean;
+  /** Add or replace a named cubic-bézier curve for animations' `easing`. */
+  registerEasing(name: string, x1: number, y1: number, x2: number, y2: number): boolean;
+  setComposition(nodeId: number, text: string | null): void;
+  insertText(nodeId: number, text: string): string | undefined;
+  appendChartData(nodeId: number, values: number[]): void;
+  /**
+   * Decode images, SVG markup and fonts in the background, so the screen that uses them
+   * doesn't stutter the first time it's shown.
+   */
+  preload(assets: { type: "image" | "svg" | "font"; src: string }[]): void;
+  /** Print a node and its children with the host's print handler, on the next tick. */
+  print(nodeId: number): void;
+  /**
+   * Redraw a node and its children on the next frame, reporting its box as damaged even
+   * if no pixels change.
+   */
+  invalidate(nodeId: number): void;
+  /** Report a region of the screen as damaged on the next frame. */
+  invalidateRect(x: number, y: number, width: number, height: number): void;
+  /**
+   * Send all presses to a node and its children, e.g. for a confirmation dialog, until
+   * cleared with `null`. Presses outside the node are dispatched to the node itself.
+   */
+  setInputGrab(nodeId: number | null): void;
+  /**
+   * The native tree as JSON, with each node's resolved inherited style and layout, for
+   * checking what the renderer actually received.
+   */
+  dumpTree(): string;
+  /**
+   * Input-to-photon latency percentiles in milliseconds, by stage, over the last 1000
+   * inputs that changed the screen. Undefined unless the host measures latency.
+   */
+  latencyStats(): LatencyStats | undefined;
+  /**
+   * Tell the host the app has drawn its first screen, so the splash it shows from startup
+   * fades out once that screen's images and SVGs are decoded. Does nothing without one.
+   */
+  ready(): void;
+}
+
+export interface LatencyPercentiles {
+  p50: number;
+  p90: number;
+  p99: number;
+  max: number;
+}
+
+export interface LatencyStats {
+  samples: number;
+  /** From the kernel's timestamp on the input event to it being handled */
+  dispatch: LatencyPercentiles;
+  /** From handling the event to the frame it changed being drawn */
+  render: LatencyPercentiles;
+  /** From drawing the frame to it reaching the panel */
+  flush: LatencyPercentiles;
+  total: LatencyPercentiles;
 }
 
 declare global {
@@ -0,0 +1,178 @@
+// Generated from the juice crate by `cargo run -p juice --features lottie --bin
+// juice-schema -- --write`. Don't edit by hand.
+
+export const SCHEMA_VERSION = 1;
+
+/** Element tags with native behaviour; others are drawn like `box`. */
+export type JuiceNodeType =
+  | "document"
+  | "box"
+  | "skeleton"
+  | "svg"
+  | "img"
+  | "image"
+  | "animatedsprite"
+  | "video"
+  | "surface"
+  | "input"
+  | "wheelpicker"
+  | "datepicker"
+  | "timepicker"
+  | "gauge"
+  | "chart"
+  | "table"
+  | "markdown"
+  | "scanner"
+  | "bound"
+  | "lottie";
+
+/** Pixels, as a number or `"12px"`. */
+export type JuicePixels = number | `${number}px`;
+
+/** Pixels, or ems of the inherited font size. */
+export type JuiceLength = JuicePixels | `${number}em`;
+
+/** Every style property the renderer understands. */
+export interface JuiceStyleSchema {
+  alignContent?:
+    | "stretch"
+    | "start"
+    | "end"
+    | "flex-start"
+    | "center"
+    | "flex-end"
+    | "space-between"
+    | "space-around"
+    | "space-evenly";
+  alignItems?:
+    | "stretch"
+    | "start"
+    | "end"
+    | "flex-start"
+    | "center"
+    | "flex-end"
+    | "baseline";
+  alignSelf?:
+    | "stretch"
+    | "start"
+    | "end"
+    | "flex-start"
+    | "center"
+    | "flex-end"
+    | "baseline";
+  background?: string;
+  borderBottomWidth?: JuiceLength;
+  borderColor?: string;
+  borderLeftWidth?: JuiceLength;
+  borderRadius?: JuicePixels;
+  borderRightWidth?: JuiceLength;
+  borderTopWidth?: JuiceLength;
+  borderWidth?: JuiceLength;
+  boxShadow?: string;
+  boxSizing?: "border-box" | "content-box";
+  color?: string;
+  columnGap?: JuiceLength | `${number}%`;
+  direction?: "ltr" | "rtl";
+  display?: "block" | "flex" | "grid" | "none";
+  flex?: number | string;
+  flexBasis?: JuiceLength | `${number}%` | "auto";
+  flexDirection?: "row" | "column" | "row-reverse" | "column-reverse";
+  flexGrow?: number;
+  flexShrink?: number;
+  flexWrap?: "nowrap" | "wrap" | "wrap-reverse";
+  font?: string;
+  fontSize?: JuicePixels;
+  fontStyle?: "normal" | "italic" | "oblique";
+  fontVariationSettings?: string | Record<string, number>;
+  fontWeight?: number | string;
+  gap?: JuiceLength | `${number}%`;
+  gapHeight?: JuiceLength | `${number}%`;
+  gapWidth?: JuiceLength | `${number}%`;
+  gridAutoColumns?: number | string;
+  gridAutoFlow?: "row" | "column" | "dense" | "row dense" | "column dense";
+  gridAutoRows?: number | string;
+  gridColumn?: number | string;
+  gridRow?: number | string;
+  gridTemplateColumns?: number | string;
+  gridTemplateRows?: number | string;
+  height?: JuiceLength | `${number}%` | "auto";
+  justifyContent?:
+    | "stretch"
+    | "start"
+    | "end"
+    | "flex-start"
+    | "center"
+    | "flex-end"
+    | "space-between"
+    | "space-around"
+    | "space-evenly";
+  justifyItems?:
+    | "stretch"
+    | "start"
+    | "end"
+    | "flex-start"
+    | "center"
+    | "flex-end"
+    | "baseline";
+  justifySelf?:
+    | "stretch"
+    | "start"
+    | "end"
+    | "flex-start"
+    | "center"
+    | "flex-end"
+    | "baseline";
+  letterSpacing?: JuicePixels;
+  lineHeight?: number | string;
+  margin?: JuiceLength | `${number}%` | "auto";
+  marginBottom?: JuiceLength | `${number}%` | "auto";
+  marginLeft?: JuiceLength | `${number}%` | "auto";
+  marginRight?: JuiceLength | `${number}%` | "auto";
+  marginTop?: JuiceLength | `${number}%` | "auto";
+  marginX?: JuiceLength | `${number}%` | "auto";
+  marginY?: JuiceLength | `${number}%` | "auto";
+  maxHeight?: JuiceLength | `${number}%`;
+  maxLines?: number;
+  maxWidth?: JuiceLength | `${number}%`;
+  objectFit?: "contain" | "cover" | "fill";
+  opacity?: number;
+  overflow?: "visible" | "hidden" | "clip" | "scroll";
+  overflowX?: "visible" | "hidden" | "clip" | "scroll";
+  overflowY?: "visible" | "hidden" | "clip" | "scroll";
+  padding?: JuiceLength | `${number}%`;
+  paddingBottom?: JuiceLength | `${number}%`;
+  paddingLeft?: JuiceLength | `${number}%`;
+  paddingRight?: JuiceLength | `${number}%`;
+  paddingTop?: JuiceLength | `${number}%`;
+  paddingX?: JuiceLength | `${number}%`;
+  paddingY?: JuiceLength | `${number}%`;
+  position?: "relative" | "absolute";
+  rowGap?: JuiceLength | `${number}%`;
+  tabSize?: number;
+  textAlign?: "left" | "center" | "right";
+  textDecoration?:
+    | "none"
+    | "underline"
+    | "line-through"
+    | "underline line-through";
+  textOverflow?: "clip" | "ellipsis";
+  transform?: string;
+  width?: JuiceLength | `${number}%` | "auto";
+  zIndex?: number;
+}
+
+/** An element in tree JSON, as `JSON.stringify(document)` gives. */
+export interface JuiceElementJson {
+  tag: JuiceNodeType | (string & {});
+  props?: Record<string, string | number | boolean | object>;
+  style?: JuiceStyleSchema;
+  children?: JuiceNodeJson[];
+}
+
+/** A text node in tree JSON. */
+export interface JuiceTextJson {
+  tag: "#text";
+  text: string;
+}
+
+export type JuiceNodeJson = JuiceElementJson | JuiceTextJson;
@@ -0,0 +1,34 @@
+export type SqlValue = null | number | string | boolean | ArrayBuffer | Uint8Array;
+
+export type SqlRow = Record<string, null | number | string | ArrayBuffer>;
+
+export interface RunResult {
+  changes: number;
+  lastInsertRowid: number;
+}
+
+export interface SqliteStatement {
+  readonly sql: string;
+  run(params?: SqlValue[]): Promise<RunResult>;
+  all(params?: SqlValue[]): Promise<SqlRow[]>;
+  get(params?: SqlValue[]): Promise<SqlRow | null>;
+}
+
+export interface SqliteDatabase {
+  run(sql: string, params?: SqlValue[]): Promise<RunResult>;
+  all(sql: string, params?: SqlValue[]): Promise<SqlRow[]>;
+  get(sql: string, params?: SqlValue[]): Promise<SqlRow | null>;
+  prepare(sql: string): SqliteStatement;
+  /** Runs every statement inside a single transaction, rolling back if any fails. */
+  transaction(statements: [string, SqlValue[]?][]): Promise<RunResult[]>;
+  close(): Promise<void>;
+}
+
+export interface JuiceSqlite {
+  open(path: string): SqliteDatabase;
+}
+
+declare global {
+  /** Only present when the host registers the `sqlite` plugin. */
+  const sqlite: JuiceSqlite;
+}
@@ -0,0 +1,29 @@
+import { document } from "./JuiceDocument.js";
+import type { UIEventListener } from "./JuiceEvent.js";
+
+/**
+ * Register a palette that `color` and `background` can refer to as `var(--name)`, e.g.
+ * `registerTheme("dark", { "--bg": "#101418", "--text": "#e0e0e0" })`.
+ */
+export function registerTheme(name: string, variables: Record<string, string>) {
+  renderer.registerTheme(name, variables);
+}
+
+/**
+ * Switch theme natively, without re-rendering. The switch and its `ThemeChange` event
+ * happen on the next tick. Returns false if the theme isn't registered or is already
+ * active.
+ */
+export function setTheme(name: string): boolean {
+  return renderer.setTheme(name);
+}
+
+/** Listen for ambient light readings from the host, in lux. Returns an unsubscribe function. */
+export function onAmbientLight(listener: UIEventListener<"AmbientLight">) {
+  return document.addEventListener("AmbientLight", listener);
+}
+
+/** Listen for the host switching theme, e.g. automatically in low light. */
+export function onThemeChange(listener: UIEventListener<"ThemeChange">) {
+  return document.addEventListener("ThemeChange", listener);
+}